@@ -0,0 +1,12 @@
+//! Generate the gRPC server code from `proto/skillts.proto` with a vendored protoc.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto/skillts.proto");
+
+    let mut config = tonic_prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+    tonic_prost_build::configure()
+        .build_client(false)
+        .compile_with_config(config, &["proto/skillts.proto"], &["proto"])?;
+    Ok(())
+}
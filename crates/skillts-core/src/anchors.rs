@@ -0,0 +1,237 @@
+//! Heading anchor preservation for intra-document links.
+//!
+//! Translating a heading changes the slug renderers derive from it, so links like
+//! `[see setup](#setup)` stop resolving. Headings of the original and translated
+//! bodies are paired in document order; either the original slugs are pinned with
+//! explicit `{#slug}` attributes, or `#slug` links are rewritten to the slugs of
+//! the translated headings. Nothing is changed when the heading counts differ.
+
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// How heading anchors are kept working after translation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum AnchorMode {
+    /// Leave headings and links as translated
+    #[default]
+    None,
+    /// Append `{#original-slug}` to each translated heading
+    Attributes,
+    /// Point `#slug` links at the slugs of the translated headings
+    Rewrite,
+}
+
+impl AnchorMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnchorMode::None => "none",
+            AnchorMode::Attributes => "attributes",
+            AnchorMode::Rewrite => "rewrite",
+        }
+    }
+}
+
+/// A heading and the anchor a renderer gives it
+struct Heading {
+    /// Byte range of the heading in the body
+    range: Range<usize>,
+    slug: String,
+    /// Set by an explicit `{#id}` attribute
+    explicit: bool,
+}
+
+/// GitHub-style slug: lowercase, punctuation dropped, spaces turned into hyphens
+pub fn slugify(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Headings of a markdown body with their slugs; repeated slugs get `-1`, `-2`, ... suffixes
+fn headings(body: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut current: Option<(Range<usize>, Option<String>, String)> = None;
+
+    for (event, range) in Parser::new_ext(body, Options::ENABLE_HEADING_ATTRIBUTES).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { id, .. }) => {
+                current = Some((range, id.map(|id| id.to_string()), String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, _, heading_text)) = current.as_mut() {
+                    heading_text.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((range, id, text)) = current.take() {
+                    headings.push(Heading {
+                        range,
+                        explicit: id.is_some(),
+                        slug: id.unwrap_or_else(|| slugify(&text)),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for heading in headings.iter_mut().filter(|h| !h.explicit) {
+        let count = seen.entry(heading.slug.clone()).or_insert(0);
+        if *count > 0 {
+            heading.slug = format!("{}-{}", heading.slug, count);
+        }
+        *count += 1;
+    }
+
+    headings
+}
+
+/// Pair the headings of both bodies, or `None` when their counts differ
+fn paired_headings(original: &str, translated: &str) -> Option<Vec<(Heading, Heading)>> {
+    let original = headings(original);
+    let translated = headings(translated);
+    if original.len() != translated.len() {
+        tracing::warn!(
+            "Translated body has {} headings but the original has {}, leaving anchors unchanged",
+            translated.len(),
+            original.len()
+        );
+        return None;
+    }
+    Some(original.into_iter().zip(translated).collect())
+}
+
+/// Apply `mode` to a translated body given the original body
+pub fn preserve_anchors(mode: AnchorMode, original: &str, translated: &str) -> String {
+    match mode {
+        AnchorMode::None => translated.to_string(),
+        AnchorMode::Attributes => add_anchor_attributes(original, translated),
+        AnchorMode::Rewrite => rewrite_anchor_links(original, translated),
+    }
+}
+
+/// Append `{#slug}` with the original slug to translated headings that lack an explicit id
+pub fn add_anchor_attributes(original: &str, translated: &str) -> String {
+    let Some(pairs) = paired_headings(original, translated) else {
+        return translated.to_string();
+    };
+
+    let mut result = String::with_capacity(translated.len());
+    let mut last = 0;
+    for (source, target) in pairs {
+        if target.explicit || source.slug.is_empty() {
+            continue;
+        }
+        // The attribute goes at the end of the heading's first line, after any closing hashes
+        let line = &translated[target.range.start..target.range.end];
+        let line_end = target.range.start + line.find('\n').unwrap_or(line.len());
+        let insert_at = target.range.start + translated[target.range.start..line_end].trim_end().len();
+
+        result.push_str(&translated[last..insert_at]);
+        result.push_str(&format!(" {{#{}}}", source.slug));
+        last = insert_at;
+    }
+    result.push_str(&translated[last..]);
+
+    result
+}
+
+/// Rewrite `#slug` link targets from original heading slugs to translated ones
+pub fn rewrite_anchor_links(original: &str, translated: &str) -> String {
+    let Some(pairs) = paired_headings(original, translated) else {
+        return translated.to_string();
+    };
+    let slugs: HashMap<String, String> = pairs
+        .into_iter()
+        .filter(|(source, target)| source.slug != target.slug && !target.slug.is_empty())
+        .map(|(source, target)| (source.slug, target.slug))
+        .collect();
+    if slugs.is_empty() {
+        return translated.to_string();
+    }
+
+    // Inline links and reference definitions; reference links use their definition
+    let mut targets: Vec<(Range<usize>, String)> = Vec::new();
+    let mut parser = Parser::new(translated).into_offset_iter();
+    for (event, range) in parser.by_ref() {
+        if let Event::Start(Tag::Link {
+            link_type: LinkType::Inline,
+            dest_url,
+            ..
+        }) = event
+        {
+            targets.push((range, dest_url.to_string()));
+        }
+    }
+    targets.extend(
+        parser
+            .reference_definitions()
+            .iter()
+            .map(|(_, def)| (def.span.clone(), def.dest.to_string())),
+    );
+    targets.sort_by_key(|(range, _)| range.start);
+
+    let mut result = String::with_capacity(translated.len());
+    let mut last = 0;
+    for (range, dest) in targets {
+        let Some(slug) = dest.strip_prefix('#').and_then(|slug| slugs.get(slug)) else {
+            continue;
+        };
+        // The destination follows the link text, so search from the end
+        let Some(offset) = translated[range.clone()].rfind(dest.as_str()) else {
+            continue;
+        };
+        let start = range.start + offset;
+        result.push_str(&translated[last..start]);
+        result.push('#');
+        result.push_str(slug);
+        last = start + dest.len();
+    }
+    result.push_str(&translated[last..]);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = "# Setup guide\n\nSee [setup](#setup-guide) and [usage][u].\n\n## Usage\n\n## Usage\n\n[u]: #usage-1\n";
+    const TRANSLATED: &str = "# 安装指南\n\n参见[安装](#setup-guide)和[用法][u]。\n\n## 用法\n\n## 用法\n\n[u]: #usage-1\n";
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Setup & Install (v2)"), "setup--install-v2");
+        assert_eq!(slugify("安装 指南"), "安装-指南");
+    }
+
+    #[test]
+    fn test_add_anchor_attributes() {
+        let result = add_anchor_attributes(ORIGINAL, TRANSLATED);
+        assert!(result.starts_with("# 安装指南 {#setup-guide}\n"));
+        assert!(result.contains("## 用法 {#usage}\n\n## 用法 {#usage-1}\n"));
+        // Headings that already carry an id are left alone
+        assert_eq!(add_anchor_attributes(ORIGINAL, &result), result);
+    }
+
+    #[test]
+    fn test_rewrite_anchor_links() {
+        let result = rewrite_anchor_links(ORIGINAL, TRANSLATED);
+        assert!(result.contains("[安装](#安装指南)"));
+        assert!(result.contains("[u]: #用法-1"));
+        // Mismatched heading counts leave the translation unchanged
+        assert_eq!(rewrite_anchor_links(ORIGINAL, "# 安装指南\n"), "# 安装指南\n");
+    }
+}
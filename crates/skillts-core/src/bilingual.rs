@@ -0,0 +1,175 @@
+//! Bilingual output.
+//!
+//! Pairs each top-level block of a translated body (paragraph, heading, list,
+//! quote or table) with the block of the original it translates, for review
+//! workflows and learning materials. Code blocks, HTML blocks, rules and blocks the
+//! translation left unchanged appear once, and so does the frontmatter of the
+//! translation. When the blocks of the two bodies do not line up, the whole original
+//! body follows the translation instead.
+
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use serde::{Deserialize, Serialize};
+
+use crate::parser::ContentParser;
+
+/// What a response holds in place of the original document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum OutputMode {
+    /// The translation alone
+    #[default]
+    Replace,
+    /// Each translated block followed by its original
+    Bilingual,
+    /// Each original block followed by its translation
+    BilingualOriginalFirst,
+}
+
+/// Kind of a top-level block, compared to line up the two bodies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BlockKind {
+    Paragraph,
+    Heading,
+    List,
+    Quote,
+    Table,
+    /// Code, HTML, rules and other blocks that are not translated as prose
+    Verbatim,
+    Other,
+}
+
+impl BlockKind {
+    fn of(tag: &Tag) -> Self {
+        match tag {
+            Tag::Paragraph => BlockKind::Paragraph,
+            Tag::Heading { .. } => BlockKind::Heading,
+            Tag::List(_) => BlockKind::List,
+            Tag::BlockQuote(_) => BlockKind::Quote,
+            Tag::Table(_) => BlockKind::Table,
+            Tag::CodeBlock(_) | Tag::HtmlBlock | Tag::MetadataBlock(_) => BlockKind::Verbatim,
+            _ => BlockKind::Other,
+        }
+    }
+}
+
+/// Document combining a translation with its original in the given mode
+pub fn render(mode: OutputMode, original: &str, translated: &str) -> String {
+    let original_first = match mode {
+        OutputMode::Replace => return translated.to_string(),
+        OutputMode::Bilingual => false,
+        OutputMode::BilingualOriginalFirst => true,
+    };
+
+    let parser = ContentParser::new();
+    let (original, translated) = (parser.parse(original), parser.parse(translated));
+    // Translation and original, in output order
+    let pair = |translation: &str, source: &str| {
+        if original_first {
+            (source.to_string(), translation.to_string())
+        } else {
+            (translation.to_string(), source.to_string())
+        }
+    };
+
+    let Some(aligned) = align(&original.body, &translated.body) else {
+        let (first, second) = pair(translated.body.trim_end(), original.body.trim());
+        return format!("{}{}\n\n---\n\n{}\n", translated.frontmatter, first, second);
+    };
+
+    let mut output = translated.frontmatter.clone();
+    let mut last = 0;
+    for (kind, original_range, range) in aligned {
+        output.push_str(&translated.body[last..range.start]);
+        let (source, translation) = (&original.body[original_range], &translated.body[range.clone()]);
+        if kind == BlockKind::Verbatim || source == translation {
+            output.push_str(translation);
+        } else {
+            let (first, second) = pair(translation, source);
+            output.push_str(&first);
+            // An empty comment keeps two lists of the same type from merging into one
+            output.push_str(if kind == BlockKind::List { "\n\n<!-- -->\n\n" } else { "\n\n" });
+            output.push_str(&second);
+        }
+        last = range.end;
+    }
+    output.push_str(&translated.body[last..]);
+    output
+}
+
+/// A top-level block of an original body and the one of its translation it lines up
+/// with, as their kind and the byte ranges in each body
+pub(crate) type BlockPair = (BlockKind, Range<usize>, Range<usize>);
+
+/// Top-level blocks of an original body paired with those of its translation;
+/// `None` when the kinds do not line up
+pub(crate) fn align(original: &str, translated: &str) -> Option<Vec<BlockPair>> {
+    let (original_blocks, translated_blocks) = (blocks(original), blocks(translated));
+    let aligned = original_blocks.len() == translated_blocks.len()
+        && original_blocks
+            .iter()
+            .zip(&translated_blocks)
+            .all(|((a, _), (b, _))| a == b);
+    aligned.then(|| {
+        original_blocks
+            .into_iter()
+            .zip(translated_blocks)
+            .map(|((kind, original_range), (_, range))| (kind, original_range, range))
+            .collect()
+    })
+}
+
+/// Kinds and byte ranges of the top-level blocks of a markdown body, without trailing newlines
+fn blocks(body: &str) -> Vec<(BlockKind, Range<usize>)> {
+    let mut blocks = Vec::new();
+    let mut depth = 0;
+    for (event, range) in Parser::new_ext(body, Options::ENABLE_TABLES).into_offset_iter() {
+        let block = match event {
+            Event::Start(tag) => {
+                depth += 1;
+                (depth == 1).then(|| (BlockKind::of(&tag), range))
+            }
+            Event::End(_) => {
+                depth -= 1;
+                None
+            }
+            Event::Rule if depth == 0 => Some((BlockKind::Verbatim, range)),
+            _ => None,
+        };
+        if let Some((kind, mut range)) = block {
+            while range.end > range.start && body.as_bytes()[range.end - 1] == b'\n' {
+                range.end -= 1;
+            }
+            blocks.push((kind, range));
+        }
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bilingual_blocks() {
+        let original = "---\nname: demo\ndescription: A demo\n---\n# Usage\n\nRun it.\n\n```bash\ndemo\n```\n\n- one\n- two\n\n[docs]: https://example.com\n";
+        let translated = "---\nname: demo\ndescription: 演示\n---\n# 用法\n\n运行它。\n\n```bash\ndemo\n```\n\n- 一\n- 二\n\n[docs]: https://example.com\n";
+
+        assert_eq!(render(OutputMode::Replace, original, translated), translated);
+        assert_eq!(
+            render(OutputMode::Bilingual, original, translated),
+            "---\nname: demo\ndescription: 演示\n---\n# 用法\n\n# Usage\n\n运行它。\n\nRun it.\n\n```bash\ndemo\n```\n\n\
+             - 一\n- 二\n\n<!-- -->\n\n- one\n- two\n\n[docs]: https://example.com\n"
+        );
+        assert!(render(OutputMode::BilingualOriginalFirst, original, translated).contains("# Usage\n\n# 用法\n\nRun it.\n\n运行它。"));
+
+        // Blocks that do not line up leave the whole original after the translation
+        let split = "# 用法\n\n运行它。\n\n再运行。\n";
+        assert_eq!(
+            render(OutputMode::Bilingual, "# Usage\n\nRun it. Run again.\n", split),
+            "# 用法\n\n运行它。\n\n再运行。\n\n---\n\n# Usage\n\nRun it. Run again.\n"
+        );
+    }
+}
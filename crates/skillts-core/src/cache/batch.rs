@@ -0,0 +1,143 @@
+//! Cache reads and writes of a batch of files.
+//!
+//! Looking up and storing each file of a large batch on its own costs a database
+//! round trip per file. A [`BatchCache`] looks up the cached translations of every
+//! file in one query before the first is translated, and stores new translations
+//! [`BATCH_WRITE_SIZE`] at a time in one transaction. Writes still pending when it
+//! is dropped, e.g. because the client went away, are stored in the background.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::{NewEntry, TranslationCache};
+use crate::error::Result;
+use crate::models::CacheEntry;
+
+/// Translations stored together in one transaction
+pub const BATCH_WRITE_SIZE: usize = 50;
+
+/// Cache lookups and writes of one batch
+pub struct BatchCache {
+    cache: Arc<TranslationCache>,
+    /// Lookup results by the first candidate key of a file, each used once
+    prefetched: Mutex<HashMap<String, Option<CacheEntry>>>,
+    writes: Mutex<Vec<NewEntry>>,
+}
+
+impl BatchCache {
+    /// Look up the candidate keys of every file of the batch at once
+    pub async fn prefetch(cache: Arc<TranslationCache>, tenant: &str, mut lookups: Vec<Vec<String>>) -> Result<Self> {
+        // Files repeated in the batch look up their key again once the first was translated
+        let mut seen = HashSet::new();
+        lookups.retain(|cache_keys| cache_keys.first().is_some_and(|key| seen.insert(key.clone())));
+
+        let found = cache.get_many(tenant, &lookups).await?;
+        let prefetched = lookups
+            .into_iter()
+            .zip(found)
+            .map(|(mut cache_keys, entry)| (cache_keys.swap_remove(0), entry))
+            .collect();
+        Ok(Self {
+            cache,
+            prefetched: Mutex::new(prefetched),
+            writes: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// The first cached translation among a file's candidate keys: the prefetched one,
+    /// or else a fresh lookup after storing the pending writes, which may hold it
+    pub async fn get_first(&self, tenant: &str, cache_keys: &[String]) -> Result<Option<CacheEntry>> {
+        let prefetched = cache_keys
+            .first()
+            .and_then(|cache_key| lock(&self.prefetched).remove(cache_key));
+        match prefetched {
+            Some(entry) => Ok(entry),
+            None => {
+                self.flush().await?;
+                self.cache.get_first(tenant, cache_keys).await
+            }
+        }
+    }
+
+    /// Queue a translation, storing the queue once it holds [`BATCH_WRITE_SIZE`] of them
+    pub async fn set(&self, entry: NewEntry) -> Result<()> {
+        let full = {
+            let mut writes = lock(&self.writes);
+            writes.push(entry);
+            writes.len() >= BATCH_WRITE_SIZE
+        };
+        if full {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Store the queued translations
+    pub async fn flush(&self) -> Result<()> {
+        let writes = std::mem::take(&mut *lock(&self.writes));
+        if !writes.is_empty() {
+            self.cache.set_many(writes).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for BatchCache {
+    fn drop(&mut self) {
+        let writes = std::mem::take(&mut *lock(&self.writes));
+        if writes.is_empty() {
+            return;
+        }
+        let cache = self.cache.clone();
+        tokio::spawn(async move {
+            if let Err(e) = cache.set_many(writes).await {
+                tracing::warn!("Failed to store the pending translations of a batch: {}", e);
+            }
+        });
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CacheConfig;
+
+    #[tokio::test]
+    async fn test_batch_cache() {
+        let dir = std::env::temp_dir().join(format!("skillts-batch-{}", std::process::id()));
+        let cache = Arc::new(
+            TranslationCache::new(CacheConfig {
+                db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+                ..CacheConfig::default()
+            })
+            .await
+            .unwrap(),
+        );
+        cache.set("cached", "h1", "a/SKILL.md", "已缓存", "t1", None).await.unwrap();
+
+        let lookups = vec![vec!["cached".to_string()], vec!["new".to_string()], vec!["new".to_string()]];
+        let batch = BatchCache::prefetch(cache.clone(), "default", lookups).await.unwrap();
+        assert!(batch.get_first("default", &["cached".to_string()]).await.unwrap().is_some());
+        assert!(batch.get_first("default", &["new".to_string()]).await.unwrap().is_none());
+
+        // A repeated file finds the translation of the first, though it was only queued
+        let entry = NewEntry {
+            cache_key: "new".to_string(),
+            content_hash: "h2".to_string(),
+            path: "b/SKILL.md".to_string(),
+            translated_content: "新译文".to_string(),
+            translated_hash: "t2".to_string(),
+            metadata: None,
+        };
+        batch.set(entry).await.unwrap();
+        let repeated = batch.get_first("default", &["new".to_string()]).await.unwrap();
+        assert_eq!(repeated.unwrap().translated_content, "新译文");
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,417 @@
+//! Storage for oversized translations.
+//!
+//! Translations larger than the configured threshold are written to a
+//! [`BlobStore`], a directory or an S3-compatible bucket, and the translations
+//! table keeps only a pointer to them in place of the content. Blobs are named by
+//! the hash of the translation, so identical translations share one. Pointers are
+//! resolved when entries are read; blobs no longer referenced by any entry are
+//! deleted after the cache evicts entries.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+
+/// Object storage request timeout
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Storage of translation blobs by key
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    /// Write a blob, replacing any blob of the same key
+    async fn put(&self, key: &str, content: &[u8]) -> Result<()>;
+
+    /// Read a blob
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+
+    /// Delete a blob; deleting a missing blob succeeds
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// Keys of every stored blob with the time it was written
+    async fn list(&self) -> Result<Vec<(String, DateTime<Utc>)>>;
+}
+
+/// Where oversized translations are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlobStoreKind {
+    /// In the translations table like any other translation
+    #[default]
+    None,
+    /// Files in a local directory
+    Filesystem,
+    /// Objects in an S3-compatible bucket
+    S3,
+}
+
+impl FromStr for BlobStoreKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "none" | "" => Ok(BlobStoreKind::None),
+            "filesystem" | "fs" => Ok(BlobStoreKind::Filesystem),
+            "s3" => Ok(BlobStoreKind::S3),
+            other => Err(format!("unknown blob store '{}', expected 'none', 'filesystem' or 's3'", other)),
+        }
+    }
+}
+
+impl fmt::Display for BlobStoreKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BlobStoreKind::None => "none",
+            BlobStoreKind::Filesystem => "filesystem",
+            BlobStoreKind::S3 => "s3",
+        })
+    }
+}
+
+/// Blob named by a translation hash such as `sha256:<hex>`: its hex digest, or `None`
+/// when the hash cannot safely name a file or object
+pub fn blob_key(translated_hash: &str) -> Option<&str> {
+    let key = translated_hash.rsplit(':').next().unwrap_or_default();
+    (!key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric())).then_some(key)
+}
+
+/// Blobs as files named by their key in a directory
+pub struct FilesystemBlobStore {
+    dir: PathBuf,
+}
+
+impl FilesystemBlobStore {
+    /// Store blobs in `dir`, which is created on the first write
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+fn io_error(action: &str, key: &str, e: std::io::Error) -> Error {
+    Error::Internal(format!("Failed to {} blob {}: {}", action, key, e))
+}
+
+#[async_trait]
+impl BlobStore for FilesystemBlobStore {
+    async fn put(&self, key: &str, content: &[u8]) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| io_error("write", key, e))?;
+        // Written under a temporary name first, so readers never see a partial blob
+        let partial = self.dir.join(format!("{}.partial", key));
+        tokio::fs::write(&partial, content)
+            .await
+            .map_err(|e| io_error("write", key, e))?;
+        tokio::fs::rename(&partial, self.dir.join(key))
+            .await
+            .map_err(|e| io_error("write", key, e))
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        tokio::fs::read(self.dir.join(key))
+            .await
+            .map_err(|e| io_error("read", key, e))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.dir.join(key)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io_error("delete", key, e)),
+            _ => Ok(()),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<(String, DateTime<Utc>)>> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error("list", "directory", e)),
+        };
+        let mut blobs = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(|e| io_error("list", "directory", e))? {
+            let Ok(key) = entry.file_name().into_string() else {
+                continue;
+            };
+            let Ok(metadata) = entry.metadata().await else {
+                continue;
+            };
+            if metadata.is_file() && blob_key(&key) == Some(key.as_str()) {
+                let written = metadata.modified().map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now());
+                blobs.push((key, written));
+            }
+        }
+        Ok(blobs)
+    }
+}
+
+/// An S3-compatible bucket addressed with path-style URLs and SigV4-signed requests
+#[derive(Clone, Default)]
+pub struct S3Bucket {
+    /// Service URL, such as `https://s3.amazonaws.com`
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+    /// Prepended to every object key
+    pub prefix: String,
+}
+
+impl fmt::Debug for S3Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Bucket")
+            .field("endpoint", &self.endpoint)
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl S3Bucket {
+    /// URL of an object key under the prefix, or of the bucket itself for an empty key
+    pub fn url(&self, key: &str) -> Result<reqwest::Url> {
+        let object = if key.is_empty() { String::new() } else { format!("{}{}", self.prefix, key) };
+        reqwest::Url::parse(&format!("{}/{}/{}", self.endpoint.trim_end_matches('/'), self.bucket, object))
+            .map_err(|e| Error::Internal(format!("Invalid object storage URL: {}", e)))
+    }
+
+    /// Send a signed request for `url` with the sorted, encoded `query` pairs
+    pub async fn send(
+        &self,
+        http: &reqwest::Client,
+        method: reqwest::Method,
+        mut url: reqwest::Url,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response> {
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(Error::Internal("Object storage URL has no host".to_string())),
+        };
+        let mut query: Vec<(String, String)> = query.iter().map(|(k, v)| (uri_encode(k), uri_encode(v))).collect();
+        query.sort();
+        let canonical_query = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
+        url.set_query((!canonical_query.is_empty()).then_some(canonical_query.as_str()));
+
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let authorization = self.authorization(
+            method.as_str(),
+            &host,
+            url.path(),
+            &canonical_query,
+            &payload_hash,
+            &amz_date,
+        );
+
+        http.request(method, url)
+            .header("x-amz-date", &amz_date)
+            .header("x-amz-content-sha256", &payload_hash)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| Error::Internal(format!("Object storage request failed: {}", e)))
+    }
+
+    /// AWS Signature Version 4 `Authorization` header of a request
+    pub fn authorization(
+        &self,
+        method: &str,
+        host: &str,
+        path: &str,
+        canonical_query: &str,
+        payload_hash: &str,
+        amz_date: &str,
+    ) -> String {
+        let date = &amz_date[..8];
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, canonical_query, host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let mut key = hmac_sha256(format!("AWS4{}", self.secret_key).as_bytes(), date.as_bytes());
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac_sha256(&key, part.as_bytes());
+        }
+        let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, signed_headers, signature
+        )
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode everything but unreserved characters, as SigV4 expects in queries
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Blobs as objects named by their key under the prefix of an S3-compatible bucket
+pub struct S3BlobStore {
+    bucket: S3Bucket,
+    http: reqwest::Client,
+}
+
+impl S3BlobStore {
+    pub fn new(bucket: S3Bucket) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self { bucket, http }
+    }
+
+    /// Send a request, failing on any non-success status
+    async fn request(&self, method: reqwest::Method, key: &str, query: &[(&str, &str)], body: Vec<u8>) -> Result<reqwest::Response> {
+        let response = self
+            .bucket
+            .send(&self.http, method.clone(), self.bucket.url(key)?, query, body)
+            .await?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status();
+        let message = response.text().await.unwrap_or_default();
+        Err(Error::Internal(format!(
+            "Object storage {} of blob {} returned {}: {}",
+            method,
+            key,
+            status,
+            message.trim()
+        )))
+    }
+}
+
+#[async_trait]
+impl BlobStore for S3BlobStore {
+    async fn put(&self, key: &str, content: &[u8]) -> Result<()> {
+        self.request(reqwest::Method::PUT, key, &[], content.to_vec()).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        let response = self.request(reqwest::Method::GET, key, &[], Vec::new()).await?;
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| Error::Internal(format!("Failed to read blob {}: {}", key, e)))?;
+        Ok(body.to_vec())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.request(reqwest::Method::DELETE, key, &[], Vec::new()).await?;
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<(String, DateTime<Utc>)>> {
+        static LISTED_OBJECT: OnceLock<Regex> = OnceLock::new();
+        static CONTINUATION_TOKEN: OnceLock<Regex> = OnceLock::new();
+        let listed_object = LISTED_OBJECT.get_or_init(|| {
+            Regex::new(r"(?s)<Contents>.*?<Key>(.*?)</Key>.*?<LastModified>(.*?)</LastModified>.*?</Contents>").unwrap()
+        });
+        let continuation_token = CONTINUATION_TOKEN
+            .get_or_init(|| Regex::new(r"<NextContinuationToken>(.*?)</NextContinuationToken>").unwrap());
+
+        let mut blobs = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", self.bucket.prefix.as_str())];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token.as_str()));
+            }
+            let response = self.request(reqwest::Method::GET, "", &query, Vec::new()).await?;
+            let listing = response
+                .text()
+                .await
+                .map_err(|e| Error::Internal(format!("Failed to read blob listing: {}", e)))?;
+
+            for object in listed_object.captures_iter(&listing) {
+                let key = object[1].strip_prefix(self.bucket.prefix.as_str()).unwrap_or(&object[1]);
+                if blob_key(key) == Some(key) {
+                    let written = DateTime::parse_from_rfc3339(&object[2])
+                        .map(|time| time.with_timezone(&Utc))
+                        .unwrap_or_else(|_| Utc::now());
+                    blobs.push((key.to_string(), written));
+                }
+            }
+            token = continuation_token
+                .captures(&listing)
+                .map(|next| next[1].replace("&amp;", "&"));
+            if token.is_none() {
+                return Ok(blobs);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_filesystem_blobs() {
+        let dir = std::env::temp_dir().join(format!("skillts-blobs-{}", std::process::id()));
+        let store = FilesystemBlobStore::new(&dir);
+        assert!(store.list().await.unwrap().is_empty());
+
+        let key = blob_key("sha256:abc123").unwrap();
+        store.put(key, "译文".as_bytes()).await.unwrap();
+        assert_eq!(store.get(key).await.unwrap(), "译文".as_bytes());
+        assert_eq!(store.list().await.unwrap().into_iter().map(|(key, _)| key).collect::<Vec<_>>(), vec!["abc123"]);
+
+        store.delete(key).await.unwrap();
+        store.delete(key).await.unwrap();
+        assert!(store.get(key).await.is_err());
+        assert_eq!(blob_key("sha256:../etc"), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_signed_listing_query() {
+        let bucket = S3Bucket {
+            endpoint: "https://s3.example.com".to_string(),
+            bucket: "blobs".to_string(),
+            region: "us-east-1".to_string(),
+            access_key: "AKIDEXAMPLE".to_string(),
+            secret_key: "secret".to_string(),
+            prefix: "skillts/blobs/".to_string(),
+        };
+        assert_eq!(bucket.url("abc").unwrap().as_str(), "https://s3.example.com/blobs/skillts/blobs/abc");
+        assert_eq!(uri_encode("skillts/blobs/"), "skillts%2Fblobs%2F");
+        assert!(!format!("{:?}", bucket).contains("secret"));
+    }
+}
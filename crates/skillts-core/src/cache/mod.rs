@@ -0,0 +1,1626 @@
+//! Cache management for translations.
+//!
+//! Fully compatible with Python version's cache implementation. Entries live
+//! in a [`CacheBackend`]: SQLite by default, or Postgres so that several
+//! replicas can share one cache. Recently used entries are also kept in a
+//! size-bounded in-memory tier so hot lookups skip the database. Entries
+//! belong to a tenant; lookups, listings, deletions and statistics are scoped
+//! to one tenant. Oversized translations can be kept out of the database in a
+//! [`blob`] store. Batches of files look up and store their translations
+//! together through a [`batch::BatchCache`].
+
+pub mod batch;
+pub mod blob;
+pub mod postgres;
+pub mod sqlite;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use moka::future::Cache;
+use sqlx::sqlite::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+use crate::error::{Error, Result};
+use crate::memory::{self, MemoryPair, MemoryScope};
+use crate::models::{
+    CacheEntry, CacheEntrySummary, CacheFilter, CacheGroupStats, CacheStats, CompactStats, DetailedCacheStats,
+    StaleVersionPolicy,
+};
+use crate::translator::DEFAULT_TENANT;
+
+use blob::{BlobStore, BlobStoreKind, FilesystemBlobStore, S3BlobStore, S3Bucket};
+pub use postgres::PostgresBackend;
+pub use sqlite::{SqliteBackend, SqliteTuning};
+
+/// Storage for translations and segments.
+///
+/// Expiry, hit batching and miss counting are handled by [`TranslationCache`];
+/// backends only store and aggregate rows. `column` arguments are always one of
+/// the indexed columns named by the cache itself, never user input.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// Check that the database is reachable
+    async fn ping(&self) -> Result<()>;
+
+    /// Get an entry by cache key, without touching its hit count
+    async fn get(&self, cache_key: &str) -> Result<Option<CacheEntry>>;
+
+    /// Get the entries of several cache keys in as few queries as possible, leaving out
+    /// missing keys, without touching their hit counts
+    async fn get_many(&self, cache_keys: &[String]) -> Result<Vec<CacheEntry>>;
+
+    /// Delete an entry by cache key
+    async fn delete(&self, cache_key: &str) -> Result<()>;
+
+    /// Get translated segments by key, refreshing their access time
+    async fn get_segments(&self, segment_keys: &[String]) -> Result<HashMap<String, String>>;
+
+    /// Store translated segments of a tenant
+    async fn set_segments(&self, tenant: &str, segments: &[(String, String)]) -> Result<()>;
+
+    /// Get the stored result of one upstream call of an unfinished translation
+    async fn get_partial(&self, partial_key: &str) -> Result<Option<String>>;
+
+    /// Store the result of one upstream call of a tenant's translation as soon as it arrives
+    async fn set_partial(&self, tenant: &str, partial_key: &str, translated_text: &str) -> Result<()>;
+
+    /// Delete the stored upstream results of a finished translation
+    async fn delete_partials(&self, partial_keys: &[String]) -> Result<()>;
+
+    /// Get translation memory entries by normalized source hash, refreshing their access time
+    async fn get_translation_memory(&self, scope: &MemoryScope<'_>, hashes: &[String]) -> Result<HashMap<String, String>>;
+
+    /// Most recently used translation memory entries, newest first
+    async fn translation_memory_candidates(&self, scope: &MemoryScope<'_>, limit: i64) -> Result<Vec<MemoryPair>>;
+
+    /// Store translation memory entries, replacing those with the same normalized source
+    async fn set_translation_memory(&self, scope: &MemoryScope<'_>, pairs: &[MemoryPair]) -> Result<()>;
+
+    /// Get a tenant's entries whose `column` equals `value`, newest first
+    async fn find(&self, tenant: &str, column: &'static str, value: &str) -> Result<Vec<CacheEntry>>;
+
+    /// Delete a tenant's entries whose `column` equals `value`, and the paths of content
+    /// no longer cached
+    async fn delete_matching(&self, tenant: &str, column: &'static str, value: &str) -> Result<i64>;
+
+    /// A page of a tenant's entries matching `filter`, with the number of matches across pages
+    async fn list(&self, tenant: &str, filter: &CacheFilter) -> Result<(Vec<CacheEntrySummary>, i64)>;
+
+    /// Record that a tenant submitted content with `content_hash` under `path`
+    async fn add_path(&self, tenant: &str, content_hash: &str, path: &str) -> Result<()>;
+
+    /// Hashes of the content a tenant submitted under `path`
+    async fn path_hashes(&self, tenant: &str, path: &str) -> Result<Vec<String>>;
+
+    /// Replace the translated content of an entry; false when it does not exist
+    async fn replace_translation(
+        &self,
+        cache_key: &str,
+        translated_content: &str,
+        translated_hash: &str,
+    ) -> Result<bool>;
+
+    /// Insert or replace entries in one transaction, recording their paths
+    async fn set_many(&self, stored: &[StoredEntry]) -> Result<()>;
+
+    /// Add hit counts by cache key and refresh the access times
+    async fn add_hits(&self, hits: &HashMap<String, i64>) -> Result<()>;
+
+    /// Add lookup outcomes to the hourly counters of each tenant
+    async fn add_lookups(&self, lookups: &LookupBuckets) -> Result<()>;
+
+    /// Hits and misses counted for a tenant in the hours starting at or after `since`, or ever
+    async fn lookup_totals(&self, tenant: &str, since: Option<DateTime<Utc>>) -> Result<LookupTotals>;
+
+    /// Delete entries, segments and translation memory whose `column` timestamp is before `cutoff`,
+    /// and the paths of content no longer cached
+    async fn clear_before(&self, column: &'static str, cutoff: DateTime<Utc>) -> Result<i64>;
+
+    /// Mark the entries stored by a translator version other than `current_version` as
+    /// `stale_version` in their metadata, returning the number newly marked.
+    /// Entries whose metadata names no version are left alone.
+    async fn mark_stale_versions(&self, current_version: &str) -> Result<i64>;
+
+    /// Delete the entries stored by a translator version other than `current_version`,
+    /// and the paths of content no longer cached. Entries whose metadata names no version are kept.
+    async fn delete_stale_versions(&self, current_version: &str) -> Result<i64>;
+
+    /// Delete a tenant's entries, segments, translation memory and paths
+    async fn clear_tenant(&self, tenant: &str) -> Result<i64>;
+
+    /// Delete every entry, segment, translation memory entry and path
+    async fn clear_all(&self) -> Result<i64>;
+
+    /// Statistics of a tenant's entries; lookup counts and rates are left at zero
+    async fn stats(&self, tenant: &str) -> Result<CacheStats>;
+
+    /// Aggregate entry count, size and hits for each distinct value of `column`,
+    /// over the entries of `tenant` or of all tenants
+    async fn group_stats(&self, column: &'static str, tenant: Option<&str>) -> Result<Vec<CacheGroupStats>>;
+
+    /// Distinct translated hashes of every tenant's entries
+    async fn translated_hashes(&self) -> Result<HashSet<String>>;
+
+    /// Release backend resources before shutdown
+    async fn close(&self) -> Result<()>;
+}
+
+/// Marks translated content stored as a pointer to a blob, followed by the blob key.
+/// A translation never starts with a NUL character.
+const BLOB_POINTER: &str = "\u{0}blob:";
+
+/// Unreferenced blobs younger than this are kept by garbage collection, as an entry
+/// may be about to reference them
+const BLOB_GRACE_PERIOD_MINUTES: i64 = 60;
+
+/// Cache hits and misses counted for a tenant
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LookupTotals {
+    pub hits: i64,
+    pub misses: i64,
+}
+
+impl LookupTotals {
+    fn lookups(&self) -> i64 {
+        self.hits + self.misses
+    }
+
+    /// Share of lookups that were hits, 0 without lookups
+    fn hit_rate(&self) -> f64 {
+        if self.lookups() > 0 {
+            self.hits as f64 / self.lookups() as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Lookup counters by tenant and the hour they were counted in
+pub type LookupBuckets = HashMap<(String, DateTime<Utc>), LookupTotals>;
+
+/// Entries listed per query when deleting by path prefix
+const PREFIX_PAGE_SIZE: i64 = 500;
+
+/// A cache entry with the columns it is grouped and scoped by
+#[derive(Debug, Clone)]
+pub struct StoredEntry {
+    pub entry: CacheEntry,
+    pub source_language: String,
+    pub target_language: String,
+    pub model: String,
+    pub path_prefix: String,
+    pub tenant: String,
+}
+
+/// A translation to store with [`TranslationCache::set_many`]
+#[derive(Debug, Clone)]
+pub struct NewEntry {
+    pub cache_key: String,
+    pub content_hash: String,
+    pub path: String,
+    pub translated_content: String,
+    pub translated_hash: String,
+    /// Names the tenant, languages and model of the entry; see [`TranslationCache::set`]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Where translations are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheBackendKind {
+    /// The local SQLite database at `db_path`
+    #[default]
+    Sqlite,
+    /// A Postgres database shared by every replica
+    Postgres,
+}
+
+impl FromStr for CacheBackendKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "sqlite" | "" => Ok(CacheBackendKind::Sqlite),
+            "postgres" | "postgresql" => Ok(CacheBackendKind::Postgres),
+            other => Err(format!("unknown cache backend '{}', expected 'sqlite' or 'postgres'", other)),
+        }
+    }
+}
+
+impl fmt::Display for CacheBackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CacheBackendKind::Sqlite => "sqlite",
+            CacheBackendKind::Postgres => "postgres",
+        })
+    }
+}
+
+/// Translation cache with expiry, batched hit counts and per-tenant lookup counts
+pub struct TranslationCache {
+    backend: Box<dyn CacheBackend>,
+    /// Recently used entries by cache key, weighed by their size in bytes
+    memory: Option<Cache<String, CacheEntry>>,
+    /// Bumped by every deletion or replacement, so a lookup that read an entry before
+    /// it does not put the old entry back into `memory`
+    invalidations: AtomicU64,
+    pool: SqlitePool,
+    db_path: String,
+    max_age_days: i64,
+    flush_threshold: usize,
+    /// Held by bulk deletes and compaction, so a compaction never overlaps them
+    maintenance: Mutex<()>,
+    /// Lookups since startup, by tenant
+    lookup_counts: Arc<Mutex<HashMap<String, LookupCounts>>>,
+    pending_hits: Arc<Mutex<HashMap<String, i64>>>,
+    /// Lookups not yet added to the persisted hourly counters
+    pending_lookups: Arc<Mutex<LookupBuckets>>,
+    /// Statistics last computed per tenant, with when they were computed
+    recent_stats: Mutex<HashMap<String, (Instant, CacheStats)>>,
+    /// Where translations above `blob_threshold_bytes` are stored, if anywhere
+    blobs: Option<Box<dyn BlobStore>>,
+    blob_threshold_bytes: usize,
+}
+
+/// Outcomes of cache lookups since startup
+#[derive(Debug, Clone, Copy, Default)]
+struct LookupCounts {
+    memory_hits: i64,
+    store_hits: i64,
+    misses: i64,
+}
+
+/// Tier a cached translation was found in
+#[derive(Debug, Clone, Copy)]
+enum Tier {
+    Memory,
+    Store,
+}
+
+/// Cache configuration
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// SQLite database file; parent directories are created as needed.
+    /// Always opened, since other stores share it even when translations live in Postgres.
+    pub db_path: String,
+    /// Where translations are stored
+    pub backend: CacheBackendKind,
+    /// Postgres connection URL, used with [`CacheBackendKind::Postgres`]
+    pub database_url: String,
+    /// Maximum Postgres connections per instance
+    pub max_connections: u32,
+    /// Seconds to wait for a Postgres connection
+    pub connect_timeout_seconds: u64,
+    /// Entries older than this are treated as expired
+    pub max_age_days: i64,
+    /// Flush pending hit counts once this many distinct keys are queued (0 disables)
+    pub flush_threshold: usize,
+    /// Size of the in-memory tier in bytes of cached content (0 disables)
+    pub memory_bytes: u64,
+    /// Store translated content zstd-compressed in SQLite
+    pub compression: bool,
+    /// Where translations above `blob_threshold_bytes` are stored
+    pub blob_store: BlobStoreKind,
+    /// Size in bytes above which a translation is stored as a blob
+    pub blob_threshold_bytes: usize,
+    /// Directory of [`BlobStoreKind::Filesystem`] blobs
+    pub blob_dir: String,
+    /// Bucket of [`BlobStoreKind::S3`] blobs
+    pub blob_bucket: S3Bucket,
+    /// Pool size and pragmas of the SQLite database
+    pub sqlite: SqliteTuning,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            db_path: "./data/cache.db".to_string(),
+            backend: CacheBackendKind::Sqlite,
+            database_url: String::new(),
+            max_connections: 5,
+            connect_timeout_seconds: 10,
+            max_age_days: 30,
+            flush_threshold: 1000,
+            memory_bytes: 16 * 1024 * 1024,
+            compression: true,
+            blob_store: BlobStoreKind::None,
+            blob_threshold_bytes: 1024 * 1024,
+            blob_dir: "./data/blobs".to_string(),
+            blob_bucket: S3Bucket::default(),
+            sqlite: SqliteTuning::default(),
+        }
+    }
+}
+
+impl TranslationCache {
+    /// Create a new cache instance
+    pub async fn new(config: CacheConfig) -> Result<Self> {
+        let pool = sqlite::connect(&config.db_path, &config.sqlite).await?;
+
+        let backend: Box<dyn CacheBackend> = match config.backend {
+            CacheBackendKind::Sqlite => Box::new(SqliteBackend::new(pool.clone(), config.compression).await?),
+            CacheBackendKind::Postgres => Box::new(PostgresBackend::connect(&config).await?),
+        };
+        tracing::info!("Cache backend: {}", config.backend);
+
+        let blobs: Option<Box<dyn BlobStore>> = match config.blob_store {
+            BlobStoreKind::None => None,
+            BlobStoreKind::Filesystem => Some(Box::new(FilesystemBlobStore::new(&config.blob_dir))),
+            BlobStoreKind::S3 => Some(Box::new(S3BlobStore::new(config.blob_bucket.clone()))),
+        };
+        if blobs.is_some() {
+            tracing::info!(
+                "Translations over {} bytes are stored in the {} blob store",
+                config.blob_threshold_bytes,
+                config.blob_store
+            );
+        }
+
+        let memory = (config.memory_bytes > 0).then(|| {
+            Cache::builder()
+                .max_capacity(config.memory_bytes)
+                .weigher(|cache_key: &String, entry: &CacheEntry| {
+                    let bytes = cache_key.len() + entry.path.len() + entry.translated_content.len();
+                    u32::try_from(bytes).unwrap_or(u32::MAX)
+                })
+                .support_invalidation_closures()
+                .build()
+        });
+
+        Ok(Self {
+            backend,
+            memory,
+            pool,
+            db_path: config.db_path,
+            max_age_days: config.max_age_days,
+            flush_threshold: config.flush_threshold,
+            maintenance: Mutex::new(()),
+            invalidations: AtomicU64::new(0),
+            lookup_counts: Arc::new(Mutex::new(HashMap::new())),
+            pending_hits: Arc::new(Mutex::new(HashMap::new())),
+            pending_lookups: Arc::new(Mutex::new(HashMap::new())),
+            recent_stats: Mutex::new(HashMap::new()),
+            blobs,
+            blob_threshold_bytes: config.blob_threshold_bytes,
+        })
+    }
+
+    /// Local SQLite pool, shared with other stores living in the cache database.
+    /// Holds the translations too unless the Postgres backend is used.
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// Check that the database is reachable
+    pub async fn ping(&self) -> Result<()> {
+        self.backend.ping().await
+    }
+
+    /// Get the first cached translation among several candidate keys.
+    /// Counts at most one miss for the whole lookup, against `tenant`.
+    #[tracing::instrument(name = "cache_get", skip_all, fields(cache_key = ?cache_keys.first()))]
+    pub async fn get_first(&self, tenant: &str, cache_keys: &[String]) -> Result<Option<CacheEntry>> {
+        for cache_key in cache_keys {
+            if let Some((entry, tier)) = self.lookup(cache_key).await? {
+                self.count_result(tenant, Some(tier)).await;
+                return Ok(Some(entry));
+            }
+        }
+        self.count_result(tenant, None).await;
+        Ok(None)
+    }
+
+    /// [`Self::get_first`] for each list of candidate keys, reading the entries missing
+    /// from the in-memory tier in one backend query instead of one per key
+    #[tracing::instrument(name = "cache_get_many", skip_all, fields(lookups = lookups.len()))]
+    pub async fn get_many(&self, tenant: &str, lookups: &[Vec<String>]) -> Result<Vec<Option<CacheEntry>>> {
+        let generation = self.invalidations.load(Ordering::SeqCst);
+        let mut found = HashMap::new();
+        let mut missing = Vec::new();
+        let mut seen = HashSet::new();
+        for cache_key in lookups.iter().flatten() {
+            if !seen.insert(cache_key) {
+                continue;
+            }
+            let cached = match &self.memory {
+                Some(memory) => memory.get(cache_key).await,
+                None => None,
+            };
+            match cached {
+                Some(entry) => {
+                    found.insert(cache_key.clone(), (entry, Tier::Memory));
+                }
+                None => missing.push(cache_key.clone()),
+            }
+        }
+        if !missing.is_empty() {
+            for entry in self.backend.get_many(&missing).await? {
+                if let Some(entry) = self.load(entry).await? {
+                    found.insert(entry.cache_key.clone(), (entry, Tier::Store));
+                }
+            }
+        }
+
+        let mut results = Vec::with_capacity(lookups.len());
+        for cache_keys in lookups {
+            let mut hit = None;
+            for cache_key in cache_keys {
+                let Some(candidate) = found.get(cache_key).cloned() else {
+                    continue;
+                };
+                hit = self.accept(cache_key, candidate, generation).await?;
+                match &hit {
+                    // Files sharing a key find it in the in-memory tier after the first
+                    Some((entry, _)) => {
+                        if self.memory.is_some() {
+                            found.insert(cache_key.clone(), (entry.clone(), Tier::Memory));
+                        }
+                        break;
+                    }
+                    // Expired and deleted
+                    None => {
+                        found.remove(cache_key);
+                    }
+                }
+            }
+            self.count_result(tenant, hit.as_ref().map(|(_, tier)| *tier)).await;
+            results.push(hit.map(|(entry, _)| entry));
+        }
+        Ok(results)
+    }
+
+    /// Count a lookup of the tenant found in `tier`, or missed
+    async fn count_result(&self, tenant: &str, tier: Option<Tier>) {
+        {
+            let mut counts = self.lookup_counts.lock().await;
+            let counts = counts.entry(tenant.to_string()).or_default();
+            match tier {
+                Some(Tier::Memory) => counts.memory_hits += 1,
+                Some(Tier::Store) => counts.store_hits += 1,
+                None => counts.misses += 1,
+            }
+        }
+        self.count_lookup(tenant, tier.is_some()).await;
+    }
+
+    /// Queue a hit or miss for the tenant's counter of the current hour
+    async fn count_lookup(&self, tenant: &str, hit: bool) {
+        let mut pending = self.pending_lookups.lock().await;
+        let totals = pending.entry((tenant.to_string(), hour_start(Utc::now()))).or_default();
+        if hit {
+            totals.hits += 1;
+        } else {
+            totals.misses += 1;
+        }
+    }
+
+    /// Look up a cached translation in memory, then in the backend.
+    /// Drops it if expired and queues a hit otherwise.
+    async fn lookup(&self, cache_key: &str) -> Result<Option<(CacheEntry, Tier)>> {
+        let generation = self.invalidations.load(Ordering::SeqCst);
+        let cached = match &self.memory {
+            Some(memory) => memory.get(cache_key).await,
+            None => None,
+        };
+        let found = match cached {
+            Some(entry) => (entry, Tier::Memory),
+            None => match self.backend.get(cache_key).await? {
+                Some(entry) => match self.load(entry).await? {
+                    Some(entry) => (entry, Tier::Store),
+                    None => return Ok(None),
+                },
+                None => return Ok(None),
+            },
+        };
+        self.accept(cache_key, found, generation).await
+    }
+
+    /// An entry read from the backend with its blob resolved; `None` when the blob is gone
+    async fn load(&self, entry: CacheEntry) -> Result<Option<CacheEntry>> {
+        let cache_key = entry.cache_key.clone();
+        match self.resolve(entry).await {
+            Ok(entry) => Ok(Some(entry)),
+            Err(e) => {
+                // A translation whose blob is gone cannot be served; retranslate it
+                tracing::warn!("Dropping cache entry {} with an unreadable blob: {}", cache_key, e);
+                self.backend.delete(&cache_key).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Serve an entry found in `tier` by a lookup that started at invalidation `generation`:
+    /// drops it if expired and queues a hit otherwise
+    async fn accept(
+        &self,
+        cache_key: &str,
+        (mut entry, tier): (CacheEntry, Tier),
+        generation: u64,
+    ) -> Result<Option<(CacheEntry, Tier)>> {
+        // Check expiration
+        if Utc::now() - entry.created_at > Duration::days(self.max_age_days) {
+            // Delete expired entry
+            if let Some(memory) = &self.memory {
+                memory.invalidate(cache_key).await;
+            }
+            self.backend.delete(cache_key).await?;
+            return Ok(None);
+        }
+
+        // Queue hit count update
+        let mut pending = self.pending_hits.lock().await;
+        *pending.entry(cache_key.to_string()).or_insert(0) += 1;
+
+        let pending_hit = pending.get(cache_key).copied().unwrap_or(0);
+        let should_flush = self.flush_threshold > 0 && pending.len() >= self.flush_threshold;
+        drop(pending);
+
+        // Flush early when too many distinct keys are pending
+        if should_flush {
+            if let Err(e) = self.flush_pending_hits().await {
+                tracing::warn!("Threshold flush of pending hits failed: {}", e);
+            }
+        }
+
+        // The stored count lags behind by the queued hits; the memory copy is kept current
+        match tier {
+            Tier::Memory => entry.hit_count += 1,
+            Tier::Store => entry.hit_count += pending_hit - 1,
+        }
+        self.remember(cache_key, &entry, generation).await;
+        Ok(Some((entry, tier)))
+    }
+
+    /// Keep an entry read at invalidation `generation` in the in-memory tier, unless
+    /// entries were deleted or replaced since: it may be one of them
+    async fn remember(&self, cache_key: &str, entry: &CacheEntry, generation: u64) {
+        let Some(memory) = &self.memory else {
+            return;
+        };
+        if self.invalidations.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        memory.insert(cache_key.to_string(), entry.clone()).await;
+        // An invalidation that ran during the insert may have missed it
+        if self.invalidations.load(Ordering::SeqCst) != generation {
+            memory.invalidate(cache_key).await;
+        }
+    }
+
+    /// Drop entries matching `predicate` from the in-memory tier. Called after the
+    /// backend write, so a concurrent lookup cannot read the old row back in.
+    fn invalidate_memory<F>(&self, predicate: F)
+    where
+        F: Fn(&String, &CacheEntry) -> bool + Send + Sync + 'static,
+    {
+        self.invalidations.fetch_add(1, Ordering::SeqCst);
+        if let Some(memory) = &self.memory {
+            if let Err(e) = memory.invalidate_entries_if(predicate) {
+                tracing::warn!("Failed to invalidate in-memory cache entries: {}", e);
+                memory.invalidate_all();
+            }
+        }
+    }
+
+    /// Look up translated segments by key, returning the ones found.
+    /// Segments are not counted in hit/miss statistics.
+    pub async fn get_segments(&self, segment_keys: &[String]) -> Result<HashMap<String, String>> {
+        self.backend.get_segments(segment_keys).await
+    }
+
+    /// Store translated segments of a tenant as `(segment_key, translated_text)` pairs
+    pub async fn set_segments(&self, tenant: &str, segments: &[(String, String)]) -> Result<()> {
+        self.backend.set_segments(tenant, segments).await
+    }
+
+    /// Look up the result of one upstream call stored by a translation that did not
+    /// finish, e.g. because the service crashed. Not counted in hit/miss statistics.
+    pub async fn get_partial(&self, partial_key: &str) -> Result<Option<String>> {
+        self.backend.get_partial(partial_key).await
+    }
+
+    /// Store the result of one upstream call while the rest of the translation is in progress
+    pub async fn set_partial(&self, tenant: &str, partial_key: &str, translated_text: &str) -> Result<()> {
+        self.backend.set_partial(tenant, partial_key, translated_text).await
+    }
+
+    /// Delete the upstream results of a translation that finished
+    pub async fn delete_partials(&self, partial_keys: &[String]) -> Result<()> {
+        if partial_keys.is_empty() {
+            return Ok(());
+        }
+        self.backend.delete_partials(partial_keys).await
+    }
+
+    /// Look up translation memory entries by normalized source hash, returning the
+    /// translations found. Like segments, they are not counted in hit/miss statistics.
+    pub async fn get_translation_memory(&self, scope: &MemoryScope<'_>, hashes: &[String]) -> Result<HashMap<String, String>> {
+        self.backend.get_translation_memory(scope, hashes).await
+    }
+
+    /// Recently used translation memory entries to compare new sections against
+    pub async fn translation_memory_candidates(&self, scope: &MemoryScope<'_>) -> Result<Vec<MemoryPair>> {
+        self.backend.translation_memory_candidates(scope, memory::CANDIDATE_LIMIT).await
+    }
+
+    /// Store translated sections in the translation memory
+    pub async fn set_translation_memory(&self, scope: &MemoryScope<'_>, pairs: &[MemoryPair]) -> Result<()> {
+        self.backend.set_translation_memory(scope, pairs).await
+    }
+
+    /// Get a tenant's cached translations for a file path, newest first, including those
+    /// stored under another path with identical content. Does not count as a cache hit.
+    pub async fn get_by_path(&self, tenant: &str, path: &str) -> Result<Vec<CacheEntry>> {
+        let mut entries = self.backend.find(tenant, "path", path).await?;
+        for content_hash in self.backend.path_hashes(tenant, path).await? {
+            for entry in self.backend.find(tenant, "content_hash", &content_hash).await? {
+                if !entries.iter().any(|found| found.cache_key == entry.cache_key) {
+                    entries.push(entry);
+                }
+            }
+        }
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.created_at));
+        Ok(self.resolve_all(entries).await)
+    }
+
+    /// List a tenant's cached translations without their content, returning a page of
+    /// entries and the number of matches. Does not count as a cache hit.
+    pub async fn list(&self, tenant: &str, filter: &CacheFilter) -> Result<(Vec<CacheEntrySummary>, i64)> {
+        self.backend.list(tenant, filter).await
+    }
+
+    /// Get a tenant's cached translation by its key. Does not count as a cache hit.
+    pub async fn get_by_key(&self, tenant: &str, cache_key: &str) -> Result<Option<CacheEntry>> {
+        let entries = self.backend.find(tenant, "cache_key", cache_key).await?;
+        Ok(self.resolve_all(entries).await.into_iter().next())
+    }
+
+    /// Get a tenant's cached translations for an original content hash, newest first.
+    /// Does not count as a cache hit.
+    pub async fn get_by_content_hash(&self, tenant: &str, content_hash: &str) -> Result<Vec<CacheEntry>> {
+        let entries = self.backend.find(tenant, "content_hash", content_hash).await?;
+        Ok(self.resolve_all(entries).await)
+    }
+
+    /// Delete a tenant's cached translations for a file path, including those stored
+    /// under another path with identical content
+    pub async fn delete_by_path(&self, tenant: &str, path: &str) -> Result<i64> {
+        let hashes = self.backend.path_hashes(tenant, path).await?;
+        let mut deleted = self.backend.delete_matching(tenant, "path", path).await?;
+        for content_hash in &hashes {
+            deleted += self.backend.delete_matching(tenant, "content_hash", content_hash).await?;
+        }
+
+        let (owner, path) = (tenant.to_string(), path.to_string());
+        self.invalidate_memory(move |_, entry| {
+            (entry.path == path || hashes.contains(&entry.content_hash)) && entry_tenant(entry) == owner
+        });
+        Ok(deleted)
+    }
+
+    /// Delete a tenant's cached translations whose path starts with `path_prefix`,
+    /// returning the deleted entries
+    pub async fn delete_by_path_prefix(&self, tenant: &str, path_prefix: &str) -> Result<Vec<CacheEntrySummary>> {
+        let mut filter = CacheFilter {
+            path_prefix: Some(path_prefix.to_string()),
+            limit: PREFIX_PAGE_SIZE,
+            ..CacheFilter::default()
+        };
+        let mut entries = Vec::new();
+        loop {
+            let (page, total) = self.backend.list(tenant, &filter).await?;
+            entries.extend(page);
+            filter.offset += PREFIX_PAGE_SIZE;
+            if filter.offset >= total {
+                break;
+            }
+        }
+
+        for entry in &entries {
+            self.backend.delete_matching(tenant, "cache_key", &entry.cache_key).await?;
+        }
+        let keys: HashSet<String> = entries.iter().map(|entry| entry.cache_key.clone()).collect();
+        self.invalidate_memory(move |cache_key, _| keys.contains(cache_key));
+        Ok(entries)
+    }
+
+    /// Delete a tenant's cached translations for an original content hash
+    pub async fn delete_by_content_hash(&self, tenant: &str, content_hash: &str) -> Result<i64> {
+        let deleted = self.backend.delete_matching(tenant, "content_hash", content_hash).await?;
+        let (owner, hash) = (tenant.to_string(), content_hash.to_string());
+        self.invalidate_memory(move |_, entry| entry.content_hash == hash && entry_tenant(entry) == owner);
+        Ok(deleted)
+    }
+
+    /// Replace the translated content of an existing entry, keeping its metadata.
+    /// Returns false when no entry exists for the key.
+    pub async fn replace_translation(
+        &self,
+        cache_key: &str,
+        translated_content: &str,
+        translated_hash: &str,
+    ) -> Result<bool> {
+        let stored = self.offload(translated_content, translated_hash).await?;
+        let replaced = self
+            .backend
+            .replace_translation(cache_key, &stored, translated_hash)
+            .await?;
+        let key = cache_key.to_string();
+        self.invalidate_memory(move |cache_key, _| *cache_key == key);
+        Ok(replaced)
+    }
+
+    /// Record that a tenant's cached content was submitted under another path, so lookups
+    /// and deletions by that path find it
+    pub async fn record_path(&self, tenant: &str, content_hash: &str, path: &str) -> Result<()> {
+        self.backend.add_path(tenant, content_hash, path).await
+    }
+
+    /// Store a translation in the cache.
+    /// The entry belongs to the tenant named in `metadata`, or the default tenant.
+    #[tracing::instrument(name = "cache_set", skip_all, fields(cache_key = %cache_key, path = %path))]
+    pub async fn set(
+        &self,
+        cache_key: &str,
+        content_hash: &str,
+        path: &str,
+        translated_content: &str,
+        translated_hash: &str,
+        metadata: Option<serde_json::Value>,
+    ) -> Result<CacheEntry> {
+        let entry = NewEntry {
+            cache_key: cache_key.to_string(),
+            content_hash: content_hash.to_string(),
+            path: path.to_string(),
+            translated_content: translated_content.to_string(),
+            translated_hash: translated_hash.to_string(),
+            metadata,
+        };
+        let mut stored = self.set_many(vec![entry]).await?;
+        Ok(stored.remove(0))
+    }
+
+    /// Store several translations in one backend transaction, like [`Self::set`] for each
+    #[tracing::instrument(name = "cache_set_many", skip_all, fields(entries = entries.len()))]
+    pub async fn set_many(&self, entries: Vec<NewEntry>) -> Result<Vec<CacheEntry>> {
+        let now = Utc::now();
+        let mut stored = Vec::with_capacity(entries.len());
+        let mut contents = Vec::with_capacity(entries.len());
+        for new in entries {
+            let metadata = new.metadata.unwrap_or(serde_json::json!({}));
+            let metadata_field = |name: &str| {
+                metadata
+                    .get(name)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let tenant = match metadata_field("tenant") {
+                tenant if tenant.is_empty() => DEFAULT_TENANT.to_string(),
+                tenant => tenant,
+            };
+
+            let content = self.offload(&new.translated_content, &new.translated_hash).await?;
+            stored.push(StoredEntry {
+                source_language: metadata_field("source_language"),
+                target_language: metadata_field("target_language"),
+                model: metadata_field("model"),
+                path_prefix: path_prefix(&new.path),
+                tenant,
+                entry: CacheEntry {
+                    cache_key: new.cache_key,
+                    content_hash: new.content_hash,
+                    path: new.path,
+                    translated_content: content,
+                    translated_hash: new.translated_hash,
+                    created_at: now,
+                    accessed_at: now,
+                    hit_count: 0,
+                    metadata,
+                },
+            });
+            contents.push(new.translated_content);
+        }
+        self.backend.set_many(&stored).await?;
+
+        let mut entries = Vec::with_capacity(stored.len());
+        for (stored, translated_content) in stored.into_iter().zip(contents) {
+            let mut entry = stored.entry;
+            entry.translated_content = translated_content;
+            if let Some(memory) = &self.memory {
+                memory.insert(entry.cache_key.clone(), entry.clone()).await;
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Flush pending hit count updates and lookup counters to database
+    pub async fn flush_pending_hits(&self) -> Result<()> {
+        let pending = {
+            let mut pending = self.pending_hits.lock().await;
+            std::mem::take(&mut *pending)
+        };
+
+        if !pending.is_empty() {
+            self.backend.add_hits(&pending).await?;
+        }
+        self.flush_pending_lookups().await
+    }
+
+    async fn flush_pending_lookups(&self) -> Result<()> {
+        let pending = std::mem::take(&mut *self.pending_lookups.lock().await);
+        if pending.is_empty() {
+            return Ok(());
+        }
+        self.backend.add_lookups(&pending).await
+    }
+
+    /// Clear all expired cache entries
+    pub async fn clear_expired(&self) -> Result<i64> {
+        let _maintenance = self.maintenance.lock().await;
+        let cutoff = Utc::now() - Duration::days(self.max_age_days);
+        let cleared = self.backend.clear_before("created_at", cutoff).await?;
+        self.invalidate_memory(move |_, entry| entry.created_at < cutoff);
+        Ok(cleared)
+    }
+
+    /// Clear stale cache entries not accessed for specified days
+    /// This is useful for cleaning up entries that haven't been used
+    pub async fn clear_stale(&self, stale_days: i64) -> Result<i64> {
+        let _maintenance = self.maintenance.lock().await;
+        let cutoff = Utc::now() - Duration::days(stale_days);
+        let cleared = self.backend.clear_before("accessed_at", cutoff).await?;
+        self.invalidate_memory(move |_, entry| entry.accessed_at < cutoff);
+
+        tracing::info!(
+            "Cleared {} stale cache entries (not accessed in {} days)",
+            cleared,
+            stale_days
+        );
+
+        Ok(cleared)
+    }
+
+    /// Purge or mark the entries of every tenant stored by a translator version other than
+    /// `current_version`, returning the number of entries deleted or newly marked
+    pub async fn retire_versions(&self, current_version: &str, policy: StaleVersionPolicy) -> Result<i64> {
+        let _maintenance = self.maintenance.lock().await;
+        let retired = match policy {
+            StaleVersionPolicy::Keep => return Ok(0),
+            StaleVersionPolicy::Purge => self.backend.delete_stale_versions(current_version).await?,
+            StaleVersionPolicy::Mark => self.backend.mark_stale_versions(current_version).await?,
+        };
+        let current = current_version.to_string();
+        self.invalidate_memory(move |_, entry| entry_version(entry).is_some_and(|version| version != current));
+        tracing::info!(
+            "Retired {} cache entries of translator versions other than {} ({:?})",
+            retired,
+            current_version,
+            policy
+        );
+        Ok(retired)
+    }
+
+    /// Get a tenant's translations of an original content hash marked `stale_version`,
+    /// newest first. Does not count as a cache hit.
+    pub async fn get_stale_versions(&self, tenant: &str, content_hash: &str) -> Result<Vec<CacheEntry>> {
+        let entries = self.backend.find(tenant, "content_hash", content_hash).await?;
+        Ok(self.resolve_all(entries.into_iter().filter(is_stale_version).collect()).await)
+    }
+
+    /// Clear a tenant's cache entries and segments
+    pub async fn clear_tenant(&self, tenant: &str) -> Result<i64> {
+        let _maintenance = self.maintenance.lock().await;
+        let cleared = self.backend.clear_tenant(tenant).await?;
+        let owner = tenant.to_string();
+        self.invalidate_memory(move |_, entry| entry_tenant(entry) == owner);
+        self.recent_stats.lock().await.remove(tenant);
+        Ok(cleared)
+    }
+
+    /// Clear all cache entries of every tenant
+    pub async fn clear_all(&self) -> Result<i64> {
+        let _maintenance = self.maintenance.lock().await;
+        let cleared = self.backend.clear_all().await?;
+        self.invalidations.fetch_add(1, Ordering::SeqCst);
+        if let Some(memory) = &self.memory {
+            memory.invalidate_all();
+        }
+        self.recent_stats.lock().await.clear();
+        Ok(cleared)
+    }
+
+    /// Content to store for a translation: a pointer to a new blob when it exceeds the
+    /// blob threshold, or the translation itself
+    async fn offload(&self, translated_content: &str, translated_hash: &str) -> Result<String> {
+        let Some(blobs) = &self.blobs else {
+            return Ok(translated_content.to_string());
+        };
+        match blob::blob_key(translated_hash) {
+            Some(key) if translated_content.len() > self.blob_threshold_bytes => {
+                blobs.put(key, translated_content.as_bytes()).await?;
+                Ok(format!("{}{}", BLOB_POINTER, key))
+            }
+            _ => Ok(translated_content.to_string()),
+        }
+    }
+
+    /// An entry read from the backend with a blob pointer replaced by the blob's content
+    async fn resolve(&self, mut entry: CacheEntry) -> Result<CacheEntry> {
+        let Some(key) = entry.translated_content.strip_prefix(BLOB_POINTER) else {
+            return Ok(entry);
+        };
+        let Some(blobs) = &self.blobs else {
+            return Err(Error::Internal(format!(
+                "Entry {} is stored as blob {} but no blob store is configured",
+                entry.cache_key, key
+            )));
+        };
+        let content = blobs.get(key).await?;
+        entry.translated_content = String::from_utf8(content)
+            .map_err(|e| Error::Internal(format!("Blob {} is not UTF-8: {}", key, e)))?;
+        Ok(entry)
+    }
+
+    /// Entries with their blobs resolved, leaving out those whose blob cannot be read
+    async fn resolve_all(&self, entries: Vec<CacheEntry>) -> Vec<CacheEntry> {
+        let mut resolved = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let cache_key = entry.cache_key.clone();
+            match self.resolve(entry).await {
+                Ok(entry) => resolved.push(entry),
+                Err(e) => tracing::warn!("Skipping cache entry {} with an unreadable blob: {}", cache_key, e),
+            }
+        }
+        resolved
+    }
+
+    /// Delete the blobs no entry references any more, returning how many were deleted.
+    /// Lists every blob and reads every entry's hash, so it runs with the scheduled
+    /// cleanup rather than after each eviction.
+    pub async fn collect_blobs(&self) -> Result<usize> {
+        let Some(blobs) = &self.blobs else {
+            return Ok(0);
+        };
+        let referenced = self.backend.translated_hashes().await?;
+        let referenced: HashSet<&str> = referenced.iter().filter_map(|hash| blob::blob_key(hash)).collect();
+        let cutoff = Utc::now() - Duration::minutes(BLOB_GRACE_PERIOD_MINUTES);
+        let mut deleted = 0usize;
+        for (key, written) in blobs.list().await? {
+            if written < cutoff && !referenced.contains(key.as_str()) {
+                blobs.delete(&key).await?;
+                deleted += 1;
+            }
+        }
+        if deleted > 0 {
+            tracing::info!("Deleted {} unreferenced translation blobs", deleted);
+        }
+        Ok(deleted)
+    }
+
+    /// Checkpoint and vacuum the local SQLite database so deleted rows give space back.
+    /// Waits for running bulk deletes and flushes pending hits first.
+    pub async fn compact(&self) -> Result<CompactStats> {
+        let _maintenance = self.maintenance.lock().await;
+        self.flush_pending_hits().await?;
+        sqlite::compact(&self.pool, &self.db_path).await
+    }
+
+    /// Get cache statistics of a tenant
+    pub async fn get_stats(&self, tenant: &str) -> Result<CacheStats> {
+        let mut stats = self.backend.stats(tenant).await?;
+
+        // Persisted counters, over whole hours up to the current one
+        self.flush_pending_lookups().await?;
+        let this_hour = hour_start(Utc::now());
+        let total = self.backend.lookup_totals(tenant, None).await?;
+        let day = self.backend.lookup_totals(tenant, Some(this_hour - Duration::hours(23))).await?;
+        let week = self.backend.lookup_totals(tenant, Some(this_hour - Duration::hours(7 * 24 - 1))).await?;
+        stats.total_hits = total.hits;
+        stats.total_misses = total.misses;
+        stats.lookups_24h = day.lookups();
+        stats.hit_rate_24h = day.hit_rate();
+        stats.lookups_7d = week.lookups();
+        stats.hit_rate_7d = week.hit_rate();
+
+        let counts = self.lookup_counts.lock().await.get(tenant).copied().unwrap_or_default();
+        let rate = |hits: i64, lookups: i64| if lookups > 0 { hits as f64 / lookups as f64 } else { 0.0 };
+        stats.memory_hits = counts.memory_hits;
+        stats.store_hits = counts.store_hits;
+        stats.memory_hit_rate = rate(counts.memory_hits, counts.memory_hits + counts.store_hits + counts.misses);
+        stats.store_hit_rate = rate(counts.store_hits, counts.store_hits + counts.misses);
+
+        self.recent_stats
+            .lock()
+            .await
+            .insert(tenant.to_string(), (Instant::now(), stats.clone()));
+        Ok(stats)
+    }
+
+    /// Get a tenant's cache statistics computed at most `max_age` ago, with their age.
+    /// Polling dashboards then cost one round of aggregate queries per `max_age`.
+    pub async fn get_recent_stats(
+        &self,
+        tenant: &str,
+        max_age: std::time::Duration,
+    ) -> Result<(CacheStats, std::time::Duration)> {
+        if let Some((computed, stats)) = self.recent_stats.lock().await.get(tenant) {
+            let age = computed.elapsed();
+            if age < max_age {
+                return Ok((stats.clone(), age));
+            }
+        }
+        Ok((self.get_stats(tenant).await?, std::time::Duration::ZERO))
+    }
+
+    /// Get a tenant's cache statistics grouped by target language, path prefix and model
+    pub async fn get_detailed_stats(&self, tenant: &str) -> Result<DetailedCacheStats> {
+        Ok(DetailedCacheStats {
+            by_target_language: self.backend.group_stats("target_language", Some(tenant)).await?,
+            by_path_prefix: self.backend.group_stats("path_prefix", Some(tenant)).await?,
+            by_model: self.backend.group_stats("model", Some(tenant)).await?,
+        })
+    }
+
+    /// Get cache statistics of every tenant with cached entries
+    pub async fn get_tenant_stats(&self) -> Result<Vec<CacheGroupStats>> {
+        self.backend.group_stats("tenant", None).await
+    }
+
+    /// Gracefully close the cache connection
+    /// Flushes pending hits and lets the backend release its resources
+    pub async fn close(&self) -> Result<()> {
+        tracing::info!("Closing cache connection...");
+
+        // Flush any pending hit count updates
+        self.flush_pending_hits().await?;
+        tracing::debug!("Flushed pending hits");
+
+        self.backend.close().await?;
+
+        tracing::info!("Cache closed successfully");
+        Ok(())
+    }
+}
+
+impl CacheGroupStats {
+    /// Group statistics with the hit rate derived from entries and hits
+    pub(crate) fn new(key: String, entries: i64, size_bytes: i64, total_hits: i64) -> Self {
+        // Each entry cost one miss when it was first translated
+        let lookups = entries + total_hits;
+        Self {
+            key,
+            entries,
+            size_bytes,
+            total_hits,
+            hit_rate: if lookups > 0 {
+                total_hits as f64 / lookups as f64
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// Translator version that stored an entry, as recorded in its metadata
+pub fn entry_version(entry: &CacheEntry) -> Option<&str> {
+    entry.metadata.get("translator_version").and_then(|v| v.as_str())
+}
+
+/// Whether an entry was marked as left behind by an earlier translator version
+fn is_stale_version(entry: &CacheEntry) -> bool {
+    entry.metadata.get("stale_version").and_then(|v| v.as_bool()) == Some(true)
+}
+
+/// Start of the hour a time falls in
+fn hour_start(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.duration_trunc(Duration::hours(1)).unwrap_or(time)
+}
+
+/// Tenant an entry belongs to, as recorded in its metadata
+fn entry_tenant(entry: &CacheEntry) -> &str {
+    entry
+        .metadata
+        .get("tenant")
+        .and_then(|v| v.as_str())
+        .filter(|tenant| !tenant.is_empty())
+        .unwrap_or(DEFAULT_TENANT)
+}
+
+/// Repository prefix of a skill path: its first two segments (e.g. `skills/owner`)
+fn path_prefix(path: &str) -> String {
+    path.trim_start_matches("./")
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .take(2)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CacheOrder;
+
+    #[test]
+    fn test_path_prefix() {
+        assert_eq!(path_prefix("skills/owner/skill-name/SKILL.md"), "skills/owner");
+        assert_eq!(path_prefix("./skills/owner/SKILL.md"), "skills/owner");
+        assert_eq!(path_prefix("SKILL.md"), "SKILL.md");
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_backend_round_trip() {
+        let dir = std::env::temp_dir().join(format!("skillts-cache-{}", std::process::id()));
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+
+        let metadata = serde_json::json!({"tenant": "registry-a", "target_language": "zh-CN"});
+        cache
+            .set("key", "hash", "skills/owner/SKILL.md", "译文", "thash", Some(metadata))
+            .await
+            .unwrap();
+
+        let keys = vec!["missing".to_string(), "key".to_string()];
+        let entry = cache.get_first("registry-a", &keys).await.unwrap().unwrap();
+        assert_eq!(entry.translated_content, "译文");
+        assert!(cache.get_first("registry-a", &["missing".to_string()]).await.unwrap().is_none());
+        cache.flush_pending_hits().await.unwrap();
+
+        let stats = cache.get_stats("registry-a").await.unwrap();
+        assert_eq!((stats.total_entries, stats.total_hits, stats.total_misses), (1, 1, 1));
+        assert_eq!((stats.memory_hits, stats.store_hits), (1, 0));
+        assert_eq!(stats.memory_hit_rate, 0.5);
+        assert_eq!((stats.lookups_24h, stats.hit_rate_24h, stats.lookups_7d), (2, 0.5, 2));
+        assert_eq!(cache.get_stats(DEFAULT_TENANT).await.unwrap().total_entries, 0);
+        assert_eq!(cache.get_by_path("registry-a", "skills/owner/SKILL.md").await.unwrap().len(), 1);
+
+        let scope = MemoryScope {
+            tenant: "registry-a",
+            source_language: "en",
+            target_language: "zh-CN",
+        };
+        let pair = MemoryPair::new("## License\n\nMIT", "## 许可证\n\nMIT");
+        cache.set_translation_memory(&scope, std::slice::from_ref(&pair)).await.unwrap();
+        let hashes = vec![memory::normalized_hash("## License MIT")];
+        assert_eq!(
+            cache.get_translation_memory(&scope, &hashes).await.unwrap().get(&hashes[0]),
+            Some(&pair.translation)
+        );
+        assert_eq!(cache.translation_memory_candidates(&scope).await.unwrap(), vec![pair]);
+
+        assert_eq!(cache.clear_tenant("registry-a").await.unwrap(), 1);
+        assert!(cache.get_first("registry-a", &keys).await.unwrap().is_none());
+        assert!(cache.get_tenant_stats().await.unwrap().is_empty());
+        assert!(cache.translation_memory_candidates(&scope).await.unwrap().is_empty());
+
+        // Lookup counters survive a restart
+        cache.close().await.unwrap();
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+        let stats = cache.get_stats("registry-a").await.unwrap();
+        assert_eq!((stats.total_hits, stats.total_misses, stats.memory_hits), (1, 2, 0));
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_rolling_hit_rates() {
+        let dir = std::env::temp_dir().join(format!("skillts-lookups-{}", std::process::id()));
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+
+        // Counters of earlier hours, as left by previous runs
+        let this_hour = hour_start(Utc::now());
+        let bucket = |tenant: &str, hours_ago: i64, hits: i64, misses: i64| {
+            ((tenant.to_string(), this_hour - Duration::hours(hours_ago)), LookupTotals { hits, misses })
+        };
+        let lookups: LookupBuckets = [
+            bucket("registry-a", 23, 3, 1),
+            bucket("registry-a", 24, 0, 4),
+            bucket("registry-a", 7 * 24, 2, 0),
+            bucket("registry-b", 0, 5, 5),
+        ]
+        .into_iter()
+        .collect();
+        cache.backend.add_lookups(&lookups).await.unwrap();
+        // Counters of the same hour add up
+        cache.backend.add_lookups(&[bucket("registry-a", 23, 1, 0)].into_iter().collect()).await.unwrap();
+
+        // The current hour counts once flushed, which reading the statistics does
+        cache.get_first("registry-a", &["missing".to_string()]).await.unwrap();
+        let stats = cache.get_stats("registry-a").await.unwrap();
+        assert_eq!((stats.total_hits, stats.total_misses), (6, 6));
+        assert_eq!((stats.lookups_24h, stats.hit_rate_24h), (6, 4.0 / 6.0));
+        assert_eq!((stats.lookups_7d, stats.hit_rate_7d), (10, 0.4));
+
+        let stats = cache.get_stats("registry-c").await.unwrap();
+        assert_eq!((stats.total_hits, stats.lookups_24h, stats.hit_rate_24h), (0, 0, 0.0));
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_oversized_translations_as_blobs() {
+        let dir = std::env::temp_dir().join(format!("skillts-cache-blobs-{}", std::process::id()));
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            memory_bytes: 0,
+            blob_store: BlobStoreKind::Filesystem,
+            blob_threshold_bytes: 8,
+            blob_dir: dir.join("blobs").to_string_lossy().into_owned(),
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+
+        let large = "一段很长的译文".repeat(4);
+        cache.set("large", "hash-a", "a/SKILL.md", &large, "sha256:abc", None).await.unwrap();
+        cache.set("small", "hash-b", "b/SKILL.md", "短", "sha256:def", None).await.unwrap();
+
+        // Only a pointer is stored in the database
+        let stored: Vec<u8> = sqlx::query_scalar("SELECT translated_content FROM translations WHERE cache_key = 'large'")
+            .fetch_one(cache.pool())
+            .await
+            .unwrap();
+        let stored = zstd::decode_all(stored.as_slice()).unwrap();
+        assert_eq!(String::from_utf8(stored).unwrap(), format!("{}abc", BLOB_POINTER));
+        assert!(dir.join("blobs/abc").exists() && !dir.join("blobs/def").exists());
+
+        let entry = cache.get_first(DEFAULT_TENANT, &["large".to_string()]).await.unwrap().unwrap();
+        assert_eq!(entry.translated_content, large);
+        assert_eq!(cache.get_by_path(DEFAULT_TENANT, "a/SKILL.md").await.unwrap()[0].translated_content, large);
+
+        // An entry whose blob is gone is a miss
+        std::fs::remove_file(dir.join("blobs/abc")).unwrap();
+        assert!(cache.get_first(DEFAULT_TENANT, &["large".to_string()]).await.unwrap().is_none());
+        assert!(cache.get_by_key(DEFAULT_TENANT, "large").await.unwrap().is_none());
+
+        // Deleting an entry leaves its blob to the scheduled collection, which keeps recent ones
+        cache.set("other", "hash-c", "c/SKILL.md", &large, "sha256:ghi", None).await.unwrap();
+        assert_eq!(cache.delete_by_content_hash(DEFAULT_TENANT, "hash-c").await.unwrap(), 1);
+        assert!(dir.join("blobs/ghi").exists());
+        assert_eq!(cache.collect_blobs().await.unwrap(), 0);
+        assert!(dir.join("blobs/ghi").exists());
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_paths_of_identical_content() {
+        let dir = std::env::temp_dir().join(format!("skillts-paths-{}", std::process::id()));
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+
+        let metadata = serde_json::json!({"tenant": "registry-a"});
+        cache.set("key", "hash", "a/README.md", "译文", "thash", Some(metadata)).await.unwrap();
+        cache.record_path("registry-a", "hash", "b/README.md").await.unwrap();
+        cache.record_path("registry-a", "hash", "b/README.md").await.unwrap();
+
+        let stats = cache.get_stats("registry-a").await.unwrap();
+        assert_eq!((stats.total_entries, stats.total_paths, stats.deduplicated_paths), (1, 2, 1));
+        assert_eq!(cache.get_by_path("registry-a", "b/README.md").await.unwrap()[0].cache_key, "key");
+        assert!(cache.get_by_path("registry-b", "b/README.md").await.unwrap().is_empty());
+
+        // Invalidating the alias removes the shared translation and its paths
+        assert_eq!(cache.delete_by_path("registry-a", "b/README.md").await.unwrap(), 1);
+        assert!(cache.get_first("registry-a", &["key".to_string()]).await.unwrap().is_none());
+        assert_eq!(cache.get_stats("registry-a").await.unwrap().total_paths, 0);
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_lookup_and_delete_by_path() {
+        let dir = std::env::temp_dir().join(format!("skillts-by-path-{}", std::process::id()));
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+
+        // The same content translated under two paths, e.g. for two target languages
+        let metadata = serde_json::json!({"tenant": "registry-a"});
+        cache.set("k1", "hash", "a/SKILL.md", "译文", "t1", Some(metadata.clone())).await.unwrap();
+        cache.set("k2", "hash", "b/SKILL.md", "訳文", "t2", Some(metadata.clone())).await.unwrap();
+        cache.set("k3", "other", "c/SKILL.md", "译文", "t3", Some(metadata)).await.unwrap();
+
+        let keys = |entries: Vec<CacheEntry>| {
+            let mut keys: Vec<String> = entries.into_iter().map(|entry| entry.cache_key).collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(keys(cache.get_by_path("registry-a", "a/SKILL.md").await.unwrap()), ["k1", "k2"]);
+        assert_eq!(keys(cache.get_by_content_hash("registry-a", "hash").await.unwrap()), ["k1", "k2"]);
+        assert!(cache.get_by_path("registry-b", "a/SKILL.md").await.unwrap().is_empty());
+        assert_eq!(cache.delete_by_path("registry-b", "a/SKILL.md").await.unwrap(), 0);
+
+        // Deleting by the first path removes the entry stored under the second one too
+        assert!(cache.get_first("registry-a", &["k2".to_string()]).await.unwrap().is_some());
+        assert_eq!(cache.delete_by_path("registry-a", "a/SKILL.md").await.unwrap(), 2);
+        assert!(cache.get_first("registry-a", &["k2".to_string()]).await.unwrap().is_none());
+        assert!(cache.get_by_path("registry-a", "b/SKILL.md").await.unwrap().is_empty());
+
+        assert_eq!(cache.delete_by_content_hash("registry-a", "other").await.unwrap(), 1);
+        assert!(cache.get_first("registry-a", &["k3".to_string()]).await.unwrap().is_none());
+        assert_eq!(cache.delete_by_content_hash("registry-a", "other").await.unwrap(), 0);
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_lookups_during_replace_translation() {
+        let dir = std::env::temp_dir().join(format!("skillts-replace-{}", std::process::id()));
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+        let keys = ["key".to_string()];
+        cache.set("key", "hash", "a/SKILL.md", "旧译文", "t1", None).await.unwrap();
+
+        // Lookups racing the replacement must not put the old translation back in memory
+        let lookups = async {
+            for _ in 0..20 {
+                cache.get_first(DEFAULT_TENANT, &keys).await.unwrap();
+                tokio::task::yield_now().await;
+            }
+        };
+        let (replaced, _) = futures::future::join(cache.replace_translation("key", "新译文", "t2"), lookups).await;
+        assert!(replaced.unwrap());
+        let entry = cache.get_first(DEFAULT_TENANT, &keys).await.unwrap().unwrap();
+        assert_eq!((entry.translated_content.as_str(), entry.translated_hash.as_str()), ("新译文", "t2"));
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_memory_tier_invalidation() {
+        let dir = std::env::temp_dir().join(format!("skillts-memory-{}", std::process::id()));
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+        let metadata = serde_json::json!({"tenant": "registry-a"});
+        for (key, hash, path) in [("k1", "h1", "a/SKILL.md"), ("k2", "h2", "b/SKILL.md"), ("k3", "h3", "c/SKILL.md")] {
+            cache.set(key, hash, path, "旧译文", "t1", Some(metadata.clone())).await.unwrap();
+        }
+        let memory = cache.memory.as_ref().unwrap();
+        let [k1, k2, k3] = ["k1", "k2", "k3"].map(|key| vec![key.to_string()]);
+
+        // Stored entries are served from memory
+        assert_eq!(cache.get_first("registry-a", &k1).await.unwrap().unwrap().translated_content, "旧译文");
+        assert_eq!(cache.get_stats("registry-a").await.unwrap().memory_hits, 1);
+
+        // An overwrite replaces the copy in memory
+        assert!(cache.replace_translation("k1", "新译文", "t2").await.unwrap());
+        assert!(!memory.contains_key("k1"));
+        assert_eq!(cache.get_first("registry-a", &k1).await.unwrap().unwrap().translated_content, "新译文");
+        cache.set("k1", "h1", "a/SKILL.md", "再译文", "t3", Some(metadata)).await.unwrap();
+        assert_eq!(cache.get_first("registry-a", &k1).await.unwrap().unwrap().translated_content, "再译文");
+
+        // Deleted entries are not served from memory
+        assert!(memory.contains_key("k2") && memory.contains_key("k3"));
+        assert_eq!(cache.delete_by_content_hash("registry-a", "h2").await.unwrap(), 1);
+        assert!(!memory.contains_key("k2") && memory.contains_key("k3"));
+        assert!(cache.get_first("registry-a", &k2).await.unwrap().is_none());
+        assert_eq!(cache.delete_by_path("registry-a", "c/SKILL.md").await.unwrap(), 1);
+        assert!(cache.get_first("registry-a", &k3).await.unwrap().is_none());
+        assert_eq!(cache.clear_tenant("registry-a").await.unwrap(), 1);
+        assert!(cache.get_first("registry-a", &k1).await.unwrap().is_none());
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_path_prefix() {
+        let dir = std::env::temp_dir().join(format!("skillts-prefix-{}", std::process::id()));
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+
+        let metadata = serde_json::json!({"tenant": "registry-a"});
+        for (key, path) in [("k1", "skills/a/SKILL.md"), ("k2", "skills/b/SKILL.md"), ("k3", "other/SKILL.md")] {
+            cache.set(key, key, path, "译文", "thash", Some(metadata.clone())).await.unwrap();
+        }
+        assert!(cache.get_first("registry-a", &["k1".to_string()]).await.unwrap().is_some());
+
+        let deleted = cache.delete_by_path_prefix("registry-a", "skills/").await.unwrap();
+        let mut paths: Vec<_> = deleted.iter().map(|entry| entry.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, ["skills/a/SKILL.md", "skills/b/SKILL.md"]);
+        assert!(cache.get_first("registry-a", &["k1".to_string()]).await.unwrap().is_none());
+        assert!(cache.get_first("registry-a", &["k3".to_string()]).await.unwrap().is_some());
+        assert!(cache.delete_by_path_prefix("registry-b", "").await.unwrap().is_empty());
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_get_many_and_set_many() {
+        let dir = std::env::temp_dir().join(format!("skillts-many-{}", std::process::id()));
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            memory_bytes: 0,
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+
+        let entries = ["k1", "k2"]
+            .into_iter()
+            .map(|key| NewEntry {
+                cache_key: key.to_string(),
+                content_hash: format!("hash-{}", key),
+                path: format!("skills/{}/SKILL.md", key),
+                translated_content: format!("译文 {}", key),
+                translated_hash: "thash".to_string(),
+                metadata: Some(serde_json::json!({"tenant": "registry-a"})),
+            })
+            .collect();
+        assert_eq!(cache.set_many(entries).await.unwrap().len(), 2);
+        assert_eq!(cache.get_stats("registry-a").await.unwrap().total_paths, 2);
+
+        // Each lookup gets the first of its candidate keys that is cached
+        let keys = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+        let lookups = [keys(&["missing", "k2"]), keys(&["k1"]), keys(&["missing"]), keys(&["k1"])];
+        let found = cache.get_many("registry-a", &lookups).await.unwrap();
+        let found: Vec<_> = found.iter().map(|entry| entry.as_ref().map(|entry| entry.translated_content.as_str())).collect();
+        assert_eq!(found, [Some("译文 k2"), Some("译文 k1"), None, Some("译文 k1")]);
+
+        let stats = cache.get_stats("registry-a").await.unwrap();
+        assert_eq!((stats.store_hits, stats.total_misses), (3, 1));
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_list_entries() {
+        let dir = std::env::temp_dir().join(format!("skillts-list-{}", std::process::id()));
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+
+        for (key, path, language) in [("a", "skills/a/SKILL.md", "zh-CN"), ("b", "skills/b/SKILL.md", "ja")] {
+            let metadata = serde_json::json!({"tenant": "registry-a", "target_language": language});
+            cache.set(key, key, path, "译文", "thash", Some(metadata)).await.unwrap();
+        }
+        cache.get_first("registry-a", &["b".to_string()]).await.unwrap();
+        cache.flush_pending_hits().await.unwrap();
+
+        let by_hits = CacheFilter {
+            order: CacheOrder::Hits,
+            limit: 1,
+            ..CacheFilter::default()
+        };
+        let (entries, total) = cache.list("registry-a", &by_hits).await.unwrap();
+        assert_eq!((entries[0].cache_key.as_str(), entries[0].hit_count, total), ("b", 1, 2));
+
+        let filter = CacheFilter {
+            path_prefix: Some("skills/a".to_string()),
+            limit: 10,
+            ..CacheFilter::default()
+        };
+        assert_eq!(cache.list("registry-a", &filter).await.unwrap().0[0].cache_key, "a");
+        let filter = CacheFilter {
+            target_language: Some("ja".to_string()),
+            limit: 10,
+            ..CacheFilter::default()
+        };
+        assert_eq!(cache.list("registry-a", &filter).await.unwrap().1, 1);
+        assert_eq!(cache.list("registry-b", &filter).await.unwrap().1, 0);
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_retire_versions() {
+        let dir = std::env::temp_dir().join(format!("skillts-versions-{}", std::process::id()));
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+
+        for (key, version) in [("old", Some("1.0.0")), ("new", Some("2.0.0")), ("unknown", None)] {
+            let metadata = serde_json::json!({"tenant": "registry-a", "translator_version": version});
+            cache.set(key, "hash", "SKILL.md", "译文", "thash", Some(metadata)).await.unwrap();
+        }
+
+        assert_eq!(cache.retire_versions("2.0.0", StaleVersionPolicy::Keep).await.unwrap(), 0);
+        assert_eq!(cache.retire_versions("2.0.0", StaleVersionPolicy::Mark).await.unwrap(), 1);
+        assert_eq!(cache.retire_versions("2.0.0", StaleVersionPolicy::Mark).await.unwrap(), 0);
+        let stale = cache.get_stale_versions("registry-a", "hash").await.unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!((stale[0].cache_key.as_str(), entry_version(&stale[0])), ("old", Some("1.0.0")));
+        assert!(cache.get_stale_versions("registry-b", "hash").await.unwrap().is_empty());
+
+        assert_eq!(cache.retire_versions("2.0.0", StaleVersionPolicy::Purge).await.unwrap(), 1);
+        let keys: Vec<String> = cache
+            .get_by_content_hash("registry-a", "hash")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.cache_key)
+            .collect();
+        assert_eq!(keys.len(), 2);
+        assert!(!keys.contains(&"old".to_string()));
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_recent_stats() {
+        let dir = std::env::temp_dir().join(format!("skillts-stats-{}", std::process::id()));
+        let cache = TranslationCache::new(CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            ..CacheConfig::default()
+        })
+        .await
+        .unwrap();
+        let ttl = std::time::Duration::from_secs(60);
+
+        cache.set("k1", "h1", "a/SKILL.md", "译文", "t1", None).await.unwrap();
+        assert_eq!(cache.get_recent_stats("default", ttl).await.unwrap().0.total_entries, 1);
+
+        // Recent statistics are reused until they are computed again
+        cache.set("k2", "h2", "b/SKILL.md", "译文", "t2", None).await.unwrap();
+        assert_eq!(cache.get_recent_stats("default", ttl).await.unwrap().0.total_entries, 1);
+        assert_eq!(cache.get_stats("default").await.unwrap().total_entries, 2);
+        assert_eq!(cache.get_recent_stats("default", ttl).await.unwrap().0.total_entries, 2);
+        let zero = std::time::Duration::ZERO;
+        cache.set("k3", "h3", "c/SKILL.md", "译文", "t3", None).await.unwrap();
+        assert_eq!(cache.get_recent_stats("default", zero).await.unwrap().0.total_entries, 3);
+
+        // Clearing the cache drops them
+        cache.clear_tenant("default").await.unwrap();
+        assert_eq!(cache.get_recent_stats("default", ttl).await.unwrap().0.total_entries, 0);
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
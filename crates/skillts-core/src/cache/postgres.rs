@@ -0,0 +1,892 @@
+//! Postgres cache backend, for running several replicas against one cache.
+//!
+//! The schema is created by numbered migrations applied under an advisory lock,
+//! so replicas starting together do not race. Cleanup also takes an advisory
+//! lock and is skipped by a replica when another one is already running it.
+//!
+//! As in the SQLite backend, static queries use the `query!` macros checked against
+//! the prepared query data in `.sqlx`; queries that build their SQL are checked at
+//! run time.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::Row;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use super::{CacheBackend, CacheConfig, LookupBuckets, LookupTotals, StoredEntry};
+use crate::error::Result;
+use crate::memory::{MemoryPair, MemoryScope};
+use crate::models::{CacheEntry, CacheEntrySummary, CacheFilter, CacheGroupStats, CacheOrder, CacheStats};
+
+/// Advisory lock held while migrations run
+const MIGRATION_LOCK: i64 = 0x736b_696c_6c74_0001;
+
+/// Advisory lock held while expired or stale entries are deleted
+const CLEANUP_LOCK: i64 = 0x736b_696c_6c74_0002;
+
+/// Schema migrations by version; applied versions are recorded in `cache_migrations`
+const MIGRATIONS: &[(i64, &str)] = &[
+    (
+        1,
+        r#"
+        CREATE TABLE IF NOT EXISTS translations (
+            cache_key TEXT PRIMARY KEY,
+            content_hash TEXT NOT NULL,
+            path TEXT NOT NULL,
+            translated_content TEXT NOT NULL,
+            translated_hash TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            accessed_at TIMESTAMPTZ NOT NULL,
+            hit_count BIGINT NOT NULL DEFAULT 0,
+            metadata TEXT NOT NULL DEFAULT '{}',
+            source_language TEXT NOT NULL DEFAULT '',
+            target_language TEXT NOT NULL DEFAULT '',
+            model TEXT NOT NULL DEFAULT '',
+            path_prefix TEXT NOT NULL DEFAULT '',
+            tenant TEXT NOT NULL DEFAULT 'default'
+        );
+        CREATE INDEX IF NOT EXISTS idx_content_hash ON translations(content_hash);
+        CREATE INDEX IF NOT EXISTS idx_path ON translations(path);
+        CREATE INDEX IF NOT EXISTS idx_created_at ON translations(created_at);
+        CREATE INDEX IF NOT EXISTS idx_accessed_at ON translations(accessed_at);
+        CREATE INDEX IF NOT EXISTS idx_target_language ON translations(target_language);
+        CREATE INDEX IF NOT EXISTS idx_path_prefix ON translations(path_prefix);
+        CREATE INDEX IF NOT EXISTS idx_model ON translations(model);
+        CREATE INDEX IF NOT EXISTS idx_tenant ON translations(tenant);
+
+        CREATE TABLE IF NOT EXISTS segments (
+            segment_key TEXT PRIMARY KEY,
+            translated_text TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            accessed_at TIMESTAMPTZ NOT NULL,
+            tenant TEXT NOT NULL DEFAULT 'default'
+        );
+        CREATE INDEX IF NOT EXISTS idx_segments_created_at ON segments(created_at);
+        CREATE INDEX IF NOT EXISTS idx_segments_accessed_at ON segments(accessed_at);
+        CREATE INDEX IF NOT EXISTS idx_segments_tenant ON segments(tenant);
+        "#,
+    ),
+    (
+        2,
+        r#"
+        CREATE TABLE IF NOT EXISTS translation_memory (
+            tenant TEXT NOT NULL,
+            source_language TEXT NOT NULL,
+            target_language TEXT NOT NULL,
+            normalized_hash TEXT NOT NULL,
+            source_text TEXT NOT NULL,
+            translated_text TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            accessed_at TIMESTAMPTZ NOT NULL,
+            PRIMARY KEY (tenant, source_language, target_language, normalized_hash)
+        );
+        CREATE INDEX IF NOT EXISTS idx_memory_accessed_at
+            ON translation_memory(tenant, source_language, target_language, accessed_at);
+        CREATE INDEX IF NOT EXISTS idx_memory_created_at ON translation_memory(created_at);
+        "#,
+    ),
+    (
+        3,
+        r#"
+        CREATE TABLE IF NOT EXISTS paths (
+            tenant TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            path TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            PRIMARY KEY (tenant, content_hash, path)
+        );
+        CREATE INDEX IF NOT EXISTS idx_paths_path ON paths(tenant, path);
+        INSERT INTO paths (tenant, content_hash, path, created_at)
+            SELECT tenant, content_hash, path, MIN(created_at) FROM translations
+            GROUP BY tenant, content_hash, path
+        ON CONFLICT DO NOTHING;
+        "#,
+    ),
+    (
+        4,
+        r#"
+        CREATE TABLE IF NOT EXISTS lookup_stats (
+            tenant TEXT NOT NULL,
+            hour TIMESTAMPTZ NOT NULL,
+            hits BIGINT NOT NULL DEFAULT 0,
+            misses BIGINT NOT NULL DEFAULT 0,
+            PRIMARY KEY (tenant, hour)
+        );
+        "#,
+    ),
+    (
+        5,
+        r#"
+        CREATE TABLE IF NOT EXISTS partial_translations (
+            partial_key TEXT PRIMARY KEY,
+            tenant TEXT NOT NULL,
+            translated_text TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            accessed_at TIMESTAMPTZ NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_partials_created_at ON partial_translations(created_at);
+        CREATE INDEX IF NOT EXISTS idx_partials_tenant ON partial_translations(tenant);
+        "#,
+    ),
+];
+
+/// Delete the paths of content without cached translations, of one tenant or all when null
+const PRUNE_PATHS: &str = r#"
+    DELETE FROM paths
+    WHERE ($1::TEXT IS NULL OR tenant = $1)
+      AND NOT EXISTS (
+          SELECT 1 FROM translations t
+          WHERE t.tenant = paths.tenant AND t.content_hash = paths.content_hash
+      )
+"#;
+
+/// Record the path content was submitted under
+const INSERT_PATH: &str = r#"
+    INSERT INTO paths (tenant, content_hash, path, created_at)
+    VALUES ($1, $2, $3, $4)
+    ON CONFLICT DO NOTHING
+"#;
+
+/// Translations, segments and translation memory in a shared Postgres database
+pub struct PostgresBackend {
+    pool: PgPool,
+}
+
+impl PostgresBackend {
+    /// Connect to `config.database_url` and apply pending migrations
+    pub async fn connect(config: &CacheConfig) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .acquire_timeout(Duration::from_secs(config.connect_timeout_seconds))
+            .connect(&config.database_url)
+            .await?;
+
+        migrate(&pool).await?;
+
+        Ok(Self { pool })
+    }
+}
+
+/// Apply migrations not yet recorded, holding the migration lock until commit
+async fn migrate(pool: &PgPool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(MIGRATION_LOCK)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS cache_migrations (
+            version BIGINT PRIMARY KEY,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        )
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM cache_migrations")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    for (version, sql) in MIGRATIONS {
+        if applied.contains(version) {
+            continue;
+        }
+        tracing::info!("Applying cache migration {}", version);
+        sqlx::raw_sql(sql).execute(&mut *tx).await?;
+        sqlx::query("INSERT INTO cache_migrations (version) VALUES ($1)")
+            .bind(version)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[async_trait]
+impl CacheBackend for PostgresBackend {
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get(&self, cache_key: &str) -> Result<Option<CacheEntry>> {
+        let row = sqlx::query_as!(
+            TranslationRow,
+            r#"
+            SELECT cache_key, content_hash, path, translated_content, translated_hash,
+                   created_at, accessed_at, hit_count, metadata
+            FROM translations WHERE cache_key = $1
+            "#,
+            cache_key
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(CacheEntry::from))
+    }
+
+    async fn get_many(&self, cache_keys: &[String]) -> Result<Vec<CacheEntry>> {
+        let rows = sqlx::query_as::<_, TranslationRow>("SELECT * FROM translations WHERE cache_key = ANY($1)")
+            .bind(cache_keys)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(CacheEntry::from).collect())
+    }
+
+    async fn delete(&self, cache_key: &str) -> Result<()> {
+        sqlx::query!("DELETE FROM translations WHERE cache_key = $1", cache_key)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_segments(&self, segment_keys: &[String]) -> Result<HashMap<String, String>> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE segments SET accessed_at = $1
+            WHERE segment_key = ANY($2)
+            RETURNING segment_key, translated_text
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(segment_keys)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("segment_key"), row.get("translated_text")))
+            .collect())
+    }
+
+    async fn set_segments(&self, tenant: &str, segments: &[(String, String)]) -> Result<()> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        for (segment_key, translated_text) in segments {
+            sqlx::query(
+                r#"
+                INSERT INTO segments (segment_key, translated_text, created_at, accessed_at, tenant)
+                VALUES ($1, $2, $3, $3, $4)
+                ON CONFLICT (segment_key) DO UPDATE SET
+                    translated_text = EXCLUDED.translated_text,
+                    created_at = EXCLUDED.created_at,
+                    accessed_at = EXCLUDED.accessed_at,
+                    tenant = EXCLUDED.tenant
+                "#,
+            )
+            .bind(segment_key)
+            .bind(translated_text)
+            .bind(now)
+            .bind(tenant)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_partial(&self, partial_key: &str) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT translated_text FROM partial_translations WHERE partial_key = $1")
+            .bind(partial_key)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|row| row.get("translated_text")))
+    }
+
+    async fn set_partial(&self, tenant: &str, partial_key: &str, translated_text: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO partial_translations (partial_key, tenant, translated_text, created_at, accessed_at)
+            VALUES ($1, $2, $3, $4, $4)
+            ON CONFLICT (partial_key) DO UPDATE SET
+                tenant = EXCLUDED.tenant,
+                translated_text = EXCLUDED.translated_text,
+                created_at = EXCLUDED.created_at,
+                accessed_at = EXCLUDED.accessed_at
+            "#,
+        )
+        .bind(partial_key)
+        .bind(tenant)
+        .bind(translated_text)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_partials(&self, partial_keys: &[String]) -> Result<()> {
+        sqlx::query("DELETE FROM partial_translations WHERE partial_key = ANY($1)")
+            .bind(partial_keys)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_translation_memory(&self, scope: &MemoryScope<'_>, hashes: &[String]) -> Result<HashMap<String, String>> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE translation_memory SET accessed_at = $1
+            WHERE tenant = $2 AND source_language = $3 AND target_language = $4
+              AND normalized_hash = ANY($5)
+            RETURNING normalized_hash, translated_text
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(scope.tenant)
+        .bind(scope.source_language)
+        .bind(scope.target_language)
+        .bind(hashes)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("normalized_hash"), row.get("translated_text")))
+            .collect())
+    }
+
+    async fn translation_memory_candidates(&self, scope: &MemoryScope<'_>, limit: i64) -> Result<Vec<MemoryPair>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT normalized_hash, source_text, translated_text FROM translation_memory
+            WHERE tenant = $1 AND source_language = $2 AND target_language = $3
+            ORDER BY accessed_at DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(scope.tenant)
+        .bind(scope.source_language)
+        .bind(scope.target_language)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| MemoryPair {
+                normalized_hash: row.get("normalized_hash"),
+                source: row.get("source_text"),
+                translation: row.get("translated_text"),
+            })
+            .collect())
+    }
+
+    async fn set_translation_memory(&self, scope: &MemoryScope<'_>, pairs: &[MemoryPair]) -> Result<()> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        for pair in pairs {
+            sqlx::query(
+                r#"
+                INSERT INTO translation_memory
+                    (tenant, source_language, target_language, normalized_hash, source_text, translated_text, created_at, accessed_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
+                ON CONFLICT (tenant, source_language, target_language, normalized_hash) DO UPDATE SET
+                    source_text = EXCLUDED.source_text,
+                    translated_text = EXCLUDED.translated_text,
+                    created_at = EXCLUDED.created_at,
+                    accessed_at = EXCLUDED.accessed_at
+                "#,
+            )
+            .bind(scope.tenant)
+            .bind(scope.source_language)
+            .bind(scope.target_language)
+            .bind(&pair.normalized_hash)
+            .bind(&pair.source)
+            .bind(&pair.translation)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn find(&self, tenant: &str, column: &'static str, value: &str) -> Result<Vec<CacheEntry>> {
+        let rows = sqlx::query_as::<_, TranslationRow>(&format!(
+            "SELECT * FROM translations WHERE tenant = $1 AND {} = $2 ORDER BY created_at DESC",
+            column
+        ))
+        .bind(tenant)
+        .bind(value)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(CacheEntry::from).collect())
+    }
+
+    async fn delete_matching(&self, tenant: &str, column: &'static str, value: &str) -> Result<i64> {
+        let result = sqlx::query(&format!(
+            "DELETE FROM translations WHERE tenant = $1 AND {} = $2",
+            column
+        ))
+        .bind(tenant)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(PRUNE_PATHS)
+            .bind(tenant)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn list(&self, tenant: &str, filter: &CacheFilter) -> Result<(Vec<CacheEntrySummary>, i64)> {
+        let matching = r#"
+            tenant = $1
+            AND ($2::TEXT IS NULL OR LEFT(path, LENGTH($2)) = $2)
+            AND ($3::TEXT IS NULL OR target_language = $3)
+        "#;
+        let order = match filter.order {
+            CacheOrder::Newest => "created_at DESC",
+            CacheOrder::Oldest => "created_at ASC",
+            CacheOrder::Hits => "hit_count DESC, created_at DESC",
+            CacheOrder::Size => "size_bytes DESC, created_at DESC",
+            CacheOrder::Accessed => "accessed_at DESC",
+        };
+
+        let rows = sqlx::query_as::<_, SummaryRow>(&format!(
+            r#"
+            SELECT cache_key, content_hash, path, translated_hash, source_language, target_language, model,
+                   OCTET_LENGTH(translated_content)::BIGINT AS size_bytes, created_at, accessed_at, hit_count, metadata
+            FROM translations
+            WHERE {matching}
+            ORDER BY {order}
+            LIMIT $4 OFFSET $5
+            "#
+        ))
+        .bind(tenant)
+        .bind(&filter.path_prefix)
+        .bind(&filter.target_language)
+        .bind(filter.limit)
+        .bind(filter.offset)
+        .fetch_all(&self.pool)
+        .await?;
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM translations WHERE {matching}"))
+            .bind(tenant)
+            .bind(&filter.path_prefix)
+            .bind(&filter.target_language)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((rows.into_iter().map(CacheEntrySummary::from).collect(), total))
+    }
+
+    async fn add_path(&self, tenant: &str, content_hash: &str, path: &str) -> Result<()> {
+        sqlx::query(INSERT_PATH)
+            .bind(tenant)
+            .bind(content_hash)
+            .bind(path)
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn path_hashes(&self, tenant: &str, path: &str) -> Result<Vec<String>> {
+        Ok(sqlx::query_scalar("SELECT content_hash FROM paths WHERE tenant = $1 AND path = $2")
+            .bind(tenant)
+            .bind(path)
+            .fetch_all(&self.pool)
+            .await?)
+    }
+
+    async fn replace_translation(
+        &self,
+        cache_key: &str,
+        translated_content: &str,
+        translated_hash: &str,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE translations SET translated_content = $1, translated_hash = $2 WHERE cache_key = $3",
+        )
+        .bind(translated_content)
+        .bind(translated_hash)
+        .bind(cache_key)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn set_many(&self, stored: &[StoredEntry]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for stored in stored {
+            let entry = &stored.entry;
+            let metadata_json = serde_json::to_string(&entry.metadata)
+                .unwrap_or_else(|_| "{}".to_string());
+
+            sqlx::query!(
+                r#"
+                INSERT INTO translations
+                (cache_key, content_hash, path, translated_content, translated_hash,
+                 created_at, accessed_at, hit_count, metadata,
+                 source_language, target_language, model, path_prefix, tenant)
+                VALUES ($1, $2, $3, $4, $5, $6, $6, 0, $7, $8, $9, $10, $11, $12)
+                ON CONFLICT (cache_key) DO UPDATE SET
+                    content_hash = EXCLUDED.content_hash,
+                    path = EXCLUDED.path,
+                    translated_content = EXCLUDED.translated_content,
+                    translated_hash = EXCLUDED.translated_hash,
+                    created_at = EXCLUDED.created_at,
+                    accessed_at = EXCLUDED.accessed_at,
+                    hit_count = 0,
+                    metadata = EXCLUDED.metadata,
+                    source_language = EXCLUDED.source_language,
+                    target_language = EXCLUDED.target_language,
+                    model = EXCLUDED.model,
+                    path_prefix = EXCLUDED.path_prefix,
+                    tenant = EXCLUDED.tenant
+                "#,
+                entry.cache_key,
+                entry.content_hash,
+                entry.path,
+                entry.translated_content,
+                entry.translated_hash,
+                entry.created_at,
+                metadata_json,
+                stored.source_language,
+                stored.target_language,
+                stored.model,
+                stored.path_prefix,
+                stored.tenant
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(INSERT_PATH)
+                .bind(&stored.tenant)
+                .bind(&entry.content_hash)
+                .bind(&entry.path)
+                .bind(entry.created_at)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn add_hits(&self, hits: &HashMap<String, i64>) -> Result<()> {
+        let (keys, counts): (Vec<String>, Vec<i64>) =
+            hits.iter().map(|(key, count)| (key.clone(), *count)).unzip();
+
+        sqlx::query!(
+            r#"
+            UPDATE translations AS t
+            SET accessed_at = $1, hit_count = t.hit_count + h.count
+            FROM UNNEST($2::TEXT[], $3::BIGINT[]) AS h(cache_key, count)
+            WHERE t.cache_key = h.cache_key
+            "#,
+            Utc::now(),
+            &keys,
+            &counts
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn add_lookups(&self, lookups: &LookupBuckets) -> Result<()> {
+        let mut tenants = Vec::new();
+        let mut hours = Vec::new();
+        let mut hits = Vec::new();
+        let mut misses = Vec::new();
+        for ((tenant, hour), totals) in lookups {
+            tenants.push(tenant.as_str());
+            hours.push(*hour);
+            hits.push(totals.hits);
+            misses.push(totals.misses);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO lookup_stats (tenant, hour, hits, misses)
+            SELECT * FROM UNNEST($1::TEXT[], $2::TIMESTAMPTZ[], $3::BIGINT[], $4::BIGINT[])
+            ON CONFLICT (tenant, hour) DO UPDATE SET
+                hits = lookup_stats.hits + EXCLUDED.hits,
+                misses = lookup_stats.misses + EXCLUDED.misses
+            "#,
+        )
+        .bind(&tenants)
+        .bind(&hours)
+        .bind(&hits)
+        .bind(&misses)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn lookup_totals(&self, tenant: &str, since: Option<DateTime<Utc>>) -> Result<LookupTotals> {
+        let row = sqlx::query(
+            r#"
+            SELECT COALESCE(SUM(hits), 0)::BIGINT AS hits, COALESCE(SUM(misses), 0)::BIGINT AS misses
+            FROM lookup_stats
+            WHERE tenant = $1 AND ($2::TIMESTAMPTZ IS NULL OR hour >= $2)
+            "#,
+        )
+        .bind(tenant)
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(LookupTotals {
+            hits: row.get("hits"),
+            misses: row.get("misses"),
+        })
+    }
+
+    async fn clear_before(&self, column: &'static str, cutoff: DateTime<Utc>) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+
+        // Another replica is already cleaning up; its deletes cover ours
+        let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_xact_lock($1)")
+            .bind(CLEANUP_LOCK)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !locked {
+            tracing::debug!("Cache cleanup is running on another instance, skipping");
+            return Ok(0);
+        }
+
+        let result = sqlx::query(&format!("DELETE FROM translations WHERE {} < $1", column))
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?;
+
+        for table in ["segments", "translation_memory", "partial_translations"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE {} < $1", table, column))
+                .bind(cutoff)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query(PRUNE_PATHS)
+            .bind(None::<&str>)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn mark_stale_versions(&self, current_version: &str) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE translations
+            SET metadata = (metadata::JSONB || '{"stale_version": true}'::JSONB)::TEXT
+            WHERE metadata::JSONB ->> 'translator_version' <> $1
+              AND (metadata::JSONB -> 'stale_version') IS DISTINCT FROM 'true'::JSONB
+            "#,
+        )
+        .bind(current_version)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn delete_stale_versions(&self, current_version: &str) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query("DELETE FROM translations WHERE metadata::JSONB ->> 'translator_version' <> $1")
+            .bind(current_version)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(PRUNE_PATHS)
+            .bind(None::<&str>)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn clear_tenant(&self, tenant: &str) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query("DELETE FROM translations WHERE tenant = $1")
+            .bind(tenant)
+            .execute(&mut *tx)
+            .await?;
+
+        for table in ["segments", "translation_memory", "partial_translations", "paths"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE tenant = $1", table))
+                .bind(tenant)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn clear_all(&self) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query("DELETE FROM translations")
+            .execute(&mut *tx)
+            .await?;
+
+        for table in ["segments", "translation_memory", "partial_translations", "paths"] {
+            sqlx::query(&format!("DELETE FROM {}", table))
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn stats(&self, tenant: &str) -> Result<CacheStats> {
+        let row = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "count!",
+                   SUM(OCTET_LENGTH(translated_content))::BIGINT AS size,
+                   MIN(created_at) AS oldest,
+                   MAX(created_at) AS newest
+            FROM translations
+            WHERE tenant = $1
+            "#,
+            tenant
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        let paths = sqlx::query!(
+            r#"
+            SELECT COUNT(DISTINCT path) AS "paths!",
+                   COUNT(*) - COUNT(DISTINCT content_hash) AS "deduplicated!"
+            FROM paths
+            WHERE tenant = $1
+            "#,
+            tenant
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(CacheStats {
+            total_entries: row.count,
+            total_size_bytes: row.size.unwrap_or(0),
+            oldest_entry: row.oldest,
+            newest_entry: row.newest,
+            total_paths: paths.paths,
+            deduplicated_paths: paths.deduplicated,
+            ..CacheStats::default()
+        })
+    }
+
+    async fn group_stats(&self, column: &'static str, tenant: Option<&str>) -> Result<Vec<CacheGroupStats>> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT {column} AS grp,
+                   COUNT(*) AS entries,
+                   COALESCE(SUM(OCTET_LENGTH(translated_content)), 0)::BIGINT AS size,
+                   COALESCE(SUM(hit_count), 0)::BIGINT AS hits
+            FROM translations
+            WHERE $1::TEXT IS NULL OR tenant = $1
+            GROUP BY {column}
+            ORDER BY entries DESC
+            "#
+        ))
+        .bind(tenant)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| CacheGroupStats::new(row.get("grp"), row.get("entries"), row.get("size"), row.get("hits")))
+            .collect())
+    }
+
+    async fn translated_hashes(&self) -> Result<HashSet<String>> {
+        let rows = sqlx::query("SELECT DISTINCT translated_hash FROM translations")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(|row| row.get("translated_hash")).collect())
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.pool.close().await;
+        Ok(())
+    }
+}
+
+/// A row of the translations table
+#[derive(sqlx::FromRow)]
+struct TranslationRow {
+    cache_key: String,
+    content_hash: String,
+    path: String,
+    translated_content: String,
+    translated_hash: String,
+    created_at: DateTime<Utc>,
+    accessed_at: DateTime<Utc>,
+    hit_count: i64,
+    metadata: String,
+}
+
+impl From<TranslationRow> for CacheEntry {
+    fn from(row: TranslationRow) -> Self {
+        CacheEntry {
+            cache_key: row.cache_key,
+            content_hash: row.content_hash,
+            path: row.path,
+            translated_content: row.translated_content,
+            translated_hash: row.translated_hash,
+            created_at: row.created_at,
+            accessed_at: row.accessed_at,
+            hit_count: row.hit_count,
+            metadata: serde_json::from_str(&row.metadata).unwrap_or(serde_json::json!({})),
+        }
+    }
+}
+
+/// A row of [`PostgresBackend::list`]
+#[derive(sqlx::FromRow)]
+struct SummaryRow {
+    cache_key: String,
+    content_hash: String,
+    path: String,
+    translated_hash: String,
+    source_language: String,
+    target_language: String,
+    model: String,
+    size_bytes: Option<i64>,
+    created_at: DateTime<Utc>,
+    accessed_at: DateTime<Utc>,
+    hit_count: i64,
+    metadata: String,
+}
+
+impl From<SummaryRow> for CacheEntrySummary {
+    fn from(row: SummaryRow) -> Self {
+        CacheEntrySummary {
+            cache_key: row.cache_key,
+            content_hash: row.content_hash,
+            path: row.path,
+            translated_hash: row.translated_hash,
+            source_language: row.source_language,
+            target_language: row.target_language,
+            model: row.model,
+            size_bytes: row.size_bytes.unwrap_or(0),
+            created_at: row.created_at,
+            accessed_at: row.accessed_at,
+            hit_count: row.hit_count,
+            metadata: serde_json::from_str(&row.metadata).unwrap_or(serde_json::json!({})),
+        }
+    }
+}
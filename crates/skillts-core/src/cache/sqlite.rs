@@ -0,0 +1,1390 @@
+//! SQLite cache backend, the default for single-instance deployments.
+//!
+//! Uses WAL mode for better concurrent performance: reads go through the pool,
+//! while every cache write goes through a single writer connection, so long batch
+//! writes never fail other cache writes with SQLITE_BUSY. The other stores in the
+//! database write through the pool and wait for the lock up to the busy timeout. Translated content is
+//! stored zstd-compressed; rows written before compression existed are
+//! compressed the first time they are read. The database file also holds the
+//! review queue, audit log and prompt templates of the service.
+//!
+//! Static queries on the translations table (get, set, delete, hit counts and
+//! statistics) use the `query!` macros, checked at build time against the query
+//! data in `.sqlx`, regenerated as the README describes. Queries that build
+//! their SQL (IN lists, filter columns) are checked at run time and decoded into
+//! typed `FromRow` structs.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use sqlx::sqlite::{SqliteConnection, SqlitePool, SqlitePoolOptions};
+use sqlx::{Connection, Row};
+use std::collections::{HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use tokio::sync::{mpsc, oneshot};
+
+use super::{path_prefix, CacheBackend, LookupBuckets, LookupTotals, StoredEntry};
+use crate::error::{Error, Result};
+use crate::memory::{MemoryPair, MemoryScope};
+use crate::migrate::{migrate, Migration, Schema, Step};
+use crate::models::{CacheEntry, CacheEntrySummary, CacheFilter, CacheGroupStats, CacheOrder, CacheStats, CompactStats};
+use crate::translator::DEFAULT_TENANT;
+
+/// Connection pool size and per-connection pragmas of the SQLite database
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SqliteTuning {
+    /// Connections of the pool; the translation cache writes through one more
+    pub max_connections: u32,
+    /// Page cache of each connection in KiB
+    pub cache_size_kib: u32,
+    /// Milliseconds a connection waits for a lock before failing with SQLITE_BUSY
+    pub busy_timeout_ms: u64,
+    /// WAL size in pages after which SQLite checkpoints it into the database file
+    pub wal_autocheckpoint_pages: u32,
+}
+
+impl Default for SqliteTuning {
+    /// Sized for a low-memory VPS
+    fn default() -> Self {
+        Self {
+            max_connections: 2,
+            cache_size_kib: 8000,
+            busy_timeout_ms: 5000,
+            wal_autocheckpoint_pages: 100,
+        }
+    }
+}
+
+/// Open the SQLite database at `db_path`, creating it and its parent directories as needed
+pub async fn connect(db_path: &str, tuning: &SqliteTuning) -> Result<SqlitePool> {
+    // Ensure parent directory exists
+    let path = Path::new(db_path);
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await.map_err(|e| {
+            Error::Internal(format!("Failed to create cache directory: {}", e))
+        })?;
+    }
+
+    // Build SQLite connection URL
+    let db_url = format!("sqlite:{}?mode=rwc", db_path);
+
+    // Pragmas other than journal_mode only last for one connection, so every
+    // connection of the pool gets them when it opens
+    let tuning = *tuning;
+    let pool = SqlitePoolOptions::new()
+        .max_connections(tuning.max_connections)
+        .after_connect(move |conn, _| Box::pin(async move { enable_wal_mode(conn, &tuning).await }))
+        .connect(&db_url)
+        .await?;
+
+    Ok(pool)
+}
+
+/// Enable WAL mode for better concurrent performance and apply the tuning pragmas
+async fn enable_wal_mode(conn: &mut SqliteConnection, tuning: &SqliteTuning) -> sqlx::Result<()> {
+    sqlx::query("PRAGMA journal_mode=WAL")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("PRAGMA synchronous=NORMAL")
+        .execute(&mut *conn)
+        .await?;
+    // A negative cache_size is in KiB rather than pages
+    sqlx::query(&format!("PRAGMA cache_size=-{}", tuning.cache_size_kib))
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(&format!("PRAGMA busy_timeout={}", tuning.busy_timeout_ms))
+        .execute(&mut *conn)
+        .await?;
+    // Checkpoint regularly to keep the WAL file from growing too large
+    sqlx::query(&format!("PRAGMA wal_autocheckpoint={}", tuning.wal_autocheckpoint_pages))
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// The connection all writes of the cache backend go through, one at a time.
+///
+/// A task owns the connection and lends it to one writer after the other, in the
+/// order their requests arrive on its channel.
+#[derive(Clone)]
+pub struct SqliteWriter {
+    requests: mpsc::Sender<oneshot::Sender<WriteLease>>,
+}
+
+impl SqliteWriter {
+    /// Serialize writes through `conn`
+    pub fn new(mut conn: SqliteConnection) -> Self {
+        let (requests, mut receiver) = mpsc::channel::<oneshot::Sender<WriteLease>>(64);
+        tokio::spawn(async move {
+            while let Some(request) = receiver.recv().await {
+                let (back, returned) = oneshot::channel();
+                // A writer that gave up waiting drops the lease, which hands the connection back
+                let _ = request.send(WriteLease {
+                    conn: Some(conn),
+                    back: Some(back),
+                });
+                match returned.await {
+                    Ok(returned) => conn = returned,
+                    Err(_) => return,
+                }
+            }
+        });
+        Self { requests }
+    }
+
+    /// Wait for the connection; it returns to the writer task when the lease is dropped
+    pub async fn acquire(&self) -> Result<WriteLease> {
+        let stopped = || Error::Internal("SQLite writer stopped".to_string());
+        let (request, lease) = oneshot::channel();
+        self.requests.send(request).await.map_err(|_| stopped())?;
+        lease.await.map_err(|_| stopped())
+    }
+}
+
+/// Exclusive use of the writer connection
+pub struct WriteLease {
+    conn: Option<SqliteConnection>,
+    back: Option<oneshot::Sender<SqliteConnection>>,
+}
+
+impl Deref for WriteLease {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &SqliteConnection {
+        self.conn.as_ref().expect("lease holds the connection until dropped")
+    }
+}
+
+impl DerefMut for WriteLease {
+    fn deref_mut(&mut self) -> &mut SqliteConnection {
+        self.conn.as_mut().expect("lease holds the connection until dropped")
+    }
+}
+
+impl Drop for WriteLease {
+    fn drop(&mut self) {
+        if let (Some(conn), Some(back)) = (self.conn.take(), self.back.take()) {
+            let _ = back.send(conn);
+        }
+    }
+}
+
+/// Size of the database file and its WAL in bytes; missing files count as empty
+async fn file_size(db_path: &str) -> u64 {
+    let mut size = 0;
+    for path in [db_path.to_string(), format!("{}-wal", db_path)] {
+        if let Ok(metadata) = tokio::fs::metadata(&path).await {
+            size += metadata.len();
+        }
+    }
+    size
+}
+
+/// Fold the WAL into the database file and rebuild it, returning the space reclaimed.
+/// `VACUUM` rewrites the whole file and blocks writers until it finishes.
+pub async fn compact(pool: &SqlitePool, db_path: &str) -> Result<CompactStats> {
+    let size_before_bytes = file_size(db_path).await;
+
+    let mut conn = pool.acquire().await?;
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("VACUUM").execute(&mut *conn).await?;
+    // VACUUM goes through the WAL as well
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(&mut *conn)
+        .await?;
+    drop(conn);
+
+    let size_after_bytes = file_size(db_path).await;
+    tracing::info!(
+        "Compacted cache database from {} to {} bytes",
+        size_before_bytes,
+        size_after_bytes
+    );
+
+    Ok(CompactStats {
+        size_before_bytes,
+        size_after_bytes,
+    })
+}
+
+/// Value of the `compression` column for zstd-compressed content
+const ZSTD: &str = "zstd";
+
+/// zstd level used for new rows, favouring speed over ratio
+const ZSTD_LEVEL: i32 = 3;
+
+/// Translations, segments and translation memory in the SQLite database
+pub struct SqliteBackend {
+    /// Reads
+    pool: SqlitePool,
+    writer: SqliteWriter,
+    /// Whether translated content is compressed when written
+    compress: bool,
+}
+
+impl SqliteBackend {
+    /// Create the backend on an open pool, applying pending schema migrations.
+    /// The writer connection is taken out of the pool, which opens another in its place.
+    pub async fn new(pool: SqlitePool, compress: bool) -> Result<Self> {
+        migrate(&pool, &SCHEMA).await?;
+        let writer = SqliteWriter::new(pool.acquire().await?.detach());
+        Ok(Self { pool, writer, compress })
+    }
+
+    /// Encode translated content for storage, returning it with its `compression` value
+    fn pack(&self, translated_content: &str) -> Result<(Vec<u8>, &'static str)> {
+        if !self.compress {
+            return Ok((translated_content.as_bytes().to_vec(), ""));
+        }
+        let compressed = zstd::encode_all(translated_content.as_bytes(), ZSTD_LEVEL)
+            .map_err(|e| Error::Internal(format!("Failed to compress cached translation: {}", e)))?;
+        Ok((compressed, ZSTD))
+    }
+
+    /// Compress a row stored before compression was enabled
+    async fn migrate_row(&self, cache_key: &str, translated_content: &str) -> Result<()> {
+        let (content, compression) = self.pack(translated_content)?;
+        sqlx::query(
+            "UPDATE translations SET translated_content = ?, compression = ? WHERE cache_key = ? AND compression = ''",
+        )
+        .bind(content)
+        .bind(compression)
+        .bind(cache_key)
+        .execute(&mut *self.writer.acquire().await?)
+        .await?;
+        Ok(())
+    }
+
+    /// The entry of a `translations` row, compressing it if it was stored plain.
+    /// Rows whose content cannot be read are deleted and treated as misses, so they are retranslated.
+    async fn read_row(&self, row: TranslationRow) -> Result<Option<CacheEntry>> {
+        let (cache_key, plain) = (row.cache_key.clone(), row.compression.is_empty());
+        let entry = match row.into_entry() {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::warn!("Dropping unreadable cache entry {}: {}", cache_key, e);
+                self.delete(&cache_key).await?;
+                return Ok(None);
+            }
+        };
+
+        if self.compress && plain {
+            if let Err(e) = self.migrate_row(&cache_key, &entry.translated_content).await {
+                tracing::warn!("Failed to compress cache entry {}: {}", cache_key, e);
+            }
+        }
+
+        Ok(Some(entry))
+    }
+}
+
+/// Migrations of the translations, segments and translation memory tables
+pub const SCHEMA: Schema = Schema {
+    component: "cache",
+    migrations: &[
+        Migration {
+            version: 1,
+            description: "Create translations and segments, upgrading databases from before migrations",
+            step: Step::Code(baseline),
+        },
+        Migration {
+            version: 2,
+            description: "Create translation memory",
+            step: Step::Sql(
+                r#"
+                CREATE TABLE translation_memory (
+                    tenant TEXT NOT NULL,
+                    source_language TEXT NOT NULL,
+                    target_language TEXT NOT NULL,
+                    normalized_hash TEXT NOT NULL,
+                    source_text TEXT NOT NULL,
+                    translated_text TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    accessed_at TEXT NOT NULL,
+                    PRIMARY KEY (tenant, source_language, target_language, normalized_hash)
+                );
+                CREATE INDEX idx_memory_accessed_at
+                    ON translation_memory(tenant, source_language, target_language, accessed_at);
+                CREATE INDEX idx_memory_created_at ON translation_memory(created_at);
+                "#,
+            ),
+        },
+        Migration {
+            version: 3,
+            description: "Record every path cached content was submitted under",
+            step: Step::Sql(
+                r#"
+                CREATE TABLE paths (
+                    tenant TEXT NOT NULL,
+                    content_hash TEXT NOT NULL,
+                    path TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    PRIMARY KEY (tenant, content_hash, path)
+                );
+                CREATE INDEX idx_paths_path ON paths(tenant, path);
+                INSERT INTO paths (tenant, content_hash, path, created_at)
+                    SELECT tenant, content_hash, path, MIN(created_at) FROM translations
+                    GROUP BY tenant, content_hash, path;
+                "#,
+            ),
+        },
+        Migration {
+            version: 4,
+            description: "Count hits and misses per tenant and hour",
+            step: Step::Sql(
+                r#"
+                CREATE TABLE lookup_stats (
+                    tenant TEXT NOT NULL,
+                    hour TEXT NOT NULL,
+                    hits INTEGER NOT NULL DEFAULT 0,
+                    misses INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (tenant, hour)
+                );
+                "#,
+            ),
+        },
+        Migration {
+            version: 5,
+            description: "Keep the upstream results of unfinished translations",
+            step: Step::Sql(
+                r#"
+                CREATE TABLE partial_translations (
+                    partial_key TEXT PRIMARY KEY,
+                    tenant TEXT NOT NULL,
+                    translated_text TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    accessed_at TEXT NOT NULL
+                );
+                CREATE INDEX idx_partials_created_at ON partial_translations(created_at);
+                CREATE INDEX idx_partials_tenant ON partial_translations(tenant);
+                "#,
+            ),
+        },
+    ],
+};
+
+/// Hours of `lookup_stats`, ordered like the times they name
+const HOUR_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+/// Delete the paths of content without cached translations, of one tenant or all when unbound
+const PRUNE_PATHS: &str = r#"
+    DELETE FROM paths
+    WHERE (?1 IS NULL OR tenant = ?1)
+      AND NOT EXISTS (
+          SELECT 1 FROM translations t
+          WHERE t.tenant = paths.tenant AND t.content_hash = paths.content_hash
+      )
+"#;
+
+/// Record the path content was submitted under
+const INSERT_PATH: &str =
+    "INSERT OR IGNORE INTO paths (tenant, content_hash, path, created_at) VALUES (?, ?, ?, ?)";
+
+/// Keys looked up per query by `get_many`, well below SQLite's limit on bound parameters
+const GET_MANY_CHUNK: usize = 500;
+
+fn baseline(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<()>> {
+    Box::pin(init_schema(conn))
+}
+
+/// Create the schema with optimized indexes, adding columns missing from older databases
+async fn init_schema(conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS translations (
+            cache_key TEXT PRIMARY KEY,
+            content_hash TEXT NOT NULL,
+            path TEXT NOT NULL,
+            translated_content TEXT NOT NULL,
+            translated_hash TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            accessed_at TEXT NOT NULL,
+            hit_count INTEGER DEFAULT 0,
+            metadata TEXT DEFAULT '{}',
+            source_language TEXT NOT NULL DEFAULT '',
+            target_language TEXT NOT NULL DEFAULT '',
+            model TEXT NOT NULL DEFAULT '',
+            path_prefix TEXT NOT NULL DEFAULT '',
+            tenant TEXT NOT NULL DEFAULT 'default',
+            compression TEXT NOT NULL DEFAULT ''
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Databases created before the grouping columns existed need them added and backfilled
+    add_grouping_columns(conn).await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_content_hash ON translations(content_hash)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_path ON translations(path)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_created_at ON translations(created_at)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_target_language ON translations(target_language)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_path_prefix ON translations(path_prefix)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_model ON translations(model)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Translated sections of documents, reused when only part of a file changes
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS segments (
+            segment_key TEXT PRIMARY KEY,
+            translated_text TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            accessed_at TEXT NOT NULL,
+            tenant TEXT NOT NULL DEFAULT 'default'
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_segments_accessed_at ON segments(accessed_at)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Entries written before tenants existed belong to the default tenant
+    for table in ["translations", "segments"] {
+        add_column(conn, table, "tenant", &format!("TEXT NOT NULL DEFAULT '{}'", DEFAULT_TENANT)).await?;
+    }
+
+    // Rows written before compression existed hold plain text
+    add_column(conn, "translations", "compression", "TEXT NOT NULL DEFAULT ''").await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_tenant ON translations(tenant)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_segments_tenant ON segments(tenant)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Add the language/model/path_prefix columns to an older table and backfill them
+async fn add_grouping_columns(conn: &mut SqliteConnection) -> Result<()> {
+    let columns: Vec<String> = sqlx::query("PRAGMA table_info(translations)")
+        .fetch_all(&mut *conn)
+        .await?
+        .iter()
+        .map(|row| row.get("name"))
+        .collect();
+
+    if columns.iter().any(|c| c == "path_prefix") {
+        return Ok(());
+    }
+
+    tracing::info!("Adding grouping columns to translations table");
+
+    for column in ["source_language", "target_language", "model", "path_prefix"] {
+        if !columns.iter().any(|c| c == column) {
+            sqlx::query(&format!(
+                "ALTER TABLE translations ADD COLUMN {} TEXT NOT NULL DEFAULT ''",
+                column
+            ))
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+
+    // Languages and model were previously only recorded in the metadata JSON
+    sqlx::query(
+        r#"
+        UPDATE translations SET
+            source_language = COALESCE(json_extract(metadata, '$.source_language'), ''),
+            target_language = COALESCE(json_extract(metadata, '$.target_language'), ''),
+            model = COALESCE(json_extract(metadata, '$.model'), '')
+        WHERE json_valid(metadata)
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let rows = sqlx::query("SELECT cache_key, path FROM translations")
+        .fetch_all(&mut *conn)
+        .await?;
+    for row in rows {
+        let cache_key: String = row.get("cache_key");
+        let path: String = row.get("path");
+        sqlx::query("UPDATE translations SET path_prefix = ? WHERE cache_key = ?")
+            .bind(path_prefix(&path))
+            .bind(&cache_key)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Add a column to an older table
+async fn add_column(conn: &mut SqliteConnection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists = sqlx::query(&format!("PRAGMA table_info({})", table))
+        .fetch_all(&mut *conn)
+        .await?
+        .iter()
+        .any(|row| row.get::<String, _>("name") == column);
+
+    if !exists {
+        tracing::info!("Adding {} column to {} table", column, table);
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl CacheBackend for SqliteBackend {
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn get(&self, cache_key: &str) -> Result<Option<CacheEntry>> {
+        let row = sqlx::query_as!(
+            TranslationRow,
+            r#"
+            SELECT cache_key AS "cache_key!", content_hash, path,
+                   translated_content AS "translated_content: Vec<u8>", translated_hash,
+                   created_at, accessed_at, hit_count AS "hit_count!", metadata AS "metadata!",
+                   compression
+            FROM translations WHERE cache_key = ?
+            "#,
+            cache_key
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => self.read_row(row).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn get_many(&self, cache_keys: &[String]) -> Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        for chunk in cache_keys.chunks(GET_MANY_CHUNK) {
+            let sql = format!(
+                "SELECT * FROM translations WHERE cache_key IN ({})",
+                vec!["?"; chunk.len()].join(", ")
+            );
+            let mut query = sqlx::query_as::<_, TranslationRow>(&sql);
+            for cache_key in chunk {
+                query = query.bind(cache_key);
+            }
+            for row in query.fetch_all(&self.pool).await? {
+                entries.extend(self.read_row(row).await?);
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn delete(&self, cache_key: &str) -> Result<()> {
+        sqlx::query!("DELETE FROM translations WHERE cache_key = ?", cache_key)
+            .execute(&mut *self.writer.acquire().await?)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_segments(&self, segment_keys: &[String]) -> Result<HashMap<String, String>> {
+        let mut writer = self.writer.acquire().await?;
+        let now = Utc::now().to_rfc3339();
+        let mut found = HashMap::new();
+
+        for segment_key in segment_keys {
+            let row = sqlx::query(
+                "UPDATE segments SET accessed_at = ? WHERE segment_key = ? RETURNING translated_text",
+            )
+            .bind(&now)
+            .bind(segment_key)
+            .fetch_optional(&mut *writer)
+            .await?;
+
+            if let Some(row) = row {
+                found.insert(segment_key.clone(), row.get("translated_text"));
+            }
+        }
+
+        Ok(found)
+    }
+
+    async fn set_segments(&self, tenant: &str, segments: &[(String, String)]) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        let mut writer = self.writer.acquire().await?;
+        let mut tx = writer.begin().await?;
+
+        for (segment_key, translated_text) in segments {
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO segments (segment_key, translated_text, created_at, accessed_at, tenant)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(segment_key)
+            .bind(translated_text)
+            .bind(&now)
+            .bind(&now)
+            .bind(tenant)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_partial(&self, partial_key: &str) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT translated_text FROM partial_translations WHERE partial_key = ?")
+            .bind(partial_key)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|row| row.get("translated_text")))
+    }
+
+    async fn set_partial(&self, tenant: &str, partial_key: &str, translated_text: &str) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO partial_translations (partial_key, tenant, translated_text, created_at, accessed_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(partial_key)
+        .bind(tenant)
+        .bind(translated_text)
+        .bind(&now)
+        .bind(&now)
+        .execute(&mut *self.writer.acquire().await?)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_partials(&self, partial_keys: &[String]) -> Result<()> {
+        let mut writer = self.writer.acquire().await?;
+        let mut tx = writer.begin().await?;
+        for partial_key in partial_keys {
+            sqlx::query("DELETE FROM partial_translations WHERE partial_key = ?")
+                .bind(partial_key)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_translation_memory(&self, scope: &MemoryScope<'_>, hashes: &[String]) -> Result<HashMap<String, String>> {
+        let mut writer = self.writer.acquire().await?;
+        let now = Utc::now().to_rfc3339();
+        let mut found = HashMap::new();
+
+        for hash in hashes {
+            let row = sqlx::query(
+                r#"
+                UPDATE translation_memory SET accessed_at = ?
+                WHERE tenant = ? AND source_language = ? AND target_language = ? AND normalized_hash = ?
+                RETURNING translated_text
+                "#,
+            )
+            .bind(&now)
+            .bind(scope.tenant)
+            .bind(scope.source_language)
+            .bind(scope.target_language)
+            .bind(hash)
+            .fetch_optional(&mut *writer)
+            .await?;
+
+            if let Some(row) = row {
+                found.insert(hash.clone(), row.get("translated_text"));
+            }
+        }
+
+        Ok(found)
+    }
+
+    async fn translation_memory_candidates(&self, scope: &MemoryScope<'_>, limit: i64) -> Result<Vec<MemoryPair>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT normalized_hash, source_text, translated_text FROM translation_memory
+            WHERE tenant = ? AND source_language = ? AND target_language = ?
+            ORDER BY accessed_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(scope.tenant)
+        .bind(scope.source_language)
+        .bind(scope.target_language)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| MemoryPair {
+                normalized_hash: row.get("normalized_hash"),
+                source: row.get("source_text"),
+                translation: row.get("translated_text"),
+            })
+            .collect())
+    }
+
+    async fn set_translation_memory(&self, scope: &MemoryScope<'_>, pairs: &[MemoryPair]) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        let mut writer = self.writer.acquire().await?;
+        let mut tx = writer.begin().await?;
+
+        for pair in pairs {
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO translation_memory
+                    (tenant, source_language, target_language, normalized_hash, source_text, translated_text, created_at, accessed_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(scope.tenant)
+            .bind(scope.source_language)
+            .bind(scope.target_language)
+            .bind(&pair.normalized_hash)
+            .bind(&pair.source)
+            .bind(&pair.translation)
+            .bind(&now)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn find(&self, tenant: &str, column: &'static str, value: &str) -> Result<Vec<CacheEntry>> {
+        let rows = sqlx::query_as::<_, TranslationRow>(&format!(
+            "SELECT * FROM translations WHERE tenant = ? AND {} = ? ORDER BY created_at DESC",
+            column
+        ))
+        .bind(tenant)
+        .bind(value)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(TranslationRow::into_entry).collect()
+    }
+
+    async fn delete_matching(&self, tenant: &str, column: &'static str, value: &str) -> Result<i64> {
+        let mut writer = self.writer.acquire().await?;
+        let result = sqlx::query(&format!(
+            "DELETE FROM translations WHERE tenant = ? AND {} = ?",
+            column
+        ))
+        .bind(tenant)
+        .bind(value)
+        .execute(&mut *writer)
+        .await?;
+
+        sqlx::query(PRUNE_PATHS)
+            .bind(tenant)
+            .execute(&mut *writer)
+            .await?;
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn list(&self, tenant: &str, filter: &CacheFilter) -> Result<(Vec<CacheEntrySummary>, i64)> {
+        let matching = r#"
+            tenant = ?1
+            AND (?2 IS NULL OR substr(path, 1, length(?2)) = ?2)
+            AND (?3 IS NULL OR target_language = ?3)
+        "#;
+        let order = match filter.order {
+            CacheOrder::Newest => "created_at DESC",
+            CacheOrder::Oldest => "created_at ASC",
+            CacheOrder::Hits => "hit_count DESC, created_at DESC",
+            CacheOrder::Size => "size_bytes DESC, created_at DESC",
+            CacheOrder::Accessed => "accessed_at DESC",
+        };
+
+        let rows = sqlx::query_as::<_, SummaryRow>(&format!(
+            r#"
+            SELECT cache_key, content_hash, path, translated_hash, source_language, target_language, model,
+                   LENGTH(translated_content) AS size_bytes, created_at, accessed_at, hit_count, metadata
+            FROM translations
+            WHERE {matching}
+            ORDER BY {order}
+            LIMIT ?4 OFFSET ?5
+            "#
+        ))
+        .bind(tenant)
+        .bind(&filter.path_prefix)
+        .bind(&filter.target_language)
+        .bind(filter.limit)
+        .bind(filter.offset)
+        .fetch_all(&self.pool)
+        .await?;
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM translations WHERE {matching}"))
+            .bind(tenant)
+            .bind(&filter.path_prefix)
+            .bind(&filter.target_language)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((rows.into_iter().map(CacheEntrySummary::from).collect(), total))
+    }
+
+    async fn add_path(&self, tenant: &str, content_hash: &str, path: &str) -> Result<()> {
+        sqlx::query(INSERT_PATH)
+            .bind(tenant)
+            .bind(content_hash)
+            .bind(path)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut *self.writer.acquire().await?)
+            .await?;
+        Ok(())
+    }
+
+    async fn path_hashes(&self, tenant: &str, path: &str) -> Result<Vec<String>> {
+        Ok(sqlx::query_scalar("SELECT content_hash FROM paths WHERE tenant = ? AND path = ?")
+            .bind(tenant)
+            .bind(path)
+            .fetch_all(&self.pool)
+            .await?)
+    }
+
+    async fn replace_translation(
+        &self,
+        cache_key: &str,
+        translated_content: &str,
+        translated_hash: &str,
+    ) -> Result<bool> {
+        let (content, compression) = self.pack(translated_content)?;
+        let result = sqlx::query(
+            "UPDATE translations SET translated_content = ?, compression = ?, translated_hash = ? WHERE cache_key = ?",
+        )
+        .bind(content)
+        .bind(compression)
+        .bind(translated_hash)
+        .bind(cache_key)
+        .execute(&mut *self.writer.acquire().await?)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn set_many(&self, stored: &[StoredEntry]) -> Result<()> {
+        let mut writer = self.writer.acquire().await?;
+        let mut tx = writer.begin().await?;
+
+        for stored in stored {
+            let entry = &stored.entry;
+            let now_str = entry.created_at.to_rfc3339();
+            let metadata_json = serde_json::to_string(&entry.metadata)
+                .unwrap_or_else(|_| "{}".to_string());
+            let (content, compression) = self.pack(&entry.translated_content)?;
+
+            sqlx::query!(
+                r#"
+                INSERT OR REPLACE INTO translations
+                (cache_key, content_hash, path, translated_content, translated_hash,
+                 created_at, accessed_at, hit_count, metadata,
+                 source_language, target_language, model, path_prefix, tenant, compression)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, 0, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+                "#,
+                entry.cache_key,
+                entry.content_hash,
+                entry.path,
+                content,
+                entry.translated_hash,
+                now_str,
+                metadata_json,
+                stored.source_language,
+                stored.target_language,
+                stored.model,
+                stored.path_prefix,
+                stored.tenant,
+                compression
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(INSERT_PATH)
+                .bind(&stored.tenant)
+                .bind(&entry.content_hash)
+                .bind(&entry.path)
+                .bind(&now_str)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn add_hits(&self, hits: &HashMap<String, i64>) -> Result<()> {
+        let mut writer = self.writer.acquire().await?;
+        let now = Utc::now().to_rfc3339();
+
+        for (cache_key, count) in hits {
+            sqlx::query!(
+                "UPDATE translations SET accessed_at = ?, hit_count = hit_count + ? WHERE cache_key = ?",
+                now,
+                count,
+                cache_key
+            )
+            .execute(&mut *writer)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn add_lookups(&self, lookups: &LookupBuckets) -> Result<()> {
+        let mut writer = self.writer.acquire().await?;
+        for ((tenant, hour), totals) in lookups {
+            sqlx::query(
+                r#"
+                INSERT INTO lookup_stats (tenant, hour, hits, misses) VALUES (?, ?, ?, ?)
+                ON CONFLICT(tenant, hour) DO UPDATE SET
+                    hits = hits + excluded.hits,
+                    misses = misses + excluded.misses
+                "#,
+            )
+            .bind(tenant)
+            .bind(hour.format(HOUR_FORMAT).to_string())
+            .bind(totals.hits)
+            .bind(totals.misses)
+            .execute(&mut *writer)
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn lookup_totals(&self, tenant: &str, since: Option<DateTime<Utc>>) -> Result<LookupTotals> {
+        let row = sqlx::query(
+            r#"
+            SELECT COALESCE(SUM(hits), 0) AS hits, COALESCE(SUM(misses), 0) AS misses
+            FROM lookup_stats
+            WHERE tenant = ?1 AND (?2 IS NULL OR hour >= ?2)
+            "#,
+        )
+        .bind(tenant)
+        .bind(since.map(|since| since.format(HOUR_FORMAT).to_string()))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(LookupTotals {
+            hits: row.get("hits"),
+            misses: row.get("misses"),
+        })
+    }
+
+    async fn clear_before(&self, column: &'static str, cutoff: DateTime<Utc>) -> Result<i64> {
+        let mut writer = self.writer.acquire().await?;
+        let cutoff = cutoff.to_rfc3339();
+
+        let result = sqlx::query(&format!("DELETE FROM translations WHERE {} < ?", column))
+            .bind(&cutoff)
+            .execute(&mut *writer)
+            .await?;
+
+        for table in ["segments", "translation_memory", "partial_translations"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE {} < ?", table, column))
+                .bind(&cutoff)
+                .execute(&mut *writer)
+                .await?;
+        }
+        sqlx::query(PRUNE_PATHS)
+            .bind(None::<&str>)
+            .execute(&mut *writer)
+            .await?;
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn mark_stale_versions(&self, current_version: &str) -> Result<i64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE translations
+            SET metadata = json_set(metadata, '$.stale_version', json('true'))
+            WHERE json_valid(metadata)
+              AND json_extract(metadata, '$.translator_version') != ?
+              AND COALESCE(json_extract(metadata, '$.stale_version'), 0) = 0
+            "#,
+        )
+        .bind(current_version)
+        .execute(&mut *self.writer.acquire().await?)
+        .await?;
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn delete_stale_versions(&self, current_version: &str) -> Result<i64> {
+        let mut writer = self.writer.acquire().await?;
+        let result = sqlx::query(
+            r#"
+            DELETE FROM translations
+            WHERE json_valid(metadata) AND json_extract(metadata, '$.translator_version') != ?
+            "#,
+        )
+        .bind(current_version)
+        .execute(&mut *writer)
+        .await?;
+
+        sqlx::query(PRUNE_PATHS)
+            .bind(None::<&str>)
+            .execute(&mut *writer)
+            .await?;
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn clear_tenant(&self, tenant: &str) -> Result<i64> {
+        let mut writer = self.writer.acquire().await?;
+        let result = sqlx::query("DELETE FROM translations WHERE tenant = ?")
+            .bind(tenant)
+            .execute(&mut *writer)
+            .await?;
+
+        for table in ["segments", "translation_memory", "partial_translations", "paths"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE tenant = ?", table))
+                .bind(tenant)
+                .execute(&mut *writer)
+                .await?;
+        }
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn clear_all(&self) -> Result<i64> {
+        let mut writer = self.writer.acquire().await?;
+        let result = sqlx::query("DELETE FROM translations")
+            .execute(&mut *writer)
+            .await?;
+
+        for table in ["segments", "translation_memory", "partial_translations", "paths"] {
+            sqlx::query(&format!("DELETE FROM {}", table))
+                .execute(&mut *writer)
+                .await?;
+        }
+
+        Ok(result.rows_affected() as i64)
+    }
+
+    async fn stats(&self, tenant: &str) -> Result<CacheStats> {
+        let row = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "count!: i64",
+                   SUM(LENGTH(translated_content)) AS "size: i64",
+                   MIN(created_at) AS "oldest: String",
+                   MAX(created_at) AS "newest: String"
+            FROM translations
+            WHERE tenant = ?
+            "#,
+            tenant
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        let paths = sqlx::query!(
+            r#"
+            SELECT COUNT(DISTINCT path) AS "paths!: i64",
+                   COUNT(*) - COUNT(DISTINCT content_hash) AS "deduplicated!: i64"
+            FROM paths
+            WHERE tenant = ?
+            "#,
+            tenant
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let parse_time = |value: Option<String>| {
+            value
+                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&Utc))
+        };
+
+        Ok(CacheStats {
+            total_entries: row.count,
+            total_size_bytes: row.size.unwrap_or(0),
+            oldest_entry: parse_time(row.oldest),
+            newest_entry: parse_time(row.newest),
+            total_paths: paths.paths,
+            deduplicated_paths: paths.deduplicated,
+            ..CacheStats::default()
+        })
+    }
+
+    async fn group_stats(&self, column: &'static str, tenant: Option<&str>) -> Result<Vec<CacheGroupStats>> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT {column} AS grp,
+                   COUNT(*) AS entries,
+                   COALESCE(SUM(LENGTH(translated_content)), 0) AS size,
+                   COALESCE(SUM(hit_count), 0) AS hits
+            FROM translations
+            WHERE ?1 IS NULL OR tenant = ?1
+            GROUP BY {column}
+            ORDER BY entries DESC
+            "#
+        ))
+        .bind(tenant)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| CacheGroupStats::new(row.get("grp"), row.get("entries"), row.get("size"), row.get("hits")))
+            .collect())
+    }
+
+    async fn translated_hashes(&self) -> Result<HashSet<String>> {
+        let rows = sqlx::query("SELECT DISTINCT translated_hash FROM translations")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.iter().map(|row| row.get("translated_hash")).collect())
+    }
+
+    async fn close(&self) -> Result<()> {
+        // Checkpoint WAL file to main database
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&self.pool)
+            .await?;
+        tracing::debug!("Checkpointed WAL file");
+        Ok(())
+    }
+}
+
+/// Parse a timestamp column, falling back to now for unreadable values
+fn parse_time(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+/// A row of the translations table, its content still compressed
+#[derive(sqlx::FromRow)]
+struct TranslationRow {
+    cache_key: String,
+    content_hash: String,
+    path: String,
+    translated_content: Vec<u8>,
+    translated_hash: String,
+    created_at: String,
+    accessed_at: String,
+    hit_count: i64,
+    metadata: String,
+    compression: String,
+}
+
+impl TranslationRow {
+    /// The cache entry of the row, decompressing its content
+    fn into_entry(self) -> Result<CacheEntry> {
+        let content = match self.compression.as_str() {
+            "" => self.translated_content,
+            ZSTD => zstd::decode_all(self.translated_content.as_slice())
+                .map_err(|e| Error::Internal(format!("Failed to decompress cached translation: {}", e)))?,
+            other => {
+                return Err(Error::Internal(format!("Unknown cache compression '{}'", other)));
+            }
+        };
+        let translated_content = String::from_utf8(content)
+            .map_err(|e| Error::Internal(format!("Cached translation is not UTF-8: {}", e)))?;
+
+        Ok(CacheEntry {
+            cache_key: self.cache_key,
+            content_hash: self.content_hash,
+            path: self.path,
+            translated_content,
+            translated_hash: self.translated_hash,
+            created_at: parse_time(&self.created_at),
+            accessed_at: parse_time(&self.accessed_at),
+            hit_count: self.hit_count,
+            metadata: serde_json::from_str(&self.metadata).unwrap_or(serde_json::json!({})),
+        })
+    }
+}
+
+/// A row of [`SqliteBackend::list`]
+#[derive(sqlx::FromRow)]
+struct SummaryRow {
+    cache_key: String,
+    content_hash: String,
+    path: String,
+    translated_hash: String,
+    source_language: String,
+    target_language: String,
+    model: String,
+    size_bytes: Option<i64>,
+    created_at: String,
+    accessed_at: String,
+    hit_count: i64,
+    metadata: String,
+}
+
+impl From<SummaryRow> for CacheEntrySummary {
+    fn from(row: SummaryRow) -> Self {
+        CacheEntrySummary {
+            cache_key: row.cache_key,
+            content_hash: row.content_hash,
+            path: row.path,
+            translated_hash: row.translated_hash,
+            source_language: row.source_language,
+            target_language: row.target_language,
+            model: row.model,
+            size_bytes: row.size_bytes.unwrap_or(0),
+            created_at: parse_time(&row.created_at),
+            accessed_at: parse_time(&row.accessed_at),
+            hit_count: row.hit_count,
+            metadata: serde_json::from_str(&row.metadata).unwrap_or(serde_json::json!({})),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_plain_rows_are_compressed_on_read() {
+        let dir = std::env::temp_dir().join(format!("skillts-sqlite-{}", std::process::id()));
+        let pool = connect(&dir.join("cache.db").to_string_lossy(), &SqliteTuning::default()).await.unwrap();
+        let backend = SqliteBackend::new(pool.clone(), true).await.unwrap();
+
+        // A row written before compression existed
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT INTO translations (cache_key, content_hash, path, translated_content, translated_hash, created_at, accessed_at) VALUES ('old', 'h', 'SKILL.md', '旧译文', 't', ?, ?)",
+        )
+        .bind(&now)
+        .bind(&now)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(backend.get("old").await.unwrap().unwrap().translated_content, "旧译文");
+        let compression: String = sqlx::query_scalar("SELECT compression FROM translations WHERE cache_key = 'old'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(compression, ZSTD);
+        assert_eq!(backend.get("old").await.unwrap().unwrap().translated_content, "旧译文");
+
+        assert!(backend.replace_translation("old", "新译文", "t2").await.unwrap());
+        assert_eq!(backend.find("default", "path", "SKILL.md").await.unwrap()[0].translated_content, "新译文");
+
+        pool.close().await;
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_compact_reclaims_deleted_rows() {
+        let dir = std::env::temp_dir().join(format!("skillts-compact-{}", std::process::id()));
+        let db_path = dir.join("cache.db").to_string_lossy().into_owned();
+        let pool = connect(&db_path, &SqliteTuning::default()).await.unwrap();
+
+        sqlx::query("CREATE TABLE blobs (body BLOB NOT NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        for _ in 0..64 {
+            sqlx::query("INSERT INTO blobs (body) VALUES (zeroblob(16384))")
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        sqlx::query("DELETE FROM blobs").execute(&pool).await.unwrap();
+
+        let stats = compact(&pool, &db_path).await.unwrap();
+        assert!(stats.size_after_bytes < stats.size_before_bytes);
+        assert_eq!(std::fs::metadata(format!("{}-wal", db_path)).map(|m| m.len()).unwrap_or(0), 0);
+
+        pool.close().await;
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_tuning_applies_to_every_connection() {
+        let dir = std::env::temp_dir().join(format!("skillts-tuning-{}", std::process::id()));
+        let tuning = SqliteTuning {
+            max_connections: 2,
+            cache_size_kib: 4000,
+            busy_timeout_ms: 2500,
+            wal_autocheckpoint_pages: 400,
+        };
+        let pool = connect(&dir.join("cache.db").to_string_lossy(), &tuning).await.unwrap();
+
+        let mut first = pool.acquire().await.unwrap();
+        let mut second = pool.acquire().await.unwrap();
+        for conn in [&mut first, &mut second] {
+            let cache_size: i64 = sqlx::query_scalar("PRAGMA cache_size").fetch_one(&mut **conn).await.unwrap();
+            let busy_timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout").fetch_one(&mut **conn).await.unwrap();
+            let checkpoint: i64 = sqlx::query_scalar("PRAGMA wal_autocheckpoint").fetch_one(&mut **conn).await.unwrap();
+            assert_eq!((cache_size, busy_timeout, checkpoint), (-4000, 2500, 400));
+        }
+
+        drop((first, second));
+        pool.close().await;
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_writes_wait_for_the_writer() {
+        let dir = std::env::temp_dir().join(format!("skillts-writer-{}", std::process::id()));
+        let pool = connect(&dir.join("cache.db").to_string_lossy(), &SqliteTuning::default()).await.unwrap();
+        let backend = SqliteBackend::new(pool.clone(), true).await.unwrap();
+
+        // A long write holds the writer connection
+        let mut lease = backend.writer.acquire().await.unwrap();
+        sqlx::query("BEGIN IMMEDIATE").execute(&mut *lease).await.unwrap();
+
+        // Reads are not blocked, and other writes queue instead of failing with SQLITE_BUSY
+        assert!(backend.path_hashes("default", "SKILL.md").await.unwrap().is_empty());
+        let mut write = std::pin::pin!(backend.add_path("default", "sha256:a", "SKILL.md"));
+        assert!(tokio::time::timeout(std::time::Duration::from_millis(100), &mut write).await.is_err());
+
+        sqlx::query("COMMIT").execute(&mut *lease).await.unwrap();
+        drop(lease);
+        write.await.unwrap();
+        assert_eq!(backend.path_hashes("default", "SKILL.md").await.unwrap(), vec!["sha256:a"]);
+
+        pool.close().await;
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
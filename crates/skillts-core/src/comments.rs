@@ -0,0 +1,290 @@
+//! Comment extraction for translating comments inside code blocks.
+//!
+//! A small per-language lexer skips string literals and reports the text of
+//! line comments, block comments and Python docstrings, one range per line and
+//! without comment markers. Only those ranges are ever replaced, so code is left
+//! untouched. Languages without a known comment syntax yield no comments.
+
+use std::ops::Range;
+
+use crate::language::language_name;
+
+/// System prompt for comment lines; `{source}` and `{target}` are replaced by language names
+const COMMENT_PROMPT_TEMPLATE: &str = r#"You are a professional technical translator.
+Translate code comments from {source} to {target}.
+
+IMPORTANT RULES:
+1. Every input line starts with a marker like [3]; keep the marker and translate the text after it
+2. Output exactly one line per input line, in the same order
+3. Keep identifiers, commands, file paths and URLs unchanged
+4. Output only the translated lines"#;
+
+/// Comment syntax of a language
+struct Style {
+    line: &'static [u8],
+    block: Option<(&'static [u8], &'static [u8])>,
+    /// String delimiters, longest first
+    quotes: &'static [&'static [u8]],
+    /// Triple-quoted strings opening a line are docstrings
+    docstrings: bool,
+}
+
+const HASH: Style = Style {
+    line: b"#",
+    block: None,
+    quotes: &[b"\"", b"'"],
+    docstrings: false,
+};
+
+const PYTHON: Style = Style {
+    line: b"#",
+    block: None,
+    quotes: &[b"\"\"\"", b"'''", b"\"", b"'"],
+    docstrings: true,
+};
+
+const JAVASCRIPT: Style = Style {
+    line: b"//",
+    block: Some((b"/*", b"*/")),
+    quotes: &[b"\"", b"'", b"`"],
+    docstrings: false,
+};
+
+/// C-family languages; `'` is left out since Rust lifetimes leave it unbalanced
+const C_LIKE: Style = Style {
+    line: b"//",
+    block: Some((b"/*", b"*/")),
+    quotes: &[b"\""],
+    docstrings: false,
+};
+
+fn style(language: &str) -> Option<&'static Style> {
+    match language.to_ascii_lowercase().as_str() {
+        "sh" | "bash" | "shell" | "zsh" | "yaml" | "yml" | "toml" | "ruby" | "rb" | "dockerfile" => {
+            Some(&HASH)
+        }
+        "python" | "py" | "python3" => Some(&PYTHON),
+        "js" | "javascript" | "jsx" | "mjs" | "cjs" | "ts" | "typescript" | "tsx" => {
+            Some(&JAVASCRIPT)
+        }
+        "c" | "h" | "cpp" | "c++" | "java" | "go" | "rust" | "rs" | "kotlin" | "swift" | "cs"
+        | "csharp" => Some(&C_LIKE),
+        _ => None,
+    }
+}
+
+/// System prompt for translating numbered comment lines
+pub fn comment_prompt(source_language: &str, target_language: &str) -> String {
+    COMMENT_PROMPT_TEMPLATE
+        .replace("{source}", &language_name(source_language))
+        .replace("{target}", &language_name(target_language))
+}
+
+/// Byte ranges of comment text in `code`, one per non-empty line, without
+/// comment markers or surrounding whitespace
+pub fn find_comments(language: &str, code: &str) -> Vec<Range<usize>> {
+    let Some(style) = style(language) else {
+        return Vec::new();
+    };
+    let bytes = code.as_bytes();
+    let mut comments = Vec::new();
+    let mut quote: Option<&[u8]> = None;
+    let mut line_start = true;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+
+        if let Some(q) = quote {
+            if rest[0] == b'\\' {
+                i += 2;
+            } else if rest.starts_with(q) {
+                quote = None;
+                i += q.len();
+            } else {
+                // Single-quoted strings end at the line end, even when unterminated
+                if rest[0] == b'\n' && q.len() == 1 && q != b"`" {
+                    quote = None;
+                    line_start = true;
+                }
+                i += 1;
+            }
+            continue;
+        }
+
+        if rest[0] == b'\n' {
+            line_start = true;
+            i += 1;
+            continue;
+        }
+
+        let after_space = line_start || bytes[i - 1].is_ascii_whitespace();
+        if rest.starts_with(style.line) && (style.line != b"#" || after_space) {
+            let end = line_end(bytes, i);
+            // A shebang is an interpreter directive, not a comment
+            if !(i == 0 && rest.starts_with(b"#!")) {
+                push_lines(code, i + style.line.len(), end, false, &mut comments);
+            }
+            i = end;
+            continue;
+        }
+
+        if let Some((open, close)) = style.block.filter(|(open, _)| rest.starts_with(open)) {
+            let start = i + open.len();
+            let (text_end, end) = find_close(bytes, start, close);
+            push_lines(code, start, text_end, true, &mut comments);
+            i = end;
+            line_start = false;
+            continue;
+        }
+
+        if style.docstrings && line_start {
+            if let Some(q) = [b"\"\"\"", b"'''"].into_iter().find(|q| rest.starts_with(*q)) {
+                let start = i + q.len();
+                let (text_end, end) = find_close(bytes, start, q);
+                push_lines(code, start, text_end, false, &mut comments);
+                i = end;
+                line_start = false;
+                continue;
+            }
+        }
+
+        if let Some(q) = style.quotes.iter().find(|q| rest.starts_with(q)) {
+            quote = Some(q);
+            i += q.len();
+            line_start = false;
+            continue;
+        }
+
+        if !rest[0].is_ascii_whitespace() {
+            line_start = false;
+        }
+        i += 1;
+    }
+
+    comments
+}
+
+/// Comment ranges of a code block, mapped from its `code` text into its markdown `source`.
+///
+/// `source` is the block as written, fences and container prefixes included; each
+/// code line must end a source line, otherwise no comments are reported.
+pub fn find_block_comments(language: &str, code: &str, source: &str) -> Vec<Range<usize>> {
+    let comments = find_comments(language, code);
+    if comments.is_empty() {
+        return comments;
+    }
+
+    let line_starts = |text: &str| -> Vec<(usize, usize)> {
+        let mut offset = 0;
+        text.split_inclusive('\n')
+            .map(|line| {
+                let start = offset;
+                offset += line.len();
+                (start, line.trim_end_matches(['\n', '\r']).len())
+            })
+            .collect()
+    };
+    let code_lines = line_starts(code);
+    let source_lines = line_starts(source);
+
+    // Fenced blocks have an opening fence line before the code, indented ones do not
+    let aligned = |skip: usize| {
+        code_lines.len() + skip <= source_lines.len()
+            && code_lines.iter().zip(&source_lines[skip..]).all(|(&(cs, cl), &(ss, sl))| {
+                sl >= cl && source[ss..ss + sl].ends_with(&code[cs..cs + cl])
+            })
+    };
+    let Some(skip) = (0..2).find(|&skip| aligned(skip)) else {
+        return Vec::new();
+    };
+
+    comments
+        .into_iter()
+        .map(|range| {
+            let line = code_lines.partition_point(|&(start, _)| start <= range.start) - 1;
+            let (code_start, code_len) = code_lines[line];
+            let (source_start, source_len) = source_lines[line + skip];
+            let start = source_start + source_len - code_len + (range.start - code_start);
+            start..start + range.len()
+        })
+        .collect()
+}
+
+/// Index of the newline ending the line at `i`, or the end of the text
+fn line_end(bytes: &[u8], i: usize) -> usize {
+    bytes[i..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |p| i + p)
+}
+
+/// End of the text before `close` and the index after it; unclosed runs to the end
+fn find_close(bytes: &[u8], start: usize, close: &[u8]) -> (usize, usize) {
+    bytes[start..]
+        .windows(close.len())
+        .position(|w| w == close)
+        .map_or((bytes.len(), bytes.len()), |p| (start + p, start + p + close.len()))
+}
+
+/// Push the trimmed text of each line in `start..end`.
+/// Block comment lines also lose their leading `*` decoration.
+fn push_lines(code: &str, start: usize, end: usize, strip_stars: bool, out: &mut Vec<Range<usize>>) {
+    for line in code[start..end].split_inclusive('\n') {
+        let mut text = line.trim_start();
+        if strip_stars {
+            text = text.trim_start_matches('*').trim_start();
+        }
+        let text = text.trim_end();
+        if text.chars().any(char::is_alphabetic) {
+            // `text` is a slice of `code`, so its position gives the range
+            let offset = text.as_ptr() as usize - code.as_ptr() as usize;
+            out.push(offset..offset + text.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comments<'a>(language: &str, code: &'a str) -> Vec<&'a str> {
+        find_comments(language, code)
+            .into_iter()
+            .map(|range| &code[range])
+            .collect()
+    }
+
+    #[test]
+    fn test_find_shell_comments() {
+        let code = "#!/bin/bash\n# Install deps\nnpm install # quietly\necho \"# not a comment\" a#b ${#x}\n";
+        assert_eq!(comments("bash", code), vec!["Install deps", "quietly"]);
+    }
+
+    #[test]
+    fn test_find_python_comments_and_docstrings() {
+        let code = "def f():\n    \"\"\"Return the answer.\n\n    Always 42.\n    \"\"\"\n    s = '#x'  # the value\n    t = \"\"\"# kept\"\"\"\n    return 42\n";
+        assert_eq!(
+            comments("python", code),
+            vec!["Return the answer.", "Always 42.", "the value"]
+        );
+    }
+
+    #[test]
+    fn test_find_javascript_comments() {
+        let code = "/**\n * Fetch a page.\n */\nconst url = \"http://x\"; // the endpoint\nconst t = `// template\n`;\n";
+        assert_eq!(comments("js", code), vec!["Fetch a page.", "the endpoint"]);
+        assert!(comments("text", code).is_empty());
+    }
+
+    #[test]
+    fn test_find_block_comments_in_source() {
+        let source = "> ```python\n> x = 1  # one\n> ```";
+        let ranges = find_block_comments("python", "x = 1  # one\n", source);
+        assert_eq!(ranges.iter().map(|r| &source[r.clone()]).collect::<Vec<_>>(), vec!["one"]);
+
+        let indented = "    # two\n    y = 2";
+        let ranges = find_block_comments("sh", "# two\ny = 2\n", indented);
+        assert_eq!(&indented[ranges[0].clone()], "two");
+    }
+}
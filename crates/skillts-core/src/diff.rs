@@ -0,0 +1,169 @@
+//! Review artifacts comparing a translation with its original.
+//!
+//! A document and its translation are split into segments, the frontmatter and each
+//! top-level block of the body lined up with the block it translates, and rendered
+//! either as a self-contained HTML page with the two side by side or as a unified
+//! diff, so a reviewer can audit a translation without external tooling. When the
+//! blocks of the two bodies do not line up, each body forms a single segment.
+
+use serde::{Deserialize, Serialize};
+
+use crate::bilingual;
+use crate::parser::ContentParser;
+
+/// Rendering of a review artifact
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum DiffFormat {
+    /// An HTML page with the original and the translation side by side
+    #[default]
+    Html,
+    /// A unified diff with the original as removed and the translation as added lines
+    Unified,
+}
+
+impl DiffFormat {
+    /// Content type of the rendered artifact
+    pub fn content_type(self) -> &'static str {
+        match self {
+            DiffFormat::Html => "text/html; charset=utf-8",
+            DiffFormat::Unified => "text/x-diff; charset=utf-8",
+        }
+    }
+}
+
+/// A part of the original and the part of the translation rendering it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment<'a> {
+    pub original: &'a str,
+    pub translated: &'a str,
+}
+
+impl Segment<'_> {
+    /// Whether the translation left the segment as it was, like code blocks
+    pub fn unchanged(&self) -> bool {
+        self.original == self.translated
+    }
+}
+
+/// Lined-up segments of a document and its translation
+pub fn segments<'a>(original: &'a str, translated: &'a str) -> Vec<Segment<'a>> {
+    let (frontmatter, body) = split(original);
+    let (translated_frontmatter, translated_body) = split(translated);
+
+    let mut segments = Vec::new();
+    if !frontmatter.is_empty() || !translated_frontmatter.is_empty() {
+        segments.push(Segment {
+            original: frontmatter.trim_end(),
+            translated: translated_frontmatter.trim_end(),
+        });
+    }
+    match bilingual::align(body, translated_body) {
+        Some(blocks) => segments.extend(blocks.into_iter().map(|(_, original, translated)| Segment {
+            original: &body[original],
+            translated: &translated_body[translated],
+        })),
+        None => segments.push(Segment {
+            original: body.trim(),
+            translated: translated_body.trim(),
+        }),
+    }
+    segments
+}
+
+/// Review artifact of a translation of the document at `path` into `target_language`
+pub fn render(format: DiffFormat, path: &str, target_language: &str, original: &str, translated: &str) -> String {
+    let segments = segments(original, translated);
+    match format {
+        DiffFormat::Html => html(path, target_language, &segments),
+        DiffFormat::Unified => unified(path, target_language, &segments),
+    }
+}
+
+/// Frontmatter block, including its delimiters, and body of a document
+fn split(content: &str) -> (&str, &str) {
+    // The parser takes the frontmatter verbatim from the start of the content
+    content.split_at(ContentParser::new().parse(content).frontmatter.len())
+}
+
+fn html(path: &str, target_language: &str, segments: &[Segment]) -> String {
+    let title = format!("{} ({})", escape(path), escape(target_language));
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n\
+         body {{ font-family: sans-serif; margin: 1em; }}\n\
+         table {{ border-collapse: collapse; width: 100%; table-layout: fixed; }}\n\
+         th, td {{ border: 1px solid #ccc; padding: 0.5em; vertical-align: top; text-align: left; }}\n\
+         td pre {{ margin: 0; white-space: pre-wrap; word-wrap: break-word; }}\n\
+         th.n {{ width: 3em; }}\n\
+         tr.unchanged td {{ color: #888; }}\n\
+         </style>\n</head>\n<body>\n<h1>{title}</h1>\n\
+         <table>\n<tr><th class=\"n\">#</th><th>Original</th><th>Translation</th></tr>\n"
+    );
+    for (n, segment) in segments.iter().enumerate() {
+        page.push_str(&format!(
+            "<tr{}><td>{}</td><td><pre>{}</pre></td><td><pre>{}</pre></td></tr>\n",
+            if segment.unchanged() { " class=\"unchanged\"" } else { "" },
+            n + 1,
+            escape(segment.original),
+            escape(segment.translated),
+        ));
+    }
+    page.push_str("</table>\n</body>\n</html>\n");
+    page
+}
+
+fn unified(path: &str, target_language: &str, segments: &[Segment]) -> String {
+    let mut diff = format!("--- a/{}\n+++ b/{} ({})\n", path, path, target_language);
+    for (n, segment) in segments.iter().enumerate() {
+        diff.push_str(&format!("@@ segment {} @@\n", n + 1));
+        if segment.unchanged() {
+            for line in segment.original.lines() {
+                diff.push_str(&format!(" {}\n", line));
+            }
+            continue;
+        }
+        for line in segment.original.lines() {
+            diff.push_str(&format!("-{}\n", line));
+        }
+        for line in segment.translated.lines() {
+            diff.push_str(&format!("+{}\n", line));
+        }
+    }
+    diff
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_segments() {
+        let original = "---\nname: demo\ndescription: A demo\n---\n# Usage\n\nRun <it>.\n\n```bash\ndemo\n```\n";
+        let translated = "---\nname: demo\ndescription: 演示\n---\n# 用法\n\n运行 <it>。\n\n```bash\ndemo\n```\n";
+
+        let segments = segments(original, translated);
+        assert_eq!(segments.len(), 4);
+        assert_eq!(segments[0].translated, "---\nname: demo\ndescription: 演示\n---");
+        assert_eq!(segments[1].original, "# Usage");
+        assert!(segments[3].unchanged());
+
+        assert_eq!(
+            render(DiffFormat::Unified, "SKILL.md", "zh-CN", original, translated),
+            "--- a/SKILL.md\n+++ b/SKILL.md (zh-CN)\n@@ segment 1 @@\n-\
+             ---\n-name: demo\n-description: A demo\n----\n+---\n+name: demo\n+description: 演示\n+---\n\
+             @@ segment 2 @@\n-# Usage\n+# 用法\n@@ segment 3 @@\n-Run <it>.\n+运行 <it>。\n\
+             @@ segment 4 @@\n ```bash\n demo\n ```\n"
+        );
+        let page = render(DiffFormat::Html, "SKILL.md", "zh-CN", original, translated);
+        assert!(page.contains("<td><pre>Run &lt;it&gt;.</pre></td><td><pre>运行 &lt;it&gt;。</pre></td>"));
+        assert!(page.contains("<tr class=\"unchanged\"><td>4</td>"));
+    }
+}
@@ -0,0 +1,98 @@
+//! Error types for skillts-core.
+
+use thiserror::Error;
+
+/// Errors returned by the parser, translator and cache
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Translation error: {0}")]
+    Translation(#[from] TranslationError),
+
+    #[error("Cache error: {0}")]
+    Cache(#[from] sqlx::Error),
+
+    #[error("Invalid base64 content: {0}")]
+    Base64(#[from] base64::DecodeError),
+
+    /// Content that decoded but is not usable, e.g. invalid UTF-8
+    #[error("{0}")]
+    InvalidContent(String),
+
+    /// Unusable language options, e.g. the same source and target language
+    #[error("{0}")]
+    InvalidLanguage(String),
+
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+/// Translation-specific errors
+#[derive(Debug, Clone, Error)]
+pub enum TranslationError {
+    #[error("Translation timed out after {0} seconds")]
+    Timeout(u64),
+
+    #[error("Translation failed after {attempts} attempts: {error}")]
+    RetryFailed {
+        attempts: u32,
+        error: String,
+        /// Status of the last upstream response, when the upstream answered
+        status: Option<u16>,
+        /// Wait advised with the last upstream response
+        retry_after: Option<std::time::Duration>,
+    },
+
+    #[error("Empty response from upstream API")]
+    EmptyResponse,
+
+    #[error("OpenAI API error: {0}")]
+    OpenAIError(String),
+
+    #[error("Input has a line of {tokens} tokens, more than the chunk limit of {limit}")]
+    InputTooLarge { tokens: usize, limit: usize },
+
+    /// The upstream refused a request too long for the model, e.g. OpenAI's `context_length_exceeded`
+    #[error("Input exceeds the model's context length: {0}")]
+    ContextLengthExceeded(String),
+
+    #[error("Upstream API unavailable, circuit breaker open (retry in {0} seconds)")]
+    CircuitOpen(u64),
+
+    #[error("Upstream API returned {status}: {message}")]
+    Upstream {
+        status: u16,
+        message: String,
+        /// Wait advised by the upstream via Retry-After or the error message
+        retry_after: Option<std::time::Duration>,
+    },
+}
+
+impl TranslationError {
+    /// HTTP status of the upstream response behind the error, if the upstream answered
+    pub fn upstream_status(&self) -> Option<u16> {
+        match self {
+            TranslationError::Upstream { status, .. } => Some(*status),
+            TranslationError::RetryFailed { status, .. } => *status,
+            _ => None,
+        }
+    }
+
+    /// How long to wait before trying again, when known
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            TranslationError::Upstream { retry_after, .. }
+            | TranslationError::RetryFailed { retry_after, .. } => *retry_after,
+            TranslationError::CircuitOpen(seconds) => Some(std::time::Duration::from_secs(*seconds)),
+            _ => None,
+        }
+    }
+}
+
+impl From<async_openai::error::OpenAIError> for Error {
+    fn from(err: async_openai::error::OpenAIError) -> Self {
+        Error::Translation(TranslationError::OpenAIError(err.to_string()))
+    }
+}
+
+/// Result type alias for core errors
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,114 @@
+//! Terms kept verbatim.
+//!
+//! Product names and similar terms listed as kept are swapped for `___TERM_n___`
+//! placeholders in every text sent to the model and swapped back in its answer, so
+//! the model cannot translate or respell them. A finished translation is checked
+//! for each term occurring as often as in the original.
+
+/// Kept terms of one translation at most
+pub const MAX_KEEP_TERMS: usize = 200;
+
+/// Trimmed, distinct terms, longest first so a term containing another is
+/// replaced before it
+pub fn normalize(terms: &[String]) -> Vec<String> {
+    let mut terms: Vec<String> = terms
+        .iter()
+        .map(|term| term.trim().to_string())
+        .filter(|term| !term.is_empty())
+        .collect();
+    terms.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    terms.dedup();
+    terms
+}
+
+/// Text with every whole-word occurrence of the normalized `terms` replaced by its placeholder
+pub fn protect(text: &str, terms: &[String]) -> String {
+    let mut text = text.to_string();
+    for (n, term) in terms.iter().enumerate() {
+        let ranges = occurrences(&text, term);
+        if ranges.is_empty() {
+            continue;
+        }
+        let placeholder = placeholder(n);
+        let mut protected = String::with_capacity(text.len());
+        let mut last = 0;
+        for range in ranges {
+            protected.push_str(&text[last..range.start]);
+            protected.push_str(&placeholder);
+            last = range.end;
+        }
+        protected.push_str(&text[last..]);
+        text = protected;
+    }
+    text
+}
+
+/// Text with the placeholders of `protect` replaced by their terms again
+pub fn restore(text: &str, terms: &[String]) -> String {
+    terms
+        .iter()
+        .enumerate()
+        .fold(text.to_string(), |text, (n, term)| text.replace(&placeholder(n), term))
+}
+
+/// A finding for each term occurring less often in the translation than in the original
+pub fn findings(original: &str, translated: &str, terms: &[String]) -> Vec<String> {
+    terms
+        .iter()
+        .filter_map(|term| {
+            let (expected, found) = (occurrences(original, term).len(), occurrences(translated, term).len());
+            (found < expected).then(|| {
+                format!(
+                    "Kept term '{}' occurs {} times in the original but {} times in the translation",
+                    term, expected, found
+                )
+            })
+        })
+        .collect()
+}
+
+fn placeholder(n: usize) -> String {
+    format!("___TERM_{}___", n)
+}
+
+/// Byte ranges of `term` in `text` not adjoining an ASCII letter, digit or underscore
+/// that would make it part of a longer word; CJK text may directly surround a term
+fn occurrences(text: &str, term: &str) -> Vec<std::ops::Range<usize>> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    text.match_indices(term)
+        .map(|(start, _)| start..start + term.len())
+        .filter(|range| {
+            let before = text[..range.start].chars().next_back();
+            let after = text[range.end..].chars().next();
+            let first = term.chars().next().is_some_and(is_word);
+            let last = term.chars().next_back().is_some_and(is_word);
+            (!first || !before.is_some_and(is_word)) && (!last || !after.is_some_and(is_word))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protect_and_restore_terms() {
+        let terms = normalize(&[" Claw".to_string(), "OpenClaw".to_string(), "MCP".to_string(), "Claw".to_string()]);
+        assert_eq!(terms, vec!["OpenClaw", "Claw", "MCP"]);
+
+        let text = "Install OpenClaw, then Claw and the MCP server (not MCPServer or my_MCP).";
+        let protected = protect(text, &terms);
+        assert_eq!(
+            protected,
+            "Install ___TERM_0___, then ___TERM_1___ and the ___TERM_2___ server (not MCPServer or my_MCP)."
+        );
+        assert_eq!(restore(&protected, &terms), text);
+
+        let translated = "OpenClawをインストールし、Claw と MCP サーバーを使います";
+        assert!(findings(text, translated, &terms).is_empty());
+        assert_eq!(
+            findings(text, "安装开放之爪，然后是 Claw 和 MCP 服务器", &terms),
+            vec!["Kept term 'OpenClaw' occurs 1 times in the original but 0 times in the translation"]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Language codes, display names and lightweight language detection.
+//!
+//! Detection looks at the writing system first (Han, kana, Hangul, Cyrillic, ...)
+//! and tells Latin-script languages apart by common function words. It is meant
+//! for picking a translation direction, not for mixed or very short texts.
+
+/// Source language value requesting detection
+pub const AUTO: &str = "auto";
+
+/// Fewest letters needed to detect a language
+const MIN_LETTERS: usize = 20;
+
+/// Frequent function words of Latin-script languages
+const STOPWORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "is", "to", "of", "in", "it", "you", "for", "with", "this", "that", "are", "or"]),
+    ("fr", &["le", "la", "les", "et", "est", "des", "une", "pour", "dans", "vous", "avec", "que", "pas", "sur"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "mit", "ein", "eine", "zu", "für", "sie", "auf", "den"]),
+    ("es", &["el", "la", "los", "las", "y", "es", "una", "para", "con", "que", "por", "del", "se", "como"]),
+    ("pt", &["o", "os", "as", "e", "é", "um", "uma", "para", "com", "que", "não", "do", "da", "em"]),
+    ("it", &["il", "lo", "gli", "e", "è", "un", "una", "per", "con", "che", "non", "del", "della", "sono"]),
+];
+
+/// English name of a language code for the system prompt; unknown codes are returned as-is
+pub fn language_name(code: &str) -> String {
+    let name = match code.to_ascii_lowercase().as_str() {
+        "en" | "en-us" | "en-gb" => "English",
+        "zh" | "zh-cn" | "zh-hans" => "Chinese (Simplified)",
+        "zh-tw" | "zh-hk" | "zh-hant" => "Chinese (Traditional)",
+        "ja" => "Japanese",
+        "ko" => "Korean",
+        "fr" => "French",
+        "de" => "German",
+        "es" => "Spanish",
+        "pt" | "pt-br" => "Portuguese",
+        "it" => "Italian",
+        "ru" => "Russian",
+        "uk" => "Ukrainian",
+        "ar" => "Arabic",
+        "he" => "Hebrew",
+        "el" => "Greek",
+        "th" => "Thai",
+        "hi" => "Hindi",
+        _ => return code.to_string(),
+    };
+    name.to_string()
+}
+
+/// Whether two language codes name the same language.
+/// Regional variants differ (`zh-CN` vs `zh-TW`), but a bare code matches its
+/// regional forms, since detection only yields bare codes.
+pub fn same_language(a: &str, b: &str) -> bool {
+    let (a, b) = (a.to_ascii_lowercase(), b.to_ascii_lowercase());
+    if a == b {
+        return true;
+    }
+    let primary = |code: &str| code.split(['-', '_']).next().unwrap_or_default().to_string();
+    (!a.contains(['-', '_']) || !b.contains(['-', '_'])) && primary(&a) == primary(&b)
+}
+
+/// Detect the language of a text, returning a bare code such as `en` or `zh`
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut counts = [0usize; 9];
+    let (mut letters, mut latin) = (0, 0);
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        let script = match c {
+            '\u{3040}'..='\u{30FF}' => 0,                           // Hiragana, Katakana
+            '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => 1, // Hangul
+            '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => 2, // Han
+            '\u{0400}'..='\u{04FF}' => 3,                           // Cyrillic
+            '\u{0600}'..='\u{06FF}' => 4,                           // Arabic
+            '\u{0590}'..='\u{05FF}' => 5,                           // Hebrew
+            '\u{0370}'..='\u{03FF}' => 6,                           // Greek
+            '\u{0E00}'..='\u{0E7F}' => 7,                           // Thai
+            '\u{0900}'..='\u{097F}' => 8,                           // Devanagari
+            _ if c.is_ascii_alphabetic() || ('\u{00C0}'..='\u{024F}').contains(&c) => {
+                latin += 1;
+                continue;
+            }
+            _ => continue,
+        };
+        counts[script] += 1;
+    }
+    if letters < MIN_LETTERS {
+        return None;
+    }
+
+    // CJK text is dense: a fifth of the letters outweighs embedded English terms.
+    // Kana marks Japanese even next to Han characters.
+    if (counts[0] + counts[2]) * 5 >= letters {
+        return Some(if counts[0] * 10 >= counts[2] { "ja" } else { "zh" });
+    }
+    if counts[1] * 5 >= letters {
+        return Some("ko");
+    }
+    let (script, &count) = counts.iter().enumerate().skip(3).max_by_key(|(_, c)| **c)?;
+    if count * 2 >= letters {
+        return Some(["ru", "ar", "he", "el", "th", "hi"][script - 3]);
+    }
+    if latin * 2 < letters {
+        return None;
+    }
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    STOPWORDS
+        .iter()
+        .map(|(code, stopwords)| {
+            let hits = words.iter().filter(|w| stopwords.contains(&w.as_str())).count();
+            (*code, hits)
+        })
+        .filter(|(_, hits)| *hits > 0)
+        // Ties go to the earlier language, English first
+        .fold(None, |best: Option<(&str, usize)>, (code, hits)| match best {
+            Some((_, best_hits)) if best_hits >= hits => best,
+            _ => Some((code, hits)),
+        })
+        .map(|(code, _)| code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(detect_language("Install the tool and run it with your API key."), Some("en"));
+        assert_eq!(detect_language("安装工具并使用你的 API 密钥运行它，然后查看输出结果。"), Some("zh"));
+        assert_eq!(detect_language("ツールをインストールして、API キーで実行してください。"), Some("ja"));
+        assert_eq!(detect_language("Installez l'outil et lancez-le avec votre clé pour le projet."), Some("fr"));
+        assert_eq!(detect_language("Установите инструмент и запустите его."), Some("ru"));
+        assert_eq!(detect_language("too short"), None);
+    }
+
+    #[test]
+    fn test_same_language_and_names() {
+        assert!(same_language("zh-CN", "zh-cn"));
+        assert!(same_language("zh", "zh-CN"));
+        assert!(!same_language("zh-CN", "zh-TW"));
+        assert!(!same_language("en", "zh-CN"));
+        assert_eq!(language_name("zh-CN"), "Chinese (Simplified)");
+        assert_eq!(language_name("EN"), "English");
+        assert_eq!(language_name("tlh"), "tlh");
+    }
+}
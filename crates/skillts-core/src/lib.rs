@@ -0,0 +1,59 @@
+//! Core translation engine for SKILL.md files.
+//!
+//! Provides the markdown [`parser`], the OpenAI-compatible [`translator`] with its
+//! [`prompt`] templates, language detection in [`language`], code comment extraction
+//! in [`comments`], heading anchor preservation in [`anchors`], [`bilingual`] output and review
+//! artifacts in [`diff`], structural checks of
+//! translations in [`validate`], judge-model scoring in [`quality`] and back-translation
+//! checks in [`roundtrip`], terms shared across a skill's files in [`terminology`], terms kept
+//! verbatim in [`keep_terms`], the upstream call [`scheduler`],
+//! the [`cache`] with its translation [`memory`] and versioned
+//! SQLite schema changes in [`migrate`] without any HTTP layer or global configuration, so other tools can embed
+//! translation directly:
+//!
+//! ```no_run
+//! use skillts_core::cache::{CacheConfig, TranslationCache};
+//! use skillts_core::translator::{TranslationProfile, Translator, TranslatorConfig};
+//!
+//! # async fn run() -> skillts_core::Result<()> {
+//! let translator = Translator::new(TranslatorConfig {
+//!     api_key: "sk-...".to_string(),
+//!     ..TranslatorConfig::default()
+//! });
+//! let cache = TranslationCache::new(CacheConfig::default()).await?;
+//!
+//! let content = "# Hello\n";
+//! let content_hash = Translator::compute_hash(content);
+//! let profile = TranslationProfile::new("en", "zh-CN");
+//! let keys = translator.cache_keys(&content_hash, &profile);
+//! if cache.get_first(&profile.tenant, &keys).await?.is_none() {
+//!     let (translated, metadata) = translator.translate(content, &profile).await?;
+//!     let key = translator.cache_key_for_model(&content_hash, &profile, &metadata.model);
+//!     let hash = Translator::compute_hash(&translated);
+//!     cache.set(&key, &content_hash, "SKILL.md", &translated, &hash, None).await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod anchors;
+pub mod bilingual;
+pub mod cache;
+pub mod comments;
+pub mod diff;
+pub mod error;
+pub mod keep_terms;
+pub mod language;
+pub mod memory;
+pub mod migrate;
+pub mod models;
+pub mod parser;
+pub mod prompt;
+pub mod quality;
+pub mod roundtrip;
+pub mod scheduler;
+pub mod terminology;
+pub mod translator;
+pub mod validate;
+
+pub use error::{Error, Result, TranslationError};
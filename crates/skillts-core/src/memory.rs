@@ -0,0 +1,155 @@
+//! Translation memory.
+//!
+//! Stores translated body sections with their source text so other documents
+//! can use them. A new section whose source matches a stored one after
+//! whitespace normalization takes its translation as-is; otherwise the most
+//! similar stored sections by character trigrams are sent to the model as
+//! examples, keeping shared boilerplate phrased the same way across files.
+
+use std::collections::HashSet;
+
+use crate::translator::{TranslationProfile, Translator};
+
+/// Stored sections compared per translation when looking for similar ones
+pub const CANDIDATE_LIMIT: i64 = 500;
+
+/// Examples added to one upstream request at most
+pub const MAX_EXAMPLES: usize = 3;
+
+/// Tenant and language pair a translation memory lookup is limited to
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryScope<'a> {
+    pub tenant: &'a str,
+    pub source_language: &'a str,
+    pub target_language: &'a str,
+}
+
+impl<'a> From<&'a TranslationProfile> for MemoryScope<'a> {
+    fn from(profile: &'a TranslationProfile) -> Self {
+        Self {
+            tenant: &profile.tenant,
+            source_language: &profile.source_language,
+            target_language: &profile.target_language,
+        }
+    }
+}
+
+/// A translated section in the translation memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryPair {
+    /// Hash of the normalized source, see [`normalized_hash`]
+    pub normalized_hash: String,
+    pub source: String,
+    pub translation: String,
+}
+
+impl MemoryPair {
+    pub fn new(source: impl Into<String>, translation: impl Into<String>) -> Self {
+        let source = source.into();
+        Self {
+            normalized_hash: normalized_hash(&source),
+            source,
+            translation: translation.into(),
+        }
+    }
+}
+
+/// Text with runs of whitespace collapsed into single spaces and trimmed
+pub fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Hash of the normalized text, equal for sources differing only in whitespace
+pub fn normalized_hash(text: &str) -> String {
+    Translator::compute_hash(&normalize(text))
+}
+
+/// Character trigrams of the normalized, lowercased text
+fn trigrams(text: &str) -> HashSet<[char; 3]> {
+    let chars: Vec<char> = normalize(text).to_lowercase().chars().collect();
+    chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+/// Dice coefficient of the texts' trigram sets, from 0.0 (nothing shared) to 1.0
+pub fn similarity(a: &str, b: &str) -> f64 {
+    trigram_similarity(&trigrams(a), &trigrams(b))
+}
+
+fn trigram_similarity(a: &HashSet<[char; 3]>, b: &HashSet<[char; 3]>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let shared = a.intersection(b).count();
+    2.0 * shared as f64 / (a.len() + b.len()) as f64
+}
+
+/// Most similar candidate for each source with a similarity of at least `min_similarity`
+pub fn best_matches<'a>(
+    sources: &[&str],
+    candidates: &'a [MemoryPair],
+    min_similarity: f64,
+) -> Vec<Option<&'a MemoryPair>> {
+    let candidate_trigrams: Vec<_> = candidates.iter().map(|c| trigrams(&c.source)).collect();
+
+    sources
+        .iter()
+        .map(|source| {
+            let source_trigrams = trigrams(source);
+            candidates
+                .iter()
+                .zip(&candidate_trigrams)
+                .map(|(candidate, grams)| (candidate, trigram_similarity(&source_trigrams, grams)))
+                .filter(|(_, score)| *score >= min_similarity)
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(candidate, _)| candidate)
+        })
+        .collect()
+}
+
+/// System prompt extended with earlier translations of similar sections
+pub fn memory_prompt(prompt: &str, examples: &[&MemoryPair]) -> String {
+    let mut prompt = format!(
+        "{}\n\nEarlier translations of similar passages follow. Where the text matches, \
+         reuse their wording and terminology; translate everything else as usual.",
+        prompt
+    );
+    for example in examples {
+        prompt.push_str(&format!(
+            "\n\n<example>\n<source>\n{}\n</source>\n<translation>\n{}\n</translation>\n</example>",
+            example.source, example.translation
+        ));
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized_hash_ignores_whitespace() {
+        assert_eq!(
+            normalized_hash("## Usage\n\nRun   the  tool.\n"),
+            normalized_hash("## Usage\nRun the tool.")
+        );
+        assert_ne!(normalized_hash("## Usage"), normalized_hash("## usage"));
+    }
+
+    #[test]
+    fn test_best_matches() {
+        let candidates = vec![
+            MemoryPair::new("## License\n\nMIT licensed. See LICENSE for details.", "## 许可证\n\nMIT 许可。详见 LICENSE。"),
+            MemoryPair::new("## Install\n\nRun npm install.", "## 安装\n\n运行 npm install。"),
+        ];
+        let matches = best_matches(
+            &["## License\n\nApache licensed. See LICENSE for details.", "## Something else entirely"],
+            &candidates,
+            0.6,
+        );
+        assert_eq!(matches[0], Some(&candidates[0]));
+        assert_eq!(matches[1], None);
+
+        assert_eq!(similarity("same text", "same  text"), 1.0);
+        assert_eq!(similarity("ab", "abc"), 0.0);
+    }
+}
@@ -0,0 +1,212 @@
+//! Versioned schema migrations for the SQLite database.
+//!
+//! Each store in the database declares a [`Schema`]: a component name and its
+//! numbered migrations. Applied versions are recorded per component in the
+//! `schema_version` table, and every migration runs in its own transaction
+//! together with its version record, so a failed migration leaves nothing behind.
+//! Transactions hold the write lock from the start, so processes migrating the same
+//! file at once (a server and `--migrate-only`) apply each migration exactly once.
+
+use chrono::Utc;
+use futures::future::BoxFuture;
+use sqlx::sqlite::{SqliteConnection, SqlitePool};
+
+use crate::error::{Error, Result};
+
+/// Migration code for changes SQL alone cannot express, e.g. backfills
+pub type MigrationFn = for<'c> fn(&'c mut SqliteConnection) -> BoxFuture<'c, Result<()>>;
+
+/// What a migration does
+#[derive(Debug)]
+pub enum Step {
+    /// Statements run as one batch
+    Sql(&'static str),
+    /// Code run on the migration's transaction
+    Code(MigrationFn),
+}
+
+/// One schema change
+#[derive(Debug)]
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub step: Step,
+}
+
+/// The migrations of one component of the database, in version order
+#[derive(Debug)]
+pub struct Schema {
+    pub component: &'static str,
+    pub migrations: &'static [Migration],
+}
+
+impl Schema {
+    /// Latest version this build knows about
+    pub fn latest_version(&self) -> i64 {
+        self.migrations.last().map_or(0, |m| m.version)
+    }
+}
+
+/// Create the version table if needed
+async fn ensure_version_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            component TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL,
+            PRIMARY KEY (component, version)
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Highest applied version of a component, 0 when none
+pub async fn current_version(pool: &SqlitePool, component: &str) -> Result<i64> {
+    ensure_version_table(pool).await?;
+    let version: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(version) FROM schema_version WHERE component = ?")
+            .bind(component)
+            .fetch_one(pool)
+            .await?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Migrations of `schema` not yet applied.
+/// Fails when the database is newer than this build, since it would not understand it.
+pub async fn pending<'a>(pool: &SqlitePool, schema: &'a Schema) -> Result<Vec<&'a Migration>> {
+    let current = current_version(pool, schema.component).await?;
+    if current > schema.latest_version() {
+        return Err(Error::Internal(format!(
+            "Database schema of {} is at version {}, newer than the latest known version {}",
+            schema.component,
+            current,
+            schema.latest_version()
+        )));
+    }
+    Ok(schema.migrations.iter().filter(|m| m.version > current).collect())
+}
+
+/// Apply the pending migrations of `schema`, returning how many were applied
+pub async fn migrate(pool: &SqlitePool, schema: &Schema) -> Result<usize> {
+    let mut applied = 0;
+
+    for migration in pending(pool, schema).await? {
+        // Take the write lock up front: a deferred transaction of a second process passing
+        // the check below would fail with SQLITE_BUSY when upgrading its read lock
+        let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        // Another process may have applied it since the pending check
+        let done: Option<i64> = sqlx::query_scalar(
+            "SELECT version FROM schema_version WHERE component = ? AND version = ?",
+        )
+        .bind(schema.component)
+        .bind(migration.version)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if done.is_some() {
+            continue;
+        }
+
+        match migration.step {
+            Step::Sql(sql) => {
+                sqlx::raw_sql(sql).execute(&mut *tx).await?;
+            }
+            Step::Code(run) => run(&mut tx).await?,
+        }
+
+        sqlx::query(
+            "INSERT INTO schema_version (component, version, description, applied_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(schema.component)
+        .bind(migration.version)
+        .bind(migration.description)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        tracing::info!(
+            "Applied {} migration {}: {}",
+            schema.component,
+            migration.version,
+            migration.description
+        );
+        applied += 1;
+    }
+
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    const NOTES: Schema = Schema {
+        component: "notes",
+        migrations: &[
+            Migration {
+                version: 1,
+                description: "Create notes",
+                step: Step::Sql("CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT NOT NULL)"),
+            },
+            Migration {
+                version: 2,
+                description: "Add author",
+                step: Step::Sql("ALTER TABLE notes ADD COLUMN author TEXT NOT NULL DEFAULT ''"),
+            },
+        ],
+    };
+
+    #[tokio::test]
+    async fn test_migrations_apply_once() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        assert_eq!(pending(&pool, &NOTES).await.unwrap().len(), 2);
+        assert_eq!(migrate(&pool, &NOTES).await.unwrap(), 2);
+        assert_eq!(migrate(&pool, &NOTES).await.unwrap(), 0);
+        assert_eq!(current_version(&pool, "notes").await.unwrap(), 2);
+
+        sqlx::query("INSERT INTO notes (body, author) VALUES ('hi', 'me')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let older = Schema {
+            component: "notes",
+            migrations: &NOTES.migrations[..1],
+        };
+        let message = pending(&pool, &older).await.unwrap_err().to_string();
+        assert!(message.contains("newer than the latest known version 1"));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_migrations() {
+        let dir = std::env::temp_dir().join(format!("skillts-migrate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.join("notes.db").display());
+        let (first, second) = futures::future::try_join(SqlitePool::connect(&url), SqlitePool::connect(&url))
+            .await
+            .unwrap();
+
+        // Like two processes starting at once, each with its own pool
+        let (a, b) = futures::future::try_join(migrate(&first, &NOTES), migrate(&second, &NOTES))
+            .await
+            .unwrap();
+        assert_eq!(a + b, 2);
+        assert_eq!(current_version(&second, "notes").await.unwrap(), 2);
+
+        first.close().await;
+        second.close().await;
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,206 @@
+//! Data types shared with the HTTP layer.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Model for a cache entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CacheEntry {
+    pub cache_key: String,
+    pub content_hash: String,
+    pub path: String,
+    pub translated_content: String,
+    pub translated_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub accessed_at: DateTime<Utc>,
+    pub hit_count: i64,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+/// A cache entry without its translated content, for browsing the cache
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CacheEntrySummary {
+    pub cache_key: String,
+    pub content_hash: String,
+    pub path: String,
+    pub translated_hash: String,
+    pub source_language: String,
+    pub target_language: String,
+    pub model: String,
+    /// Stored size of the translated content; compressed with `CACHE_COMPRESSION`
+    pub size_bytes: i64,
+    pub created_at: DateTime<Utc>,
+    pub accessed_at: DateTime<Utc>,
+    pub hit_count: i64,
+    /// Translation metadata, including warnings and quality scores
+    pub metadata: serde_json::Value,
+}
+
+/// Order of listed cache entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum CacheOrder {
+    /// Most recently translated first
+    #[default]
+    Newest,
+    /// Least recently translated first
+    Oldest,
+    /// Most hits first
+    Hits,
+    /// Largest first
+    Size,
+    /// Most recently used first
+    Accessed,
+}
+
+/// Selection and page of cache entries to list
+#[derive(Debug, Clone, Default)]
+pub struct CacheFilter {
+    /// Only entries whose path starts with this
+    pub path_prefix: Option<String>,
+    pub target_language: Option<String>,
+    pub order: CacheOrder,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Statistics about the cache
+#[derive(Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CacheStats {
+    pub total_entries: i64,
+    pub total_size_bytes: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oldest_entry: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub newest_entry: Option<DateTime<Utc>>,
+    /// Lookups answered from the cache, counted across restarts
+    pub total_hits: i64,
+    /// Lookups that found no cached translation, counted across restarts
+    pub total_misses: i64,
+    /// Lookups in the last 24 hours, counted in whole hours up to the current one
+    pub lookups_24h: i64,
+    /// Share of the lookups in the last 24 hours that were hits
+    pub hit_rate_24h: f64,
+    /// Lookups in the last 7 days, counted in whole hours up to the current one
+    pub lookups_7d: i64,
+    /// Share of the lookups in the last 7 days that were hits
+    pub hit_rate_7d: f64,
+    /// Lookups since startup served by the in-memory tier
+    pub memory_hits: i64,
+    /// Lookups since startup served by the database
+    pub store_hits: i64,
+    /// Share of all lookups since startup served from memory
+    pub memory_hit_rate: f64,
+    /// Share of lookups that reached the database and found the entry there
+    pub store_hit_rate: f64,
+    /// Distinct paths the cached content was submitted under
+    pub total_paths: i64,
+    /// Paths whose content was already cached under another path, each sparing a translation
+    pub deduplicated_paths: i64,
+}
+
+/// Size of the cache database file around a compaction
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CompactStats {
+    /// Database and WAL file before compacting
+    pub size_before_bytes: u64,
+    pub size_after_bytes: u64,
+}
+
+/// What becomes of cached translations stored by another translator version,
+/// which the cache keys of the current version never reach
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum StaleVersionPolicy {
+    /// Leave them until they expire
+    #[default]
+    Keep,
+    /// Delete them
+    Purge,
+    /// Mark them `stale_version` and answer with them when a fresh translation fails
+    Mark,
+}
+
+impl FromStr for StaleVersionPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "keep" | "" => Ok(StaleVersionPolicy::Keep),
+            "purge" => Ok(StaleVersionPolicy::Purge),
+            "mark" => Ok(StaleVersionPolicy::Mark),
+            other => Err(format!(
+                "unknown stale version policy '{}', expected 'keep', 'purge' or 'mark'",
+                other
+            )),
+        }
+    }
+}
+
+/// Aggregated cache statistics for one group of entries
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CacheGroupStats {
+    pub key: String,
+    pub entries: i64,
+    pub size_bytes: i64,
+    pub total_hits: i64,
+    /// hits / (hits + entries), counting each entry's initial translation as a miss
+    pub hit_rate: f64,
+}
+
+/// Cache statistics grouped by target language, repository path prefix and model
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DetailedCacheStats {
+    pub by_target_language: Vec<CacheGroupStats>,
+    pub by_path_prefix: Vec<CacheGroupStats>,
+    pub by_model: Vec<CacheGroupStats>,
+}
+
+/// State of the upstream circuit breaker
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CircuitBreakerStatus {
+    /// "closed", "open" or "half_open"
+    pub state: String,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_in_seconds: Option<u64>,
+}
+
+/// Item counts in the original and translated document
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CountCheck {
+    pub original: usize,
+    pub translated: usize,
+    pub ok: bool,
+}
+
+/// Structural comparison of a translation with its original
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ValidationReport {
+    /// True when every check passed
+    pub valid: bool,
+    pub headings: CountCheck,
+    pub code_blocks: CountCheck,
+    /// Frontmatter keys of the original missing from the translation
+    pub missing_frontmatter_keys: Vec<String>,
+    /// Link and image targets of the original missing from the translation
+    pub missing_link_targets: Vec<String>,
+    /// Internal placeholders such as `___CODE_BLOCK_0___` left in the translation
+    pub leaked_placeholders: Vec<String>,
+    /// Why the translated frontmatter is not valid YAML
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frontmatter_error: Option<String>,
+}
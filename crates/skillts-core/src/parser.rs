@@ -0,0 +1,1156 @@
+//! Content parser for SKILL.md files.
+//!
+//! Handles YAML frontmatter and code block extraction/preservation.
+//! Code blocks are located with pulldown-cmark so that indented blocks, `~~~`
+//! fences and fences nested in blockquotes or lists are all recognised.
+//! Tables and HTML blocks are extracted the same way, along with the cell text
+//! and HTML text nodes to translate separately from their markup. Images, badge
+//! links, link reference definitions and `:emoji:` shortcodes in the prose are
+//! replaced by placeholders too, so the model never sees their URLs or codes.
+
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use serde_yaml_neo::Value as YamlValue;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Parsed SKILL.md content structure
+#[derive(Debug, Clone)]
+pub struct ParsedContent {
+    /// Original frontmatter string (including --- delimiters)
+    pub frontmatter: String,
+    /// Parsed frontmatter as key-value map
+    pub frontmatter_dict: HashMap<String, serde_json::Value>,
+    /// Body content (after frontmatter)
+    pub body: String,
+    /// Extracted code blocks: (language, code, placeholder)
+    pub code_blocks: Vec<(String, String, String)>,
+    /// Byte ranges of each code block in `body`, in the same order as `code_blocks`
+    pub code_block_ranges: Vec<Range<usize>>,
+    /// Tables and HTML blocks in document order
+    pub structured_blocks: Vec<StructuredBlock>,
+    /// Inline spans kept verbatim, in document order
+    pub inline_spans: Vec<InlineSpan>,
+}
+
+/// Kind of an inline span kept verbatim
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InlineKind {
+    /// An image, or a link whose label is nothing but images such as a shields.io badge
+    Image,
+    /// A link reference definition like `[ci]: https://...`
+    LinkDefinition,
+    /// An emoji shortcode like `:warning:`
+    Emoji,
+}
+
+/// Markup outside code, tables and HTML blocks sent to the model as a placeholder
+#[derive(Debug, Clone)]
+pub struct InlineSpan {
+    pub kind: InlineKind,
+    pub placeholder: String,
+    /// Byte range of the span in `body`
+    pub range: Range<usize>,
+}
+
+/// Kind of a structured block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    Table,
+    Html,
+}
+
+/// A table or HTML block whose text is translated apart from its markup
+#[derive(Debug, Clone)]
+pub struct StructuredBlock {
+    pub kind: BlockKind,
+    pub placeholder: String,
+    /// Byte range of the block in `body`
+    pub range: Range<usize>,
+    /// Byte ranges in `body` of the cell text or HTML text nodes, one per line
+    pub texts: Vec<Range<usize>>,
+}
+
+impl ParsedContent {
+    /// Copy with the given byte ranges of `body` replaced, moving block ranges to match.
+    /// Replacements must be sorted and must not overlap a block boundary.
+    pub fn with_replacements(&self, replacements: &[(Range<usize>, String)]) -> ParsedContent {
+        let mut body = String::with_capacity(self.body.len());
+        let mut last = 0;
+        for (range, text) in replacements {
+            body.push_str(&self.body[last..range.start]);
+            body.push_str(text);
+            last = range.end;
+        }
+        body.push_str(&self.body[last..]);
+
+        // Offsets move by the growth of every replacement ending at or before them
+        let shift = |offset: usize| {
+            replacements
+                .iter()
+                .take_while(|(range, _)| range.end <= offset)
+                .fold(offset, |offset, (range, text)| offset + text.len() - range.len())
+        };
+        let shift_range = |range: &Range<usize>| shift(range.start)..shift(range.end);
+
+        ParsedContent {
+            frontmatter: self.frontmatter.clone(),
+            frontmatter_dict: self.frontmatter_dict.clone(),
+            body,
+            code_blocks: self.code_blocks.clone(),
+            code_block_ranges: self.code_block_ranges.iter().map(shift_range).collect(),
+            structured_blocks: self
+                .structured_blocks
+                .iter()
+                .map(|block| StructuredBlock {
+                    range: shift_range(&block.range),
+                    texts: block.texts.iter().map(shift_range).collect(),
+                    ..block.clone()
+                })
+                .collect(),
+            inline_spans: self
+                .inline_spans
+                .iter()
+                .map(|span| InlineSpan {
+                    range: shift_range(&span.range),
+                    ..span.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// Placeholders of code blocks, structured blocks and inline spans with their ranges,
+    /// in document order
+    fn placeholders(&self) -> Vec<(&str, &Range<usize>)> {
+        let mut placeholders: Vec<(&str, &Range<usize>)> = self
+            .code_blocks
+            .iter()
+            .zip(&self.code_block_ranges)
+            .map(|((_, _, placeholder), range)| (placeholder.as_str(), range))
+            .chain(
+                self.structured_blocks
+                    .iter()
+                    .map(|block| (block.placeholder.as_str(), &block.range)),
+            )
+            .chain(self.inline_spans.iter().map(|span| (span.placeholder.as_str(), &span.range)))
+            .collect();
+        placeholders.sort_by_key(|(_, range)| range.start);
+        placeholders
+    }
+}
+
+/// Parser for SKILL.md files with special handling for frontmatter and code blocks
+pub struct ContentParser {
+    /// Pattern to match YAML frontmatter
+    frontmatter_pattern: Regex,
+    /// Pattern to match emoji shortcodes such as `:warning:` or `:+1:`
+    emoji_pattern: Regex,
+}
+
+impl ContentParser {
+    /// Create a new content parser
+    pub fn new() -> Self {
+        Self {
+            // (?s) enables DOTALL mode - makes . match newlines
+            frontmatter_pattern: Regex::new(r"(?s)^---\s*\n(.*?)\n---\s*\n").unwrap(),
+            emoji_pattern: Regex::new(r":[a-z0-9_+-]*[a-z+][a-z0-9_+-]*:").unwrap(),
+        }
+    }
+
+    /// Parse SKILL.md content into structured components
+    pub fn parse(&self, content: &str) -> ParsedContent {
+        let mut frontmatter = String::new();
+        let mut frontmatter_dict = HashMap::new();
+        let mut body = content.to_string();
+
+        // Extract frontmatter
+        if let Some(caps) = self.frontmatter_pattern.captures(content) {
+            frontmatter = caps.get(0).unwrap().as_str().to_string();
+            let fm_content = caps.get(1).unwrap().as_str();
+            frontmatter_dict = self.parse_yaml_frontmatter(fm_content);
+            body = content[frontmatter.len()..].to_string();
+        }
+
+        // Extract code blocks
+        let mut code_blocks = Vec::new();
+        let mut code_block_ranges = Vec::new();
+        for (i, (language, code, range)) in self.find_code_blocks(&body).into_iter().enumerate() {
+            let placeholder = format!("___CODE_BLOCK_{}___", i);
+            code_blocks.push((language, code, placeholder));
+            code_block_ranges.push(range);
+        }
+
+        let structured_blocks = self.find_structured_blocks(&body);
+
+        // Spans inside blocks leave with their block
+        let blocks: Vec<&Range<usize>> = code_block_ranges
+            .iter()
+            .chain(structured_blocks.iter().map(|block| &block.range))
+            .collect();
+        let mut counts = HashMap::new();
+        let inline_spans = self
+            .find_inline_spans(&body)
+            .into_iter()
+            .filter(|(_, range)| {
+                !blocks
+                    .iter()
+                    .any(|block| range.start < block.end && block.start < range.end)
+            })
+            .map(|(kind, range)| {
+                let count = counts.entry(kind).or_insert(0);
+                let name = match kind {
+                    InlineKind::Image => "IMAGE",
+                    InlineKind::LinkDefinition => "LINK_DEF",
+                    InlineKind::Emoji => "EMOJI",
+                };
+                *count += 1;
+                InlineSpan {
+                    kind,
+                    placeholder: format!("___{}_{}___", name, *count - 1),
+                    range,
+                }
+            })
+            .collect();
+
+        ParsedContent {
+            frontmatter,
+            frontmatter_dict,
+            body,
+            code_blocks,
+            code_block_ranges,
+            structured_blocks,
+            inline_spans,
+        }
+    }
+
+    /// Locate images, links labelled only by images, link reference definitions and
+    /// emoji shortcodes outside code, in document order
+    fn find_inline_spans(&self, body: &str) -> Vec<(InlineKind, Range<usize>)> {
+        let mut spans = Vec::new();
+        let events = Parser::new_ext(body, Options::ENABLE_TABLES).into_offset_iter();
+        for (_, definition) in events.reference_definitions().iter() {
+            let text = &body[definition.span.clone()];
+            let end = definition.span.start + text.trim_end().len();
+            spans.push((InlineKind::LinkDefinition, definition.span.start..end));
+        }
+
+        // Open links: range, first span found inside, whether any label text lies outside images
+        let mut links: Vec<(Range<usize>, usize, bool)> = Vec::new();
+        // Prose outside images and code, adjacent text events merged since they split at `_`
+        let mut prose: Vec<Range<usize>> = Vec::new();
+        let (mut images, mut code_blocks) = (0, 0);
+        for (event, range) in events {
+            match event {
+                Event::Start(Tag::CodeBlock(_)) => code_blocks += 1,
+                Event::End(TagEnd::CodeBlock) => code_blocks -= 1,
+                Event::Start(Tag::Link { .. }) => links.push((range, spans.len(), false)),
+                Event::End(TagEnd::Link) => {
+                    if let Some((range, first, has_text)) = links.pop() {
+                        let has_image = spans[first..].iter().any(|(kind, _)| *kind == InlineKind::Image);
+                        if has_image && !has_text {
+                            spans.truncate(first);
+                            spans.push((InlineKind::Image, range));
+                        } else if let Some(outer) = links.last_mut() {
+                            outer.2 |= has_text;
+                        }
+                    }
+                }
+                Event::Start(Tag::Image { .. }) => {
+                    if images == 0 {
+                        spans.push((InlineKind::Image, range));
+                    }
+                    images += 1;
+                }
+                Event::End(TagEnd::Image) => images -= 1,
+                Event::Text(_) | Event::Code(_) | Event::InlineHtml(_) if images == 0 && code_blocks == 0 => {
+                    if let Some(link) = links.last_mut() {
+                        link.2 |= !body[range.clone()].trim().is_empty();
+                    }
+                    if matches!(event, Event::Text(_)) {
+                        match prose.last_mut() {
+                            Some(last) if last.end == range.start => last.end = range.end,
+                            _ => prose.push(range),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for range in prose {
+            for found in self.emoji_pattern.find_iter(&body[range.clone()]) {
+                let (start, end) = (range.start + found.start(), range.start + found.end());
+                // Not part of a longer word or run of colons, such as `key:value:` or `a::b::c`
+                let adjoins = |c: Option<char>| c.is_some_and(|c| c == ':' || c.is_ascii_alphanumeric());
+                if !adjoins(body[..start].chars().next_back()) && !adjoins(body[end..].chars().next()) {
+                    spans.push((InlineKind::Emoji, start..end));
+                }
+            }
+        }
+
+        spans.sort_by_key(|(_, range)| range.start);
+        spans
+    }
+
+    /// Locate tables and HTML blocks in markdown using pulldown-cmark, with the
+    /// text of each table cell and the text nodes of each HTML block
+    fn find_structured_blocks(&self, body: &str) -> Vec<StructuredBlock> {
+        let mut blocks = Vec::new();
+        let mut current: Option<(BlockKind, Range<usize>)> = None;
+        let mut texts = Vec::new();
+        let mut html = HtmlScanner::default();
+        let (mut tables, mut html_blocks) = (0, 0);
+
+        for (event, range) in Parser::new_ext(body, Options::ENABLE_TABLES).into_offset_iter() {
+            match event {
+                Event::Start(Tag::Table(_)) => current = Some((BlockKind::Table, range)),
+                Event::Start(Tag::HtmlBlock) => {
+                    current = Some((BlockKind::Html, range));
+                    html = HtmlScanner::default();
+                }
+                Event::Start(Tag::TableCell) => {
+                    let cell = &body[range.clone()];
+                    let start = range.start + cell.len() - cell.trim_start().len();
+                    let text = cell.trim();
+                    if text.chars().any(char::is_alphabetic) {
+                        texts.push(start..start + text.len());
+                    }
+                }
+                // HTML events come one per line, without container prefixes such as `> `
+                Event::Html(_) if current.is_some() => html.scan(body, range, &mut texts),
+                Event::End(TagEnd::Table | TagEnd::HtmlBlock) => {
+                    if let Some((kind, mut range)) = current.take() {
+                        // Keep the trailing newline outside the block so placeholders stay on their own line
+                        while range.end > range.start && body.as_bytes()[range.end - 1] == b'\n' {
+                            range.end -= 1;
+                        }
+                        let placeholder = match kind {
+                            BlockKind::Table => {
+                                tables += 1;
+                                format!("___TABLE_{}___", tables - 1)
+                            }
+                            BlockKind::Html => {
+                                html_blocks += 1;
+                                format!("___HTML_{}___", html_blocks - 1)
+                            }
+                        };
+                        blocks.push(StructuredBlock {
+                            kind,
+                            placeholder,
+                            range,
+                            texts: std::mem::take(&mut texts),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        blocks
+    }
+
+    /// Locate code blocks in markdown using pulldown-cmark.
+    /// Returns (language, code, byte range) for each block in document order.
+    fn find_code_blocks(&self, body: &str) -> Vec<(String, String, Range<usize>)> {
+        let mut blocks = Vec::new();
+        let mut current: Option<(String, String, Range<usize>)> = None;
+
+        for (event, range) in Parser::new(body).into_offset_iter() {
+            match event {
+                Event::Start(Tag::CodeBlock(kind)) => {
+                    let language = match kind {
+                        CodeBlockKind::Fenced(info) => {
+                            info.split_whitespace().next().unwrap_or_default().to_string()
+                        }
+                        CodeBlockKind::Indented => String::new(),
+                    };
+                    current = Some((language, String::new(), range));
+                }
+                Event::Text(text) => {
+                    if let Some((_, code, _)) = current.as_mut() {
+                        code.push_str(&text);
+                    }
+                }
+                Event::End(TagEnd::CodeBlock) => {
+                    if let Some((language, code, mut range)) = current.take() {
+                        // Keep the trailing newline outside the block so placeholders stay on their own line
+                        while range.end > range.start && body.as_bytes()[range.end - 1] == b'\n' {
+                            range.end -= 1;
+                        }
+                        blocks.push((language, code, range));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        blocks
+    }
+
+    /// Parse YAML frontmatter content into a dictionary using serde_yaml_neo
+    fn parse_yaml_frontmatter(&self, fm_content: &str) -> HashMap<String, serde_json::Value> {
+        match serde_yaml_neo::from_str::<YamlValue>(fm_content) {
+            Ok(YamlValue::Mapping(map)) => {
+                map.into_iter()
+                    .filter_map(|(k, v)| {
+                        k.as_str().map(|key| (key.to_string(), yaml_to_json_value(v)))
+                    })
+                    .collect()
+            }
+            _ => HashMap::new(),
+        }
+    }
+
+    /// Replace code blocks in the parsed body with placeholders
+    pub fn replace_code_blocks(&self, parsed: &ParsedContent) -> String {
+        let mut result = String::with_capacity(parsed.body.len());
+        let mut last = 0;
+
+        for ((_, _, placeholder), range) in parsed.code_blocks.iter().zip(&parsed.code_block_ranges) {
+            result.push_str(&parsed.body[last..range.start]);
+            result.push_str(placeholder);
+            last = range.end;
+        }
+        result.push_str(&parsed.body[last..]);
+
+        result
+    }
+
+    /// Restore code blocks from placeholders using the exact original source text
+    pub fn restore_code_blocks(&self, body: &str, parsed: &ParsedContent) -> String {
+        let mut result = body.to_string();
+
+        for ((_, _, placeholder), range) in parsed.code_blocks.iter().zip(&parsed.code_block_ranges) {
+            result = result.replace(placeholder, &parsed.body[range.clone()]);
+        }
+
+        result
+    }
+
+    /// Replace code blocks, tables and HTML blocks in the parsed body with placeholders
+    pub fn replace_blocks(&self, parsed: &ParsedContent) -> String {
+        let mut result = String::with_capacity(parsed.body.len());
+        let mut last = 0;
+
+        for (placeholder, range) in parsed.placeholders() {
+            result.push_str(&parsed.body[last..range.start]);
+            result.push_str(placeholder);
+            last = range.end;
+        }
+        result.push_str(&parsed.body[last..]);
+
+        result
+    }
+
+    /// Restore code blocks, tables and HTML blocks from placeholders using the source text in `parsed`
+    pub fn restore_blocks(&self, body: &str, parsed: &ParsedContent) -> String {
+        let mut result = body.to_string();
+
+        for (placeholder, range) in parsed.placeholders() {
+            result = result.replace(placeholder, &parsed.body[range.clone()]);
+        }
+
+        result
+    }
+
+    /// Replace a specific field in the frontmatter with its translated value
+    pub fn translate_frontmatter_field(
+        &self,
+        frontmatter: &str,
+        field: &str,
+        translated_value: &str,
+    ) -> String {
+        let lines: Vec<&str> = frontmatter.lines().collect();
+        let mut result_lines = Vec::new();
+        let mut i = 0;
+        let field_prefix = format!("{}:", field);
+
+        while i < lines.len() {
+            let line = lines[i];
+
+            // Check if this line starts with the target field
+            if line.starts_with(&field_prefix) {
+                let after_colon = &line[field_prefix.len()..].trim_start();
+
+                // Check for block scalar indicators
+                if *after_colon == ">" || *after_colon == "|" {
+                    // Found a block scalar, need to replace entire block
+                    
+                    // Filter out empty lines from translated value to preserve YAML structure
+                    let non_empty_lines: Vec<&str> = translated_value
+                        .lines()
+                        .filter(|line| !line.trim().is_empty())
+                        .collect();
+                    
+                    // If only one non-empty line, use simple format
+                    if non_empty_lines.len() == 1 {
+                        result_lines.push(format!("{}: {}", field, non_empty_lines[0]));
+                    } else {
+                        // Multiple lines - use folded block format
+                        result_lines.push(format!("{}: >", field));
+                        // Add each non-empty line with proper indentation
+                        for content_line in non_empty_lines {
+                            result_lines.push(format!("  {}", content_line));
+                        }
+                    }
+
+                    // Skip the block scalar indicator and all indented lines after it
+                    i += 1;
+                    while i < lines.len() {
+                        let next_line = lines[i];
+                        if next_line.trim().is_empty() {
+                            // Empty line, still part of block
+                            i += 1;
+                            continue;
+                        }
+                        let indent = next_line.len() - next_line.trim_start().len();
+                        if indent > 0 {
+                            // Still in block content
+                            i += 1;
+                        } else {
+                            // End of block
+                            break;
+                        }
+                    }
+                    continue;
+                } else if after_colon.starts_with('"') && after_colon.ends_with('"') {
+                    // Quoted string - preserve quotes
+                    result_lines.push(format!("{}: \"{}\"", field, translated_value));
+                } else if after_colon.starts_with('\'') && after_colon.ends_with('\'') {
+                    // Single quoted string - preserve quotes
+                    result_lines.push(format!("{}: '{}'", field, translated_value));
+                } else if !after_colon.is_empty() {
+                    // Regular unquoted value - check if translated value has newlines
+                    if translated_value.contains('\n') {
+                        // Filter out empty lines to preserve YAML structure
+                        let non_empty_lines: Vec<&str> = translated_value
+                            .lines()
+                            .filter(|line| !line.trim().is_empty())
+                            .collect();
+                        
+                        if non_empty_lines.len() == 1 {
+                            result_lines.push(format!("{}: {}", field, non_empty_lines[0]));
+                        } else {
+                            // Need to use folded block format
+                            result_lines.push(format!("{}: >", field));
+                            for content_line in non_empty_lines {
+                                result_lines.push(format!("  {}", content_line));
+                            }
+                        }
+                    } else {
+                        result_lines.push(format!("{}: {}", field, translated_value));
+                    }
+                } else {
+                    // Empty value - just keep the field name
+                    result_lines.push(line.to_string());
+                }
+            } else {
+                result_lines.push(line.to_string());
+            }
+
+            i += 1;
+        }
+
+        // Keep the line break after the closing delimiter, which separates it from the body
+        let mut result = result_lines.join("\n");
+        if frontmatter.ends_with('\n') {
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Why a frontmatter string (including --- delimiters) is not valid YAML;
+    /// `None` when it parses or there is no frontmatter
+    pub fn frontmatter_error(&self, frontmatter: &str) -> Option<String> {
+        let caps = self.frontmatter_pattern.captures(frontmatter)?;
+        serde_yaml_neo::from_str::<YamlValue>(caps.get(1).unwrap().as_str())
+            .err()
+            .map(|e| e.to_string())
+    }
+
+    /// Get the description field from frontmatter
+    pub fn get_description_field(&self, frontmatter_dict: &HashMap<String, serde_json::Value>) -> Option<String> {
+        frontmatter_dict
+            .get("description")
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+    }
+
+    /// Check if a frontmatter field should be translated
+    pub fn is_translatable_field(&self, field: &str) -> bool {
+        matches!(field, "description")
+    }
+}
+
+/// Finds text nodes in the lines of an HTML block, skipping tags, attributes,
+/// comments and the content of `script`, `style`, `pre` and `code` elements
+#[derive(Default)]
+struct HtmlScanner {
+    /// Quote character of the attribute value being read, or `>` inside a tag
+    in_tag: Option<u8>,
+    in_comment: bool,
+    /// Nesting depth of elements whose content is not text to translate
+    raw_depth: usize,
+}
+
+impl HtmlScanner {
+    const RAW_ELEMENTS: [&'static str; 4] = ["script", "style", "pre", "code"];
+
+    /// Scan one line of the block, pushing the range of each trimmed text node
+    fn scan(&mut self, body: &str, line: Range<usize>, texts: &mut Vec<Range<usize>>) {
+        let bytes = body.as_bytes();
+        let mut text_start = None;
+        let mut i = line.start;
+
+        while i < line.end {
+            let rest = &bytes[i..line.end];
+            if self.in_comment {
+                match rest.windows(3).position(|w| w == b"-->") {
+                    Some(p) => {
+                        self.in_comment = false;
+                        i += p + 3;
+                    }
+                    None => i = line.end,
+                }
+                continue;
+            }
+            if let Some(quote) = self.in_tag {
+                match (quote, rest[0]) {
+                    (b'>', b'"' | b'\'') => self.in_tag = Some(rest[0]),
+                    (b'>', b'>') => self.in_tag = None,
+                    (q, c) if q != b'>' && c == q => self.in_tag = Some(b'>'),
+                    _ => {}
+                }
+                i += 1;
+                continue;
+            }
+
+            let is_tag = rest[0] == b'<'
+                && rest.get(1).is_some_and(|&c| c.is_ascii_alphabetic() || c == b'/' || c == b'!');
+            if !is_tag {
+                text_start.get_or_insert(i);
+                i += 1;
+                continue;
+            }
+
+            if let Some(start) = text_start.take() {
+                self.push_text(body, start..i, texts);
+            }
+            if rest.starts_with(b"<!--") {
+                self.in_comment = true;
+                i += 4;
+                continue;
+            }
+
+            let closing = rest[1] == b'/';
+            let name: String = rest[1 + usize::from(closing)..]
+                .iter()
+                .take_while(|c| c.is_ascii_alphanumeric())
+                .map(|&c| char::from(c).to_ascii_lowercase())
+                .collect();
+            if Self::RAW_ELEMENTS.contains(&name.as_str()) {
+                if closing {
+                    self.raw_depth = self.raw_depth.saturating_sub(1);
+                } else {
+                    self.raw_depth += 1;
+                }
+            }
+            self.in_tag = Some(b'>');
+            i += 1;
+        }
+
+        if let Some(start) = text_start {
+            self.push_text(body, start..line.end, texts);
+        }
+    }
+
+    fn push_text(&self, body: &str, range: Range<usize>, texts: &mut Vec<Range<usize>>) {
+        let node = &body[range.clone()];
+        let text = node.trim();
+        if self.raw_depth == 0 && text.chars().any(char::is_alphabetic) {
+            let start = range.start + node.len() - node.trim_start().len();
+            texts.push(start..start + text.len());
+        }
+    }
+}
+
+/// Convert YAML value to JSON value
+fn yaml_to_json_value(v: YamlValue) -> serde_json::Value {
+    match v {
+        YamlValue::Null => serde_json::Value::Null,
+        YamlValue::Bool(b) => serde_json::Value::Bool(b),
+        YamlValue::Number(n) => {
+            // Try to convert to serde_json::Number
+            if let Some(n) = n.as_i64() {
+                serde_json::Value::Number(n.into())
+            } else if let Some(n) = n.as_f64() {
+                serde_json::Number::from_f64(n)
+                    .map(serde_json::Value::Number)
+                    .unwrap_or(serde_json::Value::Null)
+            } else {
+                serde_json::Value::Null
+            }
+        }
+        YamlValue::String(s) => serde_json::Value::String(s),
+        YamlValue::Sequence(arr) => {
+            serde_json::Value::Array(arr.into_iter().map(yaml_to_json_value).collect())
+        }
+        YamlValue::Mapping(map) => {
+            serde_json::Value::Object(
+                map.into_iter()
+                    .filter_map(|(k, v)| {
+                        k.as_str().map(|key| (key.to_string(), yaml_to_json_value(v)))
+                    })
+                    .collect(),
+            )
+        }
+        // Tagged values (e.g., !!str) - extract the inner value
+        YamlValue::Tagged(tagged) => yaml_to_json_value(tagged.value),
+    }
+}
+
+impl Default for ContentParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frontmatter() {
+        let content = r#"---
+name: test-skill
+version: 1.0.0
+description: "A test skill"
+---
+
+# Content here
+"#;
+
+        let parser = ContentParser::new();
+        let parsed = parser.parse(content);
+
+        assert!(!parsed.frontmatter.is_empty());
+        assert_eq!(
+            parsed.frontmatter_dict.get("name").and_then(|v| v.as_str()),
+            Some("test-skill")
+        );
+        assert!(parsed.body.contains("# Content here"));
+    }
+
+    #[test]
+    fn test_code_block_replacement() {
+        let body = r#"Some text
+
+```python
+print("hello")
+```
+
+More text"#;
+
+        let parser = ContentParser::new();
+        let parsed = parser.parse(body);
+        assert_eq!(parsed.code_blocks.len(), 1);
+        assert_eq!(parsed.code_blocks[0].0, "python");
+
+        let replaced = parser.replace_code_blocks(&parsed);
+        assert!(replaced.contains("___CODE_BLOCK_0___"));
+        assert!(!replaced.contains("print(\"hello\")"));
+
+        let restored = parser.restore_code_blocks(&replaced, &parsed);
+        assert_eq!(restored, body);
+    }
+
+    #[test]
+    fn test_code_block_variants_round_trip() {
+        let body = r#"Intro paragraph.
+
+~~~bash
+echo "tilde fence"
+~~~
+
+    indented_code()
+
+> Quoted text
+> ```js
+> console.log("quoted");
+> ```
+
+Closing text"#;
+
+        let parser = ContentParser::new();
+        let parsed = parser.parse(body);
+        assert_eq!(parsed.code_blocks.len(), 3, "blocks: {:?}", parsed.code_blocks);
+        assert_eq!(parsed.code_blocks[0].0, "bash");
+        assert_eq!(parsed.code_blocks[1].1, "indented_code()\n");
+        assert_eq!(parsed.code_blocks[2].0, "js");
+
+        let replaced = parser.replace_code_blocks(&parsed);
+        assert!(!replaced.contains("tilde fence"));
+        assert!(!replaced.contains("indented_code"));
+        assert!(!replaced.contains("console.log"));
+        assert!(replaced.contains("Quoted text"));
+        assert!(replaced.contains("Closing text"));
+
+        let restored = parser.restore_code_blocks(&replaced, &parsed);
+        assert_eq!(restored, body);
+    }
+
+    #[test]
+    fn test_structured_blocks_round_trip() {
+        let body = r#"Intro.
+
+| Name | Description |
+|:-----|------------:|
+| `run` | Runs **it** |
+
+<details>
+<summary>Click to expand</summary>
+
+Inner markdown.
+
+</details>
+
+<div align="center">
+  <img src="logo.png" alt="Logo"><!-- hidden -->
+  <p>Hello <b>world</b></p>
+  <pre>raw text</pre>
+</div>
+"#;
+
+        let parser = ContentParser::new();
+        let parsed = parser.parse(body);
+        let kinds: Vec<BlockKind> = parsed.structured_blocks.iter().map(|b| b.kind).collect();
+        assert_eq!(kinds, vec![BlockKind::Table, BlockKind::Html, BlockKind::Html, BlockKind::Html]);
+
+        let texts: Vec<Vec<&str>> = parsed
+            .structured_blocks
+            .iter()
+            .map(|b| b.texts.iter().map(|r| &body[r.clone()]).collect())
+            .collect();
+        assert_eq!(texts[0], vec!["Name", "Description", "`run`", "Runs **it**"]);
+        assert_eq!(texts[1], vec!["Click to expand"]);
+        assert!(texts[2].is_empty());
+        assert_eq!(texts[3], vec!["Hello", "world"]);
+
+        let replaced = parser.replace_blocks(&parsed);
+        assert!(replaced.contains("___TABLE_0___\n\n___HTML_0___"));
+        assert!(replaced.contains("Inner markdown."));
+        assert!(!replaced.contains("<div"));
+        assert_eq!(parser.restore_blocks(&replaced, &parsed), body);
+
+        // Replacing cell text moves the ranges of later blocks
+        let cell = parsed.structured_blocks[0].texts[1].clone();
+        let translated = parsed.with_replacements(&[(cell, "说明".to_string())]);
+        let restored = parser.restore_blocks(&replaced, &translated);
+        assert!(restored.contains("| Name | 说明 |"));
+        assert!(restored.contains("<p>Hello <b>world</b></p>"));
+    }
+
+    #[test]
+    fn test_inline_spans_round_trip() {
+        let body = r#"# Demo Skill [![CI](https://img.shields.io/badge/ci-passing-green)](https://ci.example.com) :rocket:
+
+[![npm][npm-badge]][npm] ![Logo](logo.png "The logo")
+
+:warning: Read the [docs ![icon](icon.svg)](https://example.com) first :+1: :white_check_mark:
+
+Not shortcodes: `:inline:`, key:value:, a::b::c, at 10:30:00 and https://example.com:8080.
+
+```bash
+echo ":code:"
+```
+
+| Status | Note |
+|--------|------|
+| ![ok](ok.svg) | :tada: |
+
+[npm-badge]: https://img.shields.io/npm/v/demo.svg
+[npm]: https://www.npmjs.com/package/demo
+"#;
+
+        let parser = ContentParser::new();
+        let parsed = parser.parse(body);
+        let spans: Vec<(InlineKind, &str, &str)> = parsed
+            .inline_spans
+            .iter()
+            .map(|span| (span.kind, span.placeholder.as_str(), &body[span.range.clone()]))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (
+                    InlineKind::Image,
+                    "___IMAGE_0___",
+                    "[![CI](https://img.shields.io/badge/ci-passing-green)](https://ci.example.com)"
+                ),
+                (InlineKind::Emoji, "___EMOJI_0___", ":rocket:"),
+                (InlineKind::Image, "___IMAGE_1___", "[![npm][npm-badge]][npm]"),
+                (InlineKind::Image, "___IMAGE_2___", "![Logo](logo.png \"The logo\")"),
+                (InlineKind::Emoji, "___EMOJI_1___", ":warning:"),
+                (InlineKind::Image, "___IMAGE_3___", "![icon](icon.svg)"),
+                (InlineKind::Emoji, "___EMOJI_2___", ":+1:"),
+                (InlineKind::Emoji, "___EMOJI_3___", ":white_check_mark:"),
+                (InlineKind::LinkDefinition, "___LINK_DEF_0___", "[npm-badge]: https://img.shields.io/npm/v/demo.svg"),
+                (InlineKind::LinkDefinition, "___LINK_DEF_1___", "[npm]: https://www.npmjs.com/package/demo"),
+            ]
+        );
+
+        let replaced = parser.replace_blocks(&parsed);
+        assert!(replaced.starts_with("# Demo Skill ___IMAGE_0___ ___EMOJI_0___\n"));
+        assert!(replaced.contains("Read the [docs ___IMAGE_3___](https://example.com) first"));
+        assert!(!replaced.contains("shields.io"));
+        assert!(replaced.contains("___TABLE_0___") && replaced.contains("___CODE_BLOCK_0___"));
+        assert_eq!(parser.restore_blocks(&replaced, &parsed), body);
+
+        // Spans keep their place when table cells are replaced by translations
+        let cells = &parsed.structured_blocks[0].texts;
+        let replacements = vec![(cells[0].clone(), "状态".to_string())];
+        let translated = parsed.with_replacements(&replacements);
+        let replaced = parser.replace_blocks(&translated);
+        assert_eq!(parser.restore_blocks(&replaced, &translated), translated.body);
+        assert!(translated.body.contains("| 状态 | Note |"));
+    }
+
+    #[test]
+    fn test_parse_frontmatter_with_multiline_metadata() {
+        // Test case from real skill file with multi-line JSON metadata
+        let content = r#"---
+name: web-monitor
+version: 3.1.0
+description: "Monitor web pages for changes, price drops, stock availability, and custom conditions."
+metadata:
+  {
+    "openclaw":
+      {
+        "emoji": "👁️",
+        "requires": { "bins": ["python3", "curl"] },
+      },
+  }
+---
+
+# Web Monitor Pro
+"#;
+
+        let parser = ContentParser::new();
+        let parsed = parser.parse(content);
+
+        // Verify frontmatter was parsed
+        assert!(!parsed.frontmatter.is_empty(), "Frontmatter should not be empty");
+
+        // Verify description was correctly extracted
+        let description = parser.get_description_field(&parsed.frontmatter_dict);
+        assert!(description.is_some(), "Description should be extracted");
+        assert!(
+            description.unwrap().contains("Monitor web pages"),
+            "Description should contain expected text"
+        );
+
+        // Verify name was parsed correctly
+        assert_eq!(
+            parsed.frontmatter_dict.get("name").and_then(|v| v.as_str()),
+            Some("web-monitor")
+        );
+
+        // Verify body was separated correctly
+        assert!(parsed.body.contains("# Web Monitor Pro"));
+        assert!(!parsed.body.contains("description:"));
+    }
+
+    #[test]
+    fn test_parse_frontmatter_inline_metadata() {
+        // Test case with inline JSON metadata (no quotes around description)
+        let content = r#"---
+name: 0protocol
+description: Agents can sign plugins, rotate credentials without losing identity.
+homepage: https://github.com/0isone/0protocol
+metadata: {"openclaw":{"emoji":"🪪","requires":{"bins":["mcporter"]}}}
+---
+
+# Content
+"#;
+
+        let parser = ContentParser::new();
+        let parsed = parser.parse(content);
+
+        // Verify frontmatter was parsed
+        assert!(!parsed.frontmatter.is_empty(), "Frontmatter should not be empty");
+
+        // Verify description was correctly extracted (unquoted)
+        let description = parser.get_description_field(&parsed.frontmatter_dict);
+        assert!(description.is_some(), "Description should be extracted");
+        let desc = description.unwrap();
+        assert!(
+            desc.contains("Agents can sign plugins"),
+            "Description should contain expected text: {:?}",
+            desc
+        );
+
+        // Verify name was parsed correctly
+        assert_eq!(
+            parsed.frontmatter_dict.get("name").and_then(|v| v.as_str()),
+            Some("0protocol")
+        );
+    }
+
+    #[test]
+    fn test_translate_frontmatter_field_quoted() {
+        let frontmatter = r#"---
+name: test
+description: "This is a test description"
+---
+"#;
+        let parser = ContentParser::new();
+        let result = parser.translate_frontmatter_field(frontmatter, "description", "这是测试描述");
+
+        assert!(result.contains(r#"description: "这是测试描述""#), "Should contain translated description with quotes: {}", result);
+        assert!(!result.contains("This is a test description"));
+    }
+
+    #[test]
+    fn test_translate_frontmatter_field_unquoted() {
+        let frontmatter = r#"---
+name: test
+description: This is a test description without quotes
+---
+"#;
+        let parser = ContentParser::new();
+        let result = parser.translate_frontmatter_field(frontmatter, "description", "这是没有引号的测试描述");
+
+        assert!(result.contains("description: 这是没有引号的测试描述"), "Should contain translated description without quotes: {}", result);
+        assert!(!result.contains("This is a test description"));
+        assert!(result.ends_with("---\n"), "Should keep the line break before the body: {:?}", result);
+    }
+
+    #[test]
+    fn test_parse_frontmatter_folded_description() {
+        // Test case with YAML folded block scalar (>)
+        let content = r#"---
+name: solo-leveling
+description: >
+  Solo Leveling — a life RPG skill that turns real-world habits into an addictive progression
+  system. Inspired by the manhwa Solo Leveling, this skill features 6 stats.
+metadata:
+  openclaw:
+    emoji: "⚔️"
+---
+
+# Content
+"#;
+
+        let parser = ContentParser::new();
+        let parsed = parser.parse(content);
+
+        // Verify frontmatter was parsed
+        assert!(!parsed.frontmatter.is_empty(), "Frontmatter should not be empty");
+
+        // For folded block scalar, description value should be extracted
+        // Note: YAML folded scalar parsing is complex, we need to handle multi-line content
+        let description = parser.get_description_field(&parsed.frontmatter_dict);
+        println!("Parsed description: {:?}", description);
+        println!("Parsed frontmatter_dict: {:?}", parsed.frontmatter_dict);
+
+        // The description field exists but may have special handling for folded scalars
+        assert!(
+            description.is_some() || parsed.frontmatter_dict.contains_key("description"),
+            "Description field should exist"
+        );
+    }
+
+    #[test]
+    fn test_translate_frontmatter_field_folded() {
+        // Test replacing a folded block scalar description
+        let frontmatter = r#"---
+name: test
+description: >
+  This is a multi-line description
+  that spans multiple lines.
+---
+"#;
+        let parser = ContentParser::new();
+        let result = parser.translate_frontmatter_field(
+            frontmatter,
+            "description",
+            "这是多行描述的翻译",
+        );
+
+        println!("Result: {}", result);
+        // Should replace entire block with single line
+        assert!(result.contains("description: 这是多行描述的翻译"), "Should contain translated description: {}", result);
+        // Should NOT contain the original block content
+        assert!(!result.contains("This is a multi-line"));
+        assert!(!result.contains("that spans"));
+        // Should NOT contain the > indicator
+        assert!(!result.contains("description: >"));
+    }
+
+    #[test]
+    fn test_translate_frontmatter_field_multiline_value() {
+        // Test replacing with a multi-line translated value
+        let frontmatter = r#"---
+name: test
+description: >
+  This is a multi-line description
+  that spans multiple lines.
+---
+"#;
+        let parser = ContentParser::new();
+        let translated = "这是第一行描述。\n这是第二行描述。\n这是第三行描述。";
+        let result = parser.translate_frontmatter_field(
+            frontmatter,
+            "description",
+            translated,
+        );
+
+        println!("Result:\n{}", result);
+        // Should use folded block format for multiline value
+        assert!(result.contains("description: >"), "Should use folded block format for multiline: {}", result);
+        assert!(result.contains("  这是第一行描述。"), "Should have indented content: {}", result);
+        assert!(result.contains("  这是第二行描述。"), "Should have indented content: {}", result);
+        assert!(result.contains("  这是第三行描述。"), "Should have indented content: {}", result);
+        // Should NOT contain the original content
+        assert!(!result.contains("This is a multi-line"));
+    }
+
+    #[test]
+    fn test_translate_frontmatter_field_with_empty_lines() {
+        // Test replacing with a multi-line translated value that contains empty lines
+        let frontmatter = r#"---
+name: test
+description: Some description here.
+---
+"#;
+        let parser = ContentParser::new();
+        // Translation with empty lines (common AI output format)
+        let translated = "这是第一行描述。
+
+这是第二行描述，前面有空行。
+
+这是第三行描述。";
+        let result = parser.translate_frontmatter_field(
+            frontmatter,
+            "description",
+            translated,
+        );
+
+        println!("Result:\n{}", result);
+        // Should use folded block format
+        assert!(result.contains("description: >"), "Should use folded block format: {}", result);
+        // Should have all non-empty lines with proper indentation
+        assert!(result.contains("  这是第一行描述。"), "Should have first line: {}", result);
+        assert!(result.contains("  这是第二行描述，前面有空行。"), "Should have second line: {}", result);
+        assert!(result.contains("  这是第三行描述。"), "Should have third line: {}", result);
+        // Should NOT have empty lines in the output (they would break YAML structure)
+        // Count lines that are just whitespace - should be none after description field
+        let lines: Vec<&str> = result.lines().collect();
+        let desc_start = lines.iter().position(|l| l.starts_with("description:")).unwrap();
+        let desc_lines: Vec<&&str> = lines[desc_start..].iter().take_while(|l| l.starts_with("  ") || l.starts_with("description:")).collect();
+        for line in desc_lines {
+            if line.starts_with("  ") {
+                assert!(!line.trim().is_empty(), "Should not have empty lines in description block: {:?}", line);
+            }
+        }
+    }
+}
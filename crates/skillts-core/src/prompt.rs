@@ -0,0 +1,407 @@
+//! System prompt templates.
+//!
+//! The built-in template applies unless a named override matches the document
+//! type and language pair of a translation. Overrides are versioned; the version
+//! of the template in use is part of the cache key, so editing a template
+//! retranslates the documents it applies to.
+//!
+//! Language rules add instructions for the target language, such as the register
+//! of Japanese or the spacing of Korean and Chinese, in the `{rules}` slot of a
+//! template. Built-in rules cover common targets; configured rules add to or
+//! replace them per language pair.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::language::{language_name, same_language};
+use crate::translator::TranslationProfile;
+
+/// Document type of SKILL.md files, used when a request names none
+pub const DEFAULT_DOCUMENT_TYPE: &str = "skill";
+
+/// Matches any document type or language in an override
+pub const ANY: &str = "*";
+
+/// Built-in system prompt; `{source}` and `{target}` are replaced by language names
+/// and `{rules}` by the rules for the language pair
+pub const BUILTIN_TEMPLATE: &str = r#"You are a professional technical translator specializing in software documentation.
+Your task is to translate SKILL.md files from {source} to {target}.
+
+IMPORTANT RULES:
+1. Translate the content naturally while preserving technical accuracy
+2. Keep all code examples, commands, and URLs unchanged
+3. Preserve the markdown formatting exactly
+4. Keep technical terms in their original form when appropriate (e.g., OpenClaw, ClawHub, API, CLI)
+5. Translate comments in code blocks only if they are clearly explanatory
+6. Maintain the same structure and organization as the original
+7. Do not add or remove any sections
+8. Preserve all placeholders like ___CODE_BLOCK_0___ exactly as they are
+{rules}
+Translate the following content to {target}:"#;
+
+/// System prompt for numbered table cells and HTML text nodes; `{source}` and
+/// `{target}` are replaced by language names and `{rules}` by the language rules
+const FRAGMENT_TEMPLATE: &str = r#"You are a professional technical translator.
+Translate text fragments from tables and HTML blocks of a markdown document from {source} to {target}.
+
+IMPORTANT RULES:
+1. Every input line starts with a marker like [3]; keep the marker and translate the text after it
+2. Output exactly one line per input line, in the same order
+3. Keep inline markdown, inline code, HTML entities, identifiers, commands, file paths and URLs unchanged
+4. Output only the translated lines
+{rules}"#;
+
+/// Rules for Chinese targets written with Simplified or Traditional characters
+const CHINESE_RULES: &str = "\
+- Put a half-width space between Chinese characters and adjacent Latin letters or digits (e.g. 使用 API 调用)
+- Use full-width punctuation (，。：；？！（）) in prose and keep half-width punctuation in code, commands and URLs";
+
+/// Built-in rules per target language
+const BUILTIN_RULES: &[(&str, &str)] = &[
+    (
+        "ja",
+        "\
+- Write consistently in the polite です/ます style; do not use casual forms or honorific and humble keigo (尊敬語/謙譲語)
+- Do not address the reader as あなた; omit the subject where Japanese naturally does
+- Use Japanese punctuation (、。) in prose and write established loanwords in katakana (e.g. ファイル, サーバー)
+- Do not put spaces between Japanese text and adjacent Latin words or digits",
+    ),
+    (
+        "ko",
+        "\
+- Write consistently in the formal polite 합니다 style
+- Follow standard Korean word spacing (띄어쓰기): separate words with spaces and attach particles and endings to the preceding word
+- Attach particles directly to English terms and code as well (e.g. API를, `config.yaml`에서)",
+    ),
+    ("zh", CHINESE_RULES),
+];
+
+/// Traditional Chinese targets, which add character and vocabulary rules to [`CHINESE_RULES`]
+const TRADITIONAL_CHINESE: &[&str] = &["zh-TW", "zh-HK", "zh-Hant"];
+
+/// Instructions for translating between one language pair
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LanguageRule {
+    /// Source language code, or `*` for any
+    pub source_language: String,
+    /// Target language code; a bare code such as `zh` also applies to its regional forms
+    pub target_language: String,
+    /// One instruction per line; empty to send no rules for the pair
+    pub rules: String,
+    /// Whether the rule comes from configuration rather than the built-in set
+    #[serde(skip)]
+    pub configured: bool,
+}
+
+impl LanguageRule {
+    /// How closely the rule matches a language pair, or `None` if it does not apply
+    fn specificity(&self, source_language: &str, target_language: &str) -> Option<usize> {
+        let source = if self.source_language == ANY {
+            0
+        } else if self.source_language.eq_ignore_ascii_case(source_language) {
+            2
+        } else {
+            return None;
+        };
+        let target = if self.target_language.eq_ignore_ascii_case(target_language) {
+            1
+        } else if same_language(&self.target_language, target_language) && !self.target_language.contains(['-', '_']) {
+            0
+        } else {
+            return None;
+        };
+        Some(source + target)
+    }
+}
+
+/// Language rules by language pair
+#[derive(Debug, Clone, Default)]
+pub struct LanguageRules {
+    rules: Vec<LanguageRule>,
+}
+
+impl LanguageRules {
+    /// The built-in rules, if enabled, followed by configured ones; a configured rule
+    /// replaces the built-in rule for the same pair
+    pub fn new(builtin: bool, configured: Vec<LanguageRule>) -> Self {
+        let mut rules = if builtin { Self::builtin() } else { Vec::new() };
+        for rule in configured {
+            rules.retain(|known| {
+                !(known.source_language.eq_ignore_ascii_case(&rule.source_language)
+                    && known.target_language.eq_ignore_ascii_case(&rule.target_language))
+            });
+            rules.push(LanguageRule {
+                configured: true,
+                ..rule
+            });
+        }
+        Self { rules }
+    }
+
+    fn builtin() -> Vec<LanguageRule> {
+        let rule = |target: &str, rules: String| LanguageRule {
+            source_language: ANY.to_string(),
+            target_language: target.to_string(),
+            rules,
+            configured: false,
+        };
+        let traditional = format!(
+            "- Use Traditional Chinese characters and the vocabulary customary in Taiwan and Hong Kong (e.g. 程式, 檔案, 伺服器)\n{}",
+            CHINESE_RULES
+        );
+        BUILTIN_RULES
+            .iter()
+            .map(|(target, rules)| rule(target, rules.to_string()))
+            .chain(TRADITIONAL_CHINESE.iter().map(|target| rule(target, traditional.clone())))
+            .collect()
+    }
+
+    /// The most specific rule for a language pair; a pinned source outranks an exact target
+    pub fn select(&self, source_language: &str, target_language: &str) -> Option<&LanguageRule> {
+        self.rules
+            .iter()
+            .filter_map(|rule| Some((rule.specificity(source_language, target_language)?, rule)))
+            .max_by_key(|(specificity, _)| *specificity)
+            .map(|(_, rule)| rule)
+    }
+
+    /// Text for the `{rules}` slot of a template: a titled list, or empty without rules
+    pub fn render(&self, source_language: &str, target_language: &str) -> String {
+        match self.select(source_language, target_language) {
+            Some(rule) if !rule.rules.trim().is_empty() => format!(
+                "\n{} RULES:\n{}\n",
+                language_name(target_language).to_uppercase(),
+                rule.rules.trim()
+            ),
+            _ => String::new(),
+        }
+    }
+}
+
+
+/// A system prompt template and the translations it applies to
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PromptTemplate {
+    pub name: String,
+    /// Document type, or `*` for any
+    pub document_type: String,
+    /// Source language code, or `*` for any
+    pub source_language: String,
+    /// Target language code, or `*` for any
+    pub target_language: String,
+    /// Unique across all saved templates; 0 for the built-in template
+    pub version: i64,
+    pub template: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl PromptTemplate {
+    /// The built-in template, applying to every translation
+    pub fn builtin() -> Self {
+        Self {
+            name: "builtin".to_string(),
+            document_type: ANY.to_string(),
+            source_language: ANY.to_string(),
+            target_language: ANY.to_string(),
+            version: 0,
+            template: BUILTIN_TEMPLATE.to_string(),
+            created_at: None,
+        }
+    }
+
+    /// System prompt for a language pair with the rules the `{rules}` slot is filled with
+    pub fn render(&self, source_language: &str, target_language: &str, rules: &LanguageRules) -> String {
+        self.template
+            .replace("{source}", &language_name(source_language))
+            .replace("{target}", &language_name(target_language))
+            .replace("{rules}", &rules.render(source_language, target_language))
+    }
+
+    /// Number of fields pinned to the profile, or `None` if the template does not apply
+    fn specificity(&self, profile: &TranslationProfile) -> Option<usize> {
+        [
+            (&self.document_type, &profile.document_type),
+            (&self.source_language, &profile.source_language),
+            (&self.target_language, &profile.target_language),
+        ]
+        .iter()
+        .try_fold(0, |pinned, (field, value)| {
+            if *field == ANY {
+                Some(pinned)
+            } else if field.eq_ignore_ascii_case(value) {
+                Some(pinned + 1)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// The built-in template and the saved overrides
+#[derive(Debug, Clone)]
+pub struct PromptTemplates {
+    builtin: PromptTemplate,
+    overrides: Vec<PromptTemplate>,
+}
+
+impl Default for PromptTemplates {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl PromptTemplates {
+    pub fn new(overrides: Vec<PromptTemplate>) -> Self {
+        Self {
+            builtin: PromptTemplate::builtin(),
+            overrides,
+        }
+    }
+
+    /// The most specific override matching the profile, else the built-in template.
+    /// Equally specific overrides are ordered by name.
+    pub fn select(&self, profile: &TranslationProfile) -> &PromptTemplate {
+        self.overrides
+            .iter()
+            .filter_map(|template| Some((template.specificity(profile)?, template)))
+            .min_by(|(a, x), (b, y)| b.cmp(a).then_with(|| x.name.cmp(&y.name)))
+            .map(|(_, template)| template)
+            .unwrap_or(&self.builtin)
+    }
+}
+
+/// System prompt for translating numbered table cells and HTML text nodes
+pub fn fragment_prompt(source_language: &str, target_language: &str, rules: &LanguageRules) -> String {
+    FRAGMENT_TEMPLATE
+        .replace("{source}", &language_name(source_language))
+        .replace("{target}", &language_name(target_language))
+        .replace("{rules}", &rules.render(source_language, target_language))
+        .trim_end()
+        .to_string()
+}
+
+/// System prompt for retranslating content whose first translation failed validation
+pub fn repair_prompt(prompt: &str, findings: &[String]) -> String {
+    let mut repair = format!(
+        "{}\n\nA previous translation of this content had structural problems:\n",
+        prompt
+    );
+    for finding in findings {
+        repair.push_str("- ");
+        repair.push_str(finding);
+        repair.push('\n');
+    }
+    repair.push_str(
+        "Translate it again without these problems: keep every heading, placeholder, \
+         link target and frontmatter key of the original.",
+    );
+    repair
+}
+
+/// Number texts one per line as `[n] text` for a translation request
+pub fn number_lines(texts: &[&str]) -> String {
+    texts
+        .iter()
+        .enumerate()
+        .map(|(n, text)| format!("[{}] {}", n, text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parse `[n] text` lines of a translation response; unmarked lines are ignored
+pub fn parse_numbered_lines(text: &str) -> HashMap<usize, String> {
+    text.lines()
+        .filter_map(|line| {
+            let (marker, rest) = line.trim().strip_prefix('[')?.split_once(']')?;
+            Some((marker.trim().parse().ok()?, rest.trim().to_string()))
+        })
+        .filter(|(_, text): &(usize, String)| !text.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(name: &str, document_type: &str, target_language: &str, version: i64) -> PromptTemplate {
+        PromptTemplate {
+            name: name.to_string(),
+            document_type: document_type.to_string(),
+            source_language: ANY.to_string(),
+            target_language: target_language.to_string(),
+            version,
+            template: format!("{} to {{target}}", name),
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn test_select_most_specific_template() {
+        let templates = PromptTemplates::new(vec![
+            template("any-ja", ANY, "ja", 1),
+            template("skill-ja", "skill", "ja", 2),
+            template("readme", "readme", ANY, 3),
+        ]);
+
+        let skill_ja = TranslationProfile::new("en", "ja");
+        assert_eq!(templates.select(&skill_ja).name, "skill-ja");
+        assert_eq!(templates.select(&skill_ja).render("en", "ja", &LanguageRules::default()), "skill-ja to Japanese");
+
+        let readme_ja = TranslationProfile {
+            document_type: "readme".to_string(),
+            ..skill_ja
+        };
+        assert_eq!(templates.select(&readme_ja).name, "any-ja");
+        assert_eq!(templates.select(&TranslationProfile::new("en", "zh-CN")).version, 0);
+    }
+
+    #[test]
+    fn test_language_rules_by_pair() {
+        let rule = |source: &str, target: &str, rules: &str| LanguageRule {
+            source_language: source.to_string(),
+            target_language: target.to_string(),
+            rules: rules.to_string(),
+            configured: false,
+        };
+        let rules = LanguageRules::new(true, vec![rule("de", "ja", "- Pair rule"), rule(ANY, "ko", "")]);
+
+        assert!(rules.select("en", "ja").unwrap().rules.contains("です/ます"));
+        assert!(!rules.select("en", "ja").unwrap().configured);
+        assert!(rules.select("de", "ja").unwrap().configured);
+        assert!(rules.select("en", "zh-CN").unwrap().rules.contains("half-width space"));
+        assert!(rules.select("en", "zh-TW").unwrap().rules.starts_with("- Use Traditional"));
+        assert!(rules.select("en", "fr").is_none());
+
+        // Configured empty rules switch the built-in ones off for the pair
+        assert_eq!(rules.render("en", "ko"), "");
+        assert_eq!(rules.render("de", "ja"), "\nJAPANESE RULES:\n- Pair rule\n");
+
+        let prompt = PromptTemplate::builtin().render("en", "ja", &rules);
+        assert!(prompt.contains("exactly as they are\n\nJAPANESE RULES:\n- Write"));
+        let plain = PromptTemplate::builtin().render("en", "fr", &rules);
+        assert!(plain.contains("exactly as they are\n\nTranslate the following content to French:"));
+        assert!(!LanguageRules::new(false, Vec::new()).render("en", "ja").contains("RULES"));
+    }
+
+    #[test]
+    fn test_numbered_lines_round_trip() {
+        let text = number_lines(&["first", "second"]);
+        assert_eq!(text, "[0] first\n[1] second");
+
+        let parsed = parse_numbered_lines("[0] 第一\nnoise\n[1]第二\n[2]\n");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[&0], "第一");
+        assert_eq!(parsed[&1], "第二");
+    }
+
+    #[test]
+    fn test_repair_prompt_lists_findings() {
+        let prompt = repair_prompt("Translate.", &["Headings differ".to_string()]);
+        assert!(prompt.starts_with("Translate.\n\n"));
+        assert!(prompt.contains("\n- Headings differ\n"));
+    }
+}
@@ -0,0 +1,114 @@
+//! Quality scoring of translations.
+//!
+//! A judge model, usually cheaper than the translating one, reads the source and
+//! its translation and rates fidelity and fluency from 1 to 5, listing concrete
+//! issues. The reply is expected as a JSON object; anything around it, such as a
+//! code fence, is ignored.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, TranslationError};
+use crate::language::language_name;
+
+/// Completion tokens allowed for a judge reply
+pub const JUDGE_MAX_TOKENS: u32 = 1000;
+
+/// Closing instruction when the judge prompt and texts share one message
+pub const JUDGE_LEAD: &str = "Reply with the JSON object only. Texts to assess:";
+
+/// System prompt for the judge; `{source}` and `{target}` are replaced by language names
+const JUDGE_TEMPLATE: &str = r#"You are a meticulous reviewer of technical translations from {source} to {target}.
+You receive a markdown document inside <source> tags and its translation inside <translation> tags.
+
+Rate the translation on two scales from 1 (unusable) to 5 (flawless):
+- fidelity: meaning, facts, commands, code and links are carried over completely and correctly
+- fluency: the translation reads naturally and uses consistent terminology in {target}
+
+List each concrete problem you find as a short sentence, at most 5, quoting the affected text.
+Answer with a JSON object only, for example:
+{"fidelity": 4, "fluency": 5, "issues": ["\"cache\" is left untranslated in the second heading"]}"#;
+
+/// A judge's rating of one translation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct QualityScore {
+    /// How completely and correctly the meaning is carried over, 1 to 5
+    pub fidelity: u8,
+    /// How naturally the translation reads, 1 to 5
+    pub fluency: u8,
+    /// Problems named by the judge
+    pub issues: Vec<String>,
+    /// Model that judged the translation
+    pub model: String,
+}
+
+impl QualityScore {
+    /// Overall score, the lower of fidelity and fluency
+    pub fn score(&self) -> u8 {
+        self.fidelity.min(self.fluency)
+    }
+}
+
+/// System prompt of a judge request for a language pair
+pub fn judge_prompt(source_language: &str, target_language: &str) -> String {
+    JUDGE_TEMPLATE
+        .replace("{source}", &language_name(source_language))
+        .replace("{target}", &language_name(target_language))
+}
+
+/// User message of a judge request
+pub fn judge_input(original: &str, translated: &str) -> String {
+    format!(
+        "<source>\n{}\n</source>\n<translation>\n{}\n</translation>",
+        original, translated
+    )
+}
+
+#[derive(Deserialize)]
+struct Judgement {
+    fidelity: f64,
+    fluency: f64,
+    #[serde(default)]
+    issues: Vec<String>,
+}
+
+/// Read the judge's reply. Scores are rounded and clamped to 1-5.
+pub fn parse_judgement(reply: &str, model: &str) -> Result<QualityScore> {
+    let invalid =
+        |reason: String| TranslationError::OpenAIError(format!("Invalid judge reply: {}", reason));
+
+    let json = match (reply.find('{'), reply.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &reply[start..=end],
+        _ => return Err(invalid("no JSON object".to_string()).into()),
+    };
+    let judgement: Judgement =
+        serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+    let scale = |score: f64| score.round().clamp(1.0, 5.0) as u8;
+
+    Ok(QualityScore {
+        fidelity: scale(judgement.fidelity),
+        fluency: scale(judgement.fluency),
+        issues: judgement.issues,
+        model: model.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_judgement() {
+        let reply = "```json\n{\"fidelity\": 2, \"fluency\": 4.6, \"issues\": [\"Step 3 is missing\"]}\n```";
+        let score = parse_judgement(reply, "gpt-4o-mini").unwrap();
+        assert_eq!((score.fidelity, score.fluency, score.score()), (2, 5, 2));
+        assert_eq!(score.issues, vec!["Step 3 is missing".to_string()]);
+
+        let clamped = parse_judgement(r#"{"fidelity": 9, "fluency": 0}"#, "m").unwrap();
+        assert_eq!((clamped.fidelity, clamped.fluency), (5, 1));
+        assert!(clamped.issues.is_empty());
+
+        assert!(parse_judgement("Looks good to me", "m").is_err());
+        assert!(judge_prompt("en", "zh-CN").contains("from English to"));
+    }
+}
@@ -0,0 +1,107 @@
+//! Back-translation checks.
+//!
+//! A translation is translated back into the source language and compared with
+//! the original section by section using the character trigram similarity of the
+//! translation [`memory`](crate::memory). Sections whose back-translation drifts
+//! far from the original are reported as hotspots, so a reviewer can start there
+//! instead of reading the whole document.
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::similarity;
+
+/// Sections whose back-translation is less similar than this are hotspots
+pub const HOTSPOT_SIMILARITY: f64 = 0.5;
+
+/// Comparison of a back-translation with the original
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RoundtripReport {
+    /// Similarity of the back-translation to the original from 0 to 1, weighted by section length
+    pub score: f64,
+    /// Whether the back-translation has the original's sections; when not, only the whole
+    /// documents were compared
+    pub sections_aligned: bool,
+    /// Sections below [`HOTSPOT_SIMILARITY`], least similar first
+    pub hotspots: Vec<Hotspot>,
+}
+
+/// A section whose back-translation differs noticeably from the original
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Hotspot {
+    /// First line of the original section, usually its heading
+    pub heading: String,
+    pub similarity: f64,
+    /// What the translation says, in the source language
+    pub back_translation: String,
+}
+
+/// Compare the sections of an original body with those of its back-translation
+pub fn compare(original: &[&str], back_translated: &[&str]) -> RoundtripReport {
+    if original.len() != back_translated.len() {
+        return RoundtripReport {
+            sections_aligned: false,
+            ..compare(&[&original.concat()], &[&back_translated.concat()])
+        };
+    }
+
+    let mut weighted = 0.0;
+    let mut total_chars = 0;
+    let mut hotspots = Vec::new();
+    for (source, back) in original.iter().zip(back_translated) {
+        let chars = source.trim().chars().count();
+        if chars == 0 {
+            continue;
+        }
+        let score = similarity(source, back);
+        weighted += score * chars as f64;
+        total_chars += chars;
+
+        if score < HOTSPOT_SIMILARITY {
+            hotspots.push(Hotspot {
+                heading: source.trim().lines().next().unwrap_or_default().to_string(),
+                similarity: round(score),
+                back_translation: back.trim().to_string(),
+            });
+        }
+    }
+    hotspots.sort_by(|a, b| a.similarity.total_cmp(&b.similarity));
+
+    RoundtripReport {
+        score: if total_chars == 0 { 1.0 } else { round(weighted / total_chars as f64) },
+        sections_aligned: true,
+        hotspots,
+    }
+}
+
+/// Scores are reported to three decimals
+fn round(score: f64) -> f64 {
+    (score * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_finds_drifting_sections() {
+        let original = [
+            "# Install\n\nRun npm install to set up the tool.\n",
+            "## Usage\n\nPass the file path as the first argument.\n",
+        ];
+        let back = [
+            "# Install\n\nRun npm install to set up the tool.\n",
+            "## Usage\n\nDelete every file in the directory.\n",
+        ];
+        let report = compare(&original, &back);
+        assert!(report.sections_aligned);
+        assert!(report.score > 0.5 && report.score < 1.0, "{}", report.score);
+        assert_eq!(report.hotspots.len(), 1);
+        assert_eq!(report.hotspots[0].heading, "## Usage");
+
+        let merged = compare(&original, &[&original.concat()]);
+        assert!(!merged.sections_aligned);
+        assert_eq!((merged.score, merged.hotspots.len()), (1.0, 0));
+    }
+}
@@ -0,0 +1,410 @@
+//! Concurrency limit shared by interactive and bulk translations.
+//!
+//! Upstream calls take a permit from a [`Scheduler`]. While permits are free
+//! they are handed out at once; otherwise callers queue by [`Priority`]. When
+//! both queues are waiting, a released permit goes to the interactive queue
+//! until it has received its configured share of the contended grants, so a
+//! large batch cannot hold up single-file requests for its whole duration.
+//!
+//! Models that can serve fewer calls at once, such as a local model next to a
+//! hosted one, get a limit of their own in [`ModelLimits`]. A call takes its
+//! model's permit after the scheduler's.
+//!
+//! With [`Scheduler::adaptive`], the limit follows the upstream instead of staying
+//! fixed: it halves when calls are rate limited or time out and then grows back
+//! one permit at a time while calls succeed.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::Serialize;
+use tokio::sync::{oneshot, OwnedSemaphorePermit, Semaphore};
+
+/// Scheduling class of a translation; it never affects what is translated or cached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Priority {
+    /// A client waiting on a single document
+    #[default]
+    Interactive,
+    /// Batches, archives, repositories and CLI runs
+    Bulk,
+}
+
+impl Priority {
+    fn index(self) -> usize {
+        match self {
+            Self::Interactive => 0,
+            Self::Bulk => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    /// Permits currently allowed, below `Scheduler::limit` while adaptive concurrency backs off
+    limit: usize,
+    /// Permits held, which can exceed `limit` right after it was lowered
+    held: usize,
+    /// Waiters by priority index
+    queues: [VecDeque<oneshot::Sender<()>>; 2],
+    /// Grants by priority index since both queues last had waiters
+    grants: [u64; 2],
+    /// Successful calls since the limit last changed
+    successes: usize,
+    /// Bumped whenever the limit is lowered, so the calls that were already
+    /// running when the upstream got overloaded lower it only once
+    generation: u64,
+}
+
+/// Current concurrency of a [`Scheduler`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ConcurrencyStatus {
+    /// Permits currently allowed
+    pub limit: usize,
+    /// Configured limit, which adaptive concurrency never exceeds
+    pub max_limit: usize,
+    pub in_use: usize,
+    pub adaptive: bool,
+}
+
+/// Weighted permit scheduler for upstream calls
+#[derive(Debug)]
+pub struct Scheduler {
+    limit: usize,
+    interactive_share: f64,
+    /// Lowest limit adaptive concurrency backs off to; `None` keeps the limit fixed
+    min_limit: Option<usize>,
+    state: Mutex<State>,
+}
+
+impl Scheduler {
+    /// Scheduler with `limit` permits, where interactive callers get `interactive_share`
+    /// (in (0, 1]) of the permits released while both classes are waiting
+    pub fn new(limit: usize, interactive_share: f64) -> Self {
+        Self {
+            limit,
+            interactive_share,
+            min_limit: None,
+            state: Mutex::new(State {
+                limit,
+                held: 0,
+                queues: [VecDeque::new(), VecDeque::new()],
+                grants: [0, 0],
+                successes: 0,
+                generation: 0,
+            }),
+        }
+    }
+
+    /// Adapt the limit to the upstream, additive increase and multiplicative decrease:
+    /// it halves, down to `min_limit`, when a call is rate limited or times out, and
+    /// grows by one after as many successful calls in a row as it allows
+    pub fn adaptive(mut self, min_limit: usize) -> Self {
+        self.min_limit = Some(min_limit);
+        self
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn interactive_share(&self) -> f64 {
+        self.interactive_share
+    }
+
+    pub fn min_limit(&self) -> Option<usize> {
+        self.min_limit
+    }
+
+    /// Permits not held by anyone
+    pub fn available_permits(&self) -> usize {
+        let state = self.lock();
+        state.limit.saturating_sub(state.held)
+    }
+
+    pub fn status(&self) -> ConcurrencyStatus {
+        let state = self.lock();
+        ConcurrencyStatus {
+            limit: state.limit,
+            max_limit: self.limit,
+            in_use: state.held,
+            adaptive: self.min_limit.is_some(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wait for a permit at the given priority
+    pub async fn acquire(self: &Arc<Self>, priority: Priority) -> Permit {
+        let receiver = {
+            let mut state = self.lock();
+            if state.held < state.limit {
+                state.held += 1;
+                return Permit {
+                    scheduler: self.clone(),
+                    generation: state.generation,
+                };
+            }
+            let (sender, receiver) = oneshot::channel();
+            state.queues[priority.index()].push_back(sender);
+            receiver
+        };
+
+        let mut waiter = Waiter {
+            scheduler: self.clone(),
+            receiver,
+            granted: false,
+        };
+        // The sender lives in our queue until it is used, so this only ends with a grant
+        let _ = (&mut waiter.receiver).await;
+        waiter.granted = true;
+
+        Permit {
+            scheduler: self.clone(),
+            generation: self.lock().generation,
+        }
+    }
+
+    /// Take back a permit and hand it to the next waiter
+    fn release(&self) {
+        let mut state = self.lock();
+        state.held -= 1;
+        self.dispatch(&mut state);
+    }
+
+    /// Hand out permits to waiters while the limit allows
+    fn dispatch(&self, state: &mut State) {
+        for queue in &mut state.queues {
+            queue.retain(|sender| !sender.is_closed());
+        }
+
+        while state.held < state.limit {
+            let [interactive, bulk] = [0, 1].map(|i| !state.queues[i].is_empty());
+            let contended = interactive && bulk;
+            let priority = if contended {
+                let total = state.grants[0] + state.grants[1];
+                if (state.grants[0] as f64) < self.interactive_share * (total + 1) as f64 {
+                    Priority::Interactive
+                } else {
+                    Priority::Bulk
+                }
+            } else if interactive {
+                Priority::Interactive
+            } else if bulk {
+                Priority::Bulk
+            } else {
+                return;
+            };
+            if !contended {
+                state.grants = [0, 0];
+            }
+
+            let Some(sender) = state.queues[priority.index()].pop_front() else {
+                continue;
+            };
+            if sender.send(()).is_ok() {
+                state.held += 1;
+                if contended {
+                    state.grants[priority.index()] += 1;
+                }
+            }
+        }
+    }
+
+    /// Raise an adaptive limit after enough successful calls
+    fn record_success(&self) {
+        if self.min_limit.is_none() {
+            return;
+        }
+        let mut state = self.lock();
+        state.successes += 1;
+        if state.successes >= state.limit && state.limit < self.limit {
+            state.limit += 1;
+            state.successes = 0;
+            tracing::debug!("Raised upstream concurrency to {}", state.limit);
+            self.dispatch(&mut state);
+        }
+    }
+
+    /// Halve an adaptive limit, once for all the calls of a generation
+    fn record_overload(&self, generation: u64) {
+        let Some(min_limit) = self.min_limit else {
+            return;
+        };
+        let mut state = self.lock();
+        if generation != state.generation {
+            return;
+        }
+        state.generation += 1;
+        state.successes = 0;
+        let limit = (state.limit / 2).max(min_limit).max(1);
+        if limit < state.limit {
+            state.limit = limit;
+            tracing::warn!("Upstream overloaded, lowered concurrency to {}", limit);
+        }
+    }
+}
+
+/// A queued `acquire`; when dropped before finishing, returns a permit it was already granted
+struct Waiter {
+    scheduler: Arc<Scheduler>,
+    receiver: oneshot::Receiver<()>,
+    granted: bool,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        if !self.granted {
+            self.receiver.close();
+            if self.receiver.try_recv().is_ok() {
+                self.scheduler.release();
+            }
+        }
+    }
+}
+
+/// Held for the duration of one upstream call; returned to the scheduler on drop
+#[derive(Debug)]
+pub struct Permit {
+    scheduler: Arc<Scheduler>,
+    /// Generation of the scheduler's limit when the permit was granted
+    generation: u64,
+}
+
+impl Permit {
+    /// The call succeeded, which lets an adaptive limit grow
+    pub fn succeeded(&self) {
+        self.scheduler.record_success();
+    }
+
+    /// The call was rate limited or timed out, which lowers an adaptive limit
+    pub fn overloaded(&self) {
+        self.scheduler.record_overload(self.generation);
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.scheduler.release();
+    }
+}
+
+/// Concurrent upstream calls allowed per model, for models with a limit of their own
+#[derive(Debug, Default)]
+pub struct ModelLimits {
+    semaphores: HashMap<String, (usize, Arc<Semaphore>)>,
+}
+
+impl ModelLimits {
+    /// Limits by model name. Models whose limit is unchanged from `current` keep its
+    /// semaphore, and so the permits held on it.
+    pub fn new(limits: &[(String, usize)], current: Option<&ModelLimits>) -> Self {
+        let semaphores = limits
+            .iter()
+            .map(|(model, limit)| {
+                let semaphore = current
+                    .and_then(|current| current.semaphores.get(model))
+                    .filter(|(current_limit, _)| current_limit == limit)
+                    .map(|(_, semaphore)| semaphore.clone())
+                    .unwrap_or_else(|| Arc::new(Semaphore::new(*limit)));
+                (model.clone(), (*limit, semaphore))
+            })
+            .collect();
+        Self { semaphores }
+    }
+
+    /// Limit of a model, if it has one
+    pub fn limit(&self, model: &str) -> Option<usize> {
+        self.semaphores.get(model).map(|(limit, _)| *limit)
+    }
+
+    /// Wait for a permit of the model; models without a limit need none
+    pub async fn acquire(&self, model: &str) -> Option<OwnedSemaphorePermit> {
+        let (_, semaphore) = self.semaphores.get(model)?;
+        // The semaphore is never closed
+        semaphore.clone().acquire_owned().await.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_contended_permits_follow_interactive_share() {
+        let scheduler = Arc::new(Scheduler::new(1, 0.75));
+        let held = scheduler.acquire(Priority::Bulk).await;
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for priority in [Priority::Bulk; 4].into_iter().chain([Priority::Interactive; 4]) {
+            let (scheduler, order) = (scheduler.clone(), order.clone());
+            tasks.push(tokio::spawn(async move {
+                let _permit = scheduler.acquire(priority).await;
+                order.lock().unwrap().push(priority);
+            }));
+            tokio::task::yield_now().await;
+        }
+
+        // A cancelled waiter gives up its place without losing a permit
+        let cancelled = tokio::time::timeout(Duration::from_millis(10), scheduler.acquire(Priority::Interactive));
+        assert!(cancelled.await.is_err());
+
+        drop(held);
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        use Priority::{Bulk as B, Interactive as I};
+        assert_eq!(*order.lock().unwrap(), vec![I, I, I, B, I, B, B, B]);
+        assert_eq!(scheduler.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_model_limits() {
+        let limits = ModelLimits::new(&[("llama3".to_string(), 1)], None);
+        assert_eq!(limits.limit("llama3"), Some(1));
+        assert!(limits.acquire("gpt-4o").await.is_none());
+
+        let held = limits.acquire("llama3").await;
+        assert!(held.is_some());
+        let waiting = tokio::time::timeout(Duration::from_millis(10), limits.acquire("llama3"));
+        assert!(waiting.await.is_err());
+
+        // A reload keeping the limit keeps the permit held; a new limit starts afresh
+        let kept = ModelLimits::new(&[("llama3".to_string(), 1)], Some(&limits));
+        assert!(tokio::time::timeout(Duration::from_millis(10), kept.acquire("llama3")).await.is_err());
+        let raised = ModelLimits::new(&[("llama3".to_string(), 2)], Some(&limits));
+        assert!(raised.acquire("llama3").await.is_some());
+        drop(held);
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_limit() {
+        let scheduler = Arc::new(Scheduler::new(4, 0.75).adaptive(1));
+        let first = scheduler.acquire(Priority::Bulk).await;
+        let second = scheduler.acquire(Priority::Bulk).await;
+
+        // Calls running together when the upstream is overloaded halve the limit once
+        first.overloaded();
+        second.overloaded();
+        assert_eq!(scheduler.status().limit, 2);
+        drop(first);
+        let third = scheduler.acquire(Priority::Bulk).await;
+        third.overloaded();
+        assert_eq!(scheduler.status().limit, 1);
+        drop((second, third));
+
+        // It grows back by one after as many successes as it allows, up to the configured limit
+        for _ in 0..20 {
+            scheduler.acquire(Priority::Bulk).await.succeeded();
+        }
+        assert_eq!(
+            scheduler.status(),
+            ConcurrencyStatus { limit: 4, max_limit: 4, in_use: 0, adaptive: true }
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Terminology shared by the documents of one skill.
+//!
+//! Besides SKILL.md, a skill often ships reference.md or examples/*.md naming the
+//! same commands and concepts. Once one of its documents is translated, the
+//! renderings chosen for its headings and bold terms are paired up as [`Term`]s
+//! and sent to the model with the next document, so a term reads the same across
+//! all files of the skill.
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+use crate::parser::ContentParser;
+
+/// Terms added to one prompt at most, the first ones found winning
+pub const MAX_TERMS: usize = 60;
+
+/// Terms longer than this are sentences rather than names and are left out
+const MAX_TERM_CHARS: usize = 80;
+
+/// A term of a source document and its rendering in the translation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub source: String,
+    pub translation: String,
+}
+
+/// Terms of a document and its translation: headings and bold spans paired in
+/// document order. A kind whose count differs between the two cannot be paired
+/// reliably and yields nothing.
+pub fn extract_terms(source: &str, translation: &str) -> Vec<Term> {
+    let parser = ContentParser::new();
+    let (source, translation) = (parser.parse(source), parser.parse(translation));
+    let (source, translation) = (spans(&source.body), spans(&translation.body));
+
+    let mut terms = Vec::new();
+    for (source, translation) in [(source.0, translation.0), (source.1, translation.1)] {
+        if source.len() != translation.len() {
+            continue;
+        }
+        let pairs = source.into_iter().zip(translation).map(|(source, translation)| Term {
+            source,
+            translation,
+        });
+        merge(&mut terms, pairs);
+    }
+    terms
+}
+
+/// Add terms not yet known by their source, keeping at most [`MAX_TERMS`]
+pub fn merge(terms: &mut Vec<Term>, new: impl IntoIterator<Item = Term>) {
+    for term in new {
+        if terms.len() >= MAX_TERMS {
+            break;
+        }
+        let usable = !term.source.is_empty()
+            && !term.translation.is_empty()
+            && term.source.chars().count() <= MAX_TERM_CHARS;
+        if usable && !terms.iter().any(|known| known.source == term.source) {
+            terms.push(term);
+        }
+    }
+}
+
+/// System prompt extended with the renderings other documents of the skill use
+pub fn terminology_prompt(prompt: &str, terms: &[Term]) -> String {
+    let mut prompt = format!(
+        "{}\n\nOther files and earlier versions of the same skill were translated with the renderings below. \
+         Where these terms occur, render them the same way:",
+        prompt
+    );
+    for term in terms {
+        prompt.push_str(&format!("\n- {} => {}", term.source, term.translation));
+    }
+    prompt
+}
+
+/// Texts of the headings and of the bold spans of a markdown body, in order
+fn spans(body: &str) -> (Vec<String>, Vec<String>) {
+    let (mut headings, mut bold) = (Vec::new(), Vec::new());
+    let mut current: Option<(bool, String)> = None;
+    for event in Parser::new(body) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => current = Some((true, String::new())),
+            Event::Start(Tag::Strong) if current.is_none() => current = Some((false, String::new())),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, span)) = current.as_mut() {
+                    span.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) | Event::End(TagEnd::Strong) => {
+                let is_heading = matches!(event, Event::End(TagEnd::Heading(_)));
+                if current.as_ref().is_some_and(|(heading, _)| *heading == is_heading) {
+                    let (_, span) = current.take().unwrap_or_default();
+                    let span = span.trim().to_string();
+                    if is_heading {
+                        headings.push(span);
+                    } else {
+                        bold.push(span);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    (headings, bold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_and_merge_terms() {
+        let source = "---\nname: demo\n---\n# Demo Skill\n\nUse the **Skill Registry** to find `demo`.\n\n\
+                      ## Usage\n\n```bash\n# not a heading\n```\n\nSee **Registry** and **Skill Registry**.\n";
+        let translation = "---\nname: demo\n---\n# 演示技能\n\n使用**技能注册表**查找 `demo`。\n\n\
+                           ## 用法\n\n```bash\n# not a heading\n```\n\n参见**注册表**和**技能注册表**。\n";
+        let terms = extract_terms(source, translation);
+        let pairs: Vec<(&str, &str)> = terms
+            .iter()
+            .map(|term| (term.source.as_str(), term.translation.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![("Demo Skill", "演示技能"), ("Usage", "用法"), ("Skill Registry", "技能注册表"), ("Registry", "注册表")]
+        );
+
+        // Bold spans that do not line up are dropped, headings still pair
+        let terms = extract_terms("# Usage\n\n**a** and **b**\n", "# 用法\n\n**甲和乙**\n");
+        assert_eq!(terms, vec![Term { source: "Usage".to_string(), translation: "用法".to_string() }]);
+
+        let mut known = terms;
+        merge(
+            &mut known,
+            [
+                Term { source: "Usage".to_string(), translation: "使用".to_string() },
+                Term { source: "Setup".to_string(), translation: "设置".to_string() },
+            ],
+        );
+        assert_eq!(known.len(), 2);
+        assert_eq!(known[0].translation, "用法");
+
+        let prompt = terminology_prompt("Translate.", &known);
+        assert!(prompt.starts_with("Translate.\n\n") && prompt.ends_with("\n- Usage => 用法\n- Setup => 设置"));
+    }
+}
@@ -0,0 +1,3157 @@
+//! Translation engine using OpenAI API.
+//!
+//! Supports streaming responses, concurrent translation control, and retry logic
+//! with exponential backoff that honours upstream rate-limit hints. Besides
+//! OpenAI-compatible endpoints, self-hosted Ollama servers are spoken to with
+//! their native `/api/chat` protocol.
+
+use async_openai::types::{
+    ChatCompletionRequestMessage, ChatCompletionRequestSystemMessageArgs,
+    ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs,
+    CreateChatCompletionStreamResponse,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use eventsource_stream::Eventsource;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::StreamExt;
+use rand::Rng;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use reqwest::header::HeaderMap;
+use sha2::{Digest, Sha256};
+use arc_swap::ArcSwap;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+
+use crate::anchors::{self, AnchorMode};
+use crate::cache::TranslationCache;
+use crate::comments;
+use crate::error::{Error, Result, TranslationError};
+use crate::language::{self, same_language};
+use crate::memory::{self, MemoryPair, MemoryScope};
+use crate::models::{CircuitBreakerStatus, ValidationReport};
+use crate::parser::{ContentParser, ParsedContent};
+use crate::scheduler::{ConcurrencyStatus, ModelLimits, Permit, Priority, Scheduler};
+use crate::bilingual::OutputMode;
+use crate::keep_terms;
+use crate::terminology::{self, Term};
+use crate::quality::{self, QualityScore, JUDGE_LEAD, JUDGE_MAX_TOKENS};
+use crate::roundtrip::{self, RoundtripReport};
+use crate::prompt::{
+    fragment_prompt, number_lines, parse_numbered_lines, repair_prompt, LanguageRules, PromptTemplate,
+    PromptTemplates, BUILTIN_TEMPLATE, DEFAULT_DOCUMENT_TYPE,
+};
+use crate::validate::validate_translation;
+
+/// Upper bound for a single backoff or Retry-After wait
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Closing instruction of translation requests sent in the plain prompt style
+const TRANSLATION_LEAD: &str = "Reply with the translation only. Text to translate:";
+
+/// Translation engine for SKILL.md files using OpenAI API
+pub struct Translator {
+    http: reqwest::Client,
+    provider: Provider,
+    prompt_style: PromptStyle,
+    api_key: String,
+    base_url: String,
+    /// Settings that can be swapped at runtime by `reconfigure`
+    runtime: ArcSwap<Runtime>,
+    parser: ContentParser,
+    /// Built-in and saved system prompt templates
+    prompts: ArcSwap<PromptTemplates>,
+    translator_version: String,
+    /// Upstream attempts per model, the first included
+    max_retries: u32,
+    retry_delay: Duration,
+    breaker: CircuitBreaker,
+}
+
+/// Reloadable translator settings.
+/// A translation works on one snapshot, so a reload never changes models mid-file.
+struct Runtime {
+    /// Primary model followed by its fallbacks
+    models: Vec<String>,
+    max_tokens: u32,
+    chunk_max_tokens: usize,
+    /// Replaced when the concurrency limits or interactive share change; in-flight
+    /// permits drain the old one
+    scheduler: Arc<Scheduler>,
+    /// Limits of models that can serve fewer calls at once than the scheduler allows
+    model_limits: ModelLimits,
+    timeout_seconds: u64,
+    translation_memory: bool,
+    memory_min_similarity: f64,
+    /// Model scoring translations when a quality check is requested
+    judge_model: String,
+    language_rules: LanguageRules,
+}
+
+impl Runtime {
+    fn new(config: &TranslatorConfig, current: Option<&Runtime>, scheduler: Option<Arc<Scheduler>>) -> Self {
+        Self {
+            models: std::iter::once(config.model.clone())
+                .chain(
+                    config
+                        .model_fallbacks
+                        .iter()
+                        .filter(|m| **m != config.model)
+                        .cloned(),
+                )
+                .collect(),
+            max_tokens: config.max_tokens,
+            chunk_max_tokens: config.chunk_max_tokens,
+            scheduler: scheduler.unwrap_or_else(|| {
+                let scheduler = Scheduler::new(config.max_concurrent_translations, config.interactive_share);
+                Arc::new(match config.adaptive_concurrency {
+                    true => scheduler.adaptive(config.min_concurrent_translations),
+                    false => scheduler,
+                })
+            }),
+            model_limits: ModelLimits::new(&config.model_concurrency, current.map(|c| &c.model_limits)),
+            timeout_seconds: config.timeout_seconds,
+            translation_memory: config.translation_memory,
+            memory_min_similarity: config.memory_min_similarity,
+            judge_model: if config.judge_model.is_empty() {
+                config.model.clone()
+            } else {
+                config.judge_model.clone()
+            },
+            language_rules: config.language_rules.clone(),
+        }
+    }
+}
+
+/// Circuit breaker state for the upstream API
+#[derive(Debug)]
+struct BreakerState {
+    consecutive_failures: u32,
+    /// Set while the circuit is open (or half-open after the cooldown)
+    opened_at: Option<Instant>,
+    /// When the single half-open probe was let through
+    probe_started: Option<Instant>,
+}
+
+/// Fails fast after repeated upstream failures instead of spending the full retry budget.
+///
+/// Closed: calls pass through. After `threshold` consecutive failures the circuit
+/// opens and calls fail immediately. Once `cooldown` has elapsed it half-opens and
+/// lets one probe through; success closes it, failure re-opens it.
+#[derive(Debug)]
+struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            state: Mutex::new(BreakerState {
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_started: None,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Check whether a call may proceed. Returns the remaining cooldown when open.
+    fn allow(&self) -> Result<(), Duration> {
+        if self.threshold == 0 {
+            return Ok(());
+        }
+
+        let mut state = self.lock();
+        let Some(opened_at) = state.opened_at else {
+            return Ok(());
+        };
+
+        let elapsed = opened_at.elapsed();
+        if elapsed < self.cooldown {
+            return Err(self.cooldown - elapsed);
+        }
+
+        // Half-open: allow one probe; a probe that never reported back is replaced after another cooldown
+        match state.probe_started {
+            Some(started) if started.elapsed() < self.cooldown => {
+                Err(self.cooldown - started.elapsed())
+            }
+            _ => {
+                state.probe_started = Some(Instant::now());
+                Ok(())
+            }
+        }
+    }
+
+    fn record_success(&self) {
+        let mut state = self.lock();
+        if state.opened_at.is_some() {
+            tracing::info!("Upstream recovered, closing circuit breaker");
+        }
+        state.consecutive_failures = 0;
+        state.opened_at = None;
+        state.probe_started = None;
+    }
+
+    fn record_failure(&self) {
+        if self.threshold == 0 {
+            return;
+        }
+
+        let mut state = self.lock();
+        state.consecutive_failures += 1;
+        let probe_failed = state.probe_started.take().is_some();
+        if probe_failed || (state.opened_at.is_none() && state.consecutive_failures >= self.threshold) {
+            tracing::warn!(
+                "Opening circuit breaker after {} consecutive upstream failures",
+                state.consecutive_failures
+            );
+            state.opened_at = Some(Instant::now());
+        }
+    }
+
+    fn status(&self) -> CircuitBreakerStatus {
+        let state = self.lock();
+        let (name, retry_in) = match state.opened_at {
+            None => ("closed", None),
+            Some(opened_at) if opened_at.elapsed() < self.cooldown => {
+                ("open", Some((self.cooldown - opened_at.elapsed()).as_secs()))
+            }
+            Some(_) => ("half_open", None),
+        };
+
+        CircuitBreakerStatus {
+            state: name.to_string(),
+            consecutive_failures: state.consecutive_failures,
+            retry_in_seconds: retry_in,
+        }
+    }
+}
+
+/// Result of translating one piece of text
+struct TextTranslation {
+    text: String,
+    retries: u32,
+    /// Index into the model chain of the model that produced the text
+    model_index: usize,
+    /// Prompt tokens sent, including the system prompt
+    input_tokens: usize,
+    /// Tokens in the translated text
+    output_tokens: usize,
+    /// Number of upstream calls the text was split into
+    chunks: usize,
+}
+
+impl TextTranslation {
+    /// Text kept as it is, without an upstream call
+    fn unchanged(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            retries: 0,
+            model_index: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            chunks: 0,
+        }
+    }
+}
+
+/// Previously translated material a document translation may reuse
+enum Reuse<'a> {
+    Nothing,
+    /// Body sections from the segment cache; new sections are stored back
+    SegmentCache(&'a TranslationCache),
+    /// Sections and description of an older version of the document
+    Prior(PriorTranslation),
+}
+
+/// Sections of an older document version paired with their translations
+struct PriorTranslation {
+    /// Translated section by segment key
+    sections: HashMap<String, String>,
+    /// Original and translated description
+    description: Option<(String, String)>,
+}
+
+/// Earlier translations `translate_sections` draws on, by segment key
+struct KnownSections<'a> {
+    /// Translations taken as-is
+    translated: &'a HashMap<String, String>,
+    /// Similar sections from the translation memory, sent to the model as examples
+    examples: &'a HashMap<String, MemoryPair>,
+}
+
+/// A parsed document and what its body is translated with
+#[derive(Clone, Copy)]
+struct DocumentParts<'a> {
+    parsed: &'a ParsedContent,
+    /// Body with code blocks, tables and HTML blocks replaced by placeholders
+    body_with_placeholders: &'a str,
+    profile: &'a TranslationProfile,
+    prompt: &'a str,
+}
+
+/// A translated body
+struct BodyPart {
+    /// The parsed content with its table cells, HTML text and code comments translated
+    restored: ParsedContent,
+    fragment_translations: Vec<TextTranslation>,
+    translation: TextTranslation,
+    /// Empty unless the body was translated section by section
+    sections: Vec<SectionOutcome>,
+}
+
+/// A translated document with the outcome of each body section
+struct DocumentTranslation {
+    content: String,
+    metadata: TranslationMetadata,
+    /// Empty unless the body was translated section by section
+    sections: Vec<SectionOutcome>,
+}
+
+/// How one body section was produced
+#[derive(Debug, Clone)]
+pub struct SectionOutcome {
+    /// First line of the section, usually its heading
+    pub heading: String,
+    /// Taken from an earlier translation instead of translated again
+    pub reused: bool,
+}
+
+/// Result of `Translator::translate_delta`
+#[derive(Debug, Clone)]
+pub struct DeltaTranslation {
+    pub content: String,
+    pub metadata: TranslationMetadata,
+    pub sections: Vec<SectionOutcome>,
+}
+
+/// What translating a document would send upstream, computed without any API call
+#[derive(Debug, Clone)]
+pub struct TranslationPreview {
+    /// Frontmatter fields that would be translated, with their current values
+    pub frontmatter_fields: Vec<(String, String)>,
+    /// Body with code blocks, tables and HTML blocks replaced by placeholders, as sent to the model
+    pub body_with_placeholders: String,
+    pub code_block_count: usize,
+    /// Number of upstream calls the translation would make
+    pub api_calls: usize,
+    /// Prompt tokens across all calls, including the system prompt
+    pub estimated_input_tokens: usize,
+    /// Estimated completion tokens, assuming output about as long as the input text
+    pub estimated_output_tokens: usize,
+}
+
+/// Metadata for translation result
+#[derive(Debug, Clone)]
+pub struct TranslationMetadata {
+    pub original_chars: usize,
+    pub translated_chars: usize,
+    pub processing_time_ms: f64,
+    pub translator_version: String,
+    /// Model that produced the translation (a fallback if the primary failed)
+    pub model: String,
+    pub source_language: String,
+    pub target_language: String,
+    /// Number of upstream retries needed across all API calls
+    pub retries: u32,
+    /// Prompt tokens sent across all API calls, including the system prompt
+    pub input_tokens: usize,
+    /// Tokens in the translated text
+    pub output_tokens: usize,
+    /// Number of chunks the body and description were split into
+    pub chunks: usize,
+    /// Body sections reused from the segment cache instead of translated
+    pub cached_segments: usize,
+    /// Time spent translating the body, including table cells, HTML text and code comments
+    pub body_time_ms: f64,
+    /// Time spent translating the frontmatter description, alongside the body
+    pub description_time_ms: f64,
+    /// Structural problems still present after the repair retry
+    pub warnings: Vec<String>,
+    /// Judge's rating, when a quality check was requested and succeeded
+    pub quality: Option<QualityScore>,
+    /// Comparison of a back-translation with the original, when requested and it succeeded
+    pub roundtrip: Option<RoundtripReport>,
+}
+
+/// API protocol of the upstream model server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Provider {
+    /// OpenAI or a compatible `/chat/completions` endpoint
+    #[default]
+    OpenAi,
+    /// An Ollama server's native `/api/chat` endpoint
+    Ollama,
+}
+
+impl FromStr for Provider {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "openai" | "" => Ok(Provider::OpenAi),
+            "ollama" => Ok(Provider::Ollama),
+            other => Err(format!("unknown provider '{}', expected 'openai' or 'ollama'", other)),
+        }
+    }
+}
+
+impl fmt::Display for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Provider::OpenAi => "openai",
+            Provider::Ollama => "ollama",
+        })
+    }
+}
+
+/// How the system prompt reaches the model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PromptStyle {
+    /// A system message followed by the text as user message
+    #[default]
+    Chat,
+    /// One user message with the instructions before the text, for models whose
+    /// templates ignore or lack a system role
+    Plain,
+}
+
+impl FromStr for PromptStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "chat" | "" => Ok(PromptStyle::Chat),
+            "plain" => Ok(PromptStyle::Plain),
+            other => Err(format!("unknown prompt style '{}', expected 'chat' or 'plain'", other)),
+        }
+    }
+}
+
+impl PromptStyle {
+    /// `(role, content)` messages of one upstream request; in the plain style
+    /// `lead` goes between the instructions and the text
+    fn messages(self, prompt: &str, text: &str, lead: &str) -> Vec<(&'static str, String)> {
+        match self {
+            PromptStyle::Chat => vec![("system", prompt.to_string()), ("user", text.to_string())],
+            PromptStyle::Plain => vec![("user", format!("{}\n\n{}\n\n{}", prompt, lead, text))],
+        }
+    }
+}
+
+/// Translator configuration
+#[derive(Debug, Clone)]
+pub struct TranslatorConfig {
+    pub provider: Provider,
+    pub prompt_style: PromptStyle,
+    /// API key; requests are sent without authentication when empty
+    pub api_key: String,
+    /// Base URL of the API: OpenAI-compatible ones usually end in `/v1`, Ollama's is the server root
+    pub base_url: String,
+    /// Primary model
+    pub model: String,
+    /// Models tried in order when the primary model fails
+    pub model_fallbacks: Vec<String>,
+    pub max_tokens: u32,
+    /// Text longer than this many tokens is split at paragraph or line
+    /// boundaries and translated chunk by chunk
+    pub chunk_max_tokens: usize,
+    /// Part of every cache key; bump to invalidate cached translations
+    pub translator_version: String,
+    pub max_concurrent_translations: usize,
+    /// Halve the concurrency limit when calls are rate limited or time out, and grow it
+    /// back towards `max_concurrent_translations` while they succeed
+    pub adaptive_concurrency: bool,
+    /// Lowest limit adaptive concurrency backs off to
+    pub min_concurrent_translations: usize,
+    /// Concurrent calls allowed per model name, for models that cannot take `max_concurrent_translations`
+    pub model_concurrency: Vec<(String, usize)>,
+    /// Share of contended permits given to interactive translations over bulk ones, in (0, 1]
+    pub interactive_share: f64,
+    /// Timeout for one piece of text per model, including retries
+    pub timeout_seconds: u64,
+    /// Consecutive failures before the circuit breaker opens (0 disables it)
+    pub circuit_breaker_threshold: u32,
+    pub circuit_breaker_cooldown_seconds: u64,
+    /// Reuse and learn from sections of other documents when translating with segments
+    pub translation_memory: bool,
+    /// Minimum trigram similarity for a stored section to be sent as an example
+    pub memory_min_similarity: f64,
+    /// Model scoring translations for quality checks; the primary model when empty
+    pub judge_model: String,
+    /// Instructions for particular language pairs added to the system prompt
+    pub language_rules: LanguageRules,
+}
+
+impl Default for TranslatorConfig {
+    fn default() -> Self {
+        Self {
+            provider: Provider::OpenAi,
+            prompt_style: PromptStyle::Chat,
+            api_key: String::new(),
+            base_url: "https://api.openai.com/v1".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            model_fallbacks: Vec::new(),
+            max_tokens: 16000,
+            chunk_max_tokens: 6000,
+            translator_version: "1.0.0".to_string(),
+            max_concurrent_translations: 5,
+            adaptive_concurrency: false,
+            min_concurrent_translations: 1,
+            model_concurrency: Vec::new(),
+            interactive_share: 0.75,
+            timeout_seconds: 600,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_seconds: 30,
+            translation_memory: false,
+            memory_min_similarity: 0.8,
+            judge_model: String::new(),
+            language_rules: LanguageRules::new(true, Vec::new()),
+        }
+    }
+}
+
+/// Tenant of requests that do not name one; its cache keys carry no tenant part
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Which parts of a document are translated; the others are kept as they are
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum TranslationScope {
+    /// The frontmatter description and the body
+    #[default]
+    Full,
+    /// Only the frontmatter description, e.g. for a registry listing
+    Frontmatter,
+    /// Only the body
+    Body,
+}
+
+impl TranslationScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TranslationScope::Full => "full",
+            TranslationScope::Frontmatter => "frontmatter",
+            TranslationScope::Body => "body",
+        }
+    }
+}
+
+/// What a document is translated for: its language pair and document type.
+/// The document type and languages select the system prompt template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranslationProfile {
+    /// Tenant whose cache entries the translation is stored under
+    pub tenant: String,
+    /// Source language code, or `auto` to detect it
+    pub source_language: String,
+    pub target_language: String,
+    pub document_type: String,
+    /// Also translate comments and docstrings inside code blocks
+    pub translate_code_comments: bool,
+    /// How intra-document links to translated headings are kept working
+    pub anchor_mode: AnchorMode,
+    /// Parts of the document to translate; partial scopes are cached apart from full translations
+    pub scope: TranslationScope,
+    /// Keep code blocks out of the text sent to the model; when false they are
+    /// translated as prose, for skills whose code is pseudocode
+    pub preserve_code_blocks: bool,
+    /// Queue the translation's upstream calls wait in; not part of any cache key
+    pub priority: Priority,
+    /// Timeout for one piece of text per model instead of the configured one
+    pub timeout_seconds: Option<u64>,
+    /// Retries of a failed upstream call instead of the built-in two
+    pub max_retries: Option<u32>,
+    /// Have the judge model score the finished translation; not part of any cache key
+    pub quality_check: bool,
+    /// Translate the result back and compare it with the original; not part of any cache key
+    pub verify_roundtrip: bool,
+    /// Told about each piece of text as it is sent upstream; not part of any cache key
+    pub progress: Option<ProgressSink>,
+    /// Translate every section anew instead of reusing cached segments or the
+    /// translation memory; fresh results still replace what is stored
+    pub force: bool,
+    /// Renderings chosen in other documents of the same skill, added to the prompt;
+    /// not part of any cache key
+    pub terminology: Vec<Term>,
+    /// Terms hidden from the model behind placeholders so they stay untranslated
+    pub keep_terms: Vec<String>,
+    /// Shape of the document returned to the caller; not part of any cache key
+    pub output_mode: OutputMode,
+}
+
+impl TranslationProfile {
+    /// Profile for a SKILL.md file
+    pub fn new(source_language: impl Into<String>, target_language: impl Into<String>) -> Self {
+        Self {
+            tenant: DEFAULT_TENANT.to_string(),
+            source_language: source_language.into(),
+            target_language: target_language.into(),
+            document_type: DEFAULT_DOCUMENT_TYPE.to_string(),
+            translate_code_comments: false,
+            anchor_mode: AnchorMode::None,
+            scope: TranslationScope::Full,
+            preserve_code_blocks: true,
+            priority: Priority::Interactive,
+            timeout_seconds: None,
+            max_retries: None,
+            quality_check: false,
+            verify_roundtrip: false,
+            progress: None,
+            force: false,
+            terminology: Vec::new(),
+            keep_terms: Vec::new(),
+            output_mode: OutputMode::Replace,
+        }
+    }
+}
+
+/// Upstream call a document translation is at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// 1-based number of the call just started
+    pub chunk: usize,
+    /// Calls the document is expected to take, estimated like `Translator::preview`.
+    /// Repairs and back-translations can take more, in which case it grows with `chunk`.
+    pub total: usize,
+}
+
+/// Callback receiving the [`Progress`] of a translation
+#[derive(Clone)]
+pub struct ProgressSink(Arc<dyn Fn(Progress) + Send + Sync>);
+
+impl ProgressSink {
+    pub fn new(report: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(report))
+    }
+
+    pub fn report(&self, progress: Progress) {
+        (self.0)(progress)
+    }
+}
+
+impl fmt::Debug for ProgressSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressSink")
+    }
+}
+
+/// Sinks are equal when they are clones of one another
+impl PartialEq for ProgressSink {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ProgressSink {}
+
+/// Counts the upstream calls of a job for its progress sink
+struct JobProgress {
+    sink: ProgressSink,
+    expected: usize,
+    started: AtomicUsize,
+}
+
+impl JobProgress {
+    fn report(&self) {
+        let chunk = self.started.fetch_add(1, Ordering::Relaxed) + 1;
+        self.sink.report(Progress {
+            chunk,
+            total: self.expected.max(chunk),
+        });
+    }
+}
+
+/// Model and limits of one upstream call
+#[derive(Clone, Copy)]
+struct UpstreamCall<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    /// Attempts, the first included
+    attempts: u32,
+    /// Scheduler permit the call runs under, told how the upstream coped
+    permit: &'a Permit,
+}
+
+/// What one document is translated with: a runtime snapshot, the priority of its
+/// calls and the profile's timeout and retry overrides resolved against the defaults
+struct Job<'a> {
+    runtime: Arc<Runtime>,
+    priority: Priority,
+    timeout_seconds: u64,
+    /// Upstream attempts per model, the first included
+    attempts: u32,
+    progress: Option<JobProgress>,
+    /// Normalized terms protected in every text sent upstream
+    keep_terms: Vec<String>,
+    partials: Option<Partials<'a>>,
+}
+
+/// Upstream results of one document, stored as they arrive so that a retry after a
+/// crash or timeout resumes from them instead of translating everything again
+struct Partials<'a> {
+    cache: &'a TranslationCache,
+    tenant: String,
+    /// Whether stored results are reused; forced translations only store them
+    reuse: bool,
+    /// Keys of the results stored or reused, deleted once the document is translated
+    keys: Mutex<Vec<String>>,
+}
+
+impl Partials<'_> {
+    async fn get(&self, partial_key: &str) -> Option<String> {
+        if !self.reuse {
+            return None;
+        }
+        match self.cache.get_partial(partial_key).await {
+            Ok(Some(translated)) => {
+                self.keys().push(partial_key.to_string());
+                Some(translated)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!("Partial translation lookup failed: {}", e);
+                None
+            }
+        }
+    }
+
+    async fn set(&self, partial_key: String, translated: &str) {
+        if let Err(e) = self.cache.set_partial(&self.tenant, &partial_key, translated).await {
+            tracing::warn!("Failed to store partial translation: {}", e);
+        }
+        self.keys().push(partial_key);
+    }
+
+    /// Delete the results of the finished document
+    async fn clear(&self) {
+        let keys = std::mem::take(&mut *self.keys());
+        if let Err(e) = self.cache.delete_partials(&keys).await {
+            tracing::warn!("Failed to delete partial translations: {}", e);
+        }
+    }
+
+    fn keys(&self) -> std::sync::MutexGuard<'_, Vec<String>> {
+        self.keys.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Translator {
+    /// Create a new translator instance
+    pub fn new(config: TranslatorConfig) -> Self {
+        let runtime = Runtime::new(&config, None, None);
+
+        Self {
+            http: reqwest::Client::new(),
+            provider: config.provider,
+            prompt_style: config.prompt_style,
+            api_key: config.api_key,
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            runtime: ArcSwap::from_pointee(runtime),
+            parser: ContentParser::new(),
+            prompts: ArcSwap::from_pointee(PromptTemplates::default()),
+            translator_version: config.translator_version,
+            max_retries: 3,
+            retry_delay: Duration::from_secs(2),
+            breaker: CircuitBreaker::new(
+                config.circuit_breaker_threshold,
+                Duration::from_secs(config.circuit_breaker_cooldown_seconds),
+            ),
+        }
+    }
+
+    /// Apply the reloadable part of a new configuration: models, max tokens,
+    /// chunk size, concurrency limits and interactive share, timeout, translation memory,
+    /// judge model and language rules. Credentials, base URL, translator version
+    /// and circuit breaker settings keep their startup values.
+    pub fn reconfigure(&self, config: &TranslatorConfig) {
+        let current = self.runtime.load();
+        // Keep the scheduler, and the permits held on it, unless its settings changed
+        let min_limit = config.adaptive_concurrency.then_some(config.min_concurrent_translations);
+        let scheduler = (current.scheduler.limit() == config.max_concurrent_translations
+            && current.scheduler.interactive_share() == config.interactive_share
+            && current.scheduler.min_limit() == min_limit)
+            .then(|| current.scheduler.clone());
+        self.runtime.store(Arc::new(Runtime::new(config, Some(&**current), scheduler)));
+    }
+
+    /// Primary model followed by its fallbacks
+    pub fn models(&self) -> Vec<String> {
+        self.runtime.load().models.clone()
+    }
+
+    /// Current concurrency limit of upstream calls and the permits in use
+    pub fn concurrency(&self) -> ConcurrencyStatus {
+        self.runtime.load().scheduler.status()
+    }
+
+    /// Current state of the upstream circuit breaker
+    pub fn circuit_status(&self) -> CircuitBreakerStatus {
+        self.breaker.status()
+    }
+
+    /// Replace the saved prompt template overrides.
+    /// Translations already running keep the prompt they started with.
+    pub fn set_prompt_templates(&self, overrides: Vec<PromptTemplate>) {
+        self.prompts.store(Arc::new(PromptTemplates::new(overrides)));
+    }
+
+    /// Prompt template a translation with this profile would use
+    pub fn prompt_template(&self, profile: &TranslationProfile) -> PromptTemplate {
+        self.prompts.load().select(profile).clone()
+    }
+
+    /// System prompt of a document translated between two languages
+    fn system_prompt(&self, profile: &TranslationProfile, source_language: &str, target_language: &str) -> String {
+        self.prompts
+            .load()
+            .select(profile)
+            .render(source_language, target_language, &self.runtime.load().language_rules)
+    }
+
+    /// Cache key part naming the prompt template version, configured language rules,
+    /// comment translation, code block handling, anchor mode, tenant and kept terms;
+    /// empty for the defaults so older keys stay valid
+    fn prompt_key(&self, profile: &TranslationProfile) -> String {
+        let mut key = match self.prompts.load().select(profile).version {
+            0 => String::new(),
+            version => format!(":prompt-{}", version),
+        };
+        let runtime = self.runtime.load();
+        if let Some(rule) = runtime
+            .language_rules
+            .select(&profile.source_language, &profile.target_language)
+            .filter(|rule| rule.configured)
+        {
+            key.push_str(":rules-");
+            key.push_str(&Self::compute_hash(&rule.rules)["sha256:".len()..][..12]);
+        }
+        if profile.translate_code_comments {
+            key.push_str(":comments");
+        }
+        if !profile.preserve_code_blocks {
+            key.push_str(":code-as-text");
+        }
+        if profile.anchor_mode != AnchorMode::None {
+            key.push_str(":anchors-");
+            key.push_str(profile.anchor_mode.as_str());
+        }
+        if profile.tenant != DEFAULT_TENANT {
+            key.push_str(":tenant-");
+            key.push_str(&profile.tenant);
+        }
+        let kept = keep_terms::normalize(&profile.keep_terms);
+        if !kept.is_empty() {
+            key.push_str(":keep-");
+            key.push_str(&Self::compute_hash(&kept.join("\n"))["sha256:".len()..][..12]);
+        }
+        key
+    }
+
+    /// Compute SHA256 hash of content with prefix
+    pub fn compute_hash(content: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(content.as_bytes());
+        let hash = hasher.finalize();
+        format!("sha256:{}", hex::encode(hash))
+    }
+
+    /// Compute cache key from content hash and translation parameters
+    pub fn compute_cache_key(&self, content_hash: &str, profile: &TranslationProfile) -> String {
+        self.versioned_cache_key(content_hash, profile, &self.translator_version)
+    }
+
+    /// Cache key of a primary model translation stored by translator `version`
+    fn versioned_cache_key(&self, content_hash: &str, profile: &TranslationProfile, version: &str) -> String {
+        let key_data = format!(
+            "{}:{}:{}:{}{}{}",
+            content_hash,
+            profile.source_language,
+            profile.target_language,
+            version,
+            self.prompt_key(profile),
+            scope_key(profile.scope)
+        );
+        Self::compute_hash(&key_data)
+    }
+
+    /// Cache key for a translation produced by `model`.
+    /// Primary model results use the plain key; fallback results also encode the model.
+    pub fn cache_key_for_model(
+        &self,
+        content_hash: &str,
+        profile: &TranslationProfile,
+        model: &str,
+    ) -> String {
+        self.versioned_key_for_model(content_hash, profile, model, &self.translator_version)
+    }
+
+    fn versioned_key_for_model(
+        &self,
+        content_hash: &str,
+        profile: &TranslationProfile,
+        model: &str,
+        version: &str,
+    ) -> String {
+        if model == self.runtime.load().models[0] {
+            return self.versioned_cache_key(content_hash, profile, version);
+        }
+
+        let key_data = format!(
+            "{}:{}:{}:{}:{}{}{}",
+            content_hash,
+            profile.source_language,
+            profile.target_language,
+            version,
+            model,
+            self.prompt_key(profile),
+            scope_key(profile.scope)
+        );
+        Self::compute_hash(&key_data)
+    }
+
+    /// All cache keys that may hold a translation, in model chain order
+    pub fn cache_keys(&self, content_hash: &str, profile: &TranslationProfile) -> Vec<String> {
+        self.cache_keys_for_version(content_hash, profile, &self.translator_version)
+    }
+
+    /// The cache keys a translation stored by translator `version` would have, for finding
+    /// translations left behind by an earlier version
+    pub fn cache_keys_for_version(
+        &self,
+        content_hash: &str,
+        profile: &TranslationProfile,
+        version: &str,
+    ) -> Vec<String> {
+        self.runtime
+            .load()
+            .models
+            .iter()
+            .map(|model| self.versioned_key_for_model(content_hash, profile, model, version))
+            .collect()
+    }
+
+    /// Segment content exactly as `translate` would and count its tokens,
+    /// without calling the API. Fails like `translate` when a line is too large to chunk.
+    pub fn preview(&self, content: &str) -> Result<TranslationPreview> {
+        let runtime = self.runtime.load();
+        let model = &runtime.models[0];
+
+        let parsed = self.parser.parse(content);
+        let body_with_placeholders = self.parser.replace_blocks(&parsed);
+        let fragments: Vec<&str> = structured_texts(&parsed)
+            .into_iter()
+            .map(|range| &parsed.body[range])
+            .collect();
+        let fragments = number_lines(&fragments);
+
+        let frontmatter_fields: Vec<(String, String)> = self
+            .parser
+            .get_description_field(&parsed.frontmatter_dict)
+            .filter(|d| !d.is_empty() && self.parser.is_translatable_field("description"))
+            .map(|d| vec![("description".to_string(), d)])
+            .unwrap_or_default();
+
+        // Empty segments are returned as-is without an API call
+        let mut chunks = Vec::new();
+        for text in [body_with_placeholders.as_str(), fragments.as_str()]
+            .into_iter()
+            .chain(frontmatter_fields.iter().map(|(_, value)| value.as_str()))
+            .filter(|text| !text.trim().is_empty())
+        {
+            chunks.extend(chunk_text(text, model, runtime.chunk_max_tokens)?);
+        }
+
+        let prompt_tokens = count_tokens(model, BUILTIN_TEMPLATE);
+        let text_tokens: usize = chunks.iter().map(|text| count_tokens(model, text)).sum();
+
+        Ok(TranslationPreview {
+            code_block_count: parsed.code_blocks.len(),
+            api_calls: chunks.len(),
+            estimated_input_tokens: text_tokens + prompt_tokens * chunks.len(),
+            estimated_output_tokens: text_tokens,
+            frontmatter_fields,
+            body_with_placeholders,
+        })
+    }
+
+    /// Segment cache key for a body section in its original markdown
+    fn segment_key(&self, source: &str, profile: &TranslationProfile) -> String {
+        let key_data = format!(
+            "segment:{}:{}:{}{}:{}",
+            profile.source_language,
+            profile.target_language,
+            self.translator_version,
+            self.prompt_key(profile),
+            source
+        );
+        Self::compute_hash(&key_data)
+    }
+
+    /// Key of the stored result of one upstream call: the protected text with its
+    /// prompt, translated by the runtime's primary model
+    fn partial_key(&self, runtime: &Runtime, prompt: &str, text: &str) -> String {
+        let key_data = format!(
+            "partial:{}:{}:{}\0{}",
+            self.translator_version, runtime.models[0], prompt, text
+        );
+        Self::compute_hash(&key_data)
+    }
+
+    /// Translate SKILL.md content from source to target language
+    pub async fn translate(
+        &self,
+        content: &str,
+        profile: &TranslationProfile,
+    ) -> Result<(String, TranslationMetadata)> {
+        let document = self.translate_document(content, profile, Reuse::Nothing, None).await?;
+        Ok((document.content, document.metadata))
+    }
+
+    /// Translate like `translate`, storing the result of each upstream call in the
+    /// cache as it arrives. When the translation does not finish, e.g. because the
+    /// service crashed, translating the same content again resumes from the stored
+    /// results; they are deleted once it finishes.
+    pub async fn translate_resumable(
+        &self,
+        content: &str,
+        profile: &TranslationProfile,
+        cache: &TranslationCache,
+    ) -> Result<(String, TranslationMetadata)> {
+        let document = self
+            .translate_document(content, profile, Reuse::Nothing, Some(cache))
+            .await?;
+        Ok((document.content, document.metadata))
+    }
+
+    /// Translate like `translate`, but split the body into heading sections and
+    /// reuse sections translated before, so a small edit only sends the changed
+    /// sections upstream. New sections are stored in the segment cache, and the
+    /// translation resumes like `translate_resumable`.
+    pub async fn translate_with_segments(
+        &self,
+        content: &str,
+        profile: &TranslationProfile,
+        segments: &TranslationCache,
+    ) -> Result<(String, TranslationMetadata)> {
+        let document = self
+            .translate_document(content, profile, Reuse::SegmentCache(segments), Some(segments))
+            .await?;
+        Ok((document.content, document.metadata))
+    }
+
+    /// Translate a new version of a document given the old version and its translation.
+    ///
+    /// Sections of the new body that also appear in the old body keep their prior
+    /// translation; only changed sections are translated and merged in. The
+    /// description is reused when unchanged. Fails when the old translation's
+    /// sections do not line up with the old content.
+    pub async fn translate_delta(
+        &self,
+        old_content: &str,
+        old_translation: &str,
+        new_content: &str,
+        profile: &TranslationProfile,
+    ) -> Result<DeltaTranslation> {
+        let profile = self.resolve_profile(new_content, profile)?;
+        let prior = self.prior_translation(old_content, old_translation, &profile)?;
+        let document = self
+            .translate_document(new_content, &profile, Reuse::Prior(prior), None)
+            .await?;
+
+        Ok(DeltaTranslation {
+            content: document.content,
+            metadata: document.metadata,
+            sections: document.sections,
+        })
+    }
+
+    /// Resolve `auto` to the language detected in the content's body and description.
+    ///
+    /// Fails when the language cannot be detected or the source language is the
+    /// target language, since there is nothing to translate.
+    pub fn resolve_profile(
+        &self,
+        content: &str,
+        profile: &TranslationProfile,
+    ) -> Result<TranslationProfile> {
+        let source_language = if profile.source_language.eq_ignore_ascii_case(language::AUTO) {
+            let parsed = self.parser.parse(content);
+            // Code is mostly English whatever the prose is written in
+            let mut text = self.parser.replace_code_blocks(&parsed);
+            if let Some(description) = self.parser.get_description_field(&parsed.frontmatter_dict) {
+                text.push('\n');
+                text.push_str(&description);
+            }
+
+            let detected = language::detect_language(&text).ok_or_else(|| {
+                Error::InvalidLanguage(
+                    "Could not detect the source language, set source_language explicitly".to_string(),
+                )
+            })?;
+            tracing::debug!("Detected source language {}", detected);
+            detected.to_string()
+        } else {
+            profile.source_language.clone()
+        };
+
+        if same_language(&source_language, &profile.target_language) {
+            return Err(Error::InvalidLanguage(format!(
+                "Source language {} is the target language {}, nothing to translate",
+                source_language, profile.target_language
+            )));
+        }
+        Ok(TranslationProfile {
+            source_language,
+            ..profile.clone()
+        })
+    }
+
+    /// Pair the body sections of an old document with those of its translation
+    fn prior_translation(
+        &self,
+        old_content: &str,
+        old_translation: &str,
+        profile: &TranslationProfile,
+    ) -> Result<PriorTranslation> {
+        let old = self.parser.parse(old_content);
+        let translated = self.parser.parse(old_translation);
+        let old_body = self.parser.replace_blocks(&old);
+        let translated_body = self.parser.replace_blocks(&translated);
+
+        let old_sections = split_sections(&old_body);
+        let translated_sections = split_sections(&translated_body);
+        if old_sections.len() != translated_sections.len() {
+            return Err(Error::InvalidContent(format!(
+                "Old translation has {} sections but the old content has {}",
+                translated_sections.len(),
+                old_sections.len()
+            )));
+        }
+
+        let sections = self
+            .section_keys(&old_sections, &old, profile)
+            .into_iter()
+            .zip(translated_sections)
+            .map(|(key, section)| (key, self.parser.restore_blocks(section.trim(), &translated)))
+            .collect();
+        let description = self
+            .parser
+            .get_description_field(&old.frontmatter_dict)
+            .zip(self.parser.get_description_field(&translated.frontmatter_dict));
+
+        Ok(PriorTranslation {
+            sections,
+            description,
+        })
+    }
+
+    /// Body sections in their original markdown
+    fn section_sources(&self, sections: &[&str], parsed: &ParsedContent) -> Vec<String> {
+        sections
+            .iter()
+            .map(|section| self.parser.restore_blocks(section.trim(), parsed))
+            .collect()
+    }
+
+    /// Segment keys of body sections, computed on their original markdown
+    fn section_keys(
+        &self,
+        sections: &[&str],
+        parsed: &ParsedContent,
+        profile: &TranslationProfile,
+    ) -> Vec<String> {
+        self.section_sources(sections, parsed)
+            .iter()
+            .map(|source| self.segment_key(source, profile))
+            .collect()
+    }
+
+    /// Consult the translation memory for sections missing from `known`.
+    ///
+    /// A source equal to a stored one after whitespace normalization takes its
+    /// translation into `known`; for the others, the most similar stored section
+    /// is returned by segment key as an example for the model. Lookup failures
+    /// only cost the reuse.
+    async fn consult_memory(
+        &self,
+        runtime: &Runtime,
+        cache: &TranslationCache,
+        scope: &MemoryScope<'_>,
+        sources: &[String],
+        keys: &[String],
+        known: &mut HashMap<String, String>,
+    ) -> HashMap<String, MemoryPair> {
+        let missing: Vec<usize> = (0..keys.len()).filter(|&i| !known.contains_key(&keys[i])).collect();
+        if missing.is_empty() {
+            return HashMap::new();
+        }
+
+        let hashes: Vec<String> = missing.iter().map(|&i| memory::normalized_hash(&sources[i])).collect();
+        match cache.get_translation_memory(scope, &hashes).await {
+            Ok(found) => {
+                for (&i, hash) in missing.iter().zip(&hashes) {
+                    if let Some(translated) = found.get(hash) {
+                        known.insert(keys[i].clone(), translated.clone());
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Translation memory lookup failed: {}", e),
+        }
+
+        let unmatched: Vec<usize> = missing.into_iter().filter(|&i| !known.contains_key(&keys[i])).collect();
+        if unmatched.is_empty() {
+            return HashMap::new();
+        }
+        let candidates = match cache.translation_memory_candidates(scope).await {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                tracing::warn!("Translation memory lookup failed: {}", e);
+                return HashMap::new();
+            }
+        };
+
+        let unmatched_sources: Vec<&str> = unmatched.iter().map(|&i| sources[i].as_str()).collect();
+        memory::best_matches(&unmatched_sources, &candidates, runtime.memory_min_similarity)
+            .into_iter()
+            .zip(&unmatched)
+            .filter_map(|(pair, &i)| pair.map(|pair| (keys[i].clone(), pair.clone())))
+            .collect()
+    }
+
+    #[tracing::instrument(
+        name = "translate",
+        skip_all,
+        fields(
+            source_language = %profile.source_language,
+            target_language = %profile.target_language,
+            document_type = %profile.document_type,
+            chars = content.len()
+        )
+    )]
+    async fn translate_document(
+        &self,
+        content: &str,
+        profile: &TranslationProfile,
+        reuse: Reuse<'_>,
+        partials: Option<&TranslationCache>,
+    ) -> Result<DocumentTranslation> {
+        let start_time = Instant::now();
+        let runtime = self.runtime.load_full();
+        let job = Job {
+            timeout_seconds: profile.timeout_seconds.unwrap_or(runtime.timeout_seconds),
+            attempts: profile.max_retries.map_or(self.max_retries, |retries| retries + 1),
+            runtime,
+            priority: profile.priority,
+            progress: profile.progress.clone().map(|sink| JobProgress {
+                sink,
+                // A document that cannot be segmented fails below anyway
+                expected: self.preview(content).map_or(0, |preview| preview.api_calls),
+                started: AtomicUsize::new(0),
+            }),
+            keep_terms: keep_terms::normalize(&profile.keep_terms),
+            partials: partials.map(|cache| Partials {
+                cache,
+                tenant: profile.tenant.clone(),
+                reuse: !profile.force,
+                keys: Mutex::new(Vec::new()),
+            }),
+        };
+        let profile = &self.resolve_profile(content, profile)?;
+        let prompt = self.system_prompt(profile, &profile.source_language, &profile.target_language);
+        let prompt = if profile.terminology.is_empty() {
+            prompt
+        } else {
+            terminology::terminology_prompt(&prompt, &profile.terminology)
+        };
+
+        // Parse the content; code blocks that are not preserved stay in the text
+        let mut parsed = self.parser.parse(content);
+        if !profile.preserve_code_blocks {
+            parsed.code_blocks.clear();
+            parsed.code_block_ranges.clear();
+        }
+
+        // Replace code blocks, tables and HTML blocks with placeholders
+        let body_with_placeholders = self.parser.replace_blocks(&parsed);
+
+        // The description is independent of the body, so both are translated at once;
+        // each part's time is recorded in the metadata
+        let translate_body = profile.scope != TranslationScope::Frontmatter;
+        let document = DocumentParts {
+            parsed: &parsed,
+            body_with_placeholders: &body_with_placeholders,
+            profile,
+            prompt: &prompt,
+        };
+        let description = self
+            .parser
+            .get_description_field(&parsed.frontmatter_dict)
+            .filter(|d| !d.is_empty() && self.parser.is_translatable_field("description"))
+            .filter(|_| profile.scope != TranslationScope::Body);
+        let ((body, body_time), ((translated_description, description_translation), description_time)) =
+            futures::future::try_join(
+                timed(self.translate_body_part(&job, &document, &reuse)),
+                timed(self.translate_description_part(&job, description.as_deref(), &reuse, &prompt)),
+            )
+            .await?;
+        let BodyPart {
+            restored,
+            fragment_translations,
+            translation: body_translation,
+            sections,
+        } = body;
+
+        let cached_segments = sections.iter().filter(|s| s.reused).count();
+        if cached_segments > 0 {
+            tracing::info!("Reused {} of {} sections", cached_segments, sections.len());
+        }
+
+        let mut translated_body = body_translation.text;
+        let mut retries = body_translation.retries;
+        let mut model_index = body_translation.model_index;
+        let mut input_tokens = body_translation.input_tokens;
+        let mut output_tokens = body_translation.output_tokens;
+        let mut chunks = body_translation.chunks;
+        for translation in fragment_translations.into_iter().chain(description_translation) {
+            retries += translation.retries;
+            model_index = model_index.max(translation.model_index);
+            input_tokens += translation.input_tokens;
+            output_tokens += translation.output_tokens;
+            chunks += translation.chunks;
+        }
+        let mut translated_frontmatter = match &translated_description {
+            Some(translated) => self.frontmatter_with_description(&parsed, translated),
+            None => parsed.frontmatter.clone(),
+        };
+
+        // Check the structure; on problems retranslate the affected parts once with
+        // the findings, keeping whichever attempt has fewer problems
+        let mut report = validate_translation(content, &format!("{}{}", translated_frontmatter, translated_body));
+        if !report.valid {
+            let findings = report.findings();
+            tracing::warn!("Translation failed validation, retrying: {}", findings.join("; "));
+            let repair = self
+                .repair_translation(
+                    &job,
+                    &restored,
+                    &body_with_placeholders,
+                    description.as_deref(),
+                    &report,
+                    &repair_prompt(&prompt, &findings),
+                )
+                .await;
+            match repair {
+                Ok((frontmatter, body, translations)) => {
+                    for translation in translations {
+                        retries += translation.retries;
+                        model_index = model_index.max(translation.model_index);
+                        input_tokens += translation.input_tokens;
+                        output_tokens += translation.output_tokens;
+                        chunks += translation.chunks;
+                    }
+                    let frontmatter = frontmatter.unwrap_or(translated_frontmatter.clone());
+                    let body = body.unwrap_or(translated_body.clone());
+                    let repaired = validate_translation(content, &format!("{}{}", frontmatter, body));
+                    if repaired.findings().len() <= findings.len() {
+                        translated_frontmatter = frontmatter;
+                        translated_body = body;
+                        report = repaired;
+                    }
+                }
+                Err(e) => tracing::warn!("Repair translation failed, keeping the first attempt: {}", e),
+            }
+        }
+        let mut warnings = report.findings();
+
+        // Links are checked against the original before anchors are adjusted
+        let translated_body =
+            anchors::preserve_anchors(profile.anchor_mode, &parsed.body, &translated_body);
+
+        // Combine frontmatter and translated body
+        let translated_content = translated_frontmatter + &translated_body;
+        warnings.extend(keep_terms::findings(content, &translated_content, &job.keep_terms));
+
+        // Translate the body back and compare it with the original; a failed check only adds a warning
+        let roundtrip = if profile.verify_roundtrip && translate_body {
+            match self
+                .verify_roundtrip(&job, &body_with_placeholders, &translated_content, profile)
+                .await
+            {
+                Ok((report, back_translation)) => {
+                    input_tokens += back_translation.input_tokens;
+                    output_tokens += back_translation.output_tokens;
+                    Some(report)
+                }
+                Err(e) => {
+                    tracing::warn!("Back-translation check failed: {}", e);
+                    warnings.push(format!("Back-translation check failed: {}", e));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Have the judge score the result; a failed check only adds a warning
+        let quality = if profile.quality_check {
+            match self.judge(&job, content, &translated_content, profile).await {
+                Ok((score, judge_input_tokens, judge_output_tokens)) => {
+                    input_tokens += judge_input_tokens;
+                    output_tokens += judge_output_tokens;
+                    Some(score)
+                }
+                Err(e) => {
+                    tracing::warn!("Quality check failed: {}", e);
+                    warnings.push(format!("Quality check failed: {}", e));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Compute metadata
+        let processing_time = start_time.elapsed();
+        let metadata = TranslationMetadata {
+            original_chars: content.len(),
+            translated_chars: translated_content.len(),
+            processing_time_ms: processing_time.as_millis() as f64,
+            translator_version: self.translator_version.clone(),
+            model: job.runtime.models[model_index].clone(),
+            source_language: profile.source_language.clone(),
+            target_language: profile.target_language.clone(),
+            retries,
+            input_tokens,
+            output_tokens,
+            chunks,
+            cached_segments,
+            body_time_ms: body_time.as_millis() as f64,
+            description_time_ms: description_time.as_millis() as f64,
+            warnings,
+            quality,
+            roundtrip,
+        };
+
+        if let Some(partials) = &job.partials {
+            partials.clear().await;
+        }
+
+        Ok(DocumentTranslation {
+            content: translated_content,
+            metadata,
+            sections,
+        })
+    }
+
+    /// Translate the body: table cells, HTML text and, if requested, code comments on their
+    /// own, then the text around them, reusing sections as `reuse` allows
+    async fn translate_body_part(
+        &self,
+        job: &Job<'_>,
+        document: &DocumentParts<'_>,
+        reuse: &Reuse<'_>,
+    ) -> Result<BodyPart> {
+        let DocumentParts { parsed, profile, .. } = *document;
+        let translate_body = profile.scope != TranslationScope::Frontmatter;
+
+        // Blocks are restored from `restored`, which holds the fragment translations
+        let (mut replacements, fragment_translation) = self
+            .translate_fragments(
+                job,
+                &parsed.body,
+                if translate_body { structured_texts(parsed) } else { Vec::new() },
+                &fragment_prompt(
+                    &profile.source_language,
+                    &profile.target_language,
+                    &job.runtime.language_rules,
+                ),
+            )
+            .await?;
+        let mut fragment_translations = vec![fragment_translation];
+        if profile.translate_code_comments && translate_body {
+            let (comment_replacements, comment_translation) = self
+                .translate_fragments(
+                    job,
+                    &parsed.body,
+                    code_comments(parsed),
+                    &comments::comment_prompt(&profile.source_language, &profile.target_language),
+                )
+                .await?;
+            replacements.extend(comment_replacements);
+            fragment_translations.push(comment_translation);
+        }
+        replacements.sort_by_key(|(range, _)| range.start);
+        let restored = parsed.with_replacements(&replacements);
+
+        // Translate the body with concurrency control, then restore code blocks
+        let (translation, sections) = match reuse {
+            _ if !translate_body => (TextTranslation::unchanged(&parsed.body), Vec::new()),
+            Reuse::Nothing => {
+                let mut translation = self
+                    .translate_chunked(job, document.body_with_placeholders, document.prompt)
+                    .await?;
+                translation.text = self.parser.restore_blocks(&translation.text, &restored);
+                (translation, Vec::new())
+            }
+            Reuse::SegmentCache(cache) => self.translate_segments(job, document, &restored, cache).await?,
+            Reuse::Prior(prior) => {
+                let sections = split_sections(document.body_with_placeholders);
+                let keys = self.section_keys(&sections, parsed, profile);
+                let (translation, outcomes, _) = self
+                    .translate_sections(
+                        job,
+                        &restored,
+                        &sections,
+                        &keys,
+                        KnownSections {
+                            translated: &prior.sections,
+                            examples: &HashMap::new(),
+                        },
+                        document.prompt,
+                    )
+                    .await?;
+                (translation, outcomes)
+            }
+        };
+
+        Ok(BodyPart {
+            restored,
+            fragment_translations,
+            translation,
+            sections,
+        })
+    }
+
+    /// Translate the body section by section, taking sections from the segment cache and
+    /// translation memory and storing the new ones back
+    async fn translate_segments(
+        &self,
+        job: &Job<'_>,
+        document: &DocumentParts<'_>,
+        restored: &ParsedContent,
+        cache: &TranslationCache,
+    ) -> Result<(TextTranslation, Vec<SectionOutcome>)> {
+        let profile = document.profile;
+        let sections = split_sections(document.body_with_placeholders);
+        let sources = self.section_sources(&sections, document.parsed);
+        let keys: Vec<String> = sources
+            .iter()
+            .map(|source| self.segment_key(source, profile))
+            .collect();
+        let mut known = if profile.force {
+            HashMap::new()
+        } else {
+            cache.get_segments(&keys).await.unwrap_or_else(|e| {
+                tracing::warn!("Segment cache lookup failed, translating every section: {}", e);
+                HashMap::new()
+            })
+        };
+
+        let scope = MemoryScope::from(profile);
+        let examples = if job.runtime.translation_memory && !profile.force {
+            let cached = known.len();
+            let examples = self
+                .consult_memory(&job.runtime, cache, &scope, &sources, &keys, &mut known)
+                .await;
+            tracing::debug!(
+                "Translation memory matched {} sections and found {} similar ones",
+                known.len() - cached,
+                examples.len()
+            );
+            examples
+        } else {
+            HashMap::new()
+        };
+
+        let (translation, outcomes, fresh) = self
+            .translate_sections(
+                job,
+                restored,
+                &sections,
+                &keys,
+                KnownSections {
+                    translated: &known,
+                    examples: &examples,
+                },
+                document.prompt,
+            )
+            .await?;
+        if !fresh.is_empty() {
+            if let Err(e) = cache.set_segments(&profile.tenant, &fresh).await {
+                tracing::warn!("Failed to store translated segments: {}", e);
+            }
+        }
+        if job.runtime.translation_memory && !fresh.is_empty() {
+            let source_by_key: HashMap<&str, &str> = keys
+                .iter()
+                .map(String::as_str)
+                .zip(sources.iter().map(String::as_str))
+                .collect();
+            let pairs: Vec<MemoryPair> = fresh
+                .iter()
+                .filter_map(|(key, translated)| {
+                    let source = source_by_key.get(key.as_str())?;
+                    Some(MemoryPair::new(*source, translated.clone()))
+                })
+                .collect();
+            if let Err(e) = cache.set_translation_memory(&scope, &pairs).await {
+                tracing::warn!("Failed to store translation memory: {}", e);
+            }
+        }
+        Ok((translation, outcomes))
+    }
+
+    /// Translate the frontmatter description, or take it from a prior translation of the
+    /// same description. Returns the translated text and the upstream translation, if any.
+    async fn translate_description_part(
+        &self,
+        job: &Job<'_>,
+        description: Option<&str>,
+        reuse: &Reuse<'_>,
+        prompt: &str,
+    ) -> Result<(Option<String>, Option<TextTranslation>)> {
+        let Some(description) = description else {
+            return Ok((None, None));
+        };
+        if let Reuse::Prior(PriorTranslation {
+            description: Some((source, translated)),
+            ..
+        }) = reuse
+        {
+            if source == description {
+                return Ok((Some(translated.clone()), None));
+            }
+        }
+        let translation = self.translate_chunked(job, description, prompt).await?;
+        Ok((Some(translation.text.clone()), Some(translation)))
+    }
+
+    /// Have the judge model rate a translation of `original`.
+    /// Returns the rating with the prompt and completion tokens it took.
+    async fn judge(
+        &self,
+        job: &Job<'_>,
+        original: &str,
+        translated: &str,
+        profile: &TranslationProfile,
+    ) -> Result<(QualityScore, usize, usize)> {
+        let model = &job.runtime.judge_model;
+        let prompt = quality::judge_prompt(&profile.source_language, &profile.target_language);
+        let input = quality::judge_input(original, translated);
+
+        let permit = job.runtime.scheduler.acquire(job.priority).await;
+        let _model_permit = job.runtime.model_limits.acquire(model).await;
+        let (reply, _) = timeout(
+            Duration::from_secs(job.timeout_seconds),
+            self.translate_text(
+                &input,
+                &prompt,
+                JUDGE_LEAD,
+                &UpstreamCall {
+                    model,
+                    max_tokens: JUDGE_MAX_TOKENS,
+                    attempts: job.attempts,
+                    permit: &permit,
+                },
+            ),
+        )
+        .await
+        .map_err(|_| {
+            self.breaker.record_failure();
+            permit.overloaded();
+            Error::from(TranslationError::Timeout(job.timeout_seconds))
+        })??;
+
+        let score = quality::parse_judgement(&reply, model)?;
+        Ok((
+            score,
+            count_tokens(model, &prompt) + count_tokens(model, &input),
+            count_tokens(model, &reply),
+        ))
+    }
+
+    /// Translate the body of `translated` back to the source language and compare its
+    /// sections with those of the original body. Code blocks stay placeholders on both sides.
+    async fn verify_roundtrip(
+        &self,
+        job: &Job<'_>,
+        original_body: &str,
+        translated: &str,
+        profile: &TranslationProfile,
+    ) -> Result<(RoundtripReport, TextTranslation)> {
+        let parsed = self.parser.parse(translated);
+        let translated_body = self.parser.replace_blocks(&parsed);
+        let prompt = self.system_prompt(profile, &profile.target_language, &profile.source_language);
+
+        let back_translation = self.translate_chunked(job, &translated_body, &prompt).await?;
+        let report = roundtrip::compare(
+            &split_sections(original_body),
+            &split_sections(&back_translation.text),
+        );
+        Ok((report, back_translation))
+    }
+
+    /// Frontmatter with the description replaced by its translation
+    fn frontmatter_with_description(&self, parsed: &ParsedContent, translated_description: &str) -> String {
+        // Filter out empty lines to preserve YAML structure
+        let cleaned_description: String = translated_description
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        self.parser.translate_frontmatter_field(
+            &parsed.frontmatter,
+            "description",
+            &cleaned_description,
+        )
+    }
+
+    /// Retranslate the parts a validation report found problems in, with a
+    /// corrective prompt. Returns the new frontmatter and body (`None` where that
+    /// part was fine) and the translations made.
+    async fn repair_translation(
+        &self,
+        job: &Job<'_>,
+        restored: &ParsedContent,
+        body_with_placeholders: &str,
+        description: Option<&str>,
+        report: &ValidationReport,
+        prompt: &str,
+    ) -> Result<(Option<String>, Option<String>, Vec<TextTranslation>)> {
+        let mut translations = Vec::new();
+
+        let body = if report.body_ok() {
+            None
+        } else {
+            let translation = self
+                .translate_chunked(job, body_with_placeholders, prompt)
+                .await?;
+            let body = self.parser.restore_blocks(&translation.text, restored);
+            translations.push(translation);
+            Some(body)
+        };
+
+        let frontmatter = match description {
+            Some(description) if !report.frontmatter_ok() => {
+                let translation = self.translate_chunked(job, description, prompt).await?;
+                let frontmatter = self.frontmatter_with_description(restored, &translation.text);
+                translations.push(translation);
+                Some(frontmatter)
+            }
+            _ => None,
+        };
+
+        Ok((frontmatter, body, translations))
+    }
+
+    /// Translate single-line fragments of `body` in one numbered request.
+    ///
+    /// Returns a replacement for each fragment found in the response; a fragment
+    /// whose translation changes the number of pipes is left as it was, since a
+    /// pipe would split a table cell.
+    async fn translate_fragments(
+        &self,
+        job: &Job<'_>,
+        body: &str,
+        ranges: Vec<Range<usize>>,
+        prompt: &str,
+    ) -> Result<(Vec<(Range<usize>, String)>, TextTranslation)> {
+        let texts: Vec<&str> = ranges.iter().map(|range| &body[range.clone()]).collect();
+        let translation = self
+            .translate_chunked(job, &number_lines(&texts), prompt)
+            .await?;
+        let mut translated = parse_numbered_lines(&translation.text);
+        if !texts.is_empty() {
+            tracing::debug!("Translated {} of {} fragments", translated.len(), texts.len());
+        }
+
+        let replacements = ranges
+            .into_iter()
+            .enumerate()
+            .filter_map(|(n, range)| {
+                let text = translated.remove(&n)?;
+                let pipes = |s: &str| s.matches('|').count();
+                (pipes(&text) == pipes(&body[range.clone()])).then_some((range, text))
+            })
+            .collect();
+        Ok((replacements, translation))
+    }
+
+    /// Translate body sections, taking those with a known translation as-is.
+    ///
+    /// Runs of consecutive unknown sections are translated in one go, with the
+    /// examples known for their sections added to the prompt, and split at
+    /// headings again so each section can be stored; when the translation does not
+    /// line up with the source sections, no pairs are returned for that run.
+    /// Returns the body with blocks restored, the outcome of every section and
+    /// the `(segment_key, translation)` pairs of newly translated sections.
+    async fn translate_sections(
+        &self,
+        job: &Job<'_>,
+        parsed: &ParsedContent,
+        sections: &[&str],
+        keys: &[String],
+        known: KnownSections<'_>,
+        prompt: &str,
+    ) -> Result<(TextTranslation, Vec<SectionOutcome>, Vec<(String, String)>)> {
+        let mut combined = TextTranslation {
+            text: String::new(),
+            retries: 0,
+            model_index: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            chunks: 0,
+        };
+        let mut outcomes: Vec<SectionOutcome> = sections
+            .iter()
+            .map(|section| SectionOutcome {
+                heading: self
+                    .parser
+                    .restore_blocks(section.trim(), parsed)
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                reused: false,
+            })
+            .collect();
+        let mut fresh = Vec::new();
+
+        let mut i = 0;
+        while i < sections.len() {
+            if let Some(translated) = known.translated.get(&keys[i]) {
+                push_translated(&mut combined.text, sections[i], translated);
+                outcomes[i].reused = true;
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            while i < sections.len() && !known.translated.contains_key(&keys[i]) {
+                i += 1;
+            }
+            let run = sections[start..i].concat();
+
+            let mut run_examples: Vec<&MemoryPair> = Vec::new();
+            for example in keys[start..i].iter().filter_map(|key| known.examples.get(key)) {
+                if run_examples.len() < memory::MAX_EXAMPLES && !run_examples.contains(&example) {
+                    run_examples.push(example);
+                }
+            }
+            let run_prompt = if run_examples.is_empty() {
+                prompt.to_string()
+            } else {
+                memory::memory_prompt(prompt, &run_examples)
+            };
+
+            let translation = self
+                .translate_chunked(job, &run, &run_prompt)
+                .await?;
+            push_translated(
+                &mut combined.text,
+                &run,
+                &self.parser.restore_blocks(&translation.text, parsed),
+            );
+
+            combined.retries += translation.retries;
+            combined.model_index = combined.model_index.max(translation.model_index);
+            combined.input_tokens += translation.input_tokens;
+            combined.output_tokens += translation.output_tokens;
+            combined.chunks += translation.chunks;
+
+            // Only primary model output is reused, like whole-document cache keys.
+            // Split before restoring code blocks so `#` lines in code are not headings.
+            if translation.model_index == 0 {
+                let pieces = split_sections(&translation.text);
+                if pieces.len() == i - start {
+                    fresh.extend(keys[start..i].iter().zip(pieces).map(|(key, piece)| {
+                        let piece = self.parser.restore_blocks(piece.trim(), parsed);
+                        (key.clone(), piece)
+                    }));
+                } else {
+                    tracing::debug!(
+                        "Translated sections do not line up with the source ({} vs {}), not caching them",
+                        pieces.len(),
+                        i - start
+                    );
+                }
+            }
+        }
+
+        Ok((combined, outcomes, fresh))
+    }
+
+    /// Translate text in chunks of at most `chunk_max_tokens` tokens.
+    /// Text that fits is sent in one call; otherwise chunks are translated in order
+    /// and rejoined with the whitespace that separated them.
+    async fn translate_chunked(
+        &self,
+        job: &Job<'_>,
+        text: &str,
+        prompt: &str,
+    ) -> Result<TextTranslation> {
+        let chunks = chunk_text(text, &job.runtime.models[0], job.runtime.chunk_max_tokens)?;
+        if chunks.len() <= 1 {
+            return self
+                .translate_with_control(job, text, prompt)
+                .await;
+        }
+
+        tracing::info!("Translating {} chunks", chunks.len());
+
+        let mut combined = TextTranslation {
+            text: String::with_capacity(text.len()),
+            retries: 0,
+            model_index: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            chunks: 0,
+        };
+        for chunk in &chunks {
+            let translation = self
+                .translate_with_control(job, chunk, prompt)
+                .await?;
+
+            push_translated(&mut combined.text, chunk, &translation.text);
+
+            combined.retries += translation.retries;
+            combined.model_index = combined.model_index.max(translation.model_index);
+            combined.input_tokens += translation.input_tokens;
+            combined.output_tokens += translation.output_tokens;
+            combined.chunks += translation.chunks;
+        }
+
+        Ok(combined)
+    }
+
+    /// Translate text with concurrency control and timeout, kept terms protected.
+    /// Walks the model chain: when a model errors or times out, the next one is tried.
+    async fn translate_with_control(
+        &self,
+        job: &Job<'_>,
+        text: &str,
+        prompt: &str,
+    ) -> Result<TextTranslation> {
+        if text.trim().is_empty() {
+            return Ok(TextTranslation::unchanged(text));
+        }
+        let protected = keep_terms::protect(text, &job.keep_terms);
+        let text = protected.as_str();
+
+        let partial = job
+            .partials
+            .as_ref()
+            .map(|partials| (partials, self.partial_key(&job.runtime, prompt, text)));
+        if let Some((partials, partial_key)) = &partial {
+            if let Some(translated) = partials.get(partial_key).await {
+                return Ok(TextTranslation::unchanged(&translated));
+            }
+        }
+
+        let permit = job.runtime.scheduler.acquire(job.priority).await;
+        if let Some(progress) = &job.progress {
+            progress.report();
+        }
+
+        let mut retries = 0;
+        let mut last_error = None;
+
+        for (model_index, model) in job.runtime.models.iter().enumerate() {
+            let _model_permit = job.runtime.model_limits.acquire(model).await;
+            let result = timeout(
+                Duration::from_secs(job.timeout_seconds),
+                self.translate_text(
+                    text,
+                    prompt,
+                    TRANSLATION_LEAD,
+                    &UpstreamCall {
+                        model,
+                        max_tokens: job.runtime.max_tokens,
+                        attempts: job.attempts,
+                        permit: &permit,
+                    },
+                ),
+            )
+            .await
+            .map_err(|_| {
+                self.breaker.record_failure();
+                permit.overloaded();
+                Error::from(TranslationError::Timeout(job.timeout_seconds))
+            })
+            .and_then(|r| r);
+
+            match result {
+                Ok((translated, model_retries)) => {
+                    if model_index > 0 {
+                        tracing::info!("Translated with fallback model {}", model);
+                    }
+                    let translation = TextTranslation {
+                        input_tokens: count_tokens(model, prompt) + count_tokens(model, text),
+                        output_tokens: count_tokens(model, &translated),
+                        text: keep_terms::restore(&translated, &job.keep_terms),
+                        retries: retries + model_retries,
+                        model_index,
+                        chunks: 1,
+                    };
+                    if let Some((partials, partial_key)) = partial {
+                        partials.set(partial_key, &translation.text).await;
+                    }
+                    return Ok(translation);
+                }
+                // Every model shares the upstream, so an open circuit fails the whole chain
+                Err(e @ Error::Translation(TranslationError::CircuitOpen(_))) => {
+                    return Err(e);
+                }
+                Err(e) => {
+                    if model_index + 1 < job.runtime.models.len() {
+                        tracing::warn!("Model {} failed, falling back: {}", model, e);
+                        retries += 1;
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| Error::Internal("No models configured".to_string())))
+    }
+
+    /// Translate text using OpenAI API with retry logic.
+    ///
+    /// Makes up to `call.attempts` calls. Retries use exponential backoff with jitter, or
+    /// the upstream's advised wait for rate limits. Client errors such as 400/401 are not retried.
+    async fn translate_text(
+        &self,
+        text: &str,
+        prompt: &str,
+        lead: &str,
+        call: &UpstreamCall<'_>,
+    ) -> Result<(String, u32)> {
+        let UpstreamCall { model, max_tokens, attempts, permit } = *call;
+        if text.trim().is_empty() {
+            return Ok((text.to_string(), 0));
+        }
+
+        let mut last_error: Option<String> = None;
+        let mut last_status: Option<u16> = None;
+        let mut retry_after: Option<Duration> = None;
+
+        for attempt in 0..attempts {
+            // Only wait before retry (not on first attempt)
+            if attempt > 0 {
+                let delay = retry_after
+                    .take()
+                    .map(|d| d.min(MAX_RETRY_DELAY))
+                    .unwrap_or_else(|| backoff_delay(self.retry_delay, attempt));
+                tracing::debug!("Retrying upstream call in {:?} (attempt {})", delay, attempt + 1);
+                tokio::time::sleep(delay).await;
+            }
+
+            if let Err(remaining) = self.breaker.allow() {
+                return Err(TranslationError::CircuitOpen(remaining.as_secs().max(1)).into());
+            }
+
+            let result = match self.provider {
+                Provider::OpenAi => {
+                    self.call_openai_api(text, prompt, lead, model, max_tokens).await
+                }
+                Provider::Ollama => {
+                    self.call_ollama_api(text, prompt, lead, model, max_tokens).await
+                }
+            };
+            match &result {
+                Ok(_) => {
+                    self.breaker.record_success();
+                    permit.succeeded();
+                }
+                Err(e) if is_retryable(e) => self.breaker.record_failure(),
+                Err(_) => {}
+            }
+            if let Err(Error::Translation(TranslationError::Upstream { status: 429, .. })) = &result {
+                permit.overloaded();
+            }
+
+            match result {
+                Ok(content) => {
+                    if !content.is_empty() {
+                        return Ok((content, attempt));
+                    }
+                    return Err(TranslationError::EmptyResponse.into());
+                }
+                Err(e) => {
+                    if !is_retryable(&e) {
+                        return Err(e);
+                    }
+                    tracing::warn!("Upstream call failed (attempt {}): {}", attempt + 1, e);
+                    last_status = None;
+                    if let Error::Translation(TranslationError::Upstream {
+                        status,
+                        retry_after: advised,
+                        ..
+                    }) = &e
+                    {
+                        retry_after = *advised;
+                        last_status = Some(*status);
+                    }
+                    last_error = Some(e.to_string());
+                }
+            }
+        }
+
+        Err(TranslationError::RetryFailed {
+            attempts,
+            error: last_error.unwrap_or_else(|| "Unknown error".to_string()),
+            status: last_status,
+            retry_after,
+        }
+        .into())
+    }
+
+    /// Call OpenAI API with streaming
+    async fn call_openai_api(
+        &self,
+        text: &str,
+        prompt: &str,
+        lead: &str,
+        model: &str,
+        max_tokens: u32,
+    ) -> Result<String> {
+        let messages = self
+            .prompt_style
+            .messages(prompt, text, lead)
+            .into_iter()
+            .map(|(role, content)| -> Result<ChatCompletionRequestMessage> {
+                Ok(match role {
+                    "system" => ChatCompletionRequestMessage::System(
+                        ChatCompletionRequestSystemMessageArgs::default()
+                            .content(content)
+                            .build()?,
+                    ),
+                    _ => ChatCompletionRequestMessage::User(
+                        ChatCompletionRequestUserMessageArgs::default()
+                            .content(content)
+                            .build()?,
+                    ),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let request = CreateChatCompletionRequestArgs::default()
+            .model(model)
+            .messages(messages)
+            .temperature(0.3)
+            .max_tokens(max_tokens)
+            .stream(true)
+            .build()?;
+
+        let mut builder = self
+            .http
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&request);
+        if !self.api_key.is_empty() {
+            builder = builder.bearer_auth(&self.api_key);
+        }
+
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| TranslationError::OpenAIError(e.to_string()))?;
+        let response = check_status(response).await?;
+
+        let mut stream = response.bytes_stream().eventsource();
+        let mut content_chunks = Vec::new();
+
+        while let Some(event) = stream.next().await {
+            let event = event.map_err(|e| {
+                tracing::warn!("Stream error: {}", e);
+                TranslationError::OpenAIError(e.to_string())
+            })?;
+
+            if event.data.trim() == "[DONE]" {
+                break;
+            }
+
+            let chunk: CreateChatCompletionStreamResponse = match serde_json::from_str(&event.data) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    // Errors can also arrive mid-stream as {"error": {...}}
+                    let message = serde_json::from_str::<serde_json::Value>(&event.data)
+                        .ok()
+                        .and_then(|v| v.pointer("/error/message").and_then(|m| m.as_str()).map(str::to_string))
+                        .unwrap_or_else(|| format!("Invalid stream chunk: {}", e));
+                    tracing::warn!("Stream error: {}", message);
+                    return Err(TranslationError::OpenAIError(message).into());
+                }
+            };
+
+            for choice in chunk.choices {
+                if let Some(content) = choice.delta.content {
+                    content_chunks.push(content);
+                }
+            }
+        }
+
+        let content = content_chunks.join("");
+        Ok(content.trim().to_string())
+    }
+
+    /// Call an Ollama server's `/api/chat`, reading its newline-delimited JSON stream
+    async fn call_ollama_api(
+        &self,
+        text: &str,
+        prompt: &str,
+        lead: &str,
+        model: &str,
+        max_tokens: u32,
+    ) -> Result<String> {
+        let messages: Vec<serde_json::Value> = self
+            .prompt_style
+            .messages(prompt, text, lead)
+            .into_iter()
+            .map(|(role, content)| serde_json::json!({ "role": role, "content": content }))
+            .collect();
+        let request = serde_json::json!({
+            "model": model,
+            "messages": messages,
+            "stream": true,
+            "options": { "temperature": 0.3, "num_predict": max_tokens },
+        });
+
+        let mut builder = self
+            .http
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&request);
+        if !self.api_key.is_empty() {
+            builder = builder.bearer_auth(&self.api_key);
+        }
+
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| TranslationError::OpenAIError(e.to_string()))?;
+        let response = check_status(response).await?;
+
+        let mut stream = response.bytes_stream();
+        let mut buffer = Vec::new();
+        let mut content = String::new();
+        while let Some(bytes) = stream.next().await {
+            let bytes = bytes.map_err(|e| {
+                tracing::warn!("Stream error: {}", e);
+                TranslationError::OpenAIError(e.to_string())
+            })?;
+            buffer.extend_from_slice(&bytes);
+
+            while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                if ollama_chunk(&line, &mut content)? {
+                    return Ok(content.trim().to_string());
+                }
+            }
+        }
+        ollama_chunk(&buffer, &mut content)?;
+
+        Ok(content.trim().to_string())
+    }
+
+    /// Models available upstream, as returned by the server:
+    /// `GET /models` for OpenAI-compatible APIs, `GET /api/tags` for Ollama
+    pub async fn list_models(&self) -> Result<serde_json::Value> {
+        let path = match self.provider {
+            Provider::OpenAi => "/models",
+            Provider::Ollama => "/api/tags",
+        };
+        let mut builder = self.http.get(format!("{}{}", self.base_url, path));
+        if !self.api_key.is_empty() {
+            builder = builder.bearer_auth(&self.api_key);
+        }
+
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| TranslationError::OpenAIError(e.to_string()))?;
+        check_status(response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| TranslationError::OpenAIError(format!("Invalid model list: {}", e)).into())
+    }
+}
+
+/// Turn an unsuccessful upstream response into an error, with the server's message
+/// (`{"error": {"message": ...}}` from OpenAI, `{"error": ...}` from Ollama) and retry hints
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let header_retry_after = parse_retry_after(response.headers());
+    let body = response.text().await.unwrap_or_default();
+    let json = serde_json::from_str::<serde_json::Value>(&body).ok();
+    let body_code = json
+        .as_ref()
+        .and_then(|v| v.pointer("/error/code"))
+        .and_then(|code| code.as_str())
+        .map(str::to_string);
+    let message = json
+        .and_then(|v| {
+            v.pointer("/error/message")
+                .or_else(|| v.get("error"))
+                .and_then(|m| m.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or(body);
+    if status == reqwest::StatusCode::BAD_REQUEST && is_context_length_error(body_code.as_deref(), &message) {
+        return Err(TranslationError::ContextLengthExceeded(message).into());
+    }
+    Err(TranslationError::Upstream {
+        status: status.as_u16(),
+        retry_after: header_retry_after.or_else(|| parse_retry_hint(&message)),
+        message,
+    }
+    .into())
+}
+
+/// Append the content of one line of an Ollama chat stream.
+/// Returns whether the line ends the response; errors sent mid-stream fail it.
+fn ollama_chunk(line: &[u8], content: &mut String) -> Result<bool> {
+    let line = String::from_utf8_lossy(line);
+    if line.trim().is_empty() {
+        return Ok(false);
+    }
+
+    let chunk: serde_json::Value = serde_json::from_str(line.trim())
+        .map_err(|e| TranslationError::OpenAIError(format!("Invalid stream chunk: {}", e)))?;
+    if let Some(error) = chunk.get("error").and_then(|e| e.as_str()) {
+        tracing::warn!("Stream error: {}", error);
+        return Err(TranslationError::OpenAIError(error.to_string()).into());
+    }
+    if let Some(text) = chunk.pointer("/message/content").and_then(|c| c.as_str()) {
+        content.push_str(text);
+    }
+    Ok(chunk.get("done").and_then(|d| d.as_bool()).unwrap_or(false))
+}
+
+impl Default for Translator {
+    fn default() -> Self {
+        Self::new(TranslatorConfig::default())
+    }
+}
+
+/// Whether a 400 response says the request does not fit the model's context window:
+/// OpenAI sends the `context_length_exceeded` code, compatible servers often only the message
+fn is_context_length_error(code: Option<&str>, message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    code == Some("context_length_exceeded")
+        || message.contains("context length")
+        || message.contains("context window")
+}
+
+/// Whether a failed upstream call is worth retrying.
+/// Client errors (other than timeouts, conflicts and rate limits) never succeed on retry.
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::Translation(TranslationError::Upstream { status, .. }) => {
+            !(400..500).contains(status) || matches!(status, 408 | 409 | 429)
+        }
+        Error::Translation(TranslationError::ContextLengthExceeded(_)) => false,
+        _ => true,
+    }
+}
+
+/// Exponential backoff with jitter: a random wait between half and all of
+/// `base * 2^(attempt - 1)`, capped at MAX_RETRY_DELAY
+fn backoff_delay(base: Duration, attempt: u32) -> Duration {
+    let exp = base
+        .saturating_mul(1u32 << attempt.saturating_sub(1).min(16))
+        .min(MAX_RETRY_DELAY);
+    let half = exp / 2;
+    half + half.mul_f64(rand::thread_rng().gen::<f64>())
+}
+
+/// Parse `retry-after-ms` or `retry-after` (seconds) response headers
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|v| v.is_finite() && *v >= 0.0)
+    };
+
+    header("retry-after-ms")
+        .map(|ms| Duration::from_secs_f64(ms / 1000.0))
+        .or_else(|| header("retry-after").map(Duration::from_secs_f64))
+}
+
+/// Parse rate-limit hints like "Please try again in 1.5s" or "in 300ms" from an error message
+fn parse_retry_hint(message: &str) -> Option<Duration> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        Regex::new(r"(?i)try again in (\d+(?:\.\d+)?)\s*(ms|s)\b").unwrap()
+    });
+
+    let caps = pattern.captures(message)?;
+    let value: f64 = caps[1].parse().ok()?;
+    if caps[2].eq_ignore_ascii_case("ms") {
+        Some(Duration::from_secs_f64(value / 1000.0))
+    } else {
+        Some(Duration::from_secs_f64(value))
+    }
+}
+
+/// Number of tokens `text` takes for `model`.
+/// Models tiktoken does not know are counted with the o200k_base encoding.
+pub fn count_tokens(model: &str, text: &str) -> usize {
+    tiktoken_rs::bpe_for_model(model)
+        .unwrap_or_else(|_| tiktoken_rs::o200k_base_singleton())
+        .encode_ordinary(text)
+        .len()
+}
+
+/// Run a step, returning its result with the time it took
+async fn timed<T>(step: impl std::future::Future<Output = Result<T>>) -> Result<(T, Duration)> {
+    let started = Instant::now();
+    let value = step.await?;
+    Ok((value, started.elapsed()))
+}
+
+/// Byte ranges in the body of the text inside tables and HTML blocks
+fn structured_texts(parsed: &ParsedContent) -> Vec<Range<usize>> {
+    parsed
+        .structured_blocks
+        .iter()
+        .flat_map(|block| block.texts.iter().cloned())
+        .collect()
+}
+
+/// Byte ranges in the body of the comments inside code blocks
+fn code_comments(parsed: &ParsedContent) -> Vec<Range<usize>> {
+    parsed
+        .code_blocks
+        .iter()
+        .zip(&parsed.code_block_ranges)
+        .flat_map(|((language, code, _), block)| {
+            comments::find_block_comments(language, code, &parsed.body[block.clone()])
+                .into_iter()
+                .map(move |range| block.start + range.start..block.start + range.end)
+        })
+        .collect()
+}
+
+/// Cache key part of a partial scope. Segment keys leave it out, since a body
+/// section translates the same in every scope.
+fn scope_key(scope: TranslationScope) -> String {
+    match scope {
+        TranslationScope::Full => String::new(),
+        scope => format!(":scope-{}", scope.as_str()),
+    }
+}
+
+/// Append the translation of `source`, restoring the whitespace around it that
+/// the model trims. Whitespace-only sources are appended unchanged.
+fn push_translated(out: &mut String, source: &str, translated: &str) {
+    if source.trim().is_empty() {
+        out.push_str(source);
+        return;
+    }
+    out.push_str(&source[..source.len() - source.trim_start().len()]);
+    out.push_str(translated.trim());
+    out.push_str(&source[source.trim_end().len()..]);
+}
+
+/// Split a markdown body into sections starting at ATX headings.
+/// Leading blank lines stay with the following section; the sections
+/// concatenate back to the original text.
+fn split_sections(text: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+
+    // Code blocks are usually placeholders, but stay in the text when not preserved
+    let mut fence: Option<&str> = None;
+    for line in text.split_inclusive('\n') {
+        let marker = line.trim_start().get(..3).filter(|m| *m == "```" || *m == "~~~");
+        match (fence, marker) {
+            (None, Some(marker)) => fence = Some(marker),
+            (Some(open), Some(marker)) if open == marker => fence = None,
+            _ => {}
+        }
+        let hashes = line.len() - line.trim_start_matches('#').len();
+        let is_heading = fence.is_none()
+            && (1..=6).contains(&hashes)
+            && line[hashes..].chars().next().is_none_or(char::is_whitespace);
+        if is_heading && !text[start..offset].trim().is_empty() {
+            sections.push(&text[start..offset]);
+            start = offset;
+        }
+        offset += line.len();
+    }
+    if start < text.len() {
+        sections.push(&text[start..]);
+    }
+
+    sections
+}
+
+/// Split text into chunks of at most `limit` tokens, preferring paragraph
+/// boundaries and falling back to lines for oversized paragraphs.
+/// The chunks concatenate back to the original text; a single line over
+/// the limit cannot be split and is rejected.
+fn chunk_text(text: &str, model: &str, limit: usize) -> Result<Vec<String>> {
+    let total = count_tokens(model, text);
+    if total <= limit {
+        return Ok(vec![text.to_string()]);
+    }
+
+    // Paragraphs keep their trailing newlines, blank lines included
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            current.push_str(line);
+            continue;
+        }
+        if current.ends_with("\n\n") || current.ends_with("\n\r\n") {
+            paragraphs.push(std::mem::take(&mut current));
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+
+    let mut pieces: Vec<(String, usize)> = Vec::new();
+    for paragraph in paragraphs {
+        let tokens = count_tokens(model, &paragraph);
+        if tokens <= limit {
+            pieces.push((paragraph, tokens));
+            continue;
+        }
+        for line in paragraph.split_inclusive('\n') {
+            let tokens = count_tokens(model, line);
+            if tokens > limit {
+                return Err(TranslationError::InputTooLarge { tokens, limit }.into());
+            }
+            pieces.push((line.to_string(), tokens));
+        }
+    }
+
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut chunk_tokens = 0;
+    for (piece, tokens) in pieces {
+        if chunk_tokens + tokens > limit && !chunk.is_empty() {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_tokens = 0;
+        }
+        chunk.push_str(&piece);
+        chunk_tokens += tokens;
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+
+    Ok(chunks)
+}
+
+/// Encode content to base64 for API transmission
+pub fn encode_content(content: &str) -> String {
+    BASE64.encode(content.as_bytes())
+}
+
+/// Decode content from base64
+pub fn decode_content(encoded: &str) -> Result<String> {
+    let bytes = BASE64.decode(encoded.as_bytes())?;
+    String::from_utf8(bytes).map_err(|e| Error::InvalidContent(format!("Invalid UTF-8 content: {}", e)))
+}
+
+/// Largest size gzip compressed content may decompress to, so a small request
+/// cannot expand into an arbitrarily large document
+pub const MAX_DECOMPRESSED_BYTES: usize = 64 * 1024 * 1024;
+
+/// Encode content to base64 of its gzip compression, several times smaller than
+/// plain base64 for markdown sent over slow links
+pub fn encode_gzip_content(content: &str) -> String {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    // Writing to a Vec cannot fail
+    let compressed = encoder
+        .write_all(content.as_bytes())
+        .and_then(|()| encoder.finish())
+        .unwrap_or_default();
+    BASE64.encode(compressed)
+}
+
+/// Decode content from base64 of its gzip compression, failing when it decompresses
+/// to more than [`MAX_DECOMPRESSED_BYTES`]
+pub fn decode_gzip_content(encoded: &str) -> Result<String> {
+    let compressed = BASE64.decode(encoded.as_bytes())?;
+    let mut bytes = Vec::new();
+    GzDecoder::new(compressed.as_slice())
+        .take(MAX_DECOMPRESSED_BYTES as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| Error::InvalidContent(format!("Invalid gzip content: {}", e)))?;
+    if bytes.len() > MAX_DECOMPRESSED_BYTES {
+        return Err(Error::InvalidContent(format!(
+            "Content decompresses to more than {} bytes",
+            MAX_DECOMPRESSED_BYTES
+        )));
+    }
+    String::from_utf8(bytes).map_err(|e| Error::InvalidContent(format!("Invalid UTF-8 content: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_hash() {
+        let content = "hello world";
+        let hash = Translator::compute_hash(content);
+        assert!(hash.starts_with("sha256:"));
+        assert_eq!(hash.len(), 71); // "sha256:" + 64 hex chars
+    }
+
+    #[test]
+    fn test_retry_classification() {
+        let upstream = |status| {
+            Error::Translation(TranslationError::Upstream {
+                status,
+                message: String::new(),
+                retry_after: None,
+            })
+        };
+        assert!(!is_retryable(&upstream(400)));
+        assert!(!is_retryable(&upstream(401)));
+        assert!(is_retryable(&upstream(429)));
+        assert!(is_retryable(&upstream(503)));
+        assert!(is_retryable(&TranslationError::OpenAIError("reset".into()).into()));
+        assert!(!is_retryable(&TranslationError::ContextLengthExceeded(String::new()).into()));
+
+        assert!(is_context_length_error(Some("context_length_exceeded"), ""));
+        assert!(is_context_length_error(
+            None,
+            "This model's maximum context length is 8192 tokens. However, your messages resulted in 9000 tokens."
+        ));
+        assert!(!is_context_length_error(Some("invalid_request_error"), "Invalid 'messages'"));
+    }
+
+    #[test]
+    fn test_backoff_delay_grows_and_caps() {
+        let base = Duration::from_secs(2);
+        let first = backoff_delay(base, 1);
+        assert!(first >= Duration::from_secs(1) && first <= Duration::from_secs(2));
+        let third = backoff_delay(base, 3);
+        assert!(third >= Duration::from_secs(4) && third <= Duration::from_secs(8));
+        assert!(backoff_delay(base, 30) <= MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_parse_retry_hints() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", "7".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(7)));
+
+        assert_eq!(
+            parse_retry_hint("Rate limit reached. Please try again in 1.5s."),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(parse_retry_hint("try again in 300ms"), Some(Duration::from_millis(300)));
+        assert_eq!(parse_retry_hint("invalid api key"), None);
+    }
+
+    #[test]
+    fn test_circuit_breaker_opens_and_half_opens() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(50));
+        assert!(breaker.allow().is_ok());
+
+        breaker.record_failure();
+        assert!(breaker.allow().is_ok());
+        breaker.record_failure();
+        assert!(breaker.allow().is_err());
+        assert_eq!(breaker.status().state, "open");
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(breaker.status().state, "half_open");
+        // Only one probe is let through while half-open
+        assert!(breaker.allow().is_ok());
+        assert!(breaker.allow().is_err());
+
+        breaker.record_success();
+        assert_eq!(breaker.status().state, "closed");
+        assert!(breaker.allow().is_ok());
+    }
+
+    #[test]
+    fn test_reconfigure_swaps_models_and_keeps_scheduler() {
+        let translator = Translator::default();
+        let scheduler = translator.runtime.load().scheduler.clone();
+
+        translator.reconfigure(&TranslatorConfig {
+            model: "gpt-4o".to_string(),
+            model_fallbacks: vec!["gpt-4o".to_string(), "gpt-4o-mini".to_string()],
+            ..TranslatorConfig::default()
+        });
+        assert_eq!(translator.models(), vec!["gpt-4o", "gpt-4o-mini"]);
+        assert!(Arc::ptr_eq(&scheduler, &translator.runtime.load().scheduler));
+
+        translator.reconfigure(&TranslatorConfig {
+            max_concurrent_translations: 1,
+            ..TranslatorConfig::default()
+        });
+        assert!(!Arc::ptr_eq(&scheduler, &translator.runtime.load().scheduler));
+        assert_eq!(translator.runtime.load().scheduler.available_permits(), 1);
+    }
+
+    #[test]
+    fn test_ollama_stream_and_plain_prompt() {
+        let mut content = String::new();
+        let first = r#"{"message":{"role":"assistant","content":"你"},"done":false}"#;
+        let last = r#"{"message":{"role":"assistant","content":"好"},"done":true}"#;
+        assert!(!ollama_chunk(first.as_bytes(), &mut content).unwrap());
+        assert!(ollama_chunk(last.as_bytes(), &mut content).unwrap());
+        assert_eq!(content, "你好");
+        assert!(ollama_chunk(br#"{"error":"model not found"}"#, &mut content).is_err());
+
+        let messages = PromptStyle::Plain.messages("Translate to Chinese.", "# Hello", TRANSLATION_LEAD);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].1.starts_with("Translate to Chinese.") && messages[0].1.ends_with("# Hello"));
+        assert_eq!(PromptStyle::Chat.messages("p", "t", "")[0], ("system", "p".to_string()));
+        assert_eq!("Ollama".parse::<Provider>(), Ok(Provider::Ollama));
+    }
+
+    #[tokio::test]
+    async fn test_profile_overrides_retries() {
+        // Nothing listens on port 1, so every attempt fails at once
+        let translator = Translator::new(TranslatorConfig {
+            base_url: "http://127.0.0.1:1/v1".to_string(),
+            ..TranslatorConfig::default()
+        });
+        let profile = TranslationProfile {
+            max_retries: Some(0),
+            timeout_seconds: Some(5),
+            ..TranslationProfile::new("en", "zh-CN")
+        };
+        let error = translator.translate("# Hello\n", &profile).await.unwrap_err();
+        assert!(error.to_string().contains("after 1 attempts"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_progress_reports_upstream_calls() {
+        let translator = Translator::new(TranslatorConfig {
+            base_url: "http://127.0.0.1:1/v1".to_string(),
+            ..TranslatorConfig::default()
+        });
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let reports = reports.clone();
+            ProgressSink::new(move |progress| reports.lock().unwrap().push(progress))
+        };
+        let profile = TranslationProfile {
+            max_retries: Some(0),
+            progress: Some(sink),
+            ..TranslationProfile::new("en", "zh-CN")
+        };
+        let content = "---\nname: demo\ndescription: A demo skill\n---\n# Usage\n";
+        assert!(translator.translate(content, &profile).await.is_err());
+
+        // The description is sent alongside the body rather than after it
+        assert_eq!(
+            *reports.lock().unwrap(),
+            vec![Progress { chunk: 1, total: 2 }, Progress { chunk: 2, total: 2 }]
+        );
+    }
+
+    /// An OpenAI-compatible upstream on a local port answering each request with its text
+    /// in upper case after `delay(text)`; texts containing `FAIL` are refused with 400
+    fn mock_upstream(delay: fn(&str) -> Duration) -> String {
+        use std::io::BufRead;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                std::thread::spawn(move || {
+                    let mut reader = std::io::BufReader::new(&stream);
+                    let mut length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line.trim().is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                length = value.trim().parse().unwrap();
+                            }
+                        }
+                    }
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+                    let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                    let text = request["messages"].as_array().unwrap().last().unwrap()["content"]
+                        .as_str()
+                        .unwrap()
+                        .to_string();
+
+                    std::thread::sleep(delay(&text));
+                    let response = if text.contains("FAIL") {
+                        let error = r#"{"error":{"message":"refused"}}"#;
+                        format!(
+                            "HTTP/1.1 400 Bad Request\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                            error.len(),
+                            error
+                        )
+                    } else {
+                        let chunk = serde_json::json!({
+                            "id": "mock",
+                            "object": "chat.completion.chunk",
+                            "created": 0,
+                            "model": request["model"],
+                            "choices": [{"index": 0, "delta": {"content": text.to_uppercase()}, "finish_reason": null}],
+                        });
+                        format!(
+                            "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nconnection: close\r\n\r\ndata: {}\n\ndata: [DONE]\n\n",
+                            chunk
+                        )
+                    };
+                    let _ = (&stream).write_all(response.as_bytes());
+                });
+            }
+        });
+        base_url
+    }
+
+    #[tokio::test]
+    async fn test_description_and_body_translate_concurrently() {
+        let translator = Translator::new(TranslatorConfig {
+            base_url: mock_upstream(|_| Duration::from_millis(300)),
+            ..TranslatorConfig::default()
+        });
+        let profile = TranslationProfile {
+            max_retries: Some(0),
+            ..TranslationProfile::new("en", "zh-CN")
+        };
+        let content = "---\nname: demo\ndescription: A demo skill\n---\n# Usage\n\nRun the demo.\n";
+        let (translated, metadata) = translator.translate(content, &profile).await.unwrap();
+
+        assert_eq!(
+            translated,
+            "---\nname: demo\ndescription: A DEMO SKILL\n---\n# USAGE\n\nRUN THE DEMO."
+        );
+        assert_eq!((metadata.chunks, metadata.warnings.len()), (2, 0), "{:?}", metadata);
+        assert!(metadata.body_time_ms >= 300.0 && metadata.description_time_ms >= 300.0, "{:?}", metadata);
+        // Run one after the other, the parts would take at least their total
+        assert!(
+            metadata.processing_time_ms < metadata.body_time_ms + metadata.description_time_ms,
+            "{:?}",
+            metadata
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_description_cancels_body() {
+        // The body would take far longer than the description takes to fail
+        let translator = Translator::new(TranslatorConfig {
+            base_url: mock_upstream(|text| {
+                if text.contains("FAIL") {
+                    Duration::ZERO
+                } else {
+                    Duration::from_secs(5)
+                }
+            }),
+            ..TranslatorConfig::default()
+        });
+        let profile = TranslationProfile {
+            max_retries: Some(0),
+            ..TranslationProfile::new("en", "zh-CN")
+        };
+        let content = "---\nname: demo\ndescription: FAIL here\n---\n# Usage\n";
+        let started = Instant::now();
+        let error = translator.translate(content, &profile).await.unwrap_err();
+
+        assert!(error.to_string().contains("refused"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(2), "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn test_preview_segments_without_api_call() {
+        let translator = Translator::default();
+        let content = "---\nname: demo\ndescription: A demo skill\n---\n# Usage\n\n```bash\nrun demo\n```\n";
+        let preview = translator.preview(content).unwrap();
+
+        assert_eq!(preview.code_block_count, 1);
+        assert_eq!(preview.api_calls, 2);
+        assert_eq!(
+            preview.frontmatter_fields,
+            vec![("description".to_string(), "A demo skill".to_string())]
+        );
+        assert!(preview.body_with_placeholders.contains("___CODE_BLOCK_0___"));
+        assert!(preview.estimated_input_tokens > preview.estimated_output_tokens);
+    }
+
+    #[test]
+    fn test_comment_translation_changes_cache_keys() {
+        let translator = Translator::default();
+        let plain = TranslationProfile::new("en", "zh-CN");
+        let comments = TranslationProfile {
+            translate_code_comments: true,
+            ..plain.clone()
+        };
+        assert_ne!(
+            translator.compute_cache_key("sha256:x", &plain),
+            translator.compute_cache_key("sha256:x", &comments)
+        );
+        assert_ne!(
+            translator.segment_key("# Usage", &plain),
+            translator.segment_key("# Usage", &comments)
+        );
+    }
+
+    #[test]
+    fn test_code_as_text_changes_cache_keys() {
+        let translator = Translator::default();
+        let plain = TranslationProfile::new("en", "zh-CN");
+        let code_as_text = TranslationProfile {
+            preserve_code_blocks: false,
+            ..plain.clone()
+        };
+        assert_ne!(
+            translator.compute_cache_key("sha256:x", &plain),
+            translator.compute_cache_key("sha256:x", &code_as_text)
+        );
+        assert_ne!(
+            translator.segment_key("# Usage", &plain),
+            translator.segment_key("# Usage", &code_as_text)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scope_skips_parts_and_changes_document_keys() {
+        // Nothing listens on port 1, so any upstream call would fail
+        let translator = Translator::new(TranslatorConfig {
+            base_url: "http://127.0.0.1:1/v1".to_string(),
+            ..TranslatorConfig::default()
+        });
+        let full = TranslationProfile {
+            max_retries: Some(0),
+            ..TranslationProfile::new("en", "zh-CN")
+        };
+        let frontmatter = TranslationProfile {
+            scope: TranslationScope::Frontmatter,
+            ..full.clone()
+        };
+        let body = TranslationProfile {
+            scope: TranslationScope::Body,
+            ..full.clone()
+        };
+
+        let body_only = "---\nname: demo\n---\n# Usage\n";
+        let (translated, metadata) = translator.translate(body_only, &frontmatter).await.unwrap();
+        assert_eq!((translated.as_str(), metadata.chunks), (body_only, 0));
+        let description_only = "---\nname: demo\ndescription: A demo skill\n---\n";
+        let (translated, _) = translator.translate(description_only, &body).await.unwrap();
+        assert_eq!(translated, description_only);
+        assert!(translator.translate(body_only, &full).await.is_err());
+
+        let full_key = translator.compute_cache_key("sha256:x", &full);
+        assert_ne!(full_key, translator.compute_cache_key("sha256:x", &frontmatter));
+        assert_ne!(full_key, translator.compute_cache_key("sha256:x", &body));
+        assert_eq!(
+            translator.segment_key("# Usage", &full),
+            translator.segment_key("# Usage", &body)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resumable_translation_reuses_stored_results() {
+        // Nothing listens on port 1, so only the stored result can complete the translation
+        let translator = Translator::new(TranslatorConfig {
+            base_url: "http://127.0.0.1:1/v1".to_string(),
+            ..TranslatorConfig::default()
+        });
+        let dir = std::env::temp_dir().join(format!("skillts-partials-{}", std::process::id()));
+        let cache = TranslationCache::new(crate::cache::CacheConfig {
+            db_path: dir.join("cache.db").to_string_lossy().into_owned(),
+            ..crate::cache::CacheConfig::default()
+        })
+        .await
+        .unwrap();
+        let profile = TranslationProfile {
+            max_retries: Some(0),
+            scope: TranslationScope::Frontmatter,
+            ..TranslationProfile::new("en", "zh-CN")
+        };
+        let content = "---\nname: demo\ndescription: A demo skill\n---\n# Usage\n";
+        assert!(translator.translate_resumable(content, &profile, &cache).await.is_err());
+
+        // A result stored before the crash is used instead of calling upstream again
+        let prompt = translator.system_prompt(&profile, "en", "zh-CN");
+        let partial_key = translator.partial_key(&translator.runtime.load(), &prompt, "A demo skill");
+        cache.set_partial("default", &partial_key, "演示技能").await.unwrap();
+        let (translated, metadata) = translator.translate_resumable(content, &profile, &cache).await.unwrap();
+        assert!(translated.contains("description: 演示技能"), "{}", translated);
+        assert_eq!(metadata.chunks, 0);
+
+        // and deleted once the document is translated
+        assert_eq!(cache.get_partial(&partial_key).await.unwrap(), None);
+
+        cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_tenant_changes_cache_keys() {
+        let translator = Translator::default();
+        let default = TranslationProfile::new("en", "zh-CN");
+        let tenant = TranslationProfile {
+            tenant: "registry-a".to_string(),
+            ..default.clone()
+        };
+        assert_ne!(
+            translator.cache_keys("sha256:x", &default),
+            translator.cache_keys("sha256:x", &tenant)
+        );
+        assert_ne!(
+            translator.segment_key("# Usage", &default),
+            translator.segment_key("# Usage", &tenant)
+        );
+    }
+
+    #[test]
+    fn test_system_prompt_names_languages() {
+        let prompt = PromptTemplate::builtin().render("zh-CN", "en", &LanguageRules::new(true, Vec::new()));
+        assert!(prompt.contains("from Chinese (Simplified) to English."));
+        assert!(prompt.ends_with("content to English:"));
+    }
+
+    #[test]
+    fn test_count_tokens() {
+        assert_eq!(count_tokens("gpt-4o-mini", ""), 0);
+        assert_eq!(count_tokens("gpt-4o-mini", "hello world"), 2);
+        // Unknown models fall back to o200k_base
+        assert_eq!(
+            count_tokens("local-model", "你好，世界"),
+            count_tokens("gpt-4o", "你好，世界")
+        );
+    }
+
+    #[test]
+    fn test_chunk_text_splits_at_paragraphs() {
+        let text = "First paragraph here.\n\nSecond paragraph here.\n\nThird one.\n";
+        assert_eq!(chunk_text(text, "gpt-4o-mini", 1000).unwrap(), vec![text.to_string()]);
+
+        let chunks = chunk_text(text, "gpt-4o-mini", 8).unwrap();
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), text);
+        assert!(chunks[0].starts_with("First") && chunks[0].ends_with("\n\n"));
+        for chunk in &chunks {
+            assert!(count_tokens("gpt-4o-mini", chunk) <= 8);
+        }
+    }
+
+    #[test]
+    fn test_split_sections_at_headings() {
+        let text = "\nIntro line.\n\n# Usage\n\nRun it.\n\n## Notes\n#hashtag is not a heading\n";
+        let sections = split_sections(text);
+        assert_eq!(
+            sections,
+            vec![
+                "\nIntro line.\n\n",
+                "# Usage\n\nRun it.\n\n",
+                "## Notes\n#hashtag is not a heading\n",
+            ]
+        );
+        assert_eq!(sections.concat(), text);
+        assert_eq!(split_sections("# Only\n"), vec!["# Only\n"]);
+
+        // Comments in code kept in the text are not headings
+        let code = "# Setup\n```bash\n# install\nnpm i\n```\n# Usage\n";
+        assert_eq!(
+            split_sections(code),
+            vec!["# Setup\n```bash\n# install\nnpm i\n```\n", "# Usage\n"]
+        );
+    }
+
+    #[test]
+    fn test_prior_translation_pairs_sections() {
+        let translator = Translator::default();
+        let profile = TranslationProfile::new("en", "zh-CN");
+        let old = "---\ndescription: Demo\n---\n# Usage\n\n```bash\n# not a heading\n```\n\n# Notes\nKeep it.\n";
+        let translated = "---\ndescription: 演示\n---\n# 用法\n\n```bash\n# not a heading\n```\n\n# 备注\n保留。\n";
+        let prior = translator
+            .prior_translation(old, translated, &profile)
+            .unwrap();
+
+        let key = translator.segment_key("# Notes\nKeep it.", &profile);
+        assert_eq!(prior.sections.len(), 2);
+        assert_eq!(prior.sections[&key], "# 备注\n保留。");
+        assert_eq!(
+            prior.description,
+            Some(("Demo".to_string(), "演示".to_string()))
+        );
+
+        let mismatched = "# 用法\n\n```bash\n# not a heading\n```\n";
+        assert!(matches!(
+            translator.prior_translation(old, mismatched, &profile),
+            Err(Error::InvalidContent(_))
+        ));
+    }
+
+    #[test]
+    fn test_push_translated_keeps_surrounding_whitespace() {
+        let mut out = String::new();
+        push_translated(&mut out, "\n# Title\n\n", "# 标题\n");
+        push_translated(&mut out, "\n\n", "ignored");
+        assert_eq!(out, "\n# 标题\n\n\n\n");
+    }
+
+    #[test]
+    fn test_chunk_text_rejects_oversized_line() {
+        let text = format!("short\n{}\n", "word ".repeat(50));
+        let err = chunk_text(&text, "gpt-4o-mini", 10).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Translation(TranslationError::InputTooLarge { limit: 10, .. })
+        ));
+    }
+
+    #[test]
+    fn test_encode_decode_content() {
+        let original = "Hello, 世界!";
+        let encoded = encode_content(original);
+        let decoded = decode_content(&encoded).unwrap();
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn test_gzip_content() {
+        let original = "# Usage\n\nRun the demo, 世界!\n".repeat(100);
+        let encoded = encode_gzip_content(&original);
+        assert!(encoded.len() < encode_content(&original).len() / 10);
+        assert_eq!(decode_gzip_content(&encoded).unwrap(), original);
+
+        assert!(decode_gzip_content(&encode_content("not gzip")).is_err());
+        let bomb = encode_gzip_content(&" ".repeat(MAX_DECOMPRESSED_BYTES + 1));
+        let error = decode_gzip_content(&bomb).unwrap_err();
+        assert!(error.to_string().contains("decompresses to more than"), "{}", error);
+    }
+}
\ No newline at end of file
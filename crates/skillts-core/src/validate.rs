@@ -0,0 +1,181 @@
+//! Structural validation of translated documents.
+//!
+//! Compares a translation with its original without calling any model: heading
+//! and code block counts, frontmatter keys, link targets, placeholders that the
+//! model should have left for restoration, and whether the translated
+//! frontmatter is still valid YAML.
+
+use pulldown_cmark::{Event, Parser, Tag};
+use regex::Regex;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use crate::models::{CountCheck, ValidationReport};
+use crate::parser::ContentParser;
+
+impl CountCheck {
+    fn new(original: usize, translated: usize) -> Self {
+        Self {
+            original,
+            translated,
+            ok: original == translated,
+        }
+    }
+}
+
+impl ValidationReport {
+    /// Whether the body checks passed: headings, code blocks, links and placeholders
+    pub fn body_ok(&self) -> bool {
+        self.headings.ok
+            && self.code_blocks.ok
+            && self.missing_link_targets.is_empty()
+            && self.leaked_placeholders.is_empty()
+    }
+
+    /// Whether the frontmatter kept its keys and is valid YAML
+    pub fn frontmatter_ok(&self) -> bool {
+        self.missing_frontmatter_keys.is_empty() && self.frontmatter_error.is_none()
+    }
+
+    /// One sentence per failed check, e.g. for logs or a corrective prompt
+    pub fn findings(&self) -> Vec<String> {
+        let mut findings = Vec::new();
+        for (name, check) in [("headings", &self.headings), ("code blocks", &self.code_blocks)] {
+            if !check.ok {
+                findings.push(format!(
+                    "The original has {} {} but the translation has {}",
+                    check.original, name, check.translated
+                ));
+            }
+        }
+        if !self.missing_frontmatter_keys.is_empty() {
+            findings.push(format!(
+                "Frontmatter keys are missing: {}",
+                self.missing_frontmatter_keys.join(", ")
+            ));
+        }
+        if !self.missing_link_targets.is_empty() {
+            findings.push(format!(
+                "Link targets are missing or changed: {}",
+                self.missing_link_targets.join(", ")
+            ));
+        }
+        if !self.leaked_placeholders.is_empty() {
+            findings.push(format!(
+                "Placeholders were left in the output: {}",
+                self.leaked_placeholders.join(", ")
+            ));
+        }
+        if let Some(error) = &self.frontmatter_error {
+            findings.push(format!("The frontmatter is not valid YAML: {}", error));
+        }
+        findings
+    }
+}
+
+/// Compare the structure of `translated` with `original`
+pub fn validate_translation(original: &str, translated: &str) -> ValidationReport {
+    let parser = ContentParser::new();
+    let source = parser.parse(original);
+    let target = parser.parse(translated);
+
+    let mut missing_frontmatter_keys: Vec<String> = source
+        .frontmatter_dict
+        .keys()
+        .filter(|key| !target.frontmatter_dict.contains_key(*key))
+        .cloned()
+        .collect();
+    missing_frontmatter_keys.sort();
+
+    let frontmatter_error = if !source.frontmatter.is_empty() && target.frontmatter.is_empty() {
+        Some("Frontmatter is missing".to_string())
+    } else {
+        parser.frontmatter_error(&target.frontmatter)
+    };
+
+    let translated_targets = link_targets(&target.body);
+    let mut missing_link_targets: Vec<String> = link_targets(&source.body)
+        .into_iter()
+        .filter(|target| !translated_targets.contains(target))
+        .collect();
+    missing_link_targets.sort();
+
+    let original_placeholders = placeholders(original);
+    let mut leaked_placeholders: Vec<String> = placeholders(translated)
+        .into_iter()
+        .filter(|placeholder| !original_placeholders.contains(placeholder))
+        .collect();
+    leaked_placeholders.sort();
+
+    let headings = CountCheck::new(heading_count(&source.body), heading_count(&target.body));
+    let code_blocks = CountCheck::new(source.code_blocks.len(), target.code_blocks.len());
+
+    let mut report = ValidationReport {
+        valid: false,
+        headings,
+        code_blocks,
+        missing_frontmatter_keys,
+        missing_link_targets,
+        leaked_placeholders,
+        frontmatter_error,
+    };
+    report.valid = report.body_ok() && report.frontmatter_ok();
+    report
+}
+
+fn heading_count(body: &str) -> usize {
+    Parser::new(body)
+        .filter(|event| matches!(event, Event::Start(Tag::Heading { .. })))
+        .count()
+}
+
+/// Destinations of links and images
+fn link_targets(body: &str) -> HashSet<String> {
+    Parser::new(body)
+        .filter_map(|event| match event {
+            Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. }) => {
+                Some(dest_url.to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Code block, table, HTML and inline span placeholders in text
+fn placeholders(text: &str) -> HashSet<String> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| Regex::new(r"___(?:CODE_BLOCK|TABLE|HTML|IMAGE|LINK_DEF|EMOJI)_\d+___").unwrap());
+    pattern
+        .find_iter(text)
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = "---\nname: demo\ndescription: A demo\n---\n# Usage\n\nSee [docs](https://example.com/docs).\n\n```bash\nrun\n```\n";
+
+    #[test]
+    fn test_valid_translation() {
+        let translated = "---\nname: demo\ndescription: 演示\n---\n# 用法\n\n参见[文档](https://example.com/docs)。\n\n```bash\nrun\n```\n";
+        let report = validate_translation(ORIGINAL, translated);
+        assert!(report.valid, "{:?}", report.findings());
+        assert!(report.findings().is_empty());
+    }
+
+    #[test]
+    fn test_structural_problems_are_reported() {
+        let translated = "---\nname: demo\ndescription: \"演示\n---\n用法\n\n参见[文档](https://example.com/doc)。\n\n___CODE_BLOCK_0___\n";
+        let report = validate_translation(ORIGINAL, translated);
+        assert!(!report.valid);
+        assert_eq!((report.headings.original, report.headings.translated), (1, 0));
+        assert!(!report.code_blocks.ok);
+        assert_eq!(report.missing_frontmatter_keys, vec!["description", "name"]);
+        assert_eq!(report.missing_link_targets, vec!["https://example.com/docs"]);
+        assert_eq!(report.leaked_placeholders, vec!["___CODE_BLOCK_0___"]);
+        assert!(report.frontmatter_error.is_some());
+        assert_eq!(report.findings().len(), 6);
+    }
+}
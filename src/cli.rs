@@ -0,0 +1,315 @@
+//! Command line interface.
+//!
+//! Without a subcommand (or with `serve`) the HTTP server is started.
+//! `translate` runs the translator against the same cache database locally,
+//! writing translated files next to the originals or into an output directory.
+//! Quotas, the token budget, terminology memory and reviews are left to the server;
+//! translations are still recorded in the audit log.
+
+use clap::{Args, Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::config::Settings;
+use crate::error::AppError;
+use crate::services::audit::{AuditLog, AuditRecord};
+use crate::services::cache::TranslationCache;
+use crate::services::github::{glob_to_regex, translated_path};
+use crate::services::pipeline::Pipeline;
+use crate::services::prompts::PromptStore;
+use crate::services::translator::{TranslationMetadata, TranslationProfile, Translator};
+use skillts_core::scheduler::Priority;
+
+/// Audit log identity of files translated from the command line
+const AUDIT_KEY_ID: &str = "cli";
+
+/// Skill Translator command line
+#[derive(Debug, Parser)]
+#[command(name = "skillts", version, about = "Skill Translator Service")]
+pub struct Cli {
+    /// Config file (TOML or YAML); defaults to SKILLTS_CONFIG or ./skillts.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Apply pending database migrations and exit
+    #[arg(long)]
+    pub migrate_only: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Available subcommands
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP server (default)
+    Serve,
+    /// Translate a file or directory tree without starting the server
+    Translate(TranslateArgs),
+}
+
+/// Arguments of the `translate` subcommand
+#[derive(Debug, Args)]
+pub struct TranslateArgs {
+    /// File or directory to translate
+    pub path: PathBuf,
+
+    /// Target language (defaults to TARGET_LANGUAGE)
+    #[arg(long)]
+    pub target: Option<String>,
+
+    /// Source language (defaults to SOURCE_LANGUAGE)
+    #[arg(long)]
+    pub source: Option<String>,
+
+    /// Glob selecting files when translating a directory
+    #[arg(long, default_value = "**/SKILL.md")]
+    pub glob: String,
+
+    /// Write translations under this directory instead of next to the originals
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Translate again even when a cached translation exists
+    #[arg(long)]
+    pub no_cache: bool,
+}
+
+/// Run the `translate` subcommand. Returns false when any file failed.
+pub async fn run_translate(args: TranslateArgs, settings: Arc<Settings>) -> anyhow::Result<bool> {
+    let profile = TranslationProfile {
+        priority: Priority::Bulk,
+        force: args.no_cache,
+        keep_terms: settings.keep_terms.clone(),
+        ..TranslationProfile::new(
+            args.source.unwrap_or_else(|| settings.source_language.clone()),
+            args.target.unwrap_or_else(|| settings.target_language.clone()),
+        )
+    };
+
+    let (root, files) = collect_files(&args.path, &args.glob)?;
+    if files.is_empty() {
+        println!("No files matching {} under {}", args.glob, args.path.display());
+        return Ok(true);
+    }
+
+    crate::services::schema::migrate_database(&settings).await?;
+    let cache = TranslationCache::new(settings.cache_config()).await?;
+    let audit = AuditLog::new(cache.pool().clone()).await?;
+    let translator = Translator::new(settings.translator_config());
+    let prompts = PromptStore::new(cache.pool().clone()).await?;
+    if settings.prompt_templates {
+        translator.set_prompt_templates(prompts.list().await?);
+    }
+    let pipeline = Pipeline {
+        translator: &translator,
+        cache: &cache,
+        settings: &settings,
+    };
+
+    let mut translated_count = 0usize;
+    let mut cached_count = 0usize;
+    let mut failed = 0usize;
+
+    for relative in &files {
+        let source_path = root.join(relative);
+        let output_path = args
+            .output_dir
+            .as_deref()
+            .unwrap_or(&root)
+            .join(translated_path(relative, &profile.target_language));
+
+        let result = match tokio::fs::read_to_string(&source_path).await {
+            Ok(text) => translate_file(pipeline, &audit, relative, &text, &profile)
+                .await
+                .map_err(anyhow::Error::from),
+            Err(e) => Err(e.into()),
+        };
+
+        let written = match result {
+            Ok((translated, cached)) => write_output(&output_path, &translated)
+                .await
+                .map(|_| cached)
+                .map_err(anyhow::Error::from),
+            Err(e) => Err(e),
+        };
+
+        match written {
+            Ok(cached) => {
+                if cached {
+                    cached_count += 1;
+                } else {
+                    translated_count += 1;
+                }
+                println!(
+                    "{} {} -> {}",
+                    if cached { "cached    " } else { "translated" },
+                    relative,
+                    output_path.display()
+                );
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("failed     {}: {}", relative, e);
+            }
+        }
+    }
+
+    if let Err(e) = cache.close().await {
+        tracing::error!("Error during cache shutdown: {}", e);
+    }
+
+    println!(
+        "{} translated, {} cached, {} failed",
+        translated_count, cached_count, failed
+    );
+
+    Ok(failed == 0)
+}
+
+/// Translate one file, or take its cached translation, and record it in the audit log.
+/// Returns the translation and whether it came from the cache.
+async fn translate_file(
+    pipeline: Pipeline<'_>,
+    audit: &AuditLog,
+    path: &str,
+    content: &str,
+    profile: &TranslationProfile,
+) -> Result<(String, bool), AppError> {
+    let start_time = Instant::now();
+    let outcome = translate_or_reuse(pipeline, path, content, profile).await;
+
+    let cost_usd = match &outcome {
+        Ok((_, Some(metadata))) => pipeline
+            .settings
+            .cost_usd(metadata.input_tokens, metadata.output_tokens),
+        _ => 0.0,
+    };
+    audit
+        .record(AuditRecord {
+            api_key_id: AUDIT_KEY_ID,
+            tenant: &profile.tenant,
+            path,
+            content_hash: &Translator::compute_hash(content),
+            source_language: &profile.source_language,
+            target_language: &profile.target_language,
+            cached: matches!(outcome, Ok((_, None))),
+            shared: false,
+            duration_ms: start_time.elapsed().as_secs_f64() * 1000.0,
+            cost_usd,
+            error: outcome.as_ref().err().map(|e| e.to_string()),
+        })
+        .await;
+
+    outcome.map(|(translated, metadata)| (translated, metadata.is_none()))
+}
+
+/// The cached translation of a file, or a fresh one together with its metadata
+async fn translate_or_reuse(
+    pipeline: Pipeline<'_>,
+    path: &str,
+    content: &str,
+    profile: &TranslationProfile,
+) -> Result<(String, Option<TranslationMetadata>), AppError> {
+    let prepared = pipeline.prepare(content, None, profile)?;
+    if !prepared.profile.force {
+        if let Some(cached) = pipeline.cached(&prepared, path, None).await? {
+            return Ok((cached.translated_content, None));
+        }
+    }
+
+    let translated = pipeline
+        .translate(
+            &prepared.content,
+            &prepared.content_hash,
+            path,
+            &prepared.profile,
+            prepared.lossy(),
+            None,
+        )
+        .await?;
+    Ok((translated.translated_content, Some(translated.metadata)))
+}
+
+/// Write a translated file, creating parent directories as needed
+async fn write_output(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, content).await
+}
+
+/// Resolve the input into a root directory and `/`-separated relative file paths.
+/// A single file is always included; directories are filtered by the glob.
+fn collect_files(path: &Path, glob: &str) -> anyhow::Result<(PathBuf, Vec<String>)> {
+    if path.is_file() {
+        let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid file name: {}", path.display()))?;
+        return Ok((root, vec![name.to_string()]));
+    }
+
+    let pattern = glob_to_regex(glob)?;
+    let mut files = Vec::new();
+    walk_dir(path, "", &mut files)?;
+    files.retain(|f| pattern.is_match(f));
+    files.sort();
+
+    Ok((path.to_path_buf(), files))
+}
+
+/// Recursively list files below `dir`, skipping hidden entries such as `.git`
+fn walk_dir(dir: &Path, prefix: &str, files: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let relative = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk_dir(&entry.path(), &relative, files)?;
+        } else if file_type.is_file() {
+            files.push(relative);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_files_filters_by_glob() {
+        let dir = std::env::temp_dir().join(format!("skillts-cli-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("skills/a")).unwrap();
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::write(dir.join("skills/a/SKILL.md"), "# A").unwrap();
+        std::fs::write(dir.join("skills/a/notes.md"), "notes").unwrap();
+        std::fs::write(dir.join(".git/SKILL.md"), "ignored").unwrap();
+
+        let (root, files) = collect_files(&dir, "**/SKILL.md").unwrap();
+        assert_eq!(root, dir);
+        assert_eq!(files, vec!["skills/a/SKILL.md".to_string()]);
+
+        let (root, files) = collect_files(&dir.join("skills/a/notes.md"), "**/SKILL.md").unwrap();
+        assert_eq!(root, dir.join("skills/a"));
+        assert_eq!(files, vec!["notes.md".to_string()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,104 +1,1006 @@
 //! Configuration management for skill-translator.
 //!
-//! Loads settings from environment variables and .env file.
+//! Loads settings from environment variables, the .env file and an optional
+//! `skillts.toml` / YAML config file, and validates them at startup.
 //! Fully compatible with Python version's configuration format.
 
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::env;
-use std::path::PathBuf;
-use std::sync::OnceLock;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use ipnet::IpNet;
+use thiserror::Error;
 
-/// Global settings instance
-static SETTINGS: OnceLock<Settings> = OnceLock::new();
+use skillts_core::cache::blob::{BlobStoreKind, S3Bucket};
+use skillts_core::cache::{CacheBackendKind, CacheConfig, SqliteTuning};
+use skillts_core::language::same_language;
+use skillts_core::models::StaleVersionPolicy;
+use skillts_core::prompt::{LanguageRule, LanguageRules, ANY};
+use skillts_core::translator::{PromptStyle, Provider, TranslatorConfig};
+
+use crate::models::schemas::LongLineMode;
+use crate::services::{ip_filter, signing};
+use crate::services::schedule::Schedule;
+
+/// Config file picked up from the working directory when no path is given
+const DEFAULT_CONFIG_FILE: &str = "skillts.toml";
+
+/// Log output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable single-line logs
+    Text,
+    /// One JSON object per event, including span fields
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "text" | "" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("unknown log format '{}', expected 'text' or 'json'", other)),
+        }
+    }
+}
+
+/// Where the HTTP API listens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Listen {
+    /// TCP on HOST:PORT
+    Tcp,
+    /// A Unix domain socket at this path
+    Unix(PathBuf),
+}
+
+impl FromStr for Listen {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "tcp" | "" => Ok(Listen::Tcp),
+            value => match value.strip_prefix("unix:") {
+                Some(path) if !path.is_empty() => Ok(Listen::Unix(PathBuf::from(path))),
+                _ => Err(format!("unknown listen address '{}', expected 'tcp' or 'unix:<path>'", value)),
+            },
+        }
+    }
+}
+
+/// Size of the host the service runs on, picking the defaults of resource settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeploymentProfile {
+    /// A low-memory VPS
+    Small,
+    Standard,
+    /// A dedicated host serving a busy registry
+    Large,
+}
+
+impl DeploymentProfile {
+    /// Default SQLite pool size and pragmas of the profile
+    pub fn sqlite_tuning(self) -> SqliteTuning {
+        match self {
+            DeploymentProfile::Small => SqliteTuning::default(),
+            DeploymentProfile::Standard => SqliteTuning {
+                max_connections: 4,
+                cache_size_kib: 32_000,
+                busy_timeout_ms: 5000,
+                wal_autocheckpoint_pages: 1000,
+            },
+            DeploymentProfile::Large => SqliteTuning {
+                max_connections: 8,
+                cache_size_kib: 128_000,
+                busy_timeout_ms: 10_000,
+                wal_autocheckpoint_pages: 4000,
+            },
+        }
+    }
+}
+
+impl FromStr for DeploymentProfile {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "small" | "" => Ok(DeploymentProfile::Small),
+            "standard" => Ok(DeploymentProfile::Standard),
+            "large" => Ok(DeploymentProfile::Large),
+            other => Err(format!(
+                "unknown deployment profile '{}', expected 'small', 'standard' or 'large'",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for DeploymentProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DeploymentProfile::Small => "small",
+            DeploymentProfile::Standard => "standard",
+            DeploymentProfile::Large => "large",
+        })
+    }
+}
+
+/// Permission bits of a file, written in octal like `chmod`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMode(pub u32);
+
+impl FromStr for FileMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match u32::from_str_radix(value.trim(), 8) {
+            Ok(mode) if mode <= 0o777 => Ok(FileMode(mode)),
+            _ => Err("expected octal permissions such as 660".to_string()),
+        }
+    }
+}
+
+/// Configuration loading or validation failure
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path}: {message}")]
+    File { path: String, message: String },
+
+    #[error("Invalid configuration:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+/// Variable lookup that records parse errors instead of silently using defaults
+struct Vars<F> {
+    lookup: F,
+    errors: RefCell<Vec<String>>,
+}
+
+impl<F: Fn(&str) -> Option<String>> Vars<F> {
+    fn new(lookup: F) -> Self {
+        Self {
+            lookup,
+            errors: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn string(&self, key: &str, default: &str) -> String {
+        (self.lookup)(key).unwrap_or_else(|| default.to_string())
+    }
+
+    /// Comma-separated list, skipping empty items
+    fn list(&self, key: &str) -> Vec<String> {
+        (self.lookup)(key)
+            .map(|v| {
+                v.split(',')
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Comma-separated networks in CIDR notation; a bare address is a network of one
+    fn networks(&self, key: &str) -> Vec<IpNet> {
+        self.list(key)
+            .into_iter()
+            .filter_map(|item| match ip_filter::parse_network(&item) {
+                Some(network) => Some(network),
+                None => {
+                    self.errors
+                        .borrow_mut()
+                        .push(format!("{}: expected an IP address or CIDR range, got '{}'", key, item));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Language rules read from the TOML or YAML file a variable names, if any
+    fn language_rules(&self, key: &str) -> Vec<LanguageRule> {
+        let path = self.string(key, "");
+        if path.is_empty() {
+            return Vec::new();
+        }
+        read_language_rules(Path::new(&path)).unwrap_or_else(|e| {
+            self.errors.borrow_mut().push(format!("{}: {}", key, e));
+            Vec::new()
+        })
+    }
+
+    /// Comma-separated `name=value` pairs
+    fn pairs(&self, key: &str) -> Vec<(String, String)> {
+        self.list(key)
+            .into_iter()
+            .filter_map(|item| match item.split_once('=') {
+                Some((name, value)) => Some((name.trim().to_string(), value.trim().to_string())),
+                None => {
+                    self.errors
+                        .borrow_mut()
+                        .push(format!("{}: expected name=value, got '{}'", key, item));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Comma-separated `name=limit` pairs with positive limits
+    fn limits(&self, key: &str) -> Vec<(String, usize)> {
+        self.pairs(key)
+            .into_iter()
+            .filter_map(|(name, value)| match value.parse() {
+                Ok(limit) if limit > 0 => Some((name, limit)),
+                _ => {
+                    self.errors
+                        .borrow_mut()
+                        .push(format!("{}: expected a positive limit for '{}', got '{}'", key, name, value));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn parse<T>(&self, key: &str, default: T) -> T
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let Some(value) = (self.lookup)(key) else {
+            return default;
+        };
+        match value.trim().parse() {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.errors
+                    .borrow_mut()
+                    .push(format!("{}: invalid value '{}' ({})", key, value, e));
+                default
+            }
+        }
+    }
+
+    fn into_errors(self) -> Vec<String> {
+        self.errors.into_inner()
+    }
+}
 
 /// Application settings loaded from environment variables
 #[derive(Debug, Clone)]
 pub struct Settings {
+    /// Config file the settings were loaded from, re-read on reload
+    pub config_file: Option<PathBuf>,
+
     // OpenAI configuration
+    /// Upstream protocol: OpenAI-compatible or Ollama's native API
+    pub llm_provider: Provider,
+    pub prompt_style: PromptStyle,
+    /// Apply prompt templates saved through `/api/admin/prompts`. Off by default with the
+    /// postgres backend, since saved templates stay in each replica's SQLite database.
+    pub prompt_templates: bool,
     pub openai_api_key: String,
     pub openai_model: String,
+    pub openai_model_fallbacks: Vec<String>,
     pub openai_base_url: String,
 
     // Server configuration
     pub host: String,
     pub port: u16,
+    /// Port of the gRPC API on the same host; 0 disables it
+    pub grpc_port: u16,
+    /// Where the HTTP API listens, unless systemd passes a socket
+    pub listen: Listen,
+    /// Permissions of the Unix domain socket created for `LISTEN=unix:<path>`
+    pub unix_socket_mode: FileMode,
+    /// PEM certificate chain and private key to serve HTTPS with; empty serves plain HTTP
+    pub tls_cert_path: String,
+    pub tls_key_path: String,
+    /// Port on HOST redirecting plain HTTP to HTTPS; 0 disables it
+    pub http_redirect_port: u16,
+    /// Connections served at once, 0 for no limit
+    pub max_connections: usize,
+    /// Time a client has to send request headers, including the wait for the next
+    /// request on an idle HTTP/1.1 connection
+    pub header_read_timeout_seconds: u64,
+    /// Longest pause while receiving a request body, 0 for none
+    pub body_read_timeout_seconds: u64,
+    /// Interval of HTTP/2 keep-alive pings on idle connections; 0 closes HTTP/1.1
+    /// connections after each response and sends no pings
+    pub keep_alive_seconds: u64,
     #[allow(dead_code)]
     pub reload: bool,
 
+    // Logging configuration
+    pub log_format: LogFormat,
+    pub log_filter: String,
+    /// Keep recent redacted request and response bodies for `GET /api/admin/recent-requests`
+    pub debug_payload_log: bool,
+    /// Exchanges kept by the debug payload log
+    pub debug_payload_log_size: usize,
+
     // API authentication
     pub local_api_bearer: String,
+    /// `(tenant, api key)` pairs; these keys only reach their own tenant's data
+    pub tenant_api_keys: Vec<(String, String)>,
+    /// Fresh translations per tenant per UTC day, 0 for no limit
+    pub tenant_daily_quota: u64,
+    /// Tokens of fresh translations allowed per UTC day across tenants, 0 for no limit
+    pub daily_token_budget: u64,
+    /// Estimated USD cost of fresh translations allowed per UTC month, 0 for no limit
+    pub monthly_cost_budget: f64,
+    /// Base64 encoded Ed25519 secret key signing translate responses; empty disables signing
+    pub response_signing_key: String,
+
+    // Network access control
+    /// Client networks allowed to use the service; empty allows all not denied
+    pub allowed_cidrs: Vec<IpNet>,
+    /// Client networks refused, even when also allowed
+    pub denied_cidrs: Vec<IpNet>,
+    /// Proxies whose `X-Forwarded-For` header names the client
+    pub trusted_proxies: Vec<IpNet>,
+
+    // GitHub integration
+    pub github_token: String,
+    pub github_api_url: String,
+
+    // Health check configuration
+    pub health_check_upstream: bool,
+    /// Report not ready until a test translation succeeds at startup
+    pub warmup_on_start: bool,
 
     // Translator configuration
     pub translator_version: String,
     pub target_language: String,
     pub source_language: String,
+    /// Add the built-in instructions for Japanese, Korean and Chinese targets to the prompt
+    pub language_rules: bool,
+    /// Rules per language pair read from LANGUAGE_RULES_FILE, replacing built-in ones
+    pub language_rules_file: Vec<LanguageRule>,
+    /// Terms never translated, such as product names; requests can add more
+    pub keep_terms: Vec<String>,
 
     // Performance configuration
     pub max_concurrent_translations: usize,
+    /// Lower the concurrency limit on rate limits and timeouts, raising it again while calls succeed
+    pub adaptive_concurrency: bool,
+    /// Lowest limit adaptive concurrency backs off to
+    pub min_concurrent_translations: usize,
+    /// Concurrent calls allowed per model, for models slower than MAX_CONCURRENT_TRANSLATIONS allows
+    pub model_concurrency: Vec<(String, usize)>,
+    /// Share of contended translation slots given to single-file requests over bulk work
+    pub interactive_share: f64,
     pub translation_timeout_seconds: u64,
+    /// Caps on the per-request `timeout_seconds` and `max_retries` options
+    pub max_request_timeout_seconds: u64,
+    pub max_request_retries: u32,
     pub max_tokens: u32,
+    pub chunk_max_tokens: usize,
+    pub max_archive_bytes: usize,
+    /// Largest request body accepted by API routes other than archive uploads
+    pub max_request_bytes: usize,
+    /// Lines longer than this many characters are handled by `long_line_mode`
+    pub max_line_length: usize,
+    pub long_line_mode: LongLineMode,
+    pub idempotency_ttl_seconds: u64,
+    /// Days completed background batch jobs and their results are kept
+    pub batch_job_retention_days: i64,
+    /// Most files a batch request may contain
+    pub max_batch_files: usize,
+    /// Largest total size of the files' content in a batch request, as sent
+    pub max_batch_total_bytes: usize,
+    pub input_cost_per_1k_tokens: f64,
+    pub output_cost_per_1k_tokens: f64,
+    pub circuit_breaker_threshold: u32,
+    pub circuit_breaker_cooldown_seconds: u64,
 
     // Cache configuration
     pub cache_db_path: String,
+    pub cache_backend: CacheBackendKind,
+    /// Postgres URL of the shared cache, required with the postgres backend
+    pub cache_database_url: String,
+    pub cache_max_connections: u32,
+    pub cache_connect_timeout_seconds: u64,
     pub cache_max_age_days: i64,
+    pub cache_flush_interval_seconds: u64,
+    /// Seconds cache statistics are served from memory before they are computed again
+    pub cache_stats_ttl_seconds: u64,
+    /// When entries that have not been accessed for `cache_stale_days` are cleared, as a
+    /// cron expression in local time
+    pub cache_cleanup_schedule: Schedule,
+    pub cache_stale_days: i64,
+    pub cache_flush_threshold: usize,
+    /// Picks the defaults of the `sqlite_*` settings
+    pub deployment_profile: DeploymentProfile,
+    pub sqlite_max_connections: u32,
+    /// Page cache of each SQLite connection in KiB
+    pub sqlite_cache_size_kib: u32,
+    pub sqlite_busy_timeout_ms: u64,
+    /// WAL pages after which SQLite checkpoints automatically
+    pub sqlite_wal_autocheckpoint: u32,
+    /// Size of the in-memory cache tier in bytes, 0 to disable
+    pub cache_memory_bytes: u64,
+    pub cache_compression: bool,
+    /// Where translations above `blob_threshold_bytes` are kept out of the database
+    pub blob_store: BlobStoreKind,
+    pub blob_threshold_bytes: usize,
+    pub blob_dir: String,
+    pub blob_s3_bucket: String,
+    pub blob_s3_endpoint: String,
+    pub blob_s3_region: String,
+    pub blob_s3_access_key: String,
+    pub blob_s3_secret_key: String,
+    pub blob_s3_prefix: String,
+    /// Cache translations of content that lost overlong lines to `LONG_LINE_MODE`
+    pub cache_lossy_translations: bool,
+    /// Applied at startup to cached translations of other translator versions
+    pub stale_version_policy: StaleVersionPolicy,
+    pub segment_cache: bool,
+    /// Reuse translated sections across documents; requires the segment cache
+    pub translation_memory: bool,
+    /// Minimum trigram similarity for a remembered section to be sent as an example
+    pub translation_memory_min_similarity: f64,
+    /// Keep the renderings of terms chosen per skill and reuse them when it is translated again
+    pub terminology_memory: bool,
+    /// Store the original of each cached translation so entries can be compared with it
+    pub store_sources: bool,
+
+    // Backup configuration
+    pub backup_dir: String,
+    /// Hours between scheduled backups, aligned to local midnight; 0 disables
+    pub backup_interval_hours: u32,
+    /// Number of backups kept in `backup_dir`
+    pub backup_retention: usize,
+    /// S3-compatible bucket receiving copies of each backup; empty disables uploads
+    pub backup_s3_bucket: String,
+    pub backup_s3_endpoint: String,
+    pub backup_s3_region: String,
+    pub backup_s3_access_key: String,
+    pub backup_s3_secret_key: String,
+    pub backup_s3_prefix: String,
+
+    // Cache warmer configuration
+    /// Manifest of files to translate ahead of requests; empty disables the warmer
+    pub warmer_manifest_url: String,
+    /// When the warmer runs, as a cron expression in local time
+    pub warmer_schedule: Schedule,
+
+    // Review configuration
+    pub review_min_length_ratio: f64,
+    pub review_max_length_ratio: f64,
+    /// Judged translations scoring below this are queued for review; 0 disables
+    pub review_min_quality_score: u8,
+    /// Model scoring translations that request a quality check; the primary model when empty
+    pub quality_judge_model: String,
 }
 
 impl Settings {
-    /// Load settings from environment variables.
-    /// First attempts to load .env file, then reads environment variables.
-    pub fn load() -> Self {
+    /// Load settings from environment variables, the .env file and an optional config file.
+    ///
+    /// Precedence is environment variables, then .env, then the config file, then
+    /// defaults. The config file is `config_path`, else `SKILLTS_CONFIG`, else
+    /// `./skillts.toml` when present. Files are read on every call without touching
+    /// the process environment, so reloading picks up edits.
+    pub fn load(config_path: Option<&Path>) -> Result<Self, ConfigError> {
         // Try to load .env file from current directory or parent directories
-        if let Some(path) = find_env_file() {
-            let _ = dotenvy::from_path(&path);
-        }
+        let dotenv_vars: HashMap<String, String> = find_env_file()
+            .and_then(|path| dotenvy::from_path_iter(path).ok())
+            .map(|iter| iter.filter_map(Result::ok).collect())
+            .unwrap_or_default();
+        let env_var = |key: &str| env::var(key).ok().or_else(|| dotenv_vars.get(key).cloned());
+
+        let config_file = config_path
+            .map(Path::to_path_buf)
+            .or_else(|| env_var("SKILLTS_CONFIG").map(PathBuf::from))
+            .or_else(|| {
+                let default = PathBuf::from(DEFAULT_CONFIG_FILE);
+                default.exists().then_some(default)
+            });
+        let file_vars = match &config_file {
+            Some(path) => read_config_file(path)?,
+            None => HashMap::new(),
+        };
+
+        let mut settings =
+            Self::from_vars(|key| env_var(key).or_else(|| file_vars.get(key).cloned()))?;
+        settings.config_file = config_file;
+        Ok(settings)
+    }
+
+    /// Build and validate settings from a variable lookup, using defaults for missing values
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let vars = Vars::new(lookup);
+        let deployment_profile = vars.parse("DEPLOYMENT_PROFILE", DeploymentProfile::Small);
+        let sqlite = deployment_profile.sqlite_tuning();
+        let cache_backend = vars.parse("CACHE_BACKEND", CacheBackendKind::Sqlite);
+
+        let settings = Settings {
+            config_file: None,
 
-        Settings {
             // OpenAI configuration
-            openai_api_key: env::var("OPENAI_API_KEY").unwrap_or_default(),
-            openai_model: env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string()),
-            openai_base_url: env::var("OPENAI_BASE_URL")
-                .unwrap_or_else(|_| "https://api.openai.com/v1".to_string()),
+            llm_provider: vars.parse("LLM_PROVIDER", Provider::OpenAi),
+            prompt_style: vars.parse("PROMPT_STYLE", PromptStyle::Chat),
+            prompt_templates: vars.parse("PROMPT_TEMPLATES", cache_backend != CacheBackendKind::Postgres),
+            openai_api_key: vars.string("OPENAI_API_KEY", ""),
+            openai_model: vars.string("OPENAI_MODEL", "gpt-4o-mini"),
+            openai_model_fallbacks: vars.list("OPENAI_MODEL_FALLBACKS"),
+            openai_base_url: vars.string("OPENAI_BASE_URL", "https://api.openai.com/v1"),
 
             // Server configuration
-            host: env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
-            port: env::var("PORT")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(8080),
-            reload: env::var("RELOAD")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(false),
+            host: vars.string("HOST", "127.0.0.1"),
+            port: vars.parse("PORT", 8080),
+            grpc_port: vars.parse("GRPC_PORT", 0),
+            listen: vars.parse("LISTEN", Listen::Tcp),
+            unix_socket_mode: vars.parse("UNIX_SOCKET_MODE", FileMode(0o660)),
+            tls_cert_path: vars.string("TLS_CERT_PATH", ""),
+            tls_key_path: vars.string("TLS_KEY_PATH", ""),
+            http_redirect_port: vars.parse("HTTP_REDIRECT_PORT", 0),
+            max_connections: vars.parse("MAX_CONNECTIONS", 0),
+            header_read_timeout_seconds: vars.parse("HEADER_READ_TIMEOUT_SECONDS", 30),
+            body_read_timeout_seconds: vars.parse("BODY_READ_TIMEOUT_SECONDS", 30),
+            keep_alive_seconds: vars.parse("KEEP_ALIVE_SECONDS", 60),
+            reload: vars.parse("RELOAD", false),
+
+            // Logging configuration
+            log_format: vars.parse("LOG_FORMAT", LogFormat::Text),
+            log_filter: vars.string("RUST_LOG", "skill_translator=info"),
+            debug_payload_log: vars.parse("DEBUG_PAYLOAD_LOG", false),
+            debug_payload_log_size: vars.parse("DEBUG_PAYLOAD_LOG_SIZE", 100),
 
             // API authentication
-            local_api_bearer: env::var("LOCAL_API_BEARER").unwrap_or_default(),
+            local_api_bearer: vars.string("LOCAL_API_BEARER", ""),
+            tenant_api_keys: vars.pairs("TENANT_API_KEYS"),
+            tenant_daily_quota: vars.parse("TENANT_DAILY_QUOTA", 0),
+            daily_token_budget: vars.parse("DAILY_TOKEN_BUDGET", 0),
+            monthly_cost_budget: vars.parse("MONTHLY_COST_BUDGET", 0.0),
+            response_signing_key: vars.string("RESPONSE_SIGNING_KEY", ""),
+
+            // Network access control
+            allowed_cidrs: vars.networks("ALLOWED_CIDRS"),
+            denied_cidrs: vars.networks("DENIED_CIDRS"),
+            trusted_proxies: vars.networks("TRUSTED_PROXIES"),
+
+            // GitHub integration
+            github_token: vars.string("GITHUB_TOKEN", ""),
+            github_api_url: vars.string("GITHUB_API_URL", "https://api.github.com"),
+
+            // Health check configuration
+            health_check_upstream: vars.parse("HEALTH_CHECK_UPSTREAM", false),
+            warmup_on_start: vars.parse("WARMUP_ON_START", false),
 
             // Translator configuration
-            translator_version: env::var("TRANSLATOR_VERSION")
-                .unwrap_or_else(|_| "1.0.0".to_string()),
-            target_language: env::var("TARGET_LANGUAGE").unwrap_or_else(|_| "zh-CN".to_string()),
-            source_language: env::var("SOURCE_LANGUAGE").unwrap_or_else(|_| "en".to_string()),
+            translator_version: vars.string("TRANSLATOR_VERSION", "1.0.0"),
+            target_language: vars.string("TARGET_LANGUAGE", "zh-CN"),
+            source_language: vars.string("SOURCE_LANGUAGE", "en"),
+            language_rules: vars.parse("LANGUAGE_RULES", true),
+            language_rules_file: vars.language_rules("LANGUAGE_RULES_FILE"),
+            keep_terms: vars.list("KEEP_TERMS"),
 
             // Performance configuration
-            max_concurrent_translations: env::var("MAX_CONCURRENT_TRANSLATIONS")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(5),
-            translation_timeout_seconds: env::var("TRANSLATION_TIMEOUT_SECONDS")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(600),
-            max_tokens: env::var("MAX_TOKENS")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(16000),
+            max_concurrent_translations: vars.parse("MAX_CONCURRENT_TRANSLATIONS", 5),
+            adaptive_concurrency: vars.parse("ADAPTIVE_CONCURRENCY", false),
+            min_concurrent_translations: vars.parse("MIN_CONCURRENT_TRANSLATIONS", 1),
+            model_concurrency: vars.limits("MODEL_CONCURRENCY"),
+            interactive_share: vars.parse("INTERACTIVE_SHARE", 0.75),
+            translation_timeout_seconds: vars.parse("TRANSLATION_TIMEOUT_SECONDS", 600),
+            max_request_timeout_seconds: vars.parse("MAX_REQUEST_TIMEOUT_SECONDS", 600),
+            max_request_retries: vars.parse("MAX_REQUEST_RETRIES", 5),
+            max_tokens: vars.parse("MAX_TOKENS", 16000),
+            chunk_max_tokens: vars.parse("CHUNK_MAX_TOKENS", 6000),
+            max_archive_bytes: vars.parse("MAX_ARCHIVE_BYTES", 50 * 1024 * 1024),
+            max_request_bytes: vars.parse("MAX_REQUEST_BYTES", 20 * 1024 * 1024),
+            max_line_length: vars.parse("MAX_LINE_LENGTH", 5000),
+            long_line_mode: vars.parse("LONG_LINE_MODE", LongLineMode::Drop),
+            idempotency_ttl_seconds: vars.parse("IDEMPOTENCY_TTL_SECONDS", 300),
+            batch_job_retention_days: vars.parse("BATCH_JOB_RETENTION_DAYS", 7),
+            max_batch_files: vars.parse("MAX_BATCH_FILES", 500),
+            max_batch_total_bytes: vars.parse("MAX_BATCH_TOTAL_BYTES", 10 * 1024 * 1024),
+            input_cost_per_1k_tokens: vars.parse("INPUT_COST_PER_1K_TOKENS", 0.00015),
+            output_cost_per_1k_tokens: vars.parse("OUTPUT_COST_PER_1K_TOKENS", 0.0006),
+            circuit_breaker_threshold: vars.parse("CIRCUIT_BREAKER_THRESHOLD", 5),
+            circuit_breaker_cooldown_seconds: vars.parse("CIRCUIT_BREAKER_COOLDOWN_SECONDS", 30),
 
             // Cache configuration
-            cache_db_path: env::var("CACHE_DB_PATH")
-                .unwrap_or_else(|_| "./data/cache.db".to_string()),
-            cache_max_age_days: env::var("CACHE_MAX_AGE_DAYS")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(30),
+            cache_db_path: vars.string("CACHE_DB_PATH", "./data/cache.db"),
+            cache_backend,
+            cache_database_url: vars.string("CACHE_DATABASE_URL", ""),
+            cache_max_connections: vars.parse("CACHE_MAX_CONNECTIONS", 5),
+            cache_connect_timeout_seconds: vars.parse("CACHE_CONNECT_TIMEOUT_SECONDS", 10),
+            cache_max_age_days: vars.parse("CACHE_MAX_AGE_DAYS", 30),
+            cache_flush_interval_seconds: vars.parse("CACHE_FLUSH_INTERVAL_SECONDS", 60),
+            cache_stats_ttl_seconds: vars.parse("CACHE_STATS_TTL_SECONDS", 10),
+            cache_cleanup_schedule: vars.parse("CACHE_CLEANUP_SCHEDULE", Schedule::daily_at(1)),
+            cache_stale_days: vars.parse("CACHE_STALE_DAYS", 30),
+            cache_flush_threshold: vars.parse("CACHE_FLUSH_THRESHOLD", 1000),
+            deployment_profile,
+            sqlite_max_connections: vars.parse("SQLITE_MAX_CONNECTIONS", sqlite.max_connections),
+            sqlite_cache_size_kib: vars.parse("SQLITE_CACHE_SIZE_KIB", sqlite.cache_size_kib),
+            sqlite_busy_timeout_ms: vars.parse("SQLITE_BUSY_TIMEOUT_MS", sqlite.busy_timeout_ms),
+            sqlite_wal_autocheckpoint: vars.parse("SQLITE_WAL_AUTOCHECKPOINT", sqlite.wal_autocheckpoint_pages),
+            cache_memory_bytes: vars.parse("CACHE_MEMORY_BYTES", 16 * 1024 * 1024),
+            cache_compression: vars.parse("CACHE_COMPRESSION", true),
+            blob_store: vars.parse("BLOB_STORE", BlobStoreKind::None),
+            blob_threshold_bytes: vars.parse("BLOB_THRESHOLD_BYTES", 1024 * 1024),
+            blob_dir: vars.string("BLOB_DIR", "./data/blobs"),
+            blob_s3_bucket: vars.string("BLOB_S3_BUCKET", ""),
+            blob_s3_endpoint: vars.string("BLOB_S3_ENDPOINT", "https://s3.amazonaws.com"),
+            blob_s3_region: vars.string("BLOB_S3_REGION", "us-east-1"),
+            blob_s3_access_key: vars.string("BLOB_S3_ACCESS_KEY", ""),
+            blob_s3_secret_key: vars.string("BLOB_S3_SECRET_KEY", ""),
+            blob_s3_prefix: vars.string("BLOB_S3_PREFIX", "skillts/blobs/"),
+            cache_lossy_translations: vars.parse("CACHE_LOSSY_TRANSLATIONS", true),
+            stale_version_policy: vars.parse("STALE_VERSION_POLICY", StaleVersionPolicy::Keep),
+            segment_cache: vars.parse("SEGMENT_CACHE", true),
+            translation_memory: vars.parse("TRANSLATION_MEMORY", false),
+            translation_memory_min_similarity: vars.parse("TRANSLATION_MEMORY_MIN_SIMILARITY", 0.8),
+            terminology_memory: vars.parse("TERMINOLOGY_MEMORY", false),
+            store_sources: vars.parse("STORE_SOURCES", true),
+
+            // Backup configuration
+            backup_dir: vars.string("BACKUP_DIR", "./data/backups"),
+            backup_interval_hours: vars.parse("BACKUP_INTERVAL_HOURS", 0),
+            backup_retention: vars.parse("BACKUP_RETENTION", 7),
+            backup_s3_bucket: vars.string("BACKUP_S3_BUCKET", ""),
+            backup_s3_endpoint: vars.string("BACKUP_S3_ENDPOINT", "https://s3.amazonaws.com"),
+            backup_s3_region: vars.string("BACKUP_S3_REGION", "us-east-1"),
+            backup_s3_access_key: vars.string("BACKUP_S3_ACCESS_KEY", ""),
+            backup_s3_secret_key: vars.string("BACKUP_S3_SECRET_KEY", ""),
+            backup_s3_prefix: vars.string("BACKUP_S3_PREFIX", "skillts/"),
+
+            // Cache warmer configuration
+            warmer_manifest_url: vars.string("WARMER_MANIFEST_URL", ""),
+            warmer_schedule: vars.parse("WARMER_SCHEDULE", Schedule::daily_at(3)),
+
+            // Review configuration
+            review_min_length_ratio: vars.parse("REVIEW_MIN_LENGTH_RATIO", 0.15),
+            review_max_length_ratio: vars.parse("REVIEW_MAX_LENGTH_RATIO", 3.0),
+            review_min_quality_score: vars.parse("REVIEW_MIN_QUALITY_SCORE", 3),
+            quality_judge_model: vars.string("QUALITY_JUDGE_MODEL", ""),
+        };
+
+        let mut errors = vars.into_errors();
+        errors.extend(settings.validate());
+        if errors.is_empty() {
+            Ok(settings)
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+
+    /// Range checks that parsing alone cannot express
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut check = |ok: bool, message: &str| {
+            if !ok {
+                errors.push(message.to_string());
+            }
+        };
+
+        check(self.port != 0, "PORT must be between 1 and 65535");
+        check(self.grpc_port != self.port, "GRPC_PORT must differ from PORT");
+        check(
+            self.tls_cert_path.is_empty() == self.tls_key_path.is_empty(),
+            "TLS_CERT_PATH and TLS_KEY_PATH must be set together",
+        );
+        check(
+            !self.tls_enabled() || self.listen == Listen::Tcp,
+            "TLS_CERT_PATH requires LISTEN=tcp",
+        );
+        if self.http_redirect_port != 0 {
+            check(self.tls_enabled(), "HTTP_REDIRECT_PORT requires TLS_CERT_PATH and TLS_KEY_PATH");
+            check(
+                self.http_redirect_port != self.port && self.http_redirect_port != self.grpc_port,
+                "HTTP_REDIRECT_PORT must differ from PORT and GRPC_PORT",
+            );
+        }
+        check(self.header_read_timeout_seconds > 0, "HEADER_READ_TIMEOUT_SECONDS must be positive");
+        check(!self.openai_model.trim().is_empty(), "OPENAI_MODEL must not be empty");
+        check(
+            self.openai_base_url.starts_with("http://") || self.openai_base_url.starts_with("https://"),
+            "OPENAI_BASE_URL must be an http(s) URL",
+        );
+        check(
+            !same_language(&self.source_language, &self.target_language),
+            "SOURCE_LANGUAGE must differ from TARGET_LANGUAGE",
+        );
+        check(self.max_concurrent_translations > 0, "MAX_CONCURRENT_TRANSLATIONS must be positive");
+        check(
+            self.min_concurrent_translations > 0
+                && self.min_concurrent_translations <= self.max_concurrent_translations,
+            "MIN_CONCURRENT_TRANSLATIONS must be positive and at most MAX_CONCURRENT_TRANSLATIONS",
+        );
+        check(
+            self.interactive_share > 0.0 && self.interactive_share <= 1.0,
+            "INTERACTIVE_SHARE must be above 0 and at most 1",
+        );
+        check(self.translation_timeout_seconds > 0, "TRANSLATION_TIMEOUT_SECONDS must be positive");
+        check(self.max_request_timeout_seconds > 0, "MAX_REQUEST_TIMEOUT_SECONDS must be positive");
+        check(self.max_tokens > 0, "MAX_TOKENS must be positive");
+        check(self.chunk_max_tokens > 0, "CHUNK_MAX_TOKENS must be positive");
+        check(self.max_archive_bytes > 0, "MAX_ARCHIVE_BYTES must be positive");
+        check(self.max_request_bytes > 0, "MAX_REQUEST_BYTES must be positive");
+        check(self.max_line_length > 0, "MAX_LINE_LENGTH must be positive");
+        check(self.debug_payload_log_size > 0, "DEBUG_PAYLOAD_LOG_SIZE must be positive");
+        check(
+            self.input_cost_per_1k_tokens >= 0.0 && self.output_cost_per_1k_tokens >= 0.0,
+            "INPUT_COST_PER_1K_TOKENS and OUTPUT_COST_PER_1K_TOKENS must not be negative",
+        );
+        check(self.monthly_cost_budget >= 0.0, "MONTHLY_COST_BUDGET must not be negative");
+        check(self.cache_max_age_days > 0, "CACHE_MAX_AGE_DAYS must be positive");
+        check(self.cache_stale_days > 0, "CACHE_STALE_DAYS must be positive");
+        check(self.batch_job_retention_days > 0, "BATCH_JOB_RETENTION_DAYS must be positive");
+        check(self.max_batch_files > 0, "MAX_BATCH_FILES must be positive");
+        check(self.max_batch_total_bytes > 0, "MAX_BATCH_TOTAL_BYTES must be positive");
+        check(
+            self.cache_backend != CacheBackendKind::Postgres
+                || self.cache_database_url.starts_with("postgres://")
+                || self.cache_database_url.starts_with("postgresql://"),
+            "CACHE_DATABASE_URL must be a postgres:// URL when CACHE_BACKEND is postgres",
+        );
+        // Replicas sharing a Postgres cache each keep these in their own SQLite database,
+        // so they would compute different cache keys and enforce separate limits
+        if self.cache_backend == CacheBackendKind::Postgres {
+            check(
+                !self.prompt_templates,
+                "PROMPT_TEMPLATES must be off when CACHE_BACKEND is postgres, saved templates are per replica",
+            );
+            check(
+                self.daily_token_budget == 0 && self.monthly_cost_budget == 0.0 && self.tenant_daily_quota == 0,
+                "DAILY_TOKEN_BUDGET, MONTHLY_COST_BUDGET and TENANT_DAILY_QUOTA must be 0 when CACHE_BACKEND is postgres, usage is counted per replica",
+            );
+        }
+        check(self.cache_max_connections > 0, "CACHE_MAX_CONNECTIONS must be positive");
+        check(self.sqlite_max_connections > 0, "SQLITE_MAX_CONNECTIONS must be positive");
+        check(self.sqlite_cache_size_kib > 0, "SQLITE_CACHE_SIZE_KIB must be positive");
+        check(self.sqlite_wal_autocheckpoint > 0, "SQLITE_WAL_AUTOCHECKPOINT must be positive");
+        check(
+            self.translation_memory_min_similarity > 0.0 && self.translation_memory_min_similarity <= 1.0,
+            "TRANSLATION_MEMORY_MIN_SIMILARITY must be above 0 and at most 1",
+        );
+        if self.blob_store == BlobStoreKind::S3 {
+            check(!self.blob_s3_bucket.is_empty(), "BLOB_S3_BUCKET is required when BLOB_STORE is 's3'");
+            check(
+                self.blob_s3_endpoint.starts_with("http://") || self.blob_s3_endpoint.starts_with("https://"),
+                "BLOB_S3_ENDPOINT must be an http(s) URL",
+            );
+            check(
+                !self.blob_s3_access_key.is_empty() && !self.blob_s3_secret_key.is_empty(),
+                "BLOB_S3_ACCESS_KEY and BLOB_S3_SECRET_KEY are required when BLOB_STORE is 's3'",
+            );
+            check(
+                self.blob_s3_prefix
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./".contains(c)),
+                "BLOB_S3_PREFIX may only contain letters, digits, '-', '_', '.' and '/'",
+            );
+        }
+        check(self.backup_interval_hours <= 24, "BACKUP_INTERVAL_HOURS must be between 0 and 24");
+        check(self.backup_retention > 0, "BACKUP_RETENTION must be positive");
+        if !self.backup_s3_bucket.is_empty() {
+            check(
+                self.backup_s3_endpoint.starts_with("http://") || self.backup_s3_endpoint.starts_with("https://"),
+                "BACKUP_S3_ENDPOINT must be an http(s) URL",
+            );
+            check(
+                !self.backup_s3_access_key.is_empty() && !self.backup_s3_secret_key.is_empty(),
+                "BACKUP_S3_ACCESS_KEY and BACKUP_S3_SECRET_KEY are required when BACKUP_S3_BUCKET is set",
+            );
+            check(
+                self.backup_s3_prefix
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./".contains(c)),
+                "BACKUP_S3_PREFIX may only contain letters, digits, '-', '_', '.' and '/'",
+            );
+        }
+        if !self.warmer_manifest_url.is_empty() {
+            check(
+                self.warmer_manifest_url.starts_with("http://") || self.warmer_manifest_url.starts_with("https://"),
+                "WARMER_MANIFEST_URL must be an http(s) URL",
+            );
+        }
+        check(
+            self.tenant_api_keys.iter().all(|(tenant, _)| valid_tenant(tenant)),
+            "TENANT_API_KEYS tenant names must be 1 to 64 letters, digits, '-' or '_'",
+        );
+        check(
+            self.tenant_api_keys
+                .iter()
+                .all(|(_, key)| !key.is_empty() && *key != self.local_api_bearer),
+            "TENANT_API_KEYS keys must be non-empty and differ from LOCAL_API_BEARER",
+        );
+        check(
+            self.review_min_length_ratio >= 0.0
+                && self.review_min_length_ratio < self.review_max_length_ratio,
+            "REVIEW_MIN_LENGTH_RATIO must be non-negative and below REVIEW_MAX_LENGTH_RATIO",
+        );
+        check(
+            self.response_signing_key.is_empty() || signing::decode_key(&self.response_signing_key).is_some(),
+            "RESPONSE_SIGNING_KEY must be a base64 encoded 32 byte Ed25519 secret key",
+        );
+        check(self.review_min_quality_score <= 5, "REVIEW_MIN_QUALITY_SCORE must be between 0 and 5");
+
+        errors
+    }
+
+    /// Estimated upstream cost in USD for the given token counts
+    pub fn cost_usd(&self, input_tokens: usize, output_tokens: usize) -> f64 {
+        (input_tokens as f64 * self.input_cost_per_1k_tokens + output_tokens as f64 * self.output_cost_per_1k_tokens)
+            / 1000.0
+    }
+
+    /// Whether the HTTP API is served over HTTPS
+    pub fn tls_enabled(&self) -> bool {
+        !self.tls_cert_path.is_empty() && !self.tls_key_path.is_empty()
+    }
+
+    /// Translator configuration derived from these settings
+    pub fn translator_config(&self) -> TranslatorConfig {
+        TranslatorConfig {
+            provider: self.llm_provider,
+            prompt_style: self.prompt_style,
+            api_key: self.openai_api_key.clone(),
+            base_url: self.openai_base_url.clone(),
+            model: self.openai_model.clone(),
+            model_fallbacks: self.openai_model_fallbacks.clone(),
+            max_tokens: self.max_tokens,
+            chunk_max_tokens: self.chunk_max_tokens,
+            translator_version: self.translator_version.clone(),
+            max_concurrent_translations: self.max_concurrent_translations,
+            adaptive_concurrency: self.adaptive_concurrency,
+            min_concurrent_translations: self.min_concurrent_translations,
+            model_concurrency: self.model_concurrency.clone(),
+            interactive_share: self.interactive_share,
+            timeout_seconds: self.translation_timeout_seconds,
+            circuit_breaker_threshold: self.circuit_breaker_threshold,
+            circuit_breaker_cooldown_seconds: self.circuit_breaker_cooldown_seconds,
+            translation_memory: self.translation_memory,
+            memory_min_similarity: self.translation_memory_min_similarity,
+            judge_model: self.quality_judge_model.clone(),
+            language_rules: LanguageRules::new(self.language_rules, self.language_rules_file.clone()),
+        }
+    }
+
+    /// Cache configuration derived from these settings
+    pub fn cache_config(&self) -> CacheConfig {
+        CacheConfig {
+            db_path: self.cache_db_path.clone(),
+            backend: self.cache_backend,
+            database_url: self.cache_database_url.clone(),
+            max_connections: self.cache_max_connections,
+            connect_timeout_seconds: self.cache_connect_timeout_seconds,
+            max_age_days: self.cache_max_age_days,
+            flush_threshold: self.cache_flush_threshold,
+            memory_bytes: self.cache_memory_bytes,
+            compression: self.cache_compression,
+            blob_store: self.blob_store,
+            blob_threshold_bytes: self.blob_threshold_bytes,
+            blob_dir: self.blob_dir.clone(),
+            blob_bucket: S3Bucket {
+                endpoint: self.blob_s3_endpoint.clone(),
+                bucket: self.blob_s3_bucket.clone(),
+                region: self.blob_s3_region.clone(),
+                access_key: self.blob_s3_access_key.clone(),
+                secret_key: self.blob_s3_secret_key.clone(),
+                prefix: self.blob_s3_prefix.clone(),
+            },
+            sqlite: self.sqlite_tuning(),
+        }
+    }
+
+    /// Pool size and pragmas of the SQLite database
+    pub fn sqlite_tuning(&self) -> SqliteTuning {
+        SqliteTuning {
+            max_connections: self.sqlite_max_connections,
+            cache_size_kib: self.sqlite_cache_size_kib,
+            busy_timeout_ms: self.sqlite_busy_timeout_ms,
+            wal_autocheckpoint_pages: self.sqlite_wal_autocheckpoint,
+        }
+    }
+}
+
+/// Whether `name` can name a tenant: 1 to 64 ASCII letters, digits, `-` or `_`
+pub fn valid_tenant(name: &str) -> bool {
+    (1..=64).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Read a TOML or YAML config file into upper-case variable names.
+///
+/// Nested tables are joined with `_`, so `[openai] model = "..."` sets `OPENAI_MODEL`;
+/// arrays become comma-separated lists.
+fn read_config_file(path: &Path) -> Result<HashMap<String, String>, ConfigError> {
+    let file_error = |message: String| ConfigError::File {
+        path: path.display().to_string(),
+        message,
+    };
+
+    let text = std::fs::read_to_string(path).map_err(|e| file_error(e.to_string()))?;
+    let is_yaml = matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("yaml") | Some("yml")
+    );
+    let value: serde_json::Value = if is_yaml {
+        serde_yaml_neo::from_str(&text).map_err(|e| file_error(e.to_string()))?
+    } else {
+        toml::from_str(&text).map_err(|e| file_error(e.to_string()))?
+    };
+
+    let mut vars = HashMap::new();
+    match value {
+        serde_json::Value::Object(_) => flatten_config("", &value, &mut vars),
+        serde_json::Value::Null => {}
+        _ => return Err(file_error("expected a table of settings".to_string())),
+    }
+    Ok(vars)
+}
+
+/// Read language rules from a TOML or YAML file of `"target" = "rules"` or
+/// `"source:target" = "rules"` entries, one instruction per line of the rules
+fn read_language_rules(path: &Path) -> Result<Vec<LanguageRule>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let is_yaml = matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("yaml") | Some("yml")
+    );
+    let entries: HashMap<String, String> = if is_yaml {
+        serde_yaml_neo::from_str(&text).map_err(|e| e.to_string())?
+    } else {
+        toml::from_str(&text).map_err(|e| e.to_string())?
+    };
+
+    let mut rules = entries
+        .into_iter()
+        .map(|(pair, rules)| {
+            let (source, target) = pair.split_once(':').unwrap_or((ANY, &pair));
+            let (source, target) = (source.trim(), target.trim());
+            if source.is_empty() || target.is_empty() {
+                return Err(format!("expected 'target' or 'source:target', got '{}'", pair));
+            }
+            Ok(LanguageRule {
+                source_language: source.to_string(),
+                target_language: target.to_string(),
+                rules,
+                configured: true,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    rules.sort_by(|a, b| (&a.source_language, &a.target_language).cmp(&(&b.source_language, &b.target_language)));
+    Ok(rules)
+}
+
+/// Flatten a config value into `PREFIX_KEY` variables
+fn flatten_config(prefix: &str, value: &serde_json::Value, vars: &mut HashMap<String, String>) {
+    use serde_json::Value;
+
+    let scalar = |value: &Value| match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    };
+
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = key.to_ascii_uppercase().replace('-', "_");
+                let name = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{}_{}", prefix, key)
+                };
+                flatten_config(&name, value, vars);
+            }
+        }
+        Value::Array(items) => {
+            let list: Vec<String> = items.iter().filter_map(scalar).collect();
+            vars.insert(prefix.to_string(), list.join(","));
+        }
+        Value::Null => {}
+        other => {
+            if let Some(value) = scalar(other) {
+                vars.insert(prefix.to_string(), value);
+            }
         }
     }
 }
@@ -126,8 +1028,209 @@ fn find_env_file() -> Option<PathBuf> {
     None
 }
 
-/// Get the global settings instance.
-/// Initializes settings on first call.
-pub fn get_settings() -> &'static Settings {
-    SETTINGS.get_or_init(Settings::load)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings_from(vars: &[(&str, &str)]) -> Result<Settings, ConfigError> {
+        let vars: HashMap<&str, &str> = vars.iter().copied().collect();
+        Settings::from_vars(|key| vars.get(key).map(|v| v.to_string()))
+    }
+
+    #[test]
+    fn test_from_vars_defaults_and_overrides() {
+        let defaults = settings_from(&[]).unwrap();
+        assert_eq!(defaults.port, 8080);
+        assert_eq!(defaults.openai_model, "gpt-4o-mini");
+        assert_eq!((defaults.listen, defaults.unix_socket_mode), (Listen::Tcp, FileMode(0o660)));
+
+        let settings = settings_from(&[
+            ("PORT", "9000"),
+            ("OPENAI_MODEL_FALLBACKS", "a, b,,"),
+            ("LOG_FORMAT", "JSON"),
+            ("LISTEN", "unix:/run/skillts.sock"),
+            ("UNIX_SOCKET_MODE", "600"),
+        ])
+        .unwrap();
+        assert_eq!(settings.port, 9000);
+        assert_eq!(settings.openai_model_fallbacks, vec!["a", "b"]);
+        assert_eq!(settings.log_format, LogFormat::Json);
+        assert_eq!(settings.listen, Listen::Unix(PathBuf::from("/run/skillts.sock")));
+        assert_eq!(settings.unix_socket_mode, FileMode(0o600));
+    }
+
+    #[test]
+    fn test_from_vars_reports_all_invalid_values() {
+        let err = settings_from(&[
+            ("PORT", "70000"),
+            ("MAX_TOKENS", "not a number"),
+            ("TRANSLATION_TIMEOUT_SECONDS", "0"),
+            ("LOG_FORMAT", "xml"),
+            ("SOURCE_LANGUAGE", "zh"),
+            ("LISTEN", "unix:"),
+            ("UNIX_SOCKET_MODE", "0o999"),
+            ("HEADER_READ_TIMEOUT_SECONDS", "0"),
+        ])
+        .unwrap_err();
+
+        let message = err.to_string();
+        assert!(message.contains("PORT: invalid value '70000'"));
+        assert!(message.contains("MAX_TOKENS: invalid value"));
+        assert!(message.contains("TRANSLATION_TIMEOUT_SECONDS must be positive"));
+        assert!(message.contains("unknown log format 'xml'"));
+        assert!(message.contains("SOURCE_LANGUAGE must differ from TARGET_LANGUAGE"));
+        assert!(message.contains("LISTEN: invalid value 'unix:'"));
+        assert!(message.contains("UNIX_SOCKET_MODE: invalid value"));
+        assert!(message.contains("HEADER_READ_TIMEOUT_SECONDS must be positive"));
+
+        let message = settings_from(&[("TLS_CERT_PATH", "cert.pem"), ("HTTP_REDIRECT_PORT", "8080")])
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("TLS_CERT_PATH and TLS_KEY_PATH must be set together"));
+        assert!(message.contains("HTTP_REDIRECT_PORT requires TLS_CERT_PATH and TLS_KEY_PATH"));
+        assert!(message.contains("HTTP_REDIRECT_PORT must differ from PORT"));
+    }
+
+    #[test]
+    fn test_tenant_api_keys() {
+        let settings = settings_from(&[("TENANT_API_KEYS", "registry-a=key-a, registry_b = key-b")]).unwrap();
+        assert_eq!(
+            settings.tenant_api_keys,
+            vec![
+                ("registry-a".to_string(), "key-a".to_string()),
+                ("registry_b".to_string(), "key-b".to_string()),
+            ]
+        );
+
+        let message = settings_from(&[
+            ("LOCAL_API_BEARER", "secret"),
+            ("TENANT_API_KEYS", "no-key,bad name=k,a=secret"),
+        ])
+        .unwrap_err()
+        .to_string();
+        assert!(message.contains("TENANT_API_KEYS: expected name=value, got 'no-key'"));
+        assert!(message.contains("tenant names must be"));
+        assert!(message.contains("differ from LOCAL_API_BEARER"));
+    }
+
+    #[test]
+    fn test_model_concurrency() {
+        let settings = settings_from(&[("MODEL_CONCURRENCY", "llama3.1:8b=1, gpt-4o=10")]).unwrap();
+        assert_eq!(
+            settings.model_concurrency,
+            vec![("llama3.1:8b".to_string(), 1), ("gpt-4o".to_string(), 10)]
+        );
+
+        let message = settings_from(&[("MODEL_CONCURRENCY", "a=0,b=x")]).unwrap_err().to_string();
+        assert!(message.contains("expected a positive limit for 'a', got '0'"));
+        assert!(message.contains("expected a positive limit for 'b', got 'x'"));
+    }
+
+    #[test]
+    fn test_backup_upload_settings() {
+        let message = settings_from(&[("BACKUP_S3_BUCKET", "backups"), ("BACKUP_RETENTION", "0")])
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("BACKUP_RETENTION must be positive"));
+        assert!(message.contains("BACKUP_S3_ACCESS_KEY and BACKUP_S3_SECRET_KEY are required"));
+
+        let settings = settings_from(&[
+            ("BACKUP_S3_BUCKET", "backups"),
+            ("BACKUP_S3_ACCESS_KEY", "key"),
+            ("BACKUP_S3_SECRET_KEY", "secret"),
+        ])
+        .unwrap();
+        assert_eq!(settings.backup_s3_prefix, "skillts/");
+    }
+
+    #[test]
+    fn test_cache_backend() {
+        let settings = settings_from(&[
+            ("CACHE_BACKEND", "Postgres"),
+            ("CACHE_DATABASE_URL", "postgres://skillts@db/cache"),
+        ])
+        .unwrap();
+        assert_eq!(settings.cache_config().backend, CacheBackendKind::Postgres);
+        assert!(!settings.prompt_templates);
+        assert!(settings_from(&[]).unwrap().prompt_templates);
+
+        let message = settings_from(&[
+            ("CACHE_BACKEND", "postgres"),
+            ("CACHE_DATABASE_URL", "postgres://skillts@db/cache"),
+            ("PROMPT_TEMPLATES", "true"),
+            ("TENANT_DAILY_QUOTA", "100"),
+        ])
+        .unwrap_err()
+        .to_string();
+        assert!(message.contains("PROMPT_TEMPLATES must be off"));
+        assert!(message.contains("TENANT_DAILY_QUOTA must be 0"));
+
+        let message = settings_from(&[("CACHE_BACKEND", "postgres")]).unwrap_err().to_string();
+        assert!(message.contains("CACHE_DATABASE_URL must be a postgres:// URL"));
+        let message = settings_from(&[("CACHE_BACKEND", "redis")]).unwrap_err().to_string();
+        assert!(message.contains("unknown cache backend 'redis'"));
+    }
+
+    #[test]
+    fn test_deployment_profile() {
+        let settings = settings_from(&[]).unwrap();
+        assert_eq!(settings.sqlite_tuning(), SqliteTuning::default());
+
+        let settings = settings_from(&[("DEPLOYMENT_PROFILE", "large"), ("SQLITE_MAX_CONNECTIONS", "16")]).unwrap();
+        let tuning = settings.cache_config().sqlite;
+        assert_eq!(tuning.max_connections, 16);
+        assert_eq!(tuning.cache_size_kib, DeploymentProfile::Large.sqlite_tuning().cache_size_kib);
+
+        let message = settings_from(&[("DEPLOYMENT_PROFILE", "huge"), ("SQLITE_CACHE_SIZE_KIB", "0")])
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("unknown deployment profile 'huge'"));
+        assert!(message.contains("SQLITE_CACHE_SIZE_KIB must be positive"));
+    }
+
+    #[test]
+    fn test_flatten_config_file() {
+        let value: serde_json::Value = toml::from_str(
+            r#"
+            port = 9000
+            health_check_upstream = true
+
+            [openai]
+            model = "gpt-4o"
+            model_fallbacks = ["gpt-4o-mini", "gpt-3.5-turbo"]
+            "#,
+        )
+        .unwrap();
+
+        let mut vars = HashMap::new();
+        flatten_config("", &value, &mut vars);
+        assert_eq!(vars["PORT"], "9000");
+        assert_eq!(vars["HEALTH_CHECK_UPSTREAM"], "true");
+        assert_eq!(vars["OPENAI_MODEL"], "gpt-4o");
+        assert_eq!(vars["OPENAI_MODEL_FALLBACKS"], "gpt-4o-mini,gpt-3.5-turbo");
+    }
+
+    #[test]
+    fn test_language_rules_file() {
+        let path = std::env::temp_dir().join(format!("skillts-rules-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "ja = \"- Use plain form\"\n\"en:ko\" = \"\"\n").unwrap();
+        let file = path.to_string_lossy();
+
+        let settings = settings_from(&[("LANGUAGE_RULES_FILE", &file)]).unwrap();
+        let pairs: Vec<(&str, &str)> = settings
+            .language_rules_file
+            .iter()
+            .map(|rule| (rule.source_language.as_str(), rule.target_language.as_str()))
+            .collect();
+        assert_eq!(pairs, vec![(ANY, "ja"), ("en", "ko")]);
+        let rules = settings.translator_config().language_rules;
+        assert_eq!(rules.select("en", "ja").unwrap().rules, "- Use plain form");
+        assert_eq!(rules.render("en", "ko"), "");
+        assert!(rules.render("en", "zh-CN").contains("CHINESE (SIMPLIFIED) RULES"));
+
+        std::fs::write(&path, "\":ja\" = \"x\"\n").unwrap();
+        let message = settings_from(&[("LANGUAGE_RULES_FILE", &file)]).unwrap_err().to_string();
+        assert!(message.contains("LANGUAGE_RULES_FILE: expected 'target' or 'source:target', got ':ja'"));
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -1,13 +1,16 @@
 //! Error types for skill-translator.
 
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
 use thiserror::Error;
 
+use crate::models::schemas::{ErrorCode, ErrorResponse, FieldError};
+
+pub use skillts_core::TranslationError;
+
 /// Main error type for the application
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -23,24 +26,37 @@ pub enum AppError {
     #[error("Invalid request: {0}")]
     BadRequest(String),
 
-    #[error("Internal server error: {0}")]
-    Internal(String),
-}
+    #[error("Invalid request body: {}", describe_fields(.0))]
+    Validation(Vec<FieldError>),
 
-/// Translation-specific errors
-#[derive(Debug, Error)]
-pub enum TranslationError {
-    #[error("Translation timed out after {0} seconds")]
-    Timeout(u64),
+    /// A body the framework refused before it was parsed, e.g. for a missing
+    /// `Content-Type` (415) or a body that took too long to arrive (408)
+    #[error("Invalid request: {1}")]
+    Rejected(StatusCode, String),
+
+    #[error("Unprocessable request: {0}")]
+    Unprocessable(String),
 
-    #[error("Translation failed after {attempts} attempts: {error}")]
-    RetryFailed { attempts: u32, error: String },
+    #[error("Not found: {0}")]
+    NotFound(String),
 
-    #[error("Empty response from upstream API")]
-    EmptyResponse,
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
 
-    #[error("OpenAI API error: {0}")]
-    OpenAIError(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+
+    #[error("GitHub API error: {0}")]
+    GitHub(String),
+
+    #[error("Internal server error: {0}")]
+    Internal(String),
 }
 
 impl From<sqlx::Error> for AppError {
@@ -49,29 +65,165 @@ impl From<sqlx::Error> for AppError {
     }
 }
 
-impl From<async_openai::error::OpenAIError> for AppError {
-    fn from(err: async_openai::error::OpenAIError) -> Self {
-        AppError::TranslationError(TranslationError::OpenAIError(err.to_string()))
+impl From<skillts_core::Error> for AppError {
+    fn from(err: skillts_core::Error) -> Self {
+        match err {
+            skillts_core::Error::Translation(e) => AppError::TranslationError(e),
+            skillts_core::Error::Cache(e) => AppError::CacheError(e),
+            skillts_core::Error::Base64(e) => AppError::Base64Error(e),
+            skillts_core::Error::InvalidContent(msg) | skillts_core::Error::InvalidLanguage(msg) => {
+                AppError::BadRequest(msg)
+            }
+            skillts_core::Error::Internal(msg) => AppError::Internal(msg),
+        }
+    }
+}
+
+impl AppError {
+    /// Machine-readable kind of the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::BadRequest(_) | AppError::Rejected(..) => ErrorCode::InvalidRequest,
+            AppError::Validation(_) => ErrorCode::ValidationFailed,
+            AppError::Base64Error(_) => ErrorCode::InvalidContent,
+            AppError::Unprocessable(_) => ErrorCode::Unprocessable,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            AppError::Conflict(_) => ErrorCode::Conflict,
+            AppError::QuotaExceeded(_) => ErrorCode::QuotaExceeded,
+            AppError::BudgetExceeded(_) => ErrorCode::BudgetExceeded,
+            AppError::GitHub(_) => ErrorCode::GitHubError,
+            AppError::TranslationError(e) => match (e, e.upstream_status()) {
+                (TranslationError::Timeout(_), _) => ErrorCode::Timeout,
+                (TranslationError::InputTooLarge { .. }, _) => ErrorCode::InputTooLarge,
+                (TranslationError::ContextLengthExceeded(_), _) => ErrorCode::ContextLengthExceeded,
+                (TranslationError::CircuitOpen(_), _) | (_, Some(503)) => ErrorCode::UpstreamUnavailable,
+                (_, Some(429)) => ErrorCode::RateLimited,
+                (_, Some(401 | 403)) => ErrorCode::UpstreamAuthFailed,
+                _ => ErrorCode::UpstreamError,
+            },
+            AppError::CacheError(_) | AppError::Internal(_) => ErrorCode::Internal,
+        }
     }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let code = self.code();
+        let mut retry_after = None;
+        let mut errors = Vec::new();
         let (status, error_message) = match self {
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            AppError::Rejected(status, msg) => (status, msg),
+            AppError::Validation(fields) => {
+                let detail = format!("Invalid request body: {}", describe_fields(&fields));
+                errors = fields;
+                (StatusCode::UNPROCESSABLE_ENTITY, detail)
+            }
+            AppError::Unprocessable(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg),
+            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            AppError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            AppError::QuotaExceeded(msg) | AppError::BudgetExceeded(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
+            AppError::GitHub(msg) => (StatusCode::BAD_GATEWAY, format!("GitHub API error: {}", msg)),
             AppError::Base64Error(e) => (StatusCode::BAD_REQUEST, format!("Invalid base64 content: {}", e)),
-            AppError::TranslationError(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Translation failed: {}", e)),
+            AppError::TranslationError(e) => {
+                // Upstream failures the client can act on keep their meaning; the rest are ours
+                let status = match code {
+                    ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+                    ErrorCode::UpstreamUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+                    ErrorCode::UpstreamAuthFailed => StatusCode::BAD_GATEWAY,
+                    ErrorCode::ContextLengthExceeded => StatusCode::UNPROCESSABLE_ENTITY,
+                    ErrorCode::InputTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                if matches!(status, StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE) {
+                    retry_after = e.retry_after();
+                }
+                (status, format!("Translation failed: {}", e))
+            }
             AppError::CacheError(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Cache error: {}", e)),
             AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
         };
 
-        let body = Json(json!({
-            "detail": error_message
-        }));
-
-        (status, body).into_response()
+        let body = ErrorResponse {
+            detail: error_message,
+            code,
+            errors,
+        };
+        let mut response = (status, Json(body)).into_response();
+        if let Some(wait) = retry_after {
+            // Whole seconds, rounded up so a client never retries too early
+            let seconds = (wait.as_secs_f64().ceil() as u64).max(1);
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
     }
 }
 
+/// Field errors as one sentence, e.g. "files: files must be non-empty"
+fn describe_fields(fields: &[FieldError]) -> String {
+    fields
+        .iter()
+        .map(|error| match error.field.as_str() {
+            "" => error.message.clone(),
+            field => format!("{}: {}", field, error.message),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 /// Result type alias for application errors
 pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_error_codes() {
+        let rate_limited = TranslationError::RetryFailed {
+            attempts: 3,
+            error: "Upstream API returned 429: slow down".to_string(),
+            status: Some(429),
+            retry_after: None,
+        };
+        assert_eq!(AppError::from(rate_limited).code(), ErrorCode::RateLimited);
+        let timeout = AppError::TranslationError(TranslationError::Timeout(30));
+        assert_eq!(timeout.code(), ErrorCode::Timeout);
+        let failed = TranslationError::RetryFailed {
+            attempts: 3,
+            error: "connection reset".to_string(),
+            status: None,
+            retry_after: None,
+        };
+        assert_eq!(AppError::from(failed).code(), ErrorCode::UpstreamError);
+        let unauthorized = TranslationError::Upstream {
+            status: 401,
+            message: "Incorrect API key provided".to_string(),
+            retry_after: None,
+        };
+        let response = AppError::from(unauthorized).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let context = AppError::TranslationError(TranslationError::ContextLengthExceeded("too long".to_string()));
+        assert_eq!(context.into_response().status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let rate_limited = TranslationError::RetryFailed {
+            attempts: 3,
+            error: "Upstream API returned 429: slow down".to_string(),
+            status: Some(429),
+            retry_after: Some(std::time::Duration::from_millis(1500)),
+        };
+        let response = AppError::from(rate_limited).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+        let response = AppError::TranslationError(TranslationError::CircuitOpen(12)).into_response();
+        assert_eq!(response.headers()[header::RETRY_AFTER], "12");
+
+        let response = AppError::BudgetExceeded("Daily token budget exhausted".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body, serde_json::json!({"detail": "Daily token budget exhausted", "code": "BUDGET_EXCEEDED"}));
+    }
+}
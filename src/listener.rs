@@ -0,0 +1,573 @@
+//! The socket the HTTP API is served on.
+//!
+//! By default the API listens on HOST:PORT. `LISTEN=unix:<path>` serves it on a
+//! Unix domain socket instead, created with UNIX_SOCKET_MODE permissions and
+//! removed again on shutdown. Under systemd socket activation (`LISTEN_PID` and
+//! `LISTEN_FDS`) the first socket systemd passes is used as is, TCP or Unix, and
+//! left for systemd to clean up.
+//!
+//! With TLS_CERT_PATH and TLS_KEY_PATH set, a TCP listener serves HTTPS. The
+//! certificate is reloaded when its files change, so renewals need no restart,
+//! and HTTP_REDIRECT_PORT adds a plain HTTP listener redirecting to HTTPS.
+//!
+//! Every listener speaks HTTP/1.1 and HTTP/2, including HTTP/2 without TLS (h2c),
+//! with the MAX_CONNECTIONS, HEADER_READ_TIMEOUT_SECONDS and KEEP_ALIVE_SECONDS
+//! settings.
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use axum::http::{header, HeaderMap, StatusCode, Uri};
+use axum::response::{IntoResponse, Redirect};
+use axum::Router;
+use axum_server::accept::Accept;
+use axum_server::tls_rustls::{RustlsAcceptor, RustlsConfig};
+use axum_server::{Address, Handle};
+use futures::future::BoxFuture;
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+use hyper_util::server::conn::auto::Builder;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpListener;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Sleep;
+
+use crate::config::{Listen, Settings};
+
+/// How often the certificate files are checked for changes
+const CERTIFICATE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// File descriptor of the first socket passed by systemd
+#[cfg(unix)]
+const SD_LISTEN_FDS_START: std::os::fd::RawFd = 3;
+
+/// A bound listener for the HTTP API
+pub enum HttpListener {
+    Tcp(TcpListener),
+    /// HTTPS, with a plain HTTP listener redirecting to it when asked for
+    Tls {
+        listener: std::net::TcpListener,
+        certificates: Certificates,
+        redirect: Option<std::net::TcpListener>,
+    },
+    #[cfg(unix)]
+    Unix {
+        listener: tokio::net::UnixListener,
+        /// Socket file created by this process, removed on shutdown
+        created: Option<PathBuf>,
+    },
+}
+
+impl HttpListener {
+    /// The socket passed by systemd, or else the one `LISTEN` asks for, serving
+    /// HTTPS when a certificate is configured
+    pub async fn bind(settings: &Settings) -> anyhow::Result<Self> {
+        #[cfg(unix)]
+        let passed = systemd_listener()?;
+        #[cfg(not(unix))]
+        let passed = None;
+
+        let listener = match passed {
+            Some(listener) => listener,
+            None => match &settings.listen {
+                Listen::Tcp => {
+                    let addr = format!("{}:{}", settings.host, settings.port);
+                    let listener = TcpListener::bind(&addr)
+                        .await
+                        .with_context(|| format!("Failed to listen on {}", addr))?;
+                    HttpListener::Tcp(listener)
+                }
+                #[cfg(unix)]
+                Listen::Unix(path) => {
+                    let listener = bind_unix(path, settings.unix_socket_mode.0)?;
+                    HttpListener::Unix {
+                        listener,
+                        created: Some(path.clone()),
+                    }
+                }
+                #[cfg(not(unix))]
+                Listen::Unix(_) => anyhow::bail!("LISTEN=unix:<path> requires a Unix platform"),
+            },
+        };
+
+        match listener {
+            HttpListener::Tcp(listener) if settings.tls_enabled() => Self::with_tls(listener, settings).await,
+            #[cfg(unix)]
+            HttpListener::Unix { .. } if settings.tls_enabled() => {
+                anyhow::bail!("TLS needs a TCP socket, but systemd passed a Unix socket")
+            }
+            listener => Ok(listener),
+        }
+    }
+
+    /// Serve HTTPS on `listener`, binding the redirect listener as well when asked for
+    async fn with_tls(listener: TcpListener, settings: &Settings) -> anyhow::Result<Self> {
+        // rustls needs a process-wide crypto provider; an error means one is installed already
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let certificates = Certificates::load(&settings.tls_cert_path, &settings.tls_key_path).await?;
+
+        let redirect = match settings.http_redirect_port {
+            0 => None,
+            port => {
+                let addr = format!("{}:{}", settings.host, port);
+                let redirect = TcpListener::bind(&addr)
+                    .await
+                    .with_context(|| format!("Failed to listen on {}", addr))?;
+                Some(redirect.into_std()?)
+            }
+        };
+
+        Ok(HttpListener::Tls {
+            listener: listener.into_std()?,
+            certificates,
+            redirect,
+        })
+    }
+
+    /// Where the listener accepts connections, for the startup log
+    pub fn describe(&self) -> String {
+        match self {
+            HttpListener::Tcp(listener) => match listener.local_addr() {
+                Ok(addr) => addr.to_string(),
+                Err(_) => "a TCP socket".to_string(),
+            },
+            HttpListener::Tls { listener, redirect, .. } => {
+                let https = match listener.local_addr() {
+                    Ok(addr) => format!("https://{}", addr),
+                    Err(_) => "a TLS socket".to_string(),
+                };
+                match redirect.as_ref().and_then(|redirect| redirect.local_addr().ok()) {
+                    Some(addr) => format!("{}, redirecting from http://{}", https, addr),
+                    None => https,
+                }
+            }
+            #[cfg(unix)]
+            HttpListener::Unix { listener, .. } => match listener.local_addr() {
+                Ok(addr) => match addr.as_pathname() {
+                    Some(path) => format!("unix:{}", path.display()),
+                    None => "an unnamed Unix socket".to_string(),
+                },
+                Err(_) => "a Unix socket".to_string(),
+            },
+        }
+    }
+
+    /// Serve `app` with the connection settings until `shutdown` completes, then
+    /// remove a socket file this process created
+    pub async fn serve(
+        self,
+        app: Router,
+        settings: &Settings,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> io::Result<()> {
+        let guard = ConnectionGuard::new(settings);
+        match self {
+            HttpListener::Tcp(listener) => {
+                let mut server = axum_server::from_tcp(listener.into_std()?)?.acceptor(guard);
+                *server.http_builder() = connection_builder(settings);
+                server
+                    .handle(shutdown_handle(shutdown))
+                    .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                    .await
+            }
+            HttpListener::Tls {
+                listener,
+                certificates,
+                redirect,
+            } => {
+                let https_port = listener.local_addr()?.port();
+                let handle = shutdown_handle(shutdown);
+
+                let redirecting = match redirect {
+                    Some(redirect) => {
+                        let server = axum_server::from_tcp(redirect)?.handle(handle.clone());
+                        Some(tokio::spawn(server.serve(redirect_router(https_port).into_make_service())))
+                    }
+                    None => None,
+                };
+                let reloading = tokio::spawn(certificates.clone().watch());
+
+                let acceptor = RustlsAcceptor::new(certificates.config).acceptor(guard);
+                let mut server = axum_server::from_tcp(listener)?.acceptor(acceptor);
+                *server.http_builder() = connection_builder(settings);
+                let served = server
+                    .handle(handle)
+                    .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                    .await;
+                reloading.abort();
+                if let Some(redirecting) = redirecting {
+                    match redirecting.await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => tracing::error!("HTTP redirect listener failed: {}", e),
+                        Err(e) => tracing::error!("HTTP redirect listener task failed: {}", e),
+                    }
+                }
+                served
+            }
+            #[cfg(unix)]
+            HttpListener::Unix { listener, created } => {
+                let mut server = axum_server::from_unix(listener.into_std()?)?.acceptor(guard);
+                *server.http_builder() = connection_builder(settings);
+                let served = server.handle(shutdown_handle(shutdown)).serve(app.into_make_service()).await;
+                if let Some(path) = created {
+                    if let Err(e) = std::fs::remove_file(&path) {
+                        tracing::warn!("Failed to remove socket {}: {}", path.display(), e);
+                    }
+                }
+                served
+            }
+        }
+    }
+}
+
+/// A server handle that shuts the server down gracefully once `shutdown` completes
+fn shutdown_handle<A>(shutdown: impl Future<Output = ()> + Send + 'static) -> Handle<A>
+where
+    A: Address + Send + Sync + 'static,
+{
+    let handle = Handle::new();
+    tokio::spawn({
+        let handle = handle.clone();
+        async move {
+            shutdown.await;
+            handle.graceful_shutdown(None);
+        }
+    });
+    handle
+}
+
+/// HTTP/1.1 and HTTP/2 connection handling per the settings
+fn connection_builder(settings: &Settings) -> Builder<TokioExecutor> {
+    let keep_alive = Duration::from_secs(settings.keep_alive_seconds);
+    let mut builder = Builder::new(TokioExecutor::new());
+    builder
+        .http1()
+        .timer(TokioTimer::new())
+        .header_read_timeout(Duration::from_secs(settings.header_read_timeout_seconds))
+        .keep_alive(!keep_alive.is_zero());
+    builder
+        .http2()
+        .timer(TokioTimer::new())
+        .keep_alive_interval((!keep_alive.is_zero()).then_some(keep_alive));
+    builder
+}
+
+/// Serves at most MAX_CONNECTIONS connections at once, further ones waiting for
+/// one to close, and closes connections that send nothing within the header read
+/// timeout. hyper only times out headers once the first bytes told it the HTTP version.
+#[derive(Clone)]
+struct ConnectionGuard {
+    slots: Option<Arc<Semaphore>>,
+    first_byte_timeout: Duration,
+}
+
+impl ConnectionGuard {
+    fn new(settings: &Settings) -> Self {
+        Self {
+            slots: (settings.max_connections > 0).then(|| Arc::new(Semaphore::new(settings.max_connections))),
+            first_byte_timeout: Duration::from_secs(settings.header_read_timeout_seconds),
+        }
+    }
+}
+
+impl<I, S> Accept<I, S> for ConnectionGuard
+where
+    I: Send + 'static,
+    S: Send + 'static,
+{
+    type Stream = Guarded<I>;
+    type Service = S;
+    type Future = BoxFuture<'static, io::Result<(Self::Stream, Self::Service)>>;
+
+    fn accept(&self, stream: I, service: S) -> Self::Future {
+        let (slots, first_byte_timeout) = (self.slots.clone(), self.first_byte_timeout);
+        Box::pin(async move {
+            let slot = match slots {
+                Some(slots) => Some(slots.acquire_owned().await.map_err(io::Error::other)?),
+                None => None,
+            };
+            let stream = Guarded {
+                stream,
+                _slot: slot,
+                first_byte: Some(Box::pin(tokio::time::sleep(first_byte_timeout))),
+            };
+            Ok((stream, service))
+        })
+    }
+}
+
+/// A connection holding its slot until it is dropped
+struct Guarded<I> {
+    stream: I,
+    _slot: Option<OwnedSemaphorePermit>,
+    /// Deadline for the first byte, cleared once it arrived
+    first_byte: Option<Pin<Box<Sleep>>>,
+}
+
+impl<I: AsyncRead + Unpin> AsyncRead for Guarded<I> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let filled = buf.filled().len();
+        match Pin::new(&mut this.stream).poll_read(cx, buf) {
+            Poll::Pending => {
+                let expired = this.first_byte.as_mut().is_some_and(|deadline| deadline.as_mut().poll(cx).is_ready());
+                if expired {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "No request within the header read timeout",
+                    )));
+                }
+                Poll::Pending
+            }
+            ready => {
+                if buf.filled().len() > filled {
+                    this.first_byte = None;
+                }
+                ready
+            }
+        }
+    }
+}
+
+impl<I: AsyncWrite + Unpin> AsyncWrite for Guarded<I> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+/// The served certificate and the files it was loaded from
+#[derive(Clone)]
+pub struct Certificates {
+    config: RustlsConfig,
+    cert_path: PathBuf,
+    key_path: PathBuf,
+}
+
+impl Certificates {
+    async fn load(cert_path: &str, key_path: &str) -> anyhow::Result<Self> {
+        let config = RustlsConfig::from_pem_file(cert_path, key_path)
+            .await
+            .with_context(|| format!("Failed to load TLS certificate {} with key {}", cert_path, key_path))?;
+        Ok(Self {
+            config,
+            cert_path: PathBuf::from(cert_path),
+            key_path: PathBuf::from(key_path),
+        })
+    }
+
+    /// Modification times of the certificate and key files
+    fn modified(&self) -> Option<(SystemTime, SystemTime)> {
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        Some((modified(&self.cert_path)?, modified(&self.key_path)?))
+    }
+
+    /// Reload the certificate whenever its files change. Files that do not load,
+    /// say while a renewal has written only one of them, keep the current
+    /// certificate in use and are tried again on the next check.
+    async fn watch(self) {
+        let mut loaded = self.modified();
+        let mut checks = tokio::time::interval(CERTIFICATE_CHECK_INTERVAL);
+        checks.tick().await;
+        loop {
+            checks.tick().await;
+            let modified = self.modified();
+            if modified.is_none() || modified == loaded {
+                continue;
+            }
+            match self.config.reload_from_pem_file(&self.cert_path, &self.key_path).await {
+                Ok(()) => {
+                    tracing::info!("Reloaded TLS certificate {}", self.cert_path.display());
+                    loaded = modified;
+                }
+                Err(e) => tracing::warn!("Failed to reload TLS certificate, keeping the current one: {}", e),
+            }
+        }
+    }
+}
+
+/// Answers every request with a permanent redirect to the same URL over HTTPS
+fn redirect_router(https_port: u16) -> Router {
+    Router::new().fallback(move |headers: HeaderMap, uri: Uri| async move {
+        match https_url(&headers, &uri, https_port) {
+            Some(url) => Redirect::permanent(&url).into_response(),
+            None => (StatusCode::BAD_REQUEST, "Missing Host header").into_response(),
+        }
+    })
+}
+
+/// `uri` on the requested host over HTTPS on `https_port`
+fn https_url(headers: &HeaderMap, uri: &Uri, https_port: u16) -> Option<String> {
+    let host = headers.get(header::HOST)?.to_str().ok()?;
+    // Drop the plain HTTP port; the brackets of an IPv6 address stay
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => name,
+        _ => host,
+    };
+    if host.is_empty() {
+        return None;
+    }
+    let path = uri.path_and_query().map_or("/", |path| path.as_str());
+    Some(match https_port {
+        443 => format!("https://{}{}", host, path),
+        port => format!("https://{}:{}{}", host, port, path),
+    })
+}
+
+/// Bind a Unix domain socket at `path` with permissions `mode`. A socket file left
+/// behind by a process that is gone is replaced; one still accepting connections is not.
+#[cfg(unix)]
+fn bind_unix(path: &Path, mode: u32) -> anyhow::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        anyhow::ensure!(
+            metadata.file_type().is_socket(),
+            "{} exists and is not a socket",
+            path.display()
+        );
+        anyhow::ensure!(
+            std::os::unix::net::UnixStream::connect(path).is_err(),
+            "{} is in use by another process",
+            path.display()
+        );
+        std::fs::remove_file(path).with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+    }
+
+    let listener = tokio::net::UnixListener::bind(path)
+        .with_context(|| format!("Failed to listen on unix:{}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+        .with_context(|| format!("Failed to set permissions of {}", path.display()))?;
+    Ok(listener)
+}
+
+/// The first socket passed by systemd socket activation, if this process was given any
+#[cfg(unix)]
+fn systemd_listener() -> anyhow::Result<Option<HttpListener>> {
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    let (Ok(pid), Ok(fds)) = (std::env::var("LISTEN_PID"), std::env::var("LISTEN_FDS")) else {
+        return Ok(None);
+    };
+    // Sockets passed to a parent process are not ours to take
+    if pid.trim().parse::<u32>().ok() != Some(std::process::id()) {
+        return Ok(None);
+    }
+    let count: u32 = fds
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid LISTEN_FDS '{}'", fds))?;
+    if count == 0 {
+        return Ok(None);
+    }
+    if count > 1 {
+        tracing::warn!("systemd passed {} sockets, serving on the first only", count);
+    }
+
+    // SAFETY: with LISTEN_PID naming this process, systemd hands it the sockets from
+    // SD_LISTEN_FDS_START on, and nothing else in the process owns that descriptor
+    let fd = unsafe { OwnedFd::from_raw_fd(SD_LISTEN_FDS_START) };
+
+    // A Unix socket reports a Unix address; anything else is taken to be TCP
+    let socket = std::os::unix::net::UnixListener::from(fd);
+    if socket.local_addr().is_ok() {
+        socket.set_nonblocking(true)?;
+        let listener = tokio::net::UnixListener::from_std(socket)?;
+        return Ok(Some(HttpListener::Unix { listener, created: None }));
+    }
+    let socket = std::net::TcpListener::from(OwnedFd::from(socket));
+    socket.set_nonblocking(true)?;
+    Ok(Some(HttpListener::Tcp(TcpListener::from_std(socket)?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_https_url() {
+        let url = |host: &str, uri: &str, port: u16| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::HOST, host.parse().unwrap());
+            https_url(&headers, &uri.parse().unwrap(), port)
+        };
+        assert_eq!(url("example.com", "/api/health?x=1", 443).as_deref(), Some("https://example.com/api/health?x=1"));
+        assert_eq!(url("example.com:80", "/", 8443).as_deref(), Some("https://example.com:8443/"));
+        assert_eq!(url("[::1]:8080", "/api", 443).as_deref(), Some("https://[::1]/api"));
+        assert_eq!(url("[::1]", "/api", 443).as_deref(), Some("https://[::1]/api"));
+        assert_eq!(https_url(&HeaderMap::new(), &Uri::from_static("/"), 443), None);
+    }
+
+    #[tokio::test]
+    async fn test_connection_guard() {
+        use tokio::io::AsyncReadExt;
+
+        let mut settings = Settings::from_vars(|_| None).unwrap();
+        settings.max_connections = 1;
+        let mut guard = ConnectionGuard::new(&settings);
+        guard.first_byte_timeout = Duration::from_millis(20);
+
+        let (first, _) = guard.accept(tokio::io::empty(), ()).await.unwrap();
+        let mut second = guard.accept(tokio::io::empty(), ());
+        assert!(futures::poll!(&mut second).is_pending());
+        drop(first);
+        second.await.unwrap();
+
+        // A client that never sends a byte is dropped
+        let (client, server) = tokio::io::duplex(64);
+        let (mut silent, _) = guard.accept(server, ()).await.unwrap();
+        let error = silent.read(&mut [0; 8]).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        drop(client);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bind_unix_socket() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("skillts-listener-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("api.sock");
+
+        let listener = bind_unix(&path, 0o600).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let message = bind_unix(&path, 0o600).unwrap_err().to_string();
+        assert!(message.contains("in use by another process"), "{}", message);
+
+        // The file outlives a listener that went away without cleaning up
+        drop(listener);
+        bind_unix(&path, 0o660).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o660);
+
+        std::fs::write(dir.join("plain"), "").unwrap();
+        assert!(bind_unix(&dir.join("plain"), 0o600).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -3,40 +3,99 @@
 //! A translation service for SKILL.md files using OpenAI API with caching support.
 //! Written in Rust for better performance and lower memory usage.
 
+mod cli;
 mod config;
 mod error;
+mod listener;
 mod models;
 mod routers;
 mod services;
 
 use axum::{
     body::Body,
-    http::{Request, Response},
+    extract::{DefaultBodyLimit, State},
+    http::{header, HeaderValue, Request, Response, StatusCode},
+    response::IntoResponse,
     middleware::{self, Next},
     routing::{delete, get, post},
-    Router,
+    Json, Router,
 };
-use chrono::Timelike;
+use arc_swap::ArcSwap;
+use clap::Parser;
 use std::sync::Arc;
 use tokio::signal;
+use tonic::transport::server::TcpIncoming;
+use tower_http::compression::predicate::{NotForContentType, Predicate};
+use tower_http::compression::{CompressionLayer, DefaultPredicate};
 use tower_http::cors::{Any, CorsLayer};
+use tower::util::option_layer;
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::RequestBodyTimeoutLayer;
+use tracing::Instrument;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::config::get_settings;
+use crate::cli::{Cli, Command};
+use crate::config::{LogFormat, Settings};
+use crate::error::AppError;
+use crate::listener::HttpListener;
+use crate::models::schemas::{ErrorResponse, StaleVersionPolicy};
 use crate::routers::translate::{
-    auth_middleware, clear_cache, clear_expired_cache, flush_cache_hits, get_cache_stats,
-    health_check, root, translate_batch, translate_file, AppState,
+    auth_middleware, cancel_job, clear_cache, clear_expired_cache, compact_cache, create_review, delete_cache_entry,
+    delete_prompt, flush_cache_hits, get_batch_job, get_budget, get_job, list_models, get_cache_entry, get_cache_entry_diff, invalidate_cache, get_cache_stats, list_cache_entries, get_detailed_cache_stats,
+    get_prompt, get_public_key, health_check, idempotency_middleware, ip_filter_middleware, in_flight_translations, list_audit, list_tenants,
+    list_prompt_versions, list_prompts, list_recent_requests, list_reviews, metrics, payload_log_middleware, preview_translation, ready_check,
+    check_translation, reload_config, reset_budget, resolve_review, retire_cache_versions, root, run_backup, run_cleanup, save_prompt, spawn_batch_job, translate_archive,
+    translate_batch,
+    translate_delta, translate_file, translate_github, validate_translation, warm_cache, cleanup, AppState, Readiness,
+    StoredResponse, NDJSON_CONTENT_TYPE, REQUEST_ID_HEADER,
 };
+use crate::services::audit::AuditLog;
+use crate::services::backup::{self, Backups};
+use crate::services::batch::BatchJobs;
+use crate::services::budget::Budget;
+use crate::services::error_messages;
+use crate::services::sources::SourceStore;
+use crate::services::terminology::TerminologyStore;
 use crate::services::cache::TranslationCache;
-use crate::services::translator::Translator;
+use crate::services::payload_log::PayloadLog;
+use crate::services::prompts::PromptStore;
+use crate::services::review::ReviewQueue;
+use crate::services::translator::{Provider, TranslationProfile, Translator};
+use skillts_core::language;
+
+/// Document translated by the startup warmup
+const WARMUP_DOCUMENT: &str = "Hello, world.\n";
+
+/// Wait between warmup attempts while the upstream does not answer
+const WARMUP_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Reuse a sane client-supplied request id, otherwise generate a new one
+fn request_id_for(req: &Request<Body>) -> String {
+    req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
 
-/// Access log middleware - FastAPI style
+/// Access log middleware - FastAPI style, or one structured event per request in JSON mode.
+/// Every request runs inside a span carrying its request id.
 async fn access_log_middleware(
-    req: Request<Body>,
+    State(log_format): State<LogFormat>,
+    mut req: Request<Body>,
     next: Next,
 ) -> Response<Body> {
     let start = std::time::Instant::now();
-    
+
+    // Handlers see the id that is logged, generated or not
+    let request_id = request_id_for(&req);
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        req.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    let span = tracing::info_span!("request", request_id = %request_id);
+
     // Get request info before moving req
     let method = req.method().clone();
     let uri = req.uri().clone();
@@ -55,7 +114,11 @@ async fn access_log_middleware(
         .to_string();
     
     // Process request
-    let response = next.run(req).await;
+    let mut response = next.run(req).instrument(span.clone()).await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
     
     // Calculate duration
     let duration = start.elapsed();
@@ -83,67 +146,169 @@ async fn access_log_middleware(
         _ => "HTTP/1.1",
     };
     
-    // Log in FastAPI style with timestamp
-    tracing::info!(
-        r#"{}:{} - "{} {} {}" {} {}"#,
-        client_ip,
-        "-", // port not easily available
-        method,
-        uri.path(),
-        http_version,
-        status_code,
-        status_text
-    );
-    
-    tracing::debug!("Request completed in {:?}", duration);
-    
-    response
-}
+    span.in_scope(|| match log_format {
+        LogFormat::Json => {
+            tracing::info!(
+                client_ip = %client_ip,
+                method = %method,
+                path = %uri.path(),
+                http_version,
+                status = status_code,
+                duration_ms = duration.as_secs_f64() * 1000.0,
+                "request completed"
+            );
+        }
+        LogFormat::Text => {
+            // Log in FastAPI style with timestamp
+            tracing::info!(
+                r#"{}:{} - "{} {} {}" {} {}"#,
+                client_ip,
+                "-", // port not easily available
+                method,
+                uri.path(),
+                http_version,
+                status_code,
+                status_text
+            );
 
-/// Backup cache database file before initialization
-async fn backup_cache_db(db_path: &str) -> anyhow::Result<()> {
-    use tokio::fs;
+            tracing::debug!("Request completed in {:?}", duration);
+        }
+    });
 
-    let db_path = std::path::Path::new(db_path);
+    response
+}
 
-    // Only backup if the database file exists
-    if !db_path.exists() {
-        tracing::debug!("Cache database does not exist, skipping backup");
-        return Ok(());
+/// Give 413 responses from body limits the JSON error body of every other error.
+/// Limits reject with plain text, both up front on Content-Length and while reading.
+async fn payload_too_large_middleware(req: Request<Body>, next: Next) -> Response<Body> {
+    let response = next.run(req).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE || is_json {
+        return response;
     }
 
-    let backup_path = db_path.with_extension("bak.db");
+    AppError::PayloadTooLarge("Request body exceeds the size limit".to_string()).into_response()
+}
 
-    // Remove old backup if exists
-    if backup_path.exists() {
-        fs::remove_file(&backup_path).await?;
-        tracing::debug!("Removed old backup: {:?}", backup_path);
+/// Localize the `detail` of JSON error responses to the language `Accept-Language`
+/// prefers, when the catalog has it; the `code` stays as it is
+async fn localize_errors_middleware(req: Request<Body>, next: Next) -> Response<Body> {
+    let language = req
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(error_messages::preferred_language);
+    let response = next.run(req).await;
+    let Some(language) = language else {
+        return response;
+    };
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if !is_json || !(response.status().is_client_error() || response.status().is_server_error()) {
+        return response;
     }
 
-    // Copy current database to backup
-    fs::copy(db_path, &backup_path).await?;
-    tracing::info!("Cache database backed up to: {:?}", backup_path);
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+        return AppError::Internal("Failed to read the error response".to_string()).into_response();
+    };
+    let Ok(mut error) = serde_json::from_slice::<ErrorResponse>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    error.detail = error_messages::localize(language, error.code, &error.detail);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::CONTENT_LANGUAGE, HeaderValue::from_static(language.tag()));
+    (parts, Json(error)).into_response()
+}
 
-    Ok(())
+/// Initialize logging with timestamp in the configured format
+fn init_logging(settings: &Settings) {
+    let registry = tracing_subscriber::registry().with(
+        tracing_subscriber::EnvFilter::try_new(&settings.log_filter)
+            .unwrap_or_else(|_| "skill_translator=info".into()),
+    );
+    match settings.log_format {
+        LogFormat::Json => registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(true)
+                    .with_timer(tracing_subscriber::fmt::time::time()),
+            )
+            .init(),
+        LogFormat::Text => registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .with_target(false)
+                    .with_timer(tracing_subscriber::fmt::time::time()),
+            )
+            .init(),
+    }
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize logging with timestamp
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "skill_translator=info".into()),
-        )
-        .with(
-            tracing_subscriber::fmt::layer()
-                .with_target(false)
-                .with_timer(tracing_subscriber::fmt::time::time())
-        )
-        .init();
+    let cli = Cli::parse();
 
     // Load settings
-    let settings = get_settings();
+    let settings = Arc::new(Settings::load(cli.config.as_deref())?);
+    init_logging(&settings);
+
+    if cli.migrate_only {
+        let applied = services::schema::migrate_database(&settings).await?;
+        tracing::info!("Applied {} schema migrations", applied);
+        return Ok(());
+    }
+
+    match cli.command {
+        Some(Command::Translate(args)) => {
+            if !cli::run_translate(args, settings).await? {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Some(Command::Serve) | None => serve(settings).await,
+    }
+}
+
+/// Translate a one-line document so the first request does not pay for connecting
+/// upstream, and to know the model answers. Retries until it does.
+async fn warm_up(translator: &Translator, settings: &Settings) {
+    // Only the round trip matters, so the document is English whatever the source language
+    let source_language = match settings.source_language.as_str() {
+        language::AUTO => "en",
+        source => source,
+    };
+    let profile = TranslationProfile::new(source_language, &settings.target_language);
+    loop {
+        let started = std::time::Instant::now();
+        match translator.translate(WARMUP_DOCUMENT, &profile).await {
+            Ok(_) => {
+                tracing::info!("Warmup translation succeeded in {} ms", started.elapsed().as_millis());
+                return;
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Warmup translation failed, retrying in {} seconds: {}",
+                    WARMUP_RETRY_DELAY.as_secs(),
+                    e
+                );
+                tokio::time::sleep(WARMUP_RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+/// Run the HTTP server, and the gRPC server when enabled, until a shutdown signal is received
+async fn serve(settings: Arc<Settings>) -> anyhow::Result<()> {
 
     tracing::info!(
         "Starting Skill Translator Service v{}",
@@ -151,9 +316,16 @@ async fn main() -> anyhow::Result<()> {
     );
     tracing::info!("OpenAI model: {}", settings.openai_model);
     tracing::info!("Cache database: {}", settings.cache_db_path);
+    tracing::info!(
+        "Deployment profile: {}, {} SQLite connections",
+        settings.deployment_profile,
+        settings.sqlite_max_connections
+    );
 
-    // Check OpenAI API key
-    if settings.openai_api_key.is_empty() {
+    // Check OpenAI API key; self-hosted Ollama servers usually run without one
+    if settings.llm_provider == Provider::Ollama {
+        tracing::info!("Using Ollama server at {}", settings.openai_base_url);
+    } else if settings.openai_api_key.is_empty() {
         tracing::warn!("OpenAI API key not configured. Translation will fail.");
     } else {
         tracing::info!("OpenAI API key configured");
@@ -166,15 +338,51 @@ async fn main() -> anyhow::Result<()> {
         tracing::info!("API authentication enabled");
     }
 
-    // Backup cache database before initialization
-    backup_cache_db(&settings.cache_db_path).await?;
+    // Readiness flags shared with the /api/ready endpoint
+    let readiness = Arc::new(Readiness::default());
+
+    // Apply pending schema migrations, backing up the database first
+    services::schema::migrate_database(&settings).await?;
 
     // Initialize cache
-    let cache = Arc::new(TranslationCache::new().await?);
+    let cache = Arc::new(TranslationCache::new(settings.cache_config()).await?);
+    services::schema::verify_database(&settings, &cache).await?;
     tracing::info!("Cache initialized successfully");
 
+    // Purge or mark cached translations of other translator versions in the background
+    if settings.stale_version_policy != StaleVersionPolicy::Keep {
+        let cache = cache.clone();
+        let (version, policy) = (settings.translator_version.clone(), settings.stale_version_policy);
+        tokio::spawn(async move {
+            if let Err(e) = cache.retire_versions(&version, policy).await {
+                tracing::warn!("Failed to retire cache entries of other translator versions: {}", e);
+            }
+        });
+    }
+
+    // Initialize review queue in the cache database
+    let reviews = Arc::new(ReviewQueue::new(cache.pool().clone(), &settings).await?);
+
+    // Initialize request audit log in the cache database
+    let audit = Arc::new(AuditLog::new(cache.pool().clone()).await?);
+
+    // Initialize saved prompt templates in the cache database
+    let prompts = Arc::new(PromptStore::new(cache.pool().clone()).await?);
+
+    // Initialize background batch jobs in the cache database
+    let batch_jobs = Arc::new(BatchJobs::new(cache.pool().clone()).await?);
+
+    // Initialize budget usage tracking in the cache database
+    let budget = Arc::new(Budget::new(cache.pool().clone()).await?);
+
+    // Initialize the terminology memory in the cache database
+    let terminology = Arc::new(TerminologyStore::new(cache.pool().clone()).await?);
+
+    // Initialize the originals of cached translations in the cache database
+    let sources = Arc::new(SourceStore::new(cache.pool().clone()).await?);
+
     // Initialize translator
-    let translator = Arc::new(Translator::new());
+    let translator = Arc::new(Translator::new(settings.translator_config()));
 
     // Get API bearer for authentication
     let api_bearer = settings.local_api_bearer.clone();
@@ -182,83 +390,282 @@ async fn main() -> anyhow::Result<()> {
     // Clone cache for graceful shutdown (before moving into AppState)
     let cache_for_shutdown = cache.clone();
 
-    // Clone cache for background cleanup task
-    let cache_for_cleanup = cache.clone();
+    // Start background task that periodically flushes pending hit counts
+    if settings.cache_flush_interval_seconds > 0 {
+        let cache_for_flush = cache.clone();
+        let flush_interval = std::time::Duration::from_secs(settings.cache_flush_interval_seconds);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(flush_interval);
+            // The first tick completes immediately
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = cache_for_flush.flush_pending_hits().await {
+                    tracing::error!("Periodic hit count flush failed: {}", e);
+                }
+            }
+        });
+        tracing::info!(
+            "Pending hit counts flushed every {} seconds",
+            settings.cache_flush_interval_seconds
+        );
+    }
 
-    // Start background cache cleanup task (runs daily at 1 AM)
+    // Create application state
+    let state = AppState {
+        translator,
+        cache,
+        reviews,
+        audit,
+        prompts,
+        backups: Arc::new(Backups::new()?),
+        batch_jobs,
+        budget,
+        terminology,
+        sources,
+        idempotency: Arc::new(StoredResponse::store(std::time::Duration::from_secs(
+            settings.idempotency_ttl_seconds,
+        ))),
+        in_flight: Arc::new(in_flight_translations()),
+        settings: Arc::new(ArcSwap::from(settings.clone())),
+        api_bearer,
+        readiness: readiness.clone(),
+        payload_log: Arc::new(PayloadLog::default()),
+    };
+    state.reload_prompts().await?;
+
+    // Resume background batch jobs interrupted by a restart
+    let unfinished = state.batch_jobs.unfinished().await?;
+    if !unfinished.is_empty() {
+        tracing::info!("Resuming {} unfinished batch jobs", unfinished.len());
+    }
+    for job in unfinished {
+        spawn_batch_job(&state, job);
+    }
+
+    // Start the cache cleanup on its schedule
+    let state_for_cleanup = state.clone();
     tokio::spawn(async move {
         loop {
-            // Calculate time until next 1 AM
-            let now = chrono::Local::now();
-            let next_1am = now
-                .with_hour(1)
-                .and_then(|t| t.with_minute(0))
-                .and_then(|t| t.with_second(0))
-                .and_then(|t| t.with_nanosecond(0));
-
-            let next_run = match next_1am {
-                Some(t) if t > now => t,
-                Some(t) => t + chrono::Duration::days(1), // Already passed today, schedule for tomorrow
-                None => {
-                    tracing::error!("Failed to calculate next cleanup time");
-                    return;
-                }
+            let schedule = state_for_cleanup.settings().cache_cleanup_schedule.clone();
+            let now = chrono::Local::now().naive_local();
+            let Some(next_run) = schedule.next_after(now) else {
+                tracing::error!("Cleanup schedule '{}' never matches, cache cleanup stopped", schedule);
+                return;
             };
-
-            let sleep_duration = (next_run - now)
-                .to_std()
-                .unwrap_or(std::time::Duration::from_secs(3600));
-
+            let sleep_duration = (next_run - now).to_std().unwrap_or_default();
             tracing::info!(
                 "Cache cleanup scheduled for {} (in {} seconds)",
                 next_run.format("%Y-%m-%d %H:%M:%S"),
                 sleep_duration.as_secs()
             );
-
             tokio::time::sleep(sleep_duration).await;
 
-            // Run cleanup: clear entries not accessed in 30 days
-            match cache_for_cleanup.clear_stale(30).await {
-                Ok(count) => {
-                    tracing::info!("Daily cache cleanup completed: {} stale entries removed", count);
+            let stale_days = state_for_cleanup.settings().cache_stale_days;
+            if let Err(e) = cleanup(&state_for_cleanup, stale_days).await {
+                tracing::error!("Scheduled cache cleanup failed: {}", e);
+            }
+        }
+    });
+
+    // Start scheduled backups, aligned to local midnight
+    if settings.backup_interval_hours > 0 {
+        let state_for_backup = state.clone();
+        let interval_hours = settings.backup_interval_hours;
+        tokio::spawn(async move {
+            loop {
+                let now = chrono::Local::now().naive_local();
+                let wait = (backup::next_run(now, interval_hours) - now)
+                    .to_std()
+                    .unwrap_or_default();
+                tokio::time::sleep(wait).await;
+
+                let settings = state_for_backup.settings();
+                if let Err(e) = state_for_backup
+                    .backups
+                    .run(&settings, state_for_backup.cache.pool())
+                    .await
+                {
+                    tracing::error!("Scheduled backup failed: {}", e);
+                }
+            }
+        });
+        tracing::info!("Cache database backed up every {} hours", interval_hours);
+    }
+
+    // Start the cache warmer on its schedule
+    if !settings.warmer_manifest_url.is_empty() {
+        let state_for_warmer = state.clone();
+        tokio::spawn(async move {
+            loop {
+                let schedule = state_for_warmer.settings().warmer_schedule.clone();
+                let now = chrono::Local::now().naive_local();
+                let Some(next_run) = schedule.next_after(now) else {
+                    tracing::error!("Warmer schedule '{}' never matches, warmer stopped", schedule);
+                    return;
+                };
+                tokio::time::sleep((next_run - now).to_std().unwrap_or_default()).await;
+
+                if state_for_warmer.settings().warmer_manifest_url.is_empty() {
+                    continue;
                 }
+                match warm_cache(&state_for_warmer).await {
+                    Ok(run) => tracing::info!(
+                        "Cache warmer translated {} of {} listed files ({} cached, {} failed)",
+                        run.translated,
+                        run.listed,
+                        run.cached,
+                        run.failed
+                    ),
+                    Err(e) => tracing::error!("Cache warmer failed: {}", e),
+                }
+            }
+        });
+        tracing::info!("Cache warmer scheduled at '{}'", settings.warmer_schedule);
+    }
+
+    // Reload configuration on SIGHUP
+    #[cfg(unix)]
+    {
+        let state_for_reload = state.clone();
+        tokio::spawn(async move {
+            let mut hangup = match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+                Ok(hangup) => hangup,
                 Err(e) => {
-                    tracing::error!("Daily cache cleanup failed: {}", e);
+                    tracing::error!("Failed to install SIGHUP handler: {}", e);
+                    return;
+                }
+            };
+            while hangup.recv().await.is_some() {
+                tracing::info!("SIGHUP received, reloading configuration");
+                if let Err(e) = state_for_reload.reload_settings() {
+                    tracing::error!("Configuration reload failed, keeping current settings: {}", e);
                 }
             }
-        }
-    });
+        });
+    }
 
-    // Create application state
-    let state = AppState {
-        translator,
-        cache,
-        api_bearer,
+    // Tells the gRPC server to stop once a shutdown signal arrives
+    let (shutdown_sender, mut shutdown_receiver) = tokio::sync::watch::channel(false);
+
+    // Start the gRPC API on its own port, sharing the application state
+    let grpc_server = if settings.grpc_port > 0 {
+        let addr = format!("{}:{}", settings.host, settings.grpc_port);
+        let listener = tokio::net::TcpListener::bind(&addr).await?;
+        tracing::info!("gRPC server listening on {}", addr);
+        let server = tonic::transport::Server::builder()
+            .add_service(routers::grpc::service(state.clone()))
+            .serve_with_incoming_shutdown(TcpIncoming::from(listener), async move {
+                let _ = shutdown_receiver.wait_for(|stopping| *stopping).await;
+            });
+        Some(tokio::spawn(server))
+    } else {
+        None
     };
 
-    // Health check route (no auth required)
-    let health_route = Router::new().route("/api/health", get(health_check));
+    let translator_for_warmup = state.translator.clone();
+    let state_for_ip_filter = state.clone();
+
+    // Root, health, readiness, metrics and API document routes (no auth required)
+    let public_routes = Router::new()
+        .route("/", get(root))
+        .route("/api/health", get(health_check))
+        .route("/api/ready", get(ready_check))
+        .route("/api/public-key", get(get_public_key))
+        .route("/metrics", get(metrics))
+        .with_state(state.clone())
+        .merge(routers::openapi::router());
 
     // Build API routes with authentication
     let api_routes = Router::new()
-        .route("/translate", post(translate_file))
-        .route("/translate/batch", post(translate_batch))
+        .route(
+            "/translate",
+            post(translate_file).layer(middleware::from_fn_with_state(
+                state.clone(),
+                idempotency_middleware,
+            )),
+        )
+        .route(
+            "/translate/batch",
+            post(translate_batch).layer(middleware::from_fn_with_state(
+                state.clone(),
+                idempotency_middleware,
+            )),
+        )
+        .route("/translate/batch/{job_id}", get(get_batch_job))
+        .route("/jobs/{job_id}", get(get_job).delete(cancel_job))
+        .route("/translate/preview", post(preview_translation))
+        .route("/translate/check", post(check_translation))
+        .route("/translate/delta", post(translate_delta))
+        .route("/translate/github", post(translate_github))
+        .route("/ws", get(routers::ws::translate_session))
+        .route("/validate", post(validate_translation))
         .route("/cache/stats", get(get_cache_stats))
+        .route("/cache/stats/detailed", get(get_detailed_cache_stats))
         .route("/cache", delete(clear_cache))
         .route("/cache/expired", delete(clear_expired_cache))
+        .route("/cache/entry", get(get_cache_entry).delete(delete_cache_entry))
+        .route("/cache/entry/{cache_key}/diff", get(get_cache_entry_diff))
+        .route("/cache/entries", get(list_cache_entries))
+        .route("/cache/invalidate", post(invalidate_cache))
         .route("/cache/flush", post(flush_cache_hits))
+        .route("/reviews", get(list_reviews).post(create_review))
+        .route("/reviews/{id}/resolve", post(resolve_review))
+        .route("/admin/reload-config", post(reload_config))
+        .route("/admin/backup", post(run_backup))
+        .route("/admin/cleanup", post(run_cleanup))
+        .route("/admin/cache/compact", post(compact_cache))
+        .route("/admin/cache/versions", post(retire_cache_versions))
+        .route("/admin/audit", get(list_audit))
+        .route("/admin/recent-requests", get(list_recent_requests))
+        .route("/admin/tenants", get(list_tenants))
+        .route("/admin/models", get(list_models))
+        .route("/admin/budget", get(get_budget))
+        .route("/admin/budget/reset", post(reset_budget))
+        .route("/admin/prompts", get(list_prompts))
+        .route(
+            "/admin/prompts/{name}",
+            get(get_prompt).put(save_prompt).delete(delete_prompt),
+        )
+        .route("/admin/prompts/{name}/versions", get(list_prompt_versions))
+        // Applies to the routes above; archive uploads get their own, larger limit
+        .layer(RequestBodyLimitLayer::new(settings.max_request_bytes))
+        .route(
+            "/translate/archive",
+            post(translate_archive).layer(RequestBodyLimitLayer::new(settings.max_archive_bytes)),
+        )
         .route_layer(axum::middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
         ))
+        // Outside authentication, so rejected requests are recorded too
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            payload_log_middleware,
+        ))
         .with_state(state);
 
     // Build application
     let app = Router::new()
-        .route("/", get(root))
-        .merge(health_route)
+        .merge(public_routes)
         .nest("/api", api_routes)
-        .layer(middleware::from_fn(access_log_middleware))
+        .layer(middleware::from_fn_with_state(state_for_ip_filter, ip_filter_middleware))
+        // Body sizes are enforced by the per-route limits instead of axum's 2 MB default
+        .layer(DefaultBodyLimit::disable())
+        .layer(option_layer(
+            (settings.body_read_timeout_seconds > 0)
+                .then(|| RequestBodyTimeoutLayer::new(std::time::Duration::from_secs(settings.body_read_timeout_seconds))),
+        ))
+        .layer(middleware::from_fn(payload_too_large_middleware))
+        .layer(middleware::from_fn(localize_errors_middleware))
+        // Compressing would hold back the lines of a streamed batch until the encoder flushes
+        .layer(CompressionLayer::new().compress_when(
+            DefaultPredicate::new().and(NotForContentType::const_new(NDJSON_CONTENT_TYPE)),
+        ))
+        .layer(middleware::from_fn_with_state(
+            settings.log_format,
+            access_log_middleware,
+        ))
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
@@ -266,14 +673,23 @@ async fn main() -> anyhow::Result<()> {
                 .allow_headers(Any),
         );
 
-    // Build server address
-    let addr = format!("{}:{}", settings.host, settings.port);
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    let listener = HttpListener::bind(&settings).await?;
+    tracing::info!("Server listening on {}", listener.describe());
 
-    tracing::info!("Server listening on {}", addr);
+    // Stay not ready until a test translation went through, when asked to
+    if settings.warmup_on_start {
+        let (settings, readiness) = (settings.clone(), readiness.clone());
+        tokio::spawn(async move {
+            warm_up(&translator_for_warmup, &settings).await;
+            readiness.mark_ready();
+        });
+    } else {
+        readiness.mark_ready();
+    }
 
     // Setup graceful shutdown
-    let shutdown_signal = async {
+    let readiness_for_shutdown = readiness.clone();
+    let shutdown_signal = async move {
         let ctrl_c = async {
             signal::ctrl_c()
                 .await
@@ -297,12 +713,21 @@ async fn main() -> anyhow::Result<()> {
         }
 
         tracing::info!("Shutdown signal received, starting graceful shutdown...");
+        readiness_for_shutdown.mark_draining();
+        let _ = shutdown_sender.send(true);
     };
 
     // Start server with graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal)
-        .await?;
+    listener.serve(app, &settings, shutdown_signal).await?;
+
+    // Let in-flight gRPC calls finish before closing the cache
+    if let Some(grpc_server) = grpc_server {
+        match grpc_server.await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::error!("gRPC server failed: {}", e),
+            Err(e) => tracing::error!("gRPC server task failed: {}", e),
+        }
+    }
 
     // Graceful shutdown: close cache connection
     if let Err(e) = cache_for_shutdown.close().await {
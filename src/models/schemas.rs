@@ -4,16 +4,144 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use utoipa::{IntoParams, ToSchema};
+
+pub use skillts_core::models::{
+    CacheEntry, CacheEntrySummary, CacheOrder, CacheStats, CircuitBreakerStatus, CompactStats, DetailedCacheStats,
+    StaleVersionPolicy, ValidationReport,
+};
+pub use skillts_core::anchors::AnchorMode;
+pub use skillts_core::bilingual::OutputMode;
+pub use skillts_core::diff::DiffFormat;
+pub use skillts_core::prompt::PromptTemplate;
+pub use skillts_core::quality::QualityScore;
+pub use skillts_core::roundtrip::RoundtripReport;
+pub use skillts_core::translator::TranslationScope;
+use skillts_core::translator::TranslationMetadata;
+use skillts_core::prompt::{ANY, DEFAULT_DOCUMENT_TYPE};
+use skillts_core::translator::{decode_content, decode_gzip_content, encode_content, encode_gzip_content};
+
+/// How document content is carried in JSON requests and responses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentEncoding {
+    /// Base64 encoded UTF-8
+    #[default]
+    Base64,
+    /// UTF-8 text as is
+    Plain,
+    /// Base64 encoded gzip compression of UTF-8
+    #[serde(rename = "gzip+base64")]
+    GzipBase64,
+}
+
+impl ContentEncoding {
+    /// Decode request content into text
+    pub fn decode(self, content: &str) -> skillts_core::Result<String> {
+        match self {
+            ContentEncoding::Base64 => decode_content(content),
+            ContentEncoding::Plain => Ok(content.to_string()),
+            ContentEncoding::GzipBase64 => decode_gzip_content(content),
+        }
+    }
+
+    /// Encode text for a response
+    pub fn encode(self, content: &str) -> String {
+        match self {
+            ContentEncoding::Base64 => encode_content(content),
+            ContentEncoding::Plain => content.to_string(),
+            ContentEncoding::GzipBase64 => encode_gzip_content(content),
+        }
+    }
+}
+
+/// What happens to lines longer than `MAX_LINE_LENGTH` characters, such as inline data URIs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LongLineMode {
+    /// Remove the lines before translating
+    #[default]
+    Drop,
+    /// Cut the lines down to the maximum length
+    Truncate,
+    /// Send the lines to the model as they are
+    Passthrough,
+    /// Refuse the document with 422
+    Reject,
+}
+
+impl FromStr for LongLineMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "drop" => Ok(LongLineMode::Drop),
+            "truncate" => Ok(LongLineMode::Truncate),
+            "passthrough" => Ok(LongLineMode::Passthrough),
+            "reject" => Ok(LongLineMode::Reject),
+            other => Err(format!(
+                "unknown long line mode '{}', expected 'drop', 'truncate', 'passthrough' or 'reject'",
+                other
+            )),
+        }
+    }
+}
+
+/// Lines of a document longer than the maximum length and how they were handled
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct LongLines {
+    pub mode: LongLineMode,
+    /// Maximum line length in characters
+    pub max_length: usize,
+    pub count: usize,
+    /// 1-based numbers of the lines in the submitted content
+    pub lines: Vec<usize>,
+}
+
+impl LongLines {
+    /// Whether the translated content differs from the submitted one
+    pub fn lossy(&self) -> bool {
+        matches!(self.mode, LongLineMode::Drop | LongLineMode::Truncate)
+    }
+}
 
 /// Options for translation
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 #[serde(default)]
 pub struct TranslateOptions {
+    /// When false the frontmatter is passed through untouched, description included
     pub preserve_frontmatter: bool,
+    /// When false code blocks are sent to the model with the prose, e.g. for pseudocode
     pub preserve_code_blocks: bool,
     pub translate_code_comments: bool,
-    pub target_language: String,
-    pub source_language: String,
+    /// Keep `#slug` links working: `none`, `attributes` or `rewrite`
+    pub anchor_mode: AnchorMode,
+    /// Translate everything (`full`), only the frontmatter description or only the body
+    pub scope: TranslationScope,
+    /// Defaults to the configured `TARGET_LANGUAGE`
+    pub target_language: Option<String>,
+    /// Defaults to the configured `SOURCE_LANGUAGE`
+    pub source_language: Option<String>,
+    /// Selects the prompt template together with the languages
+    pub document_type: String,
+    /// Encoding of the translated content in the response
+    pub response_encoding: ContentEncoding,
+    /// Timeout in seconds for each upstream call, capped at `MAX_REQUEST_TIMEOUT_SECONDS`
+    pub timeout_seconds: Option<u64>,
+    /// Retries of a failed upstream call, capped at `MAX_REQUEST_RETRIES`
+    pub max_retries: Option<u32>,
+    /// Have the judge model score fresh translations, see `QUALITY_JUDGE_MODEL`
+    pub quality_check: bool,
+    /// Translate fresh translations back and report sections that drift from the original
+    pub verify_roundtrip: bool,
+    /// Ignore cached translations and segments and translate again, replacing the cache entry
+    pub force: bool,
+    /// Terms left untranslated in addition to `KEEP_TERMS`, such as product names
+    pub keep_terms: Vec<String>,
+    /// Return the translation alone (`replace`) or paired block by block with the
+    /// original (`bilingual`, `bilingual_original_first`)
+    pub output_mode: OutputMode,
 }
 
 impl Default for TranslateOptions {
@@ -22,29 +150,132 @@ impl Default for TranslateOptions {
             preserve_frontmatter: true,
             preserve_code_blocks: true,
             translate_code_comments: false,
-            target_language: "zh-CN".to_string(),
-            source_language: "en".to_string(),
+            anchor_mode: AnchorMode::None,
+            scope: TranslationScope::Full,
+            target_language: None,
+            source_language: None,
+            document_type: DEFAULT_DOCUMENT_TYPE.to_string(),
+            response_encoding: ContentEncoding::Base64,
+            timeout_seconds: None,
+            max_retries: None,
+            quality_check: false,
+            verify_roundtrip: false,
+            force: false,
+            keep_terms: Vec::new(),
+            output_mode: OutputMode::Replace,
         }
     }
 }
 
 /// Request model for single file translation
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct TranslateRequest {
-    /// Base64 encoded content of the SKILL.md file
+    /// Content of the SKILL.md file, base64 encoded unless `content_encoding` is `plain`
     pub content: String,
+    #[serde(default)]
+    pub content_encoding: ContentEncoding,
     /// Relative path of the file in the repository
     pub path: String,
-    /// SHA256 hash of the original content (with "sha256:" prefix)
-    pub content_hash: String,
+    /// SHA256 hash of the original content (with "sha256:" prefix); computed when absent
+    /// and rejected when it does not match the content
+    pub content_hash: Option<String>,
     /// Optional translation options
     pub options: Option<TranslateOptions>,
+    /// Auxiliary markdown files of the same skill, such as reference.md or examples/*.md,
+    /// translated after this file in order, each using the renderings of headings and
+    /// bold terms chosen for the files before it
+    #[serde(default)]
+    pub related_files: Vec<FileToTranslate>,
+}
+
+/// Metadata of a translation, returned in responses and stored with the cached translation.
+///
+/// Fields only some responses have are left out when unset; fields this version does not
+/// know, e.g. written by a newer one into a shared cache, are kept in `extra`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct TranslationResponseMetadata {
+    pub original_chars: usize,
+    pub translated_chars: usize,
+    /// Time the translator took
+    pub processing_time_ms: f64,
+    pub translator_version: String,
+    /// Model that produced the translation
+    pub model: String,
+    pub source_language: String,
+    pub target_language: String,
+    pub retries: u32,
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    /// Upstream calls the translation took
+    pub chunks: usize,
+    /// Body sections reused from the segment cache
+    pub cached_segments: usize,
+    pub body_time_ms: f64,
+    pub description_time_ms: f64,
+    /// Structural problems left in the translation
+    pub warnings: Vec<String>,
+    pub quality: Option<QualityScore>,
+    pub roundtrip: Option<RoundtripReport>,
+    /// Tenant the translation is cached for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// Whether overlong lines were dropped or truncated before translating
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lossy: Option<bool>,
+    /// Time the whole request took, for fresh translations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_processing_time_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub long_lines: Option<LongLines>,
+    /// Why a fresh translation failed, when an earlier translator version's translation answered instead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_error: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl TranslationResponseMetadata {
+    /// Metadata of a translation the translator just produced
+    pub fn new(metadata: &TranslationMetadata) -> Self {
+        Self {
+            original_chars: metadata.original_chars,
+            translated_chars: metadata.translated_chars,
+            processing_time_ms: metadata.processing_time_ms,
+            translator_version: metadata.translator_version.clone(),
+            model: metadata.model.clone(),
+            source_language: metadata.source_language.clone(),
+            target_language: metadata.target_language.clone(),
+            retries: metadata.retries,
+            input_tokens: metadata.input_tokens,
+            output_tokens: metadata.output_tokens,
+            chunks: metadata.chunks,
+            cached_segments: metadata.cached_segments,
+            body_time_ms: metadata.body_time_ms,
+            description_time_ms: metadata.description_time_ms,
+            warnings: metadata.warnings.clone(),
+            quality: metadata.quality.clone(),
+            roundtrip: metadata.roundtrip.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Metadata stored with a cached translation. Entries stored before a field existed
+    /// get its default; metadata that is not an object at all is dropped.
+    pub fn from_stored(metadata: serde_json::Value) -> Self {
+        serde_json::from_value(metadata).unwrap_or_default()
+    }
+
+    /// JSON to store with the cached translation
+    pub fn to_stored(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
 }
 
 /// Response model for single file translation
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TranslateResponse {
-    /// Base64 encoded translated content
+    /// Translated content, encoded as `response_encoding` (base64 by default)
     pub translated_content: String,
     /// SHA256 hash of the original content
     pub content_hash: String,
@@ -52,26 +283,180 @@ pub struct TranslateResponse {
     pub translated_hash: String,
     /// Whether the result was retrieved from cache
     pub cached: bool,
-    /// Additional metadata
-    pub metadata: serde_json::Value,
+    pub metadata: TranslationResponseMetadata,
+    /// Present when the service signs its responses, see `GET /api/public-key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ResponseSignature>,
+    /// Results of the `related_files` of the request, in the same order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub related_files: Vec<FileTranslationResult>,
+}
+
+/// Ed25519 signature over a translation's content hash, translated hash, model and signing time
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ResponseSignature {
+    /// Always `ed25519`
+    pub algorithm: String,
+    /// Identifies the signing key, as reported by `GET /api/public-key`
+    pub key_id: String,
+    /// Model that produced the translation
+    pub model: String,
+    /// RFC 3339 time of signing
+    pub signed_at: String,
+    /// Base64 encoded signature
+    pub signature: String,
+}
+
+/// Public key verifying response signatures
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PublicKeyResponse {
+    /// Always `ed25519`
+    pub algorithm: String,
+    pub key_id: String,
+    /// Base64 encoded 32 byte Ed25519 public key
+    pub public_key: String,
+}
+
+/// Request model for a translation preview
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PreviewRequest {
+    /// Base64 encoded content of the SKILL.md file
+    pub content: String,
+}
+
+/// A frontmatter field that would be translated
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PreviewField {
+    pub name: String,
+    pub value: String,
+}
+
+/// Response model for a translation preview; no upstream call is made
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PreviewResponse {
+    pub frontmatter_fields: Vec<PreviewField>,
+    /// Body with code blocks, tables and HTML blocks replaced by placeholders, as sent to the model
+    pub body_with_placeholders: String,
+    pub code_block_count: usize,
+    /// Lines dropped for exceeding the maximum line length
+    pub removed_lines: usize,
+    /// Lines over the maximum length, when there are any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub long_lines: Option<LongLines>,
+    pub api_calls: usize,
+    pub estimated_input_tokens: usize,
+    pub estimated_output_tokens: usize,
+    /// Estimated cost in USD with the configured per-token prices
+    pub estimated_cost_usd: f64,
+    pub model: String,
+}
+
+/// Request model for validating a translation against its original
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ValidateRequest {
+    /// Base64 encoded original content
+    pub original: String,
+    /// Base64 encoded translated content
+    pub translated: String,
+}
+
+/// Request model for retranslating a changed file against its previous translation
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DeltaTranslateRequest {
+    /// Base64 encoded previous content
+    pub old_content: String,
+    /// Base64 encoded translation of the previous content
+    pub old_translation: String,
+    /// Base64 encoded new content
+    pub new_content: String,
+    /// Relative path of the file in the repository
+    #[serde(default)]
+    pub path: String,
+    /// Optional translation options
+    pub options: Option<TranslateOptions>,
+}
+
+/// How a body section of the new content was produced
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeltaSection {
+    /// First line of the section, usually its heading
+    pub heading: String,
+    /// "reused" or "retranslated"
+    pub status: String,
+}
+
+/// Response model for a delta translation
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeltaTranslateResponse {
+    /// Base64 encoded translated new content
+    pub translated_content: String,
+    /// SHA256 hash of the new content
+    pub content_hash: String,
+    /// SHA256 hash of the translated content
+    pub translated_hash: String,
+    pub sections: Vec<DeltaSection>,
+    pub reused: usize,
+    pub retranslated: usize,
+    pub metadata: TranslationResponseMetadata,
+}
+
+/// Request model for checking whether a mirrored translation is still current
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TranslateCheckRequest {
+    /// SHA256 hash of the original content (with "sha256:" prefix)
+    pub content_hash: String,
+    /// SHA256 hash of the translation the client holds
+    pub translated_hash: String,
+    /// Translation options the client's translation was requested with
+    pub options: Option<TranslateOptions>,
+}
+
+/// Response model for a translation check
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TranslateCheckResponse {
+    pub content_hash: String,
+    /// "current" when the cached translation is the client's, "changed" when it differs,
+    /// "missing" when none is cached for the current prompt, model and translator version
+    pub status: String,
+    /// SHA256 hash of the cached translation, when there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translated_hash: Option<String>,
 }
 
 /// Model for a single file in batch translation
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct FileToTranslate {
     pub path: String,
-    /// Base64 encoded content
+    /// Content, base64 encoded unless `content_encoding` is `plain`
     pub content: String,
-    pub content_hash: String,
+    #[serde(default)]
+    pub content_encoding: ContentEncoding,
+    /// Verified like `TranslateRequest::content_hash`; computed when absent
+    pub content_hash: Option<String>,
+}
+
+impl FileToTranslate {
+    /// A plain text file, for files read by the service itself
+    pub fn plain(path: &str, content: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            content: content.to_string(),
+            content_encoding: ContentEncoding::Plain,
+            content_hash: None,
+        }
+    }
 }
 
 /// Request model for batch translation
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct BatchTranslateRequest {
     pub files: Vec<FileToTranslate>,
     pub options: Option<TranslateOptions>,
     #[serde(default = "default_skip_cached")]
     pub skip_cached: bool,
+    /// Return a job id right away and process the files in the background
+    #[serde(default)]
+    pub background: bool,
 }
 
 fn default_skip_cached() -> bool {
@@ -79,7 +464,7 @@ fn default_skip_cached() -> bool {
 }
 
 /// Result for a single file in batch translation
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct FileTranslationResult {
     pub path: String,
     pub success: bool,
@@ -92,61 +477,583 @@ pub struct FileTranslationResult {
     pub cached: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Kind of the error, when the file failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<ErrorCode>,
+    /// Lines over the maximum length, when there are any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_lines: Option<LongLines>,
 }
 
 /// Response model for batch translation
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct BatchTranslateResponse {
+    pub results: Vec<FileTranslationResult>,
+    #[serde(flatten)]
+    pub summary: BatchSummary,
+}
+
+/// Counts of a batch translation, also the last line of a streamed batch
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct BatchSummary {
+    pub total_files: usize,
+    pub successful: usize,
+    pub cached_count: usize,
+    pub failed: usize,
+    pub processing_time_ms: f64,
+}
+
+impl BatchSummary {
+    /// Count the result of one more file
+    pub fn count(&mut self, result: &FileTranslationResult) {
+        self.total_files += 1;
+        if result.cached {
+            self.cached_count += 1;
+        }
+        if result.success {
+            self.successful += 1;
+        } else {
+            self.failed += 1;
+        }
+    }
+}
+
+/// Progress and results of a background batch job
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchJobStatus {
+    pub job_id: String,
+    /// `running`, `completed` or `cancelled`
+    pub status: String,
+    pub total_files: usize,
+    /// Files processed so far, successfully or not
+    pub processed_files: usize,
+    pub successful: usize,
+    pub cached_count: usize,
+    pub failed: usize,
+    pub progress: BatchJobProgress,
+    /// Results of processed files in request order, after the requested offset
+    pub results: Vec<FileTranslationResult>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Progress of a background batch job within its files
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchJobProgress {
+    /// What the job is doing with its current file: `preparing`, `translating` or `saving`.
+    /// Absent while the job is not being processed, e.g. once finished.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage: Option<String>,
+    /// Path of the file being processed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_path: Option<String>,
+    /// Upstream calls started for the current file
+    pub current_chunk: usize,
+    /// Upstream calls the current file is expected to take, growing with repairs
+    pub current_chunks: usize,
+    /// Upstream calls the processed files took
+    pub chunks_completed: usize,
+    /// Characters of the content of all files
+    pub characters_total: usize,
+    /// Characters of the content of the files translated successfully, cached ones included
+    pub characters_translated: usize,
+}
+
+/// Query parameters for polling a batch job
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BatchJobQuery {
+    /// Number of processed results to skip, e.g. those received in earlier polls
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// Request model for translating files of a GitHub repository
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct GitHubTranslateRequest {
+    /// Repository as `owner/name` or a github.com URL
+    pub repo: String,
+    /// Branch, tag or commit; defaults to the repository's default branch
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+    #[serde(default = "default_paths_glob")]
+    pub paths_glob: String,
+    pub options: Option<TranslateOptions>,
+    #[serde(default = "default_skip_cached")]
+    pub skip_cached: bool,
+    /// Open a pull request adding `<name>.<target_language>.md` next to each source file
+    #[serde(default)]
+    pub create_pr: bool,
+}
+
+fn default_paths_glob() -> String {
+    "**/SKILL.md".to_string()
+}
+
+/// Response model for GitHub repository translation
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GitHubTranslateResponse {
+    pub repo: String,
+    #[serde(rename = "ref")]
+    pub git_ref: String,
     pub results: Vec<FileTranslationResult>,
     pub total_files: usize,
     pub successful: usize,
     pub cached_count: usize,
     pub failed: usize,
     pub processing_time_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pull_request_url: Option<String>,
+    /// Why the pull request could not be opened; the translations are still returned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pull_request_error: Option<String>,
 }
 
-/// Model for a cache entry
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CacheEntry {
+/// Query parameters selecting cache entries by path or original content hash
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CacheEntryQuery {
+    pub path: Option<String>,
+    pub content_hash: Option<String>,
+}
+
+/// Query parameters of a cache entry comparison
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CacheEntryDiffQuery {
+    /// `html` (default) for a side-by-side page or `unified` for a unified diff
+    pub format: Option<DiffFormat>,
+}
+
+/// Query parameters of the cache statistics
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CacheStatsQuery {
+    /// `1` or `true` to compute the statistics again instead of reusing recent ones
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub fresh: bool,
+}
+
+/// A boolean query parameter given as `1`/`0` or `true`/`false`
+fn deserialize_flag<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    match String::deserialize(deserializer)?.as_str() {
+        "1" | "true" => Ok(true),
+        "0" | "false" | "" => Ok(false),
+        other => Err(serde::de::Error::custom(format!("expected 1 or 0, got '{}'", other))),
+    }
+}
+
+/// Query parameters for browsing cache entries
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CacheListQuery {
+    /// Only entries whose path starts with this
+    pub path_prefix: Option<String>,
+    /// Only entries translated into this language
+    pub language: Option<String>,
+    /// `newest` (default), `oldest`, `hits`, `size` or `accessed`
+    pub order_by: Option<CacheOrder>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// A page of cache entries, without their translated content
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CacheEntryPage {
+    pub entries: Vec<CacheEntrySummary>,
+    /// Entries matching the query across all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Request model for invalidating cached translations under a path prefix
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CacheInvalidateRequest {
+    /// Entries whose path starts with this are deleted
+    pub path_prefix: String,
+    /// Queue background batch jobs translating the deleted entries' originals again
+    #[serde(default)]
+    pub retranslate: bool,
+}
+
+/// Outcome of invalidating cached translations under a path prefix
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CacheInvalidateResponse {
+    pub deleted: usize,
+    /// Background batch jobs retranslating the deleted entries, one per language pair
+    pub jobs: Vec<String>,
+    /// Files queued for retranslation
+    pub queued_files: usize,
+    /// Deleted entries that cannot be retranslated because their original was not stored
+    pub missing_sources: usize,
+}
+
+/// Request model for retiring cached translations of other translator versions
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RetireVersionsRequest {
+    /// `purge` or `mark`; `keep` changes nothing
+    pub policy: StaleVersionPolicy,
+}
+
+/// Outcome of retiring cached translations of other translator versions
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RetireVersionsResponse {
+    pub policy: StaleVersionPolicy,
+    /// The version whose entries were kept
+    pub translator_version: String,
+    /// Entries deleted or newly marked, across all tenants
+    pub entries: i64,
+}
+
+/// Request model for running the cache cleanup now
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct CleanupRequest {
+    /// Clear entries not accessed for this many days; `CACHE_STALE_DAYS` when absent
+    pub stale_days: Option<i64>,
+}
+
+/// Outcome of a cache cleanup
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CleanupResult {
+    pub stale_days: i64,
+    /// Cache entries not accessed for `stale_days`, across all tenants
+    pub removed_entries: i64,
+    /// Blobs of `BLOB_STORE` no cache entry references any more
+    pub removed_blobs: usize,
+    /// Completed background batch jobs past `BATCH_JOB_RETENTION_DAYS`
+    pub removed_batch_jobs: u64,
+}
+
+/// A translation queued for human review
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReviewItem {
+    pub id: i64,
     pub cache_key: String,
-    pub content_hash: String,
     pub path: String,
-    pub translated_content: String,
-    pub translated_hash: String,
+    pub content_hash: String,
+    pub reason: String,
+    /// "auto" for heuristic flags, "manual" for API flags
+    pub source: String,
+    /// "pending" or "resolved"
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
     pub created_at: DateTime<Utc>,
-    pub accessed_at: DateTime<Utc>,
-    pub hit_count: i64,
-    #[serde(default)]
-    pub metadata: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Request model for manually flagging a cached translation
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateReviewRequest {
+    pub cache_key: String,
+    pub reason: String,
 }
 
-/// Statistics about the cache
-#[derive(Debug, Serialize)]
-pub struct CacheStats {
-    pub total_entries: i64,
-    pub total_size_bytes: i64,
+/// Request model for resolving a review
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ResolveReviewRequest {
+    /// Base64 encoded corrected translation that replaces the cache entry
+    pub translated_content: Option<String>,
+    pub note: Option<String>,
+}
+
+/// Query parameters for listing reviews
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReviewQuery {
+    /// Status filter; defaults to "pending", "all" lists every review
+    pub status: Option<String>,
+}
+
+/// One recorded translate call
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    /// Fingerprint of the API key used, "anonymous" without auth or "cli" for the CLI
+    pub api_key_id: String,
+    pub tenant: String,
+    pub path: String,
+    pub content_hash: String,
+    pub source_language: String,
+    pub target_language: String,
+    pub cached: bool,
+    pub duration_ms: f64,
+    pub cost_usd: f64,
+    /// "success", "shared" (answered by an identical in-flight request) or "error"
+    pub outcome: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub oldest_entry: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+/// Query parameters for listing audit entries
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    /// Only entries recorded at or after this time
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// A page of audit entries
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditPage {
+    pub entries: Vec<AuditEntry>,
+    /// Entries matching the query across all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Query parameters for listing recent request payloads
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RecentRequestsQuery {
+    /// At most this many exchanges, newest first; all kept ones by default
+    pub limit: Option<usize>,
+}
+
+/// One API exchange kept by the debug payload log, with content and credentials redacted
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PayloadRecord {
+    /// Increases with every recorded exchange since startup
+    pub id: u64,
+    pub timestamp: DateTime<Utc>,
+    pub request_id: Option<String>,
+    pub method: String,
+    /// Path and query string
+    pub path: String,
+    pub status: u16,
+    pub duration_ms: f64,
+    pub request_headers: serde_json::Value,
+    /// Redacted JSON body, or a description of a body of another type
+    pub request_body: Option<serde_json::Value>,
+    pub response_body: Option<serde_json::Value>,
+}
+
+/// Exchanges kept by the debug payload log
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecentRequests {
+    /// Whether DEBUG_PAYLOAD_LOG currently records exchanges
+    pub enabled: bool,
+    pub requests: Vec<PayloadRecord>,
+}
+
+/// Cache and quota usage of one tenant
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TenantSummary {
+    pub name: String,
+    /// Whether the tenant has its own API key in TENANT_API_KEYS
+    pub has_api_key: bool,
+    pub cache_entries: i64,
+    pub cache_size_bytes: i64,
+    pub cache_hits: i64,
+    /// Successful uncached translations since midnight UTC
+    pub translations_today: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub newest_entry: Option<DateTime<Utc>>,
-    pub total_hits: i64,
-    pub total_misses: i64,
+    pub daily_quota: Option<u64>,
+}
+
+/// Service-wide usage against the token and cost budgets
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BudgetStatus {
+    /// Current UTC day, `YYYY-MM-DD`
+    pub day: String,
+    /// Tokens of fresh translations today
+    pub tokens_today: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_token_budget: Option<u64>,
+    /// Current UTC month, `YYYY-MM`
+    pub month: String,
+    /// Estimated cost of fresh translations this month
+    pub cost_this_month_usd: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_cost_budget_usd: Option<f64>,
+    /// Whether new uncached translations are being rejected
+    pub exhausted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_reset_at: Option<String>,
+}
+
+/// Tenants with an API key, cached entries or translations today
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TenantList {
+    pub tenants: Vec<TenantSummary>,
+}
+
+/// Outcome of a cache database backup
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BackupResult {
+    pub path: String,
+    pub size_bytes: u64,
+    /// Older backups deleted to respect the retention count
+    pub removed: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uploaded_to: Option<String>,
+    /// Why the upload failed; the local backup is kept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_error: Option<String>,
+}
+
+/// Request model for saving a prompt template; `*` matches anything
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SavePromptRequest {
+    #[serde(default = "any")]
+    pub document_type: String,
+    #[serde(default = "any")]
+    pub source_language: String,
+    #[serde(default = "any")]
+    pub target_language: String,
+    /// Prompt text; `{source}` and `{target}` are replaced by language names
+    pub template: String,
+}
+
+fn any() -> String {
+    ANY.to_string()
+}
+
+/// Built-in prompt template and the saved overrides
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PromptList {
+    pub builtin: PromptTemplate,
+    pub templates: Vec<PromptTemplate>,
 }
 
 /// Health check response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct HealthResponse {
     pub status: String,
     pub version: String,
     pub cache_connected: bool,
     pub openai_configured: bool,
+    /// Result of the upstream probe, only present when HEALTH_CHECK_UPSTREAM is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openai_reachable: Option<bool>,
+    pub circuit_breaker: CircuitBreakerStatus,
+}
+
+/// Readiness check response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadyResponse {
+    pub status: String,
 }
 
 /// Root endpoint response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct RootResponse {
     pub service: String,
     pub version: String,
     pub description: String,
     pub endpoints: serde_json::Value,
+    pub limits: ServiceLimits,
+}
+
+/// Size limits of requests, for clients splitting their work to fit
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ServiceLimits {
+    /// Largest request body, except archive uploads
+    pub max_request_bytes: usize,
+    pub max_archive_bytes: usize,
+    /// Most files of a batch request
+    pub max_batch_files: usize,
+    /// Largest total size of the files' content of a batch request, as sent
+    pub max_batch_total_bytes: usize,
+    /// Most files a GitHub translation may match
+    pub max_github_files: usize,
+}
+
+/// Body of every error response
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub detail: String,
+    pub code: ErrorCode,
+    /// Problems with individual fields of the request body, for `VALIDATION_FAILED`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// A problem with one field of a request body
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct FieldError {
+    /// Path of the field, e.g. `files[2].content_hash`; empty for the body as a whole
+    pub field: String,
+    pub message: String,
+}
+
+/// Machine-readable kind of an error, for clients deciding whether and when to retry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// The request is malformed or has invalid options
+    InvalidRequest,
+    /// The request body is not valid JSON, does not match the request type or has
+    /// invalid fields; `errors` lists them
+    ValidationFailed,
+    /// Document content is not valid base64 or UTF-8
+    InvalidContent,
+    /// The request is well-formed but cannot be processed as it is
+    Unprocessable,
+    /// Missing or invalid API key
+    Unauthorized,
+    /// The caller may not use this endpoint or connect from its address
+    Forbidden,
+    NotFound,
+    Conflict,
+    /// The request body is over the size limit
+    PayloadTooLarge,
+    /// A single line is too large to fit an upstream request
+    InputTooLarge,
+    /// The upstream model refused the input as longer than its context window
+    ContextLengthExceeded,
+    /// The tenant's daily translation quota is used up; retry tomorrow
+    QuotaExceeded,
+    /// The service's token or cost budget is used up; cached translations are still served
+    BudgetExceeded,
+    /// The upstream call took longer than the timeout; worth retrying
+    Timeout,
+    /// The upstream rate limited the service until retries ran out; retry later
+    RateLimited,
+    /// The upstream is overloaded, or the circuit breaker is open after repeated
+    /// upstream failures; retry later
+    UpstreamUnavailable,
+    /// The upstream refused the service's credentials; retrying does not help
+    UpstreamAuthFailed,
+    /// The upstream failed or returned an unusable response
+    UpstreamError,
+    /// The GitHub API failed
+    GitHubError,
+    /// A failure within the service itself
+    Internal,
+}
+
+/// A document pushed into a WebSocket translation session
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SessionRequest {
+    /// Chosen by the client; events about the document carry it back
+    pub id: String,
+    #[serde(flatten)]
+    pub request: TranslateRequest,
+}
+
+/// What a WebSocket translation session tells the client about its documents
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SessionEvent {
+    /// The document was accepted and waits for the documents before it
+    Queued { id: String },
+    /// Upstream call `chunk` of about `total` for the document has started
+    Translating { id: String, chunk: usize, total: usize },
+    /// The translation, as `POST /api/translate` would have returned it
+    Done { id: String, result: Box<TranslateResponse> },
+    /// The document failed, or a message could not be read when `id` is absent
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        detail: String,
+    },
 }
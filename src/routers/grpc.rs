@@ -0,0 +1,326 @@
+//! gRPC API, served on GRPC_PORT next to the HTTP API.
+//!
+//! Implements the `skillts.v1.Translator` service of `proto/skillts.proto` on the
+//! same [`AppState`] as the HTTP routes, so both share the cache, quotas, audit log
+//! and upstream scheduler. Calls authenticate like HTTP requests through an
+//! interceptor reading the `authorization` and `x-tenant` metadata.
+
+use std::pin::Pin;
+
+use axum::http::StatusCode;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::error::AppError;
+use crate::models::schemas::{ContentEncoding, ErrorCode, TranslateOptions, TranslateRequest, TranslateResponse};
+use crate::routers::translate::{authenticate, translate_single, ApiKeyId, AppState, Tenant, TENANT_HEADER};
+use crate::routers::validation;
+use crate::services::ip_filter;
+use skillts_core::prompt::DEFAULT_DOCUMENT_TYPE;
+use skillts_core::scheduler::Priority;
+
+/// Code generated from `proto/skillts.proto`
+pub mod proto {
+    tonic::include_proto!("skillts.v1");
+}
+
+use proto::batch_result::Outcome;
+use proto::translator_server::{Translator, TranslatorServer};
+
+/// Batch results buffered for a client that reads them slower than they are produced
+const BATCH_RESULT_BUFFER: usize = 16;
+
+/// The translator service with its authentication interceptor. Messages are limited
+/// to `MAX_REQUEST_BYTES` like HTTP bodies; larger ones fail with `RESOURCE_EXHAUSTED`.
+pub fn service(state: AppState) -> InterceptedService<TranslatorServer<GrpcTranslator>, Auth> {
+    let server = TranslatorServer::new(GrpcTranslator { state: state.clone() })
+        .max_decoding_message_size(state.settings().max_request_bytes);
+    InterceptedService::new(server, Auth { state })
+}
+
+/// Authenticates calls and attaches the caller's `ApiKeyId` and `Tenant`
+#[derive(Clone)]
+pub struct Auth {
+    state: AppState,
+}
+
+impl Interceptor for Auth {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let settings = self.state.settings();
+        if ip_filter::enabled(&settings) {
+            let forwarded_for = request
+                .metadata()
+                .get_all(ip_filter::FORWARDED_FOR_HEADER)
+                .iter()
+                .filter_map(|value| value.to_str().ok());
+            let client = ip_filter::client_address(
+                request.remote_addr().map(|address| address.ip()),
+                forwarded_for,
+                &settings.trusted_proxies,
+            );
+            if !ip_filter::permits(&settings, client) {
+                return Err(Status::permission_denied("Client address not allowed"));
+            }
+        }
+
+        let metadata = request.metadata();
+        let requested_tenant = metadata
+            .get(TENANT_HEADER)
+            // Not visible ASCII, so not a valid tenant either
+            .map(|value| value.to_str().unwrap_or_default());
+        let authorization = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok());
+
+        let caller = authenticate(&self.state, authorization, requested_tenant).map_err(
+            |(status, detail)| match status {
+                StatusCode::UNAUTHORIZED => Status::unauthenticated(detail),
+                StatusCode::FORBIDDEN => Status::permission_denied(detail),
+                _ => Status::invalid_argument(detail),
+            },
+        )?;
+
+        request.extensions_mut().insert(caller.api_key);
+        request.extensions_mut().insert(caller.tenant);
+        Ok(request)
+    }
+}
+
+impl From<AppError> for Status {
+    fn from(error: AppError) -> Self {
+        let message = error.to_string();
+        match error.code() {
+            ErrorCode::InvalidRequest
+            | ErrorCode::ValidationFailed
+            | ErrorCode::InvalidContent
+            | ErrorCode::Unprocessable
+            | ErrorCode::PayloadTooLarge
+            | ErrorCode::InputTooLarge
+            | ErrorCode::ContextLengthExceeded => Status::invalid_argument(message),
+            ErrorCode::Unauthorized => Status::unauthenticated(message),
+            ErrorCode::Forbidden => Status::permission_denied(message),
+            ErrorCode::NotFound => Status::not_found(message),
+            ErrorCode::Conflict => Status::failed_precondition(message),
+            ErrorCode::QuotaExceeded | ErrorCode::BudgetExceeded | ErrorCode::RateLimited => {
+                Status::resource_exhausted(message)
+            }
+            ErrorCode::GitHubError | ErrorCode::UpstreamUnavailable => Status::unavailable(message),
+            ErrorCode::Timeout | ErrorCode::UpstreamAuthFailed | ErrorCode::UpstreamError | ErrorCode::Internal => {
+                Status::internal(message)
+            }
+        }
+    }
+}
+
+/// Caller attached by the [`Auth`] interceptor
+fn caller<T>(request: &Request<T>) -> Result<(ApiKeyId, Tenant), Status> {
+    let extensions = request.extensions();
+    match (extensions.get::<ApiKeyId>(), extensions.get::<Tenant>()) {
+        (Some(api_key), Some(tenant)) => Ok((api_key.clone(), tenant.clone())),
+        _ => Err(Status::unauthenticated("Missing caller")),
+    }
+}
+
+/// The HTTP request a gRPC request stands for; content travels as plain text both ways.
+/// Empty languages are left to the configured ones, as in an HTTP request without them.
+fn translate_request(request: proto::TranslateRequest) -> TranslateRequest {
+    let or_default = |value: String, default: &str| {
+        if value.is_empty() {
+            default.to_string()
+        } else {
+            value
+        }
+    };
+    let given = |value: String| Some(value).filter(|value| !value.is_empty());
+    let options = request.options.unwrap_or_default();
+
+    TranslateRequest {
+        content: request.content,
+        content_encoding: ContentEncoding::Plain,
+        path: request.path,
+        content_hash: Some(request.content_hash).filter(|hash| !hash.is_empty()),
+        options: Some(TranslateOptions {
+            source_language: given(options.source_language),
+            target_language: given(options.target_language),
+            document_type: or_default(options.document_type, DEFAULT_DOCUMENT_TYPE),
+            translate_code_comments: options.translate_code_comments,
+            response_encoding: ContentEncoding::Plain,
+            timeout_seconds: options.timeout_seconds,
+            max_retries: options.max_retries,
+            quality_check: options.quality_check,
+            verify_roundtrip: options.verify_roundtrip,
+            force: options.force,
+            ..TranslateOptions::default()
+        }),
+        related_files: Vec::new(),
+    }
+}
+
+impl From<TranslateResponse> for proto::TranslateResponse {
+    fn from(response: TranslateResponse) -> Self {
+        Self {
+            translated_content: response.translated_content,
+            content_hash: response.content_hash,
+            translated_hash: response.translated_hash,
+            cached: response.cached,
+            metadata_json: response.metadata.to_stored().to_string(),
+            signature_json: response
+                .signature
+                .map(|signature| serde_json::json!(signature).to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// `skillts.v1.Translator` on the shared application state
+pub struct GrpcTranslator {
+    state: AppState,
+}
+
+#[tonic::async_trait]
+impl Translator for GrpcTranslator {
+    async fn translate(
+        &self,
+        request: Request<proto::TranslateRequest>,
+    ) -> Result<Response<proto::TranslateResponse>, Status> {
+        let (api_key, tenant) = caller(&request)?;
+        let request = translate_request(request.into_inner());
+        validation::check_request(&request)?;
+
+        let response =
+            translate_single(&self.state, &api_key, &tenant, &request, Priority::Interactive, None).await?;
+        Ok(Response::new(response.into()))
+    }
+
+    type TranslateBatchStream =
+        Pin<Box<dyn Stream<Item = Result<proto::BatchResult, Status>> + Send + 'static>>;
+
+    /// Translate files one after another as they arrive at bulk priority. A failed file
+    /// is answered with its error and the stream goes on; when the client goes away the
+    /// translation in progress is dropped.
+    async fn translate_batch(
+        &self,
+        request: Request<Streaming<proto::TranslateRequest>>,
+    ) -> Result<Response<Self::TranslateBatchStream>, Status> {
+        let (api_key, tenant) = caller(&request)?;
+        let mut files = request.into_inner();
+        let state = self.state.clone();
+        let (sender, receiver) = mpsc::channel(BATCH_RESULT_BUFFER);
+
+        tokio::spawn(async move {
+            while let Some(file) = files.next().await {
+                let file = match file {
+                    Ok(file) => file,
+                    Err(status) => {
+                        let _ = sender.send(Err(status)).await;
+                        break;
+                    }
+                };
+                let path = file.path.clone();
+                let request = translate_request(file);
+
+                let outcome = match validation::check_request(&request) {
+                    Ok(()) => tokio::select! {
+                        outcome = translate_single(&state, &api_key, &tenant, &request, Priority::Bulk, None) => outcome,
+                        () = sender.closed() => break,
+                    },
+                    Err(e) => Err(e),
+                };
+                let outcome = match outcome {
+                    Ok(response) => Outcome::Result(response.into()),
+                    Err(e) => Outcome::Error(e.to_string()),
+                };
+                let result = proto::BatchResult {
+                    path,
+                    outcome: Some(outcome),
+                };
+                if sender.send(Ok(result)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+
+    async fn cache_stats(
+        &self,
+        request: Request<proto::CacheStatsRequest>,
+    ) -> Result<Response<proto::CacheStatsResponse>, Status> {
+        let (_, tenant) = caller(&request)?;
+        let ttl = std::time::Duration::from_secs(self.state.settings().cache_stats_ttl_seconds);
+        let (stats, _) = self
+            .state
+            .cache
+            .get_recent_stats(&tenant.0, ttl)
+            .await
+            .map_err(AppError::from)?;
+        let timestamp = |time: Option<chrono::DateTime<chrono::Utc>>| {
+            time.map(|time| time.to_rfc3339()).unwrap_or_default()
+        };
+
+        Ok(Response::new(proto::CacheStatsResponse {
+            total_entries: stats.total_entries,
+            total_size_bytes: stats.total_size_bytes,
+            oldest_entry: timestamp(stats.oldest_entry),
+            newest_entry: timestamp(stats.newest_entry),
+            total_hits: stats.total_hits,
+            total_misses: stats.total_misses,
+            memory_hits: stats.memory_hits,
+            store_hits: stats.store_hits,
+            memory_hit_rate: stats.memory_hit_rate,
+            store_hit_rate: stats.store_hit_rate,
+            total_paths: stats.total_paths,
+            deduplicated_paths: stats.deduplicated_paths,
+            lookups_24h: stats.lookups_24h,
+            hit_rate_24h: stats.hit_rate_24h,
+            lookups_7d: stats.lookups_7d,
+            hit_rate_7d: stats.hit_rate_7d,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_request_fills_defaults() {
+        let request = translate_request(proto::TranslateRequest {
+            path: "skills/demo/SKILL.md".to_string(),
+            content: "# Demo".to_string(),
+            content_hash: String::new(),
+            options: Some(proto::TranslateOptions {
+                target_language: "ja".to_string(),
+                max_retries: Some(1),
+                ..Default::default()
+            }),
+        });
+
+        let options = request.options.unwrap();
+        assert_eq!(request.content_hash, None);
+        assert_eq!(request.content_encoding, ContentEncoding::Plain);
+        assert_eq!(options.response_encoding, ContentEncoding::Plain);
+        assert_eq!(options.source_language, None);
+        assert_eq!((options.target_language.as_deref(), options.max_retries), (Some("ja"), Some(1)));
+        assert_eq!(options.document_type, DEFAULT_DOCUMENT_TYPE);
+    }
+
+    #[test]
+    fn test_requests_follow_http_rules() {
+        let request = translate_request(proto::TranslateRequest {
+            path: "../SKILL.md".to_string(),
+            content: "# Demo".to_string(),
+            content_hash: "md5:abc".to_string(),
+            options: None,
+        });
+        let status = Status::from(validation::check_request(&request).unwrap_err());
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(status.message().contains("path: path must be relative"), "{}", status.message());
+        assert!(status.message().contains("content_hash: content_hash must start with sha256:"));
+    }
+}
@@ -1 +1,5 @@
+pub mod grpc;
+pub mod openapi;
 pub mod translate;
+pub mod validation;
+pub mod ws;
@@ -0,0 +1,130 @@
+//! OpenAPI document of the HTTP API.
+//!
+//! Generated from the `utoipa` annotations on the handlers in [`translate`] and [`ws`] and the
+//! schemas in [`crate::models::schemas`]. Served without auth at `/api/openapi.json`,
+//! with a Swagger UI at `/api/docs` for trying requests out.
+
+use axum::Router;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::models::schemas::{SessionEvent, SessionRequest};
+use crate::routers::{translate, ws};
+
+/// Path of the OpenAPI document
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+
+/// Path of the Swagger UI
+pub const DOCS_PATH: &str = "/api/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Skill Translator",
+        description = "Translation service for SKILL.md files"
+    ),
+    paths(
+        translate::root,
+        translate::health_check,
+        translate::ready_check,
+        translate::get_public_key,
+        translate::metrics,
+        translate::translate_file,
+        translate::translate_batch,
+        translate::get_batch_job,
+        translate::get_job,
+        translate::cancel_job,
+        translate::preview_translation,
+        translate::check_translation,
+        translate::translate_delta,
+        translate::translate_archive,
+        translate::translate_github,
+        translate::validate_translation,
+        translate::get_cache_stats,
+        translate::get_detailed_cache_stats,
+        translate::list_cache_entries,
+        translate::get_cache_entry,
+        translate::get_cache_entry_diff,
+        translate::delete_cache_entry,
+        translate::invalidate_cache,
+        translate::clear_cache,
+        translate::clear_expired_cache,
+        translate::flush_cache_hits,
+        translate::list_reviews,
+        translate::create_review,
+        translate::resolve_review,
+        translate::reload_config,
+        translate::run_backup,
+        translate::run_cleanup,
+        translate::compact_cache,
+        translate::retire_cache_versions,
+        translate::list_audit,
+        translate::list_recent_requests,
+        translate::list_tenants,
+        translate::list_models,
+        translate::get_budget,
+        translate::reset_budget,
+        translate::list_prompts,
+        translate::get_prompt,
+        translate::save_prompt,
+        translate::delete_prompt,
+        translate::list_prompt_versions,
+        ws::translate_session,
+    ),
+    // Carried in WebSocket messages rather than referenced by a path
+    components(schemas(SessionRequest, SessionEvent)),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    tags(
+        (name = "translate", description = "Translate, preview and validate documents"),
+        (name = "cache", description = "Cached translations of the caller's tenant"),
+        (name = "reviews", description = "Human review queue, operator key only"),
+        (name = "prompts", description = "Prompt templates, operator key only"),
+        (name = "admin", description = "Service administration, operator key only"),
+        (name = "health", description = "Service information and probes, no auth"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the bearer scheme of LOCAL_API_BEARER and TENANT_API_KEYS.
+/// Without either configured, requests are accepted without it.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+/// Routes serving the document and the Swagger UI
+pub fn router() -> Router {
+    SwaggerUi::new(DOCS_PATH)
+        .url(OPENAPI_PATH, ApiDoc::openapi())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_covers_routes_and_schemas() {
+        let document = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+        let paths = document["paths"].as_object().unwrap();
+        assert!(paths.contains_key("/api/translate"));
+        assert!(paths["/api/admin/prompts/{name}"].get("put").is_some());
+        assert_eq!(paths["/api/health"]["get"]["security"], serde_json::json!([{}]));
+
+        let schemas = &document["components"]["schemas"];
+        for schema in ["TranslateRequest", "ErrorResponse", "AnchorMode", "CacheGroupStats"] {
+            assert!(schemas.get(schema).is_some(), "missing schema {}", schema);
+        }
+        assert_eq!(document["components"]["securitySchemes"]["bearer"]["scheme"], "bearer");
+    }
+}
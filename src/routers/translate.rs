@@ -3,103 +3,744 @@
 //! Fully compatible with Python version's API endpoints.
 
 use axum::{
-    extract::State,
-    http::{header, Request, StatusCode},
+    body::{Body, Bytes},
+    extract::{ConnectInfo, Extension, Multipart, OriginalUri, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
     Json,
 };
+use arc_swap::ArcSwap;
+use http_body_util::LengthLimitError;
 use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use crate::config::get_settings;
+use crate::config::{valid_tenant, ConfigError, Settings};
 use crate::error::AppError;
 use crate::models::schemas::{
-    BatchTranslateRequest, BatchTranslateResponse, CacheStats, FileTranslationResult,
-    HealthResponse, RootResponse, TranslateRequest, TranslateResponse,
+    AuditPage, AuditQuery, BatchJobQuery, BatchJobStatus, BatchSummary, BatchTranslateRequest, BatchTranslateResponse, CacheEntry, CacheEntryPage, CacheEntryDiffQuery, CacheEntryQuery, CacheInvalidateRequest, CacheInvalidateResponse, CacheListQuery, CacheStats, CacheStatsQuery, TranslationResponseMetadata, CleanupRequest, CleanupResult,
+    ContentEncoding, CreateReviewRequest, LongLineMode, LongLines, ErrorCode, ErrorResponse, DeltaSection, DeltaTranslateRequest, DeltaTranslateResponse,
+    DetailedCacheStats, FieldError, FileToTranslate, FileTranslationResult, GitHubTranslateRequest,
+    GitHubTranslateResponse, HealthResponse, PublicKeyResponse, PreviewField, PromptList, PreviewRequest, PreviewResponse,
+    PayloadRecord, ReadyResponse, RecentRequests, RecentRequestsQuery, ResolveReviewRequest, RetireVersionsRequest, RetireVersionsResponse, ReviewItem, ReviewQuery, RootResponse, SavePromptRequest, ServiceLimits,
+    BackupResult, BudgetStatus, CompactStats, TenantList, TenantSummary, TranslateOptions, TranslationScope,
+    OutputMode, TranslateCheckRequest, TranslateCheckResponse, TranslateRequest, TranslateResponse, ValidateRequest, ValidationReport,
 };
-use crate::services::cache::TranslationCache;
-use crate::services::translator::{decode_content, encode_content, Translator};
+use crate::routers::validation::ValidJson;
+use crate::services::archive::{self, ArchiveFormat};
+use crate::services::audit::{AuditLog, AuditRecord};
+use crate::services::backup::Backups;
+use crate::services::batch::{BatchJob, BatchJobs};
+use crate::services::budget::Budget;
+use crate::services::cache::batch::BatchCache;
+use crate::services::cache::{entry_version, TranslationCache};
+use crate::services::github::{self, GitHubClient, MAX_GITHUB_FILES};
+use crate::services::idempotency::{IdempotencyStore, KeyReused};
+use crate::services::ip_filter;
+use crate::services::payload_log::{self, PayloadLog};
+use crate::services::pipeline::{handle_long_lines, Pipeline, PreparedFile, Translated};
+use crate::services::prompts::PromptStore;
+use crate::services::signing::ResponseSigner;
+use crate::services::sources::SourceStore;
+use crate::services::terminology::{self as terminology_memory, TerminologyStore};
+use crate::services::review::{ReviewQueue, STATUS_PENDING};
+use crate::services::translator::{
+    decode_content, encode_content, DeltaTranslation, ProgressSink, Provider, TranslationMetadata,
+    TranslationProfile, Translator, DEFAULT_TENANT,
+};
+use crate::services::validate;
+use crate::services::warmer::{self, WarmerRun};
+use skillts_core::bilingual;
+use skillts_core::diff;
+use skillts_core::keep_terms::MAX_KEEP_TERMS;
+use skillts_core::language;
+use skillts_core::models::CacheFilter;
+use skillts_core::prompt::PromptTemplate;
+use skillts_core::scheduler::Priority;
+use skillts_core::terminology::{self, Term};
+
+/// Timeout for the optional upstream probe in the health check
+const UPSTREAM_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Header naming the tenant of a request made with the operator key or without auth
+pub const TENANT_HEADER: &str = "x-tenant";
+
+/// Route prefixes only the operator key may use; tenant keys see their own data only
+const OPERATOR_ROUTES: [&str; 2] = ["/admin", "/reviews"];
+
+/// Header carrying the per-request correlation id
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Path of the payload log listing within the API, which is not logged itself
+const RECENT_REQUESTS_PATH: &str = "/admin/recent-requests";
+
+/// Header carrying a client-chosen idempotency key
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
 
-/// Maximum line length before filtering
-const MAX_LINE_LENGTH: usize = 5000;
+/// Response header set when the response was shared with or replayed from another request
+const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Media type of a batch response streamed one JSON object per line
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Lines of a streamed batch buffered for a client that reads them slower than they are produced
+const BATCH_STREAM_BUFFER: usize = 16;
+
+/// Audit entries returned per page by default and at most
+const DEFAULT_AUDIT_PAGE_SIZE: i64 = 100;
+const MAX_AUDIT_PAGE_SIZE: i64 = 1000;
+
+/// Page sizes when browsing cache entries
+const DEFAULT_CACHE_PAGE_SIZE: i64 = 50;
+const MAX_CACHE_PAGE_SIZE: i64 = 500;
 
 /// Application state shared across handlers
 #[derive(Clone)]
 pub struct AppState {
     pub translator: Arc<Translator>,
     pub cache: Arc<TranslationCache>,
+    pub reviews: Arc<ReviewQueue>,
+    pub audit: Arc<AuditLog>,
+    pub prompts: Arc<PromptStore>,
+    pub backups: Arc<Backups>,
+    pub batch_jobs: Arc<BatchJobs>,
+    pub budget: Arc<Budget>,
+    pub terminology: Arc<TerminologyStore>,
+    pub sources: Arc<SourceStore>,
+    pub idempotency: Arc<IdempotencyStore<StoredResponse>>,
+    /// Translations currently running, by cache key
+    pub in_flight: Arc<IdempotencyStore<SharedTranslation>>,
+    /// Current settings; swapped in place when the configuration is reloaded
+    pub settings: Arc<ArcSwap<Settings>>,
     pub api_bearer: String,
+    pub readiness: Arc<Readiness>,
+    /// Recent redacted exchanges, recorded while DEBUG_PAYLOAD_LOG is on
+    pub payload_log: Arc<PayloadLog>,
 }
 
-/// Auth middleware for API endpoints
-pub async fn auth_middleware(
+impl AppState {
+    /// Snapshot of the current settings
+    pub fn settings(&self) -> Arc<Settings> {
+        self.settings.load_full()
+    }
+
+    /// Reload settings from the environment, .env and config file.
+    /// Handlers see the new values on their next request and the translator picks up
+    /// its reloadable settings; the cache pool and in-flight translations are untouched.
+    /// Invalid configuration is rejected and the current settings stay in effect.
+    pub fn reload_settings(&self) -> Result<Arc<Settings>, ConfigError> {
+        let current = self.settings();
+        let settings = Arc::new(Settings::load(current.config_file.as_deref())?);
+        self.translator.reconfigure(&settings.translator_config());
+        self.settings.store(settings.clone());
+
+        tracing::info!(
+            "Configuration reloaded: model {}, {} concurrent translations, {}s timeout",
+            settings.openai_model,
+            settings.max_concurrent_translations,
+            settings.translation_timeout_seconds
+        );
+        Ok(settings)
+    }
+
+    /// The translate-and-cache steps with these settings
+    pub fn pipeline<'a>(&'a self, settings: &'a Settings) -> Pipeline<'a> {
+        Pipeline {
+            translator: &self.translator,
+            cache: &self.cache,
+            settings,
+        }
+    }
+
+    /// Load the saved prompt templates into the translator, none with `PROMPT_TEMPLATES` off
+    pub async fn reload_prompts(&self) -> Result<(), AppError> {
+        let templates = if self.settings().prompt_templates {
+            self.prompts.list().await?
+        } else {
+            Vec::new()
+        };
+        tracing::info!("Loaded {} prompt templates", templates.len());
+        self.translator.set_prompt_templates(templates);
+        Ok(())
+    }
+}
+
+/// Service lifecycle flags used by the readiness endpoint
+#[derive(Debug, Default)]
+pub struct Readiness {
+    ready: AtomicBool,
+    draining: AtomicBool,
+}
+
+impl Readiness {
+    /// Mark the service as ready to accept traffic
+    pub fn mark_ready(&self) {
+        self.ready.store(true, Ordering::SeqCst);
+    }
+
+    /// Mark the service as draining for shutdown
+    pub fn mark_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    /// Current readiness status: "ready", "initializing" or "draining"
+    pub fn status(&self) -> &'static str {
+        if self.draining.load(Ordering::SeqCst) {
+            "draining"
+        } else if self.ready.load(Ordering::SeqCst) {
+            "ready"
+        } else {
+            "initializing"
+        }
+    }
+}
+
+/// Identifies who made a request in the audit log without storing the API key
+#[derive(Debug, Clone)]
+pub struct ApiKeyId(pub String);
+
+impl ApiKeyId {
+    /// Requests made while authentication is disabled
+    pub fn anonymous() -> Self {
+        Self("anonymous".to_string())
+    }
+
+    /// Files translated ahead of requests by the cache warmer
+    pub fn warmer() -> Self {
+        Self("warmer".to_string())
+    }
+
+    /// Short fingerprint of a bearer token
+    fn for_token(token: &str) -> Self {
+        let hash = Translator::compute_hash(token);
+        let hex = hash.trim_start_matches("sha256:");
+        Self(format!("key-{}", &hex[..12]))
+    }
+}
+
+/// Tenant a request belongs to; scopes its cache entries, statistics and quota
+#[derive(Debug, Clone)]
+pub struct Tenant(pub String);
+
+impl Default for Tenant {
+    fn default() -> Self {
+        Self(DEFAULT_TENANT.to_string())
+    }
+}
+
+/// Who a request is made by, as established by [`authenticate`]
+pub struct Caller {
+    pub api_key: ApiKeyId,
+    pub tenant: Tenant,
+    /// Authenticated with a key from TENANT_API_KEYS rather than the operator key
+    pub tenant_key: bool,
+}
+
+/// Resolve the caller from the Authorization and `X-Tenant` header values.
+/// Shared by the HTTP and gRPC APIs; errors carry the HTTP status and detail.
+pub fn authenticate(
+    state: &AppState,
+    authorization: Option<&str>,
+    requested_tenant: Option<&str>,
+) -> Result<Caller, (StatusCode, &'static str)> {
+    let settings = state.settings();
+
+    let requested_tenant = match requested_tenant {
+        Some(tenant) if valid_tenant(tenant) => Some(tenant.to_string()),
+        Some(_) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "X-Tenant must be 1 to 64 letters, digits, '-' or '_'",
+            ))
+        }
+        None => None,
+    };
+
+    // Skip auth if no key is configured
+    if state.api_bearer.is_empty() && settings.tenant_api_keys.is_empty() {
+        return Ok(Caller {
+            api_key: ApiKeyId::anonymous(),
+            tenant: requested_tenant.map(Tenant).unwrap_or_default(),
+            tenant_key: false,
+        });
+    }
+
+    let token = match authorization {
+        Some(value) => value
+            .strip_prefix("Bearer ")
+            .ok_or((StatusCode::UNAUTHORIZED, "Invalid Authorization header format"))?,
+        None => return Err((StatusCode::UNAUTHORIZED, "Missing Authorization header")),
+    };
+
+    let (tenant, tenant_key) = if !state.api_bearer.is_empty() && token == state.api_bearer {
+        (requested_tenant.map(Tenant).unwrap_or_default(), false)
+    } else if let Some((tenant, _)) = settings.tenant_api_keys.iter().find(|(_, key)| key == token) {
+        if requested_tenant.is_some_and(|requested| requested != *tenant) {
+            return Err((StatusCode::FORBIDDEN, "API key does not belong to the requested tenant"));
+        }
+        (Tenant(tenant.clone()), true)
+    } else {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid API key"));
+    };
+
+    Ok(Caller {
+        api_key: ApiKeyId::for_token(token),
+        tenant,
+        tenant_key,
+    })
+}
+
+/// Error response of a request refused by authentication or access control
+fn rejection(status: StatusCode, detail: &str) -> (StatusCode, Json<ErrorResponse>) {
+    let code = match status {
+        StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+        StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+        _ => ErrorCode::InvalidRequest,
+    };
+    let detail = detail.to_string();
+    (status, Json(ErrorResponse { detail, code, errors: Vec::new() }))
+}
+
+/// Refuse clients outside ALLOWED_CIDRS or inside DENIED_CIDRS, before anything else
+/// looks at the request
+pub async fn ip_filter_middleware(
     State(state): State<AppState>,
     request: Request<axum::body::Body>,
     next: Next,
-) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
-    // Skip auth if no bearer is configured
-    if state.api_bearer.is_empty() {
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let settings = state.settings();
+    if !ip_filter::enabled(&settings) {
         return Ok(next.run(request).await);
     }
 
-    // Extract Authorization header
-    let auth_header = request
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip());
+    let forwarded_for = request
         .headers()
-        .get(header::AUTHORIZATION)
-        .and_then(|h| h.to_str().ok());
-
-    match auth_header {
-        Some(header_value) => {
-            // Check if it starts with "Bearer "
-            if let Some(token) = header_value.strip_prefix("Bearer ") {
-                if token == state.api_bearer {
-                    Ok(next.run(request).await)
-                } else {
-                    Err((
-                        StatusCode::UNAUTHORIZED,
-                        Json(json!({ "detail": "Invalid API key" })),
-                    ))
-                }
-            } else {
-                Err((
-                    StatusCode::UNAUTHORIZED,
-                    Json(json!({ "detail": "Invalid Authorization header format" })),
-                ))
+        .get_all(ip_filter::FORWARDED_FOR_HEADER)
+        .iter()
+        .filter_map(|value| value.to_str().ok());
+    let client = ip_filter::client_address(peer, forwarded_for, &settings.trusted_proxies);
+    if !ip_filter::permits(&settings, client) {
+        let client = client.map_or_else(|| "an unknown address".to_string(), |client| client.to_string());
+        tracing::warn!("Refused request from {} to {}", client, request.uri().path());
+        return Err(rejection(StatusCode::FORBIDDEN, "Client address not allowed"));
+    }
+    Ok(next.run(request).await)
+}
+
+/// Auth middleware for API endpoints.
+/// Attaches the caller's `ApiKeyId` for the audit log and its `Tenant`. Tenant keys
+/// from TENANT_API_KEYS belong to their tenant; the operator key and unauthenticated
+/// requests pick one with the `X-Tenant` header.
+pub async fn auth_middleware(
+    State(state): State<AppState>,
+    mut request: Request<axum::body::Body>,
+    next: Next,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let caller = {
+        let header_value =
+            |name: &str| request.headers().get(name).map(|value| value.to_str());
+        let requested_tenant = match header_value(TENANT_HEADER) {
+            Some(Ok(tenant)) => Some(tenant),
+            // Not visible ASCII, so not a valid tenant either
+            Some(Err(_)) => Some(""),
+            None => None,
+        };
+        let authorization = header_value(header::AUTHORIZATION.as_str()).and_then(Result::ok);
+        authenticate(&state, authorization, requested_tenant)
+            .map_err(|(status, detail)| rejection(status, detail))?
+    };
+
+    if caller.tenant_key {
+        let path = request.uri().path().trim_start_matches("/api");
+        if OPERATOR_ROUTES.iter().any(|prefix| path.starts_with(prefix)) {
+            return Err(rejection(StatusCode::FORBIDDEN, "Tenant API keys cannot use this endpoint"));
+        }
+    }
+
+    request.extensions_mut().insert(caller.api_key);
+    request.extensions_mut().insert(caller.tenant);
+    Ok(next.run(request).await)
+}
+
+/// Result of a translation shared between identical in-flight requests
+pub type SharedTranslation = Result<Arc<FreshTranslation>, Arc<AppError>>;
+
+/// A translation just produced by the upstream API
+pub struct FreshTranslation {
+    translated_content: String,
+    translated_hash: String,
+    metadata: TranslationMetadata,
+}
+
+/// Store deduplicating concurrent translations of the same cache key
+pub fn in_flight_translations() -> IdempotencyStore<SharedTranslation> {
+    IdempotencyStore::new(Duration::ZERO, |_| false)
+}
+
+/// Response kept for requests sharing an idempotency key
+#[derive(Clone)]
+pub struct StoredResponse {
+    status: StatusCode,
+    content_type: Option<HeaderValue>,
+    etag: Option<HeaderValue>,
+    body: Bytes,
+}
+
+impl StoredResponse {
+    /// Store for idempotent responses; only successful responses are replayed after completion
+    pub fn store(ttl: Duration) -> IdempotencyStore<Self> {
+        IdempotencyStore::new(ttl, |response| response.status.is_success())
+    }
+
+    fn into_response(self, shared: bool) -> Response {
+        let mut response = Response::new(Body::from(self.body));
+        *response.status_mut() = self.status;
+        if let Some(content_type) = self.content_type {
+            response.headers_mut().insert(header::CONTENT_TYPE, content_type);
+        }
+        if let Some(etag) = self.etag {
+            response.headers_mut().insert(header::ETAG, etag);
+        }
+        if shared {
+            response
+                .headers_mut()
+                .insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+        }
+        response
+    }
+}
+
+/// Idempotency middleware for translate endpoints.
+///
+/// Requests carrying an `Idempotency-Key` header are coalesced per API key, path and
+/// key: concurrent duplicates wait for the first request and receive its response,
+/// and successful responses are replayed to retries for IDEMPOTENCY_TTL_SECONDS.
+/// Reusing a key with a different body is rejected with 422. Streamed (NDJSON) batch
+/// responses are neither buffered nor replayed: they pass through, so results still
+/// arrive as they are produced and a disconnect still stops the work.
+pub async fn idempotency_middleware(
+    State(state): State<AppState>,
+    Extension(api_key): Extension<ApiKeyId>,
+    Extension(tenant): Extension<Tenant>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(next.run(request).await);
+    };
+    if accepts_ndjson(request.headers()) {
+        return Ok(next.run(request).await);
+    }
+    let key = key
+        .to_str()
+        .ok()
+        .map(str::trim)
+        .filter(|key| !key.is_empty() && key.len() <= 255)
+        .ok_or_else(|| {
+            AppError::BadRequest("Idempotency-Key must be 1 to 255 visible characters".to_string())
+        })?;
+    let key = format!("{}:{}:{}:{}", api_key.0, tenant.0, request.uri().path(), key);
+
+    let (parts, body) = request.into_parts();
+    let body = buffer_body(body, state.settings().max_request_bytes).await?;
+    let fingerprint = Translator::compute_hash(&String::from_utf8_lossy(&body));
+    let request = Request::from_parts(parts, Body::from(body));
+
+    let outcome = state
+        .idempotency
+        .run(&key, &fingerprint, || async {
+            let response = next.run(request).await;
+            let (parts, body) = response.into_parts();
+            let body = axum::body::to_bytes(body, usize::MAX).await.unwrap_or_default();
+            StoredResponse {
+                status: parts.status,
+                content_type: parts.headers.get(header::CONTENT_TYPE).cloned(),
+                etag: parts.headers.get(header::ETAG).cloned(),
+                body,
+            }
+        })
+        .await;
+
+    match outcome {
+        Ok((response, shared)) => {
+            if shared {
+                tracing::info!("Idempotency key reused, returning shared response");
             }
+            Ok(response.into_response(shared))
         }
-        None => Err((
-            StatusCode::UNAUTHORIZED,
-            Json(json!({ "detail": "Missing Authorization header" })),
+        Err(KeyReused) => Err(AppError::Unprocessable(
+            "Idempotency-Key was already used with a different request body".to_string(),
         )),
     }
 }
 
-/// Filter lines exceeding MAX_LINE_LENGTH
-fn filter_long_lines(content: &str) -> (String, usize) {
-    let lines: Vec<&str> = content.lines().collect();
-    let mut filtered = Vec::new();
-    let mut removed = 0;
-
-    for line in lines {
-        if line.len() <= MAX_LINE_LENGTH {
-            filtered.push(line);
+/// Read a whole request body, refusing one over `limit` bytes
+async fn buffer_body(body: Body, limit: usize) -> Result<Bytes, AppError> {
+    axum::body::to_bytes(body, limit).await.map_err(|e| {
+        if std::error::Error::source(&e).is_some_and(|e| e.is::<LengthLimitError>()) {
+            AppError::PayloadTooLarge("Request body exceeds the size limit".to_string())
         } else {
-            removed += 1;
+            AppError::BadRequest(format!("Failed to read request body: {}", e))
         }
+    })
+}
+
+/// Keep redacted request and response bodies in the debug payload log while
+/// DEBUG_PAYLOAD_LOG is on. JSON bodies are buffered to be logged; others pass
+/// through untouched and are only described.
+pub async fn payload_log_middleware(
+    State(state): State<AppState>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    let settings = state.settings();
+    // Listing the log would otherwise log the log
+    if !settings.debug_payload_log || request.uri().path() == RECENT_REQUESTS_PATH {
+        return Ok(next.run(request).await);
     }
+    let start_time = Instant::now();
+
+    let (parts, body) = request.into_parts();
+    let content_type = header_str(&parts.headers, header::CONTENT_TYPE);
+    let mut record = PayloadRecord {
+        id: 0,
+        timestamp: chrono::Utc::now(),
+        request_id: header_str(&parts.headers, REQUEST_ID_HEADER),
+        method: parts.method.to_string(),
+        path: parts
+            .extensions
+            .get::<OriginalUri>()
+            .map_or(&parts.uri, |uri| &uri.0)
+            .to_string(),
+        status: 0,
+        duration_ms: 0.0,
+        request_headers: payload_log::redact_headers(&parts.headers),
+        request_body: None,
+        response_body: None,
+    };
+    let request = if is_json(content_type.as_deref()) {
+        let body = buffer_body(body, settings.max_request_bytes).await?;
+        record.request_body = payload_log::redact_body(&body, content_type.as_deref());
+        Request::from_parts(parts, Body::from(body))
+    } else {
+        record.request_body = header_str(&parts.headers, header::CONTENT_LENGTH).map(|length| {
+            json!(format!("[{} bytes of {}]", length, content_type.as_deref().unwrap_or("unknown content")))
+        });
+        Request::from_parts(parts, body)
+    };
 
-    if removed > 0 {
-        (filtered.join("\n"), removed)
+    let response = next.run(request).await;
+    let (parts, body) = response.into_parts();
+    let content_type = header_str(&parts.headers, header::CONTENT_TYPE);
+    let response = if is_json(content_type.as_deref()) {
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap_or_default();
+        record.response_body = payload_log::redact_body(&body, content_type.as_deref());
+        Response::from_parts(parts, Body::from(body))
     } else {
-        (content.to_string(), 0)
+        Response::from_parts(parts, body)
+    };
+
+    record.status = response.status().as_u16();
+    record.duration_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+    state.payload_log.record(record, settings.debug_payload_log_size);
+    Ok(response)
+}
+
+/// A header's value, when it is visible ASCII
+fn header_str(headers: &header::HeaderMap, name: impl header::AsHeaderName) -> Option<String> {
+    headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+}
+
+fn is_json(content_type: Option<&str>) -> bool {
+    content_type.is_some_and(|content_type| content_type.starts_with("application/json"))
+}
+
+/// Add the long line report of the submitted content to response metadata
+fn with_long_lines(
+    mut metadata: TranslationResponseMetadata,
+    long_lines: Option<&LongLines>,
+) -> TranslationResponseMetadata {
+    if let Some(long_lines) = long_lines {
+        metadata.long_lines = Some(long_lines.clone());
+    }
+    metadata
+}
+
+/// Resolve languages, document type, comment translation, anchor mode, kept terms and upstream limits for the
+/// caller's tenant, scheduled at `priority`; per-request options override the configured defaults,
+/// with timeout and retries capped by the server maxima
+fn resolve_profile(
+    settings: &Settings,
+    options: Option<&TranslateOptions>,
+    tenant: &Tenant,
+    priority: Priority,
+) -> Result<TranslationProfile, AppError> {
+    Ok(match options {
+        Some(options) => TranslationProfile {
+            tenant: tenant.0.clone(),
+            source_language: options
+                .source_language
+                .clone()
+                .unwrap_or_else(|| settings.source_language.clone()),
+            target_language: options
+                .target_language
+                .clone()
+                .unwrap_or_else(|| settings.target_language.clone()),
+            document_type: options.document_type.clone(),
+            translate_code_comments: options.translate_code_comments,
+            anchor_mode: options.anchor_mode,
+            scope: translation_scope(options)?,
+            preserve_code_blocks: options.preserve_code_blocks,
+            priority,
+            timeout_seconds: options
+                .timeout_seconds
+                .map(|seconds| seconds.clamp(1, settings.max_request_timeout_seconds)),
+            max_retries: options
+                .max_retries
+                .map(|retries| retries.min(settings.max_request_retries)),
+            quality_check: options.quality_check,
+            verify_roundtrip: options.verify_roundtrip,
+            progress: None,
+            force: options.force,
+            terminology: Vec::new(),
+            keep_terms: keep_terms(settings, &options.keep_terms)?,
+            output_mode: options.output_mode,
+        },
+        None => TranslationProfile {
+            tenant: tenant.0.clone(),
+            priority,
+            keep_terms: settings.keep_terms.clone(),
+            ..TranslationProfile::new(&settings.source_language, &settings.target_language)
+        },
+    })
+}
+
+/// The configured kept terms followed by those of the request
+fn keep_terms(settings: &Settings, requested: &[String]) -> Result<Vec<String>, AppError> {
+    if requested.len() > MAX_KEEP_TERMS {
+        return Err(AppError::BadRequest(format!(
+            "keep_terms lists {} terms, at most {} are allowed",
+            requested.len(),
+            MAX_KEEP_TERMS
+        )));
     }
+    Ok(settings.keep_terms.iter().chain(requested).cloned().collect())
+}
+
+/// The requested scope, narrowed to the body when the frontmatter is to be left untouched
+fn translation_scope(options: &TranslateOptions) -> Result<TranslationScope, AppError> {
+    match (options.scope, options.preserve_frontmatter) {
+        (scope, true) => Ok(scope),
+        (TranslationScope::Full | TranslationScope::Body, false) => Ok(TranslationScope::Body),
+        (TranslationScope::Frontmatter, false) => Err(AppError::BadRequest(
+            "Nothing to translate: scope is frontmatter but preserve_frontmatter is false".to_string(),
+        )),
+    }
+}
+
+/// Reject a fresh translation once the tenant has used up today's quota.
+/// Counted from the audit log, so concurrent requests may overshoot slightly.
+async fn check_quota(state: &AppState, tenant: &str) -> Result<(), AppError> {
+    let quota = state.settings().tenant_daily_quota;
+    if quota == 0 {
+        return Ok(());
+    }
+
+    let used = state
+        .audit
+        .fresh_translations(start_of_day())
+        .await?
+        .get(tenant)
+        .copied()
+        .unwrap_or(0);
+    if used >= quota as i64 {
+        return Err(AppError::QuotaExceeded(format!(
+            "Tenant {} has used its daily quota of {} translations",
+            tenant, quota
+        )));
+    }
+    Ok(())
+}
+
+/// Midnight UTC of the current day, when daily quotas reset
+fn start_of_day() -> chrono::DateTime<chrono::Utc> {
+    chrono::Utc::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_utc()
+}
+
+/// Encoding requested for translated content; base64 unless the options say otherwise
+fn response_encoding(options: Option<&TranslateOptions>) -> ContentEncoding {
+    options.map(|options| options.response_encoding).unwrap_or_default()
+}
+
+/// How a translate call was answered
+enum Answer {
+    /// Translated upstream, with the metadata of the translation
+    Fresh(Box<TranslationMetadata>),
+    /// Served from the cache
+    Cached,
+    /// Served from the in-flight translation of an identical request
+    Shared,
+}
+
+/// Record a translate call in the audit log, charging fresh translations to the budget
+async fn record_audit(
+    state: &AppState,
+    api_key: &ApiKeyId,
+    path: &str,
+    content_hash: &str,
+    profile: &TranslationProfile,
+    start_time: Instant,
+    outcome: Result<&Answer, &AppError>,
+) {
+    let cost = match outcome {
+        Ok(Answer::Fresh(metadata)) => {
+            let cost = state.settings().cost_usd(metadata.input_tokens, metadata.output_tokens);
+            state
+                .budget
+                .record(metadata.input_tokens + metadata.output_tokens, cost)
+                .await;
+            cost
+        }
+        _ => 0.0,
+    };
+
+    state
+        .audit
+        .record(AuditRecord {
+            api_key_id: &api_key.0,
+            tenant: &profile.tenant,
+            path,
+            content_hash,
+            source_language: &profile.source_language,
+            target_language: &profile.target_language,
+            cached: matches!(outcome, Ok(Answer::Cached)),
+            shared: matches!(outcome, Ok(Answer::Shared)),
+            duration_ms: start_time.elapsed().as_secs_f64() * 1000.0,
+            cost_usd: cost,
+            error: outcome.err().map(|e| e.to_string()),
+        })
+        .await;
 }
 
 /// Root endpoint with service information
-pub async fn root() -> Json<RootResponse> {
-    let settings = get_settings();
+#[utoipa::path(
+    get, path = "/", tag = "health", security(()),
+    responses((status = 200, body = RootResponse))
+)]
+pub async fn root(State(state): State<AppState>) -> Json<RootResponse> {
+    let settings = state.settings();
     Json(RootResponse {
         service: "Skill Translator".to_string(),
         version: settings.translator_version.clone(),
@@ -107,296 +748,1890 @@ pub async fn root() -> Json<RootResponse> {
         endpoints: json!({
             "translate": "/api/translate",
             "batch": "/api/translate/batch",
+            "preview": "/api/translate/preview",
+            "delta": "/api/translate/delta",
+            "archive": "/api/translate/archive",
+            "github": "/api/translate/github",
+            "session": "/api/ws",
             "health": "/api/health",
-            "cache_stats": "/api/cache/stats"
+            "public_key": "/api/public-key",
+            "ready": "/api/ready",
+            "metrics": "/metrics",
+            "cache_stats": "/api/cache/stats",
+            "cache_stats_detailed": "/api/cache/stats/detailed",
+            "cache_entries": "/api/cache/entries"
         }),
+        limits: ServiceLimits {
+            max_request_bytes: settings.max_request_bytes,
+            max_archive_bytes: settings.max_archive_bytes,
+            max_batch_files: settings.max_batch_files,
+            max_batch_total_bytes: settings.max_batch_total_bytes,
+            max_github_files: MAX_GITHUB_FILES,
+        },
     })
 }
 
+/// Prometheus metrics (no auth required)
+#[utoipa::path(
+    get, path = "/metrics", tag = "health", security(()),
+    responses((status = 200, description = "Metrics in the Prometheus text format", content_type = "text/plain", body = String))
+)]
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let concurrency = state.translator.concurrency();
+    let gauges = [
+        ("skillts_upstream_concurrency_limit", "Upstream calls currently allowed at once", concurrency.limit),
+        ("skillts_upstream_concurrency_max", "Configured limit of upstream calls at once", concurrency.max_limit),
+        ("skillts_upstream_calls_in_flight", "Upstream calls holding a concurrency permit", concurrency.in_use),
+    ];
+    let body: String = gauges
+        .iter()
+        .map(|(name, help, value)| format!("# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}\n"))
+        .collect();
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+/// Public key verifying the signatures of translate responses (no auth required)
+#[utoipa::path(
+    get, path = "/api/public-key", tag = "health", security(()),
+    responses(
+        (status = 200, body = PublicKeyResponse),
+        (status = 404, description = "Responses are not signed", body = ErrorResponse),
+    )
+)]
+pub async fn get_public_key(State(state): State<AppState>) -> Result<Json<PublicKeyResponse>, AppError> {
+    let signer = ResponseSigner::from_settings(&state.settings())
+        .ok_or_else(|| AppError::NotFound("Response signing is not configured".to_string()))?;
+    Ok(Json(signer.public_key()))
+}
+
 /// Health check endpoint (no auth required)
-pub async fn health_check() -> Json<HealthResponse> {
-    let settings = get_settings();
+#[utoipa::path(
+    get, path = "/api/health", tag = "health", security(()),
+    responses((status = 200, body = HealthResponse))
+)]
+pub async fn health_check(State(state): State<AppState>) -> Json<HealthResponse> {
+    let settings = state.settings();
+
+    let cache_connected = match state.cache.ping().await {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!("Health check: cache ping failed: {}", e);
+            false
+        }
+    };
+
+    let openai_reachable = if settings.health_check_upstream {
+        Some(probe_upstream(&settings.openai_base_url).await)
+    } else {
+        None
+    };
+
+    let circuit_breaker = state.translator.circuit_status();
+    let healthy =
+        cache_connected && openai_reachable.unwrap_or(true) && circuit_breaker.state != "open";
+
     Json(HealthResponse {
-        status: "healthy".to_string(),
+        status: if healthy { "healthy" } else { "degraded" }.to_string(),
         version: settings.translator_version.clone(),
-        cache_connected: true,
-        openai_configured: !settings.openai_api_key.is_empty(),
+        cache_connected,
+        openai_configured: !settings.openai_api_key.is_empty()
+            || settings.llm_provider == Provider::Ollama,
+        openai_reachable,
+        circuit_breaker,
     })
 }
 
+/// Send a HEAD request to the upstream base URL.
+/// Any HTTP response counts as reachable; only connection errors and timeouts fail.
+async fn probe_upstream(base_url: &str) -> bool {
+    let client = match reqwest::Client::builder()
+        .timeout(UPSTREAM_PROBE_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!("Health check: failed to build HTTP client: {}", e);
+            return false;
+        }
+    };
+
+    match client.head(base_url).send().await {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::warn!("Health check: upstream probe failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Readiness endpoint (no auth required).
+/// Returns 503 while the service is initializing or draining.
+#[utoipa::path(
+    get, path = "/api/ready", tag = "health", security(()),
+    responses(
+        (status = 200, body = ReadyResponse),
+        (status = 503, description = "Initializing or draining", body = ReadyResponse),
+    )
+)]
+pub async fn ready_check(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<ReadyResponse>) {
+    let status = state.readiness.status();
+    let code = if status == "ready" {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (code, Json(ReadyResponse { status: status.to_string() }))
+}
+
 /// Translate a single SKILL.md file
+#[utoipa::path(
+    post, path = "/api/translate", tag = "translate",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replay the response of an earlier request with the same key"),
+        ("If-None-Match" = Option<String>, Header, description = "Quoted translated_hash of a translation the client already has"),
+    ),
+    request_body = TranslateRequest,
+    responses(
+        (status = 200, description = "Translation, with its quoted translated_hash as ETag", body = TranslateResponse),
+        (status = 304, description = "The translation matches If-None-Match; no body"),
+        (status = 400, description = "Invalid content or content hash", body = ErrorResponse),
+        (status = 413, description = "Content too large", body = ErrorResponse),
+        (status = 422, description = "Invalid request body; `errors` lists the fields", body = ErrorResponse),
+        (status = 429, description = "Tenant quota or budget exhausted", body = ErrorResponse),
+        (status = 503, description = "Circuit breaker open", body = ErrorResponse),
+    )
+)]
 #[axum::debug_handler]
 pub async fn translate_file(
     State(state): State<AppState>,
-    Json(request): Json<TranslateRequest>,
-) -> Result<Json<TranslateResponse>, AppError> {
+    Extension(api_key): Extension<ApiKeyId>,
+    Extension(tenant): Extension<Tenant>,
+    headers: HeaderMap,
+    ValidJson(request): ValidJson<TranslateRequest>,
+) -> Result<Response, AppError> {
+    let response = translate_single(&state, &api_key, &tenant, &request, Priority::Interactive, None).await?;
+    // The translated hash identifies the whole body only without related files
+    if !request.related_files.is_empty() {
+        return Ok(Json(response).into_response());
+    }
+    let Ok(etag) = HeaderValue::from_str(&format!("\"{}\"", response.translated_hash)) else {
+        return Ok(Json(response).into_response());
+    };
+    if if_none_match(&headers, &response.translated_hash) {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+    Ok(([(header::ETAG, etag)], Json(response)).into_response())
+}
+
+/// Whether an `If-None-Match` header names the translated hash, quoted or weak, or is `*`
+fn if_none_match(headers: &HeaderMap, translated_hash: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/").trim_matches('"'))
+        .any(|tag| tag == "*" || tag == translated_hash)
+}
+
+/// Translate and audit a single-file request of the caller's tenant.
+/// Shared by the HTTP, gRPC and WebSocket APIs; `progress` hears of each upstream call.
+pub async fn translate_single(
+    state: &AppState,
+    api_key: &ApiKeyId,
+    tenant: &Tenant,
+    request: &TranslateRequest,
+    priority: Priority,
+    progress: Option<ProgressSink>,
+) -> Result<TranslateResponse, AppError> {
     let start_time = Instant::now();
 
-    // Decode content
-    let content = decode_content(&request.content)?;
+    // Get options
+    let settings = state.settings();
+    let profile = TranslationProfile {
+        progress,
+        ..resolve_profile(&settings, request.options.as_ref(), tenant, priority)?
+    };
 
-    // Filter out lines exceeding 5000 characters
-    let (content, removed_count) = filter_long_lines(&content);
-    if removed_count > 0 {
-        tracing::info!(
-            "Removed {} lines exceeding {} characters",
-            removed_count,
-            MAX_LINE_LENGTH
-        );
+    let outcome = translate_request(state, request, &profile, start_time).await;
+    let content_hash = match &outcome {
+        Ok((response, _)) => response.content_hash.as_str(),
+        Err(_) => request.content_hash.as_deref().unwrap_or_default(),
+    };
+    record_audit(
+        state,
+        api_key,
+        &request.path,
+        content_hash,
+        &profile,
+        start_time,
+        outcome.as_ref().map(|(_, answer)| answer),
+    )
+    .await;
+
+    let (mut response, _) = outcome?;
+    if !request.related_files.is_empty() {
+        response.related_files = translate_related_files(state, api_key, request, &profile, &response).await;
+    }
+    apply_output_mode(
+        profile.output_mode,
+        (request.content_encoding, &request.content),
+        response_encoding(request.options.as_ref()),
+        &mut response.translated_content,
+        &mut response.translated_hash,
+    )?;
+    if let Some(signer) = ResponseSigner::from_settings(&settings) {
+        response.signature = Some(signer.sign(&response.content_hash, &response.translated_hash, &response.metadata.model));
     }
+    Ok(response)
+}
 
-    // Get options
-    let settings = get_settings();
-    let source_language = request
-        .options
-        .as_ref()
-        .map(|o| o.source_language.as_str())
-        .unwrap_or_else(|| settings.source_language.as_str());
-    let target_language = request
-        .options
-        .as_ref()
-        .map(|o| o.target_language.as_str())
-        .unwrap_or_else(|| settings.target_language.as_str());
+/// Pair an encoded translation with its original as the output mode asks, rehashing
+/// it so the hash and signature describe the document returned
+fn apply_output_mode(
+    mode: OutputMode,
+    original: (ContentEncoding, &str),
+    response_encoding: ContentEncoding,
+    translated_content: &mut String,
+    translated_hash: &mut String,
+) -> Result<(), AppError> {
+    if mode == OutputMode::Replace {
+        return Ok(());
+    }
+    let original = original.0.decode(original.1)?;
+    let output = bilingual::render(mode, &original, &response_encoding.decode(translated_content)?);
+    *translated_hash = Translator::compute_hash(&output);
+    *translated_content = response_encoding.encode(&output);
+    Ok(())
+}
 
-    // Compute cache key
-    let cache_key = state
-        .translator
-        .compute_cache_key(&request.content_hash, source_language, target_language);
-
-    // Check cache
-    if let Some(cached) = state.cache.get(&cache_key).await? {
-        let encoded_cached_content = encode_content(&cached.translated_content);
-        return Ok(Json(TranslateResponse {
-            translated_content: encoded_cached_content,
-            content_hash: cached.content_hash,
-            translated_hash: cached.translated_hash,
-            cached: true,
-            metadata: cached.metadata,
-        }));
+/// [`apply_output_mode`] for a translated file of a batch
+fn apply_file_output_mode(
+    mode: OutputMode,
+    file: &FileToTranslate,
+    response_encoding: ContentEncoding,
+    result: &mut FileTranslationResult,
+) -> Result<(), AppError> {
+    match (result.translated_content.as_mut(), result.translated_hash.as_mut()) {
+        (Some(content), Some(hash)) => apply_output_mode(
+            mode,
+            (file.content_encoding, &file.content),
+            response_encoding,
+            content,
+            hash,
+        ),
+        _ => Ok(()),
     }
+}
 
-    // Translate
-    let (translated_content, metadata) = state
-        .translator
-        .translate(&content, source_language, target_language)
-        .await?;
+/// Translate the related files of a request one after another, each told the renderings
+/// of terms chosen in the main file and the related files before it. A failed file is
+/// reported in its result and does not stop the others.
+async fn translate_related_files(
+    state: &AppState,
+    api_key: &ApiKeyId,
+    request: &TranslateRequest,
+    profile: &TranslationProfile,
+    response: &TranslateResponse,
+) -> Vec<FileTranslationResult> {
+    let response_encoding = response_encoding(request.options.as_ref());
+    let mut terms = Vec::new();
+    learn_terms(
+        &mut terms,
+        (request.content_encoding, &request.content),
+        (response_encoding, &response.translated_content),
+    );
 
-    // Compute hash of translated content
-    let translated_hash = Translator::compute_hash(&translated_content);
+    let output_mode = profile.output_mode;
+    let mut results = Vec::with_capacity(request.related_files.len());
+    for file in &request.related_files {
+        let profile = TranslationProfile {
+            terminology: terms.clone(),
+            // Progress is reported for the main file only
+            progress: None,
+            // Terms are learned from the plain translation
+            output_mode: OutputMode::Replace,
+            ..profile.clone()
+        };
+        let mut result = translate_batch_file(state, api_key, file.clone(), &profile, true, response_encoding, None).await;
+        if let Some(translated) = &result.translated_content {
+            learn_terms(
+                &mut terms,
+                (file.content_encoding, &file.content),
+                (response_encoding, translated),
+            );
+        }
+        if let Err(e) = apply_file_output_mode(output_mode, file, response_encoding, &mut result) {
+            result = failed_file_result(file.clone(), e);
+        }
+        results.push(result);
+    }
+    tracing::info!(
+        "[{}] Translated {} related files with {} shared terms",
+        request.path,
+        results.len(),
+        terms.len()
+    );
+    results
+}
+
+/// Add the terms of an encoded document and its encoded translation
+fn learn_terms(terms: &mut Vec<Term>, source: (ContentEncoding, &str), translation: (ContentEncoding, &str)) {
+    if let (Ok(source), Ok(translation)) = (source.0.decode(source.1), translation.0.decode(translation.1)) {
+        terminology::merge(terms, terminology::extract_terms(&source, &translation));
+    }
+}
+
+/// Translate a single-file request, returning the response and,
+/// for a fresh translation, its metadata
+async fn translate_request(
+    state: &AppState,
+    request: &TranslateRequest,
+    profile: &TranslationProfile,
+    start_time: Instant,
+) -> Result<(TranslateResponse, Answer), AppError> {
+    // Decode content
+    let content = request.content_encoding.decode(&request.content)?;
+    let response_encoding = response_encoding(request.options.as_ref());
+    let settings = state.settings();
+    let pipeline = state.pipeline(&settings);
+    let prepared = pipeline.prepare(&content, request.content_hash.as_deref(), profile)?;
+    let lossy = prepared.lossy();
 
-    // Store in cache
-    state.cache.set(
-        &cache_key,
-        &request.content_hash,
+    // Check cache, unless asked to translate again
+    if !prepared.profile.force {
+        if let Some(cached) = pipeline.cached(&prepared, &request.path, None).await? {
+            return Ok((cached_response(cached, response_encoding, prepared.long_lines.as_ref()), Answer::Cached));
+        }
+    }
+    let PreparedFile {
+        content,
+        content_hash,
+        long_lines,
+        profile,
+        ..
+    } = prepared;
+
+    // Translate and store in cache, falling back to a translation of an earlier version
+    let (fresh, shared) = match translate_and_cache(
+        state,
+        &content,
+        &content_hash,
         &request.path,
-        &translated_content,
-        &translated_hash,
-        Some(json!({
-            "original_chars": metadata.original_chars,
-            "translated_chars": metadata.translated_chars,
-            "processing_time_ms": metadata.processing_time_ms,
-            "translator_version": metadata.translator_version,
-            "model": metadata.model,
-            "source_language": metadata.source_language,
-            "target_language": metadata.target_language,
-        })),
-    ).await?;
+        &profile,
+        lossy,
+        None,
+    )
+    .await
+    {
+        Err(e @ AppError::TranslationError(_)) => {
+            let stale = stale_fallback(state, &content_hash, &profile, e).await?;
+            return Ok((cached_response(stale, response_encoding, long_lines.as_ref()), Answer::Cached));
+        }
+        outcome => outcome?,
+    };
+    let metadata = &fresh.metadata;
 
     // Encode response
-    let encoded_content = encode_content(&translated_content);
+    let encoded_content = response_encoding.encode(&fresh.translated_content);
 
     let processing_time = start_time.elapsed().as_millis() as f64;
 
-    Ok(Json(TranslateResponse {
+    let response = TranslateResponse {
         translated_content: encoded_content,
-        content_hash: request.content_hash,
-        translated_hash,
+        content_hash,
+        translated_hash: fresh.translated_hash.clone(),
         cached: false,
-        metadata: json!({
-            "original_chars": metadata.original_chars,
-            "translated_chars": metadata.translated_chars,
-            "processing_time_ms": metadata.processing_time_ms,
-            "translator_version": metadata.translator_version,
-            "model": metadata.model,
-            "source_language": metadata.source_language,
-            "target_language": metadata.target_language,
-            "total_processing_time_ms": processing_time,
-        }),
+        metadata: with_long_lines(
+            TranslationResponseMetadata {
+                lossy: Some(lossy),
+                total_processing_time_ms: Some(processing_time),
+                ..TranslationResponseMetadata::new(metadata)
+            },
+            long_lines.as_ref(),
+        ),
+        signature: None,
+        related_files: Vec::new(),
+    };
+
+    let answer = if shared {
+        Answer::Shared
+    } else {
+        Answer::Fresh(Box::new(metadata.clone()))
+    };
+    Ok((response, answer))
+}
+
+/// Response for a translation answered from the cache
+fn cached_response(
+    cached: CacheEntry,
+    response_encoding: ContentEncoding,
+    long_lines: Option<&LongLines>,
+) -> TranslateResponse {
+    TranslateResponse {
+        translated_content: response_encoding.encode(&cached.translated_content),
+        content_hash: cached.content_hash,
+        translated_hash: cached.translated_hash,
+        cached: true,
+        metadata: with_long_lines(TranslationResponseMetadata::from_stored(cached.metadata), long_lines),
+        signature: None,
+        related_files: Vec::new(),
+    }
+}
+
+/// The newest translation of the content left behind by an earlier translator version and
+/// marked `stale_version`, with the same languages, prompt and scope as `profile`; `error`,
+/// the reason a fresh translation failed, when there is none
+async fn stale_fallback(
+    state: &AppState,
+    content_hash: &str,
+    profile: &TranslationProfile,
+    error: AppError,
+) -> Result<CacheEntry, AppError> {
+    let stale = match state.cache.get_stale_versions(&profile.tenant, content_hash).await {
+        Ok(stale) => stale,
+        Err(e) => {
+            tracing::warn!("Stale version lookup failed: {}", e);
+            return Err(error);
+        }
+    };
+    let found = stale.into_iter().find_map(|entry| {
+        let version = entry_version(&entry)?.to_string();
+        state
+            .translator
+            .cache_keys_for_version(content_hash, profile, &version)
+            .contains(&entry.cache_key)
+            .then_some((entry, version))
+    });
+    let Some((mut entry, version)) = found else {
+        return Err(error);
+    };
+
+    tracing::warn!(
+        "Translation failed, answering with the translation of translator version {}: {}",
+        version,
+        error
+    );
+    if let Some(metadata) = entry.metadata.as_object_mut() {
+        metadata.insert("fallback_error".to_string(), json!(error.to_string()));
+    }
+    Ok(entry)
+}
+
+/// Check whether a translation a mirror holds is still the one the service would return,
+/// without transferring content; a prompt, model or translator upgrade makes it `missing`.
+/// Does not count as a cache hit or miss.
+#[utoipa::path(
+    post, path = "/api/translate/check", tag = "translate",
+    request_body = TranslateCheckRequest,
+    responses(
+        (status = 200, body = TranslateCheckResponse),
+        (status = 400, body = ErrorResponse),
+    )
+)]
+pub async fn check_translation(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    Json(request): Json<TranslateCheckRequest>,
+) -> Result<Json<TranslateCheckResponse>, AppError> {
+    let settings = state.settings();
+    let profile = resolve_profile(&settings, request.options.as_ref(), &tenant, Priority::Interactive)?;
+
+    // An `auto` source language is detected from the original, when one was stored
+    let profile = if profile.source_language.eq_ignore_ascii_case(language::AUTO) {
+        let original = state.sources.get(&tenant.0, &request.content_hash).await?.ok_or_else(|| {
+            AppError::BadRequest("The original was not stored, set source_language explicitly".to_string())
+        })?;
+        state.translator.resolve_profile(&original, &profile)?
+    } else {
+        profile
+    };
+
+    let mut cached = None;
+    for cache_key in state.translator.cache_keys(&request.content_hash, &profile) {
+        if let Some(entry) = state.cache.get_by_key(&tenant.0, &cache_key).await? {
+            cached = Some(entry);
+            break;
+        }
+    }
+
+    Ok(Json(check_response(request, cached.map(|entry| entry.translated_hash))))
+}
+
+/// Compare the client's translation with the cached one
+fn check_response(request: TranslateCheckRequest, cached_hash: Option<String>) -> TranslateCheckResponse {
+    let status = match &cached_hash {
+        Some(hash) if *hash == request.translated_hash => "current",
+        Some(_) => "changed",
+        None => "missing",
+    };
+    TranslateCheckResponse {
+        content_hash: request.content_hash,
+        status: status.to_string(),
+        translated_hash: cached_hash,
+    }
+}
+
+/// Retranslate a changed file, reusing the previous translation of unchanged sections.
+/// The result is not cached, since it depends on the supplied prior translation.
+#[utoipa::path(
+    post, path = "/api/translate/delta", tag = "translate",
+    request_body = DeltaTranslateRequest,
+    responses(
+        (status = 200, body = DeltaTranslateResponse),
+        (status = 400, body = ErrorResponse),
+    )
+)]
+pub async fn translate_delta(
+    State(state): State<AppState>,
+    Extension(api_key): Extension<ApiKeyId>,
+    Extension(tenant): Extension<Tenant>,
+    Json(request): Json<DeltaTranslateRequest>,
+) -> Result<Json<DeltaTranslateResponse>, AppError> {
+    let start_time = Instant::now();
+    let settings = state.settings();
+    let profile = resolve_profile(&settings, request.options.as_ref(), &tenant, Priority::Interactive)?;
+
+    let new_content = decode_content(&request.new_content)?;
+    let content_hash = Translator::compute_hash(&new_content);
+
+    let outcome = delta_request(&state, &request, &new_content, &profile).await;
+    let answer = outcome
+        .as_ref()
+        .map(|(delta, _)| Answer::Fresh(Box::new(delta.metadata.clone())));
+    record_audit(
+        &state,
+        &api_key,
+        &request.path,
+        &content_hash,
+        &profile,
+        start_time,
+        answer.as_ref().map_err(|e| *e),
+    )
+    .await;
+    let (delta, long_lines) = outcome?;
+
+    let sections: Vec<DeltaSection> = delta
+        .sections
+        .into_iter()
+        .map(|section| DeltaSection {
+            heading: section.heading,
+            status: if section.reused { "reused" } else { "retranslated" }.to_string(),
+        })
+        .collect();
+    let reused = sections.iter().filter(|s| s.status == "reused").count();
+
+    Ok(Json(DeltaTranslateResponse {
+        translated_content: encode_content(&delta.content),
+        content_hash,
+        translated_hash: Translator::compute_hash(&delta.content),
+        retranslated: sections.len() - reused,
+        reused,
+        sections,
+        metadata: with_long_lines(TranslationResponseMetadata::new(&delta.metadata), long_lines.as_ref()),
+    }))
+}
+
+/// Decode and filter a delta request, then translate it against the prior translation
+async fn delta_request(
+    state: &AppState,
+    request: &DeltaTranslateRequest,
+    new_content: &str,
+    profile: &TranslationProfile,
+) -> Result<(DeltaTranslation, Option<LongLines>), AppError> {
+    check_quota(state, &profile.tenant).await?;
+    state.budget.check(&state.settings()).await?;
+
+    let old_content = decode_content(&request.old_content)?;
+    let old_translation = decode_content(&request.old_translation)?;
+
+    let settings = state.settings();
+    let (old_content, _) = handle_long_lines(&settings, &old_content)?;
+    let (old_translation, _) = handle_long_lines(&settings, &old_translation)?;
+    let (new_content, long_lines) = handle_long_lines(&settings, new_content)?;
+
+    let delta = state
+        .translator
+        .translate_delta(&old_content, &old_translation, &new_content, profile)
+        .await?;
+    Ok((delta, long_lines))
+}
+
+/// Preview how a file would be translated: segmentation, token and cost estimates.
+/// Makes no upstream call and does not touch the cache.
+#[utoipa::path(
+    post, path = "/api/translate/preview", tag = "translate",
+    request_body = PreviewRequest,
+    responses(
+        (status = 200, body = PreviewResponse),
+        (status = 400, body = ErrorResponse),
+    )
+)]
+pub async fn preview_translation(
+    State(state): State<AppState>,
+    Json(request): Json<PreviewRequest>,
+) -> Result<Json<PreviewResponse>, AppError> {
+    let settings = state.settings();
+    let content = decode_content(&request.content)?;
+    let (content, long_lines) = handle_long_lines(&settings, &content)?;
+    let removed_lines = long_lines
+        .as_ref()
+        .filter(|long_lines| long_lines.mode == LongLineMode::Drop)
+        .map_or(0, |long_lines| long_lines.count);
+
+    let preview = state.translator.preview(&content)?;
+    let estimated_cost_usd = settings.cost_usd(preview.estimated_input_tokens, preview.estimated_output_tokens);
+
+    Ok(Json(PreviewResponse {
+        frontmatter_fields: preview
+            .frontmatter_fields
+            .into_iter()
+            .map(|(name, value)| PreviewField { name, value })
+            .collect(),
+        body_with_placeholders: preview.body_with_placeholders,
+        code_block_count: preview.code_block_count,
+        removed_lines,
+        long_lines,
+        api_calls: preview.api_calls,
+        estimated_input_tokens: preview.estimated_input_tokens,
+        estimated_output_tokens: preview.estimated_output_tokens,
+        estimated_cost_usd,
+        model: settings.openai_model.clone(),
     }))
 }
 
-/// Translate multiple SKILL.md files in batch
+/// Compare a translation with its original: heading and code block counts, frontmatter
+/// keys, link targets, leaked placeholders and YAML validity of the translated frontmatter
+#[utoipa::path(
+    post, path = "/api/validate", tag = "translate",
+    request_body = ValidateRequest,
+    responses(
+        (status = 200, body = ValidationReport),
+        (status = 400, body = ErrorResponse),
+    )
+)]
+pub async fn validate_translation(
+    Json(request): Json<ValidateRequest>,
+) -> Result<Json<ValidationReport>, AppError> {
+    let original = decode_content(&request.original)?;
+    let translated = decode_content(&request.translated)?;
+    Ok(Json(validate::validate_translation(&original, &translated)))
+}
+
+/// Translate multiple SKILL.md files in batch.
+///
+/// With `background` set, the job is stored and processed after responding with
+/// 202 and its status; poll `GET /api/translate/batch/{job_id}` for results.
+/// With `Accept: application/x-ndjson`, each file's result is streamed as a line
+/// once it is done, followed by a line with the batch summary.
+#[utoipa::path(
+    post, path = "/api/translate/batch", tag = "translate",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Replay the response of an earlier request with the same key")),
+    request_body = BatchTranslateRequest,
+    responses(
+        (status = 200, description = "Batch results, or with `Accept: application/x-ndjson` one result per line and a `BatchSummary` line last", content(
+            (BatchTranslateResponse = "application/json"),
+            (FileTranslationResult = "application/x-ndjson", example = json!({"path": "SKILL.md", "success": true, "content_hash": "sha256:...", "cached": false})),
+        )),
+        (status = 202, description = "Background job queued", body = BatchJobStatus),
+        (status = 400, body = ErrorResponse),
+        (status = 413, description = "The files' content exceeds MAX_BATCH_TOTAL_BYTES", body = ErrorResponse),
+        (status = 422, description = "Invalid request body, or more files than MAX_BATCH_FILES; `errors` lists the fields", body = ErrorResponse),
+    )
+)]
 #[axum::debug_handler]
 pub async fn translate_batch(
     State(state): State<AppState>,
-    Json(request): Json<BatchTranslateRequest>,
-) -> Result<Json<BatchTranslateResponse>, AppError> {
+    Extension(api_key): Extension<ApiKeyId>,
+    Extension(tenant): Extension<Tenant>,
+    headers: header::HeaderMap,
+    ValidJson(request): ValidJson<BatchTranslateRequest>,
+) -> Result<Response, AppError> {
     let start_time = Instant::now();
+    // Rejects oversized batches and invalid options before a background job is queued with them
+    let settings = state.settings();
+    check_batch_size(&settings, &request.files)?;
+    let profile = resolve_profile(&settings, request.options.as_ref(), &tenant, Priority::Bulk)?;
 
-    let settings = get_settings();
-    let source_language = request
-        .options
-        .as_ref()
-        .map(|o| o.source_language.as_str())
-        .unwrap_or_else(|| settings.source_language.as_str());
-    let target_language = request
-        .options
-        .as_ref()
-        .map(|o| o.target_language.as_str())
-        .unwrap_or_else(|| settings.target_language.as_str());
+    if request.background {
+        let job = state
+            .batch_jobs
+            .create(
+                &tenant.0,
+                &api_key.0,
+                &request.files,
+                request.options.as_ref(),
+                request.skip_cached,
+            )
+            .await?;
+        let status = state.batch_jobs.status(&tenant.0, &job.id, 0).await?;
+        tracing::info!("Batch job {} queued with {} files", job.id, status.total_files);
+        spawn_batch_job(&state, job);
+        return Ok((StatusCode::ACCEPTED, Json(status)).into_response());
+    }
+
+    let response_encoding = response_encoding(request.options.as_ref());
+    let batch = prefetch_batch(&state, &request.files, &profile, request.skip_cached).await?;
+
+    if accepts_ndjson(&headers) {
+        let (sender, receiver) = tokio::sync::mpsc::channel(BATCH_STREAM_BUFFER);
+        // Files are translated in a task of their own, which ends when the client
+        // goes away, as the response body is dropped with the connection
+        tokio::spawn(async move {
+            let mut summary = BatchSummary::default();
+            for file in request.files {
+                let result = tokio::select! {
+                    result = translate_batch_file(&state, &api_key, file, &profile, request.skip_cached, response_encoding, Some(&batch)) => result,
+                    () = sender.closed() => return,
+                };
+                summary.count(&result);
+                if sender.send(ndjson_line(&result)).await.is_err() {
+                    return;
+                }
+            }
+            if let Err(e) = batch.flush().await {
+                tracing::error!("Failed to store the translations of a batch: {}", e);
+            }
+            summary.processing_time_ms = start_time.elapsed().as_millis() as f64;
+            let _ = sender.send(ndjson_line(&summary)).await;
+        });
+        let body = Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(receiver));
+        return Ok(([(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)], body).into_response());
+    }
+
+    let mut results = Vec::new();
+    let mut summary = BatchSummary::default();
+
+    for file in request.files {
+        let result = translate_batch_file(
+            &state,
+            &api_key,
+            file,
+            &profile,
+            request.skip_cached,
+            response_encoding,
+            Some(&batch),
+        )
+        .await;
+        summary.count(&result);
+        results.push(result);
+    }
+    batch.flush().await?;
+
+    summary.processing_time_ms = start_time.elapsed().as_millis() as f64;
+
+    Ok(Json(BatchTranslateResponse { results, summary }).into_response())
+}
+
+/// Refuse a batch with more files or content than one request may hold, asking the
+/// client to split it
+fn check_batch_size(settings: &Settings, files: &[FileToTranslate]) -> Result<(), AppError> {
+    if files.len() > settings.max_batch_files {
+        return Err(AppError::Validation(vec![FieldError {
+            field: "files".to_string(),
+            message: format!(
+                "{} files exceed the limit of {} per batch; split them into smaller batches",
+                files.len(),
+                settings.max_batch_files
+            ),
+        }]));
+    }
+    let total_bytes: usize = files.iter().map(|file| file.content.len()).sum();
+    if total_bytes > settings.max_batch_total_bytes {
+        return Err(AppError::PayloadTooLarge(format!(
+            "The files' content totals {} bytes, more than the limit of {} per batch; split them into smaller batches",
+            total_bytes, settings.max_batch_total_bytes
+        )));
+    }
+    Ok(())
+}
+
+/// Whether the client asked for a streamed batch response
+fn accepts_ndjson(headers: &header::HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_type| {
+            media_type
+                .split(';')
+                .next()
+                .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case(NDJSON_CONTENT_TYPE))
+        })
+}
+
+/// A value as one line of a streamed response
+fn ndjson_line(value: &impl serde::Serialize) -> Result<Bytes, serde_json::Error> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    Ok(line.into())
+}
+
+/// Translate one file of a batch, a failure being part of its result
+async fn translate_batch_file(
+    state: &AppState,
+    api_key: &ApiKeyId,
+    file: FileToTranslate,
+    profile: &TranslationProfile,
+    skip_cached: bool,
+    response_encoding: ContentEncoding,
+    batch: Option<&BatchCache>,
+) -> FileTranslationResult {
+    match process_single_file(state, api_key, &file, profile, skip_cached, response_encoding, batch).await {
+        Ok(result) => result,
+        Err(e) => failed_file_result(file, e),
+    }
+}
+
+/// Result of a file whose translation failed with `error`
+fn failed_file_result(file: FileToTranslate, error: AppError) -> FileTranslationResult {
+    FileTranslationResult {
+        path: file.path,
+        success: false,
+        translated_content: None,
+        content_hash: file.content_hash.unwrap_or_default(),
+        translated_hash: None,
+        cached: false,
+        error: Some(error.to_string()),
+        error_code: Some(error.code()),
+        long_lines: None,
+    }
+}
+
+/// Process the unprocessed files of a background batch job in a new task, then mark it completed.
+/// Each result is stored when ready, so a job interrupted by a restart resumes where it stopped.
+/// Cancelling the job drops the task's work at once, releasing its upstream call.
+pub fn spawn_batch_job(state: &AppState, job: BatchJob) {
+    let cancelled = state.batch_jobs.track(&job.id);
+    let state = state.clone();
+    tokio::spawn(async move {
+        tokio::select! {
+            result = process_batch_job(&state, &job) => {
+                if let Err(e) = result {
+                    tracing::error!("Batch job {} stopped, it resumes on restart: {}", job.id, e);
+                }
+            }
+            Ok(()) = cancelled => tracing::info!("Batch job {} cancelled", job.id),
+        }
+        state.batch_jobs.untrack(&job.id);
+    });
+}
+
+async fn process_batch_job(state: &AppState, job: &BatchJob) -> Result<(), AppError> {
+    let settings = state.settings();
+    let profile = resolve_profile(
+        &settings,
+        job.options.as_ref(),
+        &Tenant(job.tenant.clone()),
+        Priority::Bulk,
+    )?;
+    let response_encoding = response_encoding(job.options.as_ref());
+    let api_key = ApiKeyId(job.api_key.clone());
+    let profile = TranslationProfile {
+        progress: Some(state.batch_jobs.progress_sink(&job.id)),
+        ..profile
+    };
+
+    for (position, file) in state.batch_jobs.pending_files(&job.id).await? {
+        state.batch_jobs.start_file(&job.id, &file.path);
+        let result = match process_single_file(
+            state,
+            &api_key,
+            &file,
+            &profile,
+            job.skip_cached,
+            response_encoding,
+            None,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => failed_file_result(file, e),
+        };
+        let chunks = state.batch_jobs.start_saving(&job.id);
+        state.batch_jobs.record_result(&job.id, position, &result, chunks).await?;
+    }
+
+    state.batch_jobs.finish(&job.id).await?;
+    tracing::info!("Batch job {} completed", job.id);
+    Ok(())
+}
+
+/// Translate the files of the warmer manifest whose translation is not cached yet, for the
+/// default tenant with the configured languages. Stops early when the quota or budget runs out.
+pub async fn warm_cache(state: &AppState) -> Result<WarmerRun, AppError> {
+    let settings = state.settings();
+    let profile = resolve_profile(&settings, None, &Tenant::default(), Priority::Bulk)?;
+    let api_key = ApiKeyId::warmer();
+    let http = warmer::client()?;
+    let manifest = warmer::fetch_manifest(&http, &settings.warmer_manifest_url).await?;
+
+    let mut run = WarmerRun {
+        listed: manifest.len(),
+        ..WarmerRun::default()
+    };
+    for entry in manifest {
+        // Without an `auto` source language, unchanged files are recognised without downloading them
+        if !profile.source_language.eq_ignore_ascii_case(language::AUTO) {
+            let mut cached = false;
+            for cache_key in state.translator.cache_keys(&entry.content_hash, &profile) {
+                if state.cache.get_by_key(&profile.tenant, &cache_key).await?.is_some() {
+                    cached = true;
+                    break;
+                }
+            }
+            if cached {
+                run.cached += 1;
+                continue;
+            }
+        }
+
+        let file = match warmer::fetch_content(&http, &settings.warmer_manifest_url, &entry).await {
+            Ok(content) => FileToTranslate {
+                content_hash: Some(entry.content_hash.clone()),
+                ..FileToTranslate::plain(&entry.path, &content)
+            },
+            Err(e) => {
+                tracing::warn!("Warmer skipped {}: {}", entry.path, e);
+                run.failed += 1;
+                continue;
+            }
+        };
+        match process_single_file(state, &api_key, &file, &profile, true, ContentEncoding::Plain, None).await {
+            Ok(result) if result.cached => run.cached += 1,
+            Ok(_) => run.translated += 1,
+            Err(e @ (AppError::QuotaExceeded(_) | AppError::BudgetExceeded(_))) => {
+                tracing::warn!("Warmer stopped: {}", e);
+                run.failed += 1;
+                break;
+            }
+            Err(e) => {
+                tracing::warn!("Warmer failed to translate {}: {}", entry.path, e);
+                run.failed += 1;
+            }
+        }
+    }
+    Ok(run)
+}
+
+/// Progress and results of a background batch job of the caller's tenant
+#[utoipa::path(
+    get, path = "/api/translate/batch/{job_id}", tag = "translate",
+    params(("job_id" = String, Path), BatchJobQuery),
+    responses(
+        (status = 200, body = BatchJobStatus),
+        (status = 404, body = ErrorResponse),
+    )
+)]
+pub async fn get_batch_job(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    Path(job_id): Path<String>,
+    Query(query): Query<BatchJobQuery>,
+) -> Result<Json<BatchJobStatus>, AppError> {
+    Ok(Json(state.batch_jobs.status(&tenant.0, &job_id, query.offset).await?))
+}
+
+/// Progress of a background batch job of the caller's tenant, like `GET /api/translate/batch/{job_id}`
+#[utoipa::path(
+    get, path = "/api/jobs/{job_id}", tag = "translate",
+    params(("job_id" = String, Path), BatchJobQuery),
+    responses(
+        (status = 200, body = BatchJobStatus),
+        (status = 404, body = ErrorResponse),
+    )
+)]
+pub async fn get_job(
+    state: State<AppState>,
+    tenant: Extension<Tenant>,
+    job_id: Path<String>,
+    query: Query<BatchJobQuery>,
+) -> Result<Json<BatchJobStatus>, AppError> {
+    get_batch_job(state, tenant, job_id, query).await
+}
+
+/// Cancel a running background batch job of the caller's tenant.
+/// Its in-flight translation is dropped; results of processed files are kept.
+#[utoipa::path(
+    delete, path = "/api/jobs/{job_id}", tag = "translate",
+    params(("job_id" = String, Path)),
+    responses(
+        (status = 200, body = BatchJobStatus),
+        (status = 404, body = ErrorResponse),
+        (status = 409, description = "Job already finished", body = ErrorResponse),
+    )
+)]
+pub async fn cancel_job(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    Path(job_id): Path<String>,
+) -> Result<Json<BatchJobStatus>, AppError> {
+    let status = state.batch_jobs.cancel(&tenant.0, &job_id).await?;
+    tracing::info!(
+        "Batch job {} cancelled after {} of {} files",
+        job_id,
+        status.processed_files,
+        status.total_files
+    );
+    Ok(Json(status))
+}
+
+/// Translate every markdown file in an uploaded zip or tar.gz archive.
+///
+/// Expects a multipart form with a `file` field and optional `source_language`,
+/// `target_language` and `document_type` fields. Returns an archive of the same format with markdown
+/// files replaced by their translations and all other files passed through.
+#[utoipa::path(
+    post, path = "/api/translate/archive", tag = "translate",
+    request_body(content = String, content_type = "multipart/form-data", description = "`file` with a zip or tar.gz archive and optional `source_language`, `target_language` and `document_type` fields"),
+    responses(
+        (status = 200, description = "Archive of the same format with translated markdown files", content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 400, body = ErrorResponse),
+        (status = 413, body = ErrorResponse),
+    )
+)]
+pub async fn translate_archive(
+    State(state): State<AppState>,
+    Extension(api_key): Extension<ApiKeyId>,
+    Extension(tenant): Extension<Tenant>,
+    mut multipart: Multipart,
+) -> Result<Response, AppError> {
+    let settings = state.settings();
+    let mut profile = resolve_profile(&settings, None, &tenant, Priority::Bulk)?;
+    let mut upload: Option<(Option<String>, Vec<u8>)> = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadRequest(format!("Invalid multipart body: {}", e)))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "file" => {
+                let file_name = field.file_name().map(str::to_string);
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("Failed to read upload: {}", e)))?;
+                upload = Some((file_name, data.to_vec()));
+            }
+            "source_language" | "target_language" | "document_type" => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("Invalid {} field: {}", name, e)))?;
+                match name.as_str() {
+                    "source_language" => profile.source_language = value,
+                    "target_language" => profile.target_language = value,
+                    _ => profile.document_type = value,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let (file_name, data) =
+        upload.ok_or_else(|| AppError::BadRequest("Missing 'file' field".to_string()))?;
+    let format = ArchiveFormat::detect(file_name.as_deref(), &data).ok_or_else(|| {
+        AppError::BadRequest("Unsupported archive format, expected zip or tar.gz".to_string())
+    })?;
+
+    let files = tokio::task::spawn_blocking(move || archive::read_archive(format, &data))
+        .await
+        .map_err(|e| AppError::Internal(format!("Archive task failed: {}", e)))??;
+
+    // Markdown files go through the batch pipeline, reading their cached translations
+    // in one query and storing new ones in a few transactions; other files pass through
+    let mut failed = 0usize;
+    let mut indexes = Vec::new();
+    let mut batch_files = Vec::new();
+    for (index, file) in files.iter().enumerate() {
+        if !archive::is_markdown(&file.path) {
+            continue;
+        }
+        match std::str::from_utf8(&file.data) {
+            // Archive files carry no client-side hash
+            Ok(text) => {
+                indexes.push(index);
+                batch_files.push(FileToTranslate::plain(&file.path, text));
+            }
+            Err(_) => {
+                tracing::warn!("[{}] Skipping non UTF-8 markdown file", file.path);
+                failed += 1;
+            }
+        }
+    }
+
+    let batch = prefetch_batch(&state, &batch_files, &profile, true).await?;
+    let mut translated_count = 0usize;
+    let mut cached_count = 0usize;
+    let mut output = files;
+    for (index, file) in indexes.into_iter().zip(batch_files) {
+        let result = translate_batch_file(&state, &api_key, file, &profile, true, ContentEncoding::Plain, Some(&batch)).await;
+        match result.translated_content {
+            Some(translated) if result.success => {
+                if result.cached {
+                    cached_count += 1;
+                } else {
+                    translated_count += 1;
+                }
+                output[index].data = translated.into_bytes();
+            }
+            _ => {
+                if let Some(error) = &result.error {
+                    tracing::warn!("[{}] Archive file translation failed: {}", result.path, error);
+                }
+                failed += 1;
+            }
+        }
+    }
+    batch.flush().await?;
+
+    tracing::info!(
+        "Archive translated: {} translated, {} cached, {} failed",
+        translated_count,
+        cached_count,
+        failed
+    );
+
+    let bytes = tokio::task::spawn_blocking(move || archive::write_archive(format, &output))
+        .await
+        .map_err(|e| AppError::Internal(format!("Archive task failed: {}", e)))??;
+
+    let mut response = Response::new(Body::from(bytes));
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(format.content_type()));
+    if let Ok(value) = HeaderValue::from_str(&format!(
+        "attachment; filename=\"translated{}\"",
+        format.extension()
+    )) {
+        headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+    headers.insert("x-files-translated", HeaderValue::from(translated_count));
+    headers.insert("x-files-cached", HeaderValue::from(cached_count));
+    headers.insert("x-files-failed", HeaderValue::from(failed));
+
+    Ok(response)
+}
+
+/// Translate files of a GitHub repository matching a glob.
+/// Optionally opens a pull request adding the translations next to the sources.
+#[utoipa::path(
+    post, path = "/api/translate/github", tag = "translate",
+    request_body = GitHubTranslateRequest,
+    responses(
+        (status = 200, body = GitHubTranslateResponse),
+        (status = 400, body = ErrorResponse),
+        (status = 502, description = "GitHub API error", body = ErrorResponse),
+    )
+)]
+pub async fn translate_github(
+    State(state): State<AppState>,
+    Extension(api_key): Extension<ApiKeyId>,
+    Extension(tenant): Extension<Tenant>,
+    Json(request): Json<GitHubTranslateRequest>,
+) -> Result<Json<GitHubTranslateResponse>, AppError> {
+    let start_time = Instant::now();
+
+    let repo = github::parse_repo(&request.repo).ok_or_else(|| {
+        AppError::BadRequest(format!("Invalid repository: {}", request.repo))
+    })?;
+
+    let settings = state.settings();
+    let profile = resolve_profile(&settings, request.options.as_ref(), &tenant, Priority::Bulk)?;
+    let target_language = profile.target_language.as_str();
+
+    let client = GitHubClient::new(&settings)?;
+    let git_ref = match request.git_ref {
+        Some(git_ref) => git_ref,
+        None => client.default_branch(&repo).await?,
+    };
+
+    let paths = client.list_files(&repo, &git_ref, &request.paths_glob).await?;
+    if paths.len() > MAX_GITHUB_FILES {
+        return Err(AppError::BadRequest(format!(
+            "{} files match '{}', at most {} are allowed per request",
+            paths.len(),
+            request.paths_glob,
+            MAX_GITHUB_FILES
+        )));
+    }
+    tracing::info!("[{}@{}] Translating {} files", repo, git_ref, paths.len());
 
     let mut results = Vec::new();
+    let mut pr_files = Vec::new();
     let mut successful = 0usize;
     let mut cached_count = 0usize;
     let mut failed = 0usize;
 
-    for file in request.files {
-        match process_single_file(
-            &state,
-            &file.content,
-            &file.content_hash,
-            &file.path,
-            source_language,
-            target_language,
-            request.skip_cached,
-        )
-        .await
-        {
+    for path in paths {
+        let outcome = match client.fetch_file(&repo, &git_ref, &path).await {
+            Ok(content) => process_single_file(
+                &state,
+                &api_key,
+                &FileToTranslate::plain(&path, &content),
+                &profile,
+                request.skip_cached,
+                ContentEncoding::Plain,
+                None,
+            )
+            .await
+            .map_err(|e| (Translator::compute_hash(&content), e)),
+            Err(e) => Err((String::new(), e)),
+        };
+
+        match outcome {
             Ok(result) => {
                 if result.cached {
                     cached_count += 1;
                 }
-                if result.success {
-                    successful += 1;
-                } else {
-                    failed += 1;
+                successful += 1;
+                if request.create_pr {
+                    if let Some(translated) = &result.translated_content {
+                        pr_files.push((
+                            github::translated_path(&path, target_language),
+                            translated.clone(),
+                        ));
+                    }
                 }
                 results.push(result);
             }
-            Err(e) => {
+            Err((content_hash, e)) => {
                 failed += 1;
                 results.push(FileTranslationResult {
-                    path: file.path,
+                    path,
                     success: false,
                     translated_content: None,
-                    content_hash: file.content_hash,
+                    content_hash,
                     translated_hash: None,
                     cached: false,
                     error: Some(e.to_string()),
+                    error_code: Some(e.code()),
+                    long_lines: None,
                 });
             }
         }
     }
 
+    // A failed pull request is reported next to the results, so the translations
+    // already paid for are not lost
+    let (pull_request_url, pull_request_error) = if request.create_pr && !pr_files.is_empty() {
+        let branch = format!(
+            "skill-translator/{}-{}",
+            target_language,
+            chrono::Utc::now().format("%Y%m%d%H%M%S")
+        );
+        let title = format!("Add {} translations", target_language);
+        let opened = async {
+            // Pull requests target a branch; translations of a tag or commit go to the default one
+            let base = if client.branch_exists(&repo, &git_ref).await? {
+                git_ref.clone()
+            } else {
+                client.default_branch(&repo).await?
+            };
+            client
+                .open_pull_request(&repo, &git_ref, &base, &branch, &title, &pr_files)
+                .await
+        };
+        match opened.await {
+            Ok(url) => (Some(url), None),
+            Err(e) => {
+                tracing::warn!("[{}@{}] Failed to open the pull request: {}", repo, git_ref, e);
+                (None, Some(e.to_string()))
+            }
+        }
+    } else {
+        (None, None)
+    };
+
     let processing_time = start_time.elapsed().as_millis() as f64;
 
-    Ok(Json(BatchTranslateResponse {
+    Ok(Json(GitHubTranslateResponse {
+        repo,
+        git_ref,
         results,
         total_files: successful + failed,
         successful,
         cached_count,
         failed,
         processing_time_ms: processing_time,
+        pull_request_url,
+        pull_request_error,
     }))
 }
 
-/// Process a single file for batch translation
+/// Process a single file for batch translation and record it in the audit log
 async fn process_single_file(
     state: &AppState,
-    content_encoded: &str,
-    content_hash: &str,
-    path: &str,
-    source_language: &str,
-    target_language: &str,
+    api_key: &ApiKeyId,
+    file: &FileToTranslate,
+    profile: &TranslationProfile,
     skip_cached: bool,
+    response_encoding: ContentEncoding,
+    batch: Option<&BatchCache>,
 ) -> Result<FileTranslationResult, AppError> {
-    // Decode content
-    let content = decode_content(content_encoded)?;
+    let start_time = Instant::now();
 
-    // Filter out lines exceeding 5000 characters
-    let (content, removed_count) = filter_long_lines(&content);
-    if removed_count > 0 {
-        tracing::info!(
-            "[{}] Removed {} lines exceeding {} characters",
-            path,
-            removed_count,
-            MAX_LINE_LENGTH
-        );
-    }
+    let outcome =
+        translate_single_file(state, file, profile, skip_cached, response_encoding, batch).await;
+    let content_hash = match &outcome {
+        Ok((result, _)) => result.content_hash.as_str(),
+        Err(_) => file.content_hash.as_deref().unwrap_or_default(),
+    };
+    record_audit(
+        state,
+        api_key,
+        &file.path,
+        content_hash,
+        profile,
+        start_time,
+        outcome.as_ref().map(|(_, answer)| answer),
+    )
+    .await;
 
-    // Compute cache key
-    let cache_key = state
-        .translator
-        .compute_cache_key(content_hash, source_language, target_language);
-
-    // Check cache
-    if skip_cached {
-        if let Some(cached) = state.cache.get(&cache_key).await? {
-            let encoded_cached = encode_content(&cached.translated_content);
-            return Ok(FileTranslationResult {
-                path: path.to_string(),
-                success: true,
-                translated_content: Some(encoded_cached),
-                content_hash: cached.content_hash,
-                translated_hash: Some(cached.translated_hash),
-                cached: true,
-                error: None,
-            });
-        }
-    }
-
-    // Translate
-    let (translated_content, _metadata) = state
-        .translator
-        .translate(&content, source_language, target_language)
-        .await?;
+    let (mut result, _) = outcome?;
+    apply_file_output_mode(profile.output_mode, file, response_encoding, &mut result)?;
+    Ok(result)
+}
+
+/// Decode a file, handle its overlong lines and compute its cache keys
+fn prepare_file(
+    state: &AppState,
+    settings: &Settings,
+    file: &FileToTranslate,
+    profile: &TranslationProfile,
+) -> Result<PreparedFile, AppError> {
+    let content = file.content_encoding.decode(&file.content)?;
+    state.pipeline(settings).prepare(&content, file.content_hash.as_deref(), profile)
+}
+
+/// Look up the cached translations of a batch's files at once, to be used by
+/// `process_single_file`, which also stores their new translations together
+async fn prefetch_batch(
+    state: &AppState,
+    files: &[FileToTranslate],
+    profile: &TranslationProfile,
+    skip_cached: bool,
+) -> Result<BatchCache, AppError> {
+    let settings = state.settings();
+    let lookups = if skip_cached && !profile.force {
+        // Files that cannot be prepared fail with their error when processed
+        files
+            .iter()
+            .filter_map(|file| prepare_file(state, &settings, file, profile).ok())
+            .map(|prepared| prepared.cache_keys)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    Ok(BatchCache::prefetch(state.cache.clone(), &profile.tenant, lookups).await?)
+}
+
+/// Translate one file, returning the result and, for a fresh translation, its metadata
+async fn translate_single_file(
+    state: &AppState,
+    file: &FileToTranslate,
+    profile: &TranslationProfile,
+    skip_cached: bool,
+    response_encoding: ContentEncoding,
+    batch: Option<&BatchCache>,
+) -> Result<(FileTranslationResult, Answer), AppError> {
+    let path = file.path.as_str();
+    let settings = state.settings();
+    let prepared = prepare_file(state, &settings, file, profile)?;
+    let lossy = prepared.lossy();
 
-    // Compute hash
-    let translated_hash = Translator::compute_hash(&translated_content);
+    // Check cache, unless asked to translate again
+    if skip_cached && !prepared.profile.force {
+        if let Some(cached) = state.pipeline(&settings).cached(&prepared, path, batch).await? {
+            return Ok((cached_file_result(path, cached, response_encoding, prepared.long_lines), Answer::Cached));
+        }
+    }
+    let PreparedFile {
+        content,
+        content_hash,
+        long_lines,
+        profile,
+        ..
+    } = prepared;
+    let content_hash = content_hash.as_str();
 
-    // Store in cache
-    state.cache.set(
-        &cache_key,
+    // Translate and store in cache, falling back to a translation of an earlier version
+    let (fresh, shared) = match translate_and_cache(
+        state,
+        &content,
         content_hash,
         path,
-        &translated_content,
-        &translated_hash,
-        None,
-    ).await?;
+        &profile,
+        lossy,
+        batch,
+    )
+    .await
+    {
+        Err(e @ AppError::TranslationError(_)) => {
+            let stale = stale_fallback(state, content_hash, &profile, e).await?;
+            return Ok((cached_file_result(path, stale, response_encoding, long_lines), Answer::Cached));
+        }
+        outcome => outcome?,
+    };
 
     // Encode response
-    let encoded_content = encode_content(&translated_content);
+    let encoded_content = response_encoding.encode(&fresh.translated_content);
 
-    Ok(FileTranslationResult {
+    let result = FileTranslationResult {
         path: path.to_string(),
         success: true,
         translated_content: Some(encoded_content),
         content_hash: content_hash.to_string(),
-        translated_hash: Some(translated_hash),
+        translated_hash: Some(fresh.translated_hash.clone()),
         cached: false,
         error: None,
+        error_code: None,
+        long_lines,
+    };
+
+    let answer = if shared {
+        Answer::Shared
+    } else {
+        Answer::Fresh(Box::new(fresh.metadata.clone()))
+    };
+    Ok((result, answer))
+}
+
+/// Batch result for a file answered from the cache
+fn cached_file_result(
+    path: &str,
+    cached: CacheEntry,
+    response_encoding: ContentEncoding,
+    long_lines: Option<LongLines>,
+) -> FileTranslationResult {
+    FileTranslationResult {
+        path: path.to_string(),
+        success: true,
+        translated_content: Some(response_encoding.encode(&cached.translated_content)),
+        content_hash: cached.content_hash,
+        translated_hash: Some(cached.translated_hash),
+        cached: true,
+        error: None,
+        error_code: None,
+        long_lines,
+    }
+}
+
+/// Translate content, store it in the cache and queue it for review if it looks off.
+/// `lossy` marks content that lost overlong lines, cached only with `CACHE_LOSSY_TRANSLATIONS`.
+/// Translations of a batch's files are queued in its `batch` cache.
+///
+/// Concurrent calls for the same cache key share one upstream translation: the
+/// first call translates and the others wait for its result. Returns whether the
+/// result came from another request.
+async fn translate_and_cache(
+    state: &AppState,
+    content: &str,
+    content_hash: &str,
+    path: &str,
+    profile: &TranslationProfile,
+    lossy: bool,
+    batch: Option<&BatchCache>,
+) -> Result<(Arc<FreshTranslation>, bool), AppError> {
+    let key = state.translator.compute_cache_key(content_hash, profile);
+
+    let (outcome, shared) = state
+        .in_flight
+        .run(&key, "", || async {
+            translate_uncached(state, content, content_hash, path, profile, lossy, batch)
+                .await
+                .map(Arc::new)
+                .map_err(Arc::new)
+        })
+        .await
+        .map_err(|_| AppError::Internal("In-flight translation key conflict".to_string()))?;
+
+    if shared {
+        tracing::info!("[{}] Shared in-flight translation of identical content", path);
+    }
+
+    match outcome {
+        Ok(fresh) => Ok((fresh, shared)),
+        Err(e) => Err(Arc::try_unwrap(e).unwrap_or_else(|e| shared_error(&e))),
+    }
+}
+
+/// Translate content and store the result, without deduplication, within the tenant's
+/// quota and the budget, remembering its terms and queueing it for review if it looks off
+async fn translate_uncached(
+    state: &AppState,
+    content: &str,
+    content_hash: &str,
+    path: &str,
+    profile: &TranslationProfile,
+    lossy: bool,
+    batch: Option<&BatchCache>,
+) -> Result<FreshTranslation, AppError> {
+    check_quota(state, &profile.tenant).await?;
+    state.budget.check(&state.settings()).await?;
+
+    // A client that disconnects drops this future, and with it the upstream stream and permit
+    let mut cancelled = CancelledTranslation {
+        path,
+        finished: false,
+    };
+
+    // Keep the wording earlier translations of the skill chose
+    let settings = state.settings();
+    let skill = terminology_memory::skill_of(path);
+    let remembered;
+    let profile = if settings.terminology_memory {
+        let mut terms = profile.terminology.clone();
+        terminology::merge(&mut terms, state.terminology.terms(&profile.into(), skill).await?);
+        remembered = TranslationProfile {
+            terminology: terms,
+            ..profile.clone()
+        };
+        &remembered
+    } else {
+        profile
+    };
+
+    // Translate and store the result
+    let translation = state
+        .pipeline(&settings)
+        .translate(content, content_hash, path, profile, lossy, batch)
+        .await;
+    cancelled.finished = true;
+    let Translated {
+        translated_content,
+        translated_hash,
+        cache_key,
+        metadata,
+        stored,
+    } = translation?;
+
+    if settings.terminology_memory {
+        let terms = terminology::extract_terms(content, &translated_content);
+        state.terminology.record(&profile.into(), skill, &terms).await;
+    }
+    if stored && settings.store_sources {
+        state.sources.record(&profile.tenant, content_hash, content).await;
+    }
+
+    // Queue suspicious and low-scoring translations for review
+    state
+        .reviews
+        .check_translation(&cache_key, path, content_hash, content, &translated_content)
+        .await;
+    if let Some(quality) = &metadata.quality {
+        state
+            .reviews
+            .check_quality(&cache_key, path, content_hash, quality)
+            .await;
+    }
+
+    Ok(FreshTranslation {
+        translated_content,
+        translated_hash,
+        metadata,
     })
 }
 
-/// Get cache statistics
+/// Logs a translation dropped before it finished, e.g. because its client went away
+struct CancelledTranslation<'a> {
+    path: &'a str,
+    finished: bool,
+}
+
+impl Drop for CancelledTranslation<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            tracing::info!("[{}] Translation cancelled before it finished", self.path);
+        }
+    }
+}
+
+/// Rebuild an error shared between deduplicated requests.
+/// Translation errors keep their type so they map to the same status code.
+fn shared_error(error: &AppError) -> AppError {
+    match error {
+        AppError::TranslationError(e) => AppError::TranslationError(e.clone()),
+        AppError::QuotaExceeded(msg) => AppError::QuotaExceeded(msg.clone()),
+        AppError::BudgetExceeded(msg) => AppError::BudgetExceeded(msg.clone()),
+        other => AppError::Internal(other.to_string()),
+    }
+}
+
+/// Get the tenant's cache statistics, reused for CACHE_STATS_TTL_SECONDS unless `fresh` is set.
+/// The `Age` header gives how many seconds ago they were computed.
+#[utoipa::path(
+    get, path = "/api/cache/stats", tag = "cache",
+    params(CacheStatsQuery),
+    responses((status = 200, body = CacheStats, headers(("Age" = u64, description = "Seconds since the statistics were computed"))))
+)]
 pub async fn get_cache_stats(
     State(state): State<AppState>,
-) -> Result<Json<CacheStats>, AppError> {
-    let stats = state.cache.get_stats().await?;
+    Extension(tenant): Extension<Tenant>,
+    Query(query): Query<CacheStatsQuery>,
+) -> Result<Response, AppError> {
+    let (stats, age) = if query.fresh {
+        (state.cache.get_stats(&tenant.0).await?, Duration::ZERO)
+    } else {
+        let ttl = Duration::from_secs(state.settings().cache_stats_ttl_seconds);
+        state.cache.get_recent_stats(&tenant.0, ttl).await?
+    };
+    Ok(([(header::AGE, HeaderValue::from(age.as_secs()))], Json(stats)).into_response())
+}
+
+/// Get the tenant's cache statistics grouped by language, path prefix and model
+#[utoipa::path(
+    get, path = "/api/cache/stats/detailed", tag = "cache",
+    responses((status = 200, body = DetailedCacheStats))
+)]
+pub async fn get_detailed_cache_stats(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+) -> Result<Json<DetailedCacheStats>, AppError> {
+    let stats = state.cache.get_detailed_stats(&tenant.0).await?;
     Ok(Json(stats))
 }
 
-/// Clear cache endpoint
+/// Browse the tenant's cached translations, without their content
+#[utoipa::path(
+    get, path = "/api/cache/entries", tag = "cache",
+    params(CacheListQuery),
+    responses((status = 200, body = CacheEntryPage))
+)]
+pub async fn list_cache_entries(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    Query(query): Query<CacheListQuery>,
+) -> Result<Json<CacheEntryPage>, AppError> {
+    let filter = CacheFilter {
+        path_prefix: query.path_prefix.filter(|prefix| !prefix.is_empty()),
+        target_language: query.language.filter(|language| !language.is_empty()),
+        order: query.order_by.unwrap_or_default(),
+        limit: query
+            .limit
+            .unwrap_or(DEFAULT_CACHE_PAGE_SIZE)
+            .clamp(1, MAX_CACHE_PAGE_SIZE),
+        offset: query.offset.unwrap_or(0).max(0),
+    };
+
+    let (entries, total) = state.cache.list(&tenant.0, &filter).await?;
+    Ok(Json(CacheEntryPage {
+        entries,
+        total,
+        limit: filter.limit,
+        offset: filter.offset,
+    }))
+}
+
+/// Get the tenant's cached translations for a path or content hash.
+/// Translated content is base64 encoded like the translate endpoints.
+#[utoipa::path(
+    get, path = "/api/cache/entry", tag = "cache",
+    params(CacheEntryQuery),
+    responses(
+        (status = 200, description = "Matching entries with base64 encoded translations", body = Vec<CacheEntry>),
+        (status = 400, description = "Not exactly one of path and content_hash given", body = ErrorResponse),
+    )
+)]
+pub async fn get_cache_entry(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    Query(query): Query<CacheEntryQuery>,
+) -> Result<Json<Vec<CacheEntry>>, AppError> {
+    let entries = match (&query.path, &query.content_hash) {
+        (Some(path), None) => state.cache.get_by_path(&tenant.0, path).await?,
+        (None, Some(content_hash)) => state.cache.get_by_content_hash(&tenant.0, content_hash).await?,
+        _ => {
+            return Err(AppError::BadRequest(
+                "Exactly one of 'path' or 'content_hash' is required".to_string(),
+            ))
+        }
+    };
+
+    Ok(Json(
+        entries
+            .into_iter()
+            .map(|mut entry| {
+                entry.translated_content = encode_content(&entry.translated_content);
+                entry
+            })
+            .collect(),
+    ))
+}
+
+/// Compare one of the tenant's cached translations with its original, segment by segment.
+/// Requires the original, which is stored with new translations while STORE_SOURCES is enabled.
+#[utoipa::path(
+    get, path = "/api/cache/entry/{cache_key}/diff", tag = "cache",
+    params(("cache_key" = String, Path), CacheEntryDiffQuery),
+    responses(
+        (status = 200, description = "HTML page or unified diff of the original and the translation", content_type = "text/html", body = String),
+        (status = 404, description = "No such entry, or its original was not stored", body = ErrorResponse),
+    )
+)]
+pub async fn get_cache_entry_diff(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    Path(cache_key): Path<String>,
+    Query(query): Query<CacheEntryDiffQuery>,
+) -> Result<Response, AppError> {
+    let entry = state
+        .cache
+        .get_by_key(&tenant.0, &cache_key)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("No cache entry '{}'", cache_key)))?;
+    let original = state
+        .sources
+        .get(&tenant.0, &entry.content_hash)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("The original of cache entry '{}' was not stored", cache_key)))?;
+
+    let format = query.format.unwrap_or_default();
+    let target_language = entry
+        .metadata
+        .get("target_language")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    let artifact = diff::render(format, &entry.path, target_language, &original, &entry.translated_content);
+    Ok(([(header::CONTENT_TYPE, format.content_type())], artifact).into_response())
+}
+
+/// Delete the tenant's cached translations for a path or content hash
+#[utoipa::path(
+    delete, path = "/api/cache/entry", tag = "cache",
+    params(CacheEntryQuery),
+    responses(
+        (status = 200, description = "Number of deleted entries", body = Object),
+        (status = 400, body = ErrorResponse),
+    )
+)]
+pub async fn delete_cache_entry(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
+    Query(query): Query<CacheEntryQuery>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let deleted = match (&query.path, &query.content_hash) {
+        (Some(path), None) => state.cache.delete_by_path(&tenant.0, path).await?,
+        (None, Some(content_hash)) => {
+            state.sources.delete(&tenant.0, content_hash).await?;
+            state.cache.delete_by_content_hash(&tenant.0, content_hash).await?
+        }
+        _ => {
+            return Err(AppError::BadRequest(
+                "Exactly one of 'path' or 'content_hash' is required".to_string(),
+            ))
+        }
+    };
+
+    Ok(Json(json!({
+        "message": format!("Deleted {} entries", deleted),
+        "deleted": deleted
+    })))
+}
+
+/// Delete the tenant's cached translations under a path prefix, as when a publisher rewrites
+/// a whole skill collection, optionally queueing background batch jobs that translate their
+/// stored originals again with the entries' languages and default options
+#[utoipa::path(
+    post, path = "/api/cache/invalidate", tag = "cache",
+    request_body = CacheInvalidateRequest,
+    responses(
+        (status = 200, body = CacheInvalidateResponse),
+        (status = 400, body = ErrorResponse),
+    )
+)]
+pub async fn invalidate_cache(
+    State(state): State<AppState>,
+    Extension(api_key): Extension<ApiKeyId>,
+    Extension(tenant): Extension<Tenant>,
+    Json(request): Json<CacheInvalidateRequest>,
+) -> Result<Json<CacheInvalidateResponse>, AppError> {
+    if request.path_prefix.is_empty() {
+        return Err(AppError::BadRequest(
+            "'path_prefix' must not be empty; use DELETE /api/cache to clear the whole cache".to_string(),
+        ));
+    }
+    let deleted = state.cache.delete_by_path_prefix(&tenant.0, &request.path_prefix).await?;
+
+    let mut response = CacheInvalidateResponse {
+        deleted: deleted.len(),
+        jobs: Vec::new(),
+        queued_files: 0,
+        missing_sources: 0,
+    };
+    if !request.retranslate {
+        // Keep originals still shared with entries elsewhere
+        let hashes: BTreeSet<&str> = deleted.iter().map(|entry| entry.content_hash.as_str()).collect();
+        for content_hash in hashes {
+            if state.cache.get_by_content_hash(&tenant.0, content_hash).await?.is_empty() {
+                state.sources.delete(&tenant.0, content_hash).await?;
+            }
+        }
+        return Ok(Json(response));
+    }
+
+    // One job per language pair, each file once
+    let mut files: BTreeMap<(String, String), Vec<FileToTranslate>> = BTreeMap::new();
+    let mut seen = BTreeSet::new();
+    for entry in &deleted {
+        let languages = (entry.source_language.clone(), entry.target_language.clone());
+        if !seen.insert((entry.path.clone(), entry.content_hash.clone(), languages.clone())) {
+            continue;
+        }
+        match state.sources.get(&tenant.0, &entry.content_hash).await? {
+            Some(original) => files
+                .entry(languages)
+                .or_default()
+                .push(FileToTranslate::plain(&entry.path, &original)),
+            None => response.missing_sources += 1,
+        }
+    }
+
+    for ((source_language, target_language), files) in files {
+        let options = TranslateOptions {
+            source_language: Some(source_language),
+            target_language: Some(target_language),
+            ..TranslateOptions::default()
+        };
+        let job = state
+            .batch_jobs
+            .create(&tenant.0, &api_key.0, &files, Some(&options), false)
+            .await?;
+        tracing::info!("Batch job {} queued to retranslate {} invalidated files", job.id, files.len());
+        response.queued_files += files.len();
+        response.jobs.push(job.id.clone());
+        spawn_batch_job(&state, job);
+    }
+    Ok(Json(response))
+}
+
+/// Clear the tenant's cache entries
+#[utoipa::path(
+    delete, path = "/api/cache", tag = "cache",
+    responses((status = 200, description = "Number of cleared entries", body = Object))
+)]
 pub async fn clear_cache(
     State(state): State<AppState>,
+    Extension(tenant): Extension<Tenant>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    let cleared = state.cache.clear_all().await?;
+    let cleared = state.cache.clear_tenant(&tenant.0).await?;
+    state.terminology.clear(&tenant.0).await?;
+    state.sources.clear(&tenant.0).await?;
     Ok(Json(json!({
         "message": format!("Cleared all {} entries", cleared)
     })))
 }
 
 /// Clear expired cache entries
+#[utoipa::path(
+    delete, path = "/api/cache/expired", tag = "cache",
+    responses((status = 200, description = "Number of cleared entries", body = Object))
+)]
 pub async fn clear_expired_cache(
     State(state): State<AppState>,
 ) -> Result<Json<serde_json::Value>, AppError> {
@@ -407,6 +2642,10 @@ pub async fn clear_expired_cache(
 }
 
 /// Flush pending hit count updates
+#[utoipa::path(
+    post, path = "/api/cache/flush", tag = "cache",
+    responses((status = 200, body = Object))
+)]
 pub async fn flush_cache_hits(
     State(state): State<AppState>,
 ) -> Result<Json<serde_json::Value>, AppError> {
@@ -414,4 +2653,699 @@ pub async fn flush_cache_hits(
     Ok(Json(json!({
         "message": "Flushed pending hits"
     })))
-}
\ No newline at end of file
+}
+
+/// Checkpoint and vacuum the cache database.
+/// Refused while translations are running, since they write to the cache.
+#[utoipa::path(
+    post, path = "/api/admin/cache/compact", tag = "admin",
+    responses(
+        (status = 200, body = CompactStats),
+        (status = 409, description = "Translations are running", body = ErrorResponse),
+    )
+)]
+pub async fn compact_cache(State(state): State<AppState>) -> Result<Json<CompactStats>, AppError> {
+    let running = state.in_flight.in_flight();
+    if running > 0 {
+        return Err(AppError::Conflict(format!(
+            "{} translations are running; compact the cache when the service is idle",
+            running
+        )));
+    }
+    Ok(Json(state.cache.compact().await?))
+}
+
+/// Purge or mark the cached translations of translator versions other than the configured one,
+/// like `STALE_VERSION_POLICY` does at startup
+#[utoipa::path(
+    post, path = "/api/admin/cache/versions", tag = "admin",
+    request_body = RetireVersionsRequest,
+    responses((status = 200, body = RetireVersionsResponse))
+)]
+pub async fn retire_cache_versions(
+    State(state): State<AppState>,
+    Json(request): Json<RetireVersionsRequest>,
+) -> Result<Json<RetireVersionsResponse>, AppError> {
+    let translator_version = state.settings().translator_version.clone();
+    let entries = state
+        .cache
+        .retire_versions(&translator_version, request.policy)
+        .await?;
+    Ok(Json(RetireVersionsResponse {
+        policy: request.policy,
+        translator_version,
+        entries,
+    }))
+}
+
+/// Clear cache entries not accessed for `stale_days`, blobs no entry references any more
+/// and completed batch jobs past their retention, as the scheduled cleanup does
+pub async fn cleanup(state: &AppState, stale_days: i64) -> Result<CleanupResult, AppError> {
+    let removed_entries = state.cache.clear_stale(stale_days).await?;
+    // Entries evicted since the last run, by any means, leave their blobs to this
+    let removed_blobs = match state.cache.collect_blobs().await {
+        Ok(removed) => removed,
+        Err(e) => {
+            tracing::warn!("Failed to collect unreferenced translation blobs: {}", e);
+            0
+        }
+    };
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(state.settings().batch_job_retention_days);
+    let removed_batch_jobs = state.batch_jobs.clear_finished_before(cutoff).await?;
+    tracing::info!("Removed {} completed batch jobs", removed_batch_jobs);
+    Ok(CleanupResult {
+        stale_days,
+        removed_entries,
+        removed_blobs,
+        removed_batch_jobs,
+    })
+}
+
+/// Run the cache cleanup now, optionally with another staleness threshold
+#[utoipa::path(
+    post, path = "/api/admin/cleanup", tag = "admin",
+    request_body = CleanupRequest,
+    responses(
+        (status = 200, body = CleanupResult),
+        (status = 400, body = ErrorResponse),
+    )
+)]
+pub async fn run_cleanup(
+    State(state): State<AppState>,
+    Json(request): Json<CleanupRequest>,
+) -> Result<Json<CleanupResult>, AppError> {
+    let stale_days = request.stale_days.unwrap_or(state.settings().cache_stale_days);
+    if stale_days <= 0 {
+        return Err(AppError::BadRequest("'stale_days' must be positive".to_string()));
+    }
+    Ok(Json(cleanup(&state, stale_days).await?))
+}
+
+/// Recent API exchanges with content and credentials redacted, newest first
+#[utoipa::path(
+    get, path = "/api/admin/recent-requests", tag = "admin",
+    params(RecentRequestsQuery),
+    responses((status = 200, body = RecentRequests))
+)]
+pub async fn list_recent_requests(
+    State(state): State<AppState>,
+    Query(query): Query<RecentRequestsQuery>,
+) -> Json<RecentRequests> {
+    let settings = state.settings();
+    Json(RecentRequests {
+        enabled: settings.debug_payload_log,
+        requests: state.payload_log.recent(query.limit.unwrap_or(settings.debug_payload_log_size)),
+    })
+}
+
+/// Back up the cache database now
+#[utoipa::path(
+    post, path = "/api/admin/backup", tag = "admin",
+    responses((status = 200, body = BackupResult))
+)]
+pub async fn run_backup(State(state): State<AppState>) -> Result<Json<BackupResult>, AppError> {
+    let result = state.backups.run(&state.settings(), state.cache.pool()).await?;
+    Ok(Json(result))
+}
+
+/// Models offered by the upstream server, passed through as it lists them
+#[utoipa::path(
+    get, path = "/api/admin/models", tag = "admin",
+    responses(
+        (status = 200, description = "Model list of the upstream server", body = Object),
+        (status = 500, body = ErrorResponse),
+    )
+)]
+pub async fn list_models(State(state): State<AppState>) -> Result<Json<serde_json::Value>, AppError> {
+    Ok(Json(state.translator.list_models().await?))
+}
+
+/// Token and cost usage against the service-wide budgets
+#[utoipa::path(
+    get, path = "/api/admin/budget", tag = "admin",
+    responses((status = 200, body = BudgetStatus))
+)]
+pub async fn get_budget(State(state): State<AppState>) -> Result<Json<BudgetStatus>, AppError> {
+    Ok(Json(state.budget.status(&state.settings()).await?))
+}
+
+/// Zero today's and this month's usage, lifting an exhausted budget
+#[utoipa::path(
+    post, path = "/api/admin/budget/reset", tag = "admin",
+    responses((status = 200, body = BudgetStatus))
+)]
+pub async fn reset_budget(State(state): State<AppState>) -> Result<Json<BudgetStatus>, AppError> {
+    state.budget.reset().await?;
+    Ok(Json(state.budget.status(&state.settings()).await?))
+}
+
+/// Reload configuration without restarting the server
+#[utoipa::path(
+    post, path = "/api/admin/reload-config", tag = "admin",
+    responses(
+        (status = 200, body = Object),
+        (status = 400, description = "Invalid configuration; the old one stays active", body = ErrorResponse),
+    )
+)]
+pub async fn reload_config(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let settings = state
+        .reload_settings()
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    Ok(Json(json!({
+        "message": "Configuration reloaded",
+        "models": state.translator.models(),
+        "max_tokens": settings.max_tokens,
+        "max_concurrent_translations": settings.max_concurrent_translations,
+        "concurrency": state.translator.concurrency(),
+        "interactive_share": settings.interactive_share,
+        "translation_timeout_seconds": settings.translation_timeout_seconds,
+    })))
+}
+
+/// List audit log entries for compliance reporting, oldest first
+#[utoipa::path(
+    get, path = "/api/admin/audit", tag = "admin",
+    params(AuditQuery),
+    responses((status = 200, body = AuditPage))
+)]
+pub async fn list_audit(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditPage>, AppError> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_AUDIT_PAGE_SIZE)
+        .clamp(1, MAX_AUDIT_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let (entries, total) = state.audit.list(query.since, limit, offset).await?;
+    Ok(Json(AuditPage {
+        entries,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+/// List tenants with an API key, cached entries or translations today, with their usage
+#[utoipa::path(
+    get, path = "/api/admin/tenants", tag = "admin",
+    responses((status = 200, body = TenantList))
+)]
+pub async fn list_tenants(State(state): State<AppState>) -> Result<Json<TenantList>, AppError> {
+    let settings = state.settings();
+    let translations_today = state.audit.fresh_translations(start_of_day()).await?;
+
+    let summary = |name: &str| TenantSummary {
+        name: name.to_string(),
+        has_api_key: settings.tenant_api_keys.iter().any(|(tenant, _)| tenant == name),
+        cache_entries: 0,
+        cache_size_bytes: 0,
+        cache_hits: 0,
+        translations_today: translations_today.get(name).copied().unwrap_or(0),
+        daily_quota: (settings.tenant_daily_quota > 0).then_some(settings.tenant_daily_quota),
+    };
+
+    let mut tenants: BTreeMap<String, TenantSummary> = settings
+        .tenant_api_keys
+        .iter()
+        .map(|(tenant, _)| tenant)
+        .chain(translations_today.keys())
+        .map(|tenant| (tenant.clone(), summary(tenant)))
+        .collect();
+    for stats in state.cache.get_tenant_stats().await? {
+        let tenant = tenants
+            .entry(stats.key.clone())
+            .or_insert_with(|| summary(&stats.key));
+        tenant.cache_entries = stats.entries;
+        tenant.cache_size_bytes = stats.size_bytes;
+        tenant.cache_hits = stats.total_hits;
+    }
+
+    Ok(Json(TenantList {
+        tenants: tenants.into_values().collect(),
+    }))
+}
+
+/// List the built-in prompt template and the saved overrides
+#[utoipa::path(
+    get, path = "/api/admin/prompts", tag = "prompts",
+    responses((status = 200, body = PromptList))
+)]
+pub async fn list_prompts(State(state): State<AppState>) -> Result<Json<PromptList>, AppError> {
+    Ok(Json(PromptList {
+        builtin: PromptTemplate::builtin(),
+        templates: state.prompts.list().await?,
+    }))
+}
+
+/// Get the active version of a prompt template
+#[utoipa::path(
+    get, path = "/api/admin/prompts/{name}", tag = "prompts",
+    params(("name" = String, Path)),
+    responses(
+        (status = 200, body = PromptTemplate),
+        (status = 404, body = ErrorResponse),
+    )
+)]
+pub async fn get_prompt(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<PromptTemplate>, AppError> {
+    Ok(Json(state.prompts.get(&name).await?))
+}
+
+/// List every saved version of a prompt template, newest first
+#[utoipa::path(
+    get, path = "/api/admin/prompts/{name}/versions", tag = "prompts",
+    params(("name" = String, Path)),
+    responses(
+        (status = 200, body = Vec<PromptTemplate>),
+        (status = 404, body = ErrorResponse),
+    )
+)]
+pub async fn list_prompt_versions(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<PromptTemplate>>, AppError> {
+    Ok(Json(state.prompts.versions(&name).await?))
+}
+
+/// Create or update a prompt template. Each save is a new version, so translations
+/// it applies to get new cache keys and are translated again on their next request.
+#[utoipa::path(
+    put, path = "/api/admin/prompts/{name}", tag = "prompts",
+    params(("name" = String, Path)),
+    request_body = SavePromptRequest,
+    responses(
+        (status = 200, body = PromptTemplate),
+        (status = 400, body = ErrorResponse),
+        (status = 409, description = "Saved prompt templates are off", body = ErrorResponse),
+    )
+)]
+pub async fn save_prompt(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(request): Json<SavePromptRequest>,
+) -> Result<Json<PromptTemplate>, AppError> {
+    prompt_templates_enabled(&state)?;
+    let template = state.prompts.save(&name, &request).await?;
+    state.reload_prompts().await?;
+    Ok(Json(template))
+}
+
+/// Delete a prompt template; translations it applied to fall back to other templates
+#[utoipa::path(
+    delete, path = "/api/admin/prompts/{name}", tag = "prompts",
+    params(("name" = String, Path)),
+    responses(
+        (status = 200, body = Object),
+        (status = 404, body = ErrorResponse),
+        (status = 409, description = "Saved prompt templates are off", body = ErrorResponse),
+    )
+)]
+pub async fn delete_prompt(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    prompt_templates_enabled(&state)?;
+    state.prompts.delete(&name).await?;
+    state.reload_prompts().await?;
+    Ok(Json(json!({ "deleted": name })))
+}
+
+/// Refuse template changes that would not be applied
+fn prompt_templates_enabled(state: &AppState) -> Result<(), AppError> {
+    if state.settings().prompt_templates {
+        return Ok(());
+    }
+    Err(AppError::Conflict(
+        "Saved prompt templates are off; set PROMPT_TEMPLATES=true to use them".to_string(),
+    ))
+}
+
+/// Manually flag a cached translation for review
+#[utoipa::path(
+    post, path = "/api/reviews", tag = "reviews",
+    request_body = CreateReviewRequest,
+    responses(
+        (status = 200, body = ReviewItem),
+        (status = 404, description = "No cache entry with that key", body = ErrorResponse),
+    )
+)]
+pub async fn create_review(
+    State(state): State<AppState>,
+    Json(request): Json<CreateReviewRequest>,
+) -> Result<Json<ReviewItem>, AppError> {
+    let review = state.reviews.flag(&request.cache_key, &request.reason).await?;
+    Ok(Json(review))
+}
+
+/// List reviews, pending ones by default
+#[utoipa::path(
+    get, path = "/api/reviews", tag = "reviews",
+    params(ReviewQuery),
+    responses((status = 200, body = Vec<ReviewItem>))
+)]
+pub async fn list_reviews(
+    State(state): State<AppState>,
+    Query(query): Query<ReviewQuery>,
+) -> Result<Json<Vec<ReviewItem>>, AppError> {
+    let status = match query.status.as_deref() {
+        None => Some(STATUS_PENDING),
+        Some("all") => None,
+        Some(status) => Some(status),
+    };
+    let reviews = state.reviews.list(status).await?;
+    Ok(Json(reviews))
+}
+
+/// Resolve a review, optionally replacing the cached translation with a correction
+#[utoipa::path(
+    post, path = "/api/reviews/{id}/resolve", tag = "reviews",
+    params(("id" = i64, Path)),
+    request_body = ResolveReviewRequest,
+    responses(
+        (status = 200, body = ReviewItem),
+        (status = 404, description = "No pending review with that id", body = ErrorResponse),
+    )
+)]
+pub async fn resolve_review(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(request): Json<ResolveReviewRequest>,
+) -> Result<Json<ReviewItem>, AppError> {
+    let review = state.reviews.get(id).await?;
+
+    if let Some(encoded) = &request.translated_content {
+        let corrected = decode_content(encoded)?;
+        let corrected_hash = Translator::compute_hash(&corrected);
+        let replaced = state
+            .cache
+            .replace_translation(&review.cache_key, &corrected, &corrected_hash)
+            .await?;
+        if !replaced {
+            return Err(AppError::NotFound(format!(
+                "Cache entry {} no longer exists",
+                review.cache_key
+            )));
+        }
+    }
+
+    let review = state.reviews.resolve(id, request.note.as_deref()).await?;
+    Ok(Json(review))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_response_metadata() {
+        let stored = json!({
+            "model": "gpt-4o-mini",
+            "chunks": 2,
+            "quality": null,
+            "tenant": "registry-a",
+            "lossy": false,
+            "stale_version": true,
+        });
+        let metadata = TranslationResponseMetadata::from_stored(stored);
+        assert_eq!((metadata.model.as_str(), metadata.chunks), ("gpt-4o-mini", 2));
+        assert_eq!((metadata.tenant.as_deref(), metadata.lossy), (Some("registry-a"), Some(false)));
+        // Fields this version does not know are passed through
+        assert_eq!(metadata.extra.get("stale_version"), Some(&json!(true)));
+        let json = metadata.to_stored();
+        assert_eq!((json["stale_version"].clone(), json["roundtrip"].clone()), (json!(true), json!(null)));
+        assert!(json.get("total_processing_time_ms").is_none());
+
+        assert_eq!(TranslationResponseMetadata::from_stored(json!(null)), TranslationResponseMetadata::default());
+    }
+
+    #[test]
+    fn test_force_option() {
+        let settings = Settings::from_vars(|key| match key {
+            "SOURCE_LANGUAGE" => Some("ja".to_string()),
+            "TARGET_LANGUAGE" => Some("en".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        let tenant = Tenant::default();
+        let options: TranslateOptions = serde_json::from_str(r#"{"force": true}"#).unwrap();
+        let forced = resolve_profile(&settings, Some(&options), &tenant, Priority::Interactive).unwrap();
+        assert!(forced.force);
+        assert_eq!((forced.source_language.as_str(), forced.target_language.as_str()), ("ja", "en"));
+
+        let profile = resolve_profile(&settings, None, &tenant, Priority::Interactive).unwrap();
+        assert!(!profile.force);
+
+        // The forced translation is stored under the key of the entry it refreshes
+        let translator = Translator::new(settings.translator_config());
+        let keys = translator.cache_keys("sha256:x", &profile);
+        assert_eq!(translator.cache_keys("sha256:x", &forced), keys);
+        assert_eq!(translator.cache_key_for_model("sha256:x", &forced, &settings.openai_model), keys[0]);
+    }
+
+    /// An OpenAI-compatible upstream that answers every chat completion after `delay` with the
+    /// last message upper-cased, counting the calls in `calls`
+    fn mock_upstream(delay: Duration, calls: &'static AtomicUsize) -> String {
+        use std::io::{BufRead, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                std::thread::spawn(move || {
+                    let mut reader = std::io::BufReader::new(&stream);
+                    let mut length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line.trim().is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                length = value.trim().parse().unwrap();
+                            }
+                        }
+                    }
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                    let text = request["messages"].as_array().unwrap().last().unwrap()["content"]
+                        .as_str()
+                        .unwrap()
+                        .to_uppercase();
+
+                    std::thread::sleep(delay);
+                    let chunk = json!({
+                        "id": "mock",
+                        "object": "chat.completion.chunk",
+                        "created": 0,
+                        "model": request["model"],
+                        "choices": [{"index": 0, "delta": {"content": text}, "finish_reason": null}],
+                    });
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nconnection: close\r\n\r\ndata: {}\n\ndata: [DONE]\n\n",
+                        chunk
+                    );
+                    let _ = (&stream).write_all(response.as_bytes());
+                });
+            }
+        });
+        base_url
+    }
+
+    #[tokio::test]
+    async fn test_identical_translations_share_one_upstream_call() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!("skillts-in-flight-{}", std::process::id()));
+        let base_url = mock_upstream(Duration::from_millis(300), &CALLS);
+        let db_path = dir.join("cache.db").to_string_lossy().into_owned();
+        let settings = Settings::from_vars(|key| match key {
+            "OPENAI_API_KEY" => Some("sk-test".to_string()),
+            "OPENAI_BASE_URL" => Some(base_url.clone()),
+            "CACHE_DB_PATH" => Some(db_path.clone()),
+            _ => None,
+        })
+        .unwrap();
+
+        let cache = Arc::new(TranslationCache::new(settings.cache_config()).await.unwrap());
+        let pool = cache.pool().clone();
+        let state = AppState {
+            translator: Arc::new(Translator::new(settings.translator_config())),
+            reviews: Arc::new(ReviewQueue::new(pool.clone(), &settings).await.unwrap()),
+            audit: Arc::new(AuditLog::new(pool.clone()).await.unwrap()),
+            prompts: Arc::new(PromptStore::new(pool.clone()).await.unwrap()),
+            backups: Arc::new(Backups::new().unwrap()),
+            batch_jobs: Arc::new(BatchJobs::new(pool.clone()).await.unwrap()),
+            budget: Arc::new(Budget::new(pool.clone()).await.unwrap()),
+            terminology: Arc::new(TerminologyStore::new(pool.clone()).await.unwrap()),
+            sources: Arc::new(SourceStore::new(pool).await.unwrap()),
+            idempotency: Arc::new(StoredResponse::store(Duration::from_secs(60))),
+            in_flight: Arc::new(in_flight_translations()),
+            settings: Arc::new(ArcSwap::from_pointee(settings.clone())),
+            api_bearer: String::new(),
+            readiness: Arc::new(Readiness::default()),
+            payload_log: Arc::new(PayloadLog::default()),
+            cache,
+        };
+        let profile = resolve_profile(&settings, None, &Tenant::default(), Priority::Interactive).unwrap();
+        let content = "# Demo\n\nHello world.\n";
+        let content_hash = Translator::compute_hash(content);
+
+        // Files with identical content, e.g. under two paths, are translated once
+        let (first, second) = futures::future::join(
+            translate_and_cache(&state, content, &content_hash, "a/SKILL.md", &profile, false, None),
+            translate_and_cache(&state, content, &content_hash, "b/SKILL.md", &profile, false, None),
+        )
+        .await;
+        let ((first, first_shared), (second, second_shared)) = (first.unwrap(), second.unwrap());
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+        assert!(first_shared != second_shared);
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(first.translated_content.contains("HELLO WORLD"));
+        assert_eq!(state.in_flight.in_flight(), 0);
+
+        // Nothing is kept once the translation finished: a later identical request runs on its own
+        let (_, shared) = translate_and_cache(&state, content, &content_hash, "a/SKILL.md", &profile, false, None)
+            .await
+            .unwrap();
+        assert!(!shared);
+
+        state.cache.close().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_plain_content_encoding() {
+        let request: TranslateRequest = serde_json::from_value(json!({
+            "content": "# 标题\n",
+            "content_encoding": "plain",
+            "path": "SKILL.md",
+            "options": {"response_encoding": "plain"},
+        }))
+        .unwrap();
+        assert_eq!(request.content_encoding.decode(&request.content).unwrap(), "# 标题\n");
+        let encoding = response_encoding(request.options.as_ref());
+        assert_eq!((encoding, encoding.encode("# Title\n").as_str()), (ContentEncoding::Plain, "# Title\n"));
+
+        // Content is base64 unless the request says otherwise
+        let request: TranslateRequest = serde_json::from_value(json!({
+            "content": encode_content("# 标题\n"),
+            "path": "SKILL.md",
+            "options": {},
+        }))
+        .unwrap();
+        assert_eq!(request.content_encoding, ContentEncoding::Base64);
+        assert_eq!(request.content_encoding.decode(&request.content).unwrap(), "# 标题\n");
+        assert_eq!(response_encoding(request.options.as_ref()), ContentEncoding::Base64);
+        assert_eq!(response_encoding(None).encode("# Title\n"), encode_content("# Title\n"));
+        assert!(ContentEncoding::Base64.decode("# 标题").is_err());
+        assert!(serde_json::from_value::<ContentEncoding>(json!("utf8")).is_err());
+    }
+
+    #[test]
+    fn test_keep_terms_option() {
+        let settings = Settings::from_vars(|key| (key == "KEEP_TERMS").then(|| "OpenClaw, ClawHub".to_string())).unwrap();
+        let tenant = Tenant::default();
+        let options: TranslateOptions = serde_json::from_str(r#"{"keep_terms": ["MCP"]}"#).unwrap();
+        let profile = resolve_profile(&settings, Some(&options), &tenant, Priority::Interactive).unwrap();
+        assert_eq!(profile.keep_terms, vec!["OpenClaw", "ClawHub", "MCP"]);
+        let profile = resolve_profile(&settings, None, &tenant, Priority::Interactive).unwrap();
+        assert_eq!(profile.keep_terms, vec!["OpenClaw", "ClawHub"]);
+
+        let options = TranslateOptions {
+            keep_terms: vec!["term".to_string(); MAX_KEEP_TERMS + 1],
+            ..TranslateOptions::default()
+        };
+        assert!(matches!(
+            resolve_profile(&settings, Some(&options), &tenant, Priority::Interactive),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        assert!(if_none_match(&headers("\"sha256:abc\""), "sha256:abc"));
+        assert!(if_none_match(&headers("\"sha256:old\", W/\"sha256:abc\""), "sha256:abc"));
+        assert!(if_none_match(&headers("*"), "sha256:abc"));
+        assert!(!if_none_match(&headers("\"sha256:old\""), "sha256:abc"));
+        assert!(!if_none_match(&HeaderMap::new(), "sha256:abc"));
+    }
+
+    #[test]
+    fn test_check_response() {
+        let request = || TranslateCheckRequest {
+            content_hash: "sha256:a".to_string(),
+            translated_hash: "sha256:t".to_string(),
+            options: None,
+        };
+        assert_eq!(check_response(request(), Some("sha256:t".to_string())).status, "current");
+        let changed = check_response(request(), Some("sha256:u".to_string()));
+        assert_eq!((changed.status.as_str(), changed.translated_hash.as_deref()), ("changed", Some("sha256:u")));
+        assert_eq!(check_response(request(), None).status, "missing");
+    }
+
+    #[test]
+    fn test_batch_size_limits() {
+        let settings = Settings::from_vars(|key| match key {
+            "MAX_BATCH_FILES" => Some("2".to_string()),
+            "MAX_BATCH_TOTAL_BYTES" => Some("10".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        let file = |content: &str| FileToTranslate::plain("SKILL.md", content);
+
+        assert!(check_batch_size(&settings, &[file("12345"), file("67890")]).is_ok());
+        assert!(matches!(
+            check_batch_size(&settings, &[file("1"), file("2"), file("3")]),
+            Err(AppError::Validation(errors)) if errors[0].field == "files"
+        ));
+        assert!(matches!(
+            check_batch_size(&settings, &[file("12345"), file("678901")]),
+            Err(AppError::PayloadTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn test_ndjson_batch_lines() {
+        let accept = |value: &str| {
+            let mut headers = header::HeaderMap::new();
+            headers.insert(header::ACCEPT, value.parse().unwrap());
+            accepts_ndjson(&headers)
+        };
+        assert!(accept("application/x-ndjson"));
+        assert!(accept("application/json;q=0.5, Application/X-NDJSON; charset=utf-8"));
+        assert!(!accept("application/json") && !accept("*/*"));
+
+        let mut summary = BatchSummary::default();
+        let failed = failed_file_result(FileToTranslate::plain("a.md", "text"), AppError::BadRequest("bad".to_string()));
+        let line = ndjson_line(&failed).unwrap();
+        assert_eq!(line.iter().filter(|&&byte| byte == b'\n').count(), 1);
+        assert!(line.ends_with(b"}\n"));
+        summary.count(&failed);
+        summary.count(&FileTranslationResult {
+            success: true,
+            cached: true,
+            error: None,
+            error_code: None,
+            ..failed
+        });
+        let summary: serde_json::Value = serde_json::from_slice(&ndjson_line(&summary).unwrap()).unwrap();
+        assert_eq!(
+            summary,
+            json!({"total_files": 2, "successful": 1, "cached_count": 1, "failed": 1, "processing_time_ms": 0.0})
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Validation of JSON request bodies.
+//!
+//! [`ValidJson`] replaces axum's `Json` extractor for the translate endpoints. Bodies
+//! that are not valid JSON, or do not match the request type, and requests whose
+//! fields break a rule of [`Validate`] are refused with 422 and a `VALIDATION_FAILED`
+//! body listing every problem by field, instead of axum's plain text rejection. Other
+//! rejections keep their status: 413 for a body over the size limit, 415 for a missing
+//! JSON `Content-Type`, 408 for a body that did not arrive in time. The gRPC API
+//! applies the same rules through [`check_request`].
+
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    Json,
+};
+use serde::de::DeserializeOwned;
+
+use crate::error::AppError;
+use crate::models::schemas::{BatchTranslateRequest, FieldError, FileToTranslate, TranslateRequest};
+
+/// Rules a request must follow beyond its types
+pub trait Validate {
+    /// Add the problems of the request to `errors`, naming fields under `prefix`
+    fn validate(&self, prefix: &str, errors: &mut Vec<FieldError>);
+}
+
+/// A JSON body that deserialized and passed [`Validate`]
+pub struct ValidJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await.map_err(rejection_error)?;
+        check_request(&value)?;
+        Ok(ValidJson(value))
+    }
+}
+
+/// Refuse a request breaking a rule of [`Validate`], listing every problem by field.
+/// Used for requests that do not arrive as JSON bodies, such as gRPC calls.
+pub fn check_request<T: Validate>(request: &T) -> Result<(), AppError> {
+    let mut errors = Vec::new();
+    request.validate("", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Validation(errors))
+    }
+}
+
+/// The error for a body the `Json` extractor refused. Only bodies that are not valid
+/// JSON or do not match the request type are validation failures.
+fn rejection_error(rejection: JsonRejection) -> AppError {
+    match rejection {
+        JsonRejection::JsonDataError(_) | JsonRejection::JsonSyntaxError(_) => {
+            AppError::Validation(vec![field_error(&rejection)])
+        }
+        _ if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+            AppError::PayloadTooLarge("Request body exceeds the size limit".to_string())
+        }
+        _ => AppError::Rejected(rejection.status(), rejection.body_text()),
+    }
+}
+
+/// The field a body failed to deserialize at, e.g. `options.priority`, or the whole body
+fn field_error(rejection: &JsonRejection) -> FieldError {
+    let data_error = std::iter::successors(std::error::Error::source(rejection), |error| error.source())
+        .find_map(|error| error.downcast_ref::<serde_path_to_error::Error<serde_json::Error>>());
+    match data_error {
+        Some(error) => {
+            let path = error.path().to_string();
+            FieldError {
+                field: if path == "." { String::new() } else { path },
+                message: error.inner().to_string(),
+            }
+        }
+        None => FieldError {
+            field: String::new(),
+            message: rejection.body_text(),
+        },
+    }
+}
+
+fn field(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
+
+fn check(errors: &mut Vec<FieldError>, ok: bool, field: String, message: &str) {
+    if !ok {
+        errors.push(FieldError {
+            field,
+            message: message.to_string(),
+        });
+    }
+}
+
+/// Whether a repository path stays inside the repository: not absolute and without `..`
+fn is_relative_path(path: &str) -> bool {
+    let drive = path.as_bytes().get(1) == Some(&b':');
+    !path.starts_with(['/', '\\']) && !drive && !path.split(['/', '\\']).any(|part| part == "..")
+}
+
+/// Whether a content hash has the form `sha256:` and 64 hex digits
+fn is_content_hash(hash: &str) -> bool {
+    hash.strip_prefix("sha256:")
+        .is_some_and(|hex| hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Rules shared by every file of a request
+fn validate_file(prefix: &str, path: &str, content_hash: Option<&str>, errors: &mut Vec<FieldError>) {
+    check(errors, !path.trim().is_empty(), field(prefix, "path"), "path must not be empty");
+    check(errors, is_relative_path(path), field(prefix, "path"), "path must be relative");
+    if let Some(content_hash) = content_hash {
+        check(
+            errors,
+            is_content_hash(content_hash),
+            field(prefix, "content_hash"),
+            "content_hash must start with sha256: followed by 64 hex digits",
+        );
+    }
+}
+
+impl Validate for FileToTranslate {
+    fn validate(&self, prefix: &str, errors: &mut Vec<FieldError>) {
+        validate_file(prefix, &self.path, self.content_hash.as_deref(), errors);
+    }
+}
+
+impl Validate for TranslateRequest {
+    fn validate(&self, prefix: &str, errors: &mut Vec<FieldError>) {
+        validate_file(prefix, &self.path, self.content_hash.as_deref(), errors);
+        for (i, file) in self.related_files.iter().enumerate() {
+            file.validate(&field(prefix, &format!("related_files[{}]", i)), errors);
+        }
+    }
+}
+
+impl Validate for BatchTranslateRequest {
+    fn validate(&self, prefix: &str, errors: &mut Vec<FieldError>) {
+        check(errors, !self.files.is_empty(), field(prefix, "files"), "files must be non-empty");
+        for (i, file) in self.files.iter().enumerate() {
+            file.validate(&field(prefix, &format!("files[{}]", i)), errors);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors<T: Validate>(request: &T) -> Vec<String> {
+        let mut errors = Vec::new();
+        request.validate("", &mut errors);
+        errors
+            .into_iter()
+            .map(|error| format!("{}: {}", error.field, error.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_request_rules() {
+        let request: TranslateRequest = serde_json::from_value(serde_json::json!({
+            "path": "../SKILL.md",
+            "content": "SGk=",
+            "content_hash": "md5:abc",
+            "related_files": [{"path": "/etc/passwd", "content": ""}],
+        }))
+        .unwrap();
+        assert_eq!(
+            errors(&request),
+            vec![
+                "path: path must be relative",
+                "content_hash: content_hash must start with sha256: followed by 64 hex digits",
+                "related_files[0].path: path must be relative",
+            ]
+        );
+
+        let batch: BatchTranslateRequest = serde_json::from_value(serde_json::json!({"files": []})).unwrap();
+        assert_eq!(errors(&batch), vec!["files: files must be non-empty"]);
+
+        assert!(is_relative_path("./skills/demo/SKILL.md") && !is_relative_path("C:\\skills"));
+        assert!(is_content_hash(&format!("sha256:{}", "a".repeat(64))));
+    }
+
+    #[tokio::test]
+    async fn test_body_rejections() {
+        use axum::body::Body;
+        use axum::response::IntoResponse;
+
+        async fn status(content_type: Option<&str>, body: Body) -> StatusCode {
+            let mut request = Request::post("/api/translate");
+            if let Some(content_type) = content_type {
+                request = request.header("content-type", content_type);
+            }
+            let request = request.body(body).unwrap();
+            match ValidJson::<BatchTranslateRequest>::from_request(request, &()).await {
+                Ok(_) => StatusCode::OK,
+                Err(error) => error.into_response().status(),
+            }
+        }
+
+        let json = Some("application/json");
+        assert_eq!(status(json, Body::from(r#"{"files": [{"path": "a"}]}"#)).await, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(status(json, Body::from("{")).await, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(status(None, Body::from(r#"{"files": []}"#)).await, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let limited = Body::new(http_body_util::Limited::new(Body::from(r#"{"files": []}"#), 4));
+        assert_eq!(status(json, limited).await, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -0,0 +1,167 @@
+//! WebSocket translation sessions at `GET /api/ws`.
+//!
+//! A client keeps one connection open and pushes documents as JSON text messages,
+//! each a [`SessionRequest`]: a `POST /api/translate` body with an `id` of the
+//! client's choosing. Documents are translated one after another at interactive
+//! priority, and every [`SessionEvent`] about a document carries its id:
+//! `queued` on receipt, `translating` as each upstream call starts, then `done`
+//! with the result or `error`. When the client goes away, the translation in
+//! progress is dropped and queued documents are discarded.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Extension, State};
+use axum::response::Response;
+use futures::{SinkExt, StreamExt};
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+use crate::models::schemas::{SessionEvent, SessionRequest};
+use crate::routers::translate::{translate_single, ApiKeyId, AppState, Tenant};
+use crate::services::translator::ProgressSink;
+use skillts_core::scheduler::Priority;
+
+/// Open a translation session
+#[utoipa::path(
+    get, path = "/api/ws", tag = "translate",
+    responses((status = 101, description = "Switched to the session protocol; messages are `SessionRequest` and `SessionEvent` JSON"))
+)]
+pub async fn translate_session(
+    State(state): State<AppState>,
+    Extension(api_key): Extension<ApiKeyId>,
+    Extension(tenant): Extension<Tenant>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let max_message_bytes = state.settings().max_request_bytes;
+    upgrade
+        .max_message_size(max_message_bytes)
+        .on_upgrade(move |socket| session(state, api_key, tenant, socket))
+}
+
+async fn session(state: AppState, api_key: ApiKeyId, tenant: Tenant, socket: WebSocket) {
+    let (mut sink, mut stream) = socket.split();
+    // Unbounded so progress callbacks can report without waiting on a slow client
+    let (events, mut outgoing) = mpsc::unbounded_channel();
+    let (queue, mut documents) = mpsc::unbounded_channel::<SessionRequest>();
+
+    let reader = {
+        let events = events.clone();
+        async move {
+            while let Some(Ok(message)) = stream.next().await {
+                let text = match message {
+                    Message::Text(text) => text,
+                    Message::Close(_) => break,
+                    _ => continue,
+                };
+                match serde_json::from_str::<SessionRequest>(&text) {
+                    Ok(document) => {
+                        let _ = events.send(SessionEvent::Queued {
+                            id: document.id.clone(),
+                        });
+                        let _ = queue.send(document);
+                    }
+                    Err(e) => {
+                        let _ = events.send(SessionEvent::Error {
+                            id: None,
+                            detail: format!("Invalid message: {}", e),
+                        });
+                    }
+                }
+            }
+        }
+    };
+
+    let worker = async {
+        while let Some(document) = documents.recv().await {
+            let progress = progress_sink(&events, &document.id);
+            let outcome = translate_single(
+                &state,
+                &api_key,
+                &tenant,
+                &document.request,
+                Priority::Interactive,
+                Some(progress),
+            )
+            .await;
+            let _ = events.send(match outcome {
+                Ok(result) => SessionEvent::Done {
+                    id: document.id,
+                    result: Box::new(result),
+                },
+                Err(e) => SessionEvent::Error {
+                    id: Some(document.id),
+                    detail: e.to_string(),
+                },
+            });
+        }
+    };
+
+    let writer = async {
+        while let Some(event) = outgoing.recv().await {
+            let message = match serde_json::to_string(&event) {
+                Ok(message) => message,
+                Err(e) => {
+                    tracing::error!("Failed to serialize session event: {}", e);
+                    continue;
+                }
+            };
+            if sink.send(Message::Text(message.into())).await.is_err() {
+                break;
+            }
+        }
+    };
+
+    // The worker only finishes after the reader, so the session ends with the
+    // client: when it closes the connection or can no longer be written to
+    tokio::select! {
+        () = reader => {}
+        () = worker => {}
+        () = writer => {}
+    }
+    tracing::debug!("Translation session of tenant {} closed", tenant.0);
+}
+
+/// Reports the upstream calls of one document as `translating` events
+fn progress_sink(events: &UnboundedSender<SessionEvent>, id: &str) -> ProgressSink {
+    let (events, id) = (events.clone(), id.to_string());
+    ProgressSink::new(move |progress| {
+        let _ = events.send(SessionEvent::Translating {
+            id: id.clone(),
+            chunk: progress.chunk,
+            total: progress.total,
+        });
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::schemas::ContentEncoding;
+    use crate::services::translator::Progress;
+
+    #[test]
+    fn test_session_messages() {
+        let document: SessionRequest = serde_json::from_str(
+            r#"{"id": "a", "path": "SKILL.md", "content": "Hi", "content_encoding": "plain"}"#,
+        )
+        .unwrap();
+        assert_eq!((document.id.as_str(), document.request.path.as_str()), ("a", "SKILL.md"));
+        assert_eq!(document.request.content_encoding, ContentEncoding::Plain);
+
+        let (events, mut received) = mpsc::unbounded_channel();
+        let sink = progress_sink(&events, "a");
+        sink.report(Progress { chunk: 2, total: 3 });
+        let event = serde_json::to_value(received.try_recv().unwrap()).unwrap();
+        assert_eq!(
+            event,
+            serde_json::json!({"event": "translating", "id": "a", "chunk": 2, "total": 3})
+        );
+
+        let error = SessionEvent::Error {
+            id: None,
+            detail: "Invalid message".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(error).unwrap(),
+            serde_json::json!({"event": "error", "detail": "Invalid message"})
+        );
+    }
+}
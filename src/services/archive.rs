@@ -0,0 +1,326 @@
+//! Archive reading and writing for repository uploads.
+//!
+//! Supports zip and tar.gz archives. Entries are kept in memory; only regular
+//! files with safe relative paths are read, everything else is skipped. Reading
+//! fails with 413 once an entry or the whole archive decompresses to more than
+//! [`MAX_ENTRY_BYTES`] or [`MAX_TOTAL_BYTES`], so a small upload cannot expand
+//! into gigabytes.
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{Cursor, Read, Write};
+use std::path::{Component, Path};
+
+use crate::error::{AppError, AppResult};
+
+/// Largest size one archive entry may decompress to
+pub const MAX_ENTRY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Largest size all entries of an archive may decompress to together
+pub const MAX_TOTAL_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Decompressed size limits of an archive
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    entry_bytes: u64,
+    total_bytes: u64,
+}
+
+const LIMITS: Limits = Limits {
+    entry_bytes: MAX_ENTRY_BYTES,
+    total_bytes: MAX_TOTAL_BYTES,
+};
+
+/// Supported archive formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Detect the format from magic bytes, falling back to the file name
+    pub fn detect(file_name: Option<&str>, data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06") {
+            return Some(ArchiveFormat::Zip);
+        }
+        if data.starts_with(&[0x1f, 0x8b]) {
+            return Some(ArchiveFormat::TarGz);
+        }
+
+        let name = file_name?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else {
+            None
+        }
+    }
+
+    /// MIME type of the archive
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "application/zip",
+            ArchiveFormat::TarGz => "application/gzip",
+        }
+    }
+
+    /// File extension including the leading dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => ".zip",
+            ArchiveFormat::TarGz => ".tar.gz",
+        }
+    }
+}
+
+/// A regular file inside an archive
+#[derive(Debug, Clone)]
+pub struct ArchiveFile {
+    /// Relative path using `/` separators
+    pub path: String,
+    pub data: Vec<u8>,
+    /// Unix permissions of the entry, kept when the archive is written again
+    pub mode: Option<u32>,
+}
+
+/// Whether a path looks like a markdown document
+pub fn is_markdown(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    lower.ends_with(".md") || lower.ends_with(".markdown")
+}
+
+/// Normalize an archive entry path, rejecting absolute paths and `..` components
+fn safe_relative_path(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?.to_string()),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("/"))
+    }
+}
+
+/// Read all regular files from an archive
+pub fn read_archive(format: ArchiveFormat, data: &[u8]) -> AppResult<Vec<ArchiveFile>> {
+    match format {
+        ArchiveFormat::Zip => read_zip(data, LIMITS),
+        ArchiveFormat::TarGz => read_tar_gz(data, LIMITS),
+    }
+}
+
+/// Write files into a new archive
+pub fn write_archive(format: ArchiveFormat, files: &[ArchiveFile]) -> AppResult<Vec<u8>> {
+    match format {
+        ArchiveFormat::Zip => write_zip(files),
+        ArchiveFormat::TarGz => write_tar_gz(files),
+    }
+}
+
+/// Read an entry, failing once it or the archive so far decompresses to more than `limits`
+fn read_entry(entry: impl Read, path: &str, limits: Limits, total: &mut u64) -> AppResult<Vec<u8>> {
+    let limit = limits.entry_bytes.min(limits.total_bytes - *total);
+    let mut contents = Vec::new();
+    entry
+        .take(limit + 1)
+        .read_to_end(&mut contents)
+        .map_err(|e| AppError::BadRequest(format!("Failed to read {}: {}", path, e)))?;
+
+    let size = contents.len() as u64;
+    if size > limits.entry_bytes {
+        return Err(AppError::PayloadTooLarge(format!(
+            "{} decompresses to more than {} bytes",
+            path, limits.entry_bytes
+        )));
+    }
+    *total += size;
+    if *total > limits.total_bytes {
+        return Err(AppError::PayloadTooLarge(format!(
+            "Archive decompresses to more than {} bytes",
+            limits.total_bytes
+        )));
+    }
+    Ok(contents)
+}
+
+fn read_zip(data: &[u8], limits: Limits) -> AppResult<Vec<ArchiveFile>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .map_err(|e| AppError::BadRequest(format!("Invalid zip archive: {}", e)))?;
+
+    let mut files = Vec::new();
+    let mut total = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| AppError::BadRequest(format!("Invalid zip entry: {}", e)))?;
+        if !entry.is_file() {
+            continue;
+        }
+        let Some(path) = entry.enclosed_name().as_deref().and_then(safe_relative_path) else {
+            continue;
+        };
+
+        let mode = entry.unix_mode();
+        let data = read_entry(&mut entry, &path, limits, &mut total)?;
+        files.push(ArchiveFile { path, data, mode });
+    }
+
+    Ok(files)
+}
+
+fn read_tar_gz(data: &[u8], limits: Limits) -> AppResult<Vec<ArchiveFile>> {
+    let mut archive = tar::Archive::new(GzDecoder::new(data));
+    let entries = archive
+        .entries()
+        .map_err(|e| AppError::BadRequest(format!("Invalid tar.gz archive: {}", e)))?;
+
+    let mut files = Vec::new();
+    let mut total = 0;
+    for entry in entries {
+        let mut entry =
+            entry.map_err(|e| AppError::BadRequest(format!("Invalid tar entry: {}", e)))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let Some(path) = entry.path().ok().as_deref().and_then(safe_relative_path) else {
+            continue;
+        };
+
+        let mode = entry.header().mode().ok();
+        let data = read_entry(&mut entry, &path, limits, &mut total)?;
+        files.push(ArchiveFile { path, data, mode });
+    }
+
+    Ok(files)
+}
+
+fn write_zip(files: &[ArchiveFile]) -> AppResult<Vec<u8>> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for file in files {
+        let options = match file.mode {
+            Some(mode) => options.unix_permissions(mode),
+            None => options,
+        };
+        writer
+            .start_file(file.path.as_str(), options)
+            .and_then(|_| writer.write_all(&file.data).map_err(Into::into))
+            .map_err(|e| AppError::Internal(format!("Failed to write zip entry: {}", e)))?;
+    }
+
+    let cursor = writer
+        .finish()
+        .map_err(|e| AppError::Internal(format!("Failed to finish zip archive: {}", e)))?;
+    Ok(cursor.into_inner())
+}
+
+fn write_tar_gz(files: &[ArchiveFile]) -> AppResult<Vec<u8>> {
+    let encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+
+    for file in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(file.data.len() as u64);
+        header.set_mode(file.mode.unwrap_or(0o644));
+        header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, &file.path, file.data.as_slice())
+            .map_err(|e| AppError::Internal(format!("Failed to write tar entry: {}", e)))?;
+    }
+
+    let encoder = builder
+        .into_inner()
+        .map_err(|e| AppError::Internal(format!("Failed to finish tar archive: {}", e)))?;
+    encoder
+        .finish()
+        .map_err(|e| AppError::Internal(format!("Failed to finish gzip stream: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_files() -> Vec<ArchiveFile> {
+        vec![
+            ArchiveFile {
+                path: "skills/owner/skill/SKILL.md".to_string(),
+                data: b"# Skill".to_vec(),
+                mode: None,
+            },
+            ArchiveFile {
+                path: "skills/owner/skill/scripts/run.sh".to_string(),
+                data: b"echo hi".to_vec(),
+                mode: Some(0o755),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        for format in [ArchiveFormat::Zip, ArchiveFormat::TarGz] {
+            let bytes = write_archive(format, &sample_files()).unwrap();
+            assert_eq!(ArchiveFormat::detect(None, &bytes), Some(format));
+
+            let files = read_archive(format, &bytes).unwrap();
+            assert_eq!(files.len(), 2);
+            assert_eq!(files[0].path, "skills/owner/skill/SKILL.md");
+            assert_eq!(files[0].data, b"# Skill");
+            assert_eq!(files[1].mode.map(|mode| mode & 0o777), Some(0o755));
+        }
+    }
+
+    #[test]
+    fn test_decompressed_size_limits() {
+        let files = vec![
+            ArchiveFile {
+                path: "a.md".to_string(),
+                data: vec![b' '; 600],
+                mode: None,
+            },
+            ArchiveFile {
+                path: "b.md".to_string(),
+                data: vec![b' '; 600],
+                mode: None,
+            },
+        ];
+        let read = |format: ArchiveFormat, limits: Limits| {
+            let bytes = write_archive(format, &files).unwrap();
+            match format {
+                ArchiveFormat::Zip => read_zip(&bytes, limits),
+                ArchiveFormat::TarGz => read_tar_gz(&bytes, limits),
+            }
+        };
+        for format in [ArchiveFormat::Zip, ArchiveFormat::TarGz] {
+            let limits = |entry_bytes, total_bytes| Limits { entry_bytes, total_bytes };
+            assert_eq!(read(format, limits(600, 1200)).unwrap().len(), 2);
+            assert!(matches!(read(format, limits(599, 1200)), Err(AppError::PayloadTooLarge(_))));
+            assert!(matches!(read(format, limits(600, 1000)), Err(AppError::PayloadTooLarge(_))));
+        }
+    }
+
+    #[test]
+    fn test_safe_relative_path() {
+        assert_eq!(safe_relative_path(Path::new("./a/b.md")), Some("a/b.md".to_string()));
+        assert_eq!(safe_relative_path(Path::new("../etc/passwd")), None);
+        assert_eq!(safe_relative_path(Path::new("/etc/passwd")), None);
+    }
+
+    #[test]
+    fn test_is_markdown() {
+        assert!(is_markdown("skills/a/SKILL.md"));
+        assert!(is_markdown("docs/README.markdown"));
+        assert!(!is_markdown("scripts/run.sh"));
+    }
+}
@@ -0,0 +1,280 @@
+//! Persistent request audit log.
+//!
+//! Every translate call (single, batch, archive, GitHub and CLI files) is
+//! recorded in the `audit_log` table of the cache database for compliance
+//! reporting. Only a fingerprint of the API key is stored, never the key.
+//! Fresh translations per tenant are counted from the log for daily quotas.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures::future::BoxFuture;
+use sqlx::sqlite::{SqliteConnection, SqlitePool, SqliteRow};
+use sqlx::Row;
+use std::collections::HashMap;
+
+use skillts_core::migrate::{migrate, Migration, Schema, Step};
+
+use crate::error::AppResult;
+use crate::models::schemas::AuditEntry;
+
+/// Audit outcomes
+pub const OUTCOME_SUCCESS: &str = "success";
+pub const OUTCOME_ERROR: &str = "error";
+/// Answered by the in-flight translation of an identical request
+pub const OUTCOME_SHARED: &str = "shared";
+
+/// One translate call to record
+#[derive(Debug)]
+pub struct AuditRecord<'a> {
+    pub api_key_id: &'a str,
+    pub tenant: &'a str,
+    pub path: &'a str,
+    pub content_hash: &'a str,
+    pub source_language: &'a str,
+    pub target_language: &'a str,
+    pub cached: bool,
+    /// Served from another request's in-flight translation
+    pub shared: bool,
+    pub duration_ms: f64,
+    pub cost_usd: f64,
+    /// Error message when the translation failed
+    pub error: Option<String>,
+}
+
+/// SQLite-backed audit log of translate requests
+pub struct AuditLog {
+    pool: SqlitePool,
+}
+
+/// Migrations of the audit_log table
+pub const SCHEMA: Schema = Schema {
+    component: "audit",
+    migrations: &[Migration {
+        version: 1,
+        description: "Create audit_log, adding the tenant column to older tables",
+        step: Step::Code(baseline),
+    }],
+};
+
+fn baseline(conn: &mut SqliteConnection) -> BoxFuture<'_, skillts_core::Result<()>> {
+    Box::pin(async move {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at TEXT NOT NULL,
+                api_key_id TEXT NOT NULL,
+                path TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                source_language TEXT NOT NULL,
+                target_language TEXT NOT NULL,
+                cached INTEGER NOT NULL,
+                duration_ms REAL NOT NULL,
+                cost_usd REAL NOT NULL,
+                outcome TEXT NOT NULL,
+                error TEXT,
+                tenant TEXT NOT NULL DEFAULT 'default'
+            )
+            "#,
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        // Logs written before tenants existed belong to the default tenant
+        let has_tenant = sqlx::query("PRAGMA table_info(audit_log)")
+            .fetch_all(&mut *conn)
+            .await?
+            .iter()
+            .any(|row| row.get::<String, _>("name") == "tenant");
+        if !has_tenant {
+            sqlx::query("ALTER TABLE audit_log ADD COLUMN tenant TEXT NOT NULL DEFAULT 'default'")
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log(created_at)")
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    })
+}
+
+impl AuditLog {
+    /// Create the audit log on the cache database pool, applying pending migrations
+    pub async fn new(pool: SqlitePool) -> AppResult<Self> {
+        migrate(&pool, &SCHEMA).await?;
+
+        Ok(Self { pool })
+    }
+
+    /// Record a translate call.
+    /// Failures are logged rather than returned so they never fail a translation.
+    pub async fn record(&self, record: AuditRecord<'_>) {
+        let outcome = if record.error.is_some() {
+            OUTCOME_ERROR
+        } else if record.shared {
+            OUTCOME_SHARED
+        } else {
+            OUTCOME_SUCCESS
+        };
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO audit_log (
+                created_at, api_key_id, path, content_hash, source_language, target_language,
+                cached, duration_ms, cost_usd, outcome, error, tenant
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(timestamp(Utc::now()))
+        .bind(record.api_key_id)
+        .bind(record.path)
+        .bind(record.content_hash)
+        .bind(record.source_language)
+        .bind(record.target_language)
+        .bind(record.cached)
+        .bind(record.duration_ms)
+        .bind(record.cost_usd)
+        .bind(outcome)
+        .bind(&record.error)
+        .bind(record.tenant)
+        .execute(&self.pool)
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!("[{}] Failed to write audit log: {}", record.path, e);
+        }
+    }
+
+    /// List entries created at or after `since`, oldest first, with the total match count
+    pub async fn list(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+        offset: i64,
+    ) -> AppResult<(Vec<AuditEntry>, i64)> {
+        let since = since.map(timestamp).unwrap_or_default();
+
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_log WHERE created_at >= ?")
+            .bind(&since)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let rows = sqlx::query(
+            "SELECT * FROM audit_log WHERE created_at >= ? ORDER BY id LIMIT ? OFFSET ?",
+        )
+        .bind(&since)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((rows.iter().map(audit_from_row).collect(), total))
+    }
+
+    /// Successful uncached translations of each tenant since `since`
+    pub async fn fresh_translations(&self, since: DateTime<Utc>) -> AppResult<HashMap<String, i64>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT tenant, COUNT(*) AS count FROM audit_log
+            WHERE created_at >= ? AND cached = 0 AND outcome = ?
+            GROUP BY tenant
+            "#,
+        )
+        .bind(timestamp(since))
+        .bind(OUTCOME_SUCCESS)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("tenant"), row.get("count")))
+            .collect())
+    }
+}
+
+/// Fixed-width UTC timestamp, so stored values compare correctly as text
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Build an audit entry from a `SELECT *` row of the audit_log table
+fn audit_from_row(row: &SqliteRow) -> AuditEntry {
+    AuditEntry {
+        id: row.get("id"),
+        created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now()),
+        api_key_id: row.get("api_key_id"),
+        tenant: row.get("tenant"),
+        path: row.get("path"),
+        content_hash: row.get("content_hash"),
+        source_language: row.get("source_language"),
+        target_language: row.get("target_language"),
+        cached: row.get("cached"),
+        duration_ms: row.get("duration_ms"),
+        cost_usd: row.get("cost_usd"),
+        outcome: row.get("outcome"),
+        error: row.get("error"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_and_list_since() {
+        // A single connection, since every in-memory connection is its own database
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let audit = AuditLog::new(pool).await.unwrap();
+
+        let records = [
+            ("a/SKILL.md", false, None),
+            ("b/SKILL.md", false, Some("boom".to_string())),
+            ("c/SKILL.md", true, None),
+        ];
+        for (path, shared, error) in records {
+            audit
+                .record(AuditRecord {
+                    api_key_id: "anonymous",
+                    tenant: "default",
+                    path,
+                    content_hash: "sha256:abc",
+                    source_language: "en",
+                    target_language: "zh-CN",
+                    cached: false,
+                    shared,
+                    duration_ms: 12.5,
+                    cost_usd: 0.001,
+                    error,
+                })
+                .await;
+        }
+
+        let (entries, total) = audit.list(None, 1, 0).await.unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, "a/SKILL.md");
+        assert_eq!(entries[0].outcome, OUTCOME_SUCCESS);
+
+        let (entries, _) = audit.list(None, 10, 1).await.unwrap();
+        assert_eq!(entries[0].outcome, OUTCOME_ERROR);
+        assert_eq!(entries[0].error.as_deref(), Some("boom"));
+        assert_eq!(entries[1].outcome, OUTCOME_SHARED);
+
+        // Only the translation made by the request itself counts towards the quota
+        let fresh = audit.fresh_translations(Utc::now() - chrono::Duration::hours(1)).await.unwrap();
+        assert_eq!(fresh.get("default"), Some(&1));
+
+        let future = Utc::now() + chrono::Duration::hours(1);
+        let (entries, total) = audit.list(Some(future), 10, 0).await.unwrap();
+        assert!(entries.is_empty());
+        assert_eq!(total, 0);
+    }
+}
@@ -0,0 +1,225 @@
+//! Backups of the cache database.
+//!
+//! Each backup is a timestamped copy written with `VACUUM INTO` under
+//! `BACKUP_DIR`; only the newest `BACKUP_RETENTION` copies are kept. When an
+//! S3-compatible bucket is configured, every copy is also uploaded there with a
+//! SigV4-signed PUT. Backups run before migrations, every `BACKUP_INTERVAL_HOURS` and
+//! through `POST /api/admin/backup`.
+
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+use sqlx::SqlitePool;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use skillts_core::cache::blob::S3Bucket;
+
+use crate::config::Settings;
+use crate::error::{AppError, AppResult};
+use crate::models::schemas::BackupResult;
+
+/// Backup file names: `cache-<UTC timestamp>.db`
+const FILE_PREFIX: &str = "cache-";
+const FILE_SUFFIX: &str = ".db";
+
+/// Object storage upload timeout
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Writes backups one at a time
+pub struct Backups {
+    http: reqwest::Client,
+    /// Held while a backup runs, so scheduled and manual backups never overlap
+    running: Mutex<()>,
+}
+
+impl Backups {
+    /// Create the backup runner
+    pub fn new() -> AppResult<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(UPLOAD_TIMEOUT)
+            .build()
+            .map_err(|e| AppError::Internal(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(Self {
+            http,
+            running: Mutex::new(()),
+        })
+    }
+
+    /// Back up the database behind `pool`, prune old copies and upload the new one.
+    /// An upload failure is reported in the result; the local copy is kept either way.
+    pub async fn run(&self, settings: &Settings, pool: &SqlitePool) -> AppResult<BackupResult> {
+        let _running = self.running.lock().await;
+
+        let dir = Path::new(&settings.backup_dir);
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to create backup directory: {}", e)))?;
+
+        let file_name = format!(
+            "{}{}{}",
+            FILE_PREFIX,
+            Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+            FILE_SUFFIX
+        );
+        let path = dir.join(&file_name);
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().into_owned())
+            .execute(pool)
+            .await?;
+        let size_bytes = tokio::fs::metadata(&path)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to read backup: {}", e)))?
+            .len();
+        tracing::info!("Cache database backed up to {:?} ({} bytes)", path, size_bytes);
+
+        let removed = prune(dir, settings.backup_retention).await?;
+
+        let (uploaded_to, upload_error) = match backup_bucket(settings) {
+            None => (None, None),
+            Some(bucket) => match upload(&self.http, &bucket, &file_name, &path).await {
+                Ok(url) => (Some(url), None),
+                Err(e) => {
+                    tracing::error!("Backup upload failed: {}", e);
+                    (None, Some(e.to_string()))
+                }
+            },
+        };
+
+        Ok(BackupResult {
+            path: path.to_string_lossy().into_owned(),
+            size_bytes,
+            removed,
+            uploaded_to,
+            upload_error,
+        })
+    }
+}
+
+/// Delete all but the newest `retention` backups, returning the removed file names
+async fn prune(dir: &Path, retention: usize) -> AppResult<Vec<String>> {
+    let io_error = |e: std::io::Error| AppError::Internal(format!("Failed to prune backups: {}", e));
+
+    let mut names = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await.map_err(io_error)?;
+    while let Some(entry) = entries.next_entry().await.map_err(io_error)? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX) {
+            names.push(name);
+        }
+    }
+
+    // Timestamps sort lexicographically, newest last
+    names.sort();
+    let excess = names.len().saturating_sub(retention);
+    let removed: Vec<String> = names.drain(..excess).collect();
+    for name in &removed {
+        tokio::fs::remove_file(dir.join(name)).await.map_err(io_error)?;
+        tracing::debug!("Removed old backup {}", name);
+    }
+
+    Ok(removed)
+}
+
+/// Next scheduled backup after `now` (local time) for an interval of whole hours.
+/// Runs are aligned to local midnight like the cron entry `0 */N * * *`.
+pub fn next_run(now: NaiveDateTime, interval_hours: u32) -> NaiveDateTime {
+    let midnight = now.date().and_hms_opt(0, 0, 0).unwrap_or(now);
+    let step = ChronoDuration::hours(i64::from(interval_hours.max(1)));
+    let mut next = midnight + step;
+    while next <= now {
+        next += step;
+    }
+    // Slots restart at midnight, also when the interval does not divide 24 hours
+    next.min(midnight + ChronoDuration::days(1))
+}
+
+/// S3-compatible bucket receiving backup copies, if uploads are enabled
+fn backup_bucket(settings: &Settings) -> Option<S3Bucket> {
+    if settings.backup_s3_bucket.is_empty() {
+        return None;
+    }
+    Some(S3Bucket {
+        endpoint: settings.backup_s3_endpoint.clone(),
+        bucket: settings.backup_s3_bucket.clone(),
+        region: settings.backup_s3_region.clone(),
+        access_key: settings.backup_s3_access_key.clone(),
+        secret_key: settings.backup_s3_secret_key.clone(),
+        prefix: settings.backup_s3_prefix.clone(),
+    })
+}
+
+/// PUT a file as `<prefix><file_name>` using a path-style URL, returning the URL
+async fn upload(http: &reqwest::Client, bucket: &S3Bucket, file_name: &str, path: &Path) -> AppResult<String> {
+    let body = tokio::fs::read(path)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to read backup: {}", e)))?;
+
+    let url = bucket.url(file_name)?;
+    let response = bucket
+        .send(http, reqwest::Method::PUT, url.clone(), &[], body)
+        .await
+        .map_err(|e| AppError::Internal(format!("Backup upload failed: {}", e)))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let message = response.text().await.unwrap_or_default();
+        return Err(AppError::Internal(format!(
+            "Backup upload returned {}: {}",
+            status,
+            message.trim()
+        )));
+    }
+
+    Ok(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use sha2::{Digest, Sha256};
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_next_run() {
+        assert_eq!(next_run(at(0, 0), 6), at(6, 0));
+        assert_eq!(next_run(at(5, 59), 6), at(6, 0));
+        assert_eq!(next_run(at(6, 0), 6), at(12, 0));
+        assert_eq!(next_run(at(23, 30), 1), at(0, 0) + ChronoDuration::days(1));
+        assert_eq!(next_run(at(22, 0), 7), at(0, 0) + ChronoDuration::days(1));
+    }
+
+    #[test]
+    fn test_sigv4_authorization() {
+        let bucket = S3Bucket {
+            endpoint: "https://s3.example.com".to_string(),
+            bucket: "backups".to_string(),
+            region: "us-east-1".to_string(),
+            access_key: "AKIDEXAMPLE".to_string(),
+            secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            prefix: String::new(),
+        };
+        let payload_hash = hex::encode(Sha256::digest(b"backup"));
+        let authorization = bucket.authorization(
+            "PUT",
+            "s3.example.com",
+            "/backups/cache.db",
+            "",
+            &payload_hash,
+            "20240501T030000Z",
+        );
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240501/us-east-1/s3/aws4_request, \
+             SignedHeaders=host;x-amz-content-sha256;x-amz-date, \
+             Signature=a4f866e83ecd1c5b3d2e02e2a1fa9524a3b458296848809631b75a9ac69d57f9"
+        );
+    }
+}
@@ -0,0 +1,511 @@
+//! Background batch jobs.
+//!
+//! A batch submitted with `background: true` is stored in the `batch_jobs` and
+//! `batch_files` tables of the cache database and processed file by file, each
+//! result being saved as soon as it is ready. Clients poll the job for results,
+//! and jobs interrupted by a restart resume with their unprocessed files.
+//! Cancelling a job stops the task processing it and keeps the results so far.
+//!
+//! Besides file counts, a job reports the source characters of its files, the
+//! upstream calls its processed files took and, while it runs in this process,
+//! the stage and upstream calls of the file being processed, so clients can show
+//! progress within large files.
+
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::{SqlitePool, SqliteRow};
+use sqlx::Row;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+use skillts_core::migrate::{migrate, Migration, Schema, Step};
+use skillts_core::translator::{Progress, ProgressSink};
+
+use crate::error::{AppError, AppResult};
+use crate::models::schemas::{
+    BatchJobProgress, BatchJobStatus, FileToTranslate, FileTranslationResult, TranslateOptions,
+};
+
+/// Job statuses
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_COMPLETED: &str = "completed";
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+/// Stages of the file a running job is processing
+pub const STAGE_PREPARING: &str = "preparing";
+pub const STAGE_TRANSLATING: &str = "translating";
+pub const STAGE_SAVING: &str = "saving";
+
+/// Migrations of the batch job tables
+pub const SCHEMA: Schema = Schema {
+    component: "batch_jobs",
+    migrations: &[
+        Migration {
+            version: 1,
+            description: "Create batch jobs and their files",
+            step: Step::Sql(
+                r#"
+                CREATE TABLE IF NOT EXISTS batch_jobs (
+                    id TEXT PRIMARY KEY,
+                    tenant TEXT NOT NULL,
+                    api_key TEXT NOT NULL,
+                    options TEXT,
+                    skip_cached INTEGER NOT NULL,
+                    status TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    finished_at TEXT
+                );
+                CREATE INDEX IF NOT EXISTS idx_batch_jobs_status ON batch_jobs(status);
+
+                CREATE TABLE IF NOT EXISTS batch_files (
+                    job_id TEXT NOT NULL,
+                    position INTEGER NOT NULL,
+                    file TEXT NOT NULL,
+                    result TEXT,
+                    success INTEGER NOT NULL DEFAULT 0,
+                    cached INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (job_id, position)
+                );
+                "#,
+            ),
+        },
+        Migration {
+            version: 2,
+            description: "Record the characters and upstream calls of batch files",
+            step: Step::Sql(
+                r#"
+                ALTER TABLE batch_files ADD COLUMN characters INTEGER NOT NULL DEFAULT 0;
+                ALTER TABLE batch_files ADD COLUMN chunks INTEGER NOT NULL DEFAULT 0;
+                "#,
+            ),
+        },
+    ],
+};
+
+/// A stored job, with what is needed to process its files
+#[derive(Debug, Clone)]
+pub struct BatchJob {
+    pub id: String,
+    pub tenant: String,
+    /// Audit log identity of the client that submitted the job
+    pub api_key: String,
+    pub options: Option<TranslateOptions>,
+    pub skip_cached: bool,
+}
+
+/// A job this process is running
+struct RunningJob {
+    cancel: oneshot::Sender<()>,
+    /// Stage of the current file
+    stage: &'static str,
+    path: String,
+    /// Upstream calls started for the current file, and how many it is expected to take
+    chunk: usize,
+    chunks: usize,
+}
+
+/// SQLite-backed store of background batch jobs
+pub struct BatchJobs {
+    pool: SqlitePool,
+    /// Jobs this process is running
+    running: Mutex<HashMap<String, RunningJob>>,
+}
+
+impl BatchJobs {
+    /// Create the store on the cache database pool, applying pending migrations
+    pub async fn new(pool: SqlitePool) -> AppResult<Self> {
+        migrate(&pool, &SCHEMA).await?;
+        Ok(Self {
+            pool,
+            running: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn lock_running(&self) -> std::sync::MutexGuard<'_, HashMap<String, RunningJob>> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Register a job about to be processed here; the receiver fires when it is cancelled
+    pub fn track(&self, job_id: &str) -> oneshot::Receiver<()> {
+        let (cancel, receiver) = oneshot::channel();
+        let job = RunningJob {
+            cancel,
+            stage: STAGE_PREPARING,
+            path: String::new(),
+            chunk: 0,
+            chunks: 0,
+        };
+        self.lock_running().insert(job_id.to_string(), job);
+        receiver
+    }
+
+    /// Note that a running job started processing a file
+    pub fn start_file(&self, job_id: &str, path: &str) {
+        if let Some(job) = self.lock_running().get_mut(job_id) {
+            job.stage = STAGE_PREPARING;
+            job.path = path.to_string();
+            (job.chunk, job.chunks) = (0, 0);
+        }
+    }
+
+    /// Sink reporting the upstream calls of a running job's current file
+    pub fn progress_sink(self: &Arc<Self>, job_id: &str) -> ProgressSink {
+        let (jobs, job_id) = (self.clone(), job_id.to_string());
+        ProgressSink::new(move |progress: Progress| {
+            if let Some(job) = jobs.lock_running().get_mut(&job_id) {
+                job.stage = STAGE_TRANSLATING;
+                (job.chunk, job.chunks) = (progress.chunk, progress.total);
+            }
+        })
+    }
+
+    /// Note that a running job is saving the result of its current file,
+    /// returning the upstream calls the file took
+    pub fn start_saving(&self, job_id: &str) -> usize {
+        match self.lock_running().get_mut(job_id) {
+            Some(job) => {
+                job.stage = STAGE_SAVING;
+                job.chunk
+            }
+            None => 0,
+        }
+    }
+
+    /// Forget a job whose processing ended
+    pub fn untrack(&self, job_id: &str) {
+        self.lock_running().remove(job_id);
+    }
+
+    /// Store a new running job with its files
+    pub async fn create(
+        &self,
+        tenant: &str,
+        api_key: &str,
+        files: &[FileToTranslate],
+        options: Option<&TranslateOptions>,
+        skip_cached: bool,
+    ) -> AppResult<BatchJob> {
+        let job = BatchJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            tenant: tenant.to_string(),
+            api_key: api_key.to_string(),
+            options: options.cloned(),
+            skip_cached,
+        };
+        let options = job.options.as_ref().map(to_json).transpose()?;
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO batch_jobs (id, tenant, api_key, options, skip_cached, status, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&job.id)
+        .bind(&job.tenant)
+        .bind(&job.api_key)
+        .bind(options)
+        .bind(skip_cached)
+        .bind(STATUS_RUNNING)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        for (position, file) in files.iter().enumerate() {
+            sqlx::query("INSERT INTO batch_files (job_id, position, file, characters) VALUES (?, ?, ?, ?)")
+                .bind(&job.id)
+                .bind(position as i64)
+                .bind(to_json(file)?)
+                .bind(characters(file))
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(job)
+    }
+
+    /// Jobs still running, oldest first
+    pub async fn unfinished(&self) -> AppResult<Vec<BatchJob>> {
+        let rows = sqlx::query("SELECT * FROM batch_jobs WHERE status = ? ORDER BY created_at")
+            .bind(STATUS_RUNNING)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(job_from_row).collect()
+    }
+
+    /// Files of a job without a result yet, by position
+    pub async fn pending_files(&self, job_id: &str) -> AppResult<Vec<(i64, FileToTranslate)>> {
+        let rows = sqlx::query(
+            "SELECT position, file FROM batch_files WHERE job_id = ? AND result IS NULL ORDER BY position",
+        )
+        .bind(job_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| Ok((row.get("position"), from_json(row.get("file"))?)))
+            .collect()
+    }
+
+    /// Save the result of one file and the upstream calls it took
+    pub async fn record_result(
+        &self,
+        job_id: &str,
+        position: i64,
+        result: &FileTranslationResult,
+        chunks: usize,
+    ) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE batch_files SET result = ?, success = ?, cached = ?, chunks = ? WHERE job_id = ? AND position = ?",
+        )
+        .bind(to_json(result)?)
+        .bind(result.success)
+        .bind(result.cached)
+        .bind(chunks as i64)
+        .bind(job_id)
+        .bind(position)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Mark a running job as completed
+    pub async fn finish(&self, job_id: &str) -> AppResult<()> {
+        sqlx::query("UPDATE batch_jobs SET status = ?, finished_at = ? WHERE id = ? AND status = ?")
+            .bind(STATUS_COMPLETED)
+            .bind(Utc::now().to_rfc3339())
+            .bind(job_id)
+            .bind(STATUS_RUNNING)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Cancel a tenant's running job and stop its processing, returning its status.
+    /// Files already processed keep their results; a finished job is a conflict.
+    pub async fn cancel(&self, tenant: &str, job_id: &str) -> AppResult<BatchJobStatus> {
+        let result = sqlx::query(
+            "UPDATE batch_jobs SET status = ?, finished_at = ? WHERE id = ? AND tenant = ? AND status = ?",
+        )
+        .bind(STATUS_CANCELLED)
+        .bind(Utc::now().to_rfc3339())
+        .bind(job_id)
+        .bind(tenant)
+        .bind(STATUS_RUNNING)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            let status = self.status(tenant, job_id, 0).await?;
+            return Err(AppError::Conflict(format!(
+                "Batch job {} is already {}",
+                job_id, status.status
+            )));
+        }
+        if let Some(job) = self.lock_running().remove(job_id) {
+            let _ = job.cancel.send(());
+        }
+
+        self.status(tenant, job_id, 0).await
+    }
+
+    /// Progress of a tenant's job, with the results after the first `offset` processed files
+    pub async fn status(&self, tenant: &str, job_id: &str, offset: usize) -> AppResult<BatchJobStatus> {
+        let job = sqlx::query("SELECT status, created_at, finished_at FROM batch_jobs WHERE id = ? AND tenant = ?")
+            .bind(job_id)
+            .bind(tenant)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Batch job {} not found", job_id)))?;
+
+        let counts = sqlx::query(
+            r#"
+            SELECT COUNT(*) AS total,
+                   COUNT(result) AS processed,
+                   COALESCE(SUM(success), 0) AS successful,
+                   COALESCE(SUM(cached), 0) AS cached,
+                   COALESCE(SUM(characters), 0) AS characters,
+                   COALESCE(SUM(CASE WHEN success THEN characters ELSE 0 END), 0) AS characters_translated,
+                   COALESCE(SUM(chunks), 0) AS chunks
+            FROM batch_files
+            WHERE job_id = ?
+            "#,
+        )
+        .bind(job_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let results = sqlx::query(
+            "SELECT result FROM batch_files WHERE job_id = ? AND result IS NOT NULL ORDER BY position LIMIT -1 OFFSET ?",
+        )
+        .bind(job_id)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|row| from_json(row.get("result")))
+        .collect::<AppResult<Vec<FileTranslationResult>>>()?;
+
+        let count = |column: &str| counts.get::<i64, _>(column) as usize;
+        let mut progress = BatchJobProgress {
+            stage: None,
+            current_path: None,
+            current_chunk: 0,
+            current_chunks: 0,
+            chunks_completed: count("chunks"),
+            characters_total: count("characters"),
+            characters_translated: count("characters_translated"),
+        };
+        if let Some(running) = self.lock_running().get(job_id) {
+            progress.stage = Some(running.stage.to_string());
+            progress.current_path = Some(running.path.clone()).filter(|path| !path.is_empty());
+            (progress.current_chunk, progress.current_chunks) = (running.chunk, running.chunks);
+        }
+
+        Ok(BatchJobStatus {
+            job_id: job_id.to_string(),
+            status: job.get("status"),
+            total_files: count("total"),
+            processed_files: count("processed"),
+            successful: count("successful"),
+            cached_count: count("cached"),
+            failed: count("processed") - count("successful"),
+            progress,
+            results,
+            created_at: parse_time(job.get("created_at")),
+            finished_at: job.get::<Option<String>, _>("finished_at").map(parse_time),
+        })
+    }
+
+    /// Delete completed and cancelled jobs finished before `cutoff`, returning how many were deleted
+    pub async fn clear_finished_before(&self, cutoff: DateTime<Utc>) -> AppResult<u64> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            DELETE FROM batch_files WHERE job_id IN
+                (SELECT id FROM batch_jobs WHERE status != ? AND finished_at < ?)
+            "#,
+        )
+        .bind(STATUS_RUNNING)
+        .bind(cutoff.to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+        let result = sqlx::query("DELETE FROM batch_jobs WHERE status != ? AND finished_at < ?")
+            .bind(STATUS_RUNNING)
+            .bind(cutoff.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+fn job_from_row(row: &SqliteRow) -> AppResult<BatchJob> {
+    Ok(BatchJob {
+        id: row.get("id"),
+        tenant: row.get("tenant"),
+        api_key: row.get("api_key"),
+        options: row
+            .get::<Option<String>, _>("options")
+            .map(from_json)
+            .transpose()?,
+        skip_cached: row.get("skip_cached"),
+    })
+}
+
+/// Characters of a file's content, 0 when it cannot be decoded
+fn characters(file: &FileToTranslate) -> i64 {
+    file.content_encoding
+        .decode(&file.content)
+        .map_or(0, |content| content.chars().count() as i64)
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> AppResult<String> {
+    serde_json::to_string(value)
+        .map_err(|e| AppError::Internal(format!("Failed to store batch job: {}", e)))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(json: String) -> AppResult<T> {
+    serde_json::from_str(&json)
+        .map_err(|e| AppError::Internal(format!("Failed to read batch job: {}", e)))
+}
+
+fn parse_time(value: String) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(&value)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn test_job_resumes_with_unprocessed_files() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let jobs = Arc::new(BatchJobs::new(pool).await.unwrap());
+
+        let files = vec![
+            FileToTranslate::plain("a/SKILL.md", "# A"),
+            FileToTranslate::plain("b/SKILL.md", "# B"),
+        ];
+        let job = jobs.create("registry-a", "key-1", &files, None, true).await.unwrap();
+        let result = FileTranslationResult {
+            path: "a/SKILL.md".to_string(),
+            success: true,
+            translated_content: Some("# 甲".to_string()),
+            content_hash: "hash".to_string(),
+            translated_hash: None,
+            cached: true,
+            error: None,
+            error_code: None,
+            long_lines: None,
+        };
+        jobs.record_result(&job.id, 0, &result, 2).await.unwrap();
+
+        // After a restart, only the second file is left
+        let unfinished = jobs.unfinished().await.unwrap();
+        assert_eq!(unfinished.len(), 1);
+        let pending = jobs.pending_files(&unfinished[0].id).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!((pending[0].0, pending[0].1.path.as_str()), (1, "b/SKILL.md"));
+
+        let status = jobs.status("registry-a", &job.id, 0).await.unwrap();
+        assert_eq!((status.total_files, status.processed_files, status.cached_count), (2, 1, 1));
+        assert_eq!(status.results[0].translated_content.as_deref(), Some("# 甲"));
+        let progress = &status.progress;
+        assert_eq!((progress.chunks_completed, progress.characters_total, progress.characters_translated), (2, 6, 3));
+        assert!(progress.stage.is_none());
+        assert!(jobs.status("registry-a", &job.id, 1).await.unwrap().results.is_empty());
+        assert!(jobs.status("registry-b", &job.id, 0).await.is_err());
+
+        // Cancelling signals the task processing the job, and a cancelled job never finishes
+        let mut cancelled = jobs.track(&job.id);
+
+        // While the job runs here, it reports the upstream calls of its current file
+        jobs.start_file(&job.id, "b/SKILL.md");
+        jobs.progress_sink(&job.id).report(Progress { chunk: 1, total: 3 });
+        let progress = jobs.status("registry-a", &job.id, 0).await.unwrap().progress;
+        assert_eq!(progress.stage.as_deref(), Some(STAGE_TRANSLATING));
+        assert_eq!(progress.current_path.as_deref(), Some("b/SKILL.md"));
+        assert_eq!((progress.current_chunk, progress.current_chunks), (1, 3));
+        assert_eq!(jobs.start_saving(&job.id), 1);
+
+        assert!(jobs.cancel("registry-b", &job.id).await.is_err());
+        assert_eq!(jobs.cancel("registry-a", &job.id).await.unwrap().status, STATUS_CANCELLED);
+        assert!(cancelled.try_recv().is_ok());
+        assert!(matches!(jobs.cancel("registry-a", &job.id).await, Err(AppError::Conflict(_))));
+        jobs.finish(&job.id).await.unwrap();
+        assert_eq!(jobs.status("registry-a", &job.id, 0).await.unwrap().status, STATUS_CANCELLED);
+        assert!(jobs.unfinished().await.unwrap().is_empty());
+        assert_eq!(jobs.clear_finished_before(Utc::now() + chrono::Duration::seconds(1)).await.unwrap(), 1);
+    }
+}
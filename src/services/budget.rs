@@ -0,0 +1,196 @@
+//! Usage budgets.
+//!
+//! Tokens and estimated cost of fresh translations are added up per UTC day and
+//! month in the `budget_usage` table of the cache database. Once the day's
+//! `DAILY_TOKEN_BUDGET` or the month's `MONTHLY_COST_BUDGET` is used up, new
+//! uncached translations are rejected until the period ends or an admin resets it.
+
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::{SqlitePool, SqliteRow};
+use sqlx::Row;
+
+use skillts_core::migrate::{migrate, Migration, Schema, Step};
+
+use crate::config::Settings;
+use crate::error::{AppError, AppResult};
+use crate::models::schemas::BudgetStatus;
+
+/// Migrations of the budget_usage table
+pub const SCHEMA: Schema = Schema {
+    component: "budget",
+    migrations: &[Migration {
+        version: 1,
+        description: "Create budget_usage",
+        step: Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS budget_usage (
+                period TEXT PRIMARY KEY,
+                tokens INTEGER NOT NULL DEFAULT 0,
+                cost_usd REAL NOT NULL DEFAULT 0,
+                reset_at TEXT
+            )
+            "#,
+        ),
+    }],
+};
+
+/// Usage rows are keyed by `YYYY-MM-DD` for days and `YYYY-MM` for months
+fn day(now: DateTime<Utc>) -> String {
+    now.format("%Y-%m-%d").to_string()
+}
+
+fn month(now: DateTime<Utc>) -> String {
+    now.format("%Y-%m").to_string()
+}
+
+/// SQLite-backed token and cost usage of the current day and month
+pub struct Budget {
+    pool: SqlitePool,
+}
+
+impl Budget {
+    /// Create the store on the cache database pool, applying pending migrations
+    pub async fn new(pool: SqlitePool) -> AppResult<Self> {
+        migrate(&pool, &SCHEMA).await?;
+        Ok(Self { pool })
+    }
+
+    /// Add a fresh translation's usage to the current day and month.
+    /// Failures are logged rather than returned so they never fail a translation.
+    pub async fn record(&self, tokens: usize, cost_usd: f64) {
+        let now = Utc::now();
+        for period in [day(now), month(now)] {
+            let result = sqlx::query(
+                r#"
+                INSERT INTO budget_usage (period, tokens, cost_usd) VALUES (?, ?, ?)
+                ON CONFLICT(period) DO UPDATE SET
+                    tokens = tokens + excluded.tokens,
+                    cost_usd = cost_usd + excluded.cost_usd
+                "#,
+            )
+            .bind(&period)
+            .bind(tokens as i64)
+            .bind(cost_usd)
+            .execute(&self.pool)
+            .await;
+
+            if let Err(e) = result {
+                tracing::warn!("Failed to record budget usage: {}", e);
+            }
+        }
+    }
+
+    async fn usage(&self, period: &str) -> AppResult<Option<SqliteRow>> {
+        Ok(
+            sqlx::query("SELECT tokens, cost_usd, reset_at FROM budget_usage WHERE period = ?")
+                .bind(period)
+                .fetch_optional(&self.pool)
+                .await?,
+        )
+    }
+
+    /// Usage of the current day and month against the configured budgets
+    pub async fn status(&self, settings: &Settings) -> AppResult<BudgetStatus> {
+        let now = Utc::now();
+        let (day, month) = (day(now), month(now));
+        let today = self.usage(&day).await?;
+        let this_month = self.usage(&month).await?;
+
+        let tokens_today = today.as_ref().map_or(0, |row| row.get::<i64, _>("tokens")) as u64;
+        let cost_this_month_usd = this_month.as_ref().map_or(0.0, |row| row.get("cost_usd"));
+        let daily_token_budget = (settings.daily_token_budget > 0).then_some(settings.daily_token_budget);
+        let monthly_cost_budget_usd =
+            (settings.monthly_cost_budget > 0.0).then_some(settings.monthly_cost_budget);
+
+        Ok(BudgetStatus {
+            exhausted: daily_token_budget.is_some_and(|budget| tokens_today >= budget)
+                || monthly_cost_budget_usd.is_some_and(|budget| cost_this_month_usd >= budget),
+            day,
+            tokens_today,
+            daily_token_budget,
+            month,
+            cost_this_month_usd,
+            monthly_cost_budget_usd,
+            last_reset_at: this_month.and_then(|row| row.get("reset_at")),
+        })
+    }
+
+    /// Reject a fresh translation once a budget is used up
+    pub async fn check(&self, settings: &Settings) -> AppResult<()> {
+        if settings.daily_token_budget == 0 && settings.monthly_cost_budget == 0.0 {
+            return Ok(());
+        }
+
+        let status = self.status(settings).await?;
+        if let Some(budget) = status.daily_token_budget.filter(|budget| status.tokens_today >= *budget) {
+            return Err(AppError::BudgetExceeded(format!(
+                "Daily token budget of {} exhausted ({} tokens used on {}); cached translations are still served",
+                budget, status.tokens_today, status.day
+            )));
+        }
+        if let Some(budget) = status
+            .monthly_cost_budget_usd
+            .filter(|budget| status.cost_this_month_usd >= *budget)
+        {
+            return Err(AppError::BudgetExceeded(format!(
+                "Monthly cost budget of ${:.2} exhausted (${:.2} used in {}); cached translations are still served",
+                budget, status.cost_this_month_usd, status.month
+            )));
+        }
+        Ok(())
+    }
+
+    /// Zero the usage of the current day and month
+    pub async fn reset(&self) -> AppResult<()> {
+        let now = Utc::now();
+        for period in [day(now), month(now)] {
+            sqlx::query(
+                r#"
+                INSERT INTO budget_usage (period, tokens, cost_usd, reset_at) VALUES (?, 0, 0, ?)
+                ON CONFLICT(period) DO UPDATE SET tokens = 0, cost_usd = 0, reset_at = excluded.reset_at
+                "#,
+            )
+            .bind(&period)
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        }
+        tracing::info!("Budget usage of {} and {} reset", day(now), month(now));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn test_budget_exhausts_and_resets() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let budget = Budget::new(pool).await.unwrap();
+        let mut settings = Settings::from_vars(|_| None).unwrap();
+        settings.daily_token_budget = 1000;
+        settings.monthly_cost_budget = 1.0;
+
+        budget.record(600, 0.25).await;
+        assert!(budget.check(&settings).await.is_ok());
+        budget.record(600, 0.25).await;
+        let error = budget.check(&settings).await.unwrap_err();
+        assert!(error.to_string().contains("Daily token budget of 1000"), "{}", error);
+
+        let status = budget.status(&settings).await.unwrap();
+        assert_eq!((status.tokens_today, status.cost_this_month_usd), (1200, 0.5));
+        assert!(status.exhausted);
+
+        budget.reset().await.unwrap();
+        let status = budget.status(&settings).await.unwrap();
+        assert_eq!((status.tokens_today, status.exhausted), (0, false));
+        assert!(status.last_reset_at.is_some());
+        assert!(budget.check(&settings).await.is_ok());
+    }
+}
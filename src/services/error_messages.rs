@@ -0,0 +1,107 @@
+//! Localized error details.
+//!
+//! Error responses are written in English. A client sending `Accept-Language` that
+//! prefers a language of the catalog gets `detail` in that language instead, with
+//! `code` unchanged for programmatic handling. Details of request-specific errors,
+//! e.g. which field is invalid, keep the English original after the localized
+//! message, since it names things the catalog cannot know.
+
+use crate::models::schemas::ErrorCode;
+
+/// A language the catalog has messages in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Chinese,
+}
+
+impl Language {
+    /// Value of the `Content-Language` header of a localized response
+    pub fn tag(self) -> &'static str {
+        match self {
+            Language::Chinese => "zh-CN",
+        }
+    }
+}
+
+/// The catalog language an `Accept-Language` header prefers, `None` when it prefers
+/// English or a language without messages
+pub fn preferred_language(accept_language: &str) -> Option<Language> {
+    let mut ranges: Vec<(&str, f32)> = accept_language
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse().ok())?;
+            (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+        })
+        .collect();
+    // Stable, so equally weighted ranges keep their order
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let (tag, _) = ranges.first()?;
+    let primary = tag.split('-').next().unwrap_or_default();
+    primary.eq_ignore_ascii_case("zh").then_some(Language::Chinese)
+}
+
+/// `detail` of an error response in `language`
+pub fn localize(language: Language, code: ErrorCode, detail: &str) -> String {
+    let (message, with_detail) = match language {
+        Language::Chinese => chinese(code),
+    };
+    if with_detail && !detail.is_empty() {
+        format!("{}：{}", message, detail)
+    } else {
+        message.to_string()
+    }
+}
+
+/// Chinese message of an error code, and whether the original detail follows it
+fn chinese(code: ErrorCode) -> (&'static str, bool) {
+    match code {
+        ErrorCode::InvalidRequest => ("请求无效", true),
+        ErrorCode::ValidationFailed => ("请求体校验失败", true),
+        ErrorCode::InvalidContent => ("内容不是有效的 base64 或 UTF-8", true),
+        ErrorCode::Unprocessable => ("请求无法按原样处理", true),
+        ErrorCode::Unauthorized => ("API Key 缺失或无效", false),
+        ErrorCode::Forbidden => ("无权访问该接口或来源地址", false),
+        ErrorCode::NotFound => ("资源不存在", true),
+        ErrorCode::Conflict => ("状态冲突", true),
+        ErrorCode::PayloadTooLarge => ("请求体超过大小限制", false),
+        ErrorCode::InputTooLarge => ("内容中有一行过长，无法放入一次模型请求", false),
+        ErrorCode::ContextLengthExceeded => ("内容超出模型的上下文长度，重试无效", false),
+        ErrorCode::QuotaExceeded => ("租户当日翻译配额已用尽，请明天再试", false),
+        ErrorCode::BudgetExceeded => ("翻译预算已用尽，已缓存的译文仍可获取", false),
+        ErrorCode::Timeout => ("翻译超时，请稍后重试", false),
+        ErrorCode::RateLimited => ("模型服务限流，请稍后重试", false),
+        ErrorCode::UpstreamUnavailable => ("模型服务暂不可用，请稍后重试", false),
+        ErrorCode::UpstreamAuthFailed => ("模型服务拒绝了本服务的凭据，请联系管理员", false),
+        ErrorCode::UpstreamError => ("模型服务调用失败", false),
+        ErrorCode::GitHubError => ("GitHub API 调用失败", true),
+        ErrorCode::Internal => ("服务内部错误", false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localized_details() {
+        assert_eq!(preferred_language("zh-CN,zh;q=0.9,en;q=0.8"), Some(Language::Chinese));
+        assert_eq!(preferred_language("en-US, zh;q=0.5"), None);
+        assert_eq!(preferred_language("en;q=0.4, zh-TW"), Some(Language::Chinese));
+        assert_eq!(preferred_language("zh;q=0, en"), None);
+        assert_eq!(preferred_language("*"), None);
+
+        assert_eq!(
+            localize(Language::Chinese, ErrorCode::Timeout, "Translation failed: Translation timed out after 30 seconds"),
+            "翻译超时，请稍后重试"
+        );
+        assert_eq!(
+            localize(Language::Chinese, ErrorCode::NotFound, "No cache entry 'abc'"),
+            "资源不存在：No cache entry 'abc'"
+        );
+    }
+}
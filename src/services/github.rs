@@ -0,0 +1,338 @@
+//! Minimal GitHub REST API client for translating repositories by URL.
+//!
+//! Lists repository files matching a glob, fetches their contents and can open
+//! a pull request that adds translated files next to the originals.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use regex::Regex;
+use reqwest::header::{ACCEPT, AUTHORIZATION, USER_AGENT};
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::config::Settings;
+use crate::error::{AppError, AppResult};
+
+/// Maximum number of files translated from one repository request
+pub const MAX_GITHUB_FILES: usize = 500;
+
+/// GitHub API request timeout
+const GITHUB_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Characters escaped in a URL path segment: all but the unreserved ones
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// GitHub REST API client
+pub struct GitHubClient {
+    http: reqwest::Client,
+    api_url: String,
+    token: String,
+}
+
+impl GitHubClient {
+    /// Create a client from the settings
+    pub fn new(settings: &Settings) -> AppResult<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(GITHUB_TIMEOUT)
+            .build()
+            .map_err(|e| AppError::Internal(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(Self {
+            http,
+            api_url: settings.github_api_url.trim_end_matches('/').to_string(),
+            token: settings.github_token.clone(),
+        })
+    }
+
+    /// Build an authenticated request against the API
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut builder = self
+            .http
+            .request(method, format!("{}{}", self.api_url, path))
+            .header(USER_AGENT, "skill-translator")
+            .header(ACCEPT, "application/vnd.github+json");
+        if !self.token.is_empty() {
+            builder = builder.header(AUTHORIZATION, format!("Bearer {}", self.token));
+        }
+        builder
+    }
+
+    /// Send a request and parse the JSON body, mapping non-2xx responses to errors
+    async fn send_json(&self, builder: RequestBuilder) -> AppResult<Value> {
+        let (status, body) = self.send(builder).await?;
+        check_status(status, body)
+    }
+
+    /// [`Self::send_json`], with `None` when the resource does not exist
+    async fn send_json_optional(&self, builder: RequestBuilder) -> AppResult<Option<Value>> {
+        match self.send(builder).await? {
+            (StatusCode::NOT_FOUND, _) => Ok(None),
+            (status, body) => check_status(status, body).map(Some),
+        }
+    }
+
+    async fn send(&self, builder: RequestBuilder) -> AppResult<(StatusCode, Value)> {
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| AppError::GitHub(e.to_string()))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        Ok((status, body))
+    }
+
+    /// Whether a repository has a branch of this name, rather than `name` being a tag or commit
+    pub async fn branch_exists(&self, repo: &str, name: &str) -> AppResult<bool> {
+        let path = format!("/repos/{}/branches/{}", repo, encode_path(name));
+        Ok(self.send_json_optional(self.request(Method::GET, &path)).await?.is_some())
+    }
+
+    /// Default branch of a repository
+    pub async fn default_branch(&self, repo: &str) -> AppResult<String> {
+        let body = self
+            .send_json(self.request(Method::GET, &format!("/repos/{}", repo)))
+            .await?;
+        body.get("default_branch")
+            .and_then(|b| b.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| AppError::GitHub("Repository has no default branch".to_string()))
+    }
+
+    /// List blob paths at a ref matching a glob pattern. Fails when GitHub truncates the
+    /// listing of a very large tree, rather than silently missing files.
+    pub async fn list_files(&self, repo: &str, git_ref: &str, glob: &str) -> AppResult<Vec<String>> {
+        let pattern = glob_to_regex(glob)
+            .map_err(|e| AppError::BadRequest(format!("Invalid paths_glob: {}", e)))?;
+
+        let body = self
+            .send_json(
+                self.request(Method::GET, &format!("/repos/{}/git/trees/{}", repo, encode_path(git_ref)))
+                    .query(&[("recursive", "1")]),
+            )
+            .await?;
+
+        if body.get("truncated").and_then(|t| t.as_bool()).unwrap_or(false) {
+            return Err(AppError::Unprocessable(format!(
+                "The file tree of {}@{} is too large for GitHub to list at once, so matching files would be missed",
+                repo, git_ref
+            )));
+        }
+
+        Ok(body
+            .get("tree")
+            .and_then(|t| t.as_array())
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|e| e.get("type").and_then(|t| t.as_str()) == Some("blob"))
+                    .filter_map(|e| e.get("path").and_then(|p| p.as_str()))
+                    .filter(|path| pattern.is_match(path))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Fetch the UTF-8 content of a file at a ref
+    pub async fn fetch_file(&self, repo: &str, git_ref: &str, path: &str) -> AppResult<String> {
+        let body = self
+            .send_json(
+                self.request(Method::GET, &format!("/repos/{}/contents/{}", repo, encode_path(path)))
+                    .query(&[("ref", git_ref)]),
+            )
+            .await?;
+
+        let encoded: String = body
+            .get("content")
+            .and_then(|c| c.as_str())
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect();
+        let bytes = BASE64.decode(encoded.as_bytes())?;
+        String::from_utf8(bytes)
+            .map_err(|e| AppError::BadRequest(format!("Invalid UTF-8 content in {}: {}", path, e)))
+    }
+
+    /// Create a branch from `git_ref` (a branch, tag or commit) containing the given files
+    /// and open a pull request into `base`, replacing files that already exist.
+    /// Returns the pull request URL.
+    pub async fn open_pull_request(
+        &self,
+        repo: &str,
+        git_ref: &str,
+        base: &str,
+        branch: &str,
+        title: &str,
+        files: &[(String, String)],
+    ) -> AppResult<String> {
+        let commit = self
+            .send_json(self.request(Method::GET, &format!("/repos/{}/commits/{}", repo, encode_path(git_ref))))
+            .await?;
+        let base_sha = commit
+            .get("sha")
+            .and_then(|s| s.as_str())
+            .ok_or_else(|| AppError::GitHub(format!("Could not resolve ref {}", git_ref)))?;
+
+        self.send_json(
+            self.request(Method::POST, &format!("/repos/{}/git/refs", repo))
+                .json(&json!({ "ref": format!("refs/heads/{}", branch), "sha": base_sha })),
+        )
+        .await?;
+
+        for (path, content) in files {
+            let contents_path = format!("/repos/{}/contents/{}", repo, encode_path(path));
+            // Replacing a translation from an earlier run needs the sha of its blob
+            let existing = self
+                .send_json_optional(self.request(Method::GET, &contents_path).query(&[("ref", branch)]))
+                .await?;
+            let mut body = json!({
+                "message": format!("Add translation {}", path),
+                "content": BASE64.encode(content.as_bytes()),
+                "branch": branch,
+            });
+            if let Some(sha) = existing.as_ref().and_then(|file| file.get("sha")) {
+                body["message"] = json!(format!("Update translation {}", path));
+                body["sha"] = sha.clone();
+            }
+            self.send_json(self.request(Method::PUT, &contents_path).json(&body)).await?;
+        }
+
+        let pull = self
+            .send_json(
+                self.request(Method::POST, &format!("/repos/{}/pulls", repo))
+                    .json(&json!({
+                        "title": title,
+                        "head": branch,
+                        "base": base,
+                        "body": format!("Adds {} translated file(s) generated by Skill Translator.", files.len()),
+                    })),
+            )
+            .await?;
+
+        pull.get("html_url")
+            .and_then(|u| u.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| AppError::GitHub("Pull request response had no URL".to_string()))
+    }
+}
+
+/// Map a non-2xx response to an error with GitHub's message
+fn check_status(status: StatusCode, body: Value) -> AppResult<Value> {
+    if !status.is_success() {
+        let message = body
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        return Err(AppError::GitHub(format!("{} ({})", message, status)));
+    }
+    Ok(body)
+}
+
+/// A repository path or ref as a URL path, each `/`-separated segment percent-encoded
+fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Normalize `owner/name` or a github.com URL into `owner/name`
+pub fn parse_repo(repo: &str) -> Option<String> {
+    let trimmed = repo
+        .trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("github.com/")
+        .trim_end_matches('/')
+        .trim_end_matches(".git");
+
+    let parts: Vec<&str> = trimmed.split('/').collect();
+    match parts.as_slice() {
+        [owner, name] if !owner.is_empty() && !name.is_empty() => {
+            Some(format!("{}/{}", owner, name))
+        }
+        _ => None,
+    }
+}
+
+/// Path of the translated file next to the original, e.g. `SKILL.md` -> `SKILL.zh-CN.md`
+pub fn translated_path(path: &str, target_language: &str) -> String {
+    let file_start = path.rfind('/').map(|i| i + 1).unwrap_or(0);
+    match path[file_start..].rfind('.') {
+        Some(dot) if dot > 0 => {
+            let dot = file_start + dot;
+            format!("{}.{}{}", &path[..dot], target_language, &path[dot..])
+        }
+        _ => format!("{}.{}", path, target_language),
+    }
+}
+
+/// Convert a glob (`*`, `**`, `?`) into an anchored regex over `/`-separated paths
+pub fn glob_to_regex(glob: &str) -> Result<Regex, regex::Error> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` also matches zero directories
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            _ => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    pattern.push('$');
+    Regex::new(&pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_to_regex() {
+        let re = glob_to_regex("**/SKILL.md").unwrap();
+        assert!(re.is_match("SKILL.md"));
+        assert!(re.is_match("skills/owner/name/SKILL.md"));
+        assert!(!re.is_match("skills/owner/name/SKILL.md.bak"));
+
+        let re = glob_to_regex("skills/*/SKILL.md").unwrap();
+        assert!(re.is_match("skills/owner/SKILL.md"));
+        assert!(!re.is_match("skills/owner/name/SKILL.md"));
+    }
+
+    #[test]
+    fn test_parse_repo() {
+        assert_eq!(parse_repo("openclaw/skills"), Some("openclaw/skills".to_string()));
+        assert_eq!(
+            parse_repo("https://github.com/openclaw/skills.git"),
+            Some("openclaw/skills".to_string())
+        );
+        assert_eq!(parse_repo("openclaw"), None);
+        assert_eq!(parse_repo("a/b/c"), None);
+    }
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(encode_path("skills/my skill/SKILL.md"), "skills/my%20skill/SKILL.md");
+        assert_eq!(encode_path("docs/#1?.md"), "docs/%231%3F.md");
+        assert_eq!(encode_path("feature/技能"), "feature/%E6%8A%80%E8%83%BD");
+    }
+
+    #[test]
+    fn test_translated_path() {
+        assert_eq!(translated_path("skills/a/SKILL.md", "zh-CN"), "skills/a/SKILL.zh-CN.md");
+        assert_eq!(translated_path("README", "zh-CN"), "README.zh-CN");
+        assert_eq!(translated_path("a.b/.hidden", "zh-CN"), "a.b/.hidden.zh-CN");
+    }
+}
@@ -0,0 +1,218 @@
+//! Idempotency keys.
+//!
+//! Requests sharing a key coalesce onto one in-flight execution (singleflight):
+//! the first request runs, concurrent ones wait for it and receive the same
+//! result. Results accepted by the store's `keep` predicate are replayed to
+//! later requests with the key until the TTL expires.
+//!
+//! Besides `Idempotency-Key` headers, a store without replay deduplicates
+//! concurrent translations of the same cache key.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// The key was already used for a request with a different fingerprint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyReused;
+
+enum Slot<T> {
+    InFlight {
+        fingerprint: String,
+        done: watch::Receiver<Option<T>>,
+    },
+    Completed {
+        fingerprint: String,
+        value: T,
+        expires_at: Instant,
+    },
+}
+
+impl<T> Slot<T> {
+    fn fingerprint(&self) -> &str {
+        match self {
+            Slot::InFlight { fingerprint, .. } | Slot::Completed { fingerprint, .. } => fingerprint,
+        }
+    }
+}
+
+/// In-memory store of in-flight and completed executions by idempotency key
+pub struct IdempotencyStore<T> {
+    ttl: Duration,
+    /// Whether a completed result may be replayed, e.g. only successes
+    keep: fn(&T) -> bool,
+    slots: Mutex<HashMap<String, Slot<T>>>,
+}
+
+impl<T: Clone> IdempotencyStore<T> {
+    /// Create a store replaying kept results for `ttl` (zero only coalesces concurrent requests)
+    pub fn new(ttl: Duration, keep: fn(&T) -> bool) -> Self {
+        Self {
+            ttl,
+            keep,
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Number of executions currently running
+    pub fn in_flight(&self) -> usize {
+        self.lock()
+            .values()
+            .filter(|slot| matches!(slot, Slot::InFlight { .. }))
+            .count()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Slot<T>>> {
+        self.slots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Run `execute` once per key and fingerprint.
+    ///
+    /// Returns the result and whether it came from another request with the same key.
+    /// If the request running the key is cancelled, waiting requests execute on their own.
+    pub async fn run<F, Fut>(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        execute: F,
+    ) -> Result<(T, bool), KeyReused>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let waiting = {
+            let mut slots = self.lock();
+            let now = Instant::now();
+            slots.retain(|_, slot| {
+                !matches!(slot, Slot::Completed { expires_at, .. } if *expires_at <= now)
+            });
+
+            match slots.get(key) {
+                Some(slot) if slot.fingerprint() != fingerprint => return Err(KeyReused),
+                Some(Slot::Completed { value, .. }) => return Ok((value.clone(), true)),
+                Some(Slot::InFlight { done, .. }) => Some(done.clone()),
+                None => None,
+            }
+        };
+
+        if let Some(mut done) = waiting {
+            while done.borrow_and_update().is_none() {
+                if done.changed().await.is_err() {
+                    break;
+                }
+            }
+            if let Some(value) = done.borrow().clone() {
+                return Ok((value, true));
+            }
+            // The running request went away without a result
+            return Ok((execute().await, false));
+        }
+
+        let (sender, receiver) = watch::channel(None);
+        self.lock().insert(
+            key.to_string(),
+            Slot::InFlight {
+                fingerprint: fingerprint.to_string(),
+                done: receiver,
+            },
+        );
+        let mut guard = InFlightGuard {
+            store: self,
+            key,
+            armed: true,
+        };
+
+        let value = execute().await;
+
+        {
+            let mut slots = self.lock();
+            if (self.keep)(&value) && !self.ttl.is_zero() {
+                slots.insert(
+                    key.to_string(),
+                    Slot::Completed {
+                        fingerprint: fingerprint.to_string(),
+                        value: value.clone(),
+                        expires_at: Instant::now() + self.ttl,
+                    },
+                );
+            } else {
+                slots.remove(key);
+            }
+        }
+        guard.armed = false;
+        sender.send_replace(Some(value.clone()));
+
+        Ok((value, false))
+    }
+}
+
+/// Removes the in-flight slot when the executing request is dropped before finishing
+struct InFlightGuard<'a, T: Clone> {
+    store: &'a IdempotencyStore<T>,
+    key: &'a str,
+    armed: bool,
+}
+
+impl<T: Clone> Drop for InFlightGuard<'_, T> {
+    fn drop(&mut self) {
+        if self.armed {
+            let mut slots = self.store.lock();
+            if matches!(slots.get(self.key), Some(Slot::InFlight { .. })) {
+                slots.remove(self.key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn store(ttl: Duration) -> Arc<IdempotencyStore<u32>> {
+        Arc::new(IdempotencyStore::new(ttl, |value| *value < 100))
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_coalesce() {
+        let store = store(Duration::from_secs(60));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let run = |store: Arc<IdempotencyStore<u32>>, calls: Arc<AtomicUsize>| async move {
+            store
+                .run("key", "body", || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    7
+                })
+                .await
+        };
+
+        let (a, b) = tokio::join!(
+            run(store.clone(), calls.clone()),
+            run(store.clone(), calls.clone())
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(a.unwrap().0, 7);
+        assert_eq!(b.unwrap().0, 7);
+
+        // Replayed after completion
+        assert_eq!(run(store.clone(), calls.clone()).await.unwrap(), (7, true));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_key_reuse_and_unkept_results() {
+        let store = store(Duration::from_secs(60));
+
+        assert_eq!(store.run("key", "a", || async { 1 }).await, Ok((1, false)));
+        assert_eq!(store.run("key", "b", || async { 2 }).await, Err(KeyReused));
+
+        // Results rejected by `keep` are not replayed
+        assert_eq!(store.run("other", "a", || async { 500 }).await, Ok((500, false)));
+        assert_eq!(store.run("other", "a", || async { 3 }).await, Ok((3, false)));
+    }
+}
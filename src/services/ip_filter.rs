@@ -0,0 +1,99 @@
+//! Network-level access control by client address.
+//!
+//! DENIED_CIDRS and ALLOWED_CIDRS are checked before authentication, on the HTTP
+//! and gRPC APIs alike. The client is the connection's peer, unless the peer is
+//! one of TRUSTED_PROXIES: then `X-Forwarded-For` is read from the right, and the
+//! first address not itself a trusted proxy is the client. Connections over a
+//! Unix domain socket come from a local proxy and are trusted the same way.
+
+use std::net::IpAddr;
+
+use ipnet::IpNet;
+
+use crate::config::Settings;
+
+/// Header a proxy appends the address it received a request from to
+pub const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// A network in CIDR notation, or a single address
+pub fn parse_network(value: &str) -> Option<IpNet> {
+    value
+        .parse()
+        .ok()
+        .or_else(|| value.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+/// Whether the lists restrict anything at all
+pub fn enabled(settings: &Settings) -> bool {
+    !settings.allowed_cidrs.is_empty() || !settings.denied_cidrs.is_empty()
+}
+
+/// The client behind a connection from `peer`, `None` for a Unix domain socket.
+/// `forwarded_for` holds the `X-Forwarded-For` values in the order received.
+pub fn client_address<'a>(
+    peer: Option<IpAddr>,
+    forwarded_for: impl IntoIterator<Item = &'a str>,
+    trusted_proxies: &[IpNet],
+) -> Option<IpAddr> {
+    let trusted = |address: &IpAddr| trusted_proxies.iter().any(|network| network.contains(address));
+    // IPv4 clients of a dual-stack listener arrive as IPv4-mapped IPv6 addresses
+    let peer = peer.map(|peer| peer.to_canonical());
+    if peer.is_some_and(|peer| !trusted(&peer)) {
+        return peer;
+    }
+
+    let forwarded: Vec<&str> = forwarded_for.into_iter().flat_map(|value| value.split(',')).collect();
+    let mut client = peer;
+    for hop in forwarded.iter().rev() {
+        // An entry that is no address cannot be vouched for, so the search stops there
+        let Ok(address) = hop.trim().parse::<IpAddr>() else {
+            break;
+        };
+        let address = address.to_canonical();
+        client = Some(address);
+        if !trusted(&address) {
+            break;
+        }
+    }
+    client
+}
+
+/// Whether `client` may use the service: not denied, and allowed when an allow list
+/// is set. An unknown client only passes when there is no allow list.
+pub fn permits(settings: &Settings, client: Option<IpAddr>) -> bool {
+    let within = |networks: &[IpNet]| client.is_some_and(|client| networks.iter().any(|n| n.contains(&client)));
+    !within(&settings.denied_cidrs) && (settings.allowed_cidrs.is_empty() || within(&settings.allowed_cidrs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_address_and_lists() {
+        let ip = |value: &str| value.parse::<IpAddr>().unwrap();
+        let proxies = [parse_network("10.0.0.0/8").unwrap(), parse_network("::1").unwrap()];
+
+        // Untrusted peers cannot pose as someone else
+        let client = client_address(Some(ip("203.0.113.9")), ["198.51.100.1"], &proxies);
+        assert_eq!(client, Some(ip("203.0.113.9")));
+        // Behind two trusted proxies the client is the last untrusted hop
+        let client = client_address(Some(ip("10.0.0.2")), ["198.51.100.7, 203.0.113.5", "10.0.0.1"], &proxies);
+        assert_eq!(client, Some(ip("203.0.113.5")));
+        let client = client_address(Some(ip("10.0.0.2")), ["garbage, 10.0.0.3"], &proxies);
+        assert_eq!(client, Some(ip("10.0.0.3")));
+        assert_eq!(client_address(None, ["198.51.100.7"], &[]), Some(ip("198.51.100.7")));
+        assert_eq!(client_address(None, [], &[]), None);
+
+        let mut settings = Settings::from_vars(|_| None).unwrap();
+        assert!(!enabled(&settings) && permits(&settings, None));
+        settings.allowed_cidrs = vec![parse_network("203.0.113.0/24").unwrap()];
+        settings.denied_cidrs = vec![parse_network("203.0.113.66").unwrap()];
+        assert!(permits(&settings, Some(ip("203.0.113.5"))));
+        assert!(!permits(&settings, Some(ip("203.0.113.66"))));
+        assert!(!permits(&settings, Some(ip("198.51.100.7"))));
+        assert!(!permits(&settings, None));
+        settings.allowed_cidrs.clear();
+        assert!(permits(&settings, Some(ip("198.51.100.7"))) && permits(&settings, None));
+    }
+}
@@ -1,3 +1,21 @@
-pub mod cache;
-pub mod parser;
-pub mod translator;
+pub mod archive;
+pub mod audit;
+pub mod backup;
+pub mod batch;
+pub mod budget;
+pub mod error_messages;
+pub mod github;
+pub mod idempotency;
+pub mod ip_filter;
+pub mod payload_log;
+pub mod pipeline;
+pub mod prompts;
+pub mod review;
+pub mod schedule;
+pub mod schema;
+pub mod signing;
+pub mod sources;
+pub mod terminology;
+pub mod warmer;
+
+pub use skillts_core::{cache, translator, validate};
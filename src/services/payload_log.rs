@@ -0,0 +1,212 @@
+//! Recent request and response payloads, for debugging client integrations.
+//!
+//! With DEBUG_PAYLOAD_LOG enabled, the API keeps the last DEBUG_PAYLOAD_LOG_SIZE
+//! exchanges in memory and serves them at `GET /api/admin/recent-requests`.
+//! Nothing reaches disk. Document content is replaced by its size and SHA256
+//! hash, credentials by a placeholder, and bodies are cut to [`MAX_BODY_CHARS`].
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use axum::http::HeaderMap;
+use serde_json::Value;
+
+use crate::models::schemas::PayloadRecord;
+use crate::services::translator::Translator;
+
+/// Bodies longer than this once redacted are kept as truncated JSON text
+pub const MAX_BODY_CHARS: usize = 4096;
+
+/// Fields holding document content, kept as size and hash only
+const CONTENT_FIELDS: &[&str] = &[
+    "content",
+    "translated_content",
+    "old_content",
+    "new_content",
+    "old_translation",
+    "back_translation",
+    "original",
+    "translated",
+    "body_with_placeholders",
+];
+
+/// Lists whose items hold content in their `value`, such as preview frontmatter fields
+const CONTENT_LISTS: &[&str] = &["frontmatter_fields"];
+
+/// Field and header name endings that hold credentials
+const SECRET_SUFFIXES: &[&str] = &["api_key", "token", "secret", "password", "authorization", "cookie"];
+
+/// Placeholder of a redacted credential
+const REDACTED: &str = "[redacted]";
+
+/// The most recent exchanges, oldest first
+#[derive(Default)]
+pub struct PayloadLog {
+    records: Mutex<VecDeque<PayloadRecord>>,
+    next_id: AtomicU64,
+}
+
+impl PayloadLog {
+    /// Add an exchange, numbering it and dropping the oldest beyond `capacity`
+    pub fn record(&self, mut record: PayloadRecord, capacity: usize) {
+        record.id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        while records.len() >= capacity.max(1) {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Up to `limit` exchanges, newest first
+    pub fn recent(&self, limit: usize) -> Vec<PayloadRecord> {
+        let records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        records.iter().rev().take(limit).cloned().collect()
+    }
+}
+
+/// Headers with credentials replaced
+pub fn redact_headers(headers: &HeaderMap) -> Value {
+    let headers = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_secret(name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.to_string(), Value::String(value))
+        })
+        .collect();
+    Value::Object(headers)
+}
+
+/// A body as it is logged: redacted JSON, or a description of anything else
+pub fn redact_body(body: &[u8], content_type: Option<&str>) -> Option<Value> {
+    if body.is_empty() {
+        return None;
+    }
+    let mut value = match serde_json::from_slice::<Value>(body) {
+        Ok(value) => value,
+        Err(_) => {
+            return Some(Value::String(format!(
+                "[{} bytes of {}]",
+                body.len(),
+                content_type.unwrap_or("unknown content")
+            )))
+        }
+    };
+    redact(&mut value);
+
+    let text = value.to_string();
+    if text.chars().count() <= MAX_BODY_CHARS {
+        return Some(value);
+    }
+    let cut: String = text.chars().take(MAX_BODY_CHARS).collect();
+    Some(Value::String(format!("{}... [{} bytes in total]", cut, text.len())))
+}
+
+/// Replace content with its size and hash and credentials with a placeholder, recursively
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if is_secret(name) {
+                    *field = Value::String(REDACTED.to_string());
+                } else if let (true, Value::String(text)) = (CONTENT_FIELDS.contains(&name.as_str()), &field) {
+                    *field = summary(text);
+                } else if let (true, Value::Array(items)) = (CONTENT_LISTS.contains(&name.as_str()), &mut *field) {
+                    for item in items.iter_mut() {
+                        if let Some(Value::String(text)) = item.get("value") {
+                            item["value"] = summary(text);
+                        }
+                    }
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Size and hash standing in for content
+fn summary(text: &str) -> Value {
+    Value::String(format!("[{} bytes, {}]", text.len(), Translator::compute_hash(text)))
+}
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_lowercase().replace('-', "_");
+    SECRET_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_redaction_and_ring_buffer() {
+        let body = serde_json::json!({
+            "path": "SKILL.md",
+            "content": "# Hello",
+            "options": {"target_language": "ja", "github_token": "ghp_secret"},
+            "files": [{"path": "a.md", "content": "text", "cache_key": "k"}],
+        });
+        let logged = redact_body(body.to_string().as_bytes(), Some("application/json")).unwrap();
+        assert_eq!(logged["path"], "SKILL.md");
+        assert_eq!(logged["content"], format!("[7 bytes, {}]", Translator::compute_hash("# Hello")));
+        assert_eq!(logged["options"]["github_token"], REDACTED);
+        assert_eq!(logged["options"]["target_language"], "ja");
+        assert_eq!(logged["files"][0]["cache_key"], "k");
+        assert!(logged["files"][0]["content"].as_str().unwrap().starts_with("[4 bytes, sha256:"));
+
+        let validate = serde_json::json!({"original": "IyBIZWxsbw==", "translated": "IyDkvaDlpb0="});
+        let logged = redact_body(validate.to_string().as_bytes(), Some("application/json")).unwrap();
+        assert_eq!(logged["original"], format!("[12 bytes, {}]", Translator::compute_hash("IyBIZWxsbw==")));
+        assert!(logged["translated"].as_str().unwrap().starts_with("[12 bytes, sha256:"));
+
+        let preview = serde_json::json!({
+            "frontmatter_fields": [{"name": "description", "value": "Greets the user"}],
+            "body_with_placeholders": "# Hello\n\n__CODE_BLOCK_0__",
+            "model": "gpt-4o-mini",
+        });
+        let logged = redact_body(preview.to_string().as_bytes(), Some("application/json")).unwrap();
+        assert_eq!(logged["frontmatter_fields"][0]["name"], "description");
+        assert!(logged["frontmatter_fields"][0]["value"].as_str().unwrap().starts_with("[15 bytes, sha256:"));
+        assert!(logged["body_with_placeholders"].as_str().unwrap().starts_with("[25 bytes, sha256:"));
+        assert_eq!(logged["model"], "gpt-4o-mini");
+
+        let long = serde_json::json!({"detail": "x".repeat(MAX_BODY_CHARS)});
+        let cut = redact_body(long.to_string().as_bytes(), None).unwrap();
+        assert!(cut.as_str().unwrap().ends_with(" bytes in total]"));
+        assert_eq!(redact_body(b"PK\x03\x04", Some("application/zip")).unwrap(), "[4 bytes of application/zip]");
+        assert_eq!(redact_body(b"", None), None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer key".parse().unwrap());
+        headers.insert("x-tenant", "registry-a".parse().unwrap());
+        let headers = redact_headers(&headers);
+        assert_eq!((headers["authorization"].as_str(), headers["x-tenant"].as_str()), (Some(REDACTED), Some("registry-a")));
+
+        let log = PayloadLog::default();
+        for path in ["/api/a", "/api/b", "/api/c"] {
+            let record = PayloadRecord {
+                id: 0,
+                timestamp: Utc::now(),
+                request_id: None,
+                method: "POST".to_string(),
+                path: path.to_string(),
+                status: 200,
+                duration_ms: 1.0,
+                request_headers: Value::Null,
+                request_body: None,
+                response_body: None,
+            };
+            log.record(record, 2);
+        }
+        let recent = log.recent(10);
+        assert_eq!((recent.len(), recent[0].path.as_str(), recent[0].id), (2, "/api/c", 3));
+    }
+}
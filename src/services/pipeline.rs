@@ -0,0 +1,313 @@
+//! Translate-and-cache steps shared by the API handlers and the command line.
+//!
+//! A file is prepared (its hash checked, overlong lines handled and cache keys
+//! computed), looked up in the cache, and otherwise translated and stored. The
+//! handlers wrap these steps with quotas, budgets, terminology memory, reviews and
+//! the audit log; the command line needs nothing but a translator and a cache.
+
+use crate::config::Settings;
+use crate::error::AppError;
+use crate::models::schemas::{CacheEntry, LongLineMode, LongLines, TranslationResponseMetadata};
+use crate::services::cache::batch::BatchCache;
+use crate::services::cache::{NewEntry, TranslationCache};
+use crate::services::translator::{TranslationMetadata, TranslationProfile, Translator};
+
+/// The translator, cache and settings a translation runs with
+#[derive(Clone, Copy)]
+pub struct Pipeline<'a> {
+    pub translator: &'a Translator,
+    pub cache: &'a TranslationCache,
+    pub settings: &'a Settings,
+}
+
+/// Content ready to be looked up in the cache
+pub struct PreparedFile {
+    pub content: String,
+    pub content_hash: String,
+    pub long_lines: Option<LongLines>,
+    /// The requested profile with an `auto` source language resolved for the content
+    pub profile: TranslationProfile,
+    /// Candidate cache keys, primary model first, then fallbacks
+    pub cache_keys: Vec<String>,
+}
+
+impl PreparedFile {
+    /// Whether overlong lines were dropped or truncated from the content
+    pub fn lossy(&self) -> bool {
+        self.long_lines.as_ref().is_some_and(LongLines::lossy)
+    }
+}
+
+/// A translation just produced by the upstream API
+pub struct Translated {
+    pub translated_content: String,
+    pub translated_hash: String,
+    /// Key of the model that produced it, under which it was stored
+    pub cache_key: String,
+    pub metadata: TranslationMetadata,
+    /// Whether it was stored; lossy translations are not unless configured
+    pub stored: bool,
+}
+
+impl Pipeline<'_> {
+    /// Check decoded content against its claimed hash, handle its overlong lines and
+    /// compute its cache keys
+    pub fn prepare(
+        &self,
+        content: &str,
+        claimed_hash: Option<&str>,
+        profile: &TranslationProfile,
+    ) -> Result<PreparedFile, AppError> {
+        let content_hash = verify_content_hash(content, claimed_hash)?;
+
+        // Drop, truncate or refuse overlong lines as configured
+        let (content, long_lines) = handle_long_lines(self.settings, content)?;
+
+        // Detect an `auto` source language so the cache key names the actual one
+        let profile = self.translator.resolve_profile(&content, profile)?;
+        let cache_keys = self.translator.cache_keys(&content_hash, &profile);
+
+        Ok(PreparedFile {
+            content,
+            content_hash,
+            long_lines,
+            profile,
+            cache_keys,
+        })
+    }
+
+    /// The cached translation that may answer a request for `path`, read from the
+    /// prefetched `batch` when given. Records `path` for content cached under another one.
+    pub async fn cached(
+        &self,
+        prepared: &PreparedFile,
+        path: &str,
+        batch: Option<&BatchCache>,
+    ) -> Result<Option<CacheEntry>, AppError> {
+        let tenant = &prepared.profile.tenant;
+        let cached = match batch {
+            Some(batch) => batch.get_first(tenant, &prepared.cache_keys).await?,
+            None => self.cache.get_first(tenant, &prepared.cache_keys).await?,
+        };
+        let Some(cached) = cached.filter(|cached| usable_hit(self.settings, cached, prepared.lossy())) else {
+            return Ok(None);
+        };
+        if cached.path != path {
+            self.cache.record_path(tenant, &prepared.content_hash, path).await?;
+        }
+        Ok(Some(cached))
+    }
+
+    /// Translate content and store the result under the tenant, queued in `batch` when
+    /// given. `lossy` marks content that lost overlong lines, cached only with
+    /// `CACHE_LOSSY_TRANSLATIONS`.
+    pub async fn translate(
+        &self,
+        content: &str,
+        content_hash: &str,
+        path: &str,
+        profile: &TranslationProfile,
+        lossy: bool,
+        batch: Option<&BatchCache>,
+    ) -> Result<Translated, AppError> {
+        // Reuse unchanged sections when the segment cache is enabled; either way upstream
+        // results are stored as they arrive, so a retry resumes from them
+        let (translated_content, metadata) = if self.settings.segment_cache {
+            self.translator.translate_with_segments(content, profile, self.cache).await?
+        } else {
+            self.translator.translate_resumable(content, profile, self.cache).await?
+        };
+        let translated_hash = Translator::compute_hash(&translated_content);
+
+        // Key by the model that actually produced the translation
+        let cache_key = self.translator.cache_key_for_model(content_hash, profile, &metadata.model);
+
+        let stored = !lossy || self.settings.cache_lossy_translations;
+        if stored {
+            let stored_metadata = TranslationResponseMetadata {
+                tenant: Some(profile.tenant.clone()),
+                lossy: Some(lossy),
+                ..TranslationResponseMetadata::new(&metadata)
+            };
+            let entry = NewEntry {
+                cache_key: cache_key.clone(),
+                content_hash: content_hash.to_string(),
+                path: path.to_string(),
+                translated_content: translated_content.clone(),
+                translated_hash: translated_hash.clone(),
+                metadata: Some(stored_metadata.to_stored()),
+            };
+            match batch {
+                Some(batch) => batch.set(entry).await?,
+                None => {
+                    self.cache.set_many(vec![entry]).await?;
+                }
+            }
+        } else {
+            tracing::info!("[{}] Not caching translation of content with dropped or truncated lines", path);
+        }
+
+        Ok(Translated {
+            translated_content,
+            translated_hash,
+            cache_key,
+            metadata,
+            stored,
+        })
+    }
+}
+
+/// Hash of decoded content. A client-supplied hash must match it, since a wrong
+/// hash would store the translation under another file's cache key.
+pub fn verify_content_hash(content: &str, claimed: Option<&str>) -> Result<String, AppError> {
+    let actual = Translator::compute_hash(content);
+    match claimed {
+        Some(claimed) if claimed != actual => Err(AppError::BadRequest(format!(
+            "content_hash {} does not match the content, expected {}",
+            claimed, actual
+        ))),
+        _ => Ok(actual),
+    }
+}
+
+/// Apply `LONG_LINE_MODE` to lines longer than `MAX_LINE_LENGTH` characters, returning the
+/// content to translate and, when there are any such lines, a report of them
+pub fn handle_long_lines(
+    settings: &Settings,
+    content: &str,
+) -> Result<(String, Option<LongLines>), AppError> {
+    let (mode, max_length) = (settings.long_line_mode, settings.max_line_length);
+    let mut kept = String::with_capacity(content.len());
+    let mut lines = Vec::new();
+
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let text = line.trim_end_matches(['\n', '\r']);
+        let Some((end, _)) = text.char_indices().nth(max_length) else {
+            kept.push_str(line);
+            continue;
+        };
+        lines.push(index + 1);
+        match mode {
+            LongLineMode::Drop => {}
+            LongLineMode::Truncate => {
+                kept.push_str(&text[..end]);
+                kept.push_str(&line[text.len()..]);
+            }
+            LongLineMode::Passthrough | LongLineMode::Reject => kept.push_str(line),
+        }
+    }
+
+    if lines.is_empty() {
+        return Ok((kept, None));
+    }
+    let numbers = lines.iter().map(usize::to_string).collect::<Vec<_>>().join(", ");
+    if mode == LongLineMode::Reject {
+        return Err(AppError::Unprocessable(format!(
+            "Lines exceed {} characters: {}",
+            max_length, numbers
+        )));
+    }
+    tracing::info!(
+        "Lines exceeding {} characters ({:?}): {}",
+        max_length,
+        mode,
+        numbers
+    );
+
+    Ok((
+        kept,
+        Some(LongLines {
+            mode,
+            max_length,
+            count: lines.len(),
+            lines,
+        }),
+    ))
+}
+
+/// Whether a cache hit may answer a request. A translation of content that lost overlong
+/// lines only answers requests losing lines too, and only while such translations are cached.
+pub fn usable_hit(settings: &Settings, cached: &CacheEntry, lossy: bool) -> bool {
+    let cached_lossy = cached.metadata.get("lossy").and_then(|v| v.as_bool()) == Some(true);
+    !cached_lossy || (lossy && settings.cache_lossy_translations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_handle_long_lines() {
+        let settings = |mode: &str| {
+            let mode = mode.to_string();
+            Settings::from_vars(move |key| match key {
+                "MAX_LINE_LENGTH" => Some("4".to_string()),
+                "LONG_LINE_MODE" => Some(mode.clone()),
+                _ => None,
+            })
+            .unwrap()
+        };
+        let content = "ok\nlonger\r\nfine\ntoo long\n";
+
+        let (dropped, report) = handle_long_lines(&settings("drop"), content).unwrap();
+        assert_eq!(dropped, "ok\nfine\n");
+        let report = report.unwrap();
+        assert_eq!((report.count, report.lines, report.max_length), (2, vec![2, 4], 4));
+
+        let (truncated, _) = handle_long_lines(&settings("truncate"), content).unwrap();
+        assert_eq!(truncated, "ok\nlong\r\nfine\ntoo \n");
+
+        let (kept, report) = handle_long_lines(&settings("passthrough"), content).unwrap();
+        assert_eq!((kept.as_str(), report.unwrap().mode), (content, LongLineMode::Passthrough));
+
+        assert!(matches!(
+            handle_long_lines(&settings("reject"), content),
+            Err(AppError::Unprocessable(_))
+        ));
+        assert_eq!(handle_long_lines(&settings("reject"), "ok\n").unwrap(), ("ok\n".to_string(), None));
+    }
+
+    #[test]
+    fn test_verify_content_hash() {
+        let content = "# Demo\n";
+        let actual = Translator::compute_hash(content);
+        assert!(actual.starts_with("sha256:"));
+        assert_eq!(verify_content_hash(content, None).unwrap(), actual);
+        assert_eq!(verify_content_hash(content, Some(&actual)).unwrap(), actual);
+
+        // A hash of other content would store the translation under that content's key
+        let other = Translator::compute_hash("# Other\n");
+        assert!(matches!(
+            verify_content_hash(content, Some(&other)),
+            Err(AppError::BadRequest(message)) if message.contains(&actual)
+        ));
+    }
+
+    #[test]
+    fn test_lossy_cache_hits() {
+        let entry = |metadata: serde_json::Value| CacheEntry {
+            cache_key: "key".to_string(),
+            content_hash: "hash".to_string(),
+            path: "SKILL.md".to_string(),
+            translated_content: "你好".to_string(),
+            translated_hash: "translated".to_string(),
+            created_at: chrono::Utc::now(),
+            accessed_at: chrono::Utc::now(),
+            hit_count: 0,
+            metadata,
+        };
+        let (clean, lossy) = (entry(json!({})), entry(json!({"lossy": true})));
+        let settings = Settings::from_vars(|_| None).unwrap();
+        assert!(usable_hit(&settings, &clean, false) && usable_hit(&settings, &clean, true));
+        assert!(usable_hit(&settings, &lossy, true));
+        // Long lines are no longer dropped, so the lossy translation is stale
+        assert!(!usable_hit(&settings, &lossy, false));
+
+        let settings = Settings::from_vars(|key| {
+            (key == "CACHE_LOSSY_TRANSLATIONS").then(|| "false".to_string())
+        })
+        .unwrap();
+        assert!(!usable_hit(&settings, &lossy, true));
+    }
+}
@@ -0,0 +1,243 @@
+//! Saved system prompt templates.
+//!
+//! Every save appends a row to `prompt_template_versions`, whose id is the
+//! template version; `prompt_templates` points each name at its active version.
+//! Versions are never reused, even after a template is deleted, so a cache key
+//! naming a version always refers to the same prompt text.
+
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::{SqlitePool, SqliteRow};
+use sqlx::Row;
+
+use skillts_core::migrate::{migrate, Migration, Schema, Step};
+use skillts_core::prompt::PromptTemplate;
+
+use crate::error::{AppError, AppResult};
+use crate::models::schemas::SavePromptRequest;
+
+/// Longest accepted template name
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Migrations of the prompt template tables
+pub const SCHEMA: Schema = Schema {
+    component: "prompts",
+    migrations: &[Migration {
+        version: 1,
+        description: "Create prompt_template_versions and prompt_templates",
+        step: Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS prompt_template_versions (
+                version INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                document_type TEXT NOT NULL,
+                source_language TEXT NOT NULL,
+                target_language TEXT NOT NULL,
+                template TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS prompt_templates (
+                name TEXT PRIMARY KEY,
+                version INTEGER NOT NULL REFERENCES prompt_template_versions(version)
+            );
+            CREATE INDEX IF NOT EXISTS idx_prompt_template_versions_name ON prompt_template_versions(name);
+            "#,
+        ),
+    }],
+};
+
+/// SQLite-backed store of named prompt template overrides
+pub struct PromptStore {
+    pool: SqlitePool,
+}
+
+impl PromptStore {
+    /// Create the prompt store on the cache database pool, applying pending migrations
+    pub async fn new(pool: SqlitePool) -> AppResult<Self> {
+        migrate(&pool, &SCHEMA).await?;
+
+        Ok(Self { pool })
+    }
+
+    /// Active version of every saved template, by name
+    pub async fn list(&self) -> AppResult<Vec<PromptTemplate>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT v.* FROM prompt_templates t
+            JOIN prompt_template_versions v ON v.version = t.version
+            ORDER BY t.name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(template_from_row).collect())
+    }
+
+    /// Active version of a template
+    pub async fn get(&self, name: &str) -> AppResult<PromptTemplate> {
+        let row = sqlx::query(
+            r#"
+            SELECT v.* FROM prompt_templates t
+            JOIN prompt_template_versions v ON v.version = t.version
+            WHERE t.name = ?
+            "#,
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref()
+            .map(template_from_row)
+            .ok_or_else(|| AppError::NotFound(format!("Prompt template {} not found", name)))
+    }
+
+    /// Every saved version of a template, newest first, including deleted templates
+    pub async fn versions(&self, name: &str) -> AppResult<Vec<PromptTemplate>> {
+        let rows = sqlx::query(
+            "SELECT * FROM prompt_template_versions WHERE name = ? ORDER BY version DESC",
+        )
+        .bind(name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        if rows.is_empty() {
+            return Err(AppError::NotFound(format!("Prompt template {} not found", name)));
+        }
+        Ok(rows.iter().map(template_from_row).collect())
+    }
+
+    /// Save a new version of a template and make it active
+    pub async fn save(&self, name: &str, request: &SavePromptRequest) -> AppResult<PromptTemplate> {
+        validate(name, request)?;
+
+        let mut tx = self.pool.begin().await?;
+        let version: i64 = sqlx::query_scalar(
+            r#"
+            INSERT INTO prompt_template_versions (
+                name, document_type, source_language, target_language, template, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING version
+            "#,
+        )
+        .bind(name)
+        .bind(&request.document_type)
+        .bind(&request.source_language)
+        .bind(&request.target_language)
+        .bind(&request.template)
+        .bind(Utc::now().to_rfc3339())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("INSERT OR REPLACE INTO prompt_templates (name, version) VALUES (?, ?)")
+            .bind(name)
+            .bind(version)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        tracing::info!("Saved prompt template {} version {}", name, version);
+        self.get(name).await
+    }
+
+    /// Deactivate a template; its versions are kept
+    pub async fn delete(&self, name: &str) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM prompt_templates WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound(format!("Prompt template {} not found", name)));
+        }
+        tracing::info!("Deleted prompt template {}", name);
+        Ok(())
+    }
+}
+
+/// Reject unusable names and empty templates
+fn validate(name: &str, request: &SavePromptRequest) -> AppResult<()> {
+    let valid_name = !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid_name {
+        return Err(AppError::BadRequest(format!(
+            "Prompt template names must be 1-{} characters of letters, digits, '-', '_' or '.'",
+            MAX_NAME_LENGTH
+        )));
+    }
+
+    if request.template.trim().is_empty() {
+        return Err(AppError::BadRequest("Prompt template must not be empty".to_string()));
+    }
+    for (field, value) in [
+        ("document_type", &request.document_type),
+        ("source_language", &request.source_language),
+        ("target_language", &request.target_language),
+    ] {
+        if value.trim().is_empty() {
+            return Err(AppError::BadRequest(format!("{} must not be empty", field)));
+        }
+    }
+    Ok(())
+}
+
+/// Build a template from a `prompt_template_versions` row
+fn template_from_row(row: &SqliteRow) -> PromptTemplate {
+    PromptTemplate {
+        name: row.get("name"),
+        document_type: row.get("document_type"),
+        source_language: row.get("source_language"),
+        target_language: row.get("target_language"),
+        version: row.get("version"),
+        template: row.get("template"),
+        created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(template: &str) -> SavePromptRequest {
+        SavePromptRequest {
+            document_type: "skill".to_string(),
+            source_language: "*".to_string(),
+            target_language: "ja".to_string(),
+            template: template.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_versions_are_never_reused() {
+        // A single connection, since every in-memory connection is its own database
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let store = PromptStore::new(pool).await.unwrap();
+
+        let first = store.save("japanese", &request("v1")).await.unwrap();
+        let second = store.save("japanese", &request("v2")).await.unwrap();
+        assert!(second.version > first.version);
+        assert_eq!(store.list().await.unwrap().len(), 1);
+        assert_eq!(store.get("japanese").await.unwrap().template, "v2");
+
+        store.delete("japanese").await.unwrap();
+        assert!(store.list().await.unwrap().is_empty());
+        let third = store.save("japanese", &request("v3")).await.unwrap();
+        assert!(third.version > second.version);
+        assert_eq!(store.versions("japanese").await.unwrap().len(), 3);
+
+        assert!(matches!(
+            store.save("bad name", &request("v1")).await,
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(store.delete("missing").await, Err(AppError::NotFound(_))));
+    }
+}
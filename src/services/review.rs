@@ -0,0 +1,332 @@
+//! Translation quality review queue.
+//!
+//! Suspicious translations are flagged automatically by cheap heuristics or a
+//! low judge score, or manually through the API, stored in the `reviews` table of the cache
+//! database, and resolved by reviewers with an optional corrected translation.
+
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::{SqlitePool, SqliteRow};
+use sqlx::Row;
+
+use skillts_core::migrate::{migrate, Migration, Schema, Step};
+use skillts_core::quality::QualityScore;
+
+use crate::config::Settings;
+use crate::error::{AppError, AppResult};
+use crate::models::schemas::ReviewItem;
+
+/// Review sources
+pub const SOURCE_AUTO: &str = "auto";
+pub const SOURCE_MANUAL: &str = "manual";
+pub const SOURCE_QUALITY: &str = "quality";
+
+/// Review statuses
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_RESOLVED: &str = "resolved";
+
+/// Migrations of the reviews table
+pub const SCHEMA: Schema = Schema {
+    component: "reviews",
+    migrations: &[Migration {
+        version: 1,
+        description: "Create reviews",
+        step: Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS reviews (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                cache_key TEXT NOT NULL,
+                path TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                source TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                note TEXT,
+                created_at TEXT NOT NULL,
+                resolved_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_reviews_status ON reviews(status);
+            "#,
+        ),
+    }],
+};
+
+/// SQLite-backed queue of translations awaiting human review
+pub struct ReviewQueue {
+    pool: SqlitePool,
+    min_length_ratio: f64,
+    max_length_ratio: f64,
+    min_quality_score: u8,
+}
+
+impl ReviewQueue {
+    /// Create a review queue on the cache database pool, applying pending migrations
+    pub async fn new(pool: SqlitePool, settings: &Settings) -> AppResult<Self> {
+        migrate(&pool, &SCHEMA).await?;
+
+        Ok(Self {
+            pool,
+            min_length_ratio: settings.review_min_length_ratio,
+            max_length_ratio: settings.review_max_length_ratio,
+            min_quality_score: settings.review_min_quality_score,
+        })
+    }
+
+    /// Run the heuristics on a fresh translation and flag it if anything looks off.
+    /// Failures are logged rather than returned so they never fail a translation.
+    pub async fn check_translation(
+        &self,
+        cache_key: &str,
+        path: &str,
+        content_hash: &str,
+        original: &str,
+        translated: &str,
+    ) {
+        let Some(reason) =
+            detect_anomaly(original, translated, self.min_length_ratio, self.max_length_ratio)
+        else {
+            return;
+        };
+
+        tracing::info!("[{}] Flagged translation for review: {}", path, reason);
+
+        if let Err(e) = self
+            .insert(cache_key, path, content_hash, &reason, SOURCE_AUTO)
+            .await
+        {
+            tracing::warn!("[{}] Failed to queue review: {}", path, e);
+        }
+    }
+
+    /// Queue a judged translation scoring below the minimum for review, so it can be
+    /// resolved with a corrected translation. Failures are logged rather than returned.
+    pub async fn check_quality(
+        &self,
+        cache_key: &str,
+        path: &str,
+        content_hash: &str,
+        quality: &QualityScore,
+    ) {
+        let Some(reason) = low_quality(quality, self.min_quality_score) else {
+            return;
+        };
+
+        tracing::info!("[{}] Queued low-scoring translation for review: {}", path, reason);
+
+        if let Err(e) = self
+            .insert(cache_key, path, content_hash, &reason, SOURCE_QUALITY)
+            .await
+        {
+            tracing::warn!("[{}] Failed to queue review: {}", path, e);
+        }
+    }
+
+    /// Manually flag a cached translation for review
+    pub async fn flag(&self, cache_key: &str, reason: &str) -> AppResult<ReviewItem> {
+        let row = sqlx::query("SELECT path, content_hash FROM translations WHERE cache_key = ?")
+            .bind(cache_key)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("No cache entry for key {}", cache_key)))?;
+
+        let path: String = row.get("path");
+        let content_hash: String = row.get("content_hash");
+
+        self.insert(cache_key, &path, &content_hash, reason, SOURCE_MANUAL)
+            .await
+    }
+
+    /// Insert a new pending review
+    async fn insert(
+        &self,
+        cache_key: &str,
+        path: &str,
+        content_hash: &str,
+        reason: &str,
+        source: &str,
+    ) -> AppResult<ReviewItem> {
+        let now = Utc::now();
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO reviews (cache_key, path, content_hash, reason, source, status, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(cache_key)
+        .bind(path)
+        .bind(content_hash)
+        .bind(reason)
+        .bind(source)
+        .bind(STATUS_PENDING)
+        .bind(now.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(ReviewItem {
+            id: result.last_insert_rowid(),
+            cache_key: cache_key.to_string(),
+            path: path.to_string(),
+            content_hash: content_hash.to_string(),
+            reason: reason.to_string(),
+            source: source.to_string(),
+            status: STATUS_PENDING.to_string(),
+            note: None,
+            created_at: now,
+            resolved_at: None,
+        })
+    }
+
+    /// List reviews, optionally filtered by status, oldest first
+    pub async fn list(&self, status: Option<&str>) -> AppResult<Vec<ReviewItem>> {
+        let rows = match status {
+            Some(status) => {
+                sqlx::query("SELECT * FROM reviews WHERE status = ? ORDER BY id")
+                    .bind(status)
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+            None => {
+                sqlx::query("SELECT * FROM reviews ORDER BY id")
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+        };
+
+        Ok(rows.iter().map(review_from_row).collect())
+    }
+
+    /// Get a single review by id
+    pub async fn get(&self, id: i64) -> AppResult<ReviewItem> {
+        let row = sqlx::query("SELECT * FROM reviews WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Review {} not found", id)))?;
+
+        Ok(review_from_row(&row))
+    }
+
+    /// Mark a review as resolved
+    pub async fn resolve(&self, id: i64, note: Option<&str>) -> AppResult<ReviewItem> {
+        let result = sqlx::query(
+            "UPDATE reviews SET status = ?, note = ?, resolved_at = ? WHERE id = ?",
+        )
+        .bind(STATUS_RESOLVED)
+        .bind(note)
+        .bind(Utc::now().to_rfc3339())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound(format!("Review {} not found", id)));
+        }
+
+        self.get(id).await
+    }
+}
+
+/// Build a review item from a `SELECT *` row of the reviews table
+fn review_from_row(row: &SqliteRow) -> ReviewItem {
+    let parse_time = |value: String| {
+        DateTime::parse_from_rfc3339(&value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok()
+    };
+
+    ReviewItem {
+        id: row.get("id"),
+        cache_key: row.get("cache_key"),
+        path: row.get("path"),
+        content_hash: row.get("content_hash"),
+        reason: row.get("reason"),
+        source: row.get("source"),
+        status: row.get("status"),
+        note: row.get("note"),
+        created_at: parse_time(row.get("created_at")).unwrap_or_else(Utc::now),
+        resolved_at: row
+            .get::<Option<String>, _>("resolved_at")
+            .and_then(parse_time),
+    }
+}
+
+/// Heuristic checks on a translation. Returns the reason when it looks suspicious.
+pub fn detect_anomaly(
+    original: &str,
+    translated: &str,
+    min_length_ratio: f64,
+    max_length_ratio: f64,
+) -> Option<String> {
+    if translated.contains("___CODE_BLOCK_") && !original.contains("___CODE_BLOCK_") {
+        return Some("Unrestored code block placeholder in translation".to_string());
+    }
+
+    let original_chars = original.chars().count();
+    if original_chars == 0 {
+        return None;
+    }
+
+    let ratio = translated.chars().count() as f64 / original_chars as f64;
+    if ratio < min_length_ratio || ratio > max_length_ratio {
+        return Some(format!(
+            "Length ratio {:.2} outside expected range {:.2}-{:.2}",
+            ratio, min_length_ratio, max_length_ratio
+        ));
+    }
+
+    None
+}
+
+/// Reason to review a judged translation, when its score is below `min_score`
+pub fn low_quality(quality: &QualityScore, min_score: u8) -> Option<String> {
+    if quality.score() >= min_score {
+        return None;
+    }
+
+    let mut reason = format!(
+        "Quality score {}/5 from {} (fidelity {}, fluency {})",
+        quality.score(),
+        quality.model,
+        quality.fidelity,
+        quality.fluency
+    );
+    if !quality.issues.is_empty() {
+        reason.push_str(": ");
+        reason.push_str(&quality.issues.join("; "));
+    }
+    Some(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_anomaly_length_ratio() {
+        let original = "This is a reasonably long English sentence about skills.";
+        assert!(detect_anomaly(original, "这是一个关于技能的相当长的英文句子。", 0.15, 3.0).is_none());
+        assert!(detect_anomaly(original, "是", 0.15, 3.0).is_some());
+        assert!(detect_anomaly("Hi", &"很长".repeat(10), 0.15, 3.0).is_some());
+    }
+
+    #[test]
+    fn test_low_quality() {
+        let quality = QualityScore {
+            fidelity: 2,
+            fluency: 4,
+            issues: vec!["Step 3 is missing".to_string()],
+            model: "gpt-4o-mini".to_string(),
+        };
+        let reason = low_quality(&quality, 3).unwrap();
+        assert!(reason.starts_with("Quality score 2/5 from gpt-4o-mini"), "{}", reason);
+        assert!(reason.ends_with("Step 3 is missing"));
+        assert!(low_quality(&quality, 2).is_none());
+        assert!(low_quality(&quality, 0).is_none());
+    }
+
+    #[test]
+    fn test_detect_anomaly_placeholder() {
+        let reason = detect_anomaly("Text", "文本 ___CODE_BLOCK_0___", 0.0, 100.0);
+        assert!(reason.unwrap().contains("placeholder"));
+    }
+}
@@ -0,0 +1,170 @@
+//! Cron schedules of background tasks.
+//!
+//! Settings such as CACHE_CLEANUP_SCHEDULE and WARMER_SCHEDULE take a cron expression in
+//! local time, and the task waits for the next matching minute before each run.
+
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDate, NaiveDateTime};
+use std::fmt;
+use std::str::FromStr;
+
+/// Days searched for the next time matching a schedule, covering leap days
+const SCHEDULE_SEARCH_DAYS: i64 = 4 * 366;
+
+/// Cron expression with the five fields minute, hour, day of month, month and day of week.
+///
+/// Fields take `*`, values, ranges `a-b` and steps `*/n` or `a-b/n`, separated by commas;
+/// days of week run from 0 (Sunday) to 7 (Sunday again). As in cron, a time matches when
+/// its day matches either day field if both are restricted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("expected 5 fields, got {}", fields.len()));
+        };
+        let mut weekdays = field(weekday, 0, 7)?;
+        // 7 is another name for Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            expression: fields.join(" "),
+            minutes: field(minute, 0, 59)?,
+            hours: field(hour, 0, 23)?,
+            days: field(day, 1, 31)?,
+            months: field(month, 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl Schedule {
+    /// Every day at the start of `hour`
+    pub fn daily_at(hour: u32) -> Self {
+        format!("0 {} * * *", hour.min(23)).parse().expect("valid daily schedule")
+    }
+
+    /// First matching time after `now`, local time, or `None` when no date ever matches
+    pub fn next_after(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let today = now.date();
+        for offset in 0..SCHEDULE_SEARCH_DAYS {
+            let date = today + ChronoDuration::days(offset);
+            if !self.matches_day(date) {
+                continue;
+            }
+            for hour in (0..24).filter(|hour| self.hours & (1 << hour) != 0) {
+                for minute in (0..60).filter(|minute| self.minutes & (1 << minute) != 0) {
+                    let time = date.and_hms_opt(hour, minute, 0)?;
+                    if time > now {
+                        return Some(time);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+/// Bit set of the values a cron field selects within `min..=max`
+fn field(spec: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut bits = 0;
+    for item in spec.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("invalid step in '{}'", item))?;
+                if step == 0 {
+                    return Err(format!("invalid step in '{}'", item));
+                }
+                (range, step)
+            }
+            None => (item, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => {
+                let value = |value: &str| {
+                    value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|value| (min..=max).contains(value))
+                        .ok_or_else(|| format!("'{}' is not between {} and {}", value, min, max))
+                };
+                match range.split_once('-') {
+                    Some((start, end)) => (value(start)?, value(end)?),
+                    // `a/n` runs from a to the maximum
+                    None if step > 1 => (value(range)?, max),
+                    None => (value(range)?, value(range)?),
+                }
+            }
+        };
+        if start > end {
+            return Err(format!("empty range '{}'", item));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule() {
+        let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+
+        let daily = Schedule::daily_at(3);
+        assert_eq!(daily.next_after(at("2026-10-16 02:59")), Some(at("2026-10-16 03:00")));
+        assert_eq!(daily.next_after(at("2026-10-16 03:00")), Some(at("2026-10-17 03:00")));
+
+        // Every 15 minutes from 22:00 to 23:59 on weekends (2026-10-16 is a Friday)
+        let weekends: Schedule = "*/15 22-23 * * 6,7".parse().unwrap();
+        assert_eq!(weekends.next_after(at("2026-10-16 12:00")), Some(at("2026-10-17 22:00")));
+        assert_eq!(weekends.next_after(at("2026-10-17 23:50")), Some(at("2026-10-18 22:00")));
+
+        // Either day field matches when both are restricted
+        let first_or_monday: Schedule = "30 1 1 * 1".parse().unwrap();
+        assert_eq!(first_or_monday.next_after(at("2026-10-16 00:00")), Some(at("2026-10-19 01:30")));
+        assert_eq!(first_or_monday.next_after(at("2026-10-27 00:00")), Some(at("2026-11-01 01:30")));
+
+        assert!("0 3 * *".parse::<Schedule>().is_err());
+        assert!("60 3 * * *".parse::<Schedule>().is_err());
+        assert!("*/0 3 * * *".parse::<Schedule>().is_err());
+        assert_eq!("0 3 31 2 *".parse::<Schedule>().unwrap().next_after(at("2026-10-16 00:00")), None);
+    }
+}
@@ -0,0 +1,84 @@
+//! Schema migrations of the cache database.
+//!
+//! The translation cache, review queue, audit log, prompt templates, batch jobs,
+//! budget usage, terminology memory and stored originals each declare their own migrations. They are
+//! applied together at startup (or by `--migrate-only`), after backing up the
+//! database when any are pending, and verified before the server accepts traffic.
+
+use std::path::Path;
+
+use skillts_core::cache::{sqlite, CacheBackend, CacheBackendKind, PostgresBackend, TranslationCache};
+use skillts_core::migrate::{self, Schema};
+
+use crate::config::Settings;
+use crate::services::backup::Backups;
+use crate::services::{audit, batch, budget, prompts, review, sources, terminology};
+
+/// Schemas stored in the SQLite database, in migration order
+fn sqlite_schemas(settings: &Settings) -> Vec<&'static Schema> {
+    let mut schemas = Vec::new();
+    // With Postgres the translations live there, under its own migrations
+    if settings.cache_backend == CacheBackendKind::Sqlite {
+        schemas.push(&sqlite::SCHEMA);
+    }
+    schemas.extend([
+        &review::SCHEMA,
+        &audit::SCHEMA,
+        &prompts::SCHEMA,
+        &batch::SCHEMA,
+        &budget::SCHEMA,
+        &terminology::SCHEMA,
+        &sources::SCHEMA,
+    ]);
+    schemas
+}
+
+/// Apply pending migrations, backing up an existing database first.
+/// Returns the number of SQLite migrations applied.
+pub async fn migrate_database(settings: &Settings) -> anyhow::Result<usize> {
+    let db_path = &settings.cache_db_path;
+    let existed = Path::new(db_path).exists();
+    let pool = sqlite::connect(db_path, &settings.sqlite_tuning()).await?;
+
+    let mut pending = 0;
+    for schema in sqlite_schemas(settings) {
+        pending += migrate::pending(&pool, schema).await?.len();
+    }
+
+    let mut applied = 0;
+    if pending > 0 {
+        if existed {
+            Backups::new()?.run(settings, &pool).await?;
+        }
+        for schema in sqlite_schemas(settings) {
+            applied += migrate::migrate(&pool, schema).await?;
+        }
+    } else {
+        tracing::debug!("Cache database schema is up to date");
+    }
+    pool.close().await;
+
+    if settings.cache_backend == CacheBackendKind::Postgres {
+        PostgresBackend::connect(&settings.cache_config()).await?.close().await?;
+    }
+
+    Ok(applied)
+}
+
+/// Check that the cache database is reachable and every migration is applied, so the
+/// server never runs against a schema it does not know, such as one another instance
+/// has moved past
+pub async fn verify_database(settings: &Settings, cache: &TranslationCache) -> anyhow::Result<()> {
+    cache.ping().await?;
+    for schema in sqlite_schemas(settings) {
+        let pending = migrate::pending(cache.pool(), schema).await?;
+        if !pending.is_empty() {
+            anyhow::bail!(
+                "{} migrations of the {} schema are not applied",
+                pending.len(),
+                schema.component
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! Ed25519 signatures over translate responses.
+//!
+//! With RESPONSE_SIGNING_KEY set, every translate response carries a signature over
+//! its content hash, translated hash, model and signing time, so consumers holding
+//! the public key from `GET /api/public-key` can check that a translation came from
+//! this service. The signed message is [`MESSAGE_PREFIX`] followed by those four
+//! fields, each on its own line, encoded as UTF-8.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{SecondsFormat, Utc};
+use ed25519_dalek::{Signer, SigningKey, SECRET_KEY_LENGTH};
+use sha2::{Digest, Sha256};
+
+use crate::config::Settings;
+use crate::models::schemas::{PublicKeyResponse, ResponseSignature};
+
+/// Signature algorithm reported with signatures and the public key
+pub const ALGORITHM: &str = "ed25519";
+
+/// First line of every signed message, versioning its layout
+pub const MESSAGE_PREFIX: &str = "skillts-signature-v1";
+
+/// Signs responses with the configured key
+pub struct ResponseSigner {
+    key: SigningKey,
+    /// Identifies the public key, so consumers can tell rotated keys apart
+    key_id: String,
+}
+
+impl ResponseSigner {
+    /// Signer for RESPONSE_SIGNING_KEY, or `None` when signing is disabled
+    pub fn from_settings(settings: &Settings) -> Option<Self> {
+        decode_key(&settings.response_signing_key).map(Self::new)
+    }
+
+    pub fn new(key: SigningKey) -> Self {
+        let digest = Sha256::digest(key.verifying_key().as_bytes());
+        Self {
+            key_id: hex::encode(&digest[..8]),
+            key,
+        }
+    }
+
+    /// The public key to verify signatures with
+    pub fn public_key(&self) -> PublicKeyResponse {
+        PublicKeyResponse {
+            algorithm: ALGORITHM.to_string(),
+            key_id: self.key_id.clone(),
+            public_key: STANDARD.encode(self.key.verifying_key().as_bytes()),
+        }
+    }
+
+    /// Sign a translation of `content_hash` by `model`, timestamped now
+    pub fn sign(&self, content_hash: &str, translated_hash: &str, model: &str) -> ResponseSignature {
+        let signed_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let message = signed_message(content_hash, translated_hash, model, &signed_at);
+
+        ResponseSignature {
+            algorithm: ALGORITHM.to_string(),
+            key_id: self.key_id.clone(),
+            model: model.to_string(),
+            signed_at,
+            signature: STANDARD.encode(self.key.sign(message.as_bytes()).to_bytes()),
+        }
+    }
+}
+
+/// The message a response signature covers
+pub fn signed_message(content_hash: &str, translated_hash: &str, model: &str, signed_at: &str) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        MESSAGE_PREFIX, content_hash, translated_hash, model, signed_at
+    )
+}
+
+/// Read a base64 encoded 32 byte Ed25519 secret key; `None` when empty or malformed
+pub fn decode_key(encoded: &str) -> Option<SigningKey> {
+    let bytes = STANDARD.decode(encoded.trim()).ok()?;
+    let secret: [u8; SECRET_KEY_LENGTH] = bytes.try_into().ok()?;
+    Some(SigningKey::from_bytes(&secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+    #[test]
+    fn test_signature_verifies_with_public_key() {
+        let signer = ResponseSigner::new(decode_key(&STANDARD.encode([7u8; 32])).unwrap());
+        let signature = signer.sign("sha256:original", "sha256:translated", "gpt-4o-mini");
+        let public_key = signer.public_key();
+        assert_eq!((public_key.key_id.as_str(), signature.algorithm.as_str()), (signature.key_id.as_str(), ALGORITHM));
+
+        let public_key: [u8; 32] = STANDARD.decode(&public_key.public_key).unwrap().try_into().unwrap();
+        let verifying_key = VerifyingKey::from_bytes(&public_key).unwrap();
+        let value: [u8; 64] = STANDARD.decode(&signature.signature).unwrap().try_into().unwrap();
+        let message = signed_message("sha256:original", "sha256:translated", "gpt-4o-mini", &signature.signed_at);
+        assert!(verifying_key.verify(message.as_bytes(), &Signature::from_bytes(&value)).is_ok());
+
+        let tampered = signed_message("sha256:original", "sha256:other", "gpt-4o-mini", &signature.signed_at);
+        assert!(verifying_key.verify(tampered.as_bytes(), &Signature::from_bytes(&value)).is_err());
+
+        assert!(decode_key("").is_none());
+        assert!(decode_key(&STANDARD.encode([7u8; 16])).is_none());
+    }
+}
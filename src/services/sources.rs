@@ -0,0 +1,125 @@
+//! Original documents of cached translations.
+//!
+//! The translation cache keeps only translations, keyed by the hash of their
+//! original. With STORE_SOURCES enabled, the original of each cached translation is
+//! stored once per tenant and content hash in the `source_documents` table, so a
+//! cache entry can be compared with its original (see `/api/cache/entry/{cache_key}/diff`).
+
+use chrono::Utc;
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+
+use skillts_core::migrate::{migrate, Migration, Schema, Step};
+
+use crate::error::AppResult;
+
+/// Migrations of the source_documents table
+pub const SCHEMA: Schema = Schema {
+    component: "sources",
+    migrations: &[Migration {
+        version: 1,
+        description: "Create source_documents",
+        step: Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS source_documents (
+                tenant TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (tenant, content_hash)
+            )
+            "#,
+        ),
+    }],
+};
+
+/// SQLite-backed originals of cached translations
+pub struct SourceStore {
+    pool: SqlitePool,
+}
+
+impl SourceStore {
+    /// Create the store on the cache database pool, applying pending migrations
+    pub async fn new(pool: SqlitePool) -> AppResult<Self> {
+        migrate(&pool, &SCHEMA).await?;
+        Ok(Self { pool })
+    }
+
+    /// The original stored for a content hash
+    pub async fn get(&self, tenant: &str, content_hash: &str) -> AppResult<Option<String>> {
+        let row = sqlx::query("SELECT content FROM source_documents WHERE tenant = ? AND content_hash = ?")
+            .bind(tenant)
+            .bind(content_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|row| row.get("content")))
+    }
+
+    /// Store the original of a cached translation.
+    /// Failures are logged rather than returned so they never fail a translation.
+    pub async fn record(&self, tenant: &str, content_hash: &str, content: &str) {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO source_documents (tenant, content_hash, content, created_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(tenant, content_hash) DO NOTHING
+            "#,
+        )
+        .bind(tenant)
+        .bind(content_hash)
+        .bind(content)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to store the original of {}: {}", content_hash, e);
+        }
+    }
+
+    /// Forget the original stored for a content hash
+    pub async fn delete(&self, tenant: &str, content_hash: &str) -> AppResult<u64> {
+        let result = sqlx::query("DELETE FROM source_documents WHERE tenant = ? AND content_hash = ?")
+            .bind(tenant)
+            .bind(content_hash)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Forget every original stored for the tenant
+    pub async fn clear(&self, tenant: &str) -> AppResult<u64> {
+        let result = sqlx::query("DELETE FROM source_documents WHERE tenant = ?")
+            .bind(tenant)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn test_sources_per_tenant() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let store = SourceStore::new(pool).await.unwrap();
+
+        store.record("default", "sha256:a", "# Usage\n").await;
+        store.record("default", "sha256:a", "# Ignored\n").await;
+        store.record("registry-a", "sha256:b", "# Other\n").await;
+
+        assert_eq!(store.get("default", "sha256:a").await.unwrap().as_deref(), Some("# Usage\n"));
+        assert!(store.get("default", "sha256:b").await.unwrap().is_none());
+
+        assert_eq!(store.delete("default", "sha256:a").await.unwrap(), 1);
+        assert_eq!(store.clear("registry-a").await.unwrap(), 1);
+        assert!(store.get("registry-a", "sha256:b").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,175 @@
+//! Terminology memory.
+//!
+//! With TERMINOLOGY_MEMORY enabled, the renderings a fresh translation chose for
+//! its headings and bold terms are stored in the `skill_terms` table per tenant,
+//! skill and language pair, and given to the model whenever a document of the
+//! skill is translated again. An updated SKILL.md then keeps the wording of the
+//! previous version, so diffs between translations stay small. A skill is the
+//! directory of a document's path; files at the top level stand for themselves.
+
+use chrono::Utc;
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+
+use skillts_core::memory::MemoryScope;
+use skillts_core::migrate::{migrate, Migration, Schema, Step};
+use skillts_core::terminology::{Term, MAX_TERMS};
+
+use crate::error::AppResult;
+
+/// Migrations of the skill_terms table
+pub const SCHEMA: Schema = Schema {
+    component: "terminology",
+    migrations: &[Migration {
+        version: 1,
+        description: "Create skill_terms",
+        step: Step::Sql(
+            r#"
+            CREATE TABLE IF NOT EXISTS skill_terms (
+                tenant TEXT NOT NULL,
+                skill TEXT NOT NULL,
+                source_language TEXT NOT NULL,
+                target_language TEXT NOT NULL,
+                source_term TEXT NOT NULL,
+                translation TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (tenant, skill, source_language, target_language, source_term)
+            )
+            "#,
+        ),
+    }],
+};
+
+/// The skill a document belongs to: the directory of its path, or the path itself
+pub fn skill_of(path: &str) -> &str {
+    match path.trim_start_matches('/').rsplit_once('/') {
+        Some((directory, _)) if !directory.is_empty() => directory,
+        _ => path,
+    }
+}
+
+/// SQLite-backed term renderings per skill
+pub struct TerminologyStore {
+    pool: SqlitePool,
+}
+
+impl TerminologyStore {
+    /// Create the store on the cache database pool, applying pending migrations
+    pub async fn new(pool: SqlitePool) -> AppResult<Self> {
+        migrate(&pool, &SCHEMA).await?;
+        Ok(Self { pool })
+    }
+
+    /// Renderings stored for a skill, the most recently chosen first
+    pub async fn terms(&self, scope: &MemoryScope<'_>, skill: &str) -> AppResult<Vec<Term>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT source_term, translation FROM skill_terms
+            WHERE tenant = ? AND skill = ? AND source_language = ? AND target_language = ?
+            ORDER BY updated_at DESC, source_term
+            LIMIT ?
+            "#,
+        )
+        .bind(scope.tenant)
+        .bind(skill)
+        .bind(scope.source_language)
+        .bind(scope.target_language)
+        .bind(MAX_TERMS as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Term {
+                source: row.get("source_term"),
+                translation: row.get("translation"),
+            })
+            .collect())
+    }
+
+    /// Store the renderings a translation of the skill chose, replacing earlier ones.
+    /// Failures are logged rather than returned so they never fail a translation.
+    pub async fn record(&self, scope: &MemoryScope<'_>, skill: &str, terms: &[Term]) {
+        let now = Utc::now().to_rfc3339();
+        for term in terms {
+            let result = sqlx::query(
+                r#"
+                INSERT INTO skill_terms
+                    (tenant, skill, source_language, target_language, source_term, translation, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(tenant, skill, source_language, target_language, source_term) DO UPDATE SET
+                    translation = excluded.translation,
+                    updated_at = excluded.updated_at
+                "#,
+            )
+            .bind(scope.tenant)
+            .bind(skill)
+            .bind(scope.source_language)
+            .bind(scope.target_language)
+            .bind(&term.source)
+            .bind(&term.translation)
+            .bind(&now)
+            .execute(&self.pool)
+            .await;
+
+            if let Err(e) = result {
+                tracing::warn!("Failed to record terminology of {}: {}", skill, e);
+                return;
+            }
+        }
+    }
+
+    /// Forget every rendering stored for the tenant
+    pub async fn clear(&self, tenant: &str) -> AppResult<u64> {
+        let result = sqlx::query("DELETE FROM skill_terms WHERE tenant = ?")
+            .bind(tenant)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn test_terms_per_skill() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let store = TerminologyStore::new(pool).await.unwrap();
+        let scope = MemoryScope {
+            tenant: "default",
+            source_language: "en",
+            target_language: "zh-CN",
+        };
+        let term = |source: &str, translation: &str| Term {
+            source: source.to_string(),
+            translation: translation.to_string(),
+        };
+
+        store.record(&scope, "skills/a/demo", &[term("Usage", "用法"), term("Registry", "注册表")]).await;
+        store.record(&scope, "skills/a/demo", &[term("Usage", "使用方法")]).await;
+        store.record(&scope, "skills/b/other", &[term("Usage", "用途")]).await;
+
+        let terms = store.terms(&scope, "skills/a/demo").await.unwrap();
+        assert_eq!(terms.len(), 2);
+        assert!(terms.contains(&term("Usage", "使用方法")) && terms.contains(&term("Registry", "注册表")));
+        let japanese = MemoryScope {
+            target_language: "ja",
+            ..scope
+        };
+        assert!(store.terms(&japanese, "skills/a/demo").await.unwrap().is_empty());
+
+        assert_eq!(store.clear("default").await.unwrap(), 3);
+        assert!(store.terms(&scope, "skills/b/other").await.unwrap().is_empty());
+
+        assert_eq!(skill_of("skills/a/demo/SKILL.md"), "skills/a/demo");
+        assert_eq!(skill_of("skills/a/demo/examples/basic.md"), "skills/a/demo/examples");
+        assert_eq!(skill_of("SKILL.md"), "SKILL.md");
+    }
+}
@@ -0,0 +1,101 @@
+//! Scheduled cache warmer.
+//!
+//! With WARMER_MANIFEST_URL set, the service fetches a JSON manifest listing
+//! `{path, content_url, content_hash}` entries at the times of WARMER_SCHEDULE, a
+//! cron expression in local time, and translates the files whose translation is not
+//! cached yet, so the first requests after a publisher updates its skills are cache
+//! hits. Content is only downloaded for new or changed files.
+
+use reqwest::Url;
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::error::{AppError, AppResult};
+
+/// Timeout of each manifest or content download
+const WARMER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A file listed by a warmer manifest
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// Relative path of the file in the repository
+    pub path: String,
+    /// Where to download the file, absolute or relative to the manifest URL
+    pub content_url: String,
+    /// SHA256 hash of the content (with "sha256:" prefix)
+    pub content_hash: String,
+}
+
+/// Outcome of a warmer run
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct WarmerRun {
+    pub listed: usize,
+    /// Files whose translation was already cached
+    pub cached: usize,
+    pub translated: usize,
+    pub failed: usize,
+}
+
+/// HTTP client downloading manifests and content
+pub fn client() -> AppResult<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(WARMER_TIMEOUT)
+        .user_agent("skill-translator")
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to build HTTP client: {}", e)))
+}
+
+/// Download and parse the manifest
+pub async fn fetch_manifest(http: &reqwest::Client, manifest_url: &str) -> AppResult<Vec<ManifestEntry>> {
+    let response = download(http, manifest_url).await?;
+    response
+        .json()
+        .await
+        .map_err(|e| AppError::Internal(format!("Invalid warmer manifest {}: {}", manifest_url, e)))
+}
+
+/// Download the content of a manifest entry
+pub async fn fetch_content(http: &reqwest::Client, manifest_url: &str, entry: &ManifestEntry) -> AppResult<String> {
+    let url = content_url(manifest_url, &entry.content_url)?;
+    download(http, url.as_str())
+        .await?
+        .text()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to download {}: {}", url, e)))
+}
+
+/// URL of a manifest entry's content, resolved against the manifest URL
+fn content_url(manifest_url: &str, content_url: &str) -> AppResult<Url> {
+    Url::parse(manifest_url)
+        .and_then(|base| base.join(content_url))
+        .map_err(|e| AppError::Internal(format!("Invalid content URL '{}': {}", content_url, e)))
+}
+
+async fn download(http: &reqwest::Client, url: &str) -> AppResult<reqwest::Response> {
+    let response = http
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to download {}: {}", url, e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::Internal(format!("Failed to download {}: {}", url, response.status())));
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_url() {
+        assert_eq!(
+            content_url("https://example.com/skills/manifest.json", "a/SKILL.md").unwrap().as_str(),
+            "https://example.com/skills/a/SKILL.md"
+        );
+        assert_eq!(
+            content_url("https://example.com/skills/manifest.json", "https://cdn.example.com/a.md").unwrap().as_str(),
+            "https://cdn.example.com/a.md"
+        );
+    }
+}